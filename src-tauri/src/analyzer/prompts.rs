@@ -2,6 +2,8 @@
 
 use std::collections::HashMap;
 
+use crate::calibration::CalibrationContext;
+
 /// JSON schema for structured defect report output.
 /// Matches the DetectedDefect type from mapper::types.
 pub fn defect_report_schema() -> serde_json::Value {
@@ -105,6 +107,34 @@ If image is unclear or not a 3D print, note this in the notes field."#,
     )
 }
 
+/// Extra prompt text for a photo of calibration samples, appended to the
+/// defect analysis prompt. The best sample is reported in `notes`, so the
+/// response schema stays the same.
+pub fn build_calibration_note(context: &CalibrationContext) -> String {
+    let test = context.test;
+    let samples = context
+        .values
+        .iter()
+        .enumerate()
+        .map(|(i, v)| format!("{}. {}", i + 1, test.label(*v)))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let focus = test.focus_defects().join(", ");
+
+    format!(
+        r#"
+
+This photo shows calibration samples ({name}), each printed with a different {parameter}.
+Samples, left to right: {samples}
+The other settings above are the same for every sample. Concentrate on: {focus}.
+Report defects for the worst sample. In the notes field, name the sample that printed best (by its {parameter} value) and briefly say why."#,
+        name = test.display_name().to_lowercase(),
+        parameter = test.parameter(),
+        samples = samples,
+        focus = focus,
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -219,4 +249,16 @@ mod tests {
         assert!(prompt.contains("0.7=significant"));
         assert!(prompt.contains("0.9=severe"));
     }
+
+    #[test]
+    fn test_calibration_note_lists_samples() {
+        let note = build_calibration_note(&CalibrationContext {
+            test: crate::calibration::CalibrationTest::Retraction,
+            values: vec![0.4, 0.8, 1.2],
+        });
+        assert!(note.contains("retraction test"));
+        assert!(note.contains("filament_retraction_length"));
+        assert!(note.contains("1. 0.4mm, 2. 0.8mm, 3. 1.2mm"));
+        assert!(note.contains("stringing"));
+    }
 }
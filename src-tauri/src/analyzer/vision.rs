@@ -8,8 +8,9 @@ use std::time::Duration;
 use tracing::{error, info};
 
 use super::image_prep::{image_media_type, prepare_image};
-use super::prompts::{build_defect_analysis_prompt, defect_report_schema};
+use super::prompts::{build_calibration_note, build_defect_analysis_prompt, defect_report_schema};
 use super::types::DefectReport;
+use crate::calibration::CalibrationContext;
 use crate::mapper::DetectedDefect;
 use crate::str_utils::truncate_with_ellipsis;
 
@@ -19,6 +20,7 @@ use crate::str_utils::truncate_with_ellipsis;
 /// * `image_bytes` - Raw image bytes (will be resized and encoded)
 /// * `current_settings` - Current profile parameter values for context
 /// * `material_type` - Material type string (e.g., "PLA", "PETG")
+/// * `calibration` - Set when the photo shows calibration samples
 /// * `provider` - AI provider: "claude", "openai", "kimi", or "openrouter"
 /// * `model` - Model identifier
 /// * `api_key` - API key for the provider
@@ -29,6 +31,7 @@ pub async fn analyze_image(
     image_bytes: &[u8],
     current_settings: &HashMap<String, f32>,
     material_type: &str,
+    calibration: Option<&CalibrationContext>,
     provider: &str,
     model: &str,
     api_key: &str,
@@ -36,7 +39,10 @@ pub async fn analyze_image(
     // Prepare image (resize + base64)
     let base64_image = prepare_image(image_bytes)?;
 
-    let prompt = build_defect_analysis_prompt(current_settings, material_type);
+    let mut prompt = build_defect_analysis_prompt(current_settings, material_type);
+    if let Some(context) = calibration {
+        prompt.push_str(&build_calibration_note(context));
+    }
     let schema = defect_report_schema();
 
     info!(
@@ -634,6 +640,7 @@ mod tests {
             &[0; 100],
             &HashMap::new(),
             "PLA",
+            None,
             "invalid_provider",
             "model",
             "key",
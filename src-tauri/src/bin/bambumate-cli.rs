@@ -0,0 +1,3 @@
+fn main() -> std::process::ExitCode {
+    bambumate_tauri::cli::run()
+}
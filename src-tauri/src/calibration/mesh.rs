@@ -0,0 +1,286 @@
+//! Minimal triangle mesh builder and binary STL writer for calibration
+//! models.
+//!
+//! Every calibration model is a prism: a simple 2D outline extruded along
+//! one axis. That keeps each model a single closed, manifold solid without
+//! needing boolean operations.
+
+/// A triangle mesh in millimetres, Z up.
+#[derive(Debug, Clone, Default)]
+pub struct Mesh {
+    pub triangles: Vec<[[f32; 3]; 3]>,
+}
+
+/// Axis a 2D outline is extruded along.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Axis {
+    /// Outline in the XY plane, extruded upwards.
+    Z,
+    /// Outline in the XZ plane (x, z), extruded along +Y.
+    Y,
+}
+
+impl Mesh {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an axis-aligned box from `min` to `max`.
+    pub fn add_box(&mut self, min: [f32; 3], max: [f32; 3]) {
+        let outline = [
+            [min[0], min[1]],
+            [max[0], min[1]],
+            [max[0], max[1]],
+            [min[0], max[1]],
+        ];
+        self.add_prism(&outline, Axis::Z, min[2], max[2]);
+    }
+
+    /// Extrude a simple polygon (no self-intersections, either winding)
+    /// from `from` to `to` along `axis`, adding a closed solid.
+    pub fn add_prism(&mut self, outline: &[[f32; 2]], axis: Axis, from: f32, to: f32) {
+        let mut outline = outline.to_vec();
+        if signed_area(&outline) < 0.0 {
+            outline.reverse();
+        }
+        let point = |p: [f32; 2], depth: f32| match axis {
+            Axis::Z => [p[0], p[1], depth],
+            Axis::Y => [p[0], depth, p[1]],
+        };
+        // For Y extrusion the outline's (x, z) plane is mirrored relative
+        // to a right-handed view down +Y, so cap and wall windings flip.
+        let flip = axis == Axis::Y;
+        let mut push = |tri: [[f32; 3]; 3]| {
+            if flip {
+                self.triangles.push([tri[0], tri[2], tri[1]]);
+            } else {
+                self.triangles.push(tri);
+            }
+        };
+
+        for [a, b, c] in triangulate(&outline) {
+            // Bottom cap faces away from the extrusion, top cap towards it
+            push([
+                point(outline[a], from),
+                point(outline[c], from),
+                point(outline[b], from),
+            ]);
+            push([
+                point(outline[a], to),
+                point(outline[b], to),
+                point(outline[c], to),
+            ]);
+        }
+        for i in 0..outline.len() {
+            let p = outline[i];
+            let q = outline[(i + 1) % outline.len()];
+            push([point(p, from), point(q, from), point(q, to)]);
+            push([point(p, from), point(q, to), point(p, to)]);
+        }
+    }
+
+    /// Offset every vertex by `delta`.
+    pub fn translate(&mut self, delta: [f32; 3]) {
+        for tri in &mut self.triangles {
+            for v in tri.iter_mut() {
+                for axis in 0..3 {
+                    v[axis] += delta[axis];
+                }
+            }
+        }
+    }
+
+    /// Axis-aligned bounds `(min, max)`; None for an empty mesh.
+    pub fn bounds(&self) -> Option<([f32; 3], [f32; 3])> {
+        let mut vertices = self.triangles.iter().flatten();
+        let first = *vertices.next()?;
+        Some(vertices.fold((first, first), |(mut min, mut max), v| {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(v[axis]);
+                max[axis] = max[axis].max(v[axis]);
+            }
+            (min, max)
+        }))
+    }
+
+    /// Enclosed volume in mm³ (positive for outward-facing triangles).
+    pub fn volume(&self) -> f32 {
+        self.triangles
+            .iter()
+            .map(|[a, b, c]| dot(*a, cross(*b, *c)) / 6.0)
+            .sum()
+    }
+
+    /// Encode as binary STL.
+    pub fn to_binary_stl(&self, name: &str) -> Vec<u8> {
+        let mut out = Vec::with_capacity(84 + self.triangles.len() * 50);
+        let mut header = [0u8; 80];
+        let label = format!("BambuMate {}", name);
+        let len = label.len().min(80);
+        header[..len].copy_from_slice(&label.as_bytes()[..len]);
+        out.extend_from_slice(&header);
+        out.extend_from_slice(&(self.triangles.len() as u32).to_le_bytes());
+        for tri in &self.triangles {
+            let normal = normalize(cross(sub(tri[1], tri[0]), sub(tri[2], tri[0])));
+            for v in std::iter::once(&normal).chain(tri.iter()) {
+                for c in v {
+                    out.extend_from_slice(&c.to_le_bytes());
+                }
+            }
+            out.extend_from_slice(&0u16.to_le_bytes());
+        }
+        out
+    }
+}
+
+fn signed_area(outline: &[[f32; 2]]) -> f32 {
+    (0..outline.len())
+        .map(|i| {
+            let p = outline[i];
+            let q = outline[(i + 1) % outline.len()];
+            p[0] * q[1] - q[0] * p[1]
+        })
+        .sum::<f32>()
+        / 2.0
+}
+
+/// Ear-clipping triangulation of a counter-clockwise simple polygon.
+/// Returns index triples into `outline`, counter-clockwise.
+fn triangulate(outline: &[[f32; 2]]) -> Vec<[usize; 3]> {
+    let cross2 = |o: [f32; 2], a: [f32; 2], b: [f32; 2]| {
+        (a[0] - o[0]) * (b[1] - o[1]) - (a[1] - o[1]) * (b[0] - o[0])
+    };
+    let mut remaining: Vec<usize> = (0..outline.len()).collect();
+    let mut triangles = Vec::with_capacity(outline.len().saturating_sub(2));
+    while remaining.len() > 3 {
+        let n = remaining.len();
+        let ear = (0..n).find(|&i| {
+            let (a, b, c) = (
+                remaining[(i + n - 1) % n],
+                remaining[i],
+                remaining[(i + 1) % n],
+            );
+            let (pa, pb, pc) = (outline[a], outline[b], outline[c]);
+            // Convex corner with no other vertex inside the candidate ear
+            cross2(pa, pb, pc) > 0.0
+                && remaining.iter().all(|&j| {
+                    j == a
+                        || j == b
+                        || j == c
+                        || cross2(pa, pb, outline[j]) < 0.0
+                        || cross2(pb, pc, outline[j]) < 0.0
+                        || cross2(pc, pa, outline[j]) < 0.0
+                })
+        });
+        // Degenerate outline (collinear points); clip anything to finish
+        let i = ear.unwrap_or(0);
+        triangles.push([
+            remaining[(i + n - 1) % n],
+            remaining[i],
+            remaining[(i + 1) % n],
+        ]);
+        remaining.remove(i);
+    }
+    if remaining.len() == 3 {
+        triangles.push([remaining[0], remaining[1], remaining[2]]);
+    }
+    triangles
+}
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn normalize(v: [f32; 3]) -> [f32; 3] {
+    let len = dot(v, v).sqrt();
+    if len == 0.0 {
+        v
+    } else {
+        [v[0] / len, v[1] / len, v[2] / len]
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// Every directed edge must be matched by exactly one reversed edge:
+    /// closed, manifold and consistently oriented.
+    pub(crate) fn assert_watertight(mesh: &Mesh) {
+        let key = |v: [f32; 3]| v.map(|c| (c * 1000.0).round() as i64);
+        let mut edges: HashMap<([i64; 3], [i64; 3]), i32> = HashMap::new();
+        for tri in &mesh.triangles {
+            for i in 0..3 {
+                *edges
+                    .entry((key(tri[i]), key(tri[(i + 1) % 3])))
+                    .or_default() += 1;
+            }
+        }
+        for ((a, b), count) in &edges {
+            assert_eq!(*count, 1, "edge {:?}->{:?} used {} times", a, b, count);
+            assert_eq!(
+                edges.get(&(*b, *a)),
+                Some(&1),
+                "edge {:?}->{:?} has no twin",
+                a,
+                b
+            );
+        }
+        assert!(mesh.volume() > 0.0, "mesh is inside out");
+    }
+
+    #[test]
+    fn test_box_is_closed() {
+        let mut mesh = Mesh::new();
+        mesh.add_box([0.0, 0.0, 0.0], [20.0, 10.0, 5.0]);
+        assert_eq!(mesh.triangles.len(), 12);
+        assert_watertight(&mesh);
+        assert!((mesh.volume() - 1000.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_concave_prisms_are_closed() {
+        // U shape, clockwise, extruded along Y
+        let u = [
+            [0.0, 0.0],
+            [0.0, 10.0],
+            [4.0, 10.0],
+            [4.0, 2.0],
+            [16.0, 2.0],
+            [16.0, 10.0],
+            [20.0, 10.0],
+            [20.0, 0.0],
+        ];
+        let mut mesh = Mesh::new();
+        mesh.add_prism(&u, Axis::Y, 0.0, 5.0);
+        assert_watertight(&mesh);
+        let area = 20.0 * 2.0 + 2.0 * 4.0 * 8.0;
+        assert!((mesh.volume() - area * 5.0).abs() < 0.01);
+        let (min, max) = mesh.bounds().unwrap();
+        assert_eq!(min, [0.0, 0.0, 0.0]);
+        assert_eq!(max, [20.0, 5.0, 10.0]);
+    }
+
+    #[test]
+    fn test_binary_stl_layout() {
+        let mut mesh = Mesh::new();
+        mesh.add_box([0.0, 0.0, 0.0], [1.0, 1.0, 1.0]);
+        let stl = mesh.to_binary_stl("cube");
+        assert_eq!(stl.len(), 84 + 12 * 50);
+        assert!(stl.starts_with(b"BambuMate cube"));
+        assert_eq!(u32::from_le_bytes(stl[80..84].try_into().unwrap()), 12);
+    }
+}
@@ -0,0 +1,417 @@
+//! Calibration print generator.
+//!
+//! A calibration kit is a set of small test models, one per value of the
+//! parameter being calibrated, each paired with a filament profile variant
+//! that differs from the filament's own specs only in that parameter. Each
+//! sample is a separate STL so it can be sliced with its own variant; photos
+//! of the printed samples go back to the analyzer with a
+//! [`CalibrationContext`] so it knows what it's comparing.
+
+pub mod mesh;
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::profile::generator::{generate_filament_id, generate_profile};
+use crate::profile::registry::{MachineRegistry, ProfileRegistry};
+use crate::profile::types::{FilamentProfile, ProfileMetadata};
+use crate::scraper::types::{FilamentSpecs, MaterialType};
+use crate::scraper::validation::constraints_for_material;
+use mesh::{Axis, Mesh};
+
+/// Most samples one kit may contain.
+pub const MAX_SAMPLES: usize = 12;
+
+/// The available calibration tests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CalibrationTest {
+    /// Bridge and 45° overhang block, one per nozzle temperature.
+    TemperatureTower,
+    /// Two pillars to travel between, one pair per retraction length.
+    Retraction,
+    /// Flat square with a solid top, one per flow ratio.
+    Flow,
+    /// Sharp-cornered chevron wall, one per pressure advance value.
+    PressureAdvance,
+}
+
+impl CalibrationTest {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Self::TemperatureTower => "Temperature tower",
+            Self::Retraction => "Retraction test",
+            Self::Flow => "Flow rate squares",
+            Self::PressureAdvance => "Pressure advance pattern",
+        }
+    }
+
+    /// Short tag used in variant and file names.
+    fn tag(&self) -> &'static str {
+        match self {
+            Self::TemperatureTower => "Temp",
+            Self::Retraction => "Retract",
+            Self::Flow => "Flow",
+            Self::PressureAdvance => "PA",
+        }
+    }
+
+    /// Filament profile key the test varies.
+    pub fn parameter(&self) -> &'static str {
+        match self {
+            Self::TemperatureTower => "nozzle_temperature",
+            Self::Retraction => "filament_retraction_length",
+            Self::Flow => "filament_flow_ratio",
+            Self::PressureAdvance => "pressure_advance",
+        }
+    }
+
+    /// Defects the analyzer should concentrate on when comparing samples.
+    pub fn focus_defects(&self) -> &'static [&'static str] {
+        match self {
+            Self::TemperatureTower => &["stringing", "layer_adhesion", "over_extrusion"],
+            Self::Retraction => &["stringing"],
+            Self::Flow => &["under_extrusion", "over_extrusion"],
+            Self::PressureAdvance => &["over_extrusion", "under_extrusion"],
+        }
+    }
+
+    /// Sample label for `value`, e.g. "215C" or "0.8mm".
+    pub fn label(&self, value: f32) -> String {
+        match self {
+            Self::TemperatureTower => format!("{:.0}C", value),
+            Self::Retraction => format!("{:.1}mm", value),
+            Self::Flow => format!("{:.2}", value),
+            Self::PressureAdvance => format!("{:.3}", value),
+        }
+    }
+
+    /// Sweep to use when the caller doesn't give one. Temperature towers
+    /// cover the filament's own range, falling back to the material's.
+    pub fn default_range(&self, specs: &FilamentSpecs) -> CalibrationRange {
+        match self {
+            Self::TemperatureTower => {
+                let constraints =
+                    constraints_for_material(&MaterialType::from_str(&specs.material));
+                let start = specs.nozzle_temp_min.unwrap_or(constraints.nozzle_temp_min);
+                let end = specs.nozzle_temp_max.unwrap_or(constraints.nozzle_temp_max);
+                // Widen the step for broad ranges to stay under MAX_SAMPLES
+                let span = end.saturating_sub(start) as f32;
+                let step = (span / (MAX_SAMPLES - 1) as f32 / 5.0).ceil().max(1.0) * 5.0;
+                CalibrationRange {
+                    start: start as f32,
+                    end: end as f32,
+                    step,
+                }
+            }
+            Self::Retraction => CalibrationRange {
+                start: 0.2,
+                end: 1.6,
+                step: 0.2,
+            },
+            Self::Flow => CalibrationRange {
+                start: 0.92,
+                end: 1.08,
+                step: 0.02,
+            },
+            Self::PressureAdvance => CalibrationRange {
+                start: 0.0,
+                end: 0.06,
+                step: 0.01,
+            },
+        }
+    }
+
+    /// Test model, resting on Z=0.
+    pub fn model(&self) -> Mesh {
+        let mut mesh = Mesh::new();
+        match self {
+            Self::TemperatureTower => {
+                // Side profile (x, z): a 15mm bridge between two pillars,
+                // with a 45° overhang on the outside of the left pillar
+                let outline = [
+                    [5.0, 0.0],
+                    [10.0, 0.0],
+                    [10.0, 10.0],
+                    [25.0, 10.0],
+                    [25.0, 0.0],
+                    [30.0, 0.0],
+                    [30.0, 15.0],
+                    [0.0, 15.0],
+                    [0.0, 5.0],
+                ];
+                mesh.add_prism(&outline, Axis::Y, 0.0, 10.0);
+            }
+            Self::Retraction => {
+                // Two 5mm square pillars 30mm apart on a 1mm base
+                let outline = [
+                    [0.0, 0.0],
+                    [40.0, 0.0],
+                    [40.0, 25.0],
+                    [35.0, 25.0],
+                    [35.0, 1.0],
+                    [5.0, 1.0],
+                    [5.0, 25.0],
+                    [0.0, 25.0],
+                ];
+                mesh.add_prism(&outline, Axis::Y, 0.0, 5.0);
+            }
+            Self::Flow => mesh.add_box([0.0, 0.0, 0.0], [30.0, 30.0, 3.0]),
+            Self::PressureAdvance => {
+                // Chevron wall about four lines thick with a 90° corner
+                let d = 2.4;
+                let outline = [
+                    [0.0, 0.0],
+                    [d, 0.0],
+                    [20.0 + d, 20.0],
+                    [d, 40.0],
+                    [0.0, 40.0],
+                    [20.0, 20.0],
+                ];
+                mesh.add_prism(&outline, Axis::Z, 0.0, 10.0);
+            }
+        }
+        mesh
+    }
+
+    /// Set the test's parameter on `specs`.
+    fn apply(&self, specs: &mut FilamentSpecs, value: f32) {
+        match self {
+            Self::TemperatureTower => {
+                let temp = value.round() as u16;
+                specs.nozzle_temperature = Some(temp);
+                // Same temperature from the first layer so the whole sample
+                // shows the value under test
+                specs.nozzle_temperature_initial_layer = Some(temp);
+                specs.nozzle_temp_min = Some(specs.nozzle_temp_min.unwrap_or(temp).min(temp));
+                specs.nozzle_temp_max = Some(specs.nozzle_temp_max.unwrap_or(temp).max(temp));
+            }
+            Self::Retraction => specs.retraction_distance_mm = Some(value),
+            Self::Flow => specs.filament_flow_ratio = Some(value),
+            Self::PressureAdvance => specs.pressure_advance = Some(value),
+        }
+    }
+}
+
+/// An inclusive sweep of values.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CalibrationRange {
+    pub start: f32,
+    pub end: f32,
+    pub step: f32,
+}
+
+impl CalibrationRange {
+    /// The values in the sweep, rounded to 3 decimals.
+    pub fn values(&self) -> Result<Vec<f32>> {
+        if self.step.is_nan()
+            || self.step <= 0.0
+            || !self.start.is_finite()
+            || !self.end.is_finite()
+        {
+            bail!("Calibration step must be a positive number");
+        }
+        if self.start > self.end {
+            bail!(
+                "Calibration range start ({}) is above its end ({})",
+                self.start,
+                self.end
+            );
+        }
+        // Tolerate float error so 0.2..=1.6 step 0.2 includes 1.6
+        let count = ((self.end - self.start) / self.step + 1e-3).floor() as usize + 1;
+        if count > MAX_SAMPLES {
+            bail!(
+                "Calibration range has {} samples; the most is {}. Use a larger step.",
+                count,
+                MAX_SAMPLES
+            );
+        }
+        Ok((0..count)
+            .map(|i| ((self.start + i as f32 * self.step) * 1000.0).round() / 1000.0)
+            .collect())
+    }
+}
+
+/// One sample of a kit: the model and the profile variant to slice it with.
+pub struct CalibrationSample {
+    pub value: f32,
+    pub label: String,
+    pub stl_filename: String,
+    pub stl: Vec<u8>,
+    pub profile: FilamentProfile,
+    pub metadata: ProfileMetadata,
+    pub profile_filename: String,
+}
+
+/// Build a calibration kit for `specs`.
+///
+/// Each variant is generated through [`generate_profile`] from `specs` with
+/// only the test's parameter changed, and named
+/// `"<serial> Cal <test> <label>"` so it sits next to the filament's own
+/// profile without replacing it. The variants share one fresh
+/// `filament_id`, keeping them grouped in Bambu Studio but apart from the
+/// filament itself.
+pub fn build_kit(
+    test: CalibrationTest,
+    specs: &FilamentSpecs,
+    range: Option<CalibrationRange>,
+    registry: &ProfileRegistry,
+    machines: &MachineRegistry,
+    target_printer: Option<&str>,
+    base_profile: Option<&str>,
+) -> Result<Vec<CalibrationSample>> {
+    let range = range.unwrap_or_else(|| test.default_range(specs));
+    let values = range.values()?;
+    if test == CalibrationTest::TemperatureTower {
+        let constraints = constraints_for_material(&MaterialType::from_str(&specs.material));
+        for value in &values {
+            if *value < constraints.nozzle_temp_min as f32
+                || *value > constraints.nozzle_temp_max as f32
+            {
+                bail!(
+                    "{}C is outside the {} range ({}-{}C)",
+                    value,
+                    specs.material,
+                    constraints.nozzle_temp_min,
+                    constraints.nozzle_temp_max
+                );
+            }
+        }
+    }
+
+    let model = test.model();
+    let filament_id = generate_filament_id();
+    let mut samples = Vec::with_capacity(values.len());
+    for value in values {
+        let label = test.label(value);
+        let mut variant = specs.clone();
+        test.apply(&mut variant, value);
+        variant.serial = format!("{} Cal {} {}", specs.serial, test.tag(), label)
+            .trim()
+            .to_string();
+
+        let (mut profile, metadata, profile_filename) = generate_profile(
+            &variant,
+            registry,
+            machines,
+            target_printer,
+            base_profile,
+            Some(filament_id.clone()),
+        )?;
+        if test == CalibrationTest::PressureAdvance {
+            // The compatibility defaults leave pressure advance off, which
+            // would make every sample identical
+            let len = profile
+                .get_string_array("enable_pressure_advance")
+                .map_or(1, |v| v.len().max(1));
+            profile.set_string_array("enable_pressure_advance", vec!["1".to_string(); len]);
+        }
+
+        let stem = profile_filename
+            .strip_suffix(".json")
+            .unwrap_or(&profile_filename);
+        samples.push(CalibrationSample {
+            value,
+            stl: model.to_binary_stl(&format!("{} {}", test.tag(), label)),
+            stl_filename: format!("{}.stl", stem),
+            label,
+            profile,
+            metadata,
+            profile_filename,
+        });
+    }
+    Ok(samples)
+}
+
+/// What a print photo shows, when it's of calibration samples.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CalibrationContext {
+    pub test: CalibrationTest,
+    /// Parameter value of each sample in the photo, left to right.
+    pub values: Vec<f32>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mesh::tests::assert_watertight;
+    use super::*;
+
+    const TESTS: [CalibrationTest; 4] = [
+        CalibrationTest::TemperatureTower,
+        CalibrationTest::Retraction,
+        CalibrationTest::Flow,
+        CalibrationTest::PressureAdvance,
+    ];
+
+    #[test]
+    fn test_models_are_printable_solids() {
+        for test in TESTS {
+            let model = test.model();
+            assert_watertight(&model);
+            let (min, max) = model.bounds().unwrap();
+            assert_eq!(min[2], 0.0, "{:?} must rest on the bed", test);
+            assert!(max[0] <= 50.0 && max[1] <= 50.0, "{:?} is too large", test);
+        }
+    }
+
+    #[test]
+    fn test_range_values() {
+        let retraction = CalibrationTest::Retraction.default_range(&FilamentSpecs::default());
+        assert_eq!(
+            retraction.values().unwrap(),
+            [0.2, 0.4, 0.6, 0.8, 1.0, 1.2, 1.4, 1.6]
+        );
+        let flow = CalibrationTest::Flow.default_range(&FilamentSpecs::default());
+        assert_eq!(flow.values().unwrap().len(), 9);
+
+        let bad = |start, end, step| CalibrationRange { start, end, step }.values();
+        assert!(bad(1.0, 0.5, 0.1).is_err());
+        assert!(bad(0.0, 1.0, 0.0).is_err());
+        assert!(bad(0.0, 1.0, f32::NAN).is_err());
+        assert!(bad(180.0, 260.0, 5.0).is_err());
+    }
+
+    #[test]
+    fn test_default_temperature_range() {
+        let specs = FilamentSpecs {
+            material: "PLA".to_string(),
+            nozzle_temp_min: Some(190),
+            nozzle_temp_max: Some(230),
+            ..FilamentSpecs::default()
+        };
+        let values = CalibrationTest::TemperatureTower
+            .default_range(&specs)
+            .values()
+            .unwrap();
+        assert_eq!(values.first(), Some(&190.0));
+        assert_eq!(values.last(), Some(&230.0));
+        assert_eq!(values.len(), 9);
+
+        // No range on the specs: the material's whole range, in fewer steps
+        let specs = FilamentSpecs {
+            material: "PETG".to_string(),
+            ..FilamentSpecs::default()
+        };
+        let values = CalibrationTest::TemperatureTower
+            .default_range(&specs)
+            .values()
+            .unwrap();
+        assert!(values.len() <= MAX_SAMPLES);
+        assert_eq!(values.first(), Some(&210.0));
+    }
+
+    #[test]
+    fn test_apply_temperature_widens_range() {
+        let mut specs = FilamentSpecs {
+            nozzle_temp_min: Some(200),
+            nozzle_temp_max: Some(220),
+            ..FilamentSpecs::default()
+        };
+        CalibrationTest::TemperatureTower.apply(&mut specs, 225.0);
+        assert_eq!(specs.nozzle_temperature, Some(225));
+        assert_eq!(specs.nozzle_temperature_initial_layer, Some(225));
+        assert_eq!(specs.nozzle_temp_min, Some(200));
+        assert_eq!(specs.nozzle_temp_max, Some(225));
+    }
+}
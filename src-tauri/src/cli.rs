@@ -0,0 +1,618 @@
+//! Headless command-line interface over the backend library.
+//!
+//! Runs the same pipelines as the Tauri commands (filament search, profile
+//! generation and install, profile diff, print analysis and refinement
+//! history, and export for other slicers) without starting a webview, so
+//! profile generation can be scripted in shell pipelines and on build
+//! machines.
+//!
+//! Every subcommand prints its result as JSON on stdout; logs go to stderr.
+//! Commands that take JSON input read it from a file argument or stdin, so
+//! the output of one step can be piped into the next:
+//!
+//! ```text
+//! bambumate-cli search "Polymaker PolyTerra PLA" \
+//!     | bambumate-cli generate --printer "Bambu Lab X1 Carbon 0.4 nozzle" \
+//!     | bambumate-cli install
+//! ```
+//!
+//! AI settings and caches are shared with the desktop app: the CLI reads
+//! `preferences.json` from the app data directory and API keys from the
+//! system keychain, with command-line flags taking precedence.
+
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use base64::Engine;
+use clap::{Args, Parser, Subcommand};
+use serde_json::{Map, Value};
+
+use crate::commands;
+use crate::history::RefinementHistory;
+use crate::profile::paths::BambuPaths;
+use crate::profile::slicer::{EmittedProfile, SlicerTarget};
+use crate::profile::types::{FilamentProfile, ProfileMetadata};
+use crate::profile::writer::write_profile_with_metadata;
+use crate::scraper::types::FilamentSpecs;
+
+/// Tauri bundle identifier; the app data directory is named after it.
+/// Must match `identifier` in `tauri.conf.json`.
+const APP_IDENTIFIER: &str = "com.bambumate.app";
+
+/// Headless BambuMate: filament profiles and print analysis from the shell.
+#[derive(Debug, Parser)]
+#[command(name = "bambumate-cli", version)]
+pub struct Cli {
+    /// Bambu Studio config directory (defaults to the detected install).
+    #[arg(long, global = true, value_name = "DIR")]
+    config_root: Option<PathBuf>,
+
+    /// BambuMate data directory holding preferences, caches and history
+    /// (defaults to the desktop app's data directory).
+    #[arg(long, global = true, value_name = "DIR")]
+    data_dir: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Look up filament specifications by name (cache, AI knowledge, web).
+    Search {
+        /// Filament name, e.g. "Polymaker PolyTerra PLA".
+        name: String,
+        /// Skip AI and extract specs from manufacturer pages only.
+        #[arg(long)]
+        web_only: bool,
+        #[command(flatten)]
+        ai: AiArgs,
+    },
+    /// Generate a profile from filament specs JSON (preview; nothing installed).
+    Generate {
+        /// Specs JSON as printed by `search` ("-" or omitted for stdin).
+        specs: Option<PathBuf>,
+        /// Target printer, e.g. "Bambu Lab X1 Carbon 0.4 nozzle".
+        #[arg(long)]
+        printer: Option<String>,
+        /// Profile JSON to inherit from instead of the material default.
+        #[arg(long, value_name = "PATH")]
+        base: Option<PathBuf>,
+        /// Reuse this filament_id (e.g. for another nozzle of the same filament).
+        #[arg(long)]
+        filament_id: Option<String>,
+        /// Inherit from the base (default: the printer's system preset for the
+        /// material) and keep only the fields that differ from it.
+        #[arg(long)]
+        delta: bool,
+        /// Also write the profile and its .info file into this directory.
+        #[arg(long, value_name = "DIR")]
+        out: Option<PathBuf>,
+    },
+    /// Render filament specs as profiles for OrcaSlicer, PrusaSlicer or
+    /// Bambu Studio (preview; nothing installed).
+    Export {
+        /// Specs JSON as printed by `search` ("-" or omitted for stdin).
+        specs: Option<PathBuf>,
+        /// Target slicer: bambu, orca or prusa. Repeat for several.
+        #[arg(long = "slicer", value_name = "SLICER", required = true, value_parser = parse_slicer)]
+        slicers: Vec<SlicerTarget>,
+        /// Printer preset the profiles are meant for.
+        #[arg(long)]
+        printer: Option<String>,
+        /// Also write each profile into this directory.
+        #[arg(long, value_name = "DIR")]
+        out: Option<PathBuf>,
+    },
+    /// Install a generated profile into the Bambu Studio user directory.
+    Install {
+        /// Output of `generate` ("-" or omitted for stdin).
+        generated: Option<PathBuf>,
+        /// Install even while Bambu Studio is running.
+        #[arg(long)]
+        force: bool,
+    },
+    /// Compare two profiles field by field, grouped by category.
+    Diff {
+        a: PathBuf,
+        b: PathBuf,
+        /// Include fields that are identical in both profiles.
+        #[arg(long)]
+        all: bool,
+    },
+    /// Analyze a print photo for defects and recommend setting changes.
+    Analyze {
+        /// Photo of the test print.
+        image: PathBuf,
+        /// Profile the print was made with (for current values and material).
+        #[arg(long, value_name = "PATH")]
+        profile: Option<String>,
+        /// Material type when no profile is given (default PLA).
+        #[arg(long)]
+        material: Option<String>,
+        #[command(flatten)]
+        ai: AiArgs,
+    },
+    /// Inspect or revert refinement history.
+    #[command(subcommand)]
+    History(HistoryCommand),
+}
+
+#[derive(Debug, Subcommand)]
+enum HistoryCommand {
+    /// List refinement sessions for a profile (newest first).
+    List { profile: String },
+    /// Show a session's analysis and applied changes.
+    Show { session_id: i64 },
+    /// Restore a profile from the backup taken when a session was applied.
+    Revert { session_id: i64 },
+}
+
+/// AI provider overrides; unset values fall back to the app's preferences.
+#[derive(Debug, Default, Args)]
+struct AiArgs {
+    /// AI provider: claude, openai, kimi, openrouter or local.
+    #[arg(long)]
+    provider: Option<String>,
+    /// Model ID for the provider.
+    #[arg(long)]
+    model: Option<String>,
+    /// API key (the server URL for the local provider). Defaults to the
+    /// system keychain entry the desktop app uses.
+    #[arg(long, env = "BAMBUMATE_API_KEY", hide_env_values = true)]
+    api_key: Option<String>,
+}
+
+/// Entry point for the `bambumate-cli` binary.
+pub fn run() -> ExitCode {
+    let cli = Cli::parse();
+
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("warn")),
+        )
+        .init();
+
+    match tauri::async_runtime::block_on(execute(cli)) {
+        Ok(output) => match serde_json::to_string_pretty(&output) {
+            Ok(json) => {
+                println!("{}", json);
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                eprintln!("error: failed to serialize output: {}", e);
+                ExitCode::FAILURE
+            }
+        },
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn execute(cli: Cli) -> Result<Value, String> {
+    match cli.command {
+        Command::Search { name, web_only, ai } => {
+            let data_dir = data_dir(cli.data_dir)?;
+            let prefs = load_preferences(&data_dir);
+            let use_ai =
+                prefs.get("filament_search_use_ai").and_then(|v| v.as_str()) != Some("false");
+            let specs = if web_only || !use_ai {
+                crate::scraper::search_filament_web_only(&name, &data_dir).await?
+            } else {
+                let (provider, model, api_key) = resolve_ai_settings(ai, &prefs)?;
+                crate::scraper::search_filament(&name, &provider, &model, &api_key, &data_dir)
+                    .await?
+            };
+            to_json(&specs)
+        }
+        Command::Generate {
+            specs,
+            printer,
+            base,
+            filament_id,
+            delta,
+            out,
+        } => {
+            let input = read_input(specs.as_deref())?;
+            let specs: FilamentSpecs = serde_json::from_str(&input)
+                .map_err(|e| format!("Invalid filament specs JSON: {}", e))?;
+            let paths = bambu_paths(cli.config_root)?;
+            let base = base.map(|p| p.to_string_lossy().to_string());
+            let result = commands::profile::generate_profile_for_paths(
+                &paths,
+                &specs,
+                printer.as_deref(),
+                base.as_deref(),
+                filament_id,
+                delta,
+            )?;
+            if let Some(dir) = out {
+                write_generated(&dir, &result)?;
+            }
+            to_json(&result)
+        }
+        Command::Export {
+            specs,
+            slicers,
+            printer,
+            out,
+        } => {
+            let input = read_input(specs.as_deref())?;
+            let specs: FilamentSpecs = serde_json::from_str(&input)
+                .map_err(|e| format!("Invalid filament specs JSON: {}", e))?;
+            // Only the Bambu Studio target needs an install to generate against
+            let paths = if slicers.contains(&SlicerTarget::BambuStudio) {
+                Some(bambu_paths(cli.config_root)?)
+            } else {
+                None
+            };
+            let emitted = commands::slicer::emit_for_targets(
+                paths.as_ref(),
+                &specs,
+                &slicers,
+                printer.as_deref(),
+            )?;
+            if let Some(dir) = out {
+                write_emitted(&dir, &emitted)?;
+            }
+            to_json(&emitted)
+        }
+        Command::Install { generated, force } => {
+            let input = read_input(generated.as_deref())?;
+            let generated: commands::profile::GenerateResult = serde_json::from_str(&input)
+                .map_err(|e| format!("Invalid generate output: {}", e))?;
+            let paths = bambu_paths(cli.config_root)?;
+            commands::profile::recover_interrupted_installs(&paths);
+            let result = commands::profile::install_profile_for_paths(
+                &paths,
+                &generated.profile_json,
+                &generated.metadata_info,
+                &generated.filename,
+                force,
+            )?;
+            to_json(&result)
+        }
+        Command::Diff { a, b, all } => {
+            let result = commands::profile::compare_profiles(
+                a.to_string_lossy().to_string(),
+                b.to_string_lossy().to_string(),
+                all,
+            )?;
+            to_json(&result)
+        }
+        Command::Analyze {
+            image,
+            profile,
+            material,
+            ai,
+        } => {
+            let bytes = std::fs::read(&image)
+                .map_err(|e| format!("Failed to read image {:?}: {}", image, e))?;
+            let data_dir = data_dir(cli.data_dir)?;
+            let prefs = load_preferences(&data_dir);
+            let (provider, model, api_key) = resolve_ai_settings(ai, &prefs)?;
+            let request = commands::analyzer::AnalyzeRequest {
+                image_base64: base64::engine::general_purpose::STANDARD.encode(bytes),
+                profile_path: profile,
+                material_type: material,
+                calibration: None,
+            };
+            let result = commands::analyzer::analyze_with_settings(
+                request,
+                &provider,
+                &model,
+                &api_key,
+                data_dir.join("refinement_history.db"),
+            )
+            .await?;
+            to_json(&result)
+        }
+        Command::History(sub) => {
+            let db_path = data_dir(cli.data_dir)?.join("refinement_history.db");
+            match sub {
+                HistoryCommand::List { profile } => {
+                    let history = RefinementHistory::new(&db_path)?;
+                    to_json(&history.list_sessions(&profile)?)
+                }
+                HistoryCommand::Show { session_id } => {
+                    let history = RefinementHistory::new(&db_path)?;
+                    to_json(&history.get_session(session_id)?)
+                }
+                HistoryCommand::Revert { session_id } => {
+                    let message = commands::history::revert_session(&db_path, session_id)?;
+                    Ok(Value::String(message))
+                }
+            }
+        }
+    }
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> Result<Value, String> {
+    serde_json::to_value(value).map_err(|e| format!("Failed to serialize output: {}", e))
+}
+
+/// Read JSON input from a file, or from stdin when the path is omitted or "-".
+fn read_input(path: Option<&Path>) -> Result<String, String> {
+    match path {
+        Some(p) if p != Path::new("-") => {
+            std::fs::read_to_string(p).map_err(|e| format!("Failed to read {:?}: {}", p, e))
+        }
+        _ => {
+            let mut buf = String::new();
+            std::io::stdin()
+                .read_to_string(&mut buf)
+                .map_err(|e| format!("Failed to read stdin: {}", e))?;
+            Ok(buf)
+        }
+    }
+}
+
+/// Bambu Studio paths from `--config-root`, or the detected install.
+fn bambu_paths(config_root: Option<PathBuf>) -> Result<BambuPaths, String> {
+    match config_root {
+        Some(root) => {
+            if !root.is_dir() {
+                return Err(format!("Config root {:?} is not a directory", root));
+            }
+            Ok(BambuPaths::from_config_root(root))
+        }
+        None => BambuPaths::detect().map_err(|e| format!("Bambu Studio not found: {}", e)),
+    }
+}
+
+/// The app data directory shared with the desktop app, created if missing.
+fn data_dir(data_dir: Option<PathBuf>) -> Result<PathBuf, String> {
+    let dir = match data_dir {
+        Some(dir) => dir,
+        None => dirs::data_dir()
+            .ok_or_else(|| "Could not determine the data directory".to_string())?
+            .join(APP_IDENTIFIER),
+    };
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create data directory {:?}: {}", dir, e))?;
+    Ok(dir)
+}
+
+/// Load the desktop app's `preferences.json` store. Missing or unreadable
+/// stores yield an empty map so every setting falls back to its default.
+fn load_preferences(data_dir: &Path) -> Map<String, Value> {
+    std::fs::read_to_string(data_dir.join("preferences.json"))
+        .ok()
+        .and_then(|s| serde_json::from_str::<Value>(&s).ok())
+        .and_then(|v| match v {
+            Value::Object(map) => Some(map),
+            _ => None,
+        })
+        .unwrap_or_default()
+}
+
+/// Resolve (provider, model, api_key) from flags, then preferences, then
+/// defaults. The preferred model only applies when the provider was not
+/// overridden, since models are provider-specific.
+fn resolve_ai_settings(
+    ai: AiArgs,
+    prefs: &Map<String, Value>,
+) -> Result<(String, String, String), String> {
+    let pref = |key: &str| {
+        prefs
+            .get(key)
+            .and_then(|v| v.as_str())
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_string())
+    };
+
+    let provider_overridden = ai.provider.is_some();
+    let provider = ai
+        .provider
+        .or_else(|| pref("ai_provider"))
+        .unwrap_or_else(|| "claude".to_string());
+    let model = ai
+        .model
+        .or_else(|| {
+            if provider_overridden {
+                None
+            } else {
+                pref("ai_model")
+            }
+        })
+        .unwrap_or_else(|| commands::analyzer::default_model_for_provider(&provider).to_string());
+
+    let api_key = match ai.api_key {
+        Some(key) => key,
+        None if provider == "local" => {
+            pref("local_mcp_url").unwrap_or_else(|| "http://localhost:1234".to_string())
+        }
+        None => {
+            let service = commands::analyzer::keychain_service_for_provider(&provider)
+                .ok_or_else(|| {
+                    format!(
+                        "Unknown AI provider: '{}'. Supported: claude, openai, kimi, openrouter, local",
+                        provider
+                    )
+                })?;
+            commands::keychain::get_api_key(service)?.ok_or_else(|| {
+                format!(
+                    "No API key configured for '{}'. Pass --api-key, set BAMBUMATE_API_KEY, or set it in the app's Settings.",
+                    provider
+                )
+            })?
+        }
+    };
+
+    Ok((provider, model, api_key))
+}
+
+/// Write a generated profile and its `.info` companion into `dir`.
+fn write_generated(dir: &Path, result: &commands::profile::GenerateResult) -> Result<(), String> {
+    let profile = FilamentProfile::from_json(&result.profile_json)
+        .map_err(|e| format!("Invalid profile JSON: {}", e))?;
+    let metadata = ProfileMetadata::from_info_string(&result.metadata_info)
+        .map_err(|e| format!("Invalid metadata: {}", e))?;
+    std::fs::create_dir_all(dir)
+        .map_err(|e| format!("Failed to create output directory {:?}: {}", dir, e))?;
+    write_profile_with_metadata(&profile, &dir.join(&result.filename), &metadata)
+        .map_err(|e| format!("Failed to write profile: {}", e))
+}
+
+fn parse_slicer(name: &str) -> Result<SlicerTarget, String> {
+    SlicerTarget::parse(name)
+        .ok_or_else(|| format!("unknown slicer '{}' (expected bambu, orca or prusa)", name))
+}
+
+fn write_emitted(dir: &Path, emitted: &[EmittedProfile]) -> Result<(), String> {
+    std::fs::create_dir_all(dir)
+        .map_err(|e| format!("Failed to create output directory {:?}: {}", dir, e))?;
+    for profile in emitted {
+        let path = dir.join(&profile.filename);
+        std::fs::write(&path, &profile.content)
+            .map_err(|e| format!("Failed to write {:?}: {}", path, e))?;
+        if let Some(info) = &profile.metadata_info {
+            let info_path = path.with_extension("info");
+            std::fs::write(&info_path, info)
+                .map_err(|e| format!("Failed to write {:?}: {}", info_path, e))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn test_cli_definition_is_valid() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_parse_generate_with_global_config_root() {
+        let cli = Cli::try_parse_from([
+            "bambumate-cli",
+            "generate",
+            "specs.json",
+            "--printer",
+            "Bambu Lab X1 Carbon 0.4 nozzle",
+            "--config-root",
+            "/tmp/BambuStudio",
+        ])
+        .unwrap();
+        assert_eq!(cli.config_root, Some(PathBuf::from("/tmp/BambuStudio")));
+        match cli.command {
+            Command::Generate { specs, printer, .. } => {
+                assert_eq!(specs, Some(PathBuf::from("specs.json")));
+                assert_eq!(printer.as_deref(), Some("Bambu Lab X1 Carbon 0.4 nozzle"));
+            }
+            other => panic!("expected generate, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_export_slicers() {
+        let cli = Cli::try_parse_from([
+            "bambumate-cli",
+            "export",
+            "--slicer",
+            "orca",
+            "--slicer",
+            "PrusaSlicer",
+        ])
+        .unwrap();
+        match cli.command {
+            Command::Export { slicers, .. } => assert_eq!(
+                slicers,
+                vec![SlicerTarget::OrcaSlicer, SlicerTarget::PrusaSlicer]
+            ),
+            other => panic!("expected export, got {:?}", other),
+        }
+        assert!(Cli::try_parse_from(["bambumate-cli", "export", "--slicer", "cura"]).is_err());
+        assert!(Cli::try_parse_from(["bambumate-cli", "export"]).is_err());
+    }
+
+    #[test]
+    fn test_write_emitted_without_bambu_install() {
+        let specs = FilamentSpecs {
+            brand: "Sunlu".to_string(),
+            material: "PETG".to_string(),
+            nozzle_temperature: Some(240),
+            ..FilamentSpecs::default()
+        };
+        let emitted = commands::slicer::emit_for_targets(
+            None,
+            &specs,
+            &[SlicerTarget::OrcaSlicer, SlicerTarget::PrusaSlicer],
+            None,
+        )
+        .unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        write_emitted(dir.path(), &emitted).unwrap();
+        assert!(dir.path().join("Sunlu PETG.json").exists());
+        assert!(dir.path().join("Sunlu PETG.ini").exists());
+
+        let err =
+            commands::slicer::emit_for_targets(None, &specs, &[SlicerTarget::BambuStudio], None)
+                .unwrap_err();
+        assert!(err.contains("Bambu Studio not found"));
+    }
+
+    #[test]
+    fn test_parse_history_subcommands() {
+        let cli = Cli::try_parse_from(["bambumate-cli", "history", "show", "42"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Command::History(HistoryCommand::Show { session_id: 42 })
+        ));
+        assert!(Cli::try_parse_from(["bambumate-cli", "history", "show", "abc"]).is_err());
+    }
+
+    #[test]
+    fn test_ai_settings_flags_override_preferences() {
+        let prefs: Map<String, Value> = serde_json::from_str(
+            r#"{"ai_provider": "claude", "ai_model": "claude-x", "local_mcp_url": "http://box:1234"}"#,
+        )
+        .unwrap();
+        let ai = AiArgs {
+            provider: Some("local".to_string()),
+            model: None,
+            api_key: None,
+        };
+        let (provider, model, key) = resolve_ai_settings(ai, &prefs).unwrap();
+        assert_eq!(provider, "local");
+        // ai_model belongs to the preferred provider, so it is not carried over.
+        assert_eq!(model, "default");
+        assert_eq!(key, "http://box:1234");
+    }
+
+    #[test]
+    fn test_ai_settings_from_preferences_with_explicit_key() {
+        let prefs: Map<String, Value> =
+            serde_json::from_str(r#"{"ai_provider": "openai", "ai_model": "gpt-4.1"}"#).unwrap();
+        let ai = AiArgs {
+            api_key: Some("sk-test".to_string()),
+            ..Default::default()
+        };
+        let (provider, model, key) = resolve_ai_settings(ai, &prefs).unwrap();
+        assert_eq!(provider, "openai");
+        assert_eq!(model, "gpt-4.1");
+        assert_eq!(key, "sk-test");
+    }
+
+    #[test]
+    fn test_load_preferences_missing_store() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        assert!(load_preferences(tmp.path()).is_empty());
+    }
+
+    #[test]
+    fn test_read_input_from_file() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join("specs.json");
+        std::fs::write(&path, "{}").unwrap();
+        assert_eq!(read_input(Some(&path)).unwrap(), "{}");
+    }
+}
@@ -8,7 +8,7 @@
 //! Also provides apply_recommendations for applying changes to profiles.
 
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use base64::Engine;
 use keyring::Entry;
@@ -18,9 +18,14 @@ use tauri_plugin_store::StoreExt;
 use tracing::{info, warn};
 
 use crate::analyzer::{analyze_image, DefectReport};
+use crate::calibration::CalibrationContext;
+use crate::commands::profile::{load_machine_registry, profile_extruder_count};
 use crate::history::{AppliedChange, RefinementHistory, SessionDetail};
 use crate::mapper::{default_rules, Conflict, RuleEngine};
-use crate::profile::FilamentProfile;
+use crate::pending_writes::{self, PendingOperation};
+use crate::profile::fields::dictionary;
+use crate::profile::lint::{lint_profile, LintOptions};
+use crate::profile::{BambuPaths, FilamentProfile};
 use crate::scraper::types::MaterialType;
 
 /// Request payload for print analysis.
@@ -32,6 +37,9 @@ pub struct AnalyzeRequest {
     pub profile_path: Option<String>,
     /// Material type override (if not using profile)
     pub material_type: Option<String>,
+    /// Set when the photo shows samples from a calibration kit
+    #[serde(default)]
+    pub calibration: Option<CalibrationContext>,
 }
 
 /// Request payload for applying recommendations.
@@ -54,6 +62,9 @@ pub struct ApplyResult {
     pub changes_applied: Vec<AppliedChange>,
     /// Path to the modified profile
     pub profile_path: String,
+    /// Set when Bambu Studio was running and the changes were queued until
+    /// it exits; `backup_path` is empty until they're written.
+    pub queued: bool,
 }
 
 /// Full analysis response including defects, recommendations, and conflicts.
@@ -126,14 +137,33 @@ pub async fn analyze_print(
         return Err(reason);
     }
 
+    // Get AI provider settings
+    let (provider, model, api_key) = get_ai_settings(&app)?;
+
+    let data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get data dir: {}", e))?;
+    let db_path = data_dir.join("refinement_history.db");
+
+    analyze_with_settings(request, &provider, &model, &api_key, db_path).await
+}
+
+/// Analysis pipeline behind `analyze_print` with the AI settings and history
+/// database supplied by the caller. Shared with the headless CLI, which has
+/// no preferences store to read them from.
+pub(crate) async fn analyze_with_settings(
+    request: AnalyzeRequest,
+    provider: &str,
+    model: &str,
+    api_key: &str,
+    db_path: std::path::PathBuf,
+) -> Result<AnalyzeResponse, String> {
     // Decode base64 image
     let image_bytes = base64::engine::general_purpose::STANDARD
         .decode(&request.image_base64)
         .map_err(|e| format!("Invalid base64 image data: {}", e))?;
 
-    // Get AI provider settings
-    let (provider, model, api_key) = get_ai_settings(&app)?;
-
     // Load profile for current values (or use defaults)
     let (current_values, material_type) = if let Some(ref profile_path) = request.profile_path {
         load_profile_context(profile_path)?
@@ -150,9 +180,10 @@ pub async fn analyze_print(
         &image_bytes,
         &current_values,
         &material_type,
-        &provider,
-        &model,
-        &api_key,
+        request.calibration.as_ref(),
+        provider,
+        model,
+        api_key,
     )
     .await?;
 
@@ -172,12 +203,6 @@ pub async fn analyze_print(
     );
 
     // Record analysis session in history (blocking rusqlite off the async runtime)
-    let data_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("Failed to get data dir: {}", e))?;
-    let db_path = data_dir.join("refinement_history.db");
-
     // Build response JSON for storage (without session_id to avoid recursion)
     let analysis_for_storage = serde_json::json!({
         "defect_report": defect_report,
@@ -185,6 +210,7 @@ pub async fn analyze_print(
         "conflicts": evaluation.conflicts,
         "current_values": current_values,
         "material_type": material_type,
+        "calibration": request.calibration,
     });
     let analysis_json = serde_json::to_string(&analysis_for_storage).unwrap_or_default();
 
@@ -239,6 +265,8 @@ pub async fn analyze_print(
 ///
 /// Creates a backup before modification, applies the selected parameter changes,
 /// and records the application in the refinement history.
+/// While Bambu Studio is running all of this is queued until it exits (see
+/// `pending_writes`).
 #[tauri::command]
 pub async fn apply_recommendations(
     app: tauri::AppHandle,
@@ -267,17 +295,8 @@ pub async fn apply_recommendations(
     let analysis: AnalyzeResponse = serde_json::from_str(&session.analysis_json)
         .map_err(|e| format!("Failed to parse analysis: {}", e))?;
 
-    // 3. Create backup BEFORE any modification
-    let profile_path = Path::new(&request.profile_path);
-    let backup_path = crate::profile::writer::backup_profile(profile_path)
-        .map_err(|e| format!("Failed to create backup: {}", e))?;
-
-    // 4. Load current profile
-    let profile = crate::profile::reader::read_profile(profile_path)
-        .map_err(|e| format!("Failed to read profile: {}", e))?;
-
-    // 5. Apply selected recommendations
-    let mut data = profile.raw().clone();
+    // 3. Collect selected recommendations
+    let mut values: Vec<(String, String)> = Vec::new();
     let mut changes: Vec<AppliedChange> = Vec::new();
 
     for rec in &analysis.recommendations {
@@ -287,7 +306,7 @@ pub async fn apply_recommendations(
 
         // Format value for Bambu Studio (string in array)
         let formatted = format_value_for_profile(rec.recommended_value, &rec.parameter);
-        data.insert(rec.parameter.clone(), serde_json::json!([formatted]));
+        values.push((rec.parameter.clone(), formatted));
 
         changes.push(AppliedChange {
             parameter: rec.parameter.clone(),
@@ -296,23 +315,39 @@ pub async fn apply_recommendations(
         });
     }
 
-    let modified = FilamentProfile::from_map(data);
-
-    // 6. Write modified profile atomically
-    crate::profile::writer::write_profile_atomic(&modified, profile_path)
-        .map_err(|e| format!("Failed to write profile: {}", e))?;
+    // 4. Hold the write back while Bambu Studio could overwrite it on exit
+    if crate::profile::generator::is_bambu_studio_running() {
+        pending_writes::enqueue(
+            &app,
+            PendingOperation::ApplyRecommendations {
+                path: request.profile_path.clone(),
+                session_id: session_id_val,
+                values,
+                changes: changes.clone(),
+            },
+        )?;
+        return Ok(ApplyResult {
+            backup_path: String::new(),
+            changes_applied: changes,
+            profile_path: request.profile_path,
+            queued: true,
+        });
+    }
 
-    // 7. Record apply in history (blocking rusqlite off the async runtime)
-    let backup_path_string = backup_path.to_string_lossy().to_string();
+    // 5. Back up, write and record (blocking file and rusqlite I/O)
+    let profile_path = request.profile_path.clone();
     let changes_clone = changes.clone();
-    let backup_path_history = backup_path_string.clone();
-    tokio::task::spawn_blocking(move || -> Result<(), String> {
-        let history = RefinementHistory::new(&db_path)
-            .map_err(|e| format!("Failed to open history: {}", e))?;
-        history.record_apply(session_id_val, &changes_clone, &backup_path_history)
+    let backup_path = tokio::task::spawn_blocking(move || {
+        apply_values_to_profile(
+            &db_path,
+            Path::new(&profile_path),
+            session_id_val,
+            &values,
+            &changes_clone,
+        )
     })
     .await
-    .map_err(|e| format!("history join error: {}", e))??;
+    .map_err(|e| format!("apply join error: {}", e))??;
 
     info!(
         "Applied {} changes to profile, backup at {:?}",
@@ -324,9 +359,69 @@ pub async fn apply_recommendations(
         backup_path: backup_path.to_string_lossy().to_string(),
         changes_applied: changes,
         profile_path: request.profile_path,
+        queued: false,
     })
 }
 
+/// Back up the profile at `profile_path`, write the formatted `values` into
+/// it and record `changes` against `session_id` in the history at
+/// `db_path`. Returns the backup path.
+///
+/// Per-extruder values are written once per extruder of the profile's
+/// printer, or as many times as the profile has them when its machine
+/// preset isn't installed. Only the written fields are linted.
+pub(crate) fn apply_values_to_profile(
+    db_path: &Path,
+    profile_path: &Path,
+    session_id: i64,
+    values: &[(String, String)],
+    changes: &[AppliedChange],
+) -> Result<PathBuf, String> {
+    let profile = crate::profile::reader::read_profile(profile_path)
+        .map_err(|e| format!("Failed to read profile: {}", e))?;
+    let extruder_count = BambuPaths::detect()
+        .ok()
+        .and_then(|paths| profile_extruder_count(&load_machine_registry(&paths), &profile));
+    let mut data = profile.raw().clone();
+    for (parameter, formatted) in values {
+        let per_extruder = dictionary()
+            .get(parameter)
+            .is_some_and(|spec| spec.is_per_extruder());
+        let count = match (per_extruder, extruder_count) {
+            (false, _) => 1,
+            (true, Some(count)) => count.max(1),
+            (true, None) => data
+                .get(parameter)
+                .and_then(serde_json::Value::as_array)
+                .map_or(1, |items| items.len().max(1)),
+        };
+        data.insert(parameter.clone(), serde_json::json!(vec![formatted; count]));
+    }
+    let modified = FilamentProfile::from_map(data);
+    let keys: Vec<&str> = values.iter().map(|(k, _)| k.as_str()).collect();
+    let lint = lint_profile(&modified, &LintOptions { extruder_count }).for_keys(&keys);
+    if lint.has_errors() {
+        return Err(format!(
+            "Refusing to write an invalid profile: {}",
+            lint.error_summary()
+        ));
+    }
+
+    // Create backup BEFORE any modification
+    let backup_path = crate::profile::writer::backup_profile(profile_path)
+        .map_err(|e| format!("Failed to create backup: {}", e))?;
+
+    // Write modified profile atomically
+    crate::profile::writer::write_edited_profile(&modified, profile_path)
+        .map_err(|e| format!("Failed to write profile: {}", e))?;
+
+    let history =
+        RefinementHistory::new(db_path).map_err(|e| format!("Failed to open history: {}", e))?;
+    history.record_apply(session_id, changes, &backup_path.to_string_lossy())?;
+
+    Ok(backup_path)
+}
+
 /// Format a value for Bambu Studio profile format.
 /// Different parameters need different precision.
 fn format_value_for_profile(value: f32, parameter: &str) -> String {
@@ -367,18 +462,10 @@ fn get_ai_settings(app: &tauri::AppHandle) -> Result<(String, String, String), S
         .unwrap_or_else(|| "claude".to_string());
 
     // Get model preference (default based on provider)
-    let default_model = match provider.as_str() {
-        "claude" => "claude-sonnet-4-20250514",
-        "openai" => "gpt-4o",
-        "kimi" => "moonshot-v1-128k",
-        "openrouter" => "anthropic/claude-sonnet-4",
-        "local" => "default",
-        _ => "claude-sonnet-4-20250514",
-    };
     let model = store
         .get("ai_model")
         .and_then(|v| v.as_str().map(|s| s.to_string()))
-        .unwrap_or_else(|| default_model.to_string());
+        .unwrap_or_else(|| default_model_for_provider(&provider).to_string());
 
     // Local provider passes the server URL as the "api_key"
     if provider == "local" {
@@ -391,13 +478,8 @@ fn get_ai_settings(app: &tauri::AppHandle) -> Result<(String, String, String), S
     }
 
     // Get API key from keychain
-    let service = match provider.as_str() {
-        "claude" => "bambumate-claude-api",
-        "openai" => "bambumate-openai-api",
-        "kimi" => "bambumate-kimi-api",
-        "openrouter" => "bambumate-openrouter-api",
-        _ => return Err(format!("Unknown AI provider: {}", provider)),
-    };
+    let service = keychain_service_for_provider(&provider)
+        .ok_or_else(|| format!("Unknown AI provider: {}", provider))?;
 
     let entry = Entry::new(service, "bambumate").map_err(|e| e.to_string())?;
     let api_key = match entry.get_password() {
@@ -414,6 +496,31 @@ fn get_ai_settings(app: &tauri::AppHandle) -> Result<(String, String, String), S
     Ok((provider, model, api_key))
 }
 
+/// Default vision model for a provider when none is configured.
+pub(crate) fn default_model_for_provider(provider: &str) -> &'static str {
+    match provider {
+        "claude" => "claude-sonnet-4-20250514",
+        "openai" => "gpt-4o",
+        "kimi" => "moonshot-v1-128k",
+        "openrouter" => "anthropic/claude-sonnet-4",
+        "local" => "default",
+        _ => "claude-sonnet-4-20250514",
+    }
+}
+
+/// Keychain service name holding the API key for a provider.
+///
+/// Returns None for unknown providers and for "local", which has no key.
+pub(crate) fn keychain_service_for_provider(provider: &str) -> Option<&'static str> {
+    match provider {
+        "claude" => Some("bambumate-claude-api"),
+        "openai" => Some("bambumate-openai-api"),
+        "kimi" => Some("bambumate-kimi-api"),
+        "openrouter" => Some("bambumate-openrouter-api"),
+        _ => None,
+    }
+}
+
 /// Load current values and material type from a profile.
 fn load_profile_context(profile_path: &str) -> Result<(HashMap<String, f32>, String), String> {
     let path = Path::new(profile_path);
@@ -2,11 +2,14 @@ use serde::Serialize;
 use tauri::Manager;
 use tracing::{info, warn};
 
+use crate::commands::profile::{check_installable, install_profiles_for_paths, ProfileInstall};
+use crate::commands::snapshot::snapshot_before;
+use crate::pending_writes::{self, PendingOperation};
 use crate::profile::generator;
 use crate::profile::paths::BambuPaths;
-use crate::profile::reader::read_profile;
+use crate::profile::reader::{read_profile, read_profile_metadata};
+use crate::profile::rebase;
 use crate::profile::registry::ProfileRegistry;
-use crate::profile::writer::write_profile_with_metadata;
 
 /// Default target printer label used when the caller doesn't specify one.
 /// Must match `generator::generate_profile`'s internal default so the filename
@@ -50,6 +53,9 @@ pub struct BatchProgress {
     pub succeeded: usize,
     pub failed: usize,
     pub results: Vec<BatchEntry>,
+    /// The installs were queued until Bambu Studio exits (see
+    /// `pending_writes`).
+    pub queued: bool,
 }
 
 /// Get the catalog database path (same logic as scraper commands).
@@ -80,6 +86,9 @@ pub async fn list_catalog_brands(app: tauri::AppHandle) -> Result<Vec<String>, S
 ///
 /// For each filament in the brand, generates a profile from catalog metadata,
 /// and optionally installs it. Sequential with a small delay for rate limiting.
+/// Installing snapshots the user library first, then installs every
+/// generated profile in one transaction. While Bambu Studio is running the
+/// installs are queued together until it exits instead.
 #[tauri::command]
 pub async fn batch_generate_brand(
     app: tauri::AppHandle,
@@ -94,9 +103,10 @@ pub async fn batch_generate_brand(
 
     // Get all filaments for the brand
     let db_path = get_catalog_path(&app)?;
+    let catalog_brand = brand.clone();
     let entries = tokio::task::spawn_blocking(move || {
         let catalog = crate::scraper::catalog::FilamentCatalog::new(&db_path)?;
-        catalog.get_brand(&brand)
+        catalog.get_brand(&catalog_brand)
     })
     .await
     .map_err(|e| format!("Task panicked: {}", e))??;
@@ -109,12 +119,17 @@ pub async fn batch_generate_brand(
     let mut results = Vec::with_capacity(total);
     let mut succeeded = 0usize;
     let mut failed = 0usize;
+    // Profiles to install (with the index of their result) and their
+    // generation records
+    let mut installs = Vec::new();
+    let mut records = Vec::new();
 
     // Pre-load registry once for all generations
     let paths = BambuPaths::detect().map_err(|e| format!("Bambu Studio not found: {}", e))?;
     let system_dir = paths.system_filament_dir();
     let registry = ProfileRegistry::discover_system_profiles(&system_dir)
         .map_err(|e| format!("Failed to load system profiles: {}", e))?;
+    let machines = crate::commands::profile::load_machine_registry(&paths);
     let user_dir = if install {
         Some(paths.user_filament_dir().ok_or_else(|| {
             "User filament directory not found. Log into Bambu Studio first.".to_string()
@@ -122,6 +137,10 @@ pub async fn batch_generate_brand(
     } else {
         None
     };
+    let queue = install && generator::is_bambu_studio_running();
+    if install && !queue {
+        snapshot_before(&app, &paths, &format!("batch install of {}", brand)).await?;
+    }
 
     // Even when we're not installing, look up the user filament directory so we
     // can reuse existing filament IDs. This keeps IDs stable across regenerations
@@ -187,20 +206,39 @@ pub async fn batch_generate_brand(
         match generator::generate_profile(
             &specs,
             &registry,
+            &machines,
             target_printer.as_deref(),
             None,
             resolved_filament_id,
         ) {
-            Ok((profile, metadata, filename)) => {
+            Ok((profile, mut metadata, filename)) => {
                 let profile_name = profile.name().unwrap_or("<unnamed>").to_string();
 
-                if install {
-                    if let Some(ref ud) = user_dir {
-                        let target_path = ud.join(&filename);
-                        if let Err(e) =
-                            write_profile_with_metadata(&profile, &target_path, &metadata)
-                        {
-                            warn!("Failed to install {}: {}", filament_name, e);
+                if let Some(ref ud) = user_dir {
+                    // An overwritten preset keeps its identity, so its cloud
+                    // copy is updated rather than duplicated
+                    match read_profile_metadata(&ud.join(&filename)) {
+                        Ok(Some(existing)) => metadata.mark_written_over(Some(existing)),
+                        Ok(None) => {}
+                        Err(e) => warn!("Failed to read metadata for {}: {}", filename, e),
+                    }
+                    // Staged here, installed with the rest of the batch below
+                    let staged = profile
+                        .to_json_4space()
+                        .map_err(|e| e.to_string())
+                        .and_then(|profile_json| {
+                            let install = ProfileInstall {
+                                profile_json,
+                                metadata_info: metadata.to_info_string(),
+                                filename,
+                            };
+                            check_installable(&paths, &install.profile_json, &install.metadata_info)
+                                .map(|_| install)
+                        });
+                    let install = match staged {
+                        Ok(install) => install,
+                        Err(e) => {
+                            warn!("Not installing {}: {}", filament_name, e);
                             failed += 1;
                             results.push(BatchEntry {
                                 filament_name,
@@ -212,7 +250,22 @@ pub async fn batch_generate_brand(
                             });
                             continue;
                         }
+                    };
+                    let base_name = generator::base_profile_name(
+                        &crate::scraper::types::MaterialType::from_str(&specs.material),
+                    );
+                    match rebase::current_base_fingerprint(&registry, base_name) {
+                        Ok(fingerprint) => records.push(rebase::GenerationRecord::new(
+                            metadata.setting_id.clone(),
+                            profile_name.clone(),
+                            specs.clone(),
+                            target_printer.clone(),
+                            base_name.to_string(),
+                            fingerprint,
+                        )),
+                        Err(e) => warn!("Not recording generation of {}: {}", filament_name, e),
                     }
+                    installs.push((results.len(), install));
                 }
 
                 succeeded += 1;
@@ -243,6 +296,39 @@ pub async fn batch_generate_brand(
         tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
     }
 
+    // Install the whole batch in one transaction, so a failure part way
+    // leaves none of it behind
+    let queued = queue && !installs.is_empty();
+    if queued {
+        let installs = installs.into_iter().map(|(_, install)| install).collect();
+        pending_writes::enqueue(&app, PendingOperation::InstallAll { installs })?;
+        for record in records {
+            crate::commands::rebase::record_generation(&app, record).await;
+        }
+    } else if !installs.is_empty() {
+        let (indices, batch): (Vec<usize>, Vec<ProfileInstall>) = installs.into_iter().unzip();
+        let installed =
+            tokio::task::spawn_blocking(move || install_profiles_for_paths(&paths, &batch, true))
+                .await
+                .map_err(|e| format!("batch install join error: {}", e))?;
+        match installed {
+            Ok(_) => {
+                for record in records {
+                    crate::commands::rebase::record_generation(&app, record).await;
+                }
+            }
+            Err(e) => {
+                warn!("Batch install of {} failed: {}", brand, e);
+                for i in indices {
+                    results[i].success = false;
+                    results[i].error = Some(format!("Install failed: {}", e));
+                    succeeded -= 1;
+                    failed += 1;
+                }
+            }
+        }
+    }
+
     info!(
         "Batch complete: {} total, {} succeeded, {} failed",
         total, succeeded, failed
@@ -254,5 +340,6 @@ pub async fn batch_generate_brand(
         succeeded,
         failed,
         results,
+        queued,
     })
 }
@@ -0,0 +1,177 @@
+//! Tauri commands for importing and exporting Bambu Studio preset bundles.
+
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+use crate::commands::profile::assert_in_dir;
+use crate::commands::snapshot::snapshot_before;
+use crate::pending_writes::{self, PendingOperation};
+use crate::profile::bundle::{
+    self, BundleType, ConflictPolicy, ImportAction, ImportItem, FILAMENT_BUNDLE_EXT,
+};
+use crate::profile::generator;
+use crate::profile::paths::BambuPaths;
+use crate::profile::registry::ProfileRegistry;
+
+/// Result of previewing or running a bundle import.
+#[derive(Debug, Clone, Serialize)]
+pub struct BundleImportResult {
+    pub bundle_type: BundleType,
+    pub items: Vec<ImportItem>,
+    /// Printer/process presets in the bundle that BambuMate does not import.
+    pub skipped_presets: usize,
+    pub bambu_studio_was_running: bool,
+    /// The import was queued until Bambu Studio exits (see `pending_writes`);
+    /// `items` is the plan it will follow.
+    pub queued: bool,
+}
+
+fn user_dir_for(paths: &BambuPaths) -> Result<PathBuf, String> {
+    paths.user_filament_dir().ok_or_else(|| {
+        "User filament directory not found. Have you logged into Bambu Studio at least once?"
+            .to_string()
+    })
+}
+
+fn system_registry(paths: &BambuPaths) -> Option<ProfileRegistry> {
+    let system_dir = paths.system_filament_dir();
+    if !system_dir.exists() {
+        return None;
+    }
+    match ProfileRegistry::discover_system_profiles(&system_dir) {
+        Ok(registry) => Some(registry),
+        Err(e) => {
+            warn!("Failed to load system profiles for conflict check: {}", e);
+            None
+        }
+    }
+}
+
+/// Plan (and unless `dry_run`, perform) a bundle import for the given paths.
+pub(crate) fn import_bundle_for_paths(
+    paths: &BambuPaths,
+    bundle_path: &Path,
+    policy: ConflictPolicy,
+    dry_run: bool,
+) -> Result<BundleImportResult, String> {
+    let bundle = bundle::read_bundle(bundle_path).map_err(|e| format!("{:#}", e))?;
+    let user_dir = user_dir_for(paths)?;
+    let system = system_registry(paths);
+
+    let bs_running = generator::is_bambu_studio_running();
+    let items = if dry_run {
+        bundle::plan_import(&bundle, &user_dir, system.as_ref(), policy)
+    } else {
+        bundle::install_bundle(
+            &bundle,
+            &user_dir,
+            &paths.config_root,
+            system.as_ref(),
+            policy,
+        )
+    }
+    .map_err(|e| format!("Failed to import bundle: {:#}", e))?;
+
+    if !dry_run {
+        info!(
+            "Imported {} of {} presets from {:?}",
+            items
+                .iter()
+                .filter(|i| i.action != ImportAction::Skip)
+                .count(),
+            items.len(),
+            bundle_path
+        );
+    }
+
+    Ok(BundleImportResult {
+        bundle_type: bundle.bundle_type,
+        items,
+        skipped_presets: bundle.skipped_presets,
+        bambu_studio_was_running: bs_running,
+        queued: false,
+    })
+}
+
+/// Export user filament profiles to a `.bbsflmt` bundle at `output_path`.
+pub(crate) fn export_bundle_for_paths(
+    paths: &BambuPaths,
+    profile_paths: &[String],
+    output_path: &Path,
+) -> Result<String, String> {
+    let user_dir = user_dir_for(paths)?;
+    let profile_paths = profile_paths
+        .iter()
+        .map(|p| assert_in_dir(Path::new(p), true, &user_dir))
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let output = if output_path.extension().is_none() {
+        output_path.with_extension(FILAMENT_BUNDLE_EXT)
+    } else {
+        output_path.to_path_buf()
+    };
+
+    let user_id = paths.preset_folder.as_deref().unwrap_or("");
+    bundle::export_filament_bundle(&profile_paths, &output, user_id)
+        .map_err(|e| format!("Failed to export bundle: {:#}", e))?;
+
+    Ok(output.to_string_lossy().to_string())
+}
+
+/// Preview importing a `.bbsflmt` / `.bbscfg` bundle: which presets would be
+/// installed, renamed, overwritten or skipped, and which have no parent.
+#[tauri::command]
+pub fn preview_bundle_import(
+    bundle_path: String,
+    policy: ConflictPolicy,
+) -> Result<BundleImportResult, String> {
+    let paths = BambuPaths::detect().map_err(|e| format!("Bambu Studio not found: {}", e))?;
+    import_bundle_for_paths(&paths, Path::new(&bundle_path), policy, true)
+}
+
+/// Import the filament presets of a bundle into the user filament directory.
+///
+/// While Bambu Studio is running the import is queued until it exits (see
+/// `pending_writes`) and the returned plan has `queued` set; otherwise the
+/// library is snapshotted first.
+#[tauri::command]
+pub async fn import_bundle(
+    app: tauri::AppHandle,
+    bundle_path: String,
+    policy: ConflictPolicy,
+) -> Result<BundleImportResult, String> {
+    let paths = BambuPaths::detect().map_err(|e| format!("Bambu Studio not found: {}", e))?;
+    if generator::is_bambu_studio_running() {
+        // Refuse now rather than queue a bundle that can't be read
+        let mut plan = import_bundle_for_paths(&paths, Path::new(&bundle_path), policy, true)?;
+        pending_writes::enqueue(
+            &app,
+            PendingOperation::ImportBundle {
+                bundle_path,
+                policy,
+            },
+        )?;
+        plan.queued = true;
+        return Ok(plan);
+    }
+    let name = Path::new(&bundle_path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| bundle_path.clone());
+    snapshot_before(&app, &paths, &format!("import of {}", name)).await?;
+    tokio::task::spawn_blocking(move || {
+        import_bundle_for_paths(&paths, Path::new(&bundle_path), policy, false)
+    })
+    .await
+    .map_err(|e| format!("bundle import join error: {}", e))?
+}
+
+/// Export user filament profiles into a `.bbsflmt` bundle.
+///
+/// Returns the path of the written bundle.
+#[tauri::command]
+pub fn export_profiles_bundle(paths: Vec<String>, output_path: String) -> Result<String, String> {
+    let bambu_paths = BambuPaths::detect().map_err(|e| format!("Bambu Studio not found: {}", e))?;
+    export_bundle_for_paths(&bambu_paths, &paths, Path::new(&output_path))
+}
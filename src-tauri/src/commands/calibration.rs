@@ -0,0 +1,323 @@
+//! Tauri commands for calibration kits.
+//!
+//! A kit's STLs go into the STL watch directory, where they show up like
+//! any other received model. Each sample is then opened with
+//! `launch_bambu_studio` together with its profile variant, and the printed
+//! samples are photographed back into `analyze_print` with a
+//! `calibration` context.
+
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use tauri::Manager;
+use tracing::{info, warn};
+
+use crate::calibration::{build_kit, CalibrationRange, CalibrationTest};
+use crate::commands::profile::{
+    install_profiles_for_paths, load_filament_registry, load_machine_registry, ProfileInstall,
+};
+use crate::commands::snapshot::snapshot_before;
+use crate::pending_writes::{self, PendingOperation};
+use crate::profile::generator;
+use crate::profile::paths::BambuPaths;
+use crate::scraper::types::FilamentSpecs;
+use crate::stl_watcher::StlWatcherState;
+
+/// One sample of a generated kit.
+#[derive(Debug, Serialize)]
+pub struct CalibrationSampleInfo {
+    pub value: f32,
+    pub label: String,
+    pub stl_path: String,
+    pub profile_name: String,
+    pub filename: String,
+    pub profile_json: String,
+    pub metadata_info: String,
+    /// Where the variant was installed, when `install` was requested and
+    /// Bambu Studio wasn't running.
+    pub installed_path: Option<String>,
+}
+
+/// A generated calibration kit.
+#[derive(Debug, Serialize)]
+pub struct CalibrationKit {
+    pub test: CalibrationTest,
+    pub test_name: String,
+    /// Profile key the samples vary.
+    pub parameter: String,
+    pub samples: Vec<CalibrationSampleInfo>,
+    /// Set when the variants were queued until Bambu Studio exits instead
+    /// of installed.
+    pub queued: bool,
+}
+
+/// Generate a calibration kit for `specs` and write its STLs to the STL
+/// watch directory.
+///
+/// `range` defaults to the test's usual sweep. With `install`, the profile
+/// variants are also installed to the user filament directory, all in one
+/// transaction after a library snapshot, or queued together until Bambu
+/// Studio exits if it's running. Without it, install them later with
+/// `install_generated_profile`.
+#[tauri::command]
+pub async fn generate_calibration_kit(
+    app: tauri::AppHandle,
+    test: CalibrationTest,
+    specs: FilamentSpecs,
+    target_printer: Option<String>,
+    range: Option<CalibrationRange>,
+    install: bool,
+) -> Result<CalibrationKit, String> {
+    let watch_dir = app
+        .state::<StlWatcherState>()
+        .watch_dir
+        .lock()
+        .unwrap_or_else(|p| p.into_inner())
+        .clone()
+        .ok_or_else(|| {
+            "Set an STL watch folder first; calibration models are saved there".to_string()
+        })?;
+    let paths = BambuPaths::detect().map_err(|e| {
+        format!(
+            "Bambu Studio not found: {}. Please install Bambu Studio first.",
+            e
+        )
+    })?;
+
+    let queue = install && generator::is_bambu_studio_running();
+    if install && !queue {
+        let reason = format!(
+            "{} for {} {} {}",
+            test.display_name(),
+            specs.brand,
+            specs.material,
+            specs.serial
+        );
+        snapshot_before(&app, &paths, &reason).await?;
+    }
+    let mut kit = tokio::task::spawn_blocking(move || {
+        generate_kit_for_paths(
+            &paths,
+            Path::new(&watch_dir),
+            test,
+            &specs,
+            target_printer.as_deref(),
+            range,
+            install && !queue,
+        )
+    })
+    .await
+    .map_err(|e| format!("Task panicked: {}", e))??;
+
+    if queue {
+        let installs = kit
+            .samples
+            .iter()
+            .map(|sample| ProfileInstall {
+                profile_json: sample.profile_json.clone(),
+                metadata_info: sample.metadata_info.clone(),
+                filename: sample.filename.clone(),
+            })
+            .collect();
+        if let Err(e) = pending_writes::enqueue(&app, PendingOperation::InstallAll { installs }) {
+            remove_stls(kit.samples.iter().map(|s| Path::new(&s.stl_path)));
+            return Err(e);
+        }
+        kit.queued = true;
+    }
+    Ok(kit)
+}
+
+/// Build a kit, write its STLs to `stl_dir` and, with `install`, install
+/// its variants (regardless of Bambu Studio running; the command queues
+/// instead). Nothing is left behind on failure: STLs already written are
+/// removed again and the variants are installed all or none.
+fn generate_kit_for_paths(
+    paths: &BambuPaths,
+    stl_dir: &Path,
+    test: CalibrationTest,
+    specs: &FilamentSpecs,
+    target_printer: Option<&str>,
+    range: Option<CalibrationRange>,
+    install: bool,
+) -> Result<CalibrationKit, String> {
+    let registry = load_filament_registry(paths)?;
+    let machines = load_machine_registry(paths);
+    let samples = build_kit(
+        test,
+        specs,
+        range,
+        &registry,
+        &machines,
+        target_printer,
+        None,
+    )
+    .map_err(|e| format!("Failed to build calibration kit: {}", e))?;
+
+    let mut infos = Vec::with_capacity(samples.len());
+    for sample in &samples {
+        let profile_json = sample
+            .profile
+            .to_json_4space()
+            .map_err(|e| format!("Failed to serialize profile: {}", e))?;
+        infos.push(CalibrationSampleInfo {
+            value: sample.value,
+            label: sample.label.clone(),
+            stl_path: stl_dir
+                .join(&sample.stl_filename)
+                .to_string_lossy()
+                .to_string(),
+            profile_name: sample.profile.name().unwrap_or("<unnamed>").to_string(),
+            filename: sample.profile_filename.clone(),
+            profile_json,
+            metadata_info: sample.metadata.to_info_string(),
+            installed_path: None,
+        });
+    }
+
+    let mut written: Vec<PathBuf> = Vec::with_capacity(samples.len());
+    for (sample, info) in samples.iter().zip(&infos) {
+        let stl_path = PathBuf::from(&info.stl_path);
+        if let Err(e) = std::fs::write(&stl_path, &sample.stl) {
+            remove_stls(written.iter().map(PathBuf::as_path));
+            return Err(format!("Failed to write {:?}: {}", stl_path, e));
+        }
+        written.push(stl_path);
+    }
+
+    if install {
+        let installs: Vec<ProfileInstall> = infos
+            .iter()
+            .map(|info| ProfileInstall {
+                profile_json: info.profile_json.clone(),
+                metadata_info: info.metadata_info.clone(),
+                filename: info.filename.clone(),
+            })
+            .collect();
+        match install_profiles_for_paths(paths, &installs, true) {
+            Ok(results) => {
+                for (info, result) in infos.iter_mut().zip(results) {
+                    info.installed_path = Some(result.installed_path);
+                }
+            }
+            Err(e) => {
+                remove_stls(written.iter().map(PathBuf::as_path));
+                return Err(e);
+            }
+        }
+    }
+
+    info!(
+        "Generated {} with {} samples in {:?}",
+        test.display_name(),
+        infos.len(),
+        stl_dir
+    );
+
+    Ok(CalibrationKit {
+        test,
+        test_name: test.display_name().to_string(),
+        parameter: test.parameter().to_string(),
+        samples: infos,
+        queued: false,
+    })
+}
+
+/// Remove the STLs of a kit that couldn't be completed.
+fn remove_stls<'a>(paths: impl IntoIterator<Item = &'a Path>) {
+    for path in paths {
+        if let Err(e) = std::fs::remove_file(path) {
+            warn!("Failed to remove {:?}: {}", path, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pla_specs() -> FilamentSpecs {
+        FilamentSpecs {
+            serial: "Matte".to_string(),
+            brand: "Polymaker".to_string(),
+            material: "PLA".to_string(),
+            nozzle_temp_min: Some(200),
+            nozzle_temp_max: Some(220),
+            nozzle_temperature: Some(210),
+            ..FilamentSpecs::default()
+        }
+    }
+
+    /// A config tree using the include fixtures as system profiles, with
+    /// the user filament directory only when `with_user_dir`.
+    fn fixture_paths(root: &Path, with_user_dir: bool) -> BambuPaths {
+        if with_user_dir {
+            std::fs::create_dir_all(root.join("user/12345/filament/base")).unwrap();
+        }
+        std::fs::write(
+            root.join("BambuStudio.conf"),
+            r#"{"app": {"preset_folder": "12345"}}"#,
+        )
+        .unwrap();
+        BambuPaths {
+            config_root: root.to_path_buf(),
+            system_filaments: PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                .join("tests/fixtures/include_system/BBL/filament"),
+            user_root: root.join("user"),
+            preset_folder: Some("12345".to_string()),
+        }
+    }
+
+    fn stl_count(dir: &Path) -> usize {
+        std::fs::read_dir(dir).unwrap().count()
+    }
+
+    #[test]
+    fn test_kit_installs_every_variant() {
+        let root = tempfile::tempdir().unwrap();
+        let stl_dir = tempfile::tempdir().unwrap();
+        let paths = fixture_paths(root.path(), true);
+
+        let kit = generate_kit_for_paths(
+            &paths,
+            stl_dir.path(),
+            CalibrationTest::TemperatureTower,
+            &pla_specs(),
+            Some("Bambu Lab X1 Carbon 0.4 nozzle"),
+            None,
+            true,
+        )
+        .unwrap();
+
+        assert_eq!(kit.samples.len(), 5);
+        assert_eq!(stl_count(stl_dir.path()), 5);
+        let conf = std::fs::read_to_string(root.path().join("BambuStudio.conf")).unwrap();
+        for sample in &kit.samples {
+            let installed = PathBuf::from(sample.installed_path.as_ref().unwrap());
+            assert!(installed.exists());
+            assert!(installed.with_extension("info").exists());
+            assert!(conf.contains(installed.file_stem().unwrap().to_str().unwrap()));
+        }
+    }
+
+    #[test]
+    fn test_failed_install_leaves_no_stls() {
+        let root = tempfile::tempdir().unwrap();
+        let stl_dir = tempfile::tempdir().unwrap();
+        let paths = fixture_paths(root.path(), false);
+
+        let err = generate_kit_for_paths(
+            &paths,
+            stl_dir.path(),
+            CalibrationTest::TemperatureTower,
+            &pla_specs(),
+            Some("Bambu Lab X1 Carbon 0.4 nozzle"),
+            None,
+            true,
+        )
+        .unwrap_err();
+
+        assert!(err.contains("User filament directory not found"), "{}", err);
+        assert_eq!(stl_count(stl_dir.path()), 0);
+    }
+}
@@ -0,0 +1,643 @@
+//! Tauri commands for filament families (see `profile::family`).
+//!
+//! A family is every user profile sharing one `filament_id`: the same
+//! filament tuned for different printers and nozzles. These commands list
+//! families with the settings that differ between variants, apply one edit
+//! to every variant, and generate the variants a family is missing.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use serde_json::Value;
+use tracing::{info, warn};
+use walkdir::WalkDir;
+
+use crate::commands::profile::{
+    check_installable, ensure_profile_unchanged, generate_profile_for_paths,
+    install_profiles_for_paths, load_filament_registry, load_machine_registry,
+    profile_extruder_count, InstallResult, ProfileInstall,
+};
+use crate::commands::snapshot::snapshot_before;
+use crate::pending_writes::{self, PendingOperation};
+use crate::profile::conflict::ProfileVersion;
+use crate::profile::family::{group_families, missing_printers, FilamentFamily};
+use crate::profile::fields::dictionary;
+use crate::profile::generator;
+use crate::profile::inheritance::resolve_inheritance;
+use crate::profile::lint::{lint_profile, LintOptions};
+use crate::profile::nozzle::{self, Nozzle};
+use crate::profile::paths::BambuPaths;
+use crate::profile::reader::{read_profile, read_profile_metadata};
+use crate::profile::rebase::{GenerationRecord, GenerationStore};
+use crate::profile::registry::{MachineRegistry, ProfileRegistry};
+use crate::profile::types::{FilamentProfile, ProfileMetadata};
+use crate::profile::writer::{InstallTransaction, INSTALL_JOURNAL_DIR};
+
+fn detect_paths() -> Result<BambuPaths, String> {
+    BambuPaths::detect().map_err(|e| format!("Bambu Studio not found: {}", e))
+}
+
+/// User profiles as `(path, profile)`, unresolved.
+fn read_user_profiles(paths: &BambuPaths) -> Result<Vec<(PathBuf, FilamentProfile)>, String> {
+    let user_dir = paths
+        .user_filament_dir()
+        .ok_or_else(|| "User filament directory not found".to_string())?;
+    Ok(WalkDir::new(&user_dir)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| {
+            e.path().is_file() && e.path().extension().and_then(|x| x.to_str()) == Some("json")
+        })
+        .filter_map(|e| {
+            let profile = read_profile(e.path()).ok()?;
+            Some((e.path().to_path_buf(), profile))
+        })
+        .collect())
+}
+
+fn families_for_paths(paths: &BambuPaths) -> Result<Vec<FilamentFamily>, String> {
+    let registry = load_filament_registry(paths)?;
+    let profiles = read_user_profiles(paths)?
+        .into_iter()
+        .map(|(path, profile)| {
+            // Compare effective values, so delta variants line up with
+            // flattened ones
+            let resolved = resolve_inheritance(&profile, &registry).unwrap_or_else(|e| {
+                warn!("Comparing {:?} unresolved: {}", path, e);
+                FilamentProfile::from_map(profile.raw().clone())
+            });
+            (path.to_string_lossy().to_string(), resolved)
+        })
+        .collect();
+    let mut families = group_families(profiles);
+    for variant in families.iter_mut().flat_map(|f| &mut f.variants) {
+        variant.version = ProfileVersion::of_file(Path::new(&variant.path))
+            .map(|v| v.token())
+            .unwrap_or_default();
+    }
+    Ok(families)
+}
+
+fn find_family(paths: &BambuPaths, filament_id: &str) -> Result<FilamentFamily, String> {
+    families_for_paths(paths)?
+        .into_iter()
+        .find(|f| f.filament_id == filament_id)
+        .ok_or_else(|| format!("No user profiles with filament_id '{}'", filament_id))
+}
+
+/// List user profiles grouped by `filament_id`, with the printer each
+/// variant is for and the settings that differ between variants.
+#[tauri::command]
+pub fn list_filament_families() -> Result<Vec<FilamentFamily>, String> {
+    let paths = detect_paths()?;
+    families_for_paths(&paths)
+}
+
+/// Result of a family edit.
+#[derive(Debug, Clone, Serialize)]
+pub struct FamilyEdit {
+    pub family: FilamentFamily,
+    /// Set when the edit was queued until Bambu Studio exits.
+    pub queued: bool,
+}
+
+/// Set `key` to `value` (a JSON string, as for `update_profile_field`) in
+/// every variant of a family.
+///
+/// Per-extruder values are sized for each variant's printer. The edited
+/// field of every variant is linted first and the variants are written in
+/// one transaction, so an edit that's invalid for one printer, or fails
+/// partway, changes none of them. `expected_versions` maps variant paths
+/// to the `FamilyVariant::version` they were loaded at; a variant that
+/// changed since refuses the edit with a `ProfileConflict`.
+///
+/// While Bambu Studio is running the edit is queued until it exits (see
+/// `pending_writes`); otherwise the library is snapshotted first.
+#[tauri::command]
+pub async fn update_family_field(
+    app: tauri::AppHandle,
+    filament_id: String,
+    key: String,
+    value: String,
+    expected_versions: Option<HashMap<String, String>>,
+) -> Result<FamilyEdit, String> {
+    let json_value: Value =
+        serde_json::from_str(&value).map_err(|e| format!("Invalid JSON value: {}", e))?;
+    let expected_versions = expected_versions.unwrap_or_default();
+    let paths = detect_paths()?;
+
+    if generator::is_bambu_studio_running() {
+        // Refuse now rather than queue an edit that can't be written
+        prepare_family_edit(&paths, &filament_id, &key, &json_value, &expected_versions)?;
+        let family = find_family(&paths, &filament_id)?;
+        pending_writes::enqueue(
+            &app,
+            PendingOperation::UpdateFamilyField {
+                filament_id,
+                key,
+                value: json_value,
+                expected_versions: family
+                    .variants
+                    .iter()
+                    .map(|v| (v.path.clone(), v.version.clone()))
+                    .collect(),
+            },
+        )?;
+        return Ok(FamilyEdit {
+            family,
+            queued: true,
+        });
+    }
+
+    snapshot_before(
+        &app,
+        &paths,
+        &format!("bulk edit of {} in family {}", key, filament_id),
+    )
+    .await?;
+    let family = tokio::task::spawn_blocking(move || {
+        update_family_field_for_paths(&paths, &filament_id, &key, json_value, &expected_versions)
+    })
+    .await
+    .map_err(|e| format!("family edit join error: {}", e))??;
+    Ok(FamilyEdit {
+        family,
+        queued: false,
+    })
+}
+
+/// Write `key` = `json_value` to every variant of the family in one
+/// transaction, as `update_family_field` does (and a queued one once Bambu
+/// Studio has exited, checked against the versions it was queued at).
+pub(crate) fn update_family_field_for_paths(
+    paths: &BambuPaths,
+    filament_id: &str,
+    key: &str,
+    json_value: Value,
+    expected_versions: &HashMap<String, String>,
+) -> Result<FilamentFamily, String> {
+    let updated = prepare_family_edit(paths, filament_id, key, &json_value, expected_versions)?;
+    let user_dir = paths
+        .user_filament_dir()
+        .ok_or_else(|| "User filament directory not found".to_string())?;
+    let write = || -> anyhow::Result<()> {
+        let mut transaction = InstallTransaction::begin(&user_dir.join(INSTALL_JOURNAL_DIR))?;
+        for (path, profile) in &updated {
+            transaction.stage_edited_profile(profile, path)?;
+        }
+        transaction.commit()
+    };
+    write().map_err(|e| format!("Failed to write family {}: {}", filament_id, e))?;
+    info!(
+        "Set '{}' across {} variants of family {}",
+        key,
+        updated.len(),
+        filament_id
+    );
+
+    find_family(paths, filament_id)
+}
+
+/// Every variant of the family with the edit applied, checked against
+/// `expected_versions` and linted, but not written.
+fn prepare_family_edit(
+    paths: &BambuPaths,
+    filament_id: &str,
+    key: &str,
+    json_value: &Value,
+    expected_versions: &HashMap<String, String>,
+) -> Result<Vec<(PathBuf, FilamentProfile)>, String> {
+    let family = find_family(paths, filament_id)?;
+    let machines = load_machine_registry(paths);
+    let per_extruder = dictionary()
+        .get(key)
+        .is_some_and(|spec| spec.is_per_extruder());
+
+    let mut updated = Vec::new();
+    for variant in &family.variants {
+        let path = PathBuf::from(&variant.path);
+        let mut profile = read_profile(&path).map_err(|e| e.to_string())?;
+        let extruder_count = profile_extruder_count(&machines, &profile);
+        let mut variant_value = json_value.clone();
+        if let (true, Some(count), Value::Array(items)) =
+            (per_extruder, extruder_count, &mut variant_value)
+        {
+            if let Some(last) = items.last().cloned() {
+                items.resize(count.max(1), last);
+            }
+        }
+        let edit = |p: &mut FilamentProfile| {
+            p.raw_mut().insert(key.to_string(), variant_value.clone());
+        };
+        let expected = expected_versions.get(&variant.path).map(String::as_str);
+        ensure_profile_unchanged(&path, expected, &profile, edit)?;
+        edit(&mut profile);
+
+        let lint = lint_profile(&profile, &LintOptions { extruder_count }).for_keys(&[key]);
+        if lint.has_errors() {
+            return Err(format!(
+                "Refusing to write an invalid profile '{}': {}",
+                variant.name,
+                lint.error_summary()
+            ));
+        }
+        updated.push((path, profile));
+    }
+    Ok(updated)
+}
+
+/// Generate and install a variant of a family for each of `printers` that
+/// it doesn't have yet, all sharing its `filament_id`.
+///
+/// The specs come from the generation record of any variant BambuMate
+/// generated, else from an existing variant's settings. Every variant is
+/// generated before any is installed, all in one transaction, and the
+/// library is snapshotted first. While Bambu Studio is running the installs
+/// are queued together until it exits (see `pending_writes`).
+#[tauri::command]
+pub async fn generate_family_variants(
+    app: tauri::AppHandle,
+    filament_id: String,
+    printers: Vec<String>,
+) -> Result<Vec<InstallResult>, String> {
+    let db_path = super::rebase::generation_db_path(&app)?;
+    let paths = detect_paths()?;
+    let queue = generator::is_bambu_studio_running();
+    if !queue {
+        snapshot_before(
+            &app,
+            &paths,
+            &format!("variant generation for family {}", filament_id),
+        )
+        .await?;
+    }
+    let app_handle = app.clone();
+    let (installed, records) = tokio::task::spawn_blocking(move || {
+        let store = GenerationStore::new(&db_path)?;
+        let (installs, records) =
+            generate_variants_for_paths(&paths, &store, &filament_id, &printers)?;
+        if installs.is_empty() {
+            return Ok::<_, String>((Vec::new(), records));
+        }
+        if !queue {
+            return Ok((
+                install_profiles_for_paths(&paths, &installs, false)?,
+                records,
+            ));
+        }
+        let user_dir = paths
+            .user_filament_dir()
+            .ok_or_else(|| "User filament directory not found".to_string())?;
+        let queued = installs
+            .iter()
+            .map(|install| {
+                let (profile, _) =
+                    check_installable(&paths, &install.profile_json, &install.metadata_info)?;
+                Ok(InstallResult {
+                    installed_path: user_dir
+                        .join(&install.filename)
+                        .to_string_lossy()
+                        .to_string(),
+                    profile_name: profile.name().unwrap_or("<unnamed>").to_string(),
+                    bambu_studio_was_running: true,
+                    queued: true,
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        pending_writes::enqueue(&app_handle, PendingOperation::InstallAll { installs })?;
+        Ok((queued, records))
+    })
+    .await
+    .map_err(|e| format!("family generation join error: {}", e))??;
+
+    for record in records {
+        super::rebase::record_generation(&app, record).await;
+    }
+    Ok(installed)
+}
+
+/// Generate a variant of the family for each of `printers` it doesn't have
+/// yet, ready to install, with their generation records.
+fn generate_variants_for_paths(
+    paths: &BambuPaths,
+    store: &GenerationStore,
+    filament_id: &str,
+    printers: &[String],
+) -> Result<(Vec<ProfileInstall>, Vec<GenerationRecord>), String> {
+    let family = find_family(paths, filament_id)?;
+    let missing = missing_printers(&family, printers);
+    if missing.is_empty() {
+        return Ok((Vec::new(), Vec::new()));
+    }
+
+    let registry = load_filament_registry(paths)?;
+    let specs = family_specs(store, &registry, &load_machine_registry(paths), &family)?;
+    let mut installs = Vec::new();
+    let mut records = Vec::new();
+    for printer in &missing {
+        let result = generate_profile_for_paths(
+            paths,
+            &specs,
+            Some(printer),
+            None,
+            Some(filament_id.to_string()),
+            false,
+        )
+        .map_err(|e| format!("{}: {}", printer, e))?;
+        if let Ok(metadata) = ProfileMetadata::from_info_string(&result.metadata_info) {
+            records.push(GenerationRecord::new(
+                metadata.setting_id,
+                result.profile_name,
+                specs.clone(),
+                Some(printer.to_string()),
+                result.base_profile_used,
+                result.base_fingerprint,
+            ));
+        }
+        installs.push(ProfileInstall {
+            profile_json: result.profile_json,
+            metadata_info: result.metadata_info,
+            filename: result.filename,
+        });
+    }
+    info!(
+        "Generated {} variants for family {}",
+        installs.len(),
+        filament_id
+    );
+    Ok((installs, records))
+}
+
+/// Specs to generate new variants of `family` from: a recorded generation
+/// if there is one, else read back from the first variant (resolved, so
+/// delta variants have specs too) and scaled back to the reference nozzle.
+fn family_specs(
+    store: &GenerationStore,
+    registry: &ProfileRegistry,
+    machines: &MachineRegistry,
+    family: &FilamentFamily,
+) -> Result<crate::scraper::types::FilamentSpecs, String> {
+    for variant in &family.variants {
+        let Ok(Some(metadata)) = read_profile_metadata(Path::new(&variant.path)) else {
+            continue;
+        };
+        if let Some(record) = store.get(&metadata.setting_id)? {
+            return Ok(record.specs);
+        }
+    }
+    let first = family
+        .variants
+        .first()
+        .ok_or_else(|| "Family has no variants".to_string())?;
+    // Delta variants carry only what differs from their parent
+    let profile = read_profile(Path::new(&first.path)).map_err(|e| e.to_string())?;
+    let resolved = resolve_inheritance(&profile, registry)
+        .map_err(|e| format!("Failed to resolve '{}': {}", first.name, e))?;
+    let specs = generator::extract_specs_from_profile(&resolved);
+    let nozzle = first
+        .printer
+        .as_deref()
+        .and_then(|printer| nozzle::printer_nozzle(machines, printer));
+    Ok(match nozzle {
+        Some(from) => nozzle::default_table().scale_specs(&specs, from, Nozzle::REFERENCE),
+        None => specs,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Machine presets from the `preset_config` fixture (an X1C with one
+    /// extruder), user profiles under `root`.
+    fn fixture_paths(root: &Path) -> BambuPaths {
+        let fixtures = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+        std::fs::create_dir_all(root.join("user/12345/filament/base")).unwrap();
+        BambuPaths {
+            config_root: fixtures.join("preset_config"),
+            system_filaments: fixtures.join("include_system/BBL/filament"),
+            user_root: root.join("user"),
+            preset_folder: Some("12345".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_edit_legacy_dual_value_profile_on_single_extruder_printer() {
+        let root = tempfile::tempdir().unwrap();
+        let paths = fixture_paths(root.path());
+        // Generated before per-extruder arrays followed the printer
+        let path = root.path().join("user/12345/filament/base/Legacy PLA.json");
+        std::fs::write(
+            &path,
+            serde_json::json!({
+                "name": "Legacy PLA",
+                "filament_id": "PLEGACY",
+                "filament_type": ["PLA"],
+                "compatible_printers": ["Bambu Lab X1 Carbon 0.4 nozzle"],
+                "nozzle_temperature": ["215", "215"],
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        update_family_field_for_paths(
+            &paths,
+            "PLEGACY",
+            "filament_vendor",
+            serde_json::json!(["Acme"]),
+            &HashMap::new(),
+        )
+        .unwrap();
+
+        let profile = read_profile(&path).unwrap();
+        assert_eq!(
+            profile.raw()["filament_vendor"],
+            serde_json::json!(["Acme"])
+        );
+        assert_eq!(
+            profile.raw()["nozzle_temperature"],
+            serde_json::json!(["215", "215"])
+        );
+    }
+
+    #[test]
+    fn test_invalid_edit_is_still_refused() {
+        let root = tempfile::tempdir().unwrap();
+        let paths = fixture_paths(root.path());
+        let path = root.path().join("user/12345/filament/base/Legacy PLA.json");
+        std::fs::write(
+            &path,
+            serde_json::json!({
+                "name": "Legacy PLA",
+                "filament_id": "PLEGACY",
+                "compatible_printers": ["Bambu Lab X1 Carbon 0.4 nozzle"],
+                "nozzle_temperature": ["215", "215"],
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        let err = update_family_field_for_paths(
+            &paths,
+            "PLEGACY",
+            "nozzle_temperature",
+            serde_json::json!(["900"]),
+            &HashMap::new(),
+        )
+        .unwrap_err();
+        assert!(err.contains("Refusing"), "{}", err);
+        assert_eq!(
+            read_profile(&path).unwrap().raw()["nozzle_temperature"],
+            serde_json::json!(["215", "215"])
+        );
+    }
+
+    /// A two-variant family (H2D, then X1C) with `filament_vendor` set.
+    fn write_family(root: &Path) -> (PathBuf, PathBuf) {
+        let dir = root.join("user/12345/filament/base");
+        let mut written = Vec::new();
+        for (suffix, printer) in [
+            ("H2D", "Bambu Lab H2D 0.4 nozzle"),
+            ("X1C", "Bambu Lab X1 Carbon 0.4 nozzle"),
+        ] {
+            let path = dir.join(format!("Acme PLA @{}.json", suffix));
+            std::fs::write(
+                &path,
+                serde_json::json!({
+                    "name": format!("Acme PLA @{}", suffix),
+                    "filament_id": "PACME",
+                    "compatible_printers": [printer],
+                    "filament_vendor": ["Acme"],
+                })
+                .to_string(),
+            )
+            .unwrap();
+            written.push(path);
+        }
+        (written.remove(0), written.remove(0))
+    }
+
+    fn vendor(path: &Path) -> Value {
+        read_profile(path).unwrap().raw()["filament_vendor"].clone()
+    }
+
+    #[test]
+    fn test_family_edit_writes_every_variant() {
+        let root = tempfile::tempdir().unwrap();
+        let paths = fixture_paths(root.path());
+        let (h2d, x1c) = write_family(root.path());
+
+        let family = update_family_field_for_paths(
+            &paths,
+            "PACME",
+            "filament_vendor",
+            serde_json::json!(["Acme Labs"]),
+            &HashMap::new(),
+        )
+        .unwrap();
+
+        assert_eq!(vendor(&h2d), serde_json::json!(["Acme Labs"]));
+        assert_eq!(vendor(&x1c), serde_json::json!(["Acme Labs"]));
+        assert!(family
+            .divergences
+            .iter()
+            .all(|d| d.key != "filament_vendor"));
+    }
+
+    #[test]
+    fn test_failed_family_edit_writes_no_variant() {
+        let root = tempfile::tempdir().unwrap();
+        let paths = fixture_paths(root.path());
+        let (h2d, x1c) = write_family(root.path());
+        // The second variant's .info can't be read, so staging it fails
+        // after the first variant was staged
+        std::fs::create_dir(x1c.with_extension("info")).unwrap();
+
+        update_family_field_for_paths(
+            &paths,
+            "PACME",
+            "filament_vendor",
+            serde_json::json!(["Acme Labs"]),
+            &HashMap::new(),
+        )
+        .unwrap_err();
+
+        assert_eq!(vendor(&h2d), serde_json::json!(["Acme"]));
+        assert_eq!(vendor(&x1c), serde_json::json!(["Acme"]));
+    }
+
+    #[test]
+    fn test_family_edit_refused_when_a_variant_changed() {
+        let root = tempfile::tempdir().unwrap();
+        let paths = fixture_paths(root.path());
+        let (h2d, x1c) = write_family(root.path());
+        let family = find_family(&paths, "PACME").unwrap();
+        let versions: HashMap<String, String> = family
+            .variants
+            .iter()
+            .map(|v| (v.path.clone(), v.version.clone()))
+            .collect();
+        assert!(versions.values().all(|v| !v.is_empty()));
+
+        // Changed in Bambu Studio after the family was listed
+        let mut changed = read_profile(&x1c).unwrap();
+        changed
+            .raw_mut()
+            .insert("filament_cost".to_string(), serde_json::json!(["30"]));
+        std::fs::write(&x1c, serde_json::to_string(changed.raw()).unwrap()).unwrap();
+        // Within the same millisecond the file would pass for unchanged
+        std::fs::File::options()
+            .write(true)
+            .open(&x1c)
+            .unwrap()
+            .set_modified(std::time::SystemTime::now() + std::time::Duration::from_secs(1))
+            .unwrap();
+
+        let err = update_family_field_for_paths(
+            &paths,
+            "PACME",
+            "filament_vendor",
+            serde_json::json!(["Acme Labs"]),
+            &versions,
+        )
+        .unwrap_err();
+
+        assert!(err.contains("changed on disk"), "{}", err);
+        assert_eq!(vendor(&h2d), serde_json::json!(["Acme"]));
+        assert_eq!(vendor(&x1c), serde_json::json!(["Acme"]));
+    }
+
+    #[test]
+    fn test_specs_of_a_delta_variant_come_from_its_parent() {
+        let root = tempfile::tempdir().unwrap();
+        let paths = fixture_paths(root.path());
+        let path = root
+            .path()
+            .join("user/12345/filament/base/Bambu PLA Basic @BBL X1C - Copy.json");
+        std::fs::write(
+            &path,
+            serde_json::json!({
+                "name": "Bambu PLA Basic @BBL X1C - Copy",
+                "inherits": "Bambu PLA Basic @BBL X1C",
+                "filament_id": "PDELTA",
+                "filament_flow_ratio": ["0.95"],
+            })
+            .to_string(),
+        )
+        .unwrap();
+        let store = GenerationStore::new(&root.path().join("generated.db")).unwrap();
+        let family = find_family(&paths, "PDELTA").unwrap();
+
+        let specs = family_specs(
+            &store,
+            &load_filament_registry(&paths).unwrap(),
+            &load_machine_registry(&paths),
+            &family,
+        )
+        .unwrap();
+
+        assert_eq!(specs.material, "PLA");
+        assert!(specs.nozzle_temperature.is_some());
+    }
+}
@@ -0,0 +1,249 @@
+//! Tauri commands exposing the field dictionary (see `profile::fields`) and
+//! the raw field editor (see `profile::field_edit`).
+
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+use tracing::{info, warn};
+
+use super::profile::{
+    assert_in_user_filament_dir, ensure_profile_unchanged, ensure_unchanged_since_queued,
+    load_filament_registry, load_machine_registry, profile_extruder_count, read_profile_command,
+    remember_loaded_profile, ProfileDetail,
+};
+use crate::pending_writes::{self, PendingOperation};
+use crate::profile::conflict::ProfileVersion;
+use crate::profile::field_edit::{apply_edits, check_edits, is_protected, FieldEdit};
+use crate::profile::fields::{dictionary, FieldInfo};
+use crate::profile::generator;
+use crate::profile::inheritance::inherited_values;
+use crate::profile::lint::{Diagnostic, Severity};
+use crate::profile::paths::BambuPaths;
+use crate::profile::reader::read_profile;
+use crate::profile::types::FilamentProfile;
+use crate::profile::writer::{backup_profile, write_edited_profile};
+
+/// Every field in the dictionary, in dictionary order, so the frontend can
+/// label fields and build editors for them.
+#[tauri::command]
+pub fn get_field_dictionary() -> Vec<FieldInfo> {
+    dictionary().fields().to_vec()
+}
+
+/// One field of a profile as the raw field editor shows it.
+#[derive(Debug, Clone, Serialize)]
+pub struct RawField {
+    pub key: String,
+    pub label: String,
+    pub category: String,
+    /// The profile's own value, or `None` if it inherits the field.
+    pub value: Option<Value>,
+    /// The value its parent chain gives it, or `None` if nothing does.
+    pub inherited: Option<Value>,
+}
+
+/// Every field a user profile sets or inherits.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfileFields {
+    pub path: String,
+    pub name: Option<String>,
+    pub inherits: Option<String>,
+    /// Extruder count of the printer the profile is for, if known, for
+    /// sizing per-extruder arrays.
+    pub extruder_count: Option<usize>,
+    /// Version token to pass back to `update_profile_fields`.
+    pub version: String,
+    /// Sorted by category, then label.
+    pub fields: Vec<RawField>,
+}
+
+/// Result of `update_profile_fields`.
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldEditResult {
+    pub detail: ProfileDetail,
+    /// Backup taken before writing; `None` when the edits were queued.
+    pub backup_path: Option<String>,
+}
+
+/// Extruder count for `profile`, from the installed machine presets.
+fn extruder_count(paths: &BambuPaths, profile: &FilamentProfile) -> Option<usize> {
+    profile_extruder_count(&load_machine_registry(paths), profile)
+}
+
+/// Read the user profile at `path` for the raw field editor: its own
+/// values alongside the values it would inherit. Fields with their own
+/// commands (`name`, `inherits`) are left out.
+#[tauri::command]
+pub fn get_profile_fields(path: String) -> Result<ProfileFields, String> {
+    let file_path = Path::new(&path);
+    assert_in_user_filament_dir(file_path, true)?;
+    let paths = BambuPaths::detect().map_err(|e| format!("Bambu Studio not found: {}", e))?;
+
+    let version = ProfileVersion::of_file(file_path)
+        .map_err(|e| e.to_string())?
+        .token();
+    let profile = read_profile(file_path).map_err(|e| e.to_string())?;
+    remember_loaded_profile(file_path, &version, &profile);
+
+    // A missing parent shouldn't stop the profile's own fields being edited
+    let inherited = if profile.inherits().is_some_and(|p| !p.is_empty())
+        || profile.raw().contains_key("include")
+    {
+        load_filament_registry(&paths)
+            .and_then(|registry| inherited_values(&profile, &registry).map_err(|e| e.to_string()))
+            .unwrap_or_else(|e| {
+                warn!(
+                    "Could not resolve inherited values for {:?}: {}",
+                    file_path, e
+                );
+                Map::new()
+            })
+    } else {
+        Map::new()
+    };
+
+    let dict = dictionary();
+    let mut keys: Vec<&String> = profile.raw().keys().chain(inherited.keys()).collect();
+    keys.sort();
+    keys.dedup();
+    let mut fields: Vec<RawField> = keys
+        .into_iter()
+        .filter(|key| !is_protected(key))
+        .map(|key| RawField {
+            key: key.clone(),
+            label: dict.label(key),
+            category: dict.category(key).to_string(),
+            value: profile.raw().get(key).cloned(),
+            inherited: inherited.get(key).cloned(),
+        })
+        .collect();
+    fields.sort_by(|a, b| a.category.cmp(&b.category).then(a.label.cmp(&b.label)));
+
+    Ok(ProfileFields {
+        path,
+        name: profile.name().map(|s| s.to_string()),
+        inherits: profile.inherits().map(|s| s.to_string()),
+        extruder_count: extruder_count(&paths, &profile),
+        version,
+        fields,
+    })
+}
+
+/// Check `edits` against the user profile at `path` without writing
+/// anything. Returns the lint diagnostics for the edited fields.
+#[tauri::command]
+pub fn check_profile_field_edits(
+    path: String,
+    edits: Vec<FieldEdit>,
+) -> Result<Vec<Diagnostic>, String> {
+    let file_path = Path::new(&path);
+    assert_in_user_filament_dir(file_path, true)?;
+    let paths = BambuPaths::detect().map_err(|e| format!("Bambu Studio not found: {}", e))?;
+    let profile = read_profile(file_path).map_err(|e| e.to_string())?;
+    check_edits(&profile, &edits, extruder_count(&paths, &profile)).map_err(|e| e.to_string())
+}
+
+/// Apply a batch of field edits to the user profile at `path` in one
+/// write, after backing it up.
+///
+/// The edits are refused if any of them leaves an error in its field (see
+/// `check_profile_field_edits`). With `expected_version` (from
+/// `ProfileFields::version`), the write is refused with a `ProfileConflict`
+/// if the file changed since. While Bambu Studio is running the edits are
+/// queued until it exits (see `pending_writes`) and the returned detail has
+/// `queued` set.
+#[tauri::command]
+pub fn update_profile_fields(
+    app: tauri::AppHandle,
+    path: String,
+    edits: Vec<FieldEdit>,
+    expected_version: Option<String>,
+) -> Result<FieldEditResult, String> {
+    let file_path = Path::new(&path);
+    assert_in_user_filament_dir(file_path, true)?;
+    let paths = BambuPaths::detect().map_err(|e| format!("Bambu Studio not found: {}", e))?;
+    let profile = read_profile(file_path).map_err(|e| e.to_string())?;
+
+    ensure_profile_unchanged(file_path, expected_version.as_deref(), &profile, |p| {
+        apply_edits(p, &edits)
+    })?;
+    refuse_invalid_edits(&paths, &profile, &edits)?;
+
+    if generator::is_bambu_studio_running() {
+        pending_writes::enqueue(
+            &app,
+            PendingOperation::UpdateFields {
+                path: path.clone(),
+                edits,
+                expected_version: Some(
+                    ProfileVersion::of_file(file_path)
+                        .map_err(|e| e.to_string())?
+                        .token(),
+                ),
+            },
+        )?;
+        let mut detail = read_profile_command(path)?;
+        detail.queued = true;
+        return Ok(FieldEditResult {
+            detail,
+            backup_path: None,
+        });
+    }
+
+    let backup_path = write_profile_fields(file_path, &edits, None)?;
+    Ok(FieldEditResult {
+        detail: read_profile_command(path)?,
+        backup_path: Some(backup_path.to_string_lossy().to_string()),
+    })
+}
+
+/// Refuse `edits` if any of them leaves an error in its field.
+fn refuse_invalid_edits(
+    paths: &BambuPaths,
+    profile: &FilamentProfile,
+    edits: &[FieldEdit],
+) -> Result<(), String> {
+    let diagnostics =
+        check_edits(profile, edits, extruder_count(paths, profile)).map_err(|e| e.to_string())?;
+    let errors: Vec<String> = diagnostics
+        .iter()
+        .filter(|d| d.severity == Severity::Error)
+        .map(|d| d.to_string())
+        .collect();
+    if !errors.is_empty() {
+        return Err(format!(
+            "Refusing to write an invalid profile: {}",
+            errors.join("; ")
+        ));
+    }
+    Ok(())
+}
+
+/// Back up the user profile at `path`, apply `edits` and write it back in
+/// one go, as `update_profile_fields` does directly or once Bambu Studio
+/// has exited. The edits are checked again against the profile as it is
+/// now, and a queued batch is refused if the file is no longer at
+/// `expected_version`. Returns the backup path.
+pub(crate) fn write_profile_fields(
+    path: &Path,
+    edits: &[FieldEdit],
+    expected_version: Option<&str>,
+) -> Result<PathBuf, String> {
+    assert_in_user_filament_dir(path, true)?;
+    ensure_unchanged_since_queued(path, expected_version)?;
+    let paths = BambuPaths::detect().map_err(|e| format!("Bambu Studio not found: {}", e))?;
+    let mut profile = read_profile(path).map_err(|e| e.to_string())?;
+    refuse_invalid_edits(&paths, &profile, edits)?;
+    let backup_path =
+        backup_profile(path).map_err(|e| format!("Failed to back up profile: {}", e))?;
+    apply_edits(&mut profile, edits);
+    write_edited_profile(&profile, path).map_err(|e| format!("Failed to write profile: {}", e))?;
+    info!(
+        "Applied {} field edits to {:?} (backup at {:?})",
+        edits.len(),
+        path,
+        backup_path
+    );
+    Ok(backup_path)
+}
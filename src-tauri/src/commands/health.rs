@@ -215,13 +215,10 @@ fn search_config_fallback() -> Option<String> {
 
 #[cfg(target_os = "linux")]
 fn search_config_fallback() -> Option<String> {
-    if let Some(home) = dirs::home_dir() {
-        let bs_dir = home.join(".config/BambuStudio");
-        if bs_dir.exists() {
-            return Some(bs_dir.to_string_lossy().to_string());
-        }
-    }
-    None
+    // `BambuPaths::detect()` already honours $XDG_CONFIG_HOME; retry the
+    // plain home-relative locations in case that variable points elsewhere.
+    let home = dirs::home_dir()?;
+    BambuPaths::find_linux_config_root(&home, None).map(|p| p.to_string_lossy().to_string())
 }
 
 #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
@@ -297,16 +294,16 @@ fn detect_bambu_studio_install() -> (bool, Option<String>) {
     (false, None)
 }
 
+/// Covers distro packages, Flatpak (system and per-user) and AppImages.
 #[cfg(target_os = "linux")]
 fn detect_bambu_studio_install() -> (bool, Option<String>) {
-    let candidates = ["/usr/bin/BambuStudio", "/opt/BambuStudio/BambuStudio"];
-    for path_str in &candidates {
-        let path = PathBuf::from(path_str);
-        if path.exists() {
-            return (true, Some(path_str.to_string()));
-        }
+    let found = dirs::home_dir().and_then(|home| {
+        crate::profile::paths::find_linux_executable(std::path::Path::new("/"), &home)
+    });
+    match found {
+        Some(path) => (true, Some(path.to_string_lossy().to_string())),
+        None => (false, None),
     }
-    (false, None)
 }
 
 #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
@@ -79,13 +79,19 @@ pub async fn revert_to_backup(app: tauri::AppHandle, session_id: i64) -> Result<
         .map_err(|e| format!("Failed to get data dir: {}", e))?;
     let db_path = data_dir.join("refinement_history.db");
 
-    let session = tokio::task::spawn_blocking(move || -> Result<SessionDetail, String> {
-        let history = RefinementHistory::new(&db_path)
-            .map_err(|e| format!("Failed to open history: {}", e))?;
-        history.get_session(session_id)
-    })
-    .await
-    .map_err(|e| format!("history join error: {}", e))??;
+    tokio::task::spawn_blocking(move || revert_session(&db_path, session_id))
+        .await
+        .map_err(|e| format!("revert join error: {}", e))?
+}
+
+/// Restore the profile touched by `session_id` from that session's backup.
+///
+/// Blocking (rusqlite + file copy); shared by `revert_to_backup` and the
+/// headless CLI.
+pub(crate) fn revert_session(db_path: &Path, session_id: i64) -> Result<String, String> {
+    let history =
+        RefinementHistory::new(db_path).map_err(|e| format!("Failed to open history: {}", e))?;
+    let session = history.get_session(session_id)?;
 
     let backup_path = session
         .backup_path
@@ -98,14 +104,8 @@ pub async fn revert_to_backup(app: tauri::AppHandle, session_id: i64) -> Result<
         return Err(format!("Backup file not found: {}", backup_path));
     }
 
-    let backup_owned = backup.to_path_buf();
-    let profile_owned = profile_path.to_path_buf();
-    tokio::task::spawn_blocking(move || {
-        crate::profile::writer::restore_from_backup(&backup_owned, &profile_owned)
-            .map_err(|e| format!("Failed to restore: {}", e))
-    })
-    .await
-    .map_err(|e| format!("restore join error: {}", e))??;
+    crate::profile::writer::restore_from_backup(backup, profile_path)
+        .map_err(|e| format!("Failed to restore: {}", e))?;
 
     info!(
         "Reverted profile {} from backup {}",
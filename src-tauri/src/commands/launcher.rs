@@ -16,7 +16,8 @@ pub struct LaunchResult {
 ///
 /// Priority:
 /// 1. Platform-specific default location
-/// 2. Platform-specific search (Spotlight on macOS, registry/PATH on Windows)
+/// 2. Platform-specific search (Spotlight on macOS, registry/PATH on Windows,
+///    PATH on Linux)
 ///
 /// Note: The "bambu_studio_path" preference stores the CONFIG DIRECTORY,
 /// not the application binary path.
@@ -130,15 +131,13 @@ fn default_bs_path() -> Option<String> {
     None
 }
 
+/// Linux installs are spread across distro packages, Flatpak exports and
+/// AppImages; `find_linux_executable` checks all of them.
 #[cfg(target_os = "linux")]
 fn default_bs_path() -> Option<String> {
-    let candidates = ["/usr/bin/BambuStudio", "/opt/BambuStudio/BambuStudio"];
-    for path in &candidates {
-        if std::path::Path::new(path).exists() {
-            return Some(path.to_string());
-        }
-    }
-    None
+    let home = dirs::home_dir()?;
+    crate::profile::paths::find_linux_executable(std::path::Path::new("/"), &home)
+        .map(|p| p.to_string_lossy().to_string())
 }
 
 #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
@@ -324,15 +323,19 @@ fn search_registry_for_bs() -> Option<String> {
 
 #[cfg(target_os = "linux")]
 fn search_bs_path() -> Option<String> {
-    if let Ok(output) = std::process::Command::new("which")
-        .arg("BambuStudio")
-        .output()
-    {
-        if output.status.success() {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            let trimmed = stdout.trim();
-            if !trimmed.is_empty() && std::path::Path::new(trimmed).exists() {
-                return Some(trimmed.to_string());
+    // Search PATH for the package binary names and the Flatpak launcher
+    for exe_name in &[
+        "bambu-studio",
+        "BambuStudio",
+        crate::profile::paths::FLATPAK_APP_ID,
+    ] {
+        if let Ok(output) = std::process::Command::new("which").arg(exe_name).output() {
+            if output.status.success() {
+                let stdout = String::from_utf8_lossy(&output.stdout);
+                let trimmed = stdout.trim();
+                if !trimmed.is_empty() && std::path::Path::new(trimmed).exists() {
+                    return Some(trimmed.to_string());
+                }
             }
         }
     }
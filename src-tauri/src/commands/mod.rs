@@ -1,12 +1,23 @@
 pub mod analyzer;
 pub mod batch;
+pub mod bundle;
+pub mod calibration;
 pub mod config;
+pub mod family;
+pub mod fields;
 pub mod health;
 pub mod history;
 pub mod keychain;
 pub mod launcher;
 pub mod models;
+pub mod pending;
+pub mod preset;
+pub mod printer;
 pub mod profile;
+pub mod rebase;
+pub mod removal;
 pub mod scraper;
+pub mod slicer;
+pub mod snapshot;
 pub mod stl_bridge;
 pub mod updater;
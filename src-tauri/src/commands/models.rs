@@ -344,6 +344,7 @@ pub async fn validate_model(
         &probe_image,
         &probe_settings,
         "PLA",
+        None,
         &provider,
         &model,
         &api_key,
@@ -0,0 +1,37 @@
+//! Tauri commands for profile writes queued while Bambu Studio is running
+//! (see `pending_writes`).
+
+use crate::pending_writes::{self, FlushReport, PendingWrite, PendingWriteQueue};
+
+/// Profile writes waiting for Bambu Studio to exit, oldest first.
+#[tauri::command]
+pub fn list_pending_writes(app: tauri::AppHandle) -> Result<Vec<PendingWrite>, String> {
+    PendingWriteQueue::for_app(&app)?.list()
+}
+
+/// Drop a queued write without applying it.
+#[tauri::command]
+pub fn discard_pending_write(app: tauri::AppHandle, id: String) -> Result<(), String> {
+    let queue = PendingWriteQueue::for_app(&app)?;
+    if !queue.discard(&id)? {
+        return Err(format!("No queued write with id {}", id));
+    }
+    pending_writes::notify(&app, &queue);
+    Ok(())
+}
+
+/// Write the queue now, retrying writes that failed before. Refused while
+/// Bambu Studio is still running.
+#[tauri::command]
+pub async fn flush_pending_writes(app: tauri::AppHandle) -> Result<FlushReport, String> {
+    tokio::task::spawn_blocking(move || {
+        if crate::profile::generator::is_bambu_studio_running() {
+            return Err(
+                "Bambu Studio is still running. Close it to write queued changes.".to_string(),
+            );
+        }
+        Ok(pending_writes::flush_if_closed(&app, true)?.unwrap_or_default())
+    })
+    .await
+    .map_err(|e| format!("flush join error: {}", e))?
+}
@@ -0,0 +1,437 @@
+//! Tauri commands for process (print) and machine (printer) presets.
+//!
+//! Mirrors the filament profile commands in `commands::profile` for the
+//! other two preset kinds: list, read (with inheritance resolved), diff and
+//! duplicate. The kind is passed as a string (`"process"` or `"machine"`)
+//! and dispatched to the generic helpers below.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tracing::info;
+use walkdir::WalkDir;
+
+use crate::commands::profile::{
+    assert_in_dir, compare_preset_maps, CompareResult, ProfileMetadataInfo,
+};
+use crate::profile::inheritance::resolve_inheritance;
+use crate::profile::paths::BambuPaths;
+use crate::profile::reader::{read_preset, read_profile_metadata};
+use crate::profile::registry::PresetRegistry;
+use crate::profile::types::{Machine, Preset, PresetKind, Process, ProfileMetadata};
+use crate::profile::writer::write_profile_with_metadata;
+
+/// The preset kinds these commands accept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PresetType {
+    Process,
+    Machine,
+}
+
+impl PresetType {
+    fn parse(kind: &str) -> Result<Self, String> {
+        match kind {
+            "process" => Ok(Self::Process),
+            "machine" => Ok(Self::Machine),
+            other => Err(format!(
+                "Unsupported preset kind '{}'. Expected 'process' or 'machine'.",
+                other
+            )),
+        }
+    }
+}
+
+/// Summary information for a preset (used in list views).
+#[derive(Debug, Clone, Serialize)]
+pub struct PresetInfo {
+    pub name: String,
+    pub kind: PresetType,
+    pub inherits: Option<String>,
+    pub path: String,
+    pub is_user_preset: bool,
+    /// False for templates (`instantiation: "false"`) that Bambu Studio hides.
+    pub instantiable: bool,
+}
+
+/// A single summary field shown in detail views (layer height, nozzle, ...).
+#[derive(Debug, Clone, Serialize)]
+pub struct PresetField {
+    pub key: String,
+    pub value: String,
+}
+
+/// Detailed information for a single preset.
+#[derive(Debug, Clone, Serialize)]
+pub struct PresetDetail {
+    pub name: Option<String>,
+    pub kind: PresetType,
+    pub inherits: Option<String>,
+    pub field_count: usize,
+    /// Key settings, taken from the resolved preset when resolution succeeds.
+    pub summary: Vec<PresetField>,
+    pub metadata: Option<ProfileMetadataInfo>,
+    pub raw_json: String,
+    /// The preset with its `inherits`/`include` chain flattened.
+    pub resolved_json: Option<String>,
+    /// Why `resolved_json` is missing (e.g. a parent that isn't installed).
+    pub resolve_error: Option<String>,
+}
+
+fn detect_paths() -> Result<BambuPaths, String> {
+    BambuPaths::detect().map_err(|e| format!("Bambu Studio not found: {}", e))
+}
+
+/// Build a registry of all system and user presets of kind `K`.
+pub(crate) fn load_registry<K: PresetKind>(
+    paths: &BambuPaths,
+) -> Result<PresetRegistry<K>, String> {
+    let system_dir = paths.system_preset_dir::<K>();
+    let mut registry = if system_dir.exists() {
+        PresetRegistry::<K>::discover_system_profiles(&system_dir)
+            .map_err(|e| format!("Failed to load system {} presets: {}", K::FOLDER, e))?
+    } else {
+        PresetRegistry::<K>::new()
+    };
+    if let Some(user_dir) = paths.user_preset_dir::<K>() {
+        registry
+            .discover_user_profiles(&user_dir)
+            .map_err(|e| format!("Failed to load user {} presets: {}", K::FOLDER, e))?;
+    }
+    Ok(registry)
+}
+
+fn list_in_dir<K: PresetKind>(
+    dir: &Path,
+    kind: PresetType,
+    is_user_preset: bool,
+    out: &mut Vec<PresetInfo>,
+) {
+    for entry in WalkDir::new(dir).into_iter().filter_map(|e| e.ok()) {
+        let path = entry.path();
+        if !path.is_file() || path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+        match read_preset::<K>(path) {
+            Ok(preset) => {
+                let name = match preset.name() {
+                    Some(n) => n.to_string(),
+                    None => continue,
+                };
+                out.push(PresetInfo {
+                    name,
+                    kind,
+                    inherits: preset.inherits().map(|s| s.to_string()),
+                    path: path.to_string_lossy().to_string(),
+                    is_user_preset,
+                    instantiable: preset.is_instantiable(),
+                });
+            }
+            Err(e) => {
+                info!(
+                    "Skipping unreadable {} preset at {:?}: {}",
+                    K::FOLDER,
+                    path,
+                    e
+                );
+            }
+        }
+    }
+}
+
+fn list_presets_of<K: PresetKind>(kind: PresetType) -> Vec<PresetInfo> {
+    let paths = match BambuPaths::detect() {
+        Ok(p) => p,
+        Err(_) => {
+            info!("Bambu Studio not detected, returning empty preset list");
+            return Vec::new();
+        }
+    };
+
+    let mut presets = Vec::new();
+    if let Some(user_dir) = paths.user_preset_dir::<K>() {
+        list_in_dir::<K>(&user_dir, kind, true, &mut presets);
+    }
+    let system_dir = paths.system_preset_dir::<K>();
+    if system_dir.exists() {
+        list_in_dir::<K>(&system_dir, kind, false, &mut presets);
+    }
+
+    // User presets first, then alphabetically
+    presets.sort_by(|a, b| {
+        b.is_user_preset
+            .cmp(&a.is_user_preset)
+            .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+    });
+
+    info!("Found {} {} presets", presets.len(), K::FOLDER);
+    presets
+}
+
+fn summary_fields<K: PresetKind>(preset: &Preset<K>) -> Vec<PresetField> {
+    K::SUMMARY_KEYS
+        .iter()
+        .filter_map(|key| {
+            let value = match preset.raw().get(*key)? {
+                serde_json::Value::String(s) => s.clone(),
+                serde_json::Value::Array(arr) => arr
+                    .iter()
+                    .map(|v| {
+                        v.as_str()
+                            .map(|s| s.to_string())
+                            .unwrap_or_else(|| v.to_string())
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                other => other.to_string(),
+            };
+            Some(PresetField {
+                key: key.to_string(),
+                value,
+            })
+        })
+        .collect()
+}
+
+fn read_preset_of<K: PresetKind>(path: &Path, kind: PresetType) -> Result<PresetDetail, String> {
+    let preset = read_preset::<K>(path).map_err(|e| e.to_string())?;
+    let raw_json = preset.to_json_4space().map_err(|e| e.to_string())?;
+
+    let metadata = match read_profile_metadata(path) {
+        Ok(Some(meta)) => Some(ProfileMetadataInfo {
+            sync_info: meta.sync_info,
+            user_id: meta.user_id,
+            setting_id: meta.setting_id,
+            base_id: meta.base_id,
+            updated_time: meta.updated_time,
+        }),
+        _ => None,
+    };
+
+    let resolved = detect_paths()
+        .and_then(|paths| load_registry::<K>(&paths))
+        .and_then(|registry| resolve_inheritance(&preset, &registry).map_err(|e| e.to_string()));
+    let (summary, resolved_json, resolve_error) = match resolved {
+        Ok(r) => (
+            summary_fields(&r),
+            Some(r.to_json_4space().map_err(|e| e.to_string())?),
+            None,
+        ),
+        Err(e) => (summary_fields(&preset), None, Some(e)),
+    };
+
+    Ok(PresetDetail {
+        name: preset.name().map(|s| s.to_string()),
+        kind,
+        inherits: preset.inherits().map(|s| s.to_string()),
+        field_count: preset.field_count(),
+        summary,
+        metadata,
+        raw_json,
+        resolved_json,
+        resolve_error,
+    })
+}
+
+fn compare_presets_of<K: PresetKind>(
+    path_a: &Path,
+    path_b: &Path,
+    show_identical: bool,
+    resolved: bool,
+) -> Result<CompareResult, String> {
+    let mut preset_a = read_preset::<K>(path_a).map_err(|e| e.to_string())?;
+    let mut preset_b = read_preset::<K>(path_b).map_err(|e| e.to_string())?;
+
+    if resolved {
+        let registry = load_registry::<K>(&detect_paths()?)?;
+        preset_a = resolve_inheritance(&preset_a, &registry).map_err(|e| e.to_string())?;
+        preset_b = resolve_inheritance(&preset_b, &registry).map_err(|e| e.to_string())?;
+    }
+
+    Ok(compare_preset_maps(
+        preset_a.name().unwrap_or("<unnamed>"),
+        preset_a.raw(),
+        preset_b.name().unwrap_or("<unnamed>"),
+        preset_b.raw(),
+        show_identical,
+    ))
+}
+
+fn duplicate_preset_of<K: PresetKind>(
+    path: &Path,
+    new_name: &str,
+    kind: PresetType,
+) -> Result<PresetDetail, String> {
+    let new_name = new_name.trim();
+    if new_name.is_empty() {
+        return Err("Preset name cannot be empty".to_string());
+    }
+    if new_name.contains(['/', '\\']) || new_name.contains("..") {
+        return Err(format!("Invalid preset name: {:?}", new_name));
+    }
+
+    let paths = detect_paths()?;
+    let user_dir = paths
+        .user_preset_dir::<K>()
+        .ok_or_else(|| format!("User {} directory not found", K::FOLDER))?;
+    let registry = load_registry::<K>(&paths)?;
+    if registry.get_by_name(new_name).is_some() {
+        return Err(format!(
+            "A {} preset named '{}' already exists",
+            K::FOLDER,
+            new_name
+        ));
+    }
+
+    let source = read_preset::<K>(path).map_err(|e| e.to_string())?;
+    let source_name = source.name().unwrap_or("<unnamed>").to_string();
+    let source_is_user = assert_in_dir(path, true, &user_dir).is_ok();
+
+    // Flatten the source so the copy carries every setting, then point it at
+    // the closest preset Bambu Studio lets users select: the source itself
+    // for system presets, or the source's own parent for user presets.
+    let mut preset = resolve_inheritance(&source, &registry)
+        .map_err(|e| format!("Failed to resolve '{}': {}", source_name, e))?;
+    let parent = if source_is_user {
+        source.inherits().unwrap_or("").to_string()
+    } else if source.is_instantiable() {
+        source_name.clone()
+    } else {
+        String::new()
+    };
+
+    preset.set_string("name", new_name.to_string());
+    preset.set_string("inherits", parent);
+    preset.set_string("from", "User".to_string());
+    preset.set_string("instantiation", "true".to_string());
+    preset.set_string(K::SETTINGS_ID_KEY, new_name.to_string());
+    preset.raw_mut().remove("setting_id");
+
+    let target_path = user_dir.join(format!("{}.json", new_name));
+    assert_in_dir(&target_path, false, &user_dir)?;
+    if target_path.exists() {
+        return Err(format!("Target file already exists: {:?}", target_path));
+    }
+
+    let base_id = if source_is_user {
+        read_profile_metadata(path)
+            .ok()
+            .flatten()
+            .map(|m| m.base_id)
+            .unwrap_or_default()
+    } else {
+        source.setting_id().unwrap_or("").to_string()
+    };
+    let mut metadata = ProfileMetadata {
+        base_id,
+        ..ProfileMetadata::default()
+    };
+    metadata.mark_created();
+
+    write_profile_with_metadata(&preset, &target_path, &metadata)
+        .map_err(|e| format!("Failed to write duplicated preset: {}", e))?;
+
+    info!(
+        "Duplicated {} preset '{}' to {:?} as '{}'",
+        K::FOLDER,
+        source_name,
+        target_path,
+        new_name
+    );
+
+    read_preset_of::<K>(&target_path, kind)
+}
+
+/// List process or machine presets, user presets first.
+///
+/// `kind` is `"process"` or `"machine"`. Returns an empty vec if Bambu
+/// Studio is not installed (not an error).
+#[tauri::command]
+pub fn list_presets(kind: String) -> Result<Vec<PresetInfo>, String> {
+    let kind = PresetType::parse(&kind)?;
+    Ok(match kind {
+        PresetType::Process => list_presets_of::<Process>(kind),
+        PresetType::Machine => list_presets_of::<Machine>(kind),
+    })
+}
+
+/// Read a process or machine preset, including its resolved form.
+#[tauri::command]
+pub fn read_preset_command(kind: String, path: String) -> Result<PresetDetail, String> {
+    let kind = PresetType::parse(&kind)?;
+    let path = Path::new(&path);
+    match kind {
+        PresetType::Process => read_preset_of::<Process>(path, kind),
+        PresetType::Machine => read_preset_of::<Machine>(path, kind),
+    }
+}
+
+/// Compare two presets of the same kind, grouped by category.
+///
+/// With `resolved = true`, both presets are flattened through their
+/// inheritance chains first, so a user preset that only stores overrides
+/// can be compared meaningfully with a system preset.
+#[tauri::command]
+pub fn compare_presets(
+    kind: String,
+    path_a: String,
+    path_b: String,
+    show_identical: bool,
+    resolved: bool,
+) -> Result<CompareResult, String> {
+    let kind = PresetType::parse(&kind)?;
+    let (a, b) = (Path::new(&path_a), Path::new(&path_b));
+    match kind {
+        PresetType::Process => compare_presets_of::<Process>(a, b, show_identical, resolved),
+        PresetType::Machine => compare_presets_of::<Machine>(a, b, show_identical, resolved),
+    }
+}
+
+/// Duplicate a process or machine preset into the user preset directory.
+///
+/// The copy is fully flattened, renamed to `new_name` and saved as
+/// `<new_name>.json` with a fresh `.info` file.
+#[tauri::command]
+pub fn duplicate_preset(
+    kind: String,
+    path: String,
+    new_name: String,
+) -> Result<PresetDetail, String> {
+    let kind = PresetType::parse(&kind)?;
+    let path = Path::new(&path);
+    match kind {
+        PresetType::Process => duplicate_preset_of::<Process>(path, &new_name, kind),
+        PresetType::Machine => duplicate_preset_of::<Machine>(path, &new_name, kind),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_preset_type_parse() {
+        assert_eq!(PresetType::parse("process").unwrap(), PresetType::Process);
+        assert_eq!(PresetType::parse("machine").unwrap(), PresetType::Machine);
+        assert!(PresetType::parse("filament").is_err());
+    }
+
+    #[test]
+    fn test_summary_fields_follow_kind() {
+        let machine = Preset::<Machine>::from_map(
+            json!({
+                "name": "Bambu Lab H2D 0.4 nozzle",
+                "printer_model": "Bambu Lab H2D",
+                "nozzle_diameter": ["0.4", "0.4"],
+                "layer_height": "0.2"
+            })
+            .as_object()
+            .unwrap()
+            .clone(),
+        );
+        let fields = summary_fields(&machine);
+        let keys: Vec<&str> = fields.iter().map(|f| f.key.as_str()).collect();
+        assert_eq!(keys, vec!["printer_model", "nozzle_diameter"]);
+        assert_eq!(fields[1].value, "0.4, 0.4");
+    }
+}
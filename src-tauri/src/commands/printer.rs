@@ -0,0 +1,175 @@
+//! Tauri commands for live printer connections.
+//!
+//! Status updates are pushed to the frontend as events rather than polled:
+//! `printer-status` carries the merged [`PrinterStatus`] after every report
+//! and `printer-connection` carries connection state changes.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use tauri::{Emitter, Manager};
+use tracing::{info, warn};
+
+use crate::commands::profile::load_filament_registry;
+use crate::printer::{
+    resolve_trays, AmsFilamentSetting, PrinterConfig, PrinterEvent, PrinterManager, PrinterStatus,
+    PrinterSummary, TrayMatchState, TrayResolution,
+};
+use crate::profile::inheritance::resolve_inheritance;
+use crate::profile::paths::BambuPaths;
+use crate::profile::reader::read_profile;
+
+/// Connect to a printer over LAN MQTT. Replaces any existing connection to
+/// the same serial. Returns once the connection is started; watch
+/// `printer-connection` events for the outcome.
+#[tauri::command]
+pub async fn connect_printer(app: tauri::AppHandle, config: PrinterConfig) -> Result<(), String> {
+    info!("Connecting to printer {} at {}", config.serial, config.host);
+    let emitter = app.clone();
+    let on_event = Arc::new(move |event: PrinterEvent| {
+        if let Err(e) = emitter.emit(event.event_name(), &event) {
+            warn!("Failed to emit {}: {}", event.event_name(), e);
+        }
+    });
+    app.state::<PrinterManager>()
+        .connect(config, on_event)
+        .map_err(|e| format!("Failed to connect to printer: {}", e))
+}
+
+/// Disconnect from a printer.
+#[tauri::command]
+pub async fn disconnect_printer(app: tauri::AppHandle, serial: String) -> Result<(), String> {
+    if !app.state::<PrinterManager>().disconnect(&serial) {
+        return Err(format!("Printer {} is not connected", serial));
+    }
+    Ok(())
+}
+
+/// List connected printers with their latest status.
+#[tauri::command]
+pub async fn list_printers(app: tauri::AppHandle) -> Result<Vec<PrinterSummary>, String> {
+    Ok(app.state::<PrinterManager>().list())
+}
+
+/// Latest merged status of a printer.
+#[tauri::command]
+pub async fn get_printer_status(
+    app: tauri::AppHandle,
+    serial: String,
+) -> Result<PrinterStatus, String> {
+    app.state::<PrinterManager>()
+        .status(&serial)
+        .ok_or_else(|| format!("Printer {} is not connected", serial))
+}
+
+/// Ask a printer to resend its full state (arrives as a `printer-status`
+/// event).
+#[tauri::command]
+pub async fn refresh_printer_status(app: tauri::AppHandle, serial: String) -> Result<(), String> {
+    let handle = app
+        .state::<PrinterManager>()
+        .handle(&serial)
+        .map_err(|e| e.to_string())?;
+    handle
+        .request_full_status()
+        .await
+        .map_err(|e| format!("Failed to request printer status: {}", e))
+}
+
+/// Match a connected printer's AMS trays to installed filament profiles.
+///
+/// Unmatched trays carry `suggested_specs`; generate a profile for one by
+/// passing them to `generate_profile_from_specs` with the tray's
+/// `filament_id` as `existing_filament_id`.
+#[tauri::command]
+pub async fn resolve_ams_trays(
+    app: tauri::AppHandle,
+    serial: String,
+) -> Result<Vec<TrayResolution>, String> {
+    let status = app
+        .state::<PrinterManager>()
+        .status(&serial)
+        .ok_or_else(|| format!("Printer {} is not connected", serial))?;
+    let paths = BambuPaths::detect().map_err(|e| format!("Bambu Studio not found: {}", e))?;
+    let registry = load_filament_registry(&paths)?;
+    let trays = resolve_trays(&status, &registry);
+    info!(
+        "Resolved {} trays on {} ({} unmatched)",
+        trays.len(),
+        serial,
+        trays
+            .iter()
+            .filter(|t| t.state == TrayMatchState::Unmatched)
+            .count()
+    );
+    Ok(trays)
+}
+
+/// Set what's loaded in a tray from a filament profile, by sending an
+/// `ams_filament_setting` request. `ams_id` None targets the external
+/// spool. The colour defaults to the profile's, then the tray's current
+/// one.
+///
+/// Returns what was sent, including any temperature adjustments made to
+/// stay within the material's limits.
+#[tauri::command]
+pub async fn set_ams_tray_filament(
+    app: tauri::AppHandle,
+    serial: String,
+    ams_id: Option<u8>,
+    tray_id: u8,
+    profile_path: String,
+    color: Option<String>,
+) -> Result<AmsFilamentSetting, String> {
+    let manager = app.state::<PrinterManager>();
+    let handle = manager.handle(&serial).map_err(|e| e.to_string())?;
+
+    let paths = BambuPaths::detect().map_err(|e| format!("Bambu Studio not found: {}", e))?;
+    let registry = load_filament_registry(&paths)?;
+    let profile = read_profile(Path::new(&profile_path))
+        .map_err(|e| format!("Failed to read profile: {}", e))?;
+    let resolved = resolve_inheritance(&profile, &registry)
+        .map_err(|e| format!("Failed to resolve profile: {}", e))?;
+    let filament_id = registry
+        .effective_filament_id(&profile)
+        .unwrap_or_default()
+        .to_string();
+
+    let current_color = manager.status(&serial).and_then(|status| {
+        let tray = match ams_id {
+            Some(ams_id) => status
+                .ams
+                .into_iter()
+                .find(|u| u.id == ams_id.to_string())?
+                .trays
+                .into_iter()
+                .find(|t| t.id == tray_id.to_string())?,
+            None => status.external_spool?,
+        };
+        tray.color
+    });
+    let color = color
+        .or_else(|| resolved.default_colour().map(str::to_string))
+        .or(current_color)
+        .unwrap_or_else(|| "FFFFFF".to_string());
+
+    let setting =
+        AmsFilamentSetting::from_profile(&resolved, &filament_id, ams_id, tray_id, &color)
+            .map_err(|e| format!("Can't use this profile for the tray: {}", e))?;
+    for warning in &setting.warnings {
+        warn!("{}", warning.message);
+    }
+    handle
+        .send_request(&setting.to_request())
+        .await
+        .map_err(|e| format!("Failed to send tray setting: {}", e))?;
+    info!(
+        "Set {} tray {}/{} to {} ({})",
+        serial, setting.ams_id, setting.tray_id, filament_id, setting.tray_type
+    );
+    // Not every model reports the change on its own
+    if let Err(e) = handle.request_full_status().await {
+        warn!("Failed to request printer status: {}", e);
+    }
+    Ok(setting)
+}
@@ -1,18 +1,27 @@
-use serde::Serialize;
-use std::collections::{BTreeSet, HashSet};
-use std::sync::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 use tracing::{info, warn};
 use walkdir::WalkDir;
 
+use crate::pending_writes::{self, PendingOperation};
+use crate::profile::conflict::{three_way_diff, ProfileConflict, ProfileVersion};
+use crate::profile::fields::dictionary;
 use crate::profile::generator;
-use crate::profile::inheritance::resolve_inheritance;
+use crate::profile::inheritance::{minimize, resolve_inheritance};
+use crate::profile::lint::{lint_profile, LintOptions};
 use crate::profile::paths::BambuPaths;
+use crate::profile::query::ProfileQuery;
 use crate::profile::reader::{read_profile, read_profile_metadata};
-use crate::profile::registry::ProfileRegistry;
+use crate::profile::rebase;
+use crate::profile::registry::{MachineRegistry, ProfileRegistry};
 use crate::profile::types::{FilamentProfile, ProfileMetadata};
 use crate::profile::writer::{
-    register_filament_in_conf, write_profile_atomic, write_profile_with_metadata,
+    recover_install_journals, write_edited_profile, write_profile_atomic,
+    write_profile_metadata_atomic, write_profile_with_metadata, InstallTransaction,
+    INSTALL_JOURNAL_DIR,
 };
 
 const DEFAULT_TARGET_PRINTER_LABEL: &str = "Bambu Lab H2C 0.4 nozzle";
@@ -55,6 +64,15 @@ pub struct ProfileDetail {
     pub compatible_printers: Option<Vec<String>>,
     pub metadata: Option<ProfileMetadataInfo>,
     pub raw_json: String,
+    /// Version token to pass back when editing, so a write is refused if
+    /// the file changed on disk in the meantime.
+    pub version: String,
+    /// Set when the profile belongs to a different Bambu account from the
+    /// one logged in.
+    pub sync_warning: Option<String>,
+    /// Set when the edit that returned this detail was queued until Bambu
+    /// Studio exits (see `pending_writes`), so the file doesn't have it yet.
+    pub queued: bool,
 }
 
 /// Serializable metadata from a `.info` companion file.
@@ -142,20 +160,28 @@ pub fn list_profiles() -> Result<Vec<ProfileInfo>, String> {
 pub fn read_profile_command(path: String) -> Result<ProfileDetail, String> {
     let file_path = std::path::Path::new(&path);
 
+    // Taken before reading, so a concurrent change makes the token stale
+    // rather than the content
+    let version = ProfileVersion::of_file(file_path)
+        .map_err(|e| e.to_string())?
+        .token();
     let profile = read_profile(file_path).map_err(|e| e.to_string())?;
+    remember_loaded_profile(file_path, &version, &profile);
     let raw_json = profile.to_json_4space().map_err(|e| e.to_string())?;
 
     // Try to read metadata
-    let metadata = match read_profile_metadata(file_path) {
-        Ok(Some(meta)) => Some(ProfileMetadataInfo {
-            sync_info: meta.sync_info,
-            user_id: meta.user_id,
-            setting_id: meta.setting_id,
-            base_id: meta.base_id,
-            updated_time: meta.updated_time,
-        }),
-        _ => None,
-    };
+    let raw_metadata = read_profile_metadata(file_path).ok().flatten();
+    let sync_warning = raw_metadata.as_ref().and_then(|meta| {
+        let preset_folder = BambuPaths::detect().ok()?.preset_folder;
+        meta.account_warning(preset_folder.as_deref())
+    });
+    let metadata = raw_metadata.map(|meta| ProfileMetadataInfo {
+        sync_info: meta.sync_info,
+        user_id: meta.user_id,
+        setting_id: meta.setting_id,
+        base_id: meta.base_id,
+        updated_time: meta.updated_time,
+    });
 
     Ok(ProfileDetail {
         name: profile.name().map(|s| s.to_string()),
@@ -174,9 +200,89 @@ pub fn read_profile_command(path: String) -> Result<ProfileDetail, String> {
             .map(|v| v.into_iter().map(|s| s.to_string()).collect()),
         metadata,
         raw_json,
+        version,
+        sync_warning,
+        queued: false,
     })
 }
 
+// ---------------------------------------------------------------------------
+// Optimistic concurrency for edits
+// ---------------------------------------------------------------------------
+
+/// The content most recently handed out by `read_profile_command` for each
+/// path, keyed by its version token. It's the base of the three-way diff
+/// when an edit conflicts.
+type LoadedProfiles = HashMap<PathBuf, (String, serde_json::Map<String, serde_json::Value>)>;
+
+fn loaded_profiles() -> &'static Mutex<LoadedProfiles> {
+    static LOADED: std::sync::OnceLock<Mutex<LoadedProfiles>> = std::sync::OnceLock::new();
+    LOADED.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+pub(crate) fn remember_loaded_profile(path: &Path, version: &str, profile: &FilamentProfile) {
+    loaded_profiles()
+        .lock()
+        .unwrap_or_else(|p| p.into_inner())
+        .insert(
+            path.to_path_buf(),
+            (version.to_string(), profile.raw().clone()),
+        );
+}
+
+fn loaded_profile(
+    path: &Path,
+    version: &str,
+) -> Option<serde_json::Map<String, serde_json::Value>> {
+    loaded_profiles()
+        .lock()
+        .unwrap_or_else(|p| p.into_inner())
+        .get(path)
+        .filter(|(v, _)| v == version)
+        .map(|(_, raw)| raw.clone())
+}
+
+/// Refuse to write `current` (as just read from `file_path`) if the file
+/// has changed since the caller loaded `expected_version`.
+///
+/// On conflict the error is a JSON-encoded `ProfileConflict`: `edit`, the
+/// change the caller is making, is applied to the profile as they loaded it
+/// and diffed against both that and the file on disk. Without an expected
+/// version the write goes ahead unchecked.
+pub(crate) fn ensure_profile_unchanged(
+    file_path: &Path,
+    expected_version: Option<&str>,
+    current: &FilamentProfile,
+    edit: impl Fn(&mut FilamentProfile),
+) -> Result<(), String> {
+    let Some(expected) = expected_version else {
+        return Ok(());
+    };
+    let unchanged = match ProfileVersion::parse(expected) {
+        Some(version) => version.is_current(file_path).map_err(|e| e.to_string())?,
+        None => false,
+    };
+    if unchanged {
+        return Ok(());
+    }
+
+    let base = loaded_profile(file_path, expected);
+    let mut ours = FilamentProfile::from_map(base.clone().unwrap_or_else(|| current.raw().clone()));
+    edit(&mut ours);
+    let conflict = ProfileConflict {
+        message: "Profile changed on disk since it was loaded. Review the changes and save again."
+            .to_string(),
+        path: file_path.to_string_lossy().to_string(),
+        current_version: ProfileVersion::of_file(file_path)
+            .map_err(|e| e.to_string())?
+            .token(),
+        has_base: base.is_some(),
+        fields: three_way_diff(base.as_ref(), ours.raw(), current.raw()),
+    };
+    warn!("Refusing stale write to {:?}", file_path);
+    Err(serde_json::to_string(&conflict).unwrap_or(conflict.message))
+}
+
 /// Get the count of system filament profiles.
 ///
 /// Quick check: counts .json files in the system filaments directory.
@@ -287,7 +393,7 @@ pub fn list_target_printer_options() -> Result<TargetPrinterOptions, String> {
 }
 
 /// Result from profile generation (preview step, no files written).
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GenerateResult {
     pub profile_name: String,
     pub filament_id: String,
@@ -296,14 +402,22 @@ pub struct GenerateResult {
     pub filename: String,
     pub field_count: usize,
     pub base_profile_used: String,
+    /// Fingerprint of the resolved base, recorded so the profile can be
+    /// rebased when Bambu Studio updates it.
+    #[serde(default)]
+    pub base_fingerprint: String,
     pub specs_applied: GeneratedSpecs,
     pub diffs: Vec<ProfileDiff>,
     pub warnings: Vec<String>,
     pub bambu_studio_running: bool,
+    /// Profiles for the other nozzles requested in the same call, sharing
+    /// this profile's `filament_id`.
+    #[serde(default)]
+    pub variants: Vec<GenerateResult>,
 }
 
 /// Summary of which scraped specs were applied to the profile.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GeneratedSpecs {
     pub nozzle_temp: Option<String>,
     pub bed_temp: Option<String>,
@@ -312,7 +426,7 @@ pub struct GeneratedSpecs {
 }
 
 /// A single field difference between the base profile and the generated profile.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProfileDiff {
     pub key: String,
     pub label: String,
@@ -321,11 +435,15 @@ pub struct ProfileDiff {
 }
 
 /// Result from profile installation (files written to disk).
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InstallResult {
     pub installed_path: String,
     pub profile_name: String,
     pub bambu_studio_was_running: bool,
+    /// Set when Bambu Studio was running and the install was queued until
+    /// it exits instead of written.
+    #[serde(default)]
+    pub queued: bool,
 }
 
 /// Generate a filament profile from scraped specifications (preview only).
@@ -335,7 +453,8 @@ pub struct InstallResult {
 /// the profile to disk.
 ///
 /// Two-step flow: generate (preview) -> install (write) lets the UI show
-/// a preview before committing.
+/// a preview before committing. The generated profile is linted; errors
+/// fail the command and warnings are added to `warnings`.
 ///
 /// `existing_filament_id` — when the caller already knows the `filament_id`
 /// to use (e.g. the first profile in a multi-nozzle batch already resolved it),
@@ -343,12 +462,30 @@ pub struct InstallResult {
 /// When `None`, the command looks up the user filament directory for an existing
 /// profile with the same brand/material/serial and reuses its ID if found,
 /// falling back to generating a fresh ID only when none exists yet.
+///
+/// With `delta`, the profile inherits from its base and carries only the
+/// fields that differ from it (see `inheritance::minimize`), so it picks
+/// up fixes when Bambu Studio updates its system presets. Without a
+/// selected base it's built on the target printer's system preset for the
+/// material (see `generator::delta_parent_name`). `diffs` still compare
+/// against the resolved base.
+///
+/// `additional_printers` generates a variant of the same filament for each
+/// further printer preset (typically other nozzle sizes) in `variants`,
+/// all sharing one `filament_id`. Specs are scaled to each nozzle (see
+/// `profile::nozzle`).
+///
+/// The specs and base are recorded in the generation store so the profile
+/// can be rebased once installed (see `commands::rebase`).
 #[tauri::command]
 pub async fn generate_profile_from_specs(
+    app: tauri::AppHandle,
     specs: crate::scraper::types::FilamentSpecs,
     target_printer: Option<String>,
     base_profile_path: Option<String>,
     existing_filament_id: Option<String>,
+    delta: Option<bool>,
+    additional_printers: Option<Vec<String>>,
 ) -> Result<GenerateResult, String> {
     info!(
         "generate_profile_from_specs called for: {} {}",
@@ -363,6 +500,60 @@ pub async fn generate_profile_from_specs(
         )
     })?;
 
+    let mut result = generate_profile_for_paths(
+        &paths,
+        &specs,
+        target_printer.as_deref(),
+        base_profile_path.as_deref(),
+        existing_filament_id,
+        delta.unwrap_or(false),
+    )?;
+    let mut printers = vec![target_printer];
+    for printer in additional_printers.unwrap_or_default() {
+        if printers.contains(&Some(printer.clone())) {
+            continue;
+        }
+        let variant = generate_profile_for_paths(
+            &paths,
+            &specs,
+            Some(&printer),
+            base_profile_path.as_deref(),
+            Some(result.filament_id.clone()),
+            delta.unwrap_or(false),
+        )
+        .map_err(|e| format!("{}: {}", printer, e))?;
+        result.variants.push(variant);
+        printers.push(Some(printer));
+    }
+
+    let generated = std::iter::once(&result).chain(&result.variants);
+    for (generated, printer) in generated.zip(printers) {
+        if let Ok(metadata) = ProfileMetadata::from_info_string(&generated.metadata_info) {
+            let record = rebase::GenerationRecord::new(
+                metadata.setting_id,
+                generated.profile_name.clone(),
+                specs.clone(),
+                printer,
+                generated.base_profile_used.clone(),
+                generated.base_fingerprint.clone(),
+            );
+            super::rebase::record_generation(&app, record).await;
+        }
+    }
+    Ok(result)
+}
+
+/// Generation half of `generate_profile_from_specs` against an explicit
+/// Bambu Studio config tree. Shared with the headless CLI, which may point
+/// at a config root other than the detected one.
+pub(crate) fn generate_profile_for_paths(
+    paths: &BambuPaths,
+    specs: &crate::scraper::types::FilamentSpecs,
+    target_printer: Option<&str>,
+    base_profile_path: Option<&str>,
+    existing_filament_id: Option<String>,
+    delta: bool,
+) -> Result<GenerateResult, String> {
     // Build registry from system + user filament profiles
     let system_dir = paths.system_filament_dir();
     if !system_dir.exists() {
@@ -399,10 +590,21 @@ pub async fn generate_profile_from_specs(
     });
 
     // Determine the base profile to use (selected path or default by material).
+    // Delta profiles are built on the system preset they'll inherit from,
+    // so they resolve back to exactly what was generated.
     let material = crate::scraper::types::MaterialType::from_str(&specs.material);
-    let default_base_name = generator::base_profile_name(&material).to_string();
+    let default_base_name = if delta {
+        let printer = target_printer.unwrap_or(DEFAULT_TARGET_PRINTER_LABEL);
+        generator::delta_parent_name(&registry, &material, printer).ok_or_else(|| {
+            format!(
+                "No installed system preset for {} on {} to inherit from. Choose a base profile, or generate a flattened profile.",
+                specs.material, printer
+            )
+        })?
+    } else {
+        generator::base_profile_name(&material).to_string()
+    };
     let (base_name, base_resolved) = if let Some(path) = base_profile_path
-        .as_ref()
         .map(|s| s.trim().to_string())
         .filter(|s| !s.is_empty())
     {
@@ -437,14 +639,25 @@ pub async fn generate_profile_from_specs(
     };
 
     // Generate the profile
-    let (profile, metadata, filename) = generator::generate_profile(
-        &specs,
+    let machines = load_machine_registry(paths);
+    let (profile, mut metadata, filename) = generator::generate_profile(
+        specs,
         &registry,
-        target_printer.as_deref(),
+        &machines,
+        target_printer,
         Some(base_name.as_str()),
         resolved_filament_id,
     )
     .map_err(|e| format!("Failed to generate profile: {}", e))?;
+    // Regenerating an installed profile keeps its identity, so the
+    // generation is recorded under the setting_id it will be installed with
+    if let Some(ref ud) = user_dir {
+        let existing = read_profile_metadata(&ud.join(&filename))
+            .map_err(|e| format!("Failed to read existing metadata for {}: {}", filename, e))?;
+        if existing.is_some() {
+            metadata.mark_written_over(existing);
+        }
+    }
 
     // Capture the filament_id that was used (for the caller to propagate in batches)
     let filament_id = profile
@@ -452,9 +665,36 @@ pub async fn generate_profile_from_specs(
         .unwrap_or("")
         .to_string();
 
+    let lint = lint_profile(
+        &profile,
+        &LintOptions {
+            extruder_count: profile_extruder_count(&machines, &profile),
+        },
+    );
+    if lint.has_errors() {
+        return Err(format!(
+            "Generated profile failed validation: {}",
+            lint.error_summary()
+        ));
+    }
+
     // Compute diffs between base and generated profile
     let diffs = compute_profile_diffs(&base_resolved, &profile);
 
+    let profile = if delta {
+        let minimized = minimize(&profile, &base_name, &registry)
+            .map_err(|e| format!("Failed to build delta profile: {}", e))?;
+        // Bambu Studio records the parent's setting_id for inheriting presets
+        metadata.base_id = registry
+            .get_by_name(&base_name)
+            .and_then(|base| base.setting_id())
+            .unwrap_or_default()
+            .to_string();
+        minimized
+    } else {
+        profile
+    };
+
     // Serialize for transport
     let profile_json = profile
         .to_json_4space()
@@ -472,6 +712,7 @@ pub async fn generate_profile_from_specs(
                 .to_string(),
         );
     }
+    warnings.extend(lint.warnings().map(|d| d.to_string()));
 
     // Build specs summary for UI display
     let specs_applied = GeneratedSpecs {
@@ -518,20 +759,61 @@ pub async fn generate_profile_from_specs(
         filename,
         field_count: profile.field_count(),
         base_profile_used: base_name,
+        base_fingerprint: rebase::base_fingerprint(&base_resolved),
         specs_applied,
         diffs,
         warnings,
         bambu_studio_running: bs_running,
+        variants: Vec::new(),
+    })
+}
+
+/// System and user filament profiles under `paths`.
+pub(crate) fn load_filament_registry(paths: &BambuPaths) -> Result<ProfileRegistry, String> {
+    let system_dir = paths.system_filament_dir();
+    let mut registry = if system_dir.exists() {
+        ProfileRegistry::discover_system_profiles(&system_dir)
+            .map_err(|e| format!("Failed to load system profiles: {}", e))?
+    } else {
+        warn!("System filament directory not found at {:?}", system_dir);
+        ProfileRegistry::new()
+    };
+    if let Some(user_dir) = paths.user_filament_dir().filter(|d| d.exists()) {
+        registry
+            .discover_user_profiles(&user_dir)
+            .map_err(|e| format!("Failed to load user profiles: {}", e))?;
+    }
+    Ok(registry)
+}
+
+/// System and user machine presets under `paths`. Only used to size
+/// per-extruder arrays, so a failure is logged rather than returned.
+pub(crate) fn load_machine_registry(paths: &BambuPaths) -> MachineRegistry {
+    super::preset::load_registry(paths).unwrap_or_else(|e| {
+        warn!("{}", e);
+        MachineRegistry::new()
     })
 }
 
+/// Extruder count of the printer `profile` is for (its first
+/// `compatible_printers` entry), if that printer's preset is installed.
+pub(crate) fn profile_extruder_count(
+    machines: &MachineRegistry,
+    profile: &FilamentProfile,
+) -> Option<usize> {
+    let printers = profile.compatible_printers()?;
+    generator::printer_extruder_count(machines, printers.first()?)
+}
+
 /// Install a previously generated profile to the Bambu Studio user directory.
 ///
 /// Takes the profile JSON and metadata from `generate_profile_from_specs`
-/// and writes them atomically to disk. Checks if Bambu Studio is running
-/// and requires `force=true` to proceed if it is.
+/// and writes them atomically to disk. If Bambu Studio is running the
+/// install is queued until it exits (see `pending_writes`), unless `force`
+/// is set. Profiles that fail `profile::lint` are refused.
 #[tauri::command]
 pub async fn install_generated_profile(
+    app: tauri::AppHandle,
     profile_json: String,
     metadata_info: String,
     filename: String,
@@ -539,22 +821,51 @@ pub async fn install_generated_profile(
 ) -> Result<InstallResult, String> {
     info!("install_generated_profile called for: {}", filename);
 
-    // Parse the profile and metadata back from serialized form
-    let profile = FilamentProfile::from_json(&profile_json)
-        .map_err(|e| format!("Invalid profile JSON: {}", e))?;
-    let metadata = ProfileMetadata::from_info_string(&metadata_info)
-        .map_err(|e| format!("Invalid metadata: {}", e))?;
+    // Detect paths and get user filament directory
+    let paths = BambuPaths::detect().map_err(|e| {
+        format!(
+            "Bambu Studio not found: {}. Please install Bambu Studio first.",
+            e
+        )
+    })?;
 
-    // Check if Bambu Studio is running
-    let bs_running = generator::is_bambu_studio_running();
-    if bs_running && !force {
-        return Err(
-            "Bambu Studio is running. Use force=true to install anyway, but restart BS to see changes."
-                .to_string(),
-        );
+    if !force && generator::is_bambu_studio_running() {
+        let (profile, _) = check_installable(&paths, &profile_json, &metadata_info)?;
+        let user_dir = user_filament_dir_for_install(&paths)?;
+        pending_writes::enqueue(
+            &app,
+            PendingOperation::Install {
+                profile_json,
+                metadata_info,
+                filename: filename.clone(),
+            },
+        )?;
+        return Ok(InstallResult {
+            installed_path: user_dir.join(&filename).to_string_lossy().to_string(),
+            profile_name: profile.name().unwrap_or("<unnamed>").to_string(),
+            bambu_studio_was_running: true,
+            queued: true,
+        });
     }
 
-    // Detect paths and get user filament directory
+    install_profile_for_paths(&paths, &profile_json, &metadata_info, &filename, force)
+}
+
+/// Install several generated profiles (e.g. the nozzle variants from one
+/// `generate_profile_from_specs` call) in one transaction.
+///
+/// The library is snapshotted first. If Bambu Studio is running the
+/// installs are queued together until it exits (see `pending_writes`).
+#[tauri::command]
+pub async fn install_generated_profiles(
+    app: tauri::AppHandle,
+    installs: Vec<ProfileInstall>,
+) -> Result<Vec<InstallResult>, String> {
+    info!(
+        "install_generated_profiles called for {} profiles",
+        installs.len()
+    );
+
     let paths = BambuPaths::detect().map_err(|e| {
         format!(
             "Bambu Studio not found: {}. Please install Bambu Studio first.",
@@ -562,59 +873,220 @@ pub async fn install_generated_profile(
         )
     })?;
 
-    let user_dir = paths.user_filament_dir().ok_or_else(|| {
+    if generator::is_bambu_studio_running() {
+        let user_dir = user_filament_dir_for_install(&paths)?;
+        let results = installs
+            .iter()
+            .map(|install| {
+                let (profile, _) =
+                    check_installable(&paths, &install.profile_json, &install.metadata_info)
+                        .map_err(|e| format!("{}: {}", install.filename, e))?;
+                Ok(InstallResult {
+                    installed_path: user_dir
+                        .join(&install.filename)
+                        .to_string_lossy()
+                        .to_string(),
+                    profile_name: profile.name().unwrap_or("<unnamed>").to_string(),
+                    bambu_studio_was_running: true,
+                    queued: true,
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        pending_writes::enqueue(&app, PendingOperation::InstallAll { installs })?;
+        return Ok(results);
+    }
+
+    let filenames: Vec<&str> = installs.iter().map(|i| i.filename.as_str()).collect();
+    super::snapshot::snapshot_before(
+        &app,
+        &paths,
+        &format!("install of {}", filenames.join(", ")),
+    )
+    .await?;
+    tokio::task::spawn_blocking(move || install_profiles_for_paths(&paths, &installs, false))
+        .await
+        .map_err(|e| format!("install join error: {}", e))?
+}
+
+/// Parse and lint a generated profile and its metadata before installing.
+pub(crate) fn check_installable(
+    paths: &BambuPaths,
+    profile_json: &str,
+    metadata_info: &str,
+) -> Result<(FilamentProfile, ProfileMetadata), String> {
+    let profile = FilamentProfile::from_json(profile_json)
+        .map_err(|e| format!("Invalid profile JSON: {}", e))?;
+    let metadata = ProfileMetadata::from_info_string(metadata_info)
+        .map_err(|e| format!("Invalid metadata: {}", e))?;
+    let machines = load_machine_registry(paths);
+    let lint = lint_profile(
+        &profile,
+        &LintOptions {
+            extruder_count: profile_extruder_count(&machines, &profile),
+        },
+    );
+    if lint.has_errors() {
+        return Err(format!(
+            "Refusing to install an invalid profile: {}",
+            lint.error_summary()
+        ));
+    }
+    Ok((profile, metadata))
+}
+
+fn user_filament_dir_for_install(paths: &BambuPaths) -> Result<PathBuf, String> {
+    paths.user_filament_dir().ok_or_else(|| {
         "User filament directory not found. Have you logged into Bambu Studio at least once?"
             .to_string()
-    })?;
-
-    // Build target path
-    let target_path = user_dir.join(&filename);
+    })
+}
 
-    // Check for existing file
-    if target_path.exists() {
-        info!("Overwriting existing profile at {:?}", target_path);
+/// Finish or roll back installs a previous run left half done (see
+/// `profile::writer::InstallTransaction`). Failures are logged, not returned:
+/// a stuck journal shouldn't stop the app from starting.
+pub(crate) fn recover_interrupted_installs(paths: &BambuPaths) {
+    let Some(user_dir) = paths.user_filament_dir() else {
+        return;
+    };
+    match recover_install_journals(&user_dir.join(INSTALL_JOURNAL_DIR)) {
+        Ok(recovered) => {
+            for message in recovered {
+                warn!("Recovered interrupted install: {}", message);
+            }
+        }
+        Err(e) => warn!("Failed to recover interrupted installs: {}", e),
     }
+}
 
-    // Write profile + metadata atomically
-    write_profile_with_metadata(&profile, &target_path, &metadata)
-        .map_err(|e| format!("Failed to write profile: {}", e))?;
+/// A generated profile to install: what `generate_profile_from_specs`
+/// returns for it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfileInstall {
+    pub profile_json: String,
+    pub metadata_info: String,
+    pub filename: String,
+}
 
-    let profile_name = profile.name().unwrap_or("<unnamed>").to_string();
+/// Install half of `install_generated_profile` against an explicit Bambu
+/// Studio config tree. Shared with the headless CLI.
+pub(crate) fn install_profile_for_paths(
+    paths: &BambuPaths,
+    profile_json: &str,
+    metadata_info: &str,
+    filename: &str,
+    force: bool,
+) -> Result<InstallResult, String> {
+    let install = ProfileInstall {
+        profile_json: profile_json.to_string(),
+        metadata_info: metadata_info.to_string(),
+        filename: filename.to_string(),
+    };
+    let mut results = install_profiles_for_paths(paths, std::slice::from_ref(&install), force)?;
+    Ok(results.remove(0))
+}
 
-    // Register the filament in BambuStudio.conf so it appears as visible/available.
-    // The profile name in the filaments array is the file stem (filename without .json).
-    let file_stem = target_path
-        .file_stem()
-        .and_then(|s| s.to_str())
-        .unwrap_or(&filename);
-    if let Err(e) = register_filament_in_conf(&paths.config_root, file_stem) {
-        warn!(
-            "Failed to register filament in BambuStudio.conf (profile still installed): {}",
-            e
+/// Install several generated profiles in one transaction: all of them,
+/// with their metadata and BambuStudio.conf registrations, or none. Every
+/// profile is checked before anything is written.
+pub(crate) fn install_profiles_for_paths(
+    paths: &BambuPaths,
+    installs: &[ProfileInstall],
+    force: bool,
+) -> Result<Vec<InstallResult>, String> {
+    // Parse the profiles and metadata back from serialized form
+    let checked = installs
+        .iter()
+        .map(|install| {
+            check_installable(paths, &install.profile_json, &install.metadata_info)
+                .map_err(|e| format!("{}: {}", install.filename, e))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    // Check if Bambu Studio is running
+    let bs_running = generator::is_bambu_studio_running();
+    if bs_running && !force {
+        return Err(
+            "Bambu Studio is running. Use force=true to install anyway, but restart BS to see changes."
+                .to_string(),
         );
     }
 
-    info!("Installed profile '{}' to {:?}", profile_name, target_path);
+    let user_dir = user_filament_dir_for_install(paths)?;
 
-    Ok(InstallResult {
-        installed_path: target_path.to_string_lossy().to_string(),
-        profile_name,
-        bambu_studio_was_running: bs_running,
-    })
+    // Build target paths
+    let targets: Vec<PathBuf> = installs
+        .iter()
+        .map(|install| user_dir.join(&install.filename))
+        .collect();
+    for target_path in targets.iter().filter(|t| t.exists()) {
+        info!("Overwriting existing profile at {:?}", target_path);
+    }
+    // An overwritten preset keeps its identity, so its cloud copy is
+    // updated rather than duplicated
+    let checked = checked
+        .into_iter()
+        .zip(&targets)
+        .map(|((profile, mut metadata), target_path)| {
+            let existing = read_profile_metadata(target_path)
+                .map_err(|e| format!("Failed to read {:?}: {}", target_path, e))?;
+            metadata.mark_written_over(existing);
+            Ok((profile, metadata))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    // Install the profiles, their metadata and their registration in
+    // BambuStudio.conf together, so a failure part way leaves none of them
+    // behind. The name in the conf's filaments array is the file stem
+    // (filename without .json).
+    let file_stems: Vec<&str> = targets
+        .iter()
+        .zip(installs)
+        .map(|(target_path, install)| {
+            target_path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or(&install.filename)
+        })
+        .collect();
+    let install = || -> anyhow::Result<()> {
+        let mut transaction = InstallTransaction::begin(&user_dir.join(INSTALL_JOURNAL_DIR))?;
+        for ((profile, metadata), target_path) in checked.iter().zip(&targets) {
+            transaction.stage_profile(profile, target_path)?;
+            transaction.stage_metadata(metadata, target_path)?;
+        }
+        transaction.stage_conf_registration(&paths.config_root, &file_stems)?;
+        transaction.commit()
+    };
+    install().map_err(|e| format!("Failed to install profile: {}", e))?;
+
+    Ok(checked
+        .iter()
+        .zip(targets)
+        .map(|((profile, _), target_path)| {
+            let profile_name = profile.name().unwrap_or("<unnamed>").to_string();
+            info!("Installed profile '{}' to {:?}", profile_name, target_path);
+            InstallResult {
+                installed_path: target_path.to_string_lossy().to_string(),
+                profile_name,
+                bambu_studio_was_running: bs_running,
+                queued: false,
+            }
+        })
+        .collect())
 }
 
 /// Assert that `path` resolves to a location inside the user filament
 /// directory. Rejects both `..` traversal and absolute paths outside the
 /// allowed root. Returns the canonical target path on success.
 ///
-/// Used by all mutation commands (`update_profile_field`, `save_profile_specs`,
-/// `duplicate_profile`, `delete_profile`) to prevent a compromised renderer
+/// Used by all mutation commands (`update_profile_field`, `update_profile_fields`,
+/// `save_profile_specs`, `duplicate_profile`) to prevent a compromised renderer
 /// or a frontend bug from rewriting arbitrary files on disk.
 ///
 /// If `must_exist` is false the target itself is allowed to be missing (used
 /// by `duplicate_profile` writing a new file); the parent directory is
 /// canonicalised instead.
-fn assert_in_user_filament_dir(
+pub(crate) fn assert_in_user_filament_dir(
     file_path: &std::path::Path,
     must_exist: bool,
 ) -> Result<std::path::PathBuf, String> {
@@ -622,6 +1094,18 @@ fn assert_in_user_filament_dir(
     let user_dir = paths
         .user_filament_dir()
         .ok_or_else(|| "User filament directory not found".to_string())?;
+    assert_in_dir(file_path, must_exist, &user_dir)
+}
+
+/// Assert that `file_path` resolves to a location inside `user_dir`.
+///
+/// The path check behind `assert_in_user_filament_dir`, also used for the
+/// process and machine preset directories.
+pub(crate) fn assert_in_dir(
+    file_path: &std::path::Path,
+    must_exist: bool,
+    user_dir: &std::path::Path,
+) -> Result<std::path::PathBuf, String> {
     let canonical_user_dir = user_dir
         .canonicalize()
         .map_err(|e| format!("Cannot resolve user directory: {}", e))?;
@@ -647,46 +1131,31 @@ fn assert_in_user_filament_dir(
 
     if !canonical.starts_with(&canonical_user_dir) {
         return Err(format!(
-            "Refusing to touch path outside the user filament directory: {:?}",
+            "Refusing to touch path outside the user directory: {:?}",
             file_path
         ));
     }
     Ok(canonical)
 }
 
-/// Delete a user filament profile and its companion .info file.
-///
-/// Safety: Validates that the path is within the user filament directory
-/// to prevent deletion of arbitrary files.
-#[tauri::command]
-pub fn delete_profile(path: String) -> Result<(), String> {
-    let file_path = std::path::Path::new(&path);
-    assert_in_user_filament_dir(file_path, true)?;
-
-    // Delete the JSON file
-    std::fs::remove_file(&file_path).map_err(|e| format!("Failed to delete profile: {}", e))?;
-
-    // Delete companion .info file if it exists
-    let info_path = file_path.with_extension("info");
-    if info_path.exists() {
-        if let Err(e) = std::fs::remove_file(&info_path) {
-            info!("Could not delete companion .info file: {}", e);
-        }
-    }
-
-    info!("Deleted profile at {:?}", file_path);
-    Ok(())
-}
-
 /// Update a single field in a profile and write it back atomically.
 ///
 /// The value is a JSON string that will be parsed as a serde_json::Value.
+/// The profile is linted after the change and not written if the field has
+/// errors; problems in other fields don't block the edit.
+///
+/// With `expected_version` (from `ProfileDetail::version`), the write is
+/// refused with a `ProfileConflict` if the file changed since.
+/// While Bambu Studio is running the edit is queued until it exits (see
+/// `pending_writes`) and the returned detail has `queued` set.
 /// Returns the updated ProfileDetail.
 #[tauri::command]
 pub fn update_profile_field(
+    app: tauri::AppHandle,
     path: String,
     key: String,
     value: String,
+    expected_version: Option<String>,
 ) -> Result<ProfileDetail, String> {
     let file_path = std::path::Path::new(&path);
     assert_in_user_filament_dir(file_path, true)?;
@@ -697,9 +1166,43 @@ pub fn update_profile_field(
     let json_value: serde_json::Value =
         serde_json::from_str(&value).map_err(|e| format!("Invalid JSON value: {}", e))?;
 
-    profile.raw_mut().insert(key.clone(), json_value);
+    let edit = |p: &mut FilamentProfile| {
+        p.raw_mut().insert(key.clone(), json_value.clone());
+    };
+    ensure_profile_unchanged(file_path, expected_version.as_deref(), &profile, edit)?;
+    edit(&mut profile);
+
+    let extruder_count = BambuPaths::detect()
+        .ok()
+        .and_then(|paths| profile_extruder_count(&load_machine_registry(&paths), &profile));
+    let lint = lint_profile(&profile, &LintOptions { extruder_count }).for_keys(&[&key]);
+    if lint.has_errors() {
+        return Err(format!(
+            "Refusing to write an invalid profile: {}",
+            lint.error_summary()
+        ));
+    }
+
+    if generator::is_bambu_studio_running() {
+        pending_writes::enqueue(
+            &app,
+            PendingOperation::UpdateField {
+                path: path.clone(),
+                key,
+                value: json_value,
+                expected_version: Some(
+                    ProfileVersion::of_file(file_path)
+                        .map_err(|e| e.to_string())?
+                        .token(),
+                ),
+            },
+        )?;
+        let mut detail = read_profile_command(path)?;
+        detail.queued = true;
+        return Ok(detail);
+    }
 
-    write_profile_atomic(&profile, file_path)
+    write_edited_profile(&profile, file_path)
         .map_err(|e| format!("Failed to write profile: {}", e))?;
 
     info!("Updated field '{}' in {:?}", key, file_path);
@@ -708,6 +1211,58 @@ pub fn update_profile_field(
     read_profile_command(path)
 }
 
+/// Set one field of the user profile at `path` and write it back, as a
+/// queued `update_profile_field` does once Bambu Studio has exited. The
+/// edit is checked again as it would be now: refused if the file is no
+/// longer at `expected_version`, or if the field doesn't lint.
+pub(crate) fn write_profile_field(
+    path: &Path,
+    key: &str,
+    value: &serde_json::Value,
+    expected_version: Option<&str>,
+) -> Result<(), String> {
+    assert_in_user_filament_dir(path, true)?;
+    ensure_unchanged_since_queued(path, expected_version)?;
+    let mut profile = read_profile(path).map_err(|e| e.to_string())?;
+    profile.raw_mut().insert(key.to_string(), value.clone());
+
+    let extruder_count = BambuPaths::detect()
+        .ok()
+        .and_then(|paths| profile_extruder_count(&load_machine_registry(&paths), &profile));
+    let lint = lint_profile(&profile, &LintOptions { extruder_count }).for_keys(&[key]);
+    if lint.has_errors() {
+        return Err(format!(
+            "Refusing to write an invalid profile: {}",
+            lint.error_summary()
+        ));
+    }
+
+    write_edited_profile(&profile, path).map_err(|e| format!("Failed to write profile: {}", e))?;
+    info!("Wrote queued field '{}' in {:?}", key, path);
+    Ok(())
+}
+
+/// Refuse a queued edit of `path` if the file changed since it was queued,
+/// e.g. because the profile was also edited in Bambu Studio.
+pub(crate) fn ensure_unchanged_since_queued(
+    path: &Path,
+    expected_version: Option<&str>,
+) -> Result<(), String> {
+    let Some(expected) = expected_version else {
+        return Ok(());
+    };
+    let unchanged = match ProfileVersion::parse(expected) {
+        Some(version) => version.is_current(path).map_err(|e| e.to_string())?,
+        None => false,
+    };
+    if unchanged {
+        Ok(())
+    } else {
+        warn!("Refusing queued write to changed profile {:?}", path);
+        Err("Profile changed on disk since this edit was queued; it was not applied".to_string())
+    }
+}
+
 /// Duplicate a profile with a new name and IDs.
 ///
 /// Copies the profile, assigns new filament_id and name, and writes it
@@ -746,11 +1301,13 @@ pub fn duplicate_profile(path: String, new_name: String) -> Result<ProfileDetail
     // pass in a target path stays safe.
     assert_in_user_filament_dir(&target_path, false)?;
 
-    // Create metadata
-    let metadata = ProfileMetadata {
+    // Create metadata, owned by the active account so it syncs there
+    let mut metadata = ProfileMetadata {
         setting_id: new_id.clone(),
+        user_id: paths.preset_folder.clone().unwrap_or_default(),
         ..ProfileMetadata::default()
     };
+    metadata.mark_created();
 
     write_profile_with_metadata(&profile, &target_path, &metadata)
         .map_err(|e| format!("Failed to write duplicated profile: {}", e))?;
@@ -776,21 +1333,105 @@ pub fn extract_specs_from_profile(
 /// Save edited FilamentSpecs back to an existing profile.
 ///
 /// Reads the profile, applies the specs overrides (same mapping as generate),
-/// and writes it back atomically. Returns the updated ProfileDetail.
+/// and writes it back atomically. Per-extruder fields are sized for the
+/// profile's printer, or keep their current size when its machine preset
+/// isn't installed. Takes `expected_version` as `update_profile_field`
+/// does, and like it is queued while Bambu Studio is running. Returns the
+/// updated ProfileDetail.
 #[tauri::command]
 pub fn save_profile_specs(
+    app: tauri::AppHandle,
     path: String,
     specs: crate::scraper::types::FilamentSpecs,
+    expected_version: Option<String>,
 ) -> Result<ProfileDetail, String> {
     let file_path = std::path::Path::new(&path);
     assert_in_user_filament_dir(file_path, true)?;
 
-    let mut profile = read_profile(file_path).map_err(|e| e.to_string())?;
+    let profile = read_profile(file_path).map_err(|e| e.to_string())?;
+    specs_edited_profile(file_path, profile, &specs, expected_version.as_deref())?;
+
+    if generator::is_bambu_studio_running() {
+        pending_writes::enqueue(
+            &app,
+            PendingOperation::SaveSpecs {
+                path: path.clone(),
+                specs,
+                expected_version: Some(
+                    ProfileVersion::of_file(file_path)
+                        .map_err(|e| e.to_string())?
+                        .token(),
+                ),
+            },
+        )?;
+        let mut detail = read_profile_command(path)?;
+        detail.queued = true;
+        return Ok(detail);
+    }
+
+    write_profile_specs(file_path, &specs, None)
+}
+
+/// Save edited specs to the user profile at `path`, as `save_profile_specs`
+/// does directly or once Bambu Studio has exited. A queued save is refused
+/// if the file is no longer at `expected_version`.
+pub(crate) fn write_profile_specs(
+    path: &Path,
+    specs: &crate::scraper::types::FilamentSpecs,
+    expected_version: Option<&str>,
+) -> Result<ProfileDetail, String> {
+    assert_in_user_filament_dir(path, true)?;
+    ensure_unchanged_since_queued(path, expected_version)?;
+    let profile = read_profile(path).map_err(|e| e.to_string())?;
+    let profile = specs_edited_profile(path, profile, specs, None)?;
+
+    write_edited_profile(&profile, path).map_err(|e| format!("Failed to write profile: {}", e))?;
+
+    info!("Saved edited specs to {:?}", path);
+
+    // Return updated detail
+    read_profile_command(path.to_string_lossy().to_string())
+}
+
+/// `profile` with `specs` applied, refused with a `ProfileConflict` if the
+/// file at `path` isn't at `expected_version` or if the result doesn't lint.
+fn specs_edited_profile(
+    path: &Path,
+    mut profile: FilamentProfile,
+    specs: &crate::scraper::types::FilamentSpecs,
+    expected_version: Option<&str>,
+) -> Result<FilamentProfile, String> {
+    let extruder_count = BambuPaths::detect()
+        .ok()
+        .and_then(|paths| profile_extruder_count(&load_machine_registry(&paths), &profile));
+    let edit = |p: &mut FilamentProfile| apply_specs_edit(p, specs, extruder_count);
+    ensure_profile_unchanged(path, expected_version, &profile, edit)?;
+    edit(&mut profile);
+
+    let lint = lint_profile(&profile, &LintOptions { extruder_count });
+    if lint.has_errors() {
+        return Err(format!(
+            "Refusing to write an invalid profile: {}",
+            lint.error_summary()
+        ));
+    }
+    Ok(profile)
+}
 
-    // Apply specs to the existing profile (overwrites only the mapped fields)
-    generator::apply_specs_to_profile(&mut profile, &specs);
+/// Apply edited specs to an existing profile: the mapped fields, sized for
+/// `extruder_count` (or the profile's current size), and the name rebuilt
+/// from brand, material and serial with its `@printer` suffix kept.
+fn apply_specs_edit(
+    profile: &mut FilamentProfile,
+    specs: &crate::scraper::types::FilamentSpecs,
+    extruder_count: Option<usize>,
+) {
+    let current_count = profile.nozzle_temperature().map_or(1, |temps| temps.len());
+    generator::apply_specs_to_profile(profile, specs, extruder_count.unwrap_or(current_count));
+    if let Some(count) = extruder_count {
+        generator::fit_per_extruder_arrays(profile, count);
+    }
 
-    // Recompose the full profile name from brand + material + serial, preserving @printer suffix
     let existing_name = profile.name().unwrap_or("").to_string();
     let printer_suffix = existing_name
         .find(" @")
@@ -799,19 +1440,147 @@ pub fn save_profile_specs(
     let new_name = if specs.serial.is_empty() {
         format!("{} {}{}", specs.brand, specs.material, printer_suffix)
     } else {
-        format!("{} {} {}{}", specs.brand, specs.material, specs.serial, printer_suffix)
+        format!(
+            "{} {} {}{}",
+            specs.brand, specs.material, specs.serial, printer_suffix
+        )
     };
     profile.set_string("name", new_name);
+}
 
-    write_profile_atomic(&profile, file_path)
-        .map_err(|e| format!("Failed to write profile: {}", e))?;
+/// Rewrite a flattened user profile as a delta over a parent profile.
+///
+/// The profile keeps only the fields that differ from `parent` (default: the
+/// system preset for its material and printer, see
+/// `generator::delta_parent_name`) and inherits the rest, so it picks up
+/// fixes when Bambu Studio updates its system presets. The conversion is
+/// refused if the profile wouldn't resolve back to exactly its current
+/// values. Its `.info` file, if any, gets the parent's setting_id as
+/// `base_id`.
+///
+/// With `expected_version` the conversion is refused with a
+/// `ProfileConflict` if the file changed since it was loaded. While Bambu
+/// Studio is running the conversion is queued until it exits (see
+/// `pending_writes`); otherwise the library is snapshotted first. Returns
+/// the updated ProfileDetail.
+#[tauri::command]
+pub async fn convert_profile_to_delta(
+    app: tauri::AppHandle,
+    path: String,
+    parent: Option<String>,
+    expected_version: Option<String>,
+) -> Result<ProfileDetail, String> {
+    let file_path = PathBuf::from(&path);
+    assert_in_user_filament_dir(&file_path, true)?;
+    let paths = BambuPaths::detect().map_err(|e| format!("Bambu Studio not found: {}", e))?;
 
-    info!("Saved edited specs to {:?}", file_path);
+    let profile = read_profile(&file_path).map_err(|e| e.to_string())?;
+    ensure_profile_unchanged(&file_path, expected_version.as_deref(), &profile, |_| {})?;
+    let registry = load_filament_registry(&paths)?;
+    let parent = match parent {
+        Some(parent) => parent,
+        None => default_delta_parent(&registry, &profile)?,
+    };
+    // Refuse now rather than queue a conversion that can't work
+    build_delta(&profile, &parent, &registry)?;
+
+    if generator::is_bambu_studio_running() {
+        pending_writes::enqueue(
+            &app,
+            PendingOperation::ConvertToDelta {
+                path: path.clone(),
+                parent,
+            },
+        )?;
+        let mut detail = read_profile_command(path)?;
+        detail.queued = true;
+        return Ok(detail);
+    }
 
-    // Return updated detail
+    let name = profile.name().unwrap_or(&path).to_string();
+    super::snapshot::snapshot_before(&app, &paths, &format!("delta conversion of {}", name))
+        .await?;
+    convert_to_delta_for_paths(&paths, &file_path, &parent)?;
     read_profile_command(path)
 }
 
+/// The system preset `profile` would inherit from as a delta: the one for
+/// its material and first compatible printer.
+fn default_delta_parent(
+    registry: &ProfileRegistry,
+    profile: &FilamentProfile,
+) -> Result<String, String> {
+    let material = profile.filament_type().unwrap_or_default();
+    let printer = profile
+        .compatible_printers()
+        .and_then(|printers| printers.first().copied())
+        .ok_or_else(|| {
+            "Profile has no compatible printer; choose a parent to inherit from".to_string()
+        })?;
+    let material_type = crate::scraper::types::MaterialType::from_str(material);
+    generator::delta_parent_name(registry, &material_type, printer).ok_or_else(|| {
+        format!(
+            "No installed system preset for {} on {} to inherit from",
+            material, printer
+        )
+    })
+}
+
+/// `profile` as a linted delta over `parent`.
+fn build_delta(
+    profile: &FilamentProfile,
+    parent: &str,
+    registry: &ProfileRegistry,
+) -> Result<FilamentProfile, String> {
+    let delta = minimize(profile, parent, registry)
+        .map_err(|e| format!("Failed to convert profile: {}", e))?;
+    let lint = lint_profile(&delta, &LintOptions::default());
+    if lint.has_errors() {
+        return Err(format!(
+            "Refusing to write an invalid profile: {}",
+            lint.error_summary()
+        ));
+    }
+    Ok(delta)
+}
+
+/// Rewrite the user profile at `path` as a delta over `parent`, as a
+/// queued `convert_profile_to_delta` does once Bambu Studio has exited.
+pub(crate) fn convert_to_delta_for_paths(
+    paths: &BambuPaths,
+    path: &Path,
+    parent: &str,
+) -> Result<(), String> {
+    let user_dir = paths
+        .user_filament_dir()
+        .ok_or_else(|| "User filament directory not found".to_string())?;
+    assert_in_dir(path, true, &user_dir)?;
+    let profile = read_profile(path).map_err(|e| e.to_string())?;
+    let registry = load_filament_registry(paths)?;
+    let delta = build_delta(&profile, parent, &registry)?;
+
+    write_profile_atomic(&delta, path).map_err(|e| format!("Failed to write profile: {}", e))?;
+    if let Ok(Some(mut metadata)) = read_profile_metadata(path) {
+        metadata.base_id = registry
+            .get_by_name(parent)
+            .and_then(|p| p.setting_id())
+            .unwrap_or_default()
+            .to_string();
+        metadata.mark_modified();
+        write_profile_metadata_atomic(&metadata, &path.with_extension("info"))
+            .map_err(|e| format!("Failed to write profile metadata: {}", e))?;
+    }
+
+    info!(
+        "Converted {:?} to a delta of '{}' ({} -> {} fields)",
+        path,
+        parent,
+        profile.field_count(),
+        delta.field_count()
+    );
+    Ok(())
+}
+
 /// A group of diffs for a single category.
 #[derive(Debug, Clone, Serialize)]
 pub struct DiffCategory {
@@ -829,45 +1598,6 @@ pub struct CompareResult {
     pub changed_fields: usize,
 }
 
-/// Map a BS profile key to a display category.
-fn key_to_category(key: &str) -> &'static str {
-    match key {
-        k if k.contains("temperature") || k.contains("temp") => "Temperature",
-        k if k.contains("speed")
-            || k.contains("flow")
-            || k.contains("volumetric")
-            || k.contains("acceleration")
-            || k.contains("jerk") =>
-        {
-            "Speed & Flow"
-        }
-        k if k.contains("fan") || k.contains("cool") || k.contains("slow_down") => "Cooling & Fan",
-        k if k.contains("retract") || k.contains("wipe") || k.contains("z_hop") => "Retraction",
-        k if k.contains("density")
-            || k.contains("diameter")
-            || k.contains("cost")
-            || k.contains("vitrification")
-            || k.contains("shrinkage") =>
-        {
-            "Physical Properties"
-        }
-        k if k.contains("name")
-            || k.contains("id")
-            || k.contains("version")
-            || k.contains("inherits")
-            || k.contains("from")
-            || k.contains("vendor")
-            || k.contains("type")
-            || k.contains("compatible")
-            || k.contains("setting")
-            || k.contains("instantiation") =>
-        {
-            "Identity & Metadata"
-        }
-        _ => "Other",
-    }
-}
-
 /// Compare two profiles side-by-side, returning differences grouped by category.
 #[tauri::command]
 pub fn compare_profiles(
@@ -878,9 +1608,25 @@ pub fn compare_profiles(
     let profile_a = read_profile(std::path::Path::new(&path_a)).map_err(|e| e.to_string())?;
     let profile_b = read_profile(std::path::Path::new(&path_b)).map_err(|e| e.to_string())?;
 
-    let raw_a = profile_a.raw();
-    let raw_b = profile_b.raw();
+    Ok(compare_preset_maps(
+        profile_a.name().unwrap_or("<unnamed>"),
+        profile_a.raw(),
+        profile_b.name().unwrap_or("<unnamed>"),
+        profile_b.raw(),
+        show_identical,
+    ))
+}
 
+/// Field-by-field comparison of two raw presets, grouped by category.
+///
+/// Shared by `compare_profiles` and the process/machine preset commands.
+pub(crate) fn compare_preset_maps(
+    name_a: &str,
+    raw_a: &serde_json::Map<String, serde_json::Value>,
+    name_b: &str,
+    raw_b: &serde_json::Map<String, serde_json::Value>,
+    show_identical: bool,
+) -> CompareResult {
     // Collect all keys from both profiles
     let mut all_keys: Vec<String> = raw_a
         .keys()
@@ -897,6 +1643,7 @@ pub fn compare_profiles(
     // Build diffs grouped by category
     let mut category_map: std::collections::BTreeMap<&str, Vec<ProfileDiff>> =
         std::collections::BTreeMap::new();
+    let fields = dictionary();
 
     for key in &all_keys {
         let val_a = raw_a.get(key);
@@ -910,10 +1657,10 @@ pub fn compare_profiles(
         }
 
         if is_different || show_identical {
-            let cat = key_to_category(key);
+            let cat = fields.category(key);
             category_map.entry(cat).or_default().push(ProfileDiff {
                 key: key.clone(),
-                label: key_to_label(key),
+                label: fields.label(key),
                 base_value: display_a,
                 new_value: display_b,
             });
@@ -928,42 +1675,28 @@ pub fn compare_profiles(
         })
         .collect();
 
-    Ok(CompareResult {
-        profile_a_name: profile_a.name().unwrap_or("<unnamed>").to_string(),
-        profile_b_name: profile_b.name().unwrap_or("<unnamed>").to_string(),
+    CompareResult {
+        profile_a_name: name_a.to_string(),
+        profile_b_name: name_b.to_string(),
         categories,
         total_fields,
         changed_fields,
-    })
+    }
 }
 
 /// Compare two profiles field-by-field and return a list of differences.
 ///
-/// Skips identity/metadata fields that always differ (name, filament_id, etc.)
-/// and only reports printing-relevant setting changes.
+/// Skips identity fields that always differ (name, filament_id, etc.; see
+/// `identity` in the field dictionary) and only reports printing-relevant
+/// setting changes.
 fn compute_profile_diffs(base: &FilamentProfile, generated: &FilamentProfile) -> Vec<ProfileDiff> {
-    // Fields to skip — these are identity/metadata, not actual settings
-    let skip_fields: &[&str] = &[
-        "name",
-        "filament_id",
-        "filament_settings_id",
-        "setting_id",
-        "from",
-        "inherits",
-        "instantiation",
-        "compatible_printers",
-        "compatible_printers_condition",
-        "filament_vendor",
-        "filament_type",
-        "version",
-    ];
-
+    let fields = dictionary();
     let mut diffs = Vec::new();
     let base_raw = base.raw();
     let gen_raw = generated.raw();
 
     for (key, gen_value) in gen_raw.iter() {
-        if skip_fields.contains(&key.as_str()) {
+        if fields.is_identity(key) {
             continue;
         }
 
@@ -974,7 +1707,7 @@ fn compute_profile_diffs(base: &FilamentProfile, generated: &FilamentProfile) ->
         if base_str != gen_str {
             diffs.push(ProfileDiff {
                 key: key.clone(),
-                label: key_to_label(key),
+                label: fields.label(key),
                 base_value: base_str,
                 new_value: gen_str,
             });
@@ -1008,21 +1741,6 @@ fn value_to_display(value: Option<&serde_json::Value>) -> String {
     }
 }
 
-/// Convert a snake_case profile key to a human-readable label.
-fn key_to_label(key: &str) -> String {
-    key.replace('_', " ")
-        .split(' ')
-        .map(|word| {
-            let mut chars = word.chars();
-            match chars.next() {
-                None => String::new(),
-                Some(c) => c.to_uppercase().to_string() + chars.as_str(),
-            }
-        })
-        .collect::<Vec<_>>()
-        .join(" ")
-}
-
 /// A base profile match from Bambu Studio system profiles.
 #[derive(Debug, Clone, Serialize)]
 pub struct BaseProfileMatch {
@@ -1095,6 +1813,57 @@ pub fn refresh_base_profile_index() -> Result<(), String> {
     Ok(())
 }
 
+/// A profile matched by `query_profiles`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfileQueryMatch {
+    pub name: String,
+    pub path: String,
+    pub filament_type: Option<String>,
+    /// Each field the query mentions, with this profile's value for it.
+    pub fields: Vec<(String, String)>,
+}
+
+/// Result of `query_profiles`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfileQueryResult {
+    /// Matches sorted by name, at most `QUERY_RESULT_LIMIT` of them.
+    pub matches: Vec<ProfileQueryMatch>,
+    /// Number of matching profiles before truncation.
+    pub total: usize,
+}
+
+const QUERY_RESULT_LIMIT: usize = 200;
+
+/// Find system and user profiles matching a `profile::query` expression,
+/// e.g. `filament_type = PETG and nozzle_temperature[0] > 250`.
+///
+/// Profiles are matched with their inheritance resolved, from the same
+/// cached index as `search_base_profiles`.
+#[tauri::command]
+pub fn query_profiles(query: String) -> Result<ProfileQueryResult, String> {
+    let parsed = ProfileQuery::parse(&query).map_err(|e| format!("Invalid query: {}", e))?;
+    let index = get_or_build_base_profile_index()?;
+
+    let mut seen_paths = HashSet::new();
+    let mut matches: Vec<ProfileQueryMatch> = index
+        .iter()
+        .filter(|e| parsed.matches(&e.resolved))
+        .filter(|e| seen_paths.insert(e.path.clone()))
+        .map(|e| ProfileQueryMatch {
+            name: e.name.clone(),
+            path: e.path.clone(),
+            filament_type: e.filament_type.clone(),
+            fields: parsed.field_values(&e.resolved),
+        })
+        .collect();
+    matches.sort_by_key(|m| m.name.to_lowercase());
+    let total = matches.len();
+    matches.truncate(QUERY_RESULT_LIMIT);
+
+    info!("Query '{}' matched {} profiles", query, total);
+    Ok(ProfileQueryResult { matches, total })
+}
+
 fn collect_target_printer_labels(dir: &std::path::Path, labels: &mut HashSet<String>) {
     if !dir.exists() {
         return;
@@ -1192,6 +1961,9 @@ struct BaseProfileIndexEntry {
     filament_type: Option<String>,
     ftype_lower: String,
     path: String,
+    /// The profile with its inheritance resolved, for `query_profiles`.
+    /// Shared so snapshots of the index stay cheap to clone.
+    resolved: Arc<serde_json::Map<String, serde_json::Value>>,
 }
 
 struct BaseProfileCache {
@@ -1270,9 +2042,14 @@ fn build_base_profile_index() -> Result<Vec<BaseProfileIndexEntry>, String> {
         }
     }
 
+    // Resolve against system + user profiles so queries see inherited
+    // values. Without a registry, entries hold their own fields only.
+    let registry = load_filament_registry(&paths)
+        .map_err(|e| warn!("Indexing profiles unresolved: {}", e))
+        .ok();
     let mut entries = Vec::new();
     for root in &roots {
-        index_dir_into(root, &mut entries);
+        index_dir_into(root, registry.as_ref(), &mut entries);
     }
 
     info!(
@@ -1284,7 +2061,11 @@ fn build_base_profile_index() -> Result<Vec<BaseProfileIndexEntry>, String> {
     Ok(entries)
 }
 
-fn index_dir_into(dir: &std::path::Path, out: &mut Vec<BaseProfileIndexEntry>) {
+fn index_dir_into(
+    dir: &std::path::Path,
+    registry: Option<&ProfileRegistry>,
+    out: &mut Vec<BaseProfileIndexEntry>,
+) {
     for entry in WalkDir::new(dir).into_iter().filter_map(|e| e.ok()) {
         let path = entry.path();
         if !path.is_file() {
@@ -1304,12 +2085,16 @@ fn index_dir_into(dir: &std::path::Path, out: &mut Vec<BaseProfileIndexEntry>) {
         let filament_type = profile.filament_type().map(|s| s.to_string());
         let name_lower = name.to_lowercase();
         let ftype_lower = filament_type.as_deref().unwrap_or("").to_lowercase();
+        let resolved = registry
+            .and_then(|r| resolve_inheritance(&profile, r).ok())
+            .map_or_else(|| profile.raw().clone(), |p| p.raw().clone());
         out.push(BaseProfileIndexEntry {
             name,
             name_lower,
             filament_type,
             ftype_lower,
             path: path.to_string_lossy().to_string(),
+            resolved: Arc::new(resolved),
         });
     }
 }
@@ -1352,10 +2137,18 @@ fn filter_base_profile_index(
 #[cfg(test)]
 mod tests {
     use super::{
-        build_target_printer_options, filter_base_profile_index, parse_target_printer_label,
-        BaseProfileIndexEntry, DEFAULT_NOZZLE_SIZE, DEFAULT_TARGET_PRINTER_MODEL,
+        build_target_printer_options, convert_to_delta_for_paths, ensure_unchanged_since_queued,
+        filter_base_profile_index, generate_profile_for_paths, install_profile_for_paths,
+        parse_target_printer_label, BaseProfileIndexEntry, DEFAULT_NOZZLE_SIZE,
+        DEFAULT_TARGET_PRINTER_MODEL,
     };
+    use crate::profile::conflict::ProfileVersion;
+    use crate::profile::paths::BambuPaths;
+    use crate::profile::reader::{read_profile, read_profile_metadata};
+    use crate::profile::types::{FilamentProfile, ProfileMetadata};
+    use crate::scraper::types::FilamentSpecs;
     use std::collections::HashSet;
+    use std::path::{Path, PathBuf};
 
     fn entry(name: &str, ftype: &str, path: &str) -> BaseProfileIndexEntry {
         BaseProfileIndexEntry {
@@ -1364,6 +2157,7 @@ mod tests {
             filament_type: Some(ftype.to_string()),
             ftype_lower: ftype.to_lowercase(),
             path: path.to_string(),
+            resolved: Default::default(),
         }
     }
 
@@ -1463,4 +2257,140 @@ mod tests {
         let hits = filter_base_profile_index(&idx, "", Some(""));
         assert_eq!(hits.len(), 2);
     }
+
+    /// System filaments from the `include_system` fixture, user profiles
+    /// under `root`.
+    fn fixture_paths(root: &Path) -> BambuPaths {
+        std::fs::create_dir_all(root.join("user/12345/filament/base")).unwrap();
+        BambuPaths {
+            config_root: root.to_path_buf(),
+            system_filaments: PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                .join("tests/fixtures/include_system/BBL/filament"),
+            user_root: root.join("user"),
+            preset_folder: Some("12345".to_string()),
+        }
+    }
+
+    fn pla_specs() -> FilamentSpecs {
+        FilamentSpecs {
+            serial: "Matte".to_string(),
+            brand: "Polymaker".to_string(),
+            material: "PLA".to_string(),
+            nozzle_temperature: Some(215),
+            ..FilamentSpecs::default()
+        }
+    }
+
+    const X1C: &str = "Bambu Lab X1 Carbon 0.4 nozzle";
+
+    #[test]
+    fn delta_profiles_inherit_from_an_instantiable_preset() {
+        let root = tempfile::tempdir().unwrap();
+        let paths = fixture_paths(root.path());
+
+        let result =
+            generate_profile_for_paths(&paths, &pla_specs(), Some(X1C), None, None, true).unwrap();
+        let profile = FilamentProfile::from_json(&result.profile_json).unwrap();
+        assert_eq!(profile.inherits(), Some("Bambu PLA Basic @BBL X1C"));
+        let metadata = ProfileMetadata::from_info_string(&result.metadata_info).unwrap();
+        assert_eq!(metadata.base_id, "GFSA00");
+
+        // No system preset for this printer: refused rather than left
+        // without a base_id
+        let err = generate_profile_for_paths(
+            &paths,
+            &pla_specs(),
+            Some("Bambu Lab A1 0.4 nozzle"),
+            None,
+            None,
+            true,
+        )
+        .unwrap_err();
+        assert!(err.contains("No installed system preset"), "{}", err);
+    }
+
+    #[test]
+    fn installing_over_a_synced_profile_keeps_its_setting_id() {
+        let root = tempfile::tempdir().unwrap();
+        let paths = fixture_paths(root.path());
+        let result =
+            generate_profile_for_paths(&paths, &pla_specs(), Some(X1C), None, None, false).unwrap();
+        // Installed and synced to the cloud before this regeneration
+        let target = paths.user_filament_dir().unwrap().join(&result.filename);
+        std::fs::write(&target, &result.profile_json).unwrap();
+        let synced = ProfileMetadata {
+            user_id: "12345".to_string(),
+            setting_id: "PFUSsynced".to_string(),
+            updated_time: 1,
+            ..ProfileMetadata::default()
+        };
+        std::fs::write(target.with_extension("info"), synced.to_info_string()).unwrap();
+
+        install_profile_for_paths(
+            &paths,
+            &result.profile_json,
+            &result.metadata_info,
+            &result.filename,
+            false,
+        )
+        .unwrap();
+
+        let installed = read_profile_metadata(&target).unwrap().unwrap();
+        assert_eq!(installed.setting_id, "PFUSsynced");
+        assert_eq!(installed.user_id, "12345");
+        assert_eq!(installed.sync_info, "update");
+        assert!(installed.updated_time > 1);
+    }
+
+    #[test]
+    fn converted_profile_records_its_parent() {
+        let root = tempfile::tempdir().unwrap();
+        let paths = fixture_paths(root.path());
+        let parent = "Bambu PLA Basic @BBL X1C";
+        // A flattened profile built on the parent, as installed
+        let base = paths.system_filaments.join(format!("{}.json", parent));
+        let result = generate_profile_for_paths(
+            &paths,
+            &pla_specs(),
+            Some(X1C),
+            Some(base.to_str().unwrap()),
+            None,
+            false,
+        )
+        .unwrap();
+        let path = paths.user_filament_dir().unwrap().join(&result.filename);
+        std::fs::write(&path, &result.profile_json).unwrap();
+        std::fs::write(path.with_extension("info"), &result.metadata_info).unwrap();
+
+        convert_to_delta_for_paths(&paths, &path, parent).unwrap();
+
+        let profile = read_profile(&path).unwrap();
+        assert_eq!(profile.inherits(), Some(parent));
+        let metadata = read_profile_metadata(&path).unwrap().unwrap();
+        assert_eq!(metadata.base_id, "GFSA00");
+    }
+
+    #[test]
+    fn queued_edit_is_refused_once_the_profile_changed() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("PLA.json");
+        std::fs::write(&path, r#"{"name": "PLA", "nozzle_temperature": ["210"]}"#).unwrap();
+        let queued_at = ProfileVersion::of_file(&path).unwrap().token();
+
+        ensure_unchanged_since_queued(&path, Some(&queued_at)).unwrap();
+        // Queues written before versions were recorded are left unchecked
+        ensure_unchanged_since_queued(&path, None).unwrap();
+
+        // Saved again in Bambu Studio while the edit waited
+        std::fs::write(&path, r#"{"name": "PLA", "nozzle_temperature": ["225"]}"#).unwrap();
+        // Within the same millisecond the file would pass for unchanged
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(std::time::SystemTime::now() + std::time::Duration::from_secs(1))
+            .unwrap();
+        let err = ensure_unchanged_since_queued(&path, Some(&queued_at)).unwrap_err();
+        assert!(err.contains("changed on disk"), "{}", err);
+    }
 }
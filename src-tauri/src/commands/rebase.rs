@@ -0,0 +1,411 @@
+//! Tauri commands for rebasing generated profiles.
+//!
+//! `generate_profile_from_specs` and batch installs record each generation
+//! (specs, base, base fingerprint) in the app data directory.
+//! `check_profile_rebases` finds installed profiles whose base has changed
+//! since, regenerates them on the new base and returns the differences;
+//! `resolve_profile_rebase` then writes or dismisses each one.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use tauri::Manager;
+use tracing::{info, warn};
+use walkdir::WalkDir;
+
+use crate::commands::profile::{
+    assert_in_dir, compare_preset_maps, load_filament_registry, load_machine_registry,
+    profile_extruder_count, CompareResult,
+};
+use crate::commands::snapshot::snapshot_before;
+use crate::pending_writes::{self, PendingOperation};
+use crate::profile::generator;
+use crate::profile::lint::{lint_profile, LintOptions};
+use crate::profile::paths::BambuPaths;
+use crate::profile::reader::{read_profile, read_profile_metadata};
+use crate::profile::rebase::{rebase_profile, GenerationRecord, GenerationStore, Rebased};
+use crate::profile::types::ProfileMetadata;
+use crate::profile::writer::{write_profile_atomic, write_profile_metadata_atomic};
+
+/// An installed profile whose base changed since it was generated.
+#[derive(Debug, Clone, Serialize)]
+pub struct RebaseCandidate {
+    pub path: String,
+    pub profile_name: String,
+    pub base_name: String,
+    /// Installed profile (a) against the regenerated one (b).
+    pub diff: CompareResult,
+}
+
+/// Result of scanning the user filament directory for rebases.
+#[derive(Debug, Clone, Serialize)]
+pub struct RebaseReport {
+    pub candidates: Vec<RebaseCandidate>,
+    /// Profiles that have a generation record but couldn't be regenerated,
+    /// e.g. because their base profile was removed.
+    pub errors: Vec<String>,
+}
+
+pub(crate) fn generation_db_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get data dir: {}", e))?;
+    Ok(data_dir.join("generated_profiles.db"))
+}
+
+/// Store `record`. Best effort: a profile is still usable without one, it
+/// just won't be offered for rebasing.
+pub(crate) async fn record_generation(app: &tauri::AppHandle, record: GenerationRecord) {
+    let db_path = match generation_db_path(app) {
+        Ok(p) => p,
+        Err(e) => {
+            warn!("Not recording generation: {}", e);
+            return;
+        }
+    };
+    let result =
+        tokio::task::spawn_blocking(move || GenerationStore::new(&db_path)?.record(&record))
+            .await
+            .map_err(|e| format!("generation store join error: {}", e))
+            .and_then(|r| r);
+    if let Err(e) = result {
+        warn!("Failed to record generation: {}", e);
+    }
+}
+
+fn detect_paths() -> Result<BambuPaths, String> {
+    BambuPaths::detect().map_err(|e| format!("Bambu Studio not found: {}", e))
+}
+
+/// Find installed profiles whose base profile has changed since they were
+/// generated, and regenerate each from its recorded specs on the new base.
+///
+/// Nothing is written; accept or reject each candidate with
+/// `resolve_profile_rebase`. Records for profiles that are no longer
+/// installed are pruned after a month.
+#[tauri::command]
+pub async fn check_profile_rebases(app: tauri::AppHandle) -> Result<RebaseReport, String> {
+    let db_path = generation_db_path(&app)?;
+    tokio::task::spawn_blocking(move || {
+        let paths = detect_paths()?;
+        check_rebases_for_paths(&paths, &GenerationStore::new(&db_path)?)
+    })
+    .await
+    .map_err(|e| format!("rebase check join error: {}", e))?
+}
+
+fn check_rebases_for_paths(
+    paths: &BambuPaths,
+    store: &GenerationStore,
+) -> Result<RebaseReport, String> {
+    let user_dir = paths
+        .user_filament_dir()
+        .ok_or_else(|| "User filament directory not found".to_string())?;
+    let records: HashMap<String, GenerationRecord> = store
+        .list()?
+        .into_iter()
+        .map(|r| (r.setting_id.clone(), r))
+        .collect();
+    let registry = load_filament_registry(paths)?;
+    let machines = load_machine_registry(paths);
+
+    let mut installed_ids = HashSet::new();
+    let mut candidates = Vec::new();
+    let mut errors = Vec::new();
+    for entry in WalkDir::new(&user_dir).into_iter().filter_map(|e| e.ok()) {
+        let path = entry.path();
+        if !path.is_file() || path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+        let Ok(Some(metadata)) = read_profile_metadata(path) else {
+            continue;
+        };
+        installed_ids.insert(metadata.setting_id.clone());
+        let Some(record) = records.get(&metadata.setting_id) else {
+            continue;
+        };
+        let Ok(installed) = read_profile(path) else {
+            continue;
+        };
+
+        match rebase_profile(record, &installed, &registry, &machines) {
+            Ok(Some(rebased)) => {
+                let name = installed.name().unwrap_or("<unnamed>");
+                candidates.push(RebaseCandidate {
+                    path: path.to_string_lossy().to_string(),
+                    profile_name: name.to_string(),
+                    base_name: record.base_name.clone(),
+                    diff: compare_preset_maps(
+                        name,
+                        installed.raw(),
+                        name,
+                        rebased.profile.raw(),
+                        false,
+                    ),
+                });
+            }
+            Ok(None) => {}
+            Err(e) => errors.push(format!("{}: {}", record.profile_name, e)),
+        }
+    }
+    store.prune(&installed_ids)?;
+
+    info!(
+        "Rebase check: {} candidates, {} errors",
+        candidates.len(),
+        errors.len()
+    );
+    Ok(RebaseReport { candidates, errors })
+}
+
+/// Accept or reject the rebase of the profile at `path`.
+///
+/// Accepting regenerates the profile again (so the result matches the
+/// current base even if it changed since the check) and writes it in place
+/// after snapshotting the library. While Bambu Studio is running the write
+/// is queued until it exits (see `pending_writes`) and `true` is returned.
+/// Rejecting keeps the profile as it is and stops offering this base
+/// update for it.
+#[tauri::command]
+pub async fn resolve_profile_rebase(
+    app: tauri::AppHandle,
+    path: String,
+    accept: bool,
+) -> Result<bool, String> {
+    let db_path = generation_db_path(&app)?;
+    let paths = detect_paths()?;
+    if accept && generator::is_bambu_studio_running() {
+        // Refuse now rather than queue a rebase that can't be written
+        let check_path = path.clone();
+        tokio::task::spawn_blocking(move || {
+            prepare_rebase(
+                &paths,
+                &GenerationStore::new(&db_path)?,
+                Path::new(&check_path),
+                true,
+            )
+        })
+        .await
+        .map_err(|e| format!("rebase join error: {}", e))??;
+        pending_writes::enqueue(&app, PendingOperation::Rebase { path })?;
+        return Ok(true);
+    }
+    if accept {
+        snapshot_before(&app, &paths, &format!("rebase of {}", path)).await?;
+    }
+    tokio::task::spawn_blocking(move || {
+        resolve_rebase_for_paths(
+            &paths,
+            &GenerationStore::new(&db_path)?,
+            Path::new(&path),
+            accept,
+        )
+    })
+    .await
+    .map_err(|e| format!("rebase join error: {}", e))??;
+    Ok(false)
+}
+
+pub(crate) fn resolve_rebase_for_paths(
+    paths: &BambuPaths,
+    store: &GenerationStore,
+    file_path: &Path,
+    accept: bool,
+) -> Result<(), String> {
+    let (mut metadata, record, rebased) = prepare_rebase(paths, store, file_path, accept)?;
+
+    if !accept {
+        store.mark_rejected(&record.setting_id, &rebased.base_fingerprint)?;
+        info!("Rejected rebase of {:?}", file_path);
+        return Ok(());
+    }
+
+    write_profile_atomic(&rebased.profile, file_path)
+        .map_err(|e| format!("Failed to write profile: {}", e))?;
+    metadata.mark_modified();
+    write_profile_metadata_atomic(&metadata, &file_path.with_extension("info"))
+        .map_err(|e| format!("Failed to write profile metadata: {}", e))?;
+    store.mark_rebased(&record.setting_id, &rebased.base_fingerprint)?;
+
+    info!(
+        "Rebased {:?} onto the current '{}'",
+        file_path, record.base_name
+    );
+    Ok(())
+}
+
+/// Regenerate the profile at `path` on its current base, with its metadata
+/// and generation record. With `lint`, a regenerated profile with errors is
+/// refused.
+fn prepare_rebase(
+    paths: &BambuPaths,
+    store: &GenerationStore,
+    file_path: &Path,
+    lint: bool,
+) -> Result<(ProfileMetadata, GenerationRecord, Rebased), String> {
+    let user_dir = paths
+        .user_filament_dir()
+        .ok_or_else(|| "User filament directory not found".to_string())?;
+    assert_in_dir(file_path, true, &user_dir)?;
+
+    let metadata = read_profile_metadata(file_path)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Profile has no .info file".to_string())?;
+    let record = store
+        .get(&metadata.setting_id)?
+        .ok_or_else(|| "No generation record for this profile".to_string())?;
+    let installed = read_profile(file_path).map_err(|e| e.to_string())?;
+
+    let registry = load_filament_registry(paths)?;
+    let machines = load_machine_registry(paths);
+    let rebased = rebase_profile(&record, &installed, &registry, &machines)
+        .map_err(|e| format!("Failed to regenerate profile: {}", e))?
+        .ok_or_else(|| "Profile is already up to date with its base".to_string())?;
+
+    if lint {
+        let lint = lint_profile(
+            &rebased.profile,
+            &LintOptions {
+                extruder_count: profile_extruder_count(&machines, &rebased.profile),
+            },
+        );
+        if lint.has_errors() {
+            return Err(format!(
+                "Refusing to write an invalid profile: {}",
+                lint.error_summary()
+            ));
+        }
+    }
+    Ok((metadata, record, rebased))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::profile::generate_profile_for_paths;
+    use crate::scraper::types::FilamentSpecs;
+
+    const X1C: &str = "Bambu Lab X1 Carbon 0.4 nozzle";
+
+    /// A copy of the `include_system` filaments (so the base can be
+    /// updated) and a user directory, both under `root`.
+    fn fixture_paths(root: &Path) -> BambuPaths {
+        let fixtures = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/include_system/BBL/filament");
+        let system = root.join("system");
+        std::fs::create_dir_all(&system).unwrap();
+        for entry in std::fs::read_dir(fixtures).unwrap() {
+            let entry = entry.unwrap();
+            std::fs::copy(entry.path(), system.join(entry.file_name())).unwrap();
+        }
+        std::fs::create_dir_all(root.join("user/12345/filament/base")).unwrap();
+        BambuPaths {
+            config_root: root.to_path_buf(),
+            system_filaments: system,
+            user_root: root.join("user"),
+            preset_folder: Some("12345".to_string()),
+        }
+    }
+
+    /// Generate and install a PLA profile, recording its generation.
+    fn install_generated(paths: &BambuPaths, store: &GenerationStore) -> PathBuf {
+        let specs = FilamentSpecs {
+            serial: "Matte".to_string(),
+            brand: "Polymaker".to_string(),
+            material: "PLA".to_string(),
+            nozzle_temperature: Some(215),
+            ..FilamentSpecs::default()
+        };
+        let result =
+            generate_profile_for_paths(paths, &specs, Some(X1C), None, None, false).unwrap();
+        let path = paths.user_filament_dir().unwrap().join(&result.filename);
+        std::fs::write(&path, &result.profile_json).unwrap();
+        std::fs::write(path.with_extension("info"), &result.metadata_info).unwrap();
+        let metadata =
+            crate::profile::types::ProfileMetadata::from_info_string(&result.metadata_info)
+                .unwrap();
+        store
+            .record(&GenerationRecord::new(
+                metadata.setting_id,
+                result.profile_name,
+                specs,
+                Some(X1C.to_string()),
+                result.base_profile_used,
+                result.base_fingerprint,
+            ))
+            .unwrap();
+        path
+    }
+
+    /// Bambu Studio ships a new density for PLA.
+    fn update_base(paths: &BambuPaths) {
+        let base_path = paths.system_filaments.join("fdm_filament_pla.json");
+        let mut base: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&base_path).unwrap()).unwrap();
+        base["filament_density"] = serde_json::json!(["1.30"]);
+        std::fs::write(&base_path, base.to_string()).unwrap();
+    }
+
+    fn density(path: &Path) -> Option<String> {
+        read_profile(path)
+            .unwrap()
+            .get_first_array_value("filament_density")
+            .map(str::to_string)
+    }
+
+    #[test]
+    fn test_accepted_rebase_is_written_and_no_longer_offered() {
+        let root = tempfile::tempdir().unwrap();
+        let paths = fixture_paths(root.path());
+        let store = GenerationStore::new(&root.path().join("generated.db")).unwrap();
+        let path = install_generated(&paths, &store);
+        assert!(check_rebases_for_paths(&paths, &store)
+            .unwrap()
+            .candidates
+            .is_empty());
+
+        update_base(&paths);
+        let report = check_rebases_for_paths(&paths, &store).unwrap();
+        assert_eq!(report.candidates.len(), 1);
+        assert!(report.candidates[0]
+            .diff
+            .categories
+            .iter()
+            .flat_map(|c| &c.diffs)
+            .any(|d| d.key == "filament_density"));
+
+        let setting_id = read_profile_metadata(&path).unwrap().unwrap().setting_id;
+        resolve_rebase_for_paths(&paths, &store, &path, true).unwrap();
+        assert_eq!(density(&path).as_deref(), Some("1.30"));
+        // Still the same preset as far as Bambu Studio is concerned
+        let metadata = read_profile_metadata(&path).unwrap().unwrap();
+        assert_eq!(metadata.setting_id, setting_id);
+        assert!(check_rebases_for_paths(&paths, &store)
+            .unwrap()
+            .candidates
+            .is_empty());
+    }
+
+    #[test]
+    fn test_rejected_rebase_leaves_the_profile() {
+        let root = tempfile::tempdir().unwrap();
+        let paths = fixture_paths(root.path());
+        let store = GenerationStore::new(&root.path().join("generated.db")).unwrap();
+        let path = install_generated(&paths, &store);
+        let before = std::fs::read_to_string(&path).unwrap();
+
+        update_base(&paths);
+        resolve_rebase_for_paths(&paths, &store, &path, false).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), before);
+        assert!(check_rebases_for_paths(&paths, &store)
+            .unwrap()
+            .candidates
+            .is_empty());
+
+        // Nothing left to resolve
+        let err = resolve_rebase_for_paths(&paths, &store, &path, true).unwrap_err();
+        assert!(err.contains("up to date"), "{}", err);
+    }
+}
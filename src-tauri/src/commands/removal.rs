@@ -0,0 +1,150 @@
+//! Tauri commands for deleting and renaming user filament profiles (see
+//! `profile::removal`).
+//!
+//! Each operation has a preview command that returns the plan without
+//! writing anything. Running it re-plans against the library as it is
+//! then, snapshots the library first if any other profile is affected, and
+//! applies the plan in one transaction. Both need `force` while Bambu
+//! Studio is running, which would otherwise keep the old profile around.
+
+use std::path::{Path, PathBuf};
+
+use crate::commands::profile::{assert_in_dir, load_filament_registry};
+use crate::commands::snapshot::snapshot_before;
+use crate::profile::generator;
+use crate::profile::paths::BambuPaths;
+use crate::profile::reader::read_profile;
+use crate::profile::removal::{self, DependentPolicy, RemovalPlan};
+use crate::profile::writer::INSTALL_JOURNAL_DIR;
+
+/// A delete or a rename.
+enum Operation {
+    Delete(DependentPolicy),
+    Rename(String),
+}
+
+fn detect_paths() -> Result<BambuPaths, String> {
+    BambuPaths::detect().map_err(|e| format!("Bambu Studio not found: {}", e))
+}
+
+fn user_dir_for(paths: &BambuPaths) -> Result<PathBuf, String> {
+    paths
+        .user_filament_dir()
+        .ok_or_else(|| "User filament directory not found".to_string())
+}
+
+/// Plan `operation` on the user profile at `path`.
+fn plan_for_paths(
+    paths: &BambuPaths,
+    path: &Path,
+    operation: &Operation,
+) -> Result<RemovalPlan, String> {
+    let user_dir = user_dir_for(paths)?;
+    let path = assert_in_dir(path, true, &user_dir)?;
+    let profile = read_profile(&path).map_err(|e| e.to_string())?;
+    let name = profile
+        .name()
+        .ok_or_else(|| format!("Profile at {:?} has no name", path))?;
+    let registry = load_filament_registry(paths)?;
+
+    match operation {
+        Operation::Delete(policy) => removal::plan_delete(&registry, name, *policy),
+        Operation::Rename(new_name) => removal::plan_rename(&registry, name, new_name),
+    }
+    .map_err(|e| format!("{:#}", e))
+}
+
+/// Plan and apply `operation`, snapshotting the library first when other
+/// profiles are affected. Refuses while Bambu Studio is running unless
+/// `force` is set.
+async fn run(
+    app: tauri::AppHandle,
+    path: String,
+    operation: Operation,
+    force: bool,
+) -> Result<RemovalPlan, String> {
+    if !force && generator::is_bambu_studio_running() {
+        return Err(
+            "Bambu Studio is running. Use force=true to apply anyway, but restart BS to see changes."
+                .to_string(),
+        );
+    }
+    let paths = detect_paths()?;
+    let (plan, paths) = tokio::task::spawn_blocking(move || {
+        plan_for_paths(&paths, Path::new(&path), &operation).map(|plan| (plan, paths))
+    })
+    .await
+    .map_err(|e| format!("removal join error: {}", e))??;
+
+    if !plan.dependents.is_empty() {
+        let reason = match &plan.new_name {
+            Some(new_name) => format!("rename of {} to {}", plan.name, new_name),
+            None => format!("delete of {}", plan.name),
+        };
+        snapshot_before(&app, &paths, &reason).await?;
+    }
+
+    tokio::task::spawn_blocking(move || {
+        let journal_dir = user_dir_for(&paths)?.join(INSTALL_JOURNAL_DIR);
+        removal::apply_removal(&plan, &paths.config_root, &journal_dir)
+            .map_err(|e| format!("Failed to update profiles: {:#}", e))?;
+        Ok(plan)
+    })
+    .await
+    .map_err(|e| format!("removal join error: {}", e))?
+}
+
+/// Preview deleting a user filament profile: the profiles that inherit from
+/// it and what `policy` does to each, and the BambuStudio.conf change.
+#[tauri::command]
+pub fn preview_profile_delete(
+    path: String,
+    policy: Option<DependentPolicy>,
+) -> Result<RemovalPlan, String> {
+    let operation = Operation::Delete(policy.unwrap_or_default());
+    plan_for_paths(&detect_paths()?, Path::new(&path), &operation)
+}
+
+/// Delete a user filament profile and its companion .info file, unregister
+/// it from BambuStudio.conf and apply `policy` to its dependents.
+///
+/// A profile synced to Bambu cloud keeps its .info file, marked for
+/// deletion, so the next sync removes the cloud copy too.
+///
+/// Needs `force` while Bambu Studio is running.
+///
+/// Safety: Validates that the path is within the user filament directory
+/// to prevent deletion of arbitrary files.
+#[tauri::command]
+pub async fn delete_profile(
+    app: tauri::AppHandle,
+    path: String,
+    policy: Option<DependentPolicy>,
+    force: bool,
+) -> Result<RemovalPlan, String> {
+    let operation = Operation::Delete(policy.unwrap_or_default());
+    run(app, path, operation, force).await
+}
+
+/// Preview renaming a user filament profile.
+#[tauri::command]
+pub fn preview_profile_rename(path: String, new_name: String) -> Result<RemovalPlan, String> {
+    plan_for_paths(
+        &detect_paths()?,
+        Path::new(&path),
+        &Operation::Rename(new_name),
+    )
+}
+
+/// Rename a user filament profile, moving its file and BambuStudio.conf
+/// registration and pointing the profiles that inherit from it at the new
+/// name. Needs `force` while Bambu Studio is running.
+#[tauri::command]
+pub async fn rename_profile(
+    app: tauri::AppHandle,
+    path: String,
+    new_name: String,
+    force: bool,
+) -> Result<RemovalPlan, String> {
+    run(app, path, Operation::Rename(new_name), force).await
+}
@@ -0,0 +1,79 @@
+//! Tauri commands for emitting filament profiles for other slicers.
+
+use tracing::info;
+
+use crate::commands::profile::generate_profile_for_paths;
+use crate::profile::paths::BambuPaths;
+use crate::profile::slicer::{
+    EmittedProfile, OrcaSlicerEmitter, PrusaSlicerEmitter, SlicerEmitter, SlicerTarget,
+};
+use crate::scraper::types::FilamentSpecs;
+
+/// Render `specs` for each of `targets`.
+///
+/// The Bambu Studio target goes through the regular generator and needs
+/// `paths`; OrcaSlicer and PrusaSlicer profiles are self-contained and
+/// only use `target_printer` as their compatible printer.
+pub(crate) fn emit_for_targets(
+    paths: Option<&BambuPaths>,
+    specs: &FilamentSpecs,
+    targets: &[SlicerTarget],
+    target_printer: Option<&str>,
+) -> Result<Vec<EmittedProfile>, String> {
+    let mut emitted = Vec::with_capacity(targets.len());
+    for target in targets {
+        let profile = match target {
+            SlicerTarget::BambuStudio => {
+                let paths = paths.ok_or_else(|| {
+                    "Bambu Studio not found; it is required for the Bambu Studio target".to_string()
+                })?;
+                let result =
+                    generate_profile_for_paths(paths, specs, target_printer, None, None, false)?;
+                EmittedProfile {
+                    target: SlicerTarget::BambuStudio,
+                    name: result.profile_name,
+                    filename: result.filename,
+                    install_subdir: "user/<preset_folder>/filament/base".to_string(),
+                    content: result.profile_json,
+                    metadata_info: Some(result.metadata_info),
+                }
+            }
+            SlicerTarget::OrcaSlicer => OrcaSlicerEmitter {
+                compatible_printers: target_printer
+                    .map(|p| vec![p.to_string()])
+                    .unwrap_or_default(),
+            }
+            .emit(specs)
+            .map_err(|e| format!("Failed to emit OrcaSlicer profile: {}", e))?,
+            SlicerTarget::PrusaSlicer => PrusaSlicerEmitter::default()
+                .emit(specs)
+                .map_err(|e| format!("Failed to emit PrusaSlicer profile: {}", e))?,
+        };
+        info!(
+            "Emitted {} profile '{}'",
+            target.display_name(),
+            profile.name
+        );
+        emitted.push(profile);
+    }
+    Ok(emitted)
+}
+
+/// Generate profiles for several slicers from one set of specs (preview;
+/// nothing is written to disk).
+///
+/// `targets` accepts `"bambu_studio"`, `"orca_slicer"` and `"prusa_slicer"`
+/// (short forms like `"orca"` work too).
+#[tauri::command]
+pub fn generate_slicer_profiles(
+    specs: FilamentSpecs,
+    targets: Vec<String>,
+    target_printer: Option<String>,
+) -> Result<Vec<EmittedProfile>, String> {
+    let targets = targets
+        .iter()
+        .map(|t| SlicerTarget::parse(t).ok_or_else(|| format!("Unknown slicer target '{}'", t)))
+        .collect::<Result<Vec<_>, String>>()?;
+    let paths = BambuPaths::detect().ok();
+    emit_for_targets(paths.as_ref(), &specs, &targets, target_printer.as_deref())
+}
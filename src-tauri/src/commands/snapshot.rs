@@ -0,0 +1,197 @@
+//! Tauri commands for whole-library snapshots (see `profile::snapshot`).
+//!
+//! Snapshots live in `<app data>/snapshots`. Batch installs, rebases and
+//! family-wide edits take one first via `snapshot_before` (or, when they
+//! were queued, the pending-writes flush does); the commands
+//! here take one on demand, list them with what has changed since, and
+//! restore them.
+
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use tauri::Manager;
+use tracing::{info, warn};
+
+use crate::profile::generator;
+use crate::profile::paths::BambuPaths;
+use crate::profile::snapshot::{self, FileChange, SnapshotInfo, CONF_FILE};
+use crate::profile::writer::{mark_profile_modified, register_filament_in_conf};
+
+/// A snapshot and how the library has changed since it was taken.
+#[derive(Debug, Clone, Serialize)]
+pub struct SnapshotSummary {
+    #[serde(flatten)]
+    pub info: SnapshotInfo,
+    pub changes: Vec<FileChange>,
+}
+
+fn snapshot_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get data dir: {}", e))?;
+    Ok(data_dir.join("snapshots"))
+}
+
+fn detect_paths() -> Result<BambuPaths, String> {
+    BambuPaths::detect().map_err(|e| format!("Bambu Studio not found: {}", e))
+}
+
+fn user_dir_for(paths: &BambuPaths) -> Result<PathBuf, String> {
+    paths
+        .user_filament_dir()
+        .ok_or_else(|| "User filament directory not found".to_string())
+}
+
+/// Snapshot the library into `snapshot_dir`, then prune old snapshots.
+fn take_snapshot(
+    user_dir: &Path,
+    config_root: &Path,
+    snapshot_dir: &Path,
+    reason: &str,
+) -> Result<SnapshotInfo, String> {
+    let info = snapshot::create_snapshot(user_dir, config_root, snapshot_dir, reason)
+        .map_err(|e| format!("Failed to snapshot profile library: {:#}", e))?;
+    if let Err(e) = snapshot::prune_snapshots(snapshot_dir, snapshot::DEFAULT_KEEP) {
+        warn!("Failed to prune old snapshots: {:#}", e);
+    }
+    Ok(info)
+}
+
+/// Snapshot the library before an operation that rewrites many profiles.
+/// A failure aborts the operation: it's only safe to run with a way back.
+pub(crate) async fn snapshot_before(
+    app: &tauri::AppHandle,
+    paths: &BambuPaths,
+    reason: &str,
+) -> Result<SnapshotInfo, String> {
+    let snapshot_dir = snapshot_dir(app)?;
+    let user_dir = user_dir_for(paths)?;
+    let config_root = paths.config_root.clone();
+    let reason = reason.to_string();
+    tokio::task::spawn_blocking(move || {
+        take_snapshot(&user_dir, &config_root, &snapshot_dir, &reason)
+    })
+    .await
+    .map_err(|e| format!("snapshot join error: {}", e))?
+}
+
+/// `snapshot_before` for callers already off the async runtime, such as
+/// the pending-writes flush.
+pub(crate) fn snapshot_before_blocking(
+    app: &tauri::AppHandle,
+    paths: &BambuPaths,
+    reason: &str,
+) -> Result<SnapshotInfo, String> {
+    take_snapshot(
+        &user_dir_for(paths)?,
+        &paths.config_root,
+        &snapshot_dir(app)?,
+        reason,
+    )
+}
+
+/// Snapshot the whole user filament library now.
+#[tauri::command]
+pub async fn create_library_snapshot(
+    app: tauri::AppHandle,
+    label: Option<String>,
+) -> Result<SnapshotInfo, String> {
+    let paths = detect_paths()?;
+    let reason = label
+        .map(|l| l.trim().to_string())
+        .filter(|l| !l.is_empty())
+        .unwrap_or_else(|| "manual snapshot".to_string());
+    snapshot_before(&app, &paths, &reason).await
+}
+
+/// List snapshots, newest first, each with the files that differ between
+/// it and the library as it is now.
+#[tauri::command]
+pub async fn list_library_snapshots(app: tauri::AppHandle) -> Result<Vec<SnapshotSummary>, String> {
+    let snapshot_dir = snapshot_dir(&app)?;
+    tokio::task::spawn_blocking(move || {
+        let paths = detect_paths()?;
+        let user_dir = user_dir_for(&paths)?;
+        let snapshots = snapshot::list_snapshots(&snapshot_dir)
+            .map_err(|e| format!("Failed to list snapshots: {:#}", e))?;
+        snapshots
+            .into_iter()
+            .map(|info| {
+                let archive = snapshot_dir.join(&info.id);
+                let changes = snapshot::diff_snapshot(&archive, &user_dir, &paths.config_root)
+                    .map_err(|e| format!("Failed to compare snapshot {}: {:#}", info.id, e))?;
+                Ok(SnapshotSummary { info, changes })
+            })
+            .collect()
+    })
+    .await
+    .map_err(|e| format!("snapshot join error: {}", e))?
+}
+
+/// Restore snapshot `id`: the whole library, or only `profiles` (paths as
+/// listed in its changes). The current library is snapshotted first, so a
+/// restore can itself be undone. Needs `force` while Bambu Studio is
+/// running. Returns the paths that were restored.
+#[tauri::command]
+pub async fn restore_library_snapshot(
+    app: tauri::AppHandle,
+    id: String,
+    profiles: Option<Vec<String>>,
+    force: bool,
+) -> Result<Vec<String>, String> {
+    if !force && generator::is_bambu_studio_running() {
+        return Err(
+            "Bambu Studio is running. Use force=true to restore anyway, but restart BS to see changes."
+                .to_string(),
+        );
+    }
+    let snapshot_dir = snapshot_dir(&app)?;
+    let archive = snapshot::snapshot_path(&snapshot_dir, &id).map_err(|e| e.to_string())?;
+    let paths = detect_paths()?;
+    snapshot_before(&app, &paths, &format!("restore of {}", id)).await?;
+
+    tokio::task::spawn_blocking(move || {
+        let user_dir = user_dir_for(&paths)?;
+        let restored = snapshot::restore_snapshot(
+            &archive,
+            &user_dir,
+            &paths.config_root,
+            profiles.as_deref(),
+        )
+        .map_err(|e| format!("Failed to restore snapshot: {:#}", e))?;
+
+        // Restored profiles are older than any cloud copy, so mark them as
+        // changed or the next sync would undo the restore
+        for path in restored.iter().filter(|p| p.ends_with(".json")) {
+            let json_path = user_dir.join(path);
+            if json_path.exists() {
+                if let Err(e) = mark_profile_modified(&json_path) {
+                    warn!("Failed to update sync metadata for {}: {:#}", path, e);
+                }
+            }
+        }
+
+        // A selective restore leaves BambuStudio.conf as it is, so make sure
+        // restored profiles are registered in it
+        if profiles.is_some() && !restored.iter().any(|p| p == CONF_FILE) {
+            for path in restored.iter().filter(|p| p.ends_with(".json")) {
+                if !user_dir.join(path).exists() {
+                    continue;
+                }
+                let stem = Path::new(path)
+                    .file_stem()
+                    .and_then(|s| s.to_str())
+                    .unwrap_or_default();
+                if let Err(e) = register_filament_in_conf(&paths.config_root, stem) {
+                    warn!("Failed to register {} in BambuStudio.conf: {}", stem, e);
+                }
+            }
+        }
+
+        info!("Restored {} files from snapshot {}", restored.len(), id);
+        Ok(restored)
+    })
+    .await
+    .map_err(|e| format!("snapshot join error: {}", e))?
+}
@@ -1,12 +1,17 @@
 #![recursion_limit = "256"]
 
 pub mod analyzer;
+pub mod calibration;
+pub mod cli;
 mod commands;
 pub mod history;
 pub mod mapper;
 pub mod model_catalog;
+pub mod pending_writes;
+pub mod printer;
 mod process_command;
 pub mod profile;
+pub mod profile_watcher;
 pub mod scraper;
 pub mod stl_watcher;
 pub mod str_utils;
@@ -26,6 +31,8 @@ pub fn run() {
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_opener::init())
         .manage(stl_watcher::StlWatcherState::new())
+        .manage(profile_watcher::ProfileWatcherState::new())
+        .manage(printer::PrinterManager::new())
         .invoke_handler(tauri::generate_handler![
             commands::keychain::set_api_key,
             commands::keychain::get_api_key,
@@ -49,15 +56,52 @@ pub fn run() {
             commands::profile::get_system_profile_count,
             commands::profile::generate_profile_from_specs,
             commands::profile::install_generated_profile,
-            commands::profile::delete_profile,
+            commands::profile::install_generated_profiles,
+            commands::removal::preview_profile_delete,
+            commands::removal::delete_profile,
+            commands::removal::preview_profile_rename,
+            commands::removal::rename_profile,
+            commands::pending::list_pending_writes,
+            commands::pending::discard_pending_write,
+            commands::pending::flush_pending_writes,
+            commands::fields::get_field_dictionary,
+            commands::fields::get_profile_fields,
+            commands::fields::check_profile_field_edits,
+            commands::fields::update_profile_fields,
             commands::profile::update_profile_field,
             commands::profile::duplicate_profile,
             commands::profile::extract_specs_from_profile,
             commands::profile::save_profile_specs,
+            commands::profile::convert_profile_to_delta,
+            commands::rebase::check_profile_rebases,
+            commands::rebase::resolve_profile_rebase,
+            commands::family::list_filament_families,
+            commands::family::update_family_field,
+            commands::family::generate_family_variants,
+            commands::snapshot::create_library_snapshot,
+            commands::snapshot::list_library_snapshots,
+            commands::snapshot::restore_library_snapshot,
             commands::profile::compare_profiles,
             commands::profile::search_base_profiles,
             commands::profile::refresh_base_profile_index,
+            commands::profile::query_profiles,
             commands::profile::list_target_printer_options,
+            commands::preset::list_presets,
+            commands::preset::read_preset_command,
+            commands::preset::compare_presets,
+            commands::preset::duplicate_preset,
+            commands::bundle::preview_bundle_import,
+            commands::bundle::import_bundle,
+            commands::bundle::export_profiles_bundle,
+            commands::slicer::generate_slicer_profiles,
+            commands::printer::connect_printer,
+            commands::printer::disconnect_printer,
+            commands::printer::list_printers,
+            commands::printer::get_printer_status,
+            commands::printer::refresh_printer_status,
+            commands::printer::resolve_ams_trays,
+            commands::printer::set_ams_tray_filament,
+            commands::calibration::generate_calibration_kit,
             commands::scraper::search_filament,
             commands::scraper::get_cached_filament,
             commands::scraper::clear_filament_cache,
@@ -101,6 +145,25 @@ pub fn run() {
                     }
                 }
             }
+
+            let paths = profile::BambuPaths::detect().ok();
+
+            // Finish or undo installs interrupted by a crash or power loss
+            if let Some(paths) = &paths {
+                commands::profile::recover_interrupted_installs(paths);
+            }
+
+            // Watch user filament profiles for edits made outside BambuMate
+            if let Some(dir) = paths.and_then(|paths| paths.user_filament_dir()) {
+                let state = app.state::<profile_watcher::ProfileWatcherState>();
+                if let Err(e) = state.start_watching(app.handle().clone(), &dir) {
+                    tracing::warn!("Failed to watch user profiles in {:?}: {}", dir, e);
+                }
+            }
+
+            // Write profile changes queued while Bambu Studio was running,
+            // now and whenever it next exits
+            pending_writes::start_flusher(app.handle().clone());
             Ok(())
         })
         .run(tauri::generate_context!())
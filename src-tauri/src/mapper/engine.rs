@@ -5,6 +5,7 @@
 
 use std::collections::HashMap;
 
+use crate::profile::fields::{dictionary, MaterialLimit, SafeRange};
 use crate::scraper::types::MaterialType;
 use crate::scraper::validation::constraints_for_material;
 
@@ -101,23 +102,22 @@ impl RuleEngine {
     ///
     /// Returns (clamped_value, was_clamped).
     fn clamp_to_safe_range(&self, param: &str, value: f32, material: &MaterialType) -> (f32, bool) {
+        // Safe ranges come from the field dictionary
+        let Some(safe_range) = dictionary().get(param).and_then(|f| f.safe_range) else {
+            return (value, false); // No constraints for this param
+        };
         let constraints = constraints_for_material(material);
 
-        let (min, max): (f32, f32) = match param {
-            "nozzle_temperature" | "nozzle_temperature_initial_layer" => (
+        let (min, max): (f32, f32) = match safe_range {
+            SafeRange::Fixed([min, max]) => (min as f32, max as f32),
+            SafeRange::Material(MaterialLimit::NozzleTemp) => (
                 constraints.nozzle_temp_min as f32,
                 constraints.nozzle_temp_max as f32,
             ),
-            "cool_plate_temp" | "hot_plate_temp" | "textured_plate_temp" => (
+            SafeRange::Material(MaterialLimit::BedTemp) => (
                 constraints.bed_temp_min as f32,
                 constraints.bed_temp_max as f32,
             ),
-            "filament_retraction_length" => (0.0, 15.0),
-            "filament_retraction_speed" => (10.0, 100.0),
-            "filament_flow_ratio" => (0.85, 1.15),
-            "fan_min_speed" | "fan_max_speed" | "overhang_fan_speed" => (0.0, 100.0),
-            "pressure_advance" => (0.0, 0.1),
-            _ => return (value, false), // No constraints for unknown params
         };
 
         if value < min {
@@ -0,0 +1,798 @@
+//! Profile writes held back while Bambu Studio is running.
+//!
+//! Bambu Studio keeps the user presets in memory and writes them back when
+//! it exits, so a profile changed on disk while it runs can be overwritten
+//! without warning. Installs, field edits and applied recommendations made
+//! in that window are queued in `pending_writes.json` in the app data
+//! directory instead, and a background task replays them once Bambu Studio
+//! has exited. Edits are queued as the edit rather than the edited file.
+//!
+//! Field edits, specs saves and family edits also record the version of
+//! each file they were checked against. If a file changed before the queue
+//! is written (e.g. it was edited in Bambu Studio), the edit is refused and
+//! left queued with the error. Delta conversions, rebases and applied
+//! recommendations record no version: they are recomputed from the file as
+//! it is when written, on top of whatever Bambu Studio saved.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{info, warn};
+
+use crate::commands::profile::ProfileInstall;
+use crate::history::AppliedChange;
+use crate::profile::bundle::ConflictPolicy;
+use crate::profile::conflict::ProfileVersion;
+use crate::profile::field_edit::FieldEdit;
+use crate::profile::generator;
+use crate::scraper::types::FilamentSpecs;
+
+/// Tauri event emitted with the current queue whenever it changes.
+pub const PENDING_WRITES_EVENT: &str = "pending-writes-changed";
+
+/// Queue file name in the app data directory.
+const QUEUE_FILE: &str = "pending_writes.json";
+
+/// How often the flusher checks whether Bambu Studio has exited.
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Serializes access to the queue file between commands and the flusher.
+static QUEUE_LOCK: Mutex<()> = Mutex::new(());
+
+fn lock_queue() -> MutexGuard<'static, ()> {
+    QUEUE_LOCK.lock().unwrap_or_else(|p| p.into_inner())
+}
+
+/// A write to replay once Bambu Studio has exited.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PendingOperation {
+    /// Install a generated profile (see `install_generated_profile`).
+    Install {
+        profile_json: String,
+        metadata_info: String,
+        filename: String,
+    },
+    /// Install several generated profiles in one transaction (see
+    /// `install_generated_profiles` and `generate_calibration_kit`).
+    InstallAll { installs: Vec<ProfileInstall> },
+    /// Set one field of a user profile (see `update_profile_field`).
+    UpdateField {
+        path: String,
+        key: String,
+        value: serde_json::Value,
+        /// Version of the file the edit was checked against; a file that
+        /// changed since refuses the edit. Unset in queues from older
+        /// versions, which are written unchecked.
+        #[serde(default)]
+        expected_version: Option<String>,
+    },
+    /// Save edited specs to a user profile (see `save_profile_specs`),
+    /// checked against `expected_version` as `UpdateField` is.
+    SaveSpecs {
+        path: String,
+        specs: FilamentSpecs,
+        expected_version: Option<String>,
+    },
+    /// Apply a batch of field edits to a user profile (see
+    /// `update_profile_fields`).
+    UpdateFields {
+        path: String,
+        edits: Vec<FieldEdit>,
+        /// As for `UpdateField`.
+        #[serde(default)]
+        expected_version: Option<String>,
+    },
+    /// Set one field in every variant of a family (see
+    /// `update_family_field`).
+    UpdateFamilyField {
+        filament_id: String,
+        key: String,
+        value: serde_json::Value,
+        /// Version of each variant when the edit was queued, by path; a
+        /// variant that changed since refuses the edit.
+        #[serde(default)]
+        expected_versions: HashMap<String, String>,
+    },
+    /// Rewrite a user profile as a delta over `parent` (see
+    /// `convert_profile_to_delta`).
+    ConvertToDelta { path: String, parent: String },
+    /// Regenerate a profile on its updated base (see
+    /// `resolve_profile_rebase`).
+    Rebase { path: String },
+    /// Import the filament presets of a bundle (see `import_bundle`).
+    ImportBundle {
+        bundle_path: String,
+        policy: ConflictPolicy,
+    },
+    /// Apply recommendations from an analysis session: `values` are the
+    /// formatted values to write, `changes` what gets recorded in history.
+    ApplyRecommendations {
+        path: String,
+        session_id: i64,
+        values: Vec<(String, String)>,
+        changes: Vec<AppliedChange>,
+    },
+}
+
+impl PendingOperation {
+    /// The profile path, or file name for installs, this operation writes.
+    pub fn target(&self) -> &str {
+        match self {
+            PendingOperation::Install { filename, .. } => filename,
+            PendingOperation::InstallAll { installs } => installs
+                .first()
+                .map(|install| install.filename.as_str())
+                .unwrap_or_default(),
+            PendingOperation::UpdateFamilyField { filament_id, .. } => filament_id,
+            PendingOperation::ImportBundle { bundle_path, .. } => bundle_path,
+            PendingOperation::UpdateField { path, .. }
+            | PendingOperation::SaveSpecs { path, .. }
+            | PendingOperation::UpdateFields { path, .. }
+            | PendingOperation::ConvertToDelta { path, .. }
+            | PendingOperation::Rebase { path }
+            | PendingOperation::ApplyRecommendations { path, .. } => path,
+        }
+    }
+
+    /// One-line description for the queue listing.
+    pub fn summary(&self) -> String {
+        let file_name = |path: &str| {
+            Path::new(path)
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| path.to_string())
+        };
+        match self {
+            PendingOperation::Install { filename, .. } => format!("Install {}", filename),
+            PendingOperation::InstallAll { installs } => {
+                let filenames: Vec<&str> = installs.iter().map(|i| i.filename.as_str()).collect();
+                format!("Install {}", filenames.join(", "))
+            }
+            PendingOperation::UpdateField {
+                path, key, value, ..
+            } => format!("Set {} = {} in {}", key, value, file_name(path)),
+            PendingOperation::SaveSpecs { path, .. } => {
+                format!("Save edited specs to {}", file_name(path))
+            }
+            PendingOperation::UpdateFields { path, edits, .. } => {
+                let keys: Vec<&str> = edits.iter().map(FieldEdit::key).collect();
+                format!("Edit {} in {}", keys.join(", "), file_name(path))
+            }
+            PendingOperation::UpdateFamilyField {
+                filament_id,
+                key,
+                value,
+                ..
+            } => format!("Set {} = {} in family {}", key, value, filament_id),
+            PendingOperation::ConvertToDelta { path, parent } => {
+                format!("Convert {} to a delta of {}", file_name(path), parent)
+            }
+            PendingOperation::Rebase { path } => {
+                format!("Update {} to its new base", file_name(path))
+            }
+            PendingOperation::ImportBundle { bundle_path, .. } => {
+                format!("Import {}", file_name(bundle_path))
+            }
+            PendingOperation::ApplyRecommendations { path, values, .. } => {
+                let params: Vec<&str> = values.iter().map(|(k, _)| k.as_str()).collect();
+                format!(
+                    "Apply recommendations to {}: {}",
+                    file_name(path),
+                    params.join(", ")
+                )
+            }
+        }
+    }
+
+    /// The file versions a queued edit was checked against, by path, for
+    /// the edits that record them.
+    fn expected_versions_mut(&mut self) -> Vec<(String, &mut String)> {
+        match self {
+            PendingOperation::UpdateField {
+                path,
+                expected_version,
+                ..
+            }
+            | PendingOperation::SaveSpecs {
+                path,
+                expected_version,
+                ..
+            }
+            | PendingOperation::UpdateFields {
+                path,
+                expected_version,
+                ..
+            } => expected_version
+                .as_mut()
+                .map(|version| (path.clone(), version))
+                .into_iter()
+                .collect(),
+            PendingOperation::UpdateFamilyField {
+                expected_versions, ..
+            } => expected_versions
+                .iter_mut()
+                .map(|(path, version)| (path.clone(), version))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Whether this rewrites many profiles at once, like the operations
+    /// that snapshot the library before they run directly.
+    fn rewrites_many(&self) -> bool {
+        matches!(
+            self,
+            PendingOperation::InstallAll { .. }
+                | PendingOperation::UpdateFamilyField { .. }
+                | PendingOperation::ConvertToDelta { .. }
+                | PendingOperation::Rebase { .. }
+                | PendingOperation::ImportBundle { .. }
+        )
+    }
+
+    /// Whether `self` makes `earlier` redundant: a later edit of the same
+    /// field of the same profile or family.
+    fn supersedes(&self, earlier: &PendingOperation) -> bool {
+        match (self, earlier) {
+            (
+                PendingOperation::UpdateField { path, key, .. },
+                PendingOperation::UpdateField {
+                    path: earlier_path,
+                    key: earlier_key,
+                    ..
+                },
+            ) => path == earlier_path && key == earlier_key,
+            (
+                PendingOperation::UpdateFamilyField {
+                    filament_id, key, ..
+                },
+                PendingOperation::UpdateFamilyField {
+                    filament_id: earlier_id,
+                    key: earlier_key,
+                    ..
+                },
+            ) => filament_id == earlier_id && key == earlier_key,
+            _ => false,
+        }
+    }
+}
+
+/// A queued write.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingWrite {
+    pub id: String,
+    /// RFC 3339 time it was queued.
+    pub queued_at: String,
+    pub summary: String,
+    pub target: String,
+    pub operation: PendingOperation,
+    /// Why the last attempt to write it failed. Failed writes stay queued
+    /// but are only retried on request.
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// Outcome of replaying the queue.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FlushReport {
+    pub written: Vec<String>,
+    pub failed: Vec<String>,
+}
+
+/// The queue file in a data directory.
+pub struct PendingWriteQueue {
+    path: PathBuf,
+}
+
+impl PendingWriteQueue {
+    pub fn new(data_dir: &Path) -> Self {
+        Self {
+            path: data_dir.join(QUEUE_FILE),
+        }
+    }
+
+    /// The queue for the running app.
+    pub fn for_app(app: &AppHandle) -> Result<Self, String> {
+        let data_dir = app
+            .path()
+            .app_data_dir()
+            .map_err(|e| format!("Failed to get data dir: {}", e))?;
+        Ok(Self::new(&data_dir))
+    }
+
+    fn load(&self) -> Result<Vec<PendingWrite>, String> {
+        match std::fs::read_to_string(&self.path) {
+            Ok(json) => serde_json::from_str(&json)
+                .map_err(|e| format!("Failed to parse {:?}: {}", self.path, e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(format!("Failed to read {:?}: {}", self.path, e)),
+        }
+    }
+
+    fn save(&self, items: &[PendingWrite]) -> Result<(), String> {
+        if items.is_empty() {
+            return match std::fs::remove_file(&self.path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                    Err(format!("Failed to remove {:?}: {}", self.path, e))
+                }
+                _ => Ok(()),
+            };
+        }
+        let dir = self
+            .path
+            .parent()
+            .ok_or_else(|| format!("Invalid queue path {:?}", self.path))?;
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {:?}: {}", dir, e))?;
+        let json = serde_json::to_string_pretty(items).map_err(|e| e.to_string())?;
+        let mut temp = tempfile::NamedTempFile::new_in(dir).map_err(|e| e.to_string())?;
+        std::io::Write::write_all(&mut temp, json.as_bytes()).map_err(|e| e.to_string())?;
+        temp.persist(&self.path)
+            .map_err(|e| format!("Failed to write {:?}: {}", self.path, e))?;
+        Ok(())
+    }
+
+    /// Queued writes, oldest first.
+    pub fn list(&self) -> Result<Vec<PendingWrite>, String> {
+        let _guard = lock_queue();
+        self.load()
+    }
+
+    /// Queue `operation`, dropping any earlier write it supersedes.
+    pub fn push(&self, operation: PendingOperation) -> Result<PendingWrite, String> {
+        let _guard = lock_queue();
+        let mut items = self.load()?;
+        items.retain(|item| !operation.supersedes(&item.operation));
+        let item = PendingWrite {
+            id: format!(
+                "{}-{:08x}",
+                Utc::now().format("%Y%m%d%H%M%S%3f"),
+                rand::random::<u32>()
+            ),
+            queued_at: Utc::now().to_rfc3339(),
+            summary: operation.summary(),
+            target: operation.target().to_string(),
+            operation,
+            error: None,
+        };
+        items.push(item.clone());
+        self.save(&items)?;
+        Ok(item)
+    }
+
+    /// Drop the queued write `id`. Returns whether it was queued.
+    pub fn discard(&self, id: &str) -> Result<bool, String> {
+        let _guard = lock_queue();
+        let mut items = self.load()?;
+        let before = items.len();
+        items.retain(|item| item.id != id);
+        if items.len() == before {
+            return Ok(false);
+        }
+        self.save(&items)?;
+        Ok(true)
+    }
+
+    /// Whether there's anything to write: any item, or with `retry_failed`
+    /// unset, any item that hasn't failed before.
+    pub fn has_work(&self, retry_failed: bool) -> Result<bool, String> {
+        Ok(self
+            .list()?
+            .iter()
+            .any(|item| retry_failed || item.error.is_none()))
+    }
+
+    /// Replay queued writes in order with `write`. Written items leave the
+    /// queue; failed ones stay with their error. Items that failed before
+    /// are skipped unless `retry_failed` is set.
+    pub fn flush(
+        &self,
+        retry_failed: bool,
+        mut write: impl FnMut(&PendingOperation) -> Result<(), String>,
+    ) -> Result<FlushReport, String> {
+        let _guard = lock_queue();
+        let items = self.load()?;
+        let mut report = FlushReport::default();
+        let mut remaining = Vec::new();
+        // Files this flush wrote: the version they were queued against and
+        // the version the queue left them at. Later edits of the same file
+        // were checked against the former.
+        let mut rewritten: HashMap<String, (String, String)> = HashMap::new();
+        for mut item in items {
+            if item.error.is_some() && !retry_failed {
+                remaining.push(item);
+                continue;
+            }
+            let mut operation = item.operation.clone();
+            let mut files = Vec::new();
+            for (path, expected) in operation.expected_versions_mut() {
+                if let Some((queued, written)) = rewritten.get(&path) {
+                    if expected == queued
+                        && file_version(&path).as_deref() == Some(written.as_str())
+                    {
+                        *expected = written.clone();
+                    }
+                }
+                files.push(path);
+            }
+            if files.is_empty() {
+                files.push(operation.target().to_string());
+            }
+            let before: Vec<Option<String>> = files.iter().map(|f| file_version(f)).collect();
+            match write(&operation) {
+                Ok(()) => {
+                    for (file, before) in files.into_iter().zip(before) {
+                        if let (Some(before), Some(after)) = (before, file_version(&file)) {
+                            rewritten
+                                .entry(file)
+                                .and_modify(|(_, written)| *written = after.clone())
+                                .or_insert((before, after));
+                        }
+                    }
+                    report.written.push(item.summary)
+                }
+                Err(e) => {
+                    warn!("Queued write '{}' failed: {}", item.summary, e);
+                    report.failed.push(item.summary.clone());
+                    item.error = Some(e);
+                    remaining.push(item);
+                }
+            }
+        }
+        self.save(&remaining)?;
+        Ok(report)
+    }
+}
+
+/// Version token of the file at `path`, if it is one.
+fn file_version(path: &str) -> Option<String> {
+    ProfileVersion::of_file(Path::new(path))
+        .ok()
+        .map(|version| version.token())
+}
+
+/// Queue `operation` for the running app and tell the frontend.
+pub fn enqueue(app: &AppHandle, operation: PendingOperation) -> Result<PendingWrite, String> {
+    let queue = PendingWriteQueue::for_app(app)?;
+    let item = queue.push(operation)?;
+    info!("Queued '{}' until Bambu Studio exits", item.summary);
+    notify(app, &queue);
+    Ok(item)
+}
+
+/// Emit [`PENDING_WRITES_EVENT`] with the queue as it is now.
+pub fn notify(app: &AppHandle, queue: &PendingWriteQueue) {
+    match queue.list() {
+        Ok(items) => {
+            if let Err(e) = app.emit(PENDING_WRITES_EVENT, items) {
+                warn!("Failed to emit pending writes: {}", e);
+            }
+        }
+        Err(e) => warn!("Failed to read pending writes: {}", e),
+    }
+}
+
+/// Write one queued operation now.
+fn write_operation(app: &AppHandle, operation: &PendingOperation) -> Result<(), String> {
+    match operation {
+        PendingOperation::Install {
+            profile_json,
+            metadata_info,
+            filename,
+        } => {
+            let paths = crate::profile::BambuPaths::detect()
+                .map_err(|e| format!("Bambu Studio not found: {}", e))?;
+            crate::commands::profile::install_profile_for_paths(
+                &paths,
+                profile_json,
+                metadata_info,
+                filename,
+                true,
+            )
+            .map(|_| ())
+        }
+        PendingOperation::InstallAll { installs } => {
+            let paths = crate::profile::BambuPaths::detect()
+                .map_err(|e| format!("Bambu Studio not found: {}", e))?;
+            crate::commands::profile::install_profiles_for_paths(&paths, installs, true).map(|_| ())
+        }
+        PendingOperation::UpdateField {
+            path,
+            key,
+            value,
+            expected_version,
+        } => crate::commands::profile::write_profile_field(
+            Path::new(path),
+            key,
+            value,
+            expected_version.as_deref(),
+        ),
+        PendingOperation::SaveSpecs {
+            path,
+            specs,
+            expected_version,
+        } => crate::commands::profile::write_profile_specs(
+            Path::new(path),
+            specs,
+            expected_version.as_deref(),
+        )
+        .map(|_| ()),
+        PendingOperation::UpdateFields {
+            path,
+            edits,
+            expected_version,
+        } => crate::commands::fields::write_profile_fields(
+            Path::new(path),
+            edits,
+            expected_version.as_deref(),
+        )
+        .map(|_| ()),
+        PendingOperation::UpdateFamilyField {
+            filament_id,
+            key,
+            value,
+            expected_versions,
+        } => {
+            let paths = crate::profile::BambuPaths::detect()
+                .map_err(|e| format!("Bambu Studio not found: {}", e))?;
+            crate::commands::family::update_family_field_for_paths(
+                &paths,
+                filament_id,
+                key,
+                value.clone(),
+                expected_versions,
+            )
+            .map(|_| ())
+        }
+        PendingOperation::ConvertToDelta { path, parent } => {
+            let paths = crate::profile::BambuPaths::detect()
+                .map_err(|e| format!("Bambu Studio not found: {}", e))?;
+            crate::commands::profile::convert_to_delta_for_paths(&paths, Path::new(path), parent)
+        }
+        PendingOperation::Rebase { path } => {
+            let paths = crate::profile::BambuPaths::detect()
+                .map_err(|e| format!("Bambu Studio not found: {}", e))?;
+            let store = crate::profile::rebase::GenerationStore::new(
+                &crate::commands::rebase::generation_db_path(app)?,
+            )?;
+            crate::commands::rebase::resolve_rebase_for_paths(&paths, &store, Path::new(path), true)
+        }
+        PendingOperation::ImportBundle {
+            bundle_path,
+            policy,
+        } => {
+            let paths = crate::profile::BambuPaths::detect()
+                .map_err(|e| format!("Bambu Studio not found: {}", e))?;
+            crate::commands::bundle::import_bundle_for_paths(
+                &paths,
+                Path::new(bundle_path),
+                *policy,
+                false,
+            )
+            .map(|_| ())
+        }
+        PendingOperation::ApplyRecommendations {
+            path,
+            session_id,
+            values,
+            changes,
+        } => {
+            let db_path = app
+                .path()
+                .app_data_dir()
+                .map_err(|e| format!("Failed to get data dir: {}", e))?
+                .join("refinement_history.db");
+            crate::commands::analyzer::apply_values_to_profile(
+                &db_path,
+                Path::new(path),
+                *session_id,
+                values,
+                changes,
+            )
+            .map(|_| ())
+        }
+    }
+}
+
+/// Replay the queue if Bambu Studio isn't running. Returns `None` when
+/// there was nothing to do or Bambu Studio is still running.
+pub fn flush_if_closed(app: &AppHandle, retry_failed: bool) -> Result<Option<FlushReport>, String> {
+    let queue = PendingWriteQueue::for_app(app)?;
+    if !queue.has_work(retry_failed)? || generator::is_bambu_studio_running() {
+        return Ok(None);
+    }
+    // One snapshot covers every bulk write in this flush; without it the
+    // queue is left for the next try
+    let bulk = queue
+        .list()?
+        .iter()
+        .filter(|item| retry_failed || item.error.is_none())
+        .any(|item| item.operation.rewrites_many());
+    if bulk {
+        let paths = crate::profile::BambuPaths::detect()
+            .map_err(|e| format!("Bambu Studio not found: {}", e))?;
+        crate::commands::snapshot::snapshot_before_blocking(app, &paths, "queued changes")?;
+    }
+    let report = queue.flush(retry_failed, |operation| write_operation(app, operation))?;
+    info!(
+        "Wrote {} queued profile changes ({} failed)",
+        report.written.len(),
+        report.failed.len()
+    );
+    notify(app, &queue);
+    Ok(Some(report))
+}
+
+/// Poll for Bambu Studio exiting and replay the queue when it does. Also
+/// writes anything left queued by a previous run that's still safe to
+/// write.
+pub fn start_flusher(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let handle = app.clone();
+            match tokio::task::spawn_blocking(move || flush_if_closed(&handle, false)).await {
+                Ok(Err(e)) => warn!("Failed to write queued profile changes: {}", e),
+                Err(e) => warn!("Pending write flusher join error: {}", e),
+                Ok(Ok(_)) => {}
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(path: &str, key: &str, value: &str) -> PendingOperation {
+        PendingOperation::UpdateField {
+            path: path.to_string(),
+            key: key.to_string(),
+            value: serde_json::json!([value]),
+            expected_version: None,
+        }
+    }
+
+    #[test]
+    fn test_queue_persists_and_discards() {
+        let dir = tempfile::tempdir().unwrap();
+        let queue = PendingWriteQueue::new(dir.path());
+        assert!(queue.list().unwrap().is_empty());
+
+        let install = queue
+            .push(PendingOperation::Install {
+                profile_json: "{}".into(),
+                metadata_info: String::new(),
+                filename: "PLA.json".into(),
+            })
+            .unwrap();
+        queue
+            .push(field("/u/PLA.json", "nozzle_temperature", "215"))
+            .unwrap();
+
+        let reopened = PendingWriteQueue::new(dir.path());
+        let items = reopened.list().unwrap();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].summary, "Install PLA.json");
+        assert_eq!(items[1].target, "/u/PLA.json");
+
+        assert!(reopened.discard(&install.id).unwrap());
+        assert!(!reopened.discard(&install.id).unwrap());
+        assert_eq!(queue.list().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_later_edit_of_same_field_replaces_earlier() {
+        let dir = tempfile::tempdir().unwrap();
+        let queue = PendingWriteQueue::new(dir.path());
+        queue
+            .push(field("/u/PLA.json", "nozzle_temperature", "215"))
+            .unwrap();
+        queue
+            .push(field("/u/PLA.json", "hot_plate_temp", "60"))
+            .unwrap();
+        queue
+            .push(field("/u/PLA.json", "nozzle_temperature", "220"))
+            .unwrap();
+
+        let items = queue.list().unwrap();
+        assert_eq!(items.len(), 2);
+        assert!(items[1].summary.contains("220"));
+    }
+
+    #[test]
+    fn test_flush_keeps_failures_and_skips_them_until_retried() {
+        let dir = tempfile::tempdir().unwrap();
+        let queue = PendingWriteQueue::new(dir.path());
+        queue.push(field("/u/PLA.json", "a", "1")).unwrap();
+        queue.push(field("/u/PETG.json", "b", "2")).unwrap();
+
+        let fail_petg = |op: &PendingOperation| match op.target() {
+            "/u/PETG.json" => Err("gone".to_string()),
+            _ => Ok(()),
+        };
+        let report = queue.flush(false, fail_petg).unwrap();
+        assert_eq!(report.written.len(), 1);
+        assert_eq!(report.failed.len(), 1);
+
+        let items = queue.list().unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].error.as_deref(), Some("gone"));
+        assert!(!queue.has_work(false).unwrap());
+        assert!(queue.has_work(true).unwrap());
+
+        let mut calls = 0;
+        queue
+            .flush(false, |_| {
+                calls += 1;
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(calls, 0);
+
+        queue.flush(true, |_| Ok(())).unwrap();
+        assert!(queue.list().unwrap().is_empty());
+        assert!(!dir.path().join(QUEUE_FILE).exists());
+    }
+
+    #[test]
+    fn test_queued_edits_of_one_file_all_apply() {
+        let dir = tempfile::tempdir().unwrap();
+        let profile = dir.path().join("PLA.json");
+        std::fs::write(&profile, "{}").unwrap();
+        let path = profile.to_string_lossy().to_string();
+        let version = || ProfileVersion::of_file(&profile).unwrap().token();
+
+        let queue = PendingWriteQueue::new(dir.path());
+        for (key, value) in [("a", "1"), ("b", "2")] {
+            let mut op = field(&path, key, value);
+            if let PendingOperation::UpdateField {
+                expected_version, ..
+            } = &mut op
+            {
+                *expected_version = Some(version());
+            }
+            queue.push(op).unwrap();
+        }
+        // A family edit covering the same file
+        queue
+            .push(PendingOperation::UpdateFamilyField {
+                filament_id: "P1".into(),
+                key: "c".into(),
+                value: serde_json::json!(["3"]),
+                expected_versions: HashMap::from([(path.clone(), version())]),
+            })
+            .unwrap();
+
+        // Stands in for the real writers: refuses a changed file, else
+        // appends the field
+        let mut contents = String::new();
+        let report = queue
+            .flush(false, |op| {
+                let mut op = op.clone();
+                if op
+                    .expected_versions_mut()
+                    .iter()
+                    .any(|(_, expected)| **expected != version())
+                {
+                    return Err("changed".to_string());
+                }
+                let (PendingOperation::UpdateField { key, .. }
+                | PendingOperation::UpdateFamilyField { key, .. }) = &op
+                else {
+                    unreachable!()
+                };
+                contents.push_str(key);
+                std::fs::write(&profile, &contents).unwrap();
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(report.written.len(), 3, "{:?}", report.failed);
+        assert_eq!(std::fs::read_to_string(&profile).unwrap(), "abc");
+    }
+}
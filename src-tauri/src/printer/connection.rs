@@ -0,0 +1,433 @@
+//! MQTT connection to a single printer.
+//!
+//! In LAN mode the printer runs its own broker on port 8883 (TLS). Clients
+//! log in as `bblp` with the printer's LAN access code, subscribe to
+//! `device/<serial>/report` and send commands to `device/<serial>/request`.
+//!
+//! The printer's certificate is signed by Bambu's private CA and issued to
+//! the serial number rather than the IP address, so it can't be validated
+//! with the system trust store. Like every other LAN client we skip chain
+//! validation (handshake signatures are still checked); the access code is
+//! what authenticates the session.
+
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use rumqttc::{
+    AsyncClient, ConnectionError, Event, MqttOptions, Outgoing, Packet, QoS, TlsConfiguration,
+    Transport,
+};
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{DigitallySignedStruct, SignatureScheme};
+use serde::{Deserialize, Serialize};
+use tauri::async_runtime::JoinHandle;
+use tracing::{debug, info, warn};
+
+use super::report::{parse_report, PrinterStatus};
+
+/// Port of the printer's built-in MQTT broker.
+pub const DEFAULT_MQTT_PORT: u16 = 8883;
+
+/// Username for LAN-mode MQTT.
+const MQTT_USERNAME: &str = "bblp";
+
+/// Reports carry the full AMS state and can reach tens of KB.
+const MAX_PACKET_SIZE: usize = 1024 * 1024;
+
+/// Wait before polling again after the connection drops (rumqttc
+/// reconnects on the next poll).
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// How long `disconnect` lets the event loop send DISCONNECT before the
+/// task is aborted (e.g. while it sleeps between reconnect attempts).
+const DISCONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+
+fn default_port() -> u16 {
+    DEFAULT_MQTT_PORT
+}
+
+fn default_tls() -> bool {
+    true
+}
+
+/// How to reach one printer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrinterConfig {
+    pub serial: String,
+    /// IP address or hostname on the LAN.
+    pub host: String,
+    /// LAN access code from the printer's network settings screen.
+    pub access_code: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default = "default_port")]
+    pub port: u16,
+    /// Printers always use TLS; plain TCP is for broker stand-ins in tests.
+    #[serde(default = "default_tls")]
+    pub tls: bool,
+}
+
+impl PrinterConfig {
+    pub fn report_topic(&self) -> String {
+        format!("device/{}/report", self.serial)
+    }
+
+    pub fn request_topic(&self) -> String {
+        format!("device/{}/request", self.serial)
+    }
+}
+
+/// Lifecycle of a printer connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionState {
+    Connecting,
+    Connected,
+    /// Lost the connection; retrying.
+    Reconnecting,
+    Disconnected,
+}
+
+/// Something the frontend should hear about.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PrinterEvent {
+    Status {
+        serial: String,
+        status: Box<PrinterStatus>,
+    },
+    Connection {
+        serial: String,
+        state: ConnectionState,
+        message: Option<String>,
+    },
+}
+
+impl PrinterEvent {
+    /// Tauri event name this event is emitted under.
+    pub fn event_name(&self) -> &'static str {
+        match self {
+            Self::Status { .. } => "printer-status",
+            Self::Connection { .. } => "printer-connection",
+        }
+    }
+}
+
+/// Callback receiving every event of a connection.
+pub type EventCallback = Arc<dyn Fn(PrinterEvent) + Send + Sync>;
+
+fn lock_recover<T>(m: &Mutex<T>) -> MutexGuard<'_, T> {
+    m.lock().unwrap_or_else(|p| p.into_inner())
+}
+
+/// A live connection: the MQTT client plus the task driving its event loop.
+pub struct PrinterConnection {
+    config: PrinterConfig,
+    client: AsyncClient,
+    status: Arc<Mutex<PrinterStatus>>,
+    state: Arc<Mutex<ConnectionState>>,
+    task: JoinHandle<()>,
+}
+
+impl PrinterConnection {
+    /// Start connecting in the background. Returns immediately; progress
+    /// is reported through `on_event` and [`Self::state`].
+    ///
+    /// On every (re)connect the report topic is subscribed and a full
+    /// status refresh (`pushall`) is requested, since P1/A1 printers only
+    /// send deltas otherwise.
+    pub fn connect(config: PrinterConfig, on_event: EventCallback) -> Result<Self> {
+        if config.serial.trim().is_empty() {
+            return Err(anyhow!("Printer serial is required"));
+        }
+        let mut options = MqttOptions::new(
+            format!("bambumate-{:08x}", rand::random::<u32>()),
+            config.host.clone(),
+            config.port,
+        );
+        options
+            .set_credentials(MQTT_USERNAME, config.access_code.clone())
+            .set_keep_alive(Duration::from_secs(30))
+            .set_max_packet_size(MAX_PACKET_SIZE, MAX_PACKET_SIZE);
+        if config.tls {
+            options.set_transport(Transport::tls_with_config(lan_tls_config()));
+        }
+
+        let (client, mut eventloop) = AsyncClient::new(options, 16);
+        let status = Arc::new(Mutex::new(PrinterStatus::default()));
+        let state = Arc::new(Mutex::new(ConnectionState::Connecting));
+
+        let task = {
+            let config = config.clone();
+            let client = client.clone();
+            let status = status.clone();
+            let state = state.clone();
+            tauri::async_runtime::spawn(async move {
+                let report_topic = config.report_topic();
+                let set_state = |new: ConnectionState, message: Option<String>| {
+                    let mut current = lock_recover(&state);
+                    if *current != new || message.is_some() {
+                        *current = new;
+                        on_event(PrinterEvent::Connection {
+                            serial: config.serial.clone(),
+                            state: new,
+                            message,
+                        });
+                    }
+                };
+
+                loop {
+                    match eventloop.poll().await {
+                        Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                            info!("Connected to printer {} at {}", config.serial, config.host);
+                            set_state(ConnectionState::Connected, None);
+                            if let Err(e) = client.subscribe(&report_topic, QoS::AtMostOnce).await {
+                                warn!("Failed to subscribe to {}: {}", report_topic, e);
+                            }
+                            if let Err(e) = publish_pushall(&client, &config).await {
+                                warn!("Failed to request full status: {}", e);
+                            }
+                        }
+                        Ok(Event::Incoming(Packet::Publish(publish))) => {
+                            if publish.topic != report_topic {
+                                continue;
+                            }
+                            match parse_report(&publish.payload) {
+                                Ok(message) => {
+                                    if let Some(print) = message.print {
+                                        let snapshot = {
+                                            let mut s = lock_recover(&status);
+                                            s.apply(&print);
+                                            s.clone()
+                                        };
+                                        on_event(PrinterEvent::Status {
+                                            serial: config.serial.clone(),
+                                            status: Box::new(snapshot),
+                                        });
+                                    }
+                                }
+                                Err(e) => debug!("Ignoring unparseable report: {}", e),
+                            }
+                        }
+                        // rumqttc reports the outgoing packet once it has
+                        // been written and flushed
+                        Ok(Event::Outgoing(Outgoing::Disconnect)) => {
+                            set_state(ConnectionState::Disconnected, None);
+                            break;
+                        }
+                        Ok(_) => {}
+                        Err(ConnectionError::RequestsDone) => {
+                            set_state(ConnectionState::Disconnected, None);
+                            break;
+                        }
+                        Err(e) => {
+                            warn!("Printer {} connection error: {}", config.serial, e);
+                            set_state(ConnectionState::Reconnecting, Some(e.to_string()));
+                            tokio::time::sleep(RECONNECT_DELAY).await;
+                        }
+                    }
+                }
+            })
+        };
+
+        Ok(Self {
+            config,
+            client,
+            status,
+            state,
+            task,
+        })
+    }
+
+    pub fn config(&self) -> &PrinterConfig {
+        &self.config
+    }
+
+    pub fn status(&self) -> PrinterStatus {
+        lock_recover(&self.status).clone()
+    }
+
+    pub fn state(&self) -> ConnectionState {
+        *lock_recover(&self.state)
+    }
+
+    /// A cloneable handle for publishing requests without holding on to
+    /// the connection (e.g. across an `.await` while a lock is held).
+    pub fn handle(&self) -> PrinterHandle {
+        PrinterHandle {
+            config: self.config.clone(),
+            client: self.client.clone(),
+        }
+    }
+
+    /// Close the connection and stop the event loop.
+    ///
+    /// DISCONNECT is queued for the event loop, which exits once it has been
+    /// flushed. Returns without waiting; the loop is aborted if it hasn't
+    /// finished within `DISCONNECT_TIMEOUT`.
+    pub fn disconnect(self) {
+        let mut task = self.task;
+        if self.client.try_disconnect().is_ok() {
+            tauri::async_runtime::spawn(async move {
+                if tokio::time::timeout(DISCONNECT_TIMEOUT, &mut task)
+                    .await
+                    .is_err()
+                {
+                    task.abort();
+                }
+            });
+        } else {
+            task.abort();
+        }
+        *lock_recover(&self.state) = ConnectionState::Disconnected;
+        info!("Disconnected from printer {}", self.config.serial);
+    }
+}
+
+/// Publishes to a printer's request topic.
+#[derive(Clone)]
+pub struct PrinterHandle {
+    config: PrinterConfig,
+    client: AsyncClient,
+}
+
+impl PrinterHandle {
+    pub fn serial(&self) -> &str {
+        &self.config.serial
+    }
+
+    /// Ask the printer to send its complete state.
+    pub async fn request_full_status(&self) -> Result<()> {
+        publish_pushall(&self.client, &self.config).await
+    }
+
+    /// Publish a command object to `device/<serial>/request`.
+    pub async fn send_request(&self, payload: &serde_json::Value) -> Result<()> {
+        self.client
+            .publish(
+                self.config.request_topic(),
+                QoS::AtMostOnce,
+                false,
+                serde_json::to_vec(payload)?,
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+async fn publish_pushall(client: &AsyncClient, config: &PrinterConfig) -> Result<()> {
+    let payload = serde_json::json!({
+        "pushing": {"sequence_id": "0", "command": "pushall"}
+    });
+    client
+        .publish(
+            config.request_topic(),
+            QoS::AtMostOnce,
+            false,
+            serde_json::to_vec(&payload)?,
+        )
+        .await?;
+    Ok(())
+}
+
+fn lan_tls_config() -> TlsConfiguration {
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let config = rustls::ClientConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .expect("ring provider supports the default protocol versions")
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(LanCertVerifier { provider }))
+        .with_no_client_auth();
+    TlsConfiguration::Rustls(Arc::new(config))
+}
+
+/// Accepts the printer's self-signed certificate but still verifies the
+/// handshake signatures made with it.
+#[derive(Debug)]
+struct LanCertVerifier {
+    provider: Arc<rustls::crypto::CryptoProvider>,
+}
+
+impl ServerCertVerifier for LanCertVerifier {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_defaults_and_topics() {
+        let config: PrinterConfig = serde_json::from_str(
+            r#"{"serial": "01S00A000000000", "host": "192.168.1.50", "access_code": "12345678"}"#,
+        )
+        .unwrap();
+        assert_eq!(config.port, DEFAULT_MQTT_PORT);
+        assert!(config.tls);
+        assert_eq!(config.report_topic(), "device/01S00A000000000/report");
+        assert_eq!(config.request_topic(), "device/01S00A000000000/request");
+    }
+
+    #[test]
+    fn test_event_names() {
+        let event = PrinterEvent::Connection {
+            serial: "X".to_string(),
+            state: ConnectionState::Connected,
+            message: None,
+        };
+        assert_eq!(event.event_name(), "printer-connection");
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["type"], "connection");
+        assert_eq!(json["state"], "connected");
+    }
+
+    #[test]
+    fn test_lan_tls_config_builds() {
+        assert!(matches!(lan_tls_config(), TlsConfiguration::Rustls(_)));
+    }
+}
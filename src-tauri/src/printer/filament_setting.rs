@@ -0,0 +1,248 @@
+//! The `ams_filament_setting` request: tell the printer what is loaded in
+//! a tray, as if it had been picked on the touchscreen.
+
+use anyhow::{anyhow, bail, Result};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::profile::types::FilamentProfile;
+use crate::scraper::types::{MaterialType, ValidationWarning};
+use crate::scraper::validation::constraints_for_material;
+
+/// `ams_id` the printer uses for the external spool holder.
+pub const EXTERNAL_AMS_ID: u8 = 255;
+
+/// `tray_id` the printer uses for the external spool holder.
+pub const EXTERNAL_TRAY_ID: u8 = 254;
+
+/// Settings to send for one tray.
+#[derive(Debug, Clone, Serialize)]
+pub struct AmsFilamentSetting {
+    pub ams_id: u8,
+    /// Slot within the AMS unit (0-3), or 254 for the external spool.
+    pub tray_id: u8,
+    pub filament_id: String,
+    /// System preset `setting_id` (e.g. "GFSA00"); empty for user presets.
+    pub setting_id: String,
+    pub tray_type: String,
+    /// `RRGGBBAA`.
+    pub tray_color: String,
+    pub nozzle_temp_min: u16,
+    pub nozzle_temp_max: u16,
+    /// Adjustments made while validating against the material's limits.
+    pub warnings: Vec<ValidationWarning>,
+}
+
+impl AmsFilamentSetting {
+    /// Build the settings for `ams_id`/`tray_id` (None for the external
+    /// spool) from a resolved profile.
+    ///
+    /// The range comes from `nozzle_temperature_range_low`/`_high`, or the
+    /// printing temperature when a profile has no range. It is checked
+    /// against [`constraints_for_material`]: ends outside the material's
+    /// limits are clamped (with a warning), and a range that lies entirely
+    /// outside them is rejected as most likely the wrong material.
+    pub fn from_profile(
+        profile: &FilamentProfile,
+        filament_id: &str,
+        ams_id: Option<u8>,
+        tray_id: u8,
+        color: &str,
+    ) -> Result<Self> {
+        if filament_id.is_empty() {
+            bail!("Profile has no filament_id; the printer can't identify it");
+        }
+        let tray_type = profile
+            .filament_type()
+            .filter(|t| !t.is_empty())
+            .ok_or_else(|| anyhow!("Profile has no filament_type"))?
+            .to_string();
+        let (ams_id, tray_id) = match ams_id {
+            Some(ams_id) if tray_id < 4 => (ams_id, tray_id),
+            Some(_) => bail!("Tray id {} is out of range (0-3)", tray_id),
+            None => (EXTERNAL_AMS_ID, EXTERNAL_TRAY_ID),
+        };
+
+        let (low, high) = profile
+            .nozzle_temperature_range()
+            .or_else(|| {
+                let temp = profile.nozzle_temperature()?.first()?.trim().parse().ok()?;
+                Some((temp, temp))
+            })
+            .ok_or_else(|| anyhow!("Profile has no nozzle temperature"))?;
+        if low > high {
+            bail!("Nozzle temperature range {}-{}C is inverted", low, high);
+        }
+
+        let constraints = constraints_for_material(&MaterialType::from_str(&tray_type));
+        if high < constraints.nozzle_temp_min || low > constraints.nozzle_temp_max {
+            bail!(
+                "Nozzle temperature range {}-{}C is outside the {} range ({}-{}C)",
+                low,
+                high,
+                tray_type,
+                constraints.nozzle_temp_min,
+                constraints.nozzle_temp_max
+            );
+        }
+        let mut warnings = Vec::new();
+        let mut clamp = |field: &str, value: u16| {
+            let clamped = value.clamp(constraints.nozzle_temp_min, constraints.nozzle_temp_max);
+            if clamped != value {
+                warnings.push(ValidationWarning {
+                    field: field.to_string(),
+                    message: format!(
+                        "{}C is outside the {} range ({}-{}C); sending {}C",
+                        value,
+                        tray_type,
+                        constraints.nozzle_temp_min,
+                        constraints.nozzle_temp_max,
+                        clamped
+                    ),
+                    value: value.to_string(),
+                });
+            }
+            clamped
+        };
+        let nozzle_temp_min = clamp("nozzle_temp_min", low);
+        let nozzle_temp_max = clamp("nozzle_temp_max", high);
+
+        Ok(Self {
+            ams_id,
+            tray_id,
+            filament_id: filament_id.to_string(),
+            setting_id: profile.setting_id().unwrap_or_default().to_string(),
+            tray_type,
+            tray_color: normalize_color(color)?,
+            nozzle_temp_min,
+            nozzle_temp_max,
+            warnings,
+        })
+    }
+
+    /// Request payload for `device/<serial>/request`.
+    pub fn to_request(&self) -> Value {
+        json!({
+            "print": {
+                "sequence_id": "0",
+                "command": "ams_filament_setting",
+                "ams_id": self.ams_id,
+                "tray_id": self.tray_id,
+                "slot_id": self.tray_id,
+                "tray_info_idx": self.filament_id,
+                "setting_id": self.setting_id,
+                "tray_type": self.tray_type,
+                "tray_color": self.tray_color,
+                "nozzle_temp_min": self.nozzle_temp_min,
+                "nozzle_temp_max": self.nozzle_temp_max,
+            }
+        })
+    }
+}
+
+/// Normalize `#RRGGBB`, `RRGGBB` or `RRGGBBAA` to the printer's uppercase
+/// `RRGGBBAA`.
+pub fn normalize_color(color: &str) -> Result<String> {
+    let hex = color.trim().trim_start_matches('#');
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        bail!("Invalid colour '{}'", color);
+    }
+    match hex.len() {
+        6 => Ok(format!("{}FF", hex.to_ascii_uppercase())),
+        8 => Ok(hex.to_ascii_uppercase()),
+        _ => bail!("Invalid colour '{}'", color),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(extra: Value) -> FilamentProfile {
+        let mut data = json!({
+            "name": "Polymaker PLA Matte @BBL X1C",
+            "filament_type": ["PLA"],
+            "nozzle_temperature": ["215"],
+        });
+        data.as_object_mut()
+            .unwrap()
+            .extend(extra.as_object().unwrap().clone());
+        FilamentProfile::from_json(&data.to_string()).unwrap()
+    }
+
+    #[test]
+    fn test_setting_from_profile_range() {
+        let p = profile(json!({
+            "nozzle_temperature_range_low": ["190"],
+            "nozzle_temperature_range_high": ["230"],
+        }));
+        let setting =
+            AmsFilamentSetting::from_profile(&p, "P1a2b3c4", Some(1), 2, "#f2754e").unwrap();
+        assert_eq!(setting.ams_id, 1);
+        assert_eq!(setting.tray_id, 2);
+        assert_eq!(
+            (setting.nozzle_temp_min, setting.nozzle_temp_max),
+            (190, 230)
+        );
+        assert_eq!(setting.tray_color, "F2754EFF");
+        assert!(setting.warnings.is_empty());
+
+        let request = setting.to_request();
+        assert_eq!(request["print"]["command"], "ams_filament_setting");
+        assert_eq!(request["print"]["tray_info_idx"], "P1a2b3c4");
+        assert_eq!(request["print"]["tray_type"], "PLA");
+        assert_eq!(request["print"]["nozzle_temp_max"], 230);
+    }
+
+    #[test]
+    fn test_setting_falls_back_to_print_temperature() {
+        let setting =
+            AmsFilamentSetting::from_profile(&profile(json!({})), "P1", None, 0, "FFFFFF").unwrap();
+        assert_eq!(
+            (setting.nozzle_temp_min, setting.nozzle_temp_max),
+            (215, 215)
+        );
+        assert_eq!(
+            (setting.ams_id, setting.tray_id),
+            (EXTERNAL_AMS_ID, EXTERNAL_TRAY_ID)
+        );
+    }
+
+    #[test]
+    fn test_setting_clamps_to_material_limits() {
+        // Stock generic PLA presets allow up to 240C; PLA tops out at 235C
+        let p = profile(json!({
+            "nozzle_temperature_range_low": ["190"],
+            "nozzle_temperature_range_high": ["240"],
+        }));
+        let setting = AmsFilamentSetting::from_profile(&p, "GFL99", Some(0), 0, "FFFFFF").unwrap();
+        assert_eq!(setting.nozzle_temp_max, 235);
+        assert_eq!(setting.warnings.len(), 1);
+        assert_eq!(setting.warnings[0].field, "nozzle_temp_max");
+    }
+
+    #[test]
+    fn test_setting_rejects_wrong_material_range() {
+        let p = profile(json!({
+            "nozzle_temperature_range_low": ["260"],
+            "nozzle_temperature_range_high": ["290"],
+        }));
+        assert!(AmsFilamentSetting::from_profile(&p, "P1", Some(0), 0, "FFFFFF").is_err());
+        assert!(
+            AmsFilamentSetting::from_profile(&profile(json!({})), "", Some(0), 0, "FFFFFF")
+                .is_err()
+        );
+        assert!(
+            AmsFilamentSetting::from_profile(&profile(json!({})), "P1", Some(0), 4, "FFFFFF")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_normalize_color() {
+        assert_eq!(normalize_color("#00ae42").unwrap(), "00AE42FF");
+        assert_eq!(normalize_color("161616FF").unwrap(), "161616FF");
+        assert!(normalize_color("red").is_err());
+        assert!(normalize_color("#FFF").is_err());
+    }
+}
@@ -0,0 +1,109 @@
+//! Live printer telemetry over the Bambu LAN MQTT protocol.
+//!
+//! - [`report`]: typed parser for `device/<serial>/report` payloads and the
+//!   merged [`PrinterStatus`].
+//! - [`connection`]: one MQTT-over-TLS session per printer.
+//! - [`trays`]: resolves AMS trays to installed profiles by `filament_id`.
+//! - [`filament_setting`]: the `ams_filament_setting` request.
+//! - [`PrinterManager`]: the set of open connections, held as Tauri state.
+
+pub mod connection;
+pub mod filament_setting;
+pub mod report;
+pub mod trays;
+
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard};
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+
+pub use connection::{
+    ConnectionState, EventCallback, PrinterConfig, PrinterConnection, PrinterEvent, PrinterHandle,
+};
+pub use filament_setting::AmsFilamentSetting;
+pub use report::{AmsTray, AmsUnit, PrinterStatus};
+pub use trays::{resolve_trays, TrayMatchState, TrayResolution};
+
+/// Summary of one managed printer for list views.
+#[derive(Debug, Clone, Serialize)]
+pub struct PrinterSummary {
+    pub serial: String,
+    pub name: Option<String>,
+    pub host: String,
+    pub state: ConnectionState,
+    pub status: PrinterStatus,
+}
+
+fn lock_recover<T>(m: &Mutex<T>) -> MutexGuard<'_, T> {
+    m.lock().unwrap_or_else(|p| p.into_inner())
+}
+
+/// Open printer connections, keyed by serial number.
+#[derive(Default)]
+pub struct PrinterManager {
+    connections: Mutex<HashMap<String, PrinterConnection>>,
+}
+
+impl PrinterManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Connect to a printer, replacing any existing connection to the same
+    /// serial (e.g. after its IP address or access code changed).
+    pub fn connect(&self, config: PrinterConfig, on_event: EventCallback) -> Result<()> {
+        let serial = config.serial.clone();
+        let connection = PrinterConnection::connect(config, on_event)?;
+        if let Some(old) = lock_recover(&self.connections).insert(serial, connection) {
+            old.disconnect();
+        }
+        Ok(())
+    }
+
+    /// Close the connection to `serial`. Returns false if none was open.
+    pub fn disconnect(&self, serial: &str) -> bool {
+        match lock_recover(&self.connections).remove(serial) {
+            Some(connection) => {
+                connection.disconnect();
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn disconnect_all(&self) {
+        for (_, connection) in lock_recover(&self.connections).drain() {
+            connection.disconnect();
+        }
+    }
+
+    pub fn list(&self) -> Vec<PrinterSummary> {
+        let mut printers: Vec<PrinterSummary> = lock_recover(&self.connections)
+            .values()
+            .map(|c| PrinterSummary {
+                serial: c.config().serial.clone(),
+                name: c.config().name.clone(),
+                host: c.config().host.clone(),
+                state: c.state(),
+                status: c.status(),
+            })
+            .collect();
+        printers.sort_by(|a, b| a.serial.cmp(&b.serial));
+        printers
+    }
+
+    pub fn status(&self, serial: &str) -> Option<PrinterStatus> {
+        lock_recover(&self.connections)
+            .get(serial)
+            .map(|c| c.status())
+    }
+
+    /// Request handle for `serial`, or an error if it isn't connected.
+    pub fn handle(&self, serial: &str) -> Result<PrinterHandle> {
+        lock_recover(&self.connections)
+            .get(serial)
+            .map(|c| c.handle())
+            .ok_or_else(|| anyhow!("Printer {} is not connected", serial))
+    }
+}
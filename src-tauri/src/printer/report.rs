@@ -0,0 +1,375 @@
+//! Typed parser for `device/<serial>/report` payloads.
+//!
+//! Printers publish JSON objects keyed by subsystem; only the `print`
+//! object carries telemetry. X1-series printers send the full state on
+//! every report, while P1/A1 printers send only the fields that changed
+//! since the last one. Every field is therefore optional here, and
+//! [`PrinterStatus::apply`] merges each report into the last known state.
+//!
+//! The firmware is loose with types: the same field can arrive as a number
+//! on one model and a numeric string on another (`"temp": "26.5"`,
+//! `"cooling_fan_speed": "15"`), so numeric fields go through
+//! [`lenient_f64`].
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// `tray_now` value meaning no filament is loaded.
+pub const TRAY_NONE: &str = "255";
+
+/// `tray_now` / tray id of the external spool holder.
+pub const TRAY_EXTERNAL: &str = "254";
+
+/// Top-level report message. Other subsystems (`info`, `system`, ...)
+/// are ignored.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ReportMessage {
+    pub print: Option<PrintReport>,
+}
+
+/// The `print` object of a report.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PrintReport {
+    pub command: Option<String>,
+    pub sequence_id: Option<String>,
+    #[serde(default, deserialize_with = "lenient_f64")]
+    pub nozzle_temper: Option<f64>,
+    #[serde(default, deserialize_with = "lenient_f64")]
+    pub nozzle_target_temper: Option<f64>,
+    #[serde(default, deserialize_with = "lenient_f64")]
+    pub bed_temper: Option<f64>,
+    #[serde(default, deserialize_with = "lenient_f64")]
+    pub bed_target_temper: Option<f64>,
+    #[serde(default, deserialize_with = "lenient_f64")]
+    pub chamber_temper: Option<f64>,
+    /// IDLE, PREPARE, RUNNING, PAUSE, FINISH or FAILED.
+    pub gcode_state: Option<String>,
+    #[serde(default, deserialize_with = "lenient_f64")]
+    pub mc_percent: Option<f64>,
+    /// Minutes.
+    #[serde(default, deserialize_with = "lenient_f64")]
+    pub mc_remaining_time: Option<f64>,
+    #[serde(default, deserialize_with = "lenient_f64")]
+    pub layer_num: Option<f64>,
+    #[serde(default, deserialize_with = "lenient_f64")]
+    pub total_layer_num: Option<f64>,
+    pub subtask_name: Option<String>,
+    pub gcode_file: Option<String>,
+    /// 1 silent, 2 standard, 3 sport, 4 ludicrous.
+    #[serde(default, deserialize_with = "lenient_f64")]
+    pub spd_lvl: Option<f64>,
+    /// Part cooling fan on a 0-15 scale.
+    #[serde(default, deserialize_with = "lenient_f64")]
+    pub cooling_fan_speed: Option<f64>,
+    #[serde(default, deserialize_with = "lenient_f64")]
+    pub print_error: Option<f64>,
+    pub wifi_signal: Option<String>,
+    pub nozzle_diameter: Option<String>,
+    pub nozzle_type: Option<String>,
+    pub ams: Option<AmsReport>,
+    /// External spool holder.
+    pub vt_tray: Option<TrayReport>,
+}
+
+/// The `print.ams` object.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AmsReport {
+    pub ams: Option<Vec<AmsUnitReport>>,
+    /// Global tray index currently feeding the extruder (`ams_id * 4 + slot`),
+    /// [`TRAY_EXTERNAL`] or [`TRAY_NONE`].
+    pub tray_now: Option<String>,
+}
+
+/// One AMS unit in `print.ams.ams`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AmsUnitReport {
+    pub id: Option<String>,
+    /// Humidity index 1 (wet) to 5 (dry).
+    pub humidity: Option<String>,
+    #[serde(default, deserialize_with = "lenient_f64")]
+    pub temp: Option<f64>,
+    #[serde(default)]
+    pub tray: Vec<TrayReport>,
+}
+
+/// One tray (AMS slot or external spool). Empty slots only carry `id`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct TrayReport {
+    pub id: Option<String>,
+    pub tray_type: Option<String>,
+    pub tray_sub_brands: Option<String>,
+    /// RRGGBBAA hex.
+    pub tray_color: Option<String>,
+    /// Filament preset id (`filament_id` in profiles), e.g. "GFA00".
+    pub tray_info_idx: Option<String>,
+    #[serde(default, deserialize_with = "lenient_f64")]
+    pub nozzle_temp_min: Option<f64>,
+    #[serde(default, deserialize_with = "lenient_f64")]
+    pub nozzle_temp_max: Option<f64>,
+    /// Remaining filament in percent, -1 when unknown.
+    #[serde(default, deserialize_with = "lenient_f64")]
+    pub remain: Option<f64>,
+    /// Pressure advance calibrated for this tray.
+    #[serde(default, deserialize_with = "lenient_f64")]
+    pub k: Option<f64>,
+    pub tag_uid: Option<String>,
+    pub tray_uuid: Option<String>,
+}
+
+/// Parse a raw report payload.
+pub fn parse_report(payload: &[u8]) -> Result<ReportMessage> {
+    serde_json::from_slice(payload).context("Invalid printer report JSON")
+}
+
+/// Accept a JSON number or a numeric string; anything else becomes `None`.
+fn lenient_f64<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<f64>, D::Error> {
+    Ok(match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::Number(n) => n.as_f64(),
+        serde_json::Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    })
+}
+
+/// Last known printer state, merged from every report received so far.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PrinterStatus {
+    pub nozzle_temp: Option<f64>,
+    pub nozzle_target_temp: Option<f64>,
+    pub bed_temp: Option<f64>,
+    pub bed_target_temp: Option<f64>,
+    pub chamber_temp: Option<f64>,
+    pub gcode_state: Option<String>,
+    pub print_percent: Option<u8>,
+    pub remaining_minutes: Option<u32>,
+    pub layer: Option<u32>,
+    pub total_layers: Option<u32>,
+    pub job_name: Option<String>,
+    pub speed_level: Option<u8>,
+    pub part_fan_percent: Option<u8>,
+    pub print_error: Option<i64>,
+    pub wifi_signal: Option<String>,
+    pub nozzle_diameter: Option<String>,
+    pub nozzle_type: Option<String>,
+    pub ams: Vec<AmsUnit>,
+    /// Global index of the tray feeding the extruder, `None` when unloaded.
+    pub active_tray: Option<String>,
+    pub external_spool: Option<AmsTray>,
+    /// RFC 3339 time of the last applied report.
+    pub updated_at: Option<String>,
+}
+
+/// An AMS unit as shown to the frontend.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AmsUnit {
+    pub id: String,
+    pub humidity: Option<String>,
+    pub temperature: Option<f64>,
+    pub trays: Vec<AmsTray>,
+}
+
+/// A loaded (or empty) tray as shown to the frontend.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AmsTray {
+    pub id: String,
+    /// None for an empty slot.
+    pub filament_type: Option<String>,
+    pub sub_brand: Option<String>,
+    /// RRGGBBAA hex.
+    pub color: Option<String>,
+    pub filament_id: Option<String>,
+    pub nozzle_temp_min: Option<u16>,
+    pub nozzle_temp_max: Option<u16>,
+    /// None when the printer can't tell (non-RFID spools report -1).
+    pub remain_percent: Option<u8>,
+    pub k: Option<f64>,
+    pub tag_uid: Option<String>,
+}
+
+impl AmsTray {
+    fn from_report(tray: &TrayReport) -> Self {
+        // Empty slots report tray_type "" on some firmware instead of omitting it
+        let non_empty = |s: &Option<String>| s.clone().filter(|v| !v.is_empty());
+        Self {
+            id: tray.id.clone().unwrap_or_default(),
+            filament_type: non_empty(&tray.tray_type),
+            sub_brand: non_empty(&tray.tray_sub_brands),
+            color: non_empty(&tray.tray_color),
+            filament_id: non_empty(&tray.tray_info_idx),
+            nozzle_temp_min: tray.nozzle_temp_min.map(|v| v as u16),
+            nozzle_temp_max: tray.nozzle_temp_max.map(|v| v as u16),
+            remain_percent: tray
+                .remain
+                .filter(|v| (0.0..=100.0).contains(v))
+                .map(|v| v as u8),
+            k: tray.k,
+            tag_uid: non_empty(&tray.tag_uid).filter(|uid| uid.chars().any(|c| c != '0')),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.filament_type.is_none()
+    }
+}
+
+impl PrinterStatus {
+    /// Merge one report into the current state. Fields absent from the
+    /// report keep their previous value; an `ams` list replaces the
+    /// previous one wholesale since units are always reported complete.
+    pub fn apply(&mut self, report: &PrintReport) {
+        fn set<T>(slot: &mut Option<T>, value: Option<T>) {
+            if value.is_some() {
+                *slot = value;
+            }
+        }
+
+        set(&mut self.nozzle_temp, report.nozzle_temper);
+        set(&mut self.nozzle_target_temp, report.nozzle_target_temper);
+        set(&mut self.bed_temp, report.bed_temper);
+        set(&mut self.bed_target_temp, report.bed_target_temper);
+        set(&mut self.chamber_temp, report.chamber_temper);
+        set(&mut self.gcode_state, report.gcode_state.clone());
+        set(
+            &mut self.print_percent,
+            report.mc_percent.map(|v| v.clamp(0.0, 100.0) as u8),
+        );
+        set(
+            &mut self.remaining_minutes,
+            report.mc_remaining_time.map(|v| v.max(0.0) as u32),
+        );
+        set(&mut self.layer, report.layer_num.map(|v| v.max(0.0) as u32));
+        set(
+            &mut self.total_layers,
+            report.total_layer_num.map(|v| v.max(0.0) as u32),
+        );
+        set(
+            &mut self.job_name,
+            report
+                .subtask_name
+                .clone()
+                .filter(|s| !s.is_empty())
+                .or_else(|| report.gcode_file.clone().filter(|s| !s.is_empty())),
+        );
+        set(&mut self.speed_level, report.spd_lvl.map(|v| v as u8));
+        set(
+            &mut self.part_fan_percent,
+            report
+                .cooling_fan_speed
+                .map(|v| ((v.clamp(0.0, 15.0) / 15.0) * 100.0).round() as u8),
+        );
+        set(&mut self.print_error, report.print_error.map(|v| v as i64));
+        set(&mut self.wifi_signal, report.wifi_signal.clone());
+        set(&mut self.nozzle_diameter, report.nozzle_diameter.clone());
+        set(&mut self.nozzle_type, report.nozzle_type.clone());
+
+        if let Some(ams) = &report.ams {
+            if let Some(units) = &ams.ams {
+                self.ams = units
+                    .iter()
+                    .map(|unit| AmsUnit {
+                        id: unit.id.clone().unwrap_or_default(),
+                        humidity: unit.humidity.clone(),
+                        temperature: unit.temp,
+                        trays: unit.tray.iter().map(AmsTray::from_report).collect(),
+                    })
+                    .collect();
+            }
+            if let Some(tray_now) = &ams.tray_now {
+                self.active_tray = (tray_now != TRAY_NONE).then(|| tray_now.clone());
+            }
+        }
+        if let Some(vt) = &report.vt_tray {
+            self.external_spool = Some(AmsTray::from_report(vt));
+        }
+
+        self.updated_at = Some(chrono::Utc::now().to_rfc3339());
+    }
+
+    /// The tray currently feeding the extruder, if known.
+    pub fn active_tray(&self) -> Option<&AmsTray> {
+        let active = self.active_tray.as_deref()?;
+        if active == TRAY_EXTERNAL {
+            return self.external_spool.as_ref();
+        }
+        let index: usize = active.parse().ok()?;
+        let unit_id = (index / 4).to_string();
+        let slot_id = (index % 4).to_string();
+        self.ams
+            .iter()
+            .find(|u| u.id == unit_id)?
+            .trays
+            .iter()
+            .find(|t| t.id == slot_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lenient_numbers() {
+        let msg = parse_report(
+            br#"{"print": {"nozzle_temper": 219.5, "bed_temper": "55", "cooling_fan_speed": "15", "mc_percent": "bad"}}"#,
+        )
+        .unwrap();
+        let print = msg.print.unwrap();
+        assert_eq!(print.nozzle_temper, Some(219.5));
+        assert_eq!(print.bed_temper, Some(55.0));
+        assert_eq!(print.cooling_fan_speed, Some(15.0));
+        assert_eq!(print.mc_percent, None);
+    }
+
+    #[test]
+    fn test_non_print_messages_parse() {
+        let msg = parse_report(br#"{"info": {"command": "get_version", "module": []}}"#).unwrap();
+        assert!(msg.print.is_none());
+        assert!(parse_report(b"not json").is_err());
+    }
+
+    #[test]
+    fn test_apply_merges_deltas() {
+        let mut status = PrinterStatus::default();
+        let full = parse_report(
+            br#"{"print": {"nozzle_temper": 25.0, "bed_temper": 24.0, "gcode_state": "IDLE", "cooling_fan_speed": "0"}}"#,
+        )
+        .unwrap();
+        status.apply(full.print.as_ref().unwrap());
+
+        let delta =
+            parse_report(br#"{"print": {"nozzle_temper": 180.0, "cooling_fan_speed": "8"}}"#)
+                .unwrap();
+        status.apply(delta.print.as_ref().unwrap());
+
+        assert_eq!(status.nozzle_temp, Some(180.0));
+        assert_eq!(status.bed_temp, Some(24.0));
+        assert_eq!(status.gcode_state.as_deref(), Some("IDLE"));
+        assert_eq!(status.part_fan_percent, Some(53));
+        assert!(status.updated_at.is_some());
+    }
+
+    #[test]
+    fn test_active_tray_lookup() {
+        let msg = parse_report(
+            br#"{"print": {"ams": {"tray_now": "5", "ams": [
+                {"id": "0", "tray": [{"id": "0"}]},
+                {"id": "1", "tray": [{"id": "0", "tray_type": ""}, {"id": "1", "tray_type": "PETG", "tray_info_idx": "GFG99", "remain": -1, "tag_uid": "0000000000000000"}]}
+            ]}}}"#,
+        )
+        .unwrap();
+        let mut status = PrinterStatus::default();
+        status.apply(msg.print.as_ref().unwrap());
+
+        let tray = status.active_tray().expect("tray 5 is unit 1 slot 1");
+        assert_eq!(tray.filament_type.as_deref(), Some("PETG"));
+        assert_eq!(tray.filament_id.as_deref(), Some("GFG99"));
+        assert_eq!(tray.remain_percent, None);
+        assert_eq!(tray.tag_uid, None);
+        assert!(status.ams[1].trays[0].is_empty());
+
+        let unload = parse_report(br#"{"print": {"ams": {"tray_now": "255"}}}"#).unwrap();
+        status.apply(unload.print.as_ref().unwrap());
+        assert!(status.active_tray.is_none());
+        // AMS units survive a report without an ams list
+        assert_eq!(status.ams.len(), 2);
+    }
+}
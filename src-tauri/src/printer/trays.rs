@@ -0,0 +1,230 @@
+//! Map AMS trays to installed filament profiles.
+//!
+//! The printer identifies what's loaded in each tray by `tray_info_idx`,
+//! which is the `filament_id` of the preset chosen for it (read from the
+//! RFID tag for Bambu spools, set by hand in the slicer or on the printer
+//! otherwise). Resolving a tray means finding the profiles with that id.
+
+use serde::Serialize;
+
+use super::report::{AmsTray, PrinterStatus, TRAY_EXTERNAL};
+use crate::profile::registry::ProfileRegistry;
+use crate::scraper::types::FilamentSpecs;
+
+/// Outcome of resolving one tray.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrayMatchState {
+    /// Nothing loaded.
+    Empty,
+    /// At least one installed profile has the tray's `filament_id`.
+    Matched,
+    /// Loaded, but no installed profile has its `filament_id` (or the tray
+    /// has none). Offer to generate one from `suggested_specs`.
+    Unmatched,
+}
+
+/// An installed profile a tray resolves to.
+#[derive(Debug, Clone, Serialize)]
+pub struct MatchedProfile {
+    pub name: String,
+    pub is_user_profile: bool,
+    pub path: Option<String>,
+}
+
+/// One AMS tray (or the external spool) and the profiles it resolves to.
+#[derive(Debug, Clone, Serialize)]
+pub struct TrayResolution {
+    /// Label as shown on the printer: `A1`..`D4`, or `Ext`.
+    pub slot: String,
+    /// AMS unit id; None for the external spool.
+    pub ams_id: Option<String>,
+    pub tray: AmsTray,
+    pub state: TrayMatchState,
+    /// Matching profiles, user profiles first.
+    pub profiles: Vec<MatchedProfile>,
+    /// For unmatched trays: specs to pass to `generate_profile_from_specs`,
+    /// together with the tray's `filament_id` as `existing_filament_id`
+    /// so the new profile resolves for this tray next time.
+    pub suggested_specs: Option<FilamentSpecs>,
+}
+
+/// Resolve every AMS tray and the external spool in `status`.
+pub fn resolve_trays(status: &PrinterStatus, registry: &ProfileRegistry) -> Vec<TrayResolution> {
+    let mut resolutions = Vec::new();
+    for unit in &status.ams {
+        for tray in &unit.trays {
+            resolutions.push(resolve_tray(
+                slot_label(Some(&unit.id), &tray.id),
+                Some(unit.id.clone()),
+                tray,
+                registry,
+            ));
+        }
+    }
+    if let Some(external) = &status.external_spool {
+        resolutions.push(resolve_tray(
+            slot_label(None, TRAY_EXTERNAL),
+            None,
+            external,
+            registry,
+        ));
+    }
+    resolutions
+}
+
+fn resolve_tray(
+    slot: String,
+    ams_id: Option<String>,
+    tray: &AmsTray,
+    registry: &ProfileRegistry,
+) -> TrayResolution {
+    let profiles: Vec<MatchedProfile> = match &tray.filament_id {
+        Some(id) if !tray.is_empty() => registry
+            .find_by_filament_id(id)
+            .into_iter()
+            .filter_map(|p| {
+                let name = p.name()?.to_string();
+                let source = registry.source(&name);
+                Some(MatchedProfile {
+                    is_user_profile: source.is_some_and(|s| s.is_user),
+                    path: source.map(|s| s.path.to_string_lossy().to_string()),
+                    name,
+                })
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    let state = if tray.is_empty() {
+        TrayMatchState::Empty
+    } else if profiles.is_empty() {
+        TrayMatchState::Unmatched
+    } else {
+        TrayMatchState::Matched
+    };
+    TrayResolution {
+        slot,
+        ams_id,
+        suggested_specs: (state == TrayMatchState::Unmatched).then(|| specs_from_tray(tray)),
+        tray: tray.clone(),
+        state,
+        profiles,
+    }
+}
+
+/// Printer-style slot label: AMS unit 0 tray 0 is `A1`, unit 1 tray 3 is
+/// `B4`. The external spool is `Ext`.
+pub fn slot_label(ams_id: Option<&str>, tray_id: &str) -> String {
+    let Some(ams_id) = ams_id else {
+        return "Ext".to_string();
+    };
+    let unit = ams_id.parse::<u8>().ok().filter(|u| *u < 26);
+    let tray = tray_id.parse::<u8>().ok();
+    match (unit, tray) {
+        (Some(unit), Some(tray)) => format!("{}{}", (b'A' + unit) as char, tray + 1),
+        _ => format!("{}-{}", ams_id, tray_id),
+    }
+}
+
+/// Starting specs for a tray that has no profile.
+///
+/// Trays only report material, sub-brand and a nozzle temperature range,
+/// so everything else comes from the material's base profile when the
+/// specs are generated. Spools with an RFID tag are Bambu's own.
+pub fn specs_from_tray(tray: &AmsTray) -> FilamentSpecs {
+    let material = tray.filament_type.clone().unwrap_or_default();
+    let serial = tray
+        .sub_brand
+        .as_deref()
+        .map(|s| {
+            s.strip_prefix(material.as_str())
+                .unwrap_or(s)
+                .trim()
+                .to_string()
+        })
+        .unwrap_or_default();
+    let brand = if tray.tag_uid.is_some() {
+        "Bambu Lab"
+    } else {
+        "Generic"
+    };
+    let nozzle_temperature = match (tray.nozzle_temp_min, tray.nozzle_temp_max) {
+        (Some(min), Some(max)) if min <= max => Some((min + max) / 2),
+        _ => None,
+    };
+    FilamentSpecs {
+        serial,
+        brand: brand.to_string(),
+        material,
+        nozzle_temp_min: tray.nozzle_temp_min,
+        nozzle_temp_max: tray.nozzle_temp_max,
+        nozzle_temperature,
+        pressure_advance: tray.k.map(|k| k as f32),
+        source_url: "printer".to_string(),
+        extraction_confidence: 1.0,
+        ..FilamentSpecs::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tray(filament_type: &str, sub_brand: Option<&str>) -> AmsTray {
+        AmsTray {
+            id: "0".to_string(),
+            filament_type: Some(filament_type.to_string()),
+            sub_brand: sub_brand.map(str::to_string),
+            nozzle_temp_min: Some(220),
+            nozzle_temp_max: Some(260),
+            k: Some(0.04),
+            ..AmsTray::default()
+        }
+    }
+
+    #[test]
+    fn test_slot_labels() {
+        assert_eq!(slot_label(Some("0"), "0"), "A1");
+        assert_eq!(slot_label(Some("1"), "3"), "B4");
+        assert_eq!(slot_label(None, "254"), "Ext");
+        // AMS HT units report ids from 128 up
+        assert_eq!(slot_label(Some("128"), "0"), "128-0");
+    }
+
+    #[test]
+    fn test_specs_from_tray() {
+        let specs = specs_from_tray(&tray("PETG", Some("PETG HF")));
+        assert_eq!(specs.material, "PETG");
+        assert_eq!(specs.serial, "HF");
+        assert_eq!(specs.brand, "Generic");
+        assert_eq!(specs.nozzle_temperature, Some(240));
+        assert_eq!(specs.pressure_advance, Some(0.04));
+
+        let mut tagged = tray("PLA", None);
+        tagged.tag_uid = Some("A1B2C3D4E5F60708".to_string());
+        let specs = specs_from_tray(&tagged);
+        assert_eq!(specs.brand, "Bambu Lab");
+        assert_eq!(specs.serial, "");
+    }
+
+    #[test]
+    fn test_empty_tray_has_no_suggestion() {
+        let resolution = resolve_tray(
+            "A3".to_string(),
+            Some("0".to_string()),
+            &AmsTray::default(),
+            &ProfileRegistry::new(),
+        );
+        assert_eq!(resolution.state, TrayMatchState::Empty);
+        assert!(resolution.suggested_specs.is_none());
+
+        let resolution = resolve_tray(
+            "A4".to_string(),
+            Some("0".to_string()),
+            &tray("PLA", None),
+            &ProfileRegistry::new(),
+        );
+        assert_eq!(resolution.state, TrayMatchState::Unmatched);
+        assert!(resolution.suggested_specs.is_some());
+    }
+}
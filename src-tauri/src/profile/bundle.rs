@@ -0,0 +1,818 @@
+//! Bambu Studio preset bundles (`.bbsflmt` / `.bbscfg`).
+//!
+//! Both formats are plain zip archives with a `bundle_structure.json`
+//! manifest at the root and one JSON file per preset:
+//!
+//! - `.bbsflmt` (filament bundle): `<vendor>/<name>.json`, listed per vendor
+//!   under `printer_vendor[].filament_path`.
+//! - `.bbscfg` (printer config bundle): `printer/`, `filament/` and
+//!   `process/` folders listed under `printer_config`, `filament_config` and
+//!   `process_config`.
+//!
+//! Only filament presets are imported; printer and process presets in a
+//! `.bbscfg` are counted and reported but left alone. Exported bundles are
+//! always filament bundles.
+
+use std::collections::HashSet;
+use std::io::{Read, Seek, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use tempfile::NamedTempFile;
+use tracing::{debug, info, warn};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use super::reader::{read_profile, read_profile_metadata};
+use super::registry::ProfileRegistry;
+use super::types::{FilamentProfile, ProfileMetadata};
+use super::writer::{InstallTransaction, INSTALL_JOURNAL_DIR};
+
+/// File extension of a filament bundle.
+pub const FILAMENT_BUNDLE_EXT: &str = "bbsflmt";
+
+/// File extension of a printer config bundle.
+pub const PRINTER_BUNDLE_EXT: &str = "bbscfg";
+
+/// Name of the manifest at the root of every bundle.
+const STRUCTURE_FILE: &str = "bundle_structure.json";
+
+/// Vendor folder used for exported filament bundles.
+const EXPORT_VENDOR: &str = "BBL";
+
+/// Refuse to read bundle entries larger than this (a real preset is a few KB).
+const MAX_ENTRY_SIZE: u64 = 4 * 1024 * 1024;
+
+/// Which kind of bundle an archive is, from its manifest's `bundle_type`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BundleType {
+    /// `"filament config bundle"` (`.bbsflmt`)
+    Filament,
+    /// `"printer config bundle"` (`.bbscfg`)
+    Printer,
+}
+
+impl BundleType {
+    fn from_manifest(value: &str) -> Option<Self> {
+        match value {
+            "filament config bundle" => Some(Self::Filament),
+            "printer config bundle" => Some(Self::Printer),
+            _ => None,
+        }
+    }
+}
+
+/// A filament preset unpacked from a bundle.
+pub struct BundleEntry {
+    /// Path of the JSON file inside the archive.
+    pub archive_path: String,
+    pub profile: FilamentProfile,
+    /// Taken from a companion `.info` entry when the bundle has one,
+    /// otherwise defaulted.
+    pub metadata: ProfileMetadata,
+}
+
+/// The filament presets of a bundle plus what its manifest says about it.
+pub struct Bundle {
+    pub bundle_type: BundleType,
+    pub bundle_id: Option<String>,
+    /// Bambu Studio version that wrote the bundle.
+    pub version: Option<String>,
+    pub filaments: Vec<BundleEntry>,
+    /// Printer and process presets in a `.bbscfg` that were not imported.
+    pub skipped_presets: usize,
+}
+
+/// Read a bundle file from disk.
+pub fn read_bundle(path: &Path) -> Result<Bundle> {
+    let file =
+        std::fs::File::open(path).with_context(|| format!("Failed to open bundle {:?}", path))?;
+    let bundle = read_bundle_from(file).with_context(|| format!("Invalid bundle {:?}", path))?;
+    info!(
+        "Read {:?} bundle {:?} with {} filament presets",
+        bundle.bundle_type,
+        path,
+        bundle.filaments.len()
+    );
+    Ok(bundle)
+}
+
+/// Read a bundle from any seekable reader (a file, or bytes in memory).
+pub fn read_bundle_from<R: Read + Seek>(reader: R) -> Result<Bundle> {
+    let mut archive = ZipArchive::new(reader).context("Not a zip archive")?;
+
+    let structure: serde_json::Value = serde_json::from_str(
+        &read_entry(&mut archive, STRUCTURE_FILE)?
+            .ok_or_else(|| anyhow::anyhow!("Missing {}", STRUCTURE_FILE))?,
+    )
+    .with_context(|| format!("Failed to parse {}", STRUCTURE_FILE))?;
+
+    let type_str = structure
+        .get("bundle_type")
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+    let bundle_type = BundleType::from_manifest(type_str)
+        .ok_or_else(|| anyhow::anyhow!("Unsupported bundle_type '{}'", type_str))?;
+
+    let (filament_paths, skipped_presets) = match bundle_type {
+        BundleType::Filament => {
+            let paths = structure
+                .get("printer_vendor")
+                .and_then(|v| v.as_array())
+                .into_iter()
+                .flatten()
+                .flat_map(|vendor| string_list(vendor.get("filament_path")))
+                .collect::<Vec<_>>();
+            (paths, 0)
+        }
+        BundleType::Printer => {
+            let skipped = string_list(structure.get("printer_config")).len()
+                + string_list(structure.get("process_config")).len();
+            (string_list(structure.get("filament_config")), skipped)
+        }
+    };
+
+    // A filament bundle repeats the same preset once per vendor folder;
+    // keep the first copy of each name.
+    let mut seen = HashSet::new();
+    let mut filaments = Vec::new();
+    for archive_path in filament_paths {
+        let content = read_entry(&mut archive, &archive_path)?
+            .ok_or_else(|| anyhow::anyhow!("Bundle lists missing file '{}'", archive_path))?;
+        let profile = FilamentProfile::from_json(&content)
+            .with_context(|| format!("Failed to parse '{}'", archive_path))?;
+        let name = profile
+            .name()
+            .ok_or_else(|| anyhow::anyhow!("'{}' has no name field", archive_path))?
+            .to_string();
+        if !seen.insert(name.clone()) {
+            debug!(
+                "Skipping duplicate bundle entry '{}' ({})",
+                name, archive_path
+            );
+            continue;
+        }
+
+        let info_path = match archive_path.strip_suffix(".json") {
+            Some(stem) => format!("{}.info", stem),
+            None => format!("{}.info", archive_path),
+        };
+        let metadata = match read_entry(&mut archive, &info_path)? {
+            Some(info) => ProfileMetadata::from_info_string(&info)?,
+            None => ProfileMetadata::default(),
+        };
+
+        filaments.push(BundleEntry {
+            archive_path,
+            profile,
+            metadata,
+        });
+    }
+
+    Ok(Bundle {
+        bundle_type,
+        bundle_id: structure
+            .get("bundle_id")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string()),
+        version: structure
+            .get("version")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string()),
+        filaments,
+        skipped_presets,
+    })
+}
+
+fn string_list(value: Option<&serde_json::Value>) -> Vec<String> {
+    value
+        .and_then(|v| v.as_array())
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|v| v.as_str().map(|s| s.to_string()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Read a UTF-8 entry by name. Returns `Ok(None)` if the entry is absent.
+fn read_entry<R: Read + Seek>(archive: &mut ZipArchive<R>, name: &str) -> Result<Option<String>> {
+    let entry = match archive.by_name(name) {
+        Ok(entry) => entry,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    if entry.size() > MAX_ENTRY_SIZE {
+        bail!(
+            "Bundle entry '{}' is too large ({} bytes)",
+            name,
+            entry.size()
+        );
+    }
+    let mut content = String::new();
+    entry
+        .take(MAX_ENTRY_SIZE)
+        .read_to_string(&mut content)
+        .with_context(|| format!("Failed to read bundle entry '{}'", name))?;
+    Ok(Some(content))
+}
+
+/// How an import handles a preset whose name is already taken.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictPolicy {
+    /// Leave the existing preset alone and skip the imported one.
+    #[default]
+    Skip,
+    /// Replace the existing user preset. System presets are never replaced.
+    Overwrite,
+    /// Install the imported preset under a new, unused name.
+    Rename,
+}
+
+/// Why an imported preset collides with what is already installed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictKind {
+    /// A user preset with the same name already exists.
+    UserPreset,
+    /// A system preset has the same name (Bambu Studio would shadow it).
+    SystemPreset,
+}
+
+/// What happened (or, in a preview, would happen) to one bundle entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportAction {
+    Install,
+    Overwrite,
+    Rename,
+    Skip,
+}
+
+/// Per-preset result of planning or running an import.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportItem {
+    /// Name of the preset as stored in the bundle.
+    pub name: String,
+    /// Name it is (or would be) installed under.
+    pub installed_name: String,
+    pub target_path: String,
+    pub action: ImportAction,
+    pub conflict: Option<ConflictKind>,
+    /// Parent the preset inherits from, if it is not installed locally.
+    pub missing_parent: Option<String>,
+}
+
+/// Work out what importing `bundle` into `user_dir` would do, without
+/// writing anything.
+///
+/// Conflicts are checked by preset name against the user presets already
+/// in `user_dir` and, when given, the system registry. Entries whose
+/// `inherits` parent is in neither are flagged via `missing_parent`;
+/// Bambu Studio hides such presets until the parent shows up.
+pub fn plan_import(
+    bundle: &Bundle,
+    user_dir: &Path,
+    system: Option<&ProfileRegistry>,
+    policy: ConflictPolicy,
+) -> Result<Vec<ImportItem>> {
+    let user_files = user_files_by_name(user_dir)?;
+    let is_system = |name: &str| system.is_some_and(|r| r.get_by_name(name).is_some());
+
+    let mut taken: HashSet<String> = user_files.iter().map(|(n, _)| n.clone()).collect();
+    let mut items = Vec::with_capacity(bundle.filaments.len());
+
+    for entry in &bundle.filaments {
+        let name = entry.profile.name().unwrap_or_default().to_string();
+        validate_preset_name(&name)?;
+
+        let conflict = if is_system(&name) {
+            Some(ConflictKind::SystemPreset)
+        } else if taken.contains(&name) {
+            Some(ConflictKind::UserPreset)
+        } else {
+            None
+        };
+
+        let (action, installed_name) = match (conflict, policy) {
+            (None, _) => (ImportAction::Install, name.clone()),
+            (Some(_), ConflictPolicy::Skip) => (ImportAction::Skip, name.clone()),
+            (Some(ConflictKind::UserPreset), ConflictPolicy::Overwrite) => {
+                (ImportAction::Overwrite, name.clone())
+            }
+            (Some(ConflictKind::SystemPreset), ConflictPolicy::Overwrite)
+            | (Some(_), ConflictPolicy::Rename) => {
+                let new_name = unique_name(&name, |n| taken.contains(n) || is_system(n));
+                (ImportAction::Rename, new_name)
+            }
+        };
+
+        // Overwrites go to the file that already holds the preset, which
+        // may not be named after it (e.g. BambuMate's own filament_id names).
+        let target_path = match action {
+            ImportAction::Overwrite => user_files
+                .iter()
+                .find(|(n, _)| *n == name)
+                .map(|(_, p)| p.clone())
+                .unwrap_or_else(|| user_dir.join(format!("{}.json", name))),
+            _ => user_dir.join(format!("{}.json", installed_name)),
+        };
+
+        let missing_parent = entry
+            .profile
+            .inherits()
+            .filter(|p| !p.is_empty() && !is_system(p) && !taken.contains(*p))
+            .filter(|p| !bundle.filaments.iter().any(|e| e.profile.name() == Some(p)))
+            .map(|p| p.to_string());
+
+        if action != ImportAction::Skip {
+            taken.insert(installed_name.clone());
+        }
+        items.push(ImportItem {
+            name,
+            installed_name,
+            target_path: target_path.to_string_lossy().to_string(),
+            action,
+            conflict,
+            missing_parent,
+        });
+    }
+
+    Ok(items)
+}
+
+/// Import the filament presets of `bundle` into `user_dir` and register
+/// them in the BambuStudio.conf under `config_root`.
+///
+/// Follows [`plan_import`], installing every non-skipped preset, its
+/// metadata and the registrations in one [`InstallTransaction`], so a
+/// failure part way leaves none of them behind. Account-specific metadata
+/// (`sync_info`, `user_id`, `setting_id`) from the source machine is
+/// dropped so Bambu Studio treats the presets as new local ones; `base_id`
+/// is kept. A preset overwritten in place keeps its own metadata (see
+/// `ProfileMetadata::mark_written_over`). Returns the executed plan.
+pub fn install_bundle(
+    bundle: &Bundle,
+    user_dir: &Path,
+    config_root: &Path,
+    system: Option<&ProfileRegistry>,
+    policy: ConflictPolicy,
+) -> Result<Vec<ImportItem>> {
+    let items = plan_import(bundle, user_dir, system, policy)?;
+
+    let mut transaction = InstallTransaction::begin(&user_dir.join(INSTALL_JOURNAL_DIR))?;
+    let mut stems = Vec::new();
+
+    for (entry, item) in bundle.filaments.iter().zip(&items) {
+        if item.action == ImportAction::Skip {
+            info!("Skipping bundle preset '{}' (conflict)", item.name);
+            continue;
+        }
+
+        let mut profile = FilamentProfile::from_map(entry.profile.raw().clone());
+        if item.action == ImportAction::Rename {
+            profile.set_string("name", item.installed_name.clone());
+            if profile.raw().contains_key("filament_settings_id") {
+                profile.set_string_array("filament_settings_id", vec![item.installed_name.clone()]);
+            }
+        }
+
+        let target_path = PathBuf::from(&item.target_path);
+        let mut metadata = ProfileMetadata {
+            base_id: entry.metadata.base_id.clone(),
+            ..ProfileMetadata::default()
+        };
+        metadata.mark_written_over(read_profile_metadata(&target_path)?);
+
+        transaction
+            .stage_profile(&profile, &target_path)
+            .and_then(|()| transaction.stage_metadata(&metadata, &target_path))
+            .with_context(|| format!("Failed to install '{}'", item.installed_name))?;
+        // The conf lists presets by file stem
+        stems.push(
+            target_path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or(&item.installed_name)
+                .to_string(),
+        );
+    }
+    let stems: Vec<&str> = stems.iter().map(String::as_str).collect();
+    transaction.stage_conf_registration(config_root, &stems)?;
+    transaction.commit()?;
+
+    for item in items.iter().filter(|i| i.action != ImportAction::Skip) {
+        info!(
+            "Imported bundle preset '{}' as '{}' ({:?})",
+            item.name, item.installed_name, item.action
+        );
+    }
+    Ok(items)
+}
+
+/// Map preset name to file path for every user preset in `dir`.
+fn user_files_by_name(dir: &Path) -> Result<Vec<(String, PathBuf)>> {
+    let mut files = Vec::new();
+    if !dir.exists() {
+        return Ok(files);
+    }
+    for entry in std::fs::read_dir(dir)?.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+        match read_profile(&path) {
+            Ok(profile) => {
+                if let Some(name) = profile.name() {
+                    files.push((name.to_string(), path));
+                }
+            }
+            Err(e) => warn!("Skipping unreadable profile {:?}: {}", path, e),
+        }
+    }
+    Ok(files)
+}
+
+/// Preset names become file names, so reject anything that could escape
+/// the target directory.
+fn validate_preset_name(name: &str) -> Result<()> {
+    if name.trim().is_empty() {
+        bail!("Bundle contains a preset without a name");
+    }
+    if name.contains(['/', '\\']) || name.contains("..") {
+        bail!("Bundle contains an invalid preset name: {:?}", name);
+    }
+    Ok(())
+}
+
+/// First of `name (2)`, `name (3)`, ... that `taken` rejects.
+fn unique_name(name: &str, taken: impl Fn(&str) -> bool) -> String {
+    (2..)
+        .map(|n| format!("{} ({})", name, n))
+        .find(|candidate| !taken(candidate))
+        .expect("unbounded range always yields a free name")
+}
+
+/// Package user filament presets into a `.bbsflmt` bundle at `output`.
+///
+/// Each preset is stored under `BBL/<name>.json` exactly as it is on disk
+/// (inherits-based presets stay as deltas), with its `.info` file alongside
+/// when one exists. `user_id` is written to the manifest like Bambu Studio
+/// does; pass an empty string to leave it out. The archive is written to a
+/// temp file and renamed, so a failed export never leaves a partial bundle.
+pub fn export_filament_bundle(
+    profile_paths: &[PathBuf],
+    output: &Path,
+    user_id: &str,
+) -> Result<()> {
+    if profile_paths.is_empty() {
+        bail!("No profiles selected for export");
+    }
+
+    let parent = output
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    std::fs::create_dir_all(parent)?;
+    let mut temp = NamedTempFile::new_in(parent)?;
+    write_filament_bundle(profile_paths, temp.as_file_mut(), user_id)?;
+    temp.as_file_mut().flush()?;
+    temp.persist(output)?;
+
+    info!(
+        "Exported {} profiles to bundle {:?}",
+        profile_paths.len(),
+        output
+    );
+    Ok(())
+}
+
+/// Write a `.bbsflmt` archive for `profile_paths` to any seekable writer.
+pub fn write_filament_bundle<W: Write + Seek>(
+    profile_paths: &[PathBuf],
+    writer: W,
+    user_id: &str,
+) -> Result<()> {
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    let mut zip = ZipWriter::new(writer);
+    let mut archive_paths = Vec::with_capacity(profile_paths.len());
+    let mut names: Vec<String> = Vec::with_capacity(profile_paths.len());
+
+    for path in profile_paths {
+        let profile = read_profile(path)?;
+        let name = profile
+            .name()
+            .ok_or_else(|| anyhow::anyhow!("Profile {:?} has no name field", path))?
+            .to_string();
+        validate_preset_name(&name)?;
+        if names.contains(&name) {
+            bail!("Two selected profiles are both named '{}'", name);
+        }
+
+        let archive_path = format!("{}/{}.json", EXPORT_VENDOR, name);
+        zip.start_file(archive_path.as_str(), options)?;
+        zip.write_all(profile.to_json_4space()?.as_bytes())?;
+
+        if let Some(metadata) = read_profile_metadata(path)? {
+            zip.start_file(format!("{}/{}.info", EXPORT_VENDOR, name), options)?;
+            zip.write_all(metadata.to_info_string().as_bytes())?;
+        }
+        archive_paths.push(archive_path);
+        names.push(name);
+    }
+
+    let first_name = &names[0];
+    let structure = serde_json::json!({
+        "bundle_id": format!("{}_{}_{}", user_id, first_name, Utc::now().timestamp()),
+        "bundle_type": "filament config bundle",
+        "filament_name": first_name,
+        "printer_vendor": [
+            {
+                "vendor": EXPORT_VENDOR,
+                "filament_path": archive_paths,
+            }
+        ],
+        "user_id": user_id,
+        "user_name": "",
+        "version": env!("CARGO_PKG_VERSION"),
+    });
+    zip.start_file(STRUCTURE_FILE, options)?;
+    zip.write_all(serde_json::to_string_pretty(&structure)?.as_bytes())?;
+    zip.finish()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn write_user_profile(dir: &Path, file: &str, json: &str, info: Option<&str>) -> PathBuf {
+        let path = dir.join(file);
+        std::fs::write(&path, json).unwrap();
+        if let Some(info) = info {
+            std::fs::write(path.with_extension("info"), info).unwrap();
+        }
+        path
+    }
+
+    fn bundle_with(entries: &[(&str, &str)]) -> Vec<u8> {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default();
+        for (name, content) in entries {
+            zip.start_file(*name, options).unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_export_then_read_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = write_user_profile(
+            dir.path(),
+            "a.json",
+            r#"{"name": "My PLA", "inherits": "Bambu PLA Basic @BBL X1C", "nozzle_temperature": ["215"]}"#,
+            Some("sync_info =\nuser_id = 42\nsetting_id = PFUS1\nbase_id = GFSA00\nupdated_time = 1\n"),
+        );
+        let b = write_user_profile(
+            dir.path(),
+            "b.json",
+            r#"{"name": "My PETG", "nozzle_temperature": ["245"]}"#,
+            None,
+        );
+
+        let mut buf = Cursor::new(Vec::new());
+        write_filament_bundle(&[a, b], &mut buf, "42").unwrap();
+        buf.set_position(0);
+        let bundle = read_bundle_from(buf).unwrap();
+
+        assert_eq!(bundle.bundle_type, BundleType::Filament);
+        assert_eq!(bundle.filaments.len(), 2);
+        assert_eq!(bundle.filaments[0].archive_path, "BBL/My PLA.json");
+        assert_eq!(bundle.filaments[0].metadata.base_id, "GFSA00");
+        assert_eq!(bundle.filaments[1].profile.name(), Some("My PETG"));
+        assert_eq!(bundle.filaments[1].metadata.base_id, "");
+        assert!(bundle.bundle_id.unwrap().starts_with("42_My PLA_"));
+    }
+
+    #[test]
+    fn test_read_printer_bundle_takes_filaments_only() {
+        let data = bundle_with(&[
+            (
+                STRUCTURE_FILE,
+                r#"{"bundle_type": "printer config bundle",
+                    "printer_config": ["printer/My X1C.json"],
+                    "filament_config": ["filament/My PLA.json"],
+                    "process_config": ["process/My 0.2.json", "process/My 0.28.json"]}"#,
+            ),
+            ("printer/My X1C.json", r#"{"name": "My X1C"}"#),
+            ("filament/My PLA.json", r#"{"name": "My PLA"}"#),
+            ("process/My 0.2.json", r#"{"name": "My 0.2"}"#),
+            ("process/My 0.28.json", r#"{"name": "My 0.28"}"#),
+        ]);
+
+        let bundle = read_bundle_from(Cursor::new(data)).unwrap();
+        assert_eq!(bundle.bundle_type, BundleType::Printer);
+        assert_eq!(bundle.filaments.len(), 1);
+        assert_eq!(bundle.filaments[0].profile.name(), Some("My PLA"));
+        assert_eq!(bundle.skipped_presets, 3);
+    }
+
+    #[test]
+    fn test_read_dedupes_vendor_copies() {
+        let data = bundle_with(&[
+            (
+                STRUCTURE_FILE,
+                r#"{"bundle_type": "filament config bundle",
+                    "printer_vendor": [
+                        {"vendor": "BBL", "filament_path": ["BBL/My PLA.json"]},
+                        {"vendor": "Custom", "filament_path": ["Custom/My PLA.json"]}
+                    ]}"#,
+            ),
+            ("BBL/My PLA.json", r#"{"name": "My PLA"}"#),
+            ("Custom/My PLA.json", r#"{"name": "My PLA"}"#),
+        ]);
+
+        let bundle = read_bundle_from(Cursor::new(data)).unwrap();
+        assert_eq!(bundle.filaments.len(), 1);
+        assert_eq!(bundle.filaments[0].archive_path, "BBL/My PLA.json");
+    }
+
+    #[test]
+    fn test_read_rejects_bad_bundles() {
+        assert!(read_bundle_from(Cursor::new(b"not a zip".to_vec())).is_err());
+
+        let no_manifest = bundle_with(&[("BBL/My PLA.json", r#"{"name": "My PLA"}"#)]);
+        assert!(read_bundle_from(Cursor::new(no_manifest)).is_err());
+
+        let missing_file = bundle_with(&[(
+            STRUCTURE_FILE,
+            r#"{"bundle_type": "filament config bundle",
+                "printer_vendor": [{"vendor": "BBL", "filament_path": ["BBL/Gone.json"]}]}"#,
+        )]);
+        assert!(read_bundle_from(Cursor::new(missing_file)).is_err());
+
+        let wrong_type = bundle_with(&[(STRUCTURE_FILE, r#"{"bundle_type": "plate bundle"}"#)]);
+        assert!(read_bundle_from(Cursor::new(wrong_type)).is_err());
+    }
+
+    fn sample_bundle(names: &[&str]) -> Bundle {
+        Bundle {
+            bundle_type: BundleType::Filament,
+            bundle_id: None,
+            version: None,
+            filaments: names
+                .iter()
+                .map(|name| BundleEntry {
+                    archive_path: format!("BBL/{}.json", name),
+                    profile: FilamentProfile::from_json(&format!(
+                        r#"{{"name": "{}", "inherits": "Bambu PLA Basic @BBL X1C", "filament_settings_id": ["{}"]}}"#,
+                        name, name
+                    ))
+                    .unwrap(),
+                    metadata: ProfileMetadata {
+                        user_id: "999".to_string(),
+                        base_id: "GFSA00".to_string(),
+                        ..ProfileMetadata::default()
+                    },
+                })
+                .collect(),
+            skipped_presets: 0,
+        }
+    }
+
+    #[test]
+    fn test_plan_detects_conflicts() {
+        let dir = tempfile::tempdir().unwrap();
+        write_user_profile(dir.path(), "Existing.json", r#"{"name": "Existing"}"#, None);
+        let bundle = sample_bundle(&["Existing", "Fresh"]);
+
+        let items = plan_import(&bundle, dir.path(), None, ConflictPolicy::Skip).unwrap();
+        assert_eq!(items[0].action, ImportAction::Skip);
+        assert_eq!(items[0].conflict, Some(ConflictKind::UserPreset));
+        assert_eq!(items[1].action, ImportAction::Install);
+        assert_eq!(items[1].conflict, None);
+        // No system registry was given, so the parent counts as missing
+        assert_eq!(
+            items[1].missing_parent.as_deref(),
+            Some("Bambu PLA Basic @BBL X1C")
+        );
+
+        let items = plan_import(&bundle, dir.path(), None, ConflictPolicy::Rename).unwrap();
+        assert_eq!(items[0].action, ImportAction::Rename);
+        assert_eq!(items[0].installed_name, "Existing (2)");
+    }
+
+    #[test]
+    fn test_install_overwrites_existing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        // Existing preset lives in a file not named after it
+        let existing = write_user_profile(
+            dir.path(),
+            "BambuMate_abc.json",
+            r#"{"name": "Existing", "nozzle_temperature": ["200"]}"#,
+            None,
+        );
+        let bundle = sample_bundle(&["Existing"]);
+
+        let items = install_bundle(
+            &bundle,
+            dir.path(),
+            dir.path(),
+            None,
+            ConflictPolicy::Overwrite,
+        )
+        .unwrap();
+        assert_eq!(items[0].action, ImportAction::Overwrite);
+        assert_eq!(PathBuf::from(&items[0].target_path), existing);
+
+        let written = read_profile(&existing).unwrap();
+        assert!(written.raw().get("nozzle_temperature").is_none());
+        let meta = read_profile_metadata(&existing).unwrap().unwrap();
+        assert_eq!(meta.base_id, "GFSA00");
+        assert_eq!(meta.user_id, "");
+        assert!(meta.updated_time > 0);
+    }
+
+    #[test]
+    fn test_install_registers_presets_in_conf() {
+        let dir = tempfile::tempdir().unwrap();
+        let conf_path = dir.path().join("BambuStudio.conf");
+        std::fs::write(&conf_path, "{\n  \"filaments\": []\n}\n").unwrap();
+        let bundle = sample_bundle(&["First", "Second"]);
+
+        install_bundle(
+            &bundle,
+            dir.path(),
+            dir.path(),
+            None,
+            ConflictPolicy::Rename,
+        )
+        .unwrap();
+
+        let conf = std::fs::read_to_string(&conf_path).unwrap();
+        assert!(
+            conf.contains("\"First\"") && conf.contains("\"Second\""),
+            "{}",
+            conf
+        );
+    }
+
+    #[test]
+    fn test_failed_install_leaves_no_preset_behind() {
+        let dir = tempfile::tempdir().unwrap();
+        // The second preset's metadata can't be read, so it fails after
+        // the first was staged
+        std::fs::create_dir(dir.path().join("Second.info")).unwrap();
+        let bundle = sample_bundle(&["First", "Second"]);
+
+        install_bundle(
+            &bundle,
+            dir.path(),
+            dir.path(),
+            None,
+            ConflictPolicy::Rename,
+        )
+        .unwrap_err();
+
+        assert!(!dir.path().join("First.json").exists());
+        assert!(!dir.path().join("First.info").exists());
+    }
+
+    #[test]
+    fn test_install_rename_updates_settings_id() {
+        let dir = tempfile::tempdir().unwrap();
+        write_user_profile(dir.path(), "Taken.json", r#"{"name": "Taken"}"#, None);
+        let bundle = sample_bundle(&["Taken"]);
+
+        install_bundle(
+            &bundle,
+            dir.path(),
+            dir.path(),
+            None,
+            ConflictPolicy::Rename,
+        )
+        .unwrap();
+        let renamed = read_profile(&dir.path().join("Taken (2).json")).unwrap();
+        assert_eq!(renamed.name(), Some("Taken (2)"));
+        assert_eq!(
+            renamed.get_string_array("filament_settings_id"),
+            Some(vec!["Taken (2)"])
+        );
+        // Original untouched
+        let original = read_profile(&dir.path().join("Taken.json")).unwrap();
+        assert_eq!(original.name(), Some("Taken"));
+    }
+
+    #[test]
+    fn test_plan_rejects_path_like_names() {
+        let dir = tempfile::tempdir().unwrap();
+        let bundle = sample_bundle(&["../escape"]);
+        assert!(plan_import(&bundle, dir.path(), None, ConflictPolicy::Skip).is_err());
+    }
+}
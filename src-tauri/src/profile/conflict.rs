@@ -0,0 +1,216 @@
+//! Optimistic concurrency for profile edits.
+//!
+//! Bambu Studio can rewrite a profile while BambuMate has it open. Each
+//! read hands out a [`ProfileVersion`] token; a write that carries a token
+//! is refused if the file has changed since, and the caller gets a
+//! [`ProfileConflict`] with a three-way diff (what they loaded, what they
+//! meant to write, what's on disk now) instead of clobbering the change.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeSet;
+use std::hash::Hasher;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+/// The modification time and content hash of a profile file.
+///
+/// The hash is only stable within one build of BambuMate, which is all a
+/// token handed to the frontend needs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProfileVersion {
+    /// Modification time, in milliseconds since the Unix epoch.
+    pub modified_ms: u64,
+    pub hash: u64,
+}
+
+impl ProfileVersion {
+    /// Version of the file at `path` as it is now.
+    pub fn of_file(path: &Path) -> Result<Self> {
+        let bytes = std::fs::read(path).with_context(|| format!("Failed to read {:?}", path))?;
+        Ok(Self::of_bytes(&bytes, modified_ms(path)?))
+    }
+
+    pub fn of_bytes(bytes: &[u8], modified_ms: u64) -> Self {
+        let mut hasher = DefaultHasher::new();
+        hasher.write(bytes);
+        Self {
+            modified_ms,
+            hash: hasher.finish(),
+        }
+    }
+
+    /// Opaque token for the frontend, e.g. `18c2f0a3b10-9f3c...`.
+    pub fn token(&self) -> String {
+        format!("{:x}-{:016x}", self.modified_ms, self.hash)
+    }
+
+    /// Parse a token from [`ProfileVersion::token`].
+    pub fn parse(token: &str) -> Option<Self> {
+        let (modified, hash) = token.split_once('-')?;
+        Some(Self {
+            modified_ms: u64::from_str_radix(modified, 16).ok()?,
+            hash: u64::from_str_radix(hash, 16).ok()?,
+        })
+    }
+
+    /// Whether the file at `path` still has this version's content.
+    ///
+    /// An unchanged modification time is taken as unchanged content, so
+    /// the file is only read and hashed when it has been touched.
+    pub fn is_current(&self, path: &Path) -> Result<bool> {
+        if modified_ms(path)? == self.modified_ms {
+            return Ok(true);
+        }
+        Ok(Self::of_file(path)?.hash == self.hash)
+    }
+}
+
+fn modified_ms(path: &Path) -> Result<u64> {
+    let modified = std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .with_context(|| format!("Failed to stat {:?}", path))?;
+    Ok(modified
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default())
+}
+
+/// One field that differs between the loaded, intended and on-disk
+/// versions of a profile. `None` means the field is absent.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldConflict {
+    pub key: String,
+    /// Value when the caller loaded the profile, if that's still known.
+    pub base: Option<Value>,
+    /// Value the caller was about to write.
+    pub ours: Option<Value>,
+    /// Value on disk now.
+    pub theirs: Option<Value>,
+    /// Both sides changed the field, to different values.
+    pub conflicting: bool,
+}
+
+/// A write refused because the profile changed on disk since it was loaded.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfileConflict {
+    pub message: String,
+    pub path: String,
+    /// Version token of the profile on disk, to retry against.
+    pub current_version: String,
+    /// Whether `base` values are known. Without them every difference
+    /// between `ours` and `theirs` is reported as a conflict.
+    pub has_base: bool,
+    pub fields: Vec<FieldConflict>,
+}
+
+/// Fields changed by either side since `base`, in key order.
+///
+/// Without a base, every field where `ours` and `theirs` differ is
+/// returned as conflicting.
+pub fn three_way_diff(
+    base: Option<&Map<String, Value>>,
+    ours: &Map<String, Value>,
+    theirs: &Map<String, Value>,
+) -> Vec<FieldConflict> {
+    let keys: BTreeSet<&String> = ours
+        .keys()
+        .chain(theirs.keys())
+        .chain(base.into_iter().flat_map(|b| b.keys()))
+        .collect();
+
+    keys.into_iter()
+        .filter_map(|key| {
+            let ours_value = ours.get(key);
+            let theirs_value = theirs.get(key);
+            let (changed_ours, changed_theirs) = match base {
+                Some(base) => {
+                    let base_value = base.get(key);
+                    (ours_value != base_value, theirs_value != base_value)
+                }
+                None => {
+                    let differs = ours_value != theirs_value;
+                    (differs, differs)
+                }
+            };
+            if !changed_ours && !changed_theirs {
+                return None;
+            }
+            Some(FieldConflict {
+                key: key.clone(),
+                base: base.and_then(|b| b.get(key)).cloned(),
+                ours: ours_value.cloned(),
+                theirs: theirs_value.cloned(),
+                conflicting: changed_ours && changed_theirs && ours_value != theirs_value,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn map(value: Value) -> Map<String, Value> {
+        value.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn test_version_token_round_trip_and_staleness() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("p.json");
+        std::fs::write(&path, r#"{"name": "A"}"#).unwrap();
+
+        let version = ProfileVersion::of_file(&path).unwrap();
+        assert_eq!(ProfileVersion::parse(&version.token()), Some(version));
+        assert!(version.is_current(&path).unwrap());
+
+        // Touched with the same content: still current
+        let touched = ProfileVersion {
+            modified_ms: version.modified_ms + 1,
+            ..version
+        };
+        assert!(touched.is_current(&path).unwrap());
+
+        std::fs::write(&path, r#"{"name": "B"}"#).unwrap();
+        let stale = ProfileVersion {
+            modified_ms: version.modified_ms.wrapping_sub(1),
+            ..version
+        };
+        assert!(!stale.is_current(&path).unwrap());
+        assert_eq!(ProfileVersion::parse("not a token"), None);
+    }
+
+    #[test]
+    fn test_three_way_diff_separates_one_sided_changes_from_conflicts() {
+        let base = map(json!({"a": "1", "b": "1", "c": "1", "d": "1"}));
+        let ours = map(json!({"a": "2", "b": "1", "c": "2", "d": "1"}));
+        let theirs = map(json!({"a": "1", "b": "2", "c": "3", "d": "1", "e": "1"}));
+
+        let diff = three_way_diff(Some(&base), &ours, &theirs);
+        let summary: Vec<(&str, bool)> = diff
+            .iter()
+            .map(|f| (f.key.as_str(), f.conflicting))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![("a", false), ("b", false), ("c", true), ("e", false)]
+        );
+        assert_eq!(diff[3].base, None);
+        assert_eq!(diff[3].theirs, Some(json!("1")));
+    }
+
+    #[test]
+    fn test_three_way_diff_without_base() {
+        let ours = map(json!({"a": "1", "b": "2"}));
+        let theirs = map(json!({"a": "1", "b": "3"}));
+        let diff = three_way_diff(None, &ours, &theirs);
+        assert_eq!(diff.len(), 1);
+        assert_eq!(diff[0].key, "b");
+        assert!(diff[0].conflicting);
+    }
+}
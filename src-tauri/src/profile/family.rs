@@ -0,0 +1,237 @@
+//! Filament families: the printer/nozzle variants of one physical filament.
+//!
+//! Each variant is its own file (`{brand} {material} {serial} @{printer}`),
+//! and they're tied together only by a shared `filament_id`, which is what
+//! Bambu Studio groups them by.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use super::fields::dictionary;
+use super::types::FilamentProfile;
+
+/// Whether `key` identifies a variant rather than describes the filament,
+/// so it's expected to differ within a family: an `identity` or `metadata`
+/// field in the field dictionary.
+fn is_variant_identity(key: &str) -> bool {
+    let fields = dictionary();
+    fields.is_identity(key) || fields.is_metadata(key)
+}
+
+/// One variant of a family.
+#[derive(Debug, Clone, Serialize)]
+pub struct FamilyVariant {
+    pub path: String,
+    pub name: String,
+    /// Printer preset the variant is for, if it can be told.
+    pub printer: Option<String>,
+    /// Version token of the file, to pass back when editing (see
+    /// `conflict::ProfileVersion`). Left empty here; filled in by callers
+    /// that read the files.
+    pub version: String,
+}
+
+/// A field whose value isn't the same across a family.
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldDivergence {
+    pub key: String,
+    /// One value per variant, in `FilamentFamily::variants` order; `--`
+    /// where the variant doesn't have the field.
+    pub values: Vec<String>,
+}
+
+/// Profiles sharing a `filament_id`.
+#[derive(Debug, Clone, Serialize)]
+pub struct FilamentFamily {
+    pub filament_id: String,
+    /// The variants' name without the `@printer` suffix.
+    pub display_name: String,
+    pub variants: Vec<FamilyVariant>,
+    pub divergences: Vec<FieldDivergence>,
+}
+
+/// Printer preset a variant is for: its first `compatible_printers` entry,
+/// else the part of its name after `@`.
+pub fn variant_printer(profile: &FilamentProfile) -> Option<String> {
+    profile
+        .compatible_printers()
+        .and_then(|printers| printers.first().map(|p| p.to_string()))
+        .filter(|p| !p.is_empty())
+        .or_else(|| {
+            let name = profile.name()?;
+            name.split_once(" @")
+                .map(|(_, printer)| printer.to_string())
+        })
+}
+
+/// Group `(path, profile)` pairs into families by `filament_id`.
+///
+/// Pass resolved profiles, so that variants stored as deltas compare by
+/// their effective values. Profiles without a `filament_id` are left out.
+/// Families are sorted by name, variants by printer.
+pub fn group_families(profiles: Vec<(String, FilamentProfile)>) -> Vec<FilamentFamily> {
+    let mut by_id: BTreeMap<String, Vec<(String, FilamentProfile)>> = BTreeMap::new();
+    for (path, profile) in profiles {
+        let Some(id) = profile.filament_id().filter(|id| !id.is_empty()) else {
+            continue;
+        };
+        by_id
+            .entry(id.to_string())
+            .or_default()
+            .push((path, profile));
+    }
+
+    let mut families: Vec<FilamentFamily> = by_id
+        .into_iter()
+        .map(|(filament_id, mut members)| {
+            members.sort_by_key(|(_, p)| variant_printer(p));
+            let profiles: Vec<&FilamentProfile> = members.iter().map(|(_, p)| p).collect();
+            let divergences = divergent_fields(&profiles);
+            let display_name = members
+                .first()
+                .and_then(|(_, p)| p.name())
+                .map(|name| name.split(" @").next().unwrap_or(name).to_string())
+                .unwrap_or_default();
+            let variants = members
+                .iter()
+                .map(|(path, p)| FamilyVariant {
+                    path: path.clone(),
+                    name: p.name().unwrap_or("<unnamed>").to_string(),
+                    printer: variant_printer(p),
+                    version: String::new(),
+                })
+                .collect();
+            FilamentFamily {
+                filament_id,
+                display_name,
+                variants,
+                divergences,
+            }
+        })
+        .collect();
+    families.sort_by(|a, b| a.display_name.cmp(&b.display_name));
+    families
+}
+
+/// Settings whose values differ between `profiles`, sorted by key.
+pub fn divergent_fields(profiles: &[&FilamentProfile]) -> Vec<FieldDivergence> {
+    let keys: std::collections::BTreeSet<&String> =
+        profiles.iter().flat_map(|p| p.raw().keys()).collect();
+    keys.into_iter()
+        .filter(|key| !is_variant_identity(key))
+        .filter_map(|key| {
+            let values: Vec<Option<&Value>> = profiles.iter().map(|p| p.raw().get(key)).collect();
+            if values.windows(2).all(|pair| pair[0] == pair[1]) {
+                return None;
+            }
+            Some(FieldDivergence {
+                key: key.clone(),
+                values: values.into_iter().map(display_value).collect(),
+            })
+        })
+        .collect()
+}
+
+/// Printers from `wanted` that no variant of `family` is for.
+pub fn missing_printers<'a>(family: &FilamentFamily, wanted: &'a [String]) -> Vec<&'a str> {
+    wanted
+        .iter()
+        .filter(|printer| {
+            !family
+                .variants
+                .iter()
+                .any(|v| v.printer.as_deref() == Some(printer.as_str()))
+        })
+        .map(String::as_str)
+        .collect()
+}
+
+fn display_value(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => "--".to_string(),
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Array(items)) => items
+            .iter()
+            .map(|v| v.as_str().map_or_else(|| v.to_string(), str::to_string))
+            .collect::<Vec<_>>()
+            .join(", "),
+        Some(other) => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variant(name: &str, printer: &str, extras: &str) -> (String, FilamentProfile) {
+        let json = format!(
+            r#"{{"name": "{name} @{printer}", "filament_id": "P1234567",
+                "compatible_printers": ["{printer}"]{extras}}}"#
+        );
+        (
+            format!("/user/{name} @{printer}.json"),
+            FilamentProfile::from_json(&json).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_group_families_and_divergences() {
+        let families = group_families(vec![
+            variant(
+                "Polymaker PLA Matte",
+                "Bambu Lab X1 Carbon 0.6 nozzle",
+                r#", "nozzle_temperature": ["220"], "filament_max_volumetric_speed": ["24"]"#,
+            ),
+            variant(
+                "Polymaker PLA Matte",
+                "Bambu Lab X1 Carbon 0.4 nozzle",
+                r#", "nozzle_temperature": ["220"], "filament_max_volumetric_speed": ["18"]"#,
+            ),
+            (
+                "/user/loose.json".to_string(),
+                FilamentProfile::from_json(r#"{"name": "No ID"}"#).unwrap(),
+            ),
+        ]);
+
+        assert_eq!(families.len(), 1);
+        let family = &families[0];
+        assert_eq!(family.display_name, "Polymaker PLA Matte");
+        let printers: Vec<_> = family
+            .variants
+            .iter()
+            .map(|v| v.printer.as_deref().unwrap())
+            .collect();
+        assert_eq!(
+            printers,
+            [
+                "Bambu Lab X1 Carbon 0.4 nozzle",
+                "Bambu Lab X1 Carbon 0.6 nozzle"
+            ]
+        );
+        assert_eq!(family.divergences.len(), 1);
+        assert_eq!(family.divergences[0].key, "filament_max_volumetric_speed");
+        assert_eq!(family.divergences[0].values, ["18", "24"]);
+
+        let wanted = vec![
+            "Bambu Lab X1 Carbon 0.4 nozzle".to_string(),
+            "Bambu Lab H2D 0.4 nozzle".to_string(),
+        ];
+        assert_eq!(
+            missing_printers(family, &wanted),
+            ["Bambu Lab H2D 0.4 nozzle"]
+        );
+    }
+
+    #[test]
+    fn test_variant_printer_falls_back_to_name() {
+        let profile =
+            FilamentProfile::from_json(r#"{"name": "Generic PLA @Bambu Lab A1 0.4 nozzle"}"#)
+                .unwrap();
+        assert_eq!(
+            variant_printer(&profile).as_deref(),
+            Some("Bambu Lab A1 0.4 nozzle")
+        );
+    }
+}
@@ -0,0 +1,231 @@
+//! Raw field edits: changes to any field of a filament profile, typed and
+//! checked against the field dictionary (see `profile::fields`).
+//!
+//! Edits are applied as a batch. [`check_edits`] refuses malformed edits
+//! outright and lints the edited profile, reporting only problems in the
+//! fields being edited, so an unrelated existing problem doesn't block
+//! them.
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::fields::{dictionary, Arity};
+use super::lint::{lint_profile, Diagnostic, LintOptions};
+use super::types::FilamentProfile;
+
+/// Fields with their own commands: renaming changes the file name and
+/// BambuStudio.conf, and re-parenting needs the values re-resolved.
+const PROTECTED_FIELDS: &[(&str, &str)] = &[
+    ("name", "rename the profile instead"),
+    ("inherits", "rebase or flatten the profile instead"),
+];
+
+/// Whether `key` can only be changed through its own command.
+pub fn is_protected(key: &str) -> bool {
+    PROTECTED_FIELDS.iter().any(|(k, _)| *k == key)
+}
+
+/// One change to a profile field.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum FieldEdit {
+    /// Set the field to `value`: a string, or an array of strings. `"nil"`
+    /// elements mean "use the printer's value" where the field allows it.
+    Set { key: String, value: Value },
+    /// Remove the field, so its value comes from the parent profile.
+    Inherit { key: String },
+}
+
+impl FieldEdit {
+    pub fn key(&self) -> &str {
+        match self {
+            FieldEdit::Set { key, .. } | FieldEdit::Inherit { key } => key,
+        }
+    }
+}
+
+/// Apply `edits` to `profile` in order.
+pub fn apply_edits(profile: &mut FilamentProfile, edits: &[FieldEdit]) {
+    let raw = profile.raw_mut();
+    for edit in edits {
+        match edit {
+            FieldEdit::Set { key, value } => {
+                raw.insert(key.clone(), value.clone());
+            }
+            FieldEdit::Inherit { key } => {
+                raw.remove(key);
+            }
+        }
+    }
+}
+
+/// Check the shape of one edit against the profile it's for.
+fn check_edit(profile: &FilamentProfile, edit: &FieldEdit) -> Result<()> {
+    let key = edit.key();
+    if key.trim().is_empty() {
+        bail!("Field name is empty");
+    }
+    if let Some((_, instead)) = PROTECTED_FIELDS.iter().find(|(k, _)| *k == key) {
+        bail!("'{}' can't be edited directly; {}", key, instead);
+    }
+    let value = match edit {
+        FieldEdit::Inherit { .. } => {
+            if profile.inherits().is_none_or(str::is_empty) {
+                bail!("'{}' can't be inherited: the profile has no parent", key);
+            }
+            // Never taken from the parent, so removing it just loses it
+            if dictionary().is_metadata(key) {
+                bail!("'{}' belongs to this profile and can't be inherited", key);
+            }
+            return Ok(());
+        }
+        FieldEdit::Set { value, .. } => value,
+    };
+
+    // Bambu Studio stores every value as a string or an array of them
+    let is_array = match value {
+        Value::String(_) => false,
+        Value::Array(items) if items.iter().all(Value::is_string) => true,
+        _ => bail!("'{}' must be a string or an array of strings", key),
+    };
+    match dictionary().get(key).map(|field| field.arity) {
+        Some(Arity::Scalar) if is_array => bail!("'{}' takes a single value", key),
+        Some(Arity::PerExtruder | Arity::List) if !is_array => {
+            bail!("'{}' takes an array of values", key)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Check `edits` against `profile` as it is now. Malformed edits are an
+/// error; otherwise returns the lint diagnostics for the edited fields.
+pub fn check_edits(
+    profile: &FilamentProfile,
+    edits: &[FieldEdit],
+    extruder_count: Option<usize>,
+) -> Result<Vec<Diagnostic>> {
+    if edits.is_empty() {
+        bail!("No field changes to apply");
+    }
+    for edit in edits {
+        check_edit(profile, edit)?;
+    }
+    let keys: Vec<&str> = edits.iter().map(FieldEdit::key).collect();
+    let mut edited = FilamentProfile::from_map(profile.raw().clone());
+    apply_edits(&mut edited, edits);
+    let report = lint_profile(&edited, &LintOptions { extruder_count });
+    Ok(report.for_keys(&keys).diagnostics)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profile::lint::{LintCode, Severity};
+    use serde_json::json;
+
+    fn profile(inherits: &str) -> FilamentProfile {
+        FilamentProfile::from_json(
+            &json!({
+                "name": "My PLA",
+                "inherits": inherits,
+                "filament_id": "P1",
+                "filament_type": ["PLA"],
+                "nozzle_temperature": ["215", "215"],
+                "fan_max_speed": ["101"],
+                "chamber_temperatures": ["0"],
+            })
+            .to_string(),
+        )
+        .unwrap()
+    }
+
+    fn set(key: &str, value: Value) -> FieldEdit {
+        FieldEdit::Set {
+            key: key.to_string(),
+            value,
+        }
+    }
+
+    #[test]
+    fn test_apply_sets_and_inherits() {
+        let mut p = profile("Bambu PLA Basic @base");
+        apply_edits(
+            &mut p,
+            &[
+                set("filament_start_gcode", json!(["; hello"])),
+                FieldEdit::Inherit {
+                    key: "chamber_temperatures".into(),
+                },
+            ],
+        );
+        assert_eq!(p.raw()["filament_start_gcode"], json!(["; hello"]));
+        assert!(!p.raw().contains_key("chamber_temperatures"));
+    }
+
+    #[test]
+    fn test_malformed_edits_are_refused() {
+        let p = profile("");
+        let refused = |edit: FieldEdit| check_edits(&p, &[edit], None).is_err();
+        assert!(refused(set("name", json!("Other"))));
+        assert!(refused(set("nozzle_temperature", json!("215"))));
+        assert!(refused(set("filament_notes", json!(["a"]))));
+        assert!(refused(set("chamber_temperatures", json!([40]))));
+        assert!(refused(FieldEdit::Inherit {
+            key: "nozzle_temperature".into()
+        }));
+        assert!(check_edits(&p, &[], None).is_err());
+        // Unknown fields only need the right value types
+        assert!(!refused(set("chamber_temperatures", json!(["40"]))));
+    }
+
+    #[test]
+    fn test_metadata_fields_cant_be_inherited() {
+        let p = profile("Bambu PLA Basic @base");
+        let inherit = |key: &str| {
+            check_edits(
+                &p,
+                &[FieldEdit::Inherit {
+                    key: key.to_string(),
+                }],
+                None,
+            )
+        };
+        let err = inherit("filament_id").unwrap_err().to_string();
+        assert!(err.contains("can't be inherited"), "{}", err);
+        assert!(inherit("compatible_printers").is_err());
+        assert!(inherit("chamber_temperatures").is_ok());
+    }
+
+    #[test]
+    fn test_only_edited_fields_are_reported() {
+        // fan_max_speed is already out of range; editing something else
+        // doesn't report it
+        let p = profile("");
+        let ok = check_edits(
+            &p,
+            &[set("nozzle_temperature", json!(["220", "220"]))],
+            None,
+        );
+        assert!(ok.unwrap().is_empty());
+
+        let bad = check_edits(
+            &p,
+            &[set("nozzle_temperature", json!(["220", "hot"]))],
+            Some(2),
+        )
+        .unwrap();
+        assert_eq!(bad.len(), 1);
+        assert_eq!(bad[0].severity, Severity::Error);
+        assert_eq!(bad[0].code, LintCode::InvalidValue);
+        assert_eq!(bad[0].index, Some(1));
+
+        let arity = check_edits(
+            &p,
+            &[set("nozzle_temperature", json!(["220", "220", "220"]))],
+            Some(2),
+        )
+        .unwrap();
+        assert_eq!(arity[0].code, LintCode::WrongArity);
+    }
+}
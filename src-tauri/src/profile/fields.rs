@@ -0,0 +1,324 @@
+//! Field dictionary: what BambuMate knows about each Bambu Studio filament
+//! field.
+//!
+//! Labels, categories, units, value types, per-extruder layout, ranges and
+//! editor steps all come from `src-tauri/config/field_dictionary.toml`. The
+//! linter checks profiles against it, the comparison and diff views label
+//! and group fields with it, the defect rule engine clamps recommendations
+//! to its safe ranges, and the frontend builds field editors from it.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+/// Dictionary embedded in the binary at compile time.
+const DEFAULT_DICTIONARY: &str = include_str!("../../config/field_dictionary.toml");
+
+/// Category for fields the dictionary doesn't list and whose name doesn't
+/// suggest one.
+pub const OTHER_CATEGORY: &str = "Other";
+
+/// Value type of a field. Bambu Studio stores every value as a string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldKind {
+    Text,
+    Int,
+    Float,
+    /// Number with an optional `%` suffix.
+    Percent,
+    /// `"0"` or `"1"`.
+    Bool,
+    /// One of the field's `options`.
+    Enum,
+}
+
+/// How a field's values are laid out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Arity {
+    /// A bare string.
+    Scalar,
+    /// One element per extruder, or a single element used for all of them.
+    PerExtruder,
+    /// An array of any length.
+    List,
+}
+
+/// Material limits a safe range can defer to (see
+/// `scraper::validation::constraints_for_material`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MaterialLimit {
+    NozzleTemp,
+    BedTemp,
+}
+
+/// Range recommendations for a field are clamped to.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SafeRange {
+    Fixed([f64; 2]),
+    Material(MaterialLimit),
+}
+
+/// Dictionary entry for one field.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldInfo {
+    pub key: String,
+    pub label: String,
+    pub category: String,
+    pub kind: FieldKind,
+    pub arity: Arity,
+    #[serde(default)]
+    pub unit: Option<String>,
+    /// Allowed values for [`FieldKind::Enum`].
+    #[serde(default)]
+    pub options: Vec<String>,
+    /// Inclusive bounds for numeric kinds. Hard physical limits, not
+    /// per-material recommendations.
+    #[serde(default)]
+    pub range: Option<[f64; 2]>,
+    /// Editor increment for numeric kinds.
+    #[serde(default)]
+    pub step: Option<f64>,
+    /// Accepts `nil` ("use the printer's value").
+    #[serde(default)]
+    pub nullable: bool,
+    /// Must be present once the profile is flattened (no `inherits`).
+    #[serde(default)]
+    pub required: bool,
+    /// Names or identifies the profile rather than changing how it prints.
+    #[serde(default)]
+    pub identity: bool,
+    /// Belongs to the preset file rather than its settings, so it's never
+    /// inherited from a parent preset.
+    #[serde(default)]
+    pub metadata: bool,
+    #[serde(default)]
+    pub safe_range: Option<SafeRange>,
+}
+
+impl FieldInfo {
+    pub fn is_per_extruder(&self) -> bool {
+        self.arity == Arity::PerExtruder
+    }
+
+    pub fn is_numeric(&self) -> bool {
+        matches!(
+            self.kind,
+            FieldKind::Int | FieldKind::Float | FieldKind::Percent
+        )
+    }
+}
+
+#[derive(Deserialize)]
+struct DictionaryFile {
+    fields: Vec<FieldInfo>,
+}
+
+/// Every field the dictionary lists, in file order.
+#[derive(Debug, Clone)]
+pub struct FieldDictionary {
+    fields: Vec<FieldInfo>,
+    index: HashMap<String, usize>,
+}
+
+impl FieldDictionary {
+    /// Parse and check a dictionary from TOML.
+    pub fn from_toml(content: &str) -> Result<Self> {
+        let file: DictionaryFile = toml::from_str(content)?;
+        let mut index = HashMap::new();
+        for (i, field) in file.fields.iter().enumerate() {
+            if index.insert(field.key.clone(), i).is_some() {
+                bail!("field '{}' is listed twice", field.key);
+            }
+            if (field.kind == FieldKind::Enum) == field.options.is_empty() {
+                bail!(
+                    "field '{}': options go with kind = \"enum\" only",
+                    field.key
+                );
+            }
+            if !field.is_numeric() && (field.range.is_some() || field.step.is_some()) {
+                bail!("field '{}': range and step need a numeric kind", field.key);
+            }
+            if let Some([min, max]) = field.range {
+                if min > max {
+                    bail!("field '{}': range minimum is above maximum", field.key);
+                }
+            }
+        }
+        Ok(Self {
+            fields: file.fields,
+            index,
+        })
+    }
+
+    pub fn fields(&self) -> &[FieldInfo] {
+        &self.fields
+    }
+
+    pub fn get(&self, key: &str) -> Option<&FieldInfo> {
+        self.index.get(key).map(|&i| &self.fields[i])
+    }
+
+    /// Display label for `key`, title-cased from the key if it isn't listed.
+    pub fn label(&self, key: &str) -> String {
+        match self.get(key) {
+            Some(field) => field.label.clone(),
+            None => title_case(key),
+        }
+    }
+
+    /// Display category for `key`, guessed from its name if it isn't listed.
+    pub fn category<'a>(&'a self, key: &str) -> &'a str {
+        match self.get(key) {
+            Some(field) => &field.category,
+            None => guess_category(key),
+        }
+    }
+
+    /// Whether `key` names or identifies the profile.
+    pub fn is_identity(&self, key: &str) -> bool {
+        self.get(key).is_some_and(|field| field.identity)
+    }
+
+    /// Whether `key` belongs to the preset file rather than its settings.
+    pub fn is_metadata(&self, key: &str) -> bool {
+        self.get(key).is_some_and(|field| field.metadata)
+    }
+}
+
+/// The embedded dictionary.
+///
+/// # Panics
+/// Panics if the embedded TOML is invalid (this would be a compile-time bug).
+pub fn dictionary() -> &'static FieldDictionary {
+    static DICTIONARY: OnceLock<FieldDictionary> = OnceLock::new();
+    DICTIONARY.get_or_init(|| {
+        FieldDictionary::from_toml(DEFAULT_DICTIONARY)
+            .expect("embedded field_dictionary.toml must be a valid field dictionary")
+    })
+}
+
+/// `snake_case_key` as "Snake Case Key".
+fn title_case(key: &str) -> String {
+    key.split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                None => String::new(),
+                Some(c) => c.to_uppercase().to_string() + chars.as_str(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Category for an unlisted field, from words in its name.
+fn guess_category(key: &str) -> &'static str {
+    match key {
+        k if k.contains("temperature") || k.contains("temp") => "Temperature",
+        k if k.contains("speed")
+            || k.contains("flow")
+            || k.contains("volumetric")
+            || k.contains("acceleration")
+            || k.contains("jerk") =>
+        {
+            "Speed & Flow"
+        }
+        k if k.contains("fan") || k.contains("cool") || k.contains("slow_down") => "Cooling & Fan",
+        k if k.contains("retract") || k.contains("wipe") || k.contains("z_hop") => "Retraction",
+        k if k.contains("density")
+            || k.contains("diameter")
+            || k.contains("cost")
+            || k.contains("vitrification")
+            || k.contains("shrinkage") =>
+        {
+            "Physical Properties"
+        }
+        k if k.contains("name")
+            || k.contains("id")
+            || k.contains("version")
+            || k.contains("inherits")
+            || k.contains("from")
+            || k.contains("vendor")
+            || k.contains("type")
+            || k.contains("compatible")
+            || k.contains("setting")
+            || k.contains("instantiation") =>
+        {
+            "Identity & Metadata"
+        }
+        _ => OTHER_CATEGORY,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_embedded_dictionary_loads() {
+        let dict = dictionary();
+        let temp = dict.get("nozzle_temperature").unwrap();
+        assert_eq!(temp.kind, FieldKind::Int);
+        assert!(temp.is_per_extruder() && temp.required);
+        assert_eq!(temp.unit.as_deref(), Some("°C"));
+        assert_eq!(
+            temp.safe_range,
+            Some(SafeRange::Material(MaterialLimit::NozzleTemp))
+        );
+        assert_eq!(
+            dict.get("filament_flow_ratio").unwrap().safe_range,
+            Some(SafeRange::Fixed([0.85, 1.15]))
+        );
+        assert!(dict.is_identity("filament_settings_id"));
+        assert!(!dict.is_identity("nozzle_temperature"));
+        assert!(dict.is_metadata("compatible_prints_condition"));
+        assert!(!dict.is_metadata("version"));
+    }
+
+    #[test]
+    fn test_unlisted_fields_fall_back_to_their_name() {
+        let dict = dictionary();
+        assert_eq!(
+            dict.label("slow_down_for_layer_cooling"),
+            "Slow Down For Layer Cooling"
+        );
+        assert_eq!(
+            dict.category("slow_down_for_layer_cooling"),
+            "Cooling & Fan"
+        );
+        assert_eq!(dict.category("mystery_setting_x"), "Identity & Metadata");
+        assert_eq!(dict.category("mystery"), OTHER_CATEGORY);
+        assert_eq!(
+            dict.label("filament_max_volumetric_speed"),
+            "Max Volumetric Speed"
+        );
+    }
+
+    #[test]
+    fn test_rejects_inconsistent_entries() {
+        let entry = |extra: &str| {
+            format!(
+                "[[fields]]\nkey = \"a\"\nlabel = \"A\"\ncategory = \"Other\"\n{}",
+                extra
+            )
+        };
+        assert!(FieldDictionary::from_toml(&entry("kind = \"int\"\narity = \"scalar\"")).is_ok());
+        assert!(FieldDictionary::from_toml(&entry("kind = \"enum\"\narity = \"scalar\"")).is_err());
+        assert!(FieldDictionary::from_toml(&entry(
+            "kind = \"text\"\narity = \"scalar\"\nrange = [0, 1]"
+        ))
+        .is_err());
+        assert!(FieldDictionary::from_toml(&entry(
+            "kind = \"float\"\narity = \"list\"\nrange = [2, 1]"
+        ))
+        .is_err());
+        let twice = entry("kind = \"int\"\narity = \"scalar\"\n").repeat(2);
+        assert!(FieldDictionary::from_toml(&twice).is_err());
+    }
+}
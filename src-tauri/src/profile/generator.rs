@@ -1,15 +1,17 @@
 use anyhow::{anyhow, Result};
-use chrono::Utc;
+use serde_json::Value;
 use std::path::Path;
 use tracing::debug;
 
 use crate::process_command;
+use super::fields::dictionary;
 use super::inheritance::resolve_inheritance;
+use super::nozzle::{self, Nozzle};
 use super::paths::BambuPaths;
 use super::reader::read_profile;
-use super::registry::ProfileRegistry;
+use super::registry::{MachineRegistry, ProfileRegistry};
 use super::types::{FilamentProfile, ProfileMetadata};
-use crate::scraper::types::{FilamentSpecs, MaterialType};
+use crate::scraper::types::{ExtruderOverrides, FilamentSpecs, MaterialType};
 
 /// Map a MaterialType to the corresponding Bambu Studio base profile name.
 ///
@@ -33,6 +35,50 @@ pub fn base_profile_name(material: &MaterialType) -> &'static str {
     }
 }
 
+/// The system preset a delta profile for `material` on `printer` inherits
+/// from. Bambu Studio only keeps user presets whose parent is an
+/// instantiable system preset with a `setting_id` (recorded as their
+/// `base_id`), so the abstract `base_profile_name` won't do: this picks a
+/// preset descending from it that lists `printer` as compatible, generic
+/// ones first. None when the installed presets have no such preset.
+pub fn delta_parent_name(
+    registry: &ProfileRegistry,
+    material: &MaterialType,
+    printer: &str,
+) -> Option<String> {
+    let base = base_profile_name(material);
+    let mut candidates: Vec<&str> = registry
+        .iter()
+        .filter(|p| {
+            p.is_instantiable()
+                && p.setting_id().is_some_and(|id| !id.is_empty())
+                && p.compatible_printers()
+                    .is_some_and(|printers| printers.contains(&printer))
+        })
+        .filter_map(|p| p.name())
+        .filter(|name| registry.source(name).is_some_and(|s| !s.is_user))
+        .filter(|name| inherits_from(registry, name, base))
+        .collect();
+    candidates.sort_by_key(|name| (!name.starts_with("Generic "), *name));
+    candidates.first().map(|name| name.to_string())
+}
+
+/// Whether `name`'s inheritance chain in `registry` reaches `ancestor`.
+fn inherits_from(registry: &ProfileRegistry, name: &str, ancestor: &str) -> bool {
+    let mut current = registry.get_by_name(name);
+    // Bounded, in case of an inheritance cycle
+    for _ in 0..16 {
+        let Some(parent) = current.and_then(|p| p.inherits()).filter(|p| !p.is_empty()) else {
+            return false;
+        };
+        if parent == ancestor {
+            return true;
+        }
+        current = registry.get_by_name(parent);
+    }
+    false
+}
+
 /// Generate a random filament_id in the format "P" + 7 hex chars.
 ///
 /// User profiles use "P" prefix (not "GFL" which is for system profiles).
@@ -117,41 +163,61 @@ pub fn find_existing_filament_id(
 
 /// Apply scraped filament specs to a profile, overriding the base profile values.
 ///
-/// All array fields are set with exactly 2 elements for dual-extruder compatibility.
-/// This matches Bambu Studio's convention where every array field has one element
-/// per extruder (H2C/H2D printers have dual extruders).
-pub fn apply_specs_to_profile(profile: &mut FilamentProfile, specs: &FilamentSpecs) {
-    // Helper: set a 2-element string array from a single value
-    let set_dual = |p: &mut FilamentProfile, key: &str, val: String| {
-        p.set_string_array(key, vec![val.clone(), val]);
+/// Per-extruder fields are written with `extruder_count` elements, one per
+/// extruder of the target printer (see [`printer_extruder_count`]).
+/// `specs.extruder_overrides` replace the filament-wide value for individual
+/// extruders; an override for an extruder the printer doesn't have is
+/// ignored.
+pub fn apply_specs_to_profile(
+    profile: &mut FilamentProfile,
+    specs: &FilamentSpecs,
+    extruder_count: usize,
+) {
+    let count = extruder_count.max(1);
+    // Helper: set a per-extruder string array from a single value
+    let set_all = |p: &mut FilamentProfile, key: &str, val: String| {
+        p.set_string_array(key, vec![val; count]);
     };
+    let overrides = |i: usize| specs.extruder_overrides.get(i);
 
     // === Nozzle temperatures ===
     // Prefer explicit nozzle_temperature if available, fall back to range max
-    if let Some(temp) = specs.nozzle_temperature.or(specs.nozzle_temp_max) {
-        set_dual(profile, "nozzle_temperature", temp.to_string());
-    }
-    if let Some(temp) = specs.nozzle_temperature_initial_layer.or(specs
-        .nozzle_temperature
-        .map(|t| t + 5)
-        .or(specs.nozzle_temp_max.map(|t| t + 5)))
-    {
-        set_dual(
-            profile,
-            "nozzle_temperature_initial_layer",
-            temp.to_string(),
-        );
-    }
+    set_per_extruder(
+        profile,
+        "nozzle_temperature",
+        count,
+        specs
+            .nozzle_temperature
+            .or(specs.nozzle_temp_max)
+            .map(|t| t.to_string()),
+        |i| overrides(i)?.nozzle_temperature.map(|t| t.to_string()),
+    );
+    set_per_extruder(
+        profile,
+        "nozzle_temperature_initial_layer",
+        count,
+        specs
+            .nozzle_temperature_initial_layer
+            .or(specs.nozzle_temperature.map(|t| t + 5))
+            .or(specs.nozzle_temp_max.map(|t| t + 5))
+            .map(|t| t.to_string()),
+        |i| {
+            let o = overrides(i)?;
+            o.nozzle_temperature_initial_layer
+                .or(o.nozzle_temperature.map(|t| t + 5))
+                .map(|t| t.to_string())
+        },
+    );
     // Range bounds for BS temperature slider
     if let Some(temp_max) = specs.nozzle_temp_max {
-        set_dual(
+        set_all(
             profile,
             "nozzle_temperature_range_high",
             (temp_max + 20).to_string(),
         );
     }
     if let Some(temp_min) = specs.nozzle_temp_min {
-        set_dual(
+        set_all(
             profile,
             "nozzle_temperature_range_low",
             temp_min.to_string(),
@@ -161,47 +227,47 @@ pub fn apply_specs_to_profile(profile: &mut FilamentProfile, specs: &FilamentSpe
     // === Per-plate bed temperatures ===
     // Prefer explicit plate temps, fall back to bed_temp range
     if let Some(temp) = specs.hot_plate_temp.or(specs.bed_temp_max) {
-        set_dual(profile, "hot_plate_temp", temp.to_string());
+        set_all(profile, "hot_plate_temp", temp.to_string());
     }
     if let Some(temp) = specs
         .hot_plate_temp_initial_layer
         .or(specs.hot_plate_temp)
         .or(specs.bed_temp_max)
     {
-        set_dual(profile, "hot_plate_temp_initial_layer", temp.to_string());
+        set_all(profile, "hot_plate_temp_initial_layer", temp.to_string());
     }
     if let Some(temp) = specs.cool_plate_temp.or(specs.bed_temp_min) {
-        set_dual(profile, "cool_plate_temp", temp.to_string());
+        set_all(profile, "cool_plate_temp", temp.to_string());
     }
     if let Some(temp) = specs
         .cool_plate_temp_initial_layer
         .or(specs.cool_plate_temp)
         .or(specs.bed_temp_min)
     {
-        set_dual(profile, "cool_plate_temp_initial_layer", temp.to_string());
+        set_all(profile, "cool_plate_temp_initial_layer", temp.to_string());
     }
     if let Some(temp) = specs.eng_plate_temp.or(specs.bed_temp_max) {
-        set_dual(profile, "eng_plate_temp", temp.to_string());
+        set_all(profile, "eng_plate_temp", temp.to_string());
     }
     if let Some(temp) = specs
         .eng_plate_temp_initial_layer
         .or(specs.eng_plate_temp)
         .or(specs.bed_temp_max)
     {
-        set_dual(profile, "eng_plate_temp_initial_layer", temp.to_string());
+        set_all(profile, "eng_plate_temp_initial_layer", temp.to_string());
     }
     if let Some(temp) = specs
         .textured_plate_temp
         .or(specs.bed_temp_min.map(|t| t.saturating_sub(5)))
     {
-        set_dual(profile, "textured_plate_temp", temp.to_string());
+        set_all(profile, "textured_plate_temp", temp.to_string());
     }
     if let Some(temp) = specs
         .textured_plate_temp_initial_layer
         .or(specs.textured_plate_temp)
         .or(specs.bed_temp_min.map(|t| t.saturating_sub(5)))
     {
-        set_dual(
+        set_all(
             profile,
             "textured_plate_temp_initial_layer",
             temp.to_string(),
@@ -209,91 +275,182 @@ pub fn apply_specs_to_profile(profile: &mut FilamentProfile, specs: &FilamentSpe
     }
 
     // === Flow & volumetric speed ===
-    if let Some(mvs) = specs.max_volumetric_speed {
-        set_dual(
-            profile,
-            "filament_max_volumetric_speed",
-            format!("{:.0}", mvs),
-        );
-    }
-    if let Some(ratio) = specs.filament_flow_ratio {
-        set_dual(profile, "filament_flow_ratio", format!("{:.2}", ratio));
-    }
-    if let Some(pa) = specs.pressure_advance {
-        set_dual(profile, "pressure_advance", format!("{:.3}", pa));
-    }
+    set_per_extruder(
+        profile,
+        "filament_max_volumetric_speed",
+        count,
+        specs.max_volumetric_speed.map(|v| format!("{:.0}", v)),
+        |i| {
+            overrides(i)?
+                .max_volumetric_speed
+                .map(|v| format!("{:.0}", v))
+        },
+    );
+    set_per_extruder(
+        profile,
+        "filament_flow_ratio",
+        count,
+        specs.filament_flow_ratio.map(|r| format!("{:.2}", r)),
+        |i| {
+            overrides(i)?
+                .filament_flow_ratio
+                .map(|r| format!("{:.2}", r))
+        },
+    );
+    set_per_extruder(
+        profile,
+        "pressure_advance",
+        count,
+        specs.pressure_advance.map(|pa| format!("{:.3}", pa)),
+        |i| {
+            overrides(i)?
+                .pressure_advance
+                .map(|pa| format!("{:.3}", pa))
+        },
+    );
 
     // === Fan/cooling ===
     // Prefer explicit fan_min/max, fall back to legacy fan_speed_percent
     if let Some(fan_max) = specs.fan_max_speed.or(specs.fan_speed_percent) {
-        set_dual(profile, "fan_max_speed", fan_max.to_string());
+        set_all(profile, "fan_max_speed", fan_max.to_string());
     }
     if let Some(fan_min) = specs
         .fan_min_speed
         .or(specs.fan_speed_percent.map(|f| (f as f32 * 0.6) as u8))
     {
-        set_dual(profile, "fan_min_speed", fan_min.to_string());
+        set_all(profile, "fan_min_speed", fan_min.to_string());
     }
     if let Some(overhang) = specs.overhang_fan_speed {
-        set_dual(profile, "overhang_fan_speed", overhang.to_string());
+        set_all(profile, "overhang_fan_speed", overhang.to_string());
     }
     if let Some(layers) = specs.close_fan_the_first_x_layers {
-        set_dual(profile, "close_fan_the_first_x_layers", layers.to_string());
+        set_all(profile, "close_fan_the_first_x_layers", layers.to_string());
     }
     if let Some(aux) = specs.additional_cooling_fan_speed {
-        set_dual(profile, "additional_cooling_fan_speed", aux.to_string());
+        set_all(profile, "additional_cooling_fan_speed", aux.to_string());
     }
 
     // === Cooling slowdown ===
     if let Some(time) = specs.slow_down_layer_time {
-        set_dual(profile, "slow_down_layer_time", time.to_string());
+        set_all(profile, "slow_down_layer_time", time.to_string());
     }
     if let Some(speed) = specs.slow_down_min_speed {
-        set_dual(profile, "slow_down_min_speed", speed.to_string());
+        set_all(profile, "slow_down_min_speed", speed.to_string());
     }
 
     // === Retraction ===
-    if let Some(dist) = specs.retraction_distance_mm {
-        set_dual(
-            profile,
-            "filament_retraction_length",
-            format!("{:.1}", dist),
-        );
-    }
-    if let Some(speed) = specs.retraction_speed_mm_s {
-        set_dual(profile, "filament_retraction_speed", speed.to_string());
-    }
+    set_per_extruder(
+        profile,
+        "filament_retraction_length",
+        count,
+        specs.retraction_distance_mm.map(|d| format!("{:.1}", d)),
+        |i| {
+            overrides(i)?
+                .retraction_distance_mm
+                .map(|d| format!("{:.1}", d))
+        },
+    );
+    set_per_extruder(
+        profile,
+        "filament_retraction_speed",
+        count,
+        specs.retraction_speed_mm_s.map(|s| s.to_string()),
+        |i| overrides(i)?.retraction_speed_mm_s.map(|s| s.to_string()),
+    );
     if let Some(speed) = specs.deretraction_speed_mm_s {
-        set_dual(profile, "filament_deretraction_speed", speed.to_string());
+        set_all(profile, "filament_deretraction_speed", speed.to_string());
     }
 
     // === Bridge ===
     if let Some(speed) = specs.bridge_speed {
-        set_dual(profile, "filament_bridge_speed", speed.to_string());
+        set_all(profile, "filament_bridge_speed", speed.to_string());
     }
 
     // === Physical properties ===
     if let Some(density) = specs.density_g_cm3 {
-        set_dual(profile, "filament_density", format!("{:.2}", density));
+        set_all(profile, "filament_density", format!("{:.2}", density));
     }
     if let Some(vitrification) = specs.temperature_vitrification {
-        set_dual(
+        set_all(
             profile,
             "temperature_vitrification",
             vitrification.to_string(),
         );
     }
     if let Some(cost) = specs.filament_cost {
-        set_dual(profile, "filament_cost", format!("{:.2}", cost));
+        set_all(profile, "filament_cost", format!("{:.2}", cost));
     }
 
     // Material identity fields. Bambu Studio profiles store these as
     // single-element string arrays — NOT per-extruder duplicates — so use
-    // `set_string_array` rather than `set_dual` to avoid `["X", "X"]` output.
+    // `set_string_array` rather than `set_all` to avoid `["X", "X"]` output.
     profile.set_string_array("filament_type", vec![specs.material.clone()]);
     profile.set_string_array("filament_vendor", vec![specs.brand.clone()]);
 }
 
+/// Set a per-extruder array: extruder `i` gets `overrides(i)`, else
+/// `value`, else what the profile already has for it. Leaves the field
+/// alone when there's nothing to set.
+fn set_per_extruder(
+    profile: &mut FilamentProfile,
+    key: &str,
+    count: usize,
+    value: Option<String>,
+    overrides: impl Fn(usize) -> Option<String>,
+) {
+    let per_extruder: Vec<Option<String>> = (0..count).map(&overrides).collect();
+    if value.is_none() && per_extruder.iter().all(Option::is_none) {
+        return;
+    }
+    let existing: Vec<String> = profile
+        .get_string_array(key)
+        .unwrap_or_default()
+        .into_iter()
+        .map(str::to_string)
+        .collect();
+    let values: Option<Vec<String>> = per_extruder
+        .into_iter()
+        .enumerate()
+        .map(|(i, v)| {
+            v.or_else(|| value.clone())
+                .or_else(|| existing.get(i).or(existing.first()).cloned())
+        })
+        .collect();
+    // An override for some extruders, but no value for the others
+    if let Some(values) = values {
+        profile.set_string_array(key, values);
+    }
+}
+
+/// Number of extruders on `printer`, read from its machine preset.
+/// None when the preset isn't installed or doesn't resolve.
+pub fn printer_extruder_count(machines: &MachineRegistry, printer: &str) -> Option<usize> {
+    let preset = machines.get_by_name(printer)?;
+    match resolve_inheritance(preset, machines) {
+        Ok(resolved) => resolved.extruder_count(),
+        Err(e) => {
+            debug!("Failed to resolve machine preset '{}': {}", printer, e);
+            None
+        }
+    }
+}
+
+/// Resize every per-extruder array (per the field dictionary) to `count`
+/// elements: single values and shorter arrays are padded with their last
+/// element, longer ones are truncated.
+pub fn fit_per_extruder_arrays(profile: &mut FilamentProfile, count: usize) {
+    let count = count.max(1);
+    for spec in dictionary().fields().iter().filter(|s| s.is_per_extruder()) {
+        let Some(Value::Array(items)) = profile.raw_mut().get_mut(&spec.key) else {
+            continue;
+        };
+        let Some(last) = items.last().cloned() else {
+            continue;
+        };
+        items.resize(count, last);
+    }
+}
+
 /// Extract FilamentSpecs from an existing Bambu Studio profile.
 ///
 /// This is the reverse of `apply_specs_to_profile`: it reads BS profile fields
@@ -383,11 +540,54 @@ pub fn extract_specs_from_profile(profile: &FilamentProfile) -> FilamentSpecs {
 
         max_speed_mm_s: None,
 
+        extruder_overrides: extract_extruder_overrides(profile),
+
         source_url: "profile".to_string(),
         extraction_confidence: 1.0,
     }
 }
 
+/// Per-extruder values that differ from the first extruder's (which
+/// `extract_specs_from_profile` reports as the filament-wide value).
+/// Empty when every extruder agrees.
+fn extract_extruder_overrides(profile: &FilamentProfile) -> Vec<ExtruderOverrides> {
+    fn differing<T: std::str::FromStr>(
+        profile: &FilamentProfile,
+        key: &str,
+        i: usize,
+    ) -> Option<T> {
+        let values = profile.get_string_array(key)?;
+        let value = values.get(i)?;
+        if value == values.first()? {
+            return None;
+        }
+        value.parse().ok()
+    }
+
+    let count = profile
+        .get_string_array("nozzle_temperature")
+        .map_or(0, |v| v.len());
+    let mut overrides: Vec<ExtruderOverrides> = (0..count)
+        .map(|i| ExtruderOverrides {
+            nozzle_temperature: differing(profile, "nozzle_temperature", i),
+            nozzle_temperature_initial_layer: differing(
+                profile,
+                "nozzle_temperature_initial_layer",
+                i,
+            ),
+            max_volumetric_speed: differing(profile, "filament_max_volumetric_speed", i),
+            filament_flow_ratio: differing(profile, "filament_flow_ratio", i),
+            pressure_advance: differing(profile, "pressure_advance", i),
+            retraction_distance_mm: differing(profile, "filament_retraction_length", i),
+            retraction_speed_mm_s: differing(profile, "filament_retraction_speed", i),
+        })
+        .collect();
+    while overrides.last() == Some(&ExtruderOverrides::default()) {
+        overrides.pop();
+    }
+    overrides
+}
+
 /// Generate a fully-flattened filament profile from scraped specifications.
 ///
 /// This is the core value function: it takes a `FilamentSpecs` (from the scraper)
@@ -396,10 +596,18 @@ pub fn extract_specs_from_profile(profile: &FilamentProfile) -> FilamentSpecs {
 /// Steps:
 /// 1. Determine material type and look up the corresponding base profile
 /// 2. Resolve the base profile's inheritance chain to get all ~139 fields
-/// 3. Set identity fields (name, filament_id, inherits="")
+/// 3. Set identity fields (name, filament_id, inherits="", no include)
 /// 4. Apply scraped spec overrides (temperatures, speeds, etc.)
 /// 5. Generate metadata (.info file content)
 ///
+/// `machines` supplies the target printer's machine preset, which decides how
+/// many elements per-extruder arrays get (1 on an X1C, 2 on an H2D). When the
+/// printer isn't in it, the base profile's array sizes are kept.
+///
+/// Volumetric speed, retraction and flow ratio in `specs` are taken to be
+/// for a standard 0.4 nozzle and are scaled to the target printer's nozzle
+/// (see `profile::nozzle`).
+///
 /// `existing_filament_id` — when `Some`, the supplied value is used for the
 /// `filament_id` field instead of generating a fresh random one. Pass this when
 /// generating multiple nozzle-size variants of the same physical filament so they
@@ -410,6 +618,7 @@ pub fn extract_specs_from_profile(profile: &FilamentProfile) -> FilamentSpecs {
 pub fn generate_profile(
     specs: &FilamentSpecs,
     registry: &ProfileRegistry,
+    machines: &MachineRegistry,
     target_printer: Option<&str>,
     base_profile_override: Option<&str>,
     existing_filament_id: Option<String>,
@@ -442,6 +651,7 @@ pub fn generate_profile(
 
     profile.set_string("name", profile_name.clone());
     profile.set_string("inherits", String::new()); // Fully flattened
+    profile.raw_mut().remove("include"); // Merged in by resolve_inheritance
     profile.set_string("from", "User".to_string());
     let filament_id = existing_filament_id.unwrap_or_else(generate_filament_id);
     profile.set_string("filament_id", filament_id.clone());
@@ -450,12 +660,31 @@ pub fn generate_profile(
     // 3. Set display identifier (single element matching profile name)
     profile.set_string_array("filament_settings_id", vec![profile_name.clone()]);
 
-    // 4. Apply scraped spec overrides
-    apply_specs_to_profile(&mut profile, specs);
+    // 4. Apply scraped spec overrides, one array element per extruder of the
+    //    target printer. Without its machine preset we can't know the count,
+    //    so specs are sized like the base profile's nozzle temperatures.
+    let extruder_count = printer_extruder_count(machines, printer);
+    if extruder_count.is_none() {
+        debug!(
+            "No machine preset for '{}'; extruder count unknown",
+            printer
+        );
+    }
+    //    Specs are written for a standard 0.4 nozzle, so scale them to the
+    //    target printer's nozzle first.
+    let specs = match nozzle::printer_nozzle(machines, printer) {
+        Some(target) => nozzle::default_table().scale_specs(specs, Nozzle::REFERENCE, target),
+        None => specs.clone(),
+    };
+    let base_count = profile.nozzle_temperature().map_or(1, |temps| temps.len());
+    apply_specs_to_profile(&mut profile, &specs, extruder_count.unwrap_or(base_count));
 
     // 5. Apply compatibility defaults for fields required by newer Bambu Studio
     //    versions that may be absent from older system profile installations.
     apply_compat_defaults(&mut profile);
+    if let Some(count) = extruder_count {
+        fit_per_extruder_arrays(&mut profile, count);
+    }
 
     // 6. Set compatible_printers to the target printer (e.g.
     //    "Bambu Lab H2C 0.4 nozzle"). This matches what Bambu Studio itself
@@ -488,13 +717,12 @@ pub fn generate_profile(
         .and_then(|p| p.preset_folder.clone())
         .unwrap_or_default();
 
-    let metadata = ProfileMetadata {
-        sync_info: String::new(),
+    let mut metadata = ProfileMetadata {
         user_id,
         setting_id: generate_setting_id(),
-        base_id: String::new(),
-        updated_time: Utc::now().timestamp() as u64,
+        ..ProfileMetadata::default()
     };
+    metadata.mark_created();
 
     // 8. Generate filename
     let filename = if specs.serial.is_empty() {
@@ -603,12 +831,67 @@ mod tests {
         assert_eq!(profile.raw()["filament_wipe"], json!(["0", "0"]),
             "spec-derived value must not be overwritten by compat default");
     }
+
+    #[test]
+    fn specs_written_once_per_extruder() {
+        let mut profile = FilamentProfile::from_json(
+            r#"{"nozzle_temperature": ["200"], "filament_retraction_length": ["0.8"]}"#,
+        )
+        .unwrap();
+        let specs = FilamentSpecs {
+            nozzle_temperature: Some(215),
+            extruder_overrides: vec![
+                ExtruderOverrides::default(),
+                ExtruderOverrides {
+                    retraction_distance_mm: Some(1.2),
+                    ..ExtruderOverrides::default()
+                },
+            ],
+            ..FilamentSpecs::default()
+        };
+
+        apply_specs_to_profile(&mut profile, &specs, 2);
+        assert_eq!(profile.raw()["nozzle_temperature"], json!(["215", "215"]));
+        assert_eq!(
+            profile.raw()["nozzle_temperature_initial_layer"],
+            json!(["220", "220"])
+        );
+        // Extruders without an override keep the profile's value
+        assert_eq!(
+            profile.raw()["filament_retraction_length"],
+            json!(["0.8", "1.2"])
+        );
+
+        apply_specs_to_profile(&mut profile, &specs, 1);
+        assert_eq!(profile.raw()["nozzle_temperature"], json!(["215"]));
+        // Nothing to write for extruder 1; resizing is fit_per_extruder_arrays' job
+        assert_eq!(
+            profile.raw()["filament_retraction_length"],
+            json!(["0.8", "1.2"])
+        );
+    }
+
+    #[test]
+    fn per_extruder_arrays_fitted_to_count() {
+        let mut profile = FilamentProfile::from_json(
+            r#"{"fan_max_speed": ["80%"], "filament_wipe": ["1", "0", "1"],
+                "filament_type": ["PLA"], "compatible_printers": ["A", "B"]}"#,
+        )
+        .unwrap();
+
+        fit_per_extruder_arrays(&mut profile, 2);
+        assert_eq!(profile.raw()["fan_max_speed"], json!(["80%", "80%"]));
+        assert_eq!(profile.raw()["filament_wipe"], json!(["1", "0"]));
+        assert_eq!(profile.raw()["filament_type"], json!(["PLA"]));
+        assert_eq!(profile.raw()["compatible_printers"], json!(["A", "B"]));
+    }
 }
 
 /// Check if Bambu Studio is currently running.
 ///
 /// Uses platform-specific process detection:
-/// - macOS/Linux: `pgrep -f BambuStudio`
+/// - macOS: `pgrep -f BambuStudio`
+/// - Linux: `pgrep -x` on the known process names
 /// - Windows: `tasklist /FI` filtering for BambuStudio.exe
 ///
 /// This is a lightweight check using std::process::Command to avoid
@@ -644,16 +927,25 @@ pub fn is_bambu_studio_running() -> bool {
     false
 }
 
+/// Check if Bambu Studio is currently running on Linux.
+///
+/// Matches the exact process name rather than the full command line, so an
+/// editor with a file under `~/.config/BambuStudio` open doesn't count.
+/// Distro packages, AppImages (whose `AppRun` execs `bin/bambu-studio`) and
+/// Flatpak (sandboxed processes are still visible to the host's `pgrep`)
+/// all run as `bambu-studio`; older tarball builds ship `BambuStudio`.
 #[cfg(target_os = "linux")]
 pub fn is_bambu_studio_running() -> bool {
-    std::process::Command::new("pgrep")
-        .arg("-f")
-        .arg("BambuStudio")
-        .stdout(std::process::Stdio::null())
-        .stderr(std::process::Stdio::null())
-        .status()
-        .map(|s| s.success())
-        .unwrap_or(false)
+    ["bambu-studio", "BambuStudio"].iter().any(|name| {
+        std::process::Command::new("pgrep")
+            .arg("-x")
+            .arg(name)
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .status()
+            .map(|s| s.success())
+            .unwrap_or(false)
+    })
 }
 
 #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
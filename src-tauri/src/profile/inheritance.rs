@@ -1,112 +1,50 @@
-use std::collections::HashSet;
-
 use anyhow::{bail, Result};
 use serde_json::{Map, Value};
 use tracing::debug;
 
-use super::registry::ProfileRegistry;
-use super::types::FilamentProfile;
+use super::fields::dictionary;
+use super::registry::PresetRegistry;
+use super::types::{Preset, PresetKind};
 
-/// Metadata fields that should NOT be inherited from parent profiles.
+/// Whether `key` is a metadata field that is NOT inherited from parent
+/// profiles: one marked `metadata` in the field dictionary, or the preset
+/// kind's settings ID.
 ///
 /// During inheritance merge, these fields are skipped from ancestor profiles.
 /// The leaf profile's own values for these fields are applied last.
-const SKIP_INHERIT_FIELDS: &[&str] = &[
-    "inherits",
-    "name",
-    "type",
-    "from",
-    "instantiation",
-    "filament_id",
-    "setting_id",
-    "include",
-    "description",
-    "compatible_printers",
-    "compatible_prints",
-    "compatible_printers_condition",
-    "compatible_prints_condition",
-    "filament_settings_id",
-];
+fn skips_inheritance<K: PresetKind>(key: &str) -> bool {
+    key == K::SETTINGS_ID_KEY || dictionary().is_metadata(key)
+}
 
 /// Maximum inheritance depth to prevent infinite loops.
 const MAX_INHERITANCE_DEPTH: usize = 10;
 
 /// Resolve the inheritance chain for a profile.
 ///
-/// Walks the `inherits` chain from leaf to root, then merges fields
-/// from base (root) to leaf. Metadata fields are skipped during
-/// ancestor merge; the leaf profile's own values override everything.
+/// Collects every profile that contributes to `profile` through `inherits`
+/// and `include`, then merges fields from base (root) to leaf. Metadata
+/// fields are skipped during ancestor merge; the leaf profile's own values
+/// override everything.
+///
+/// Precedence, lowest to highest, matches Bambu Studio's loader:
+/// 1. The resolved `inherits` parent
+/// 2. Each `include` fragment, resolved in turn, in list order (later
+///    fragments override earlier ones)
+/// 3. The profile's own fields
+///
+/// Fragments may themselves inherit or include; the same circular-reference
+/// and `MAX_INHERITANCE_DEPTH` guards apply to both kinds of reference.
 ///
 /// The string `"nil"` (and arrays of all `"nil"` strings) are treated
 /// as "inherit from parent" and do not overwrite parent values.
-pub fn resolve_inheritance(
-    profile: &FilamentProfile,
-    registry: &ProfileRegistry,
-) -> Result<FilamentProfile> {
-    // Build inheritance chain: leaf -> ... -> root
-    let mut chain: Vec<&FilamentProfile> = vec![profile];
-    let mut visited: HashSet<String> = HashSet::new();
-
-    if let Some(name) = profile.name() {
-        visited.insert(name.to_string());
-    }
-
-    // Check for include field (not resolved, just logged)
-    if let Some(include) = profile.raw().get("include") {
-        debug!(
-            "Profile {:?} has include field: {:?} (not resolved in this version)",
-            profile.name().unwrap_or("<unnamed>"),
-            include
-        );
-    }
-
-    let mut current = profile;
-    while let Some(parent_name) = current.inherits() {
-        if parent_name.is_empty() {
-            break;
-        }
-
-        // Guard against circular inheritance
-        if visited.contains(parent_name) {
-            bail!(
-                "Circular inheritance detected: {:?} already visited in chain",
-                parent_name
-            );
-        }
-
-        // Guard against excessive depth
-        if chain.len() >= MAX_INHERITANCE_DEPTH {
-            bail!(
-                "Inheritance chain exceeds maximum depth of {} for profile {:?}",
-                MAX_INHERITANCE_DEPTH,
-                profile.name().unwrap_or("<unnamed>")
-            );
-        }
-
-        let parent = registry.get_by_name(parent_name).ok_or_else(|| {
-            anyhow::anyhow!(
-                "Parent profile not found: {:?} (referenced by {:?})",
-                parent_name,
-                current.name().unwrap_or("<unnamed>")
-            )
-        })?;
-
-        visited.insert(parent_name.to_string());
-
-        // Log include field on parent too
-        if let Some(include) = parent.raw().get("include") {
-            debug!(
-                "Parent profile {:?} has include field: {:?} (not resolved)",
-                parent_name, include
-            );
-        }
-
-        chain.push(parent);
-        current = parent;
-    }
-
-    // Reverse: base first, leaf last
-    chain.reverse();
+pub fn resolve_inheritance<K: PresetKind>(
+    profile: &Preset<K>,
+    registry: &PresetRegistry<K>,
+) -> Result<Preset<K>> {
+    // Build the merge order: base first, leaf last
+    let mut chain: Vec<&Preset<K>> = Vec::new();
+    let mut stack: Vec<&str> = Vec::new();
+    collect_layers(profile, registry, &mut stack, &mut chain)?;
 
     // Merge from base to leaf
     let mut resolved = Map::new();
@@ -115,7 +53,7 @@ pub fn resolve_inheritance(
     for ancestor in &chain[..chain.len().saturating_sub(1)] {
         for (key, value) in ancestor.raw() {
             // Skip metadata fields during ancestor merge
-            if SKIP_INHERIT_FIELDS.contains(&key.as_str()) {
+            if skips_inheritance::<K>(key) {
                 continue;
             }
 
@@ -155,7 +93,7 @@ pub fn resolve_inheritance(
     // automatically include it — no code changes required.
     for ancestor in chain.iter() {
         for (key, value) in ancestor.raw() {
-            if SKIP_INHERIT_FIELDS.contains(&key.as_str()) {
+            if skips_inheritance::<K>(key) {
                 continue;
             }
             if is_nil_value(value) && !resolved.contains_key(key) {
@@ -164,7 +102,197 @@ pub fn resolve_inheritance(
         }
     }
 
-    Ok(FilamentProfile::from_map(resolved))
+    Ok(Preset::from_map(resolved))
+}
+
+/// What `profile` gets from its `inherits` parent and `include` fragments
+/// for each field it could inherit: the value it would have without its
+/// own. Metadata fields (see `skips_inheritance`) are left out.
+pub fn inherited_values<K: PresetKind>(
+    profile: &Preset<K>,
+    registry: &PresetRegistry<K>,
+) -> Result<Map<String, Value>> {
+    let bare: Map<String, Value> = profile
+        .raw()
+        .iter()
+        .filter(|(key, _)| skips_inheritance::<K>(key))
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect();
+    let resolved = resolve_inheritance(&Preset::from_map(bare), registry)?;
+    Ok(resolved
+        .raw()
+        .iter()
+        .filter(|(key, _)| !skips_inheritance::<K>(key))
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect())
+}
+
+/// Reduce a fully flattened profile to a delta over `parent_name`.
+///
+/// The result inherits from the parent and keeps only what re-resolving it
+/// can't recover: `profile`'s own metadata fields (name, IDs, compatibility
+/// lists; see `skips_inheritance`) and every other field whose value
+/// differs from the resolved parent. Resolving the result against
+/// `registry` gives back `profile` with only `inherits` changed.
+///
+/// Fails when that round trip can't hold, naming the fields that would
+/// change: the parent has fields `profile` lacks, or `profile` has `nil`
+/// where the parent has a value (a leaf `nil` means "inherit").
+pub fn minimize<K: PresetKind>(
+    profile: &Preset<K>,
+    parent_name: &str,
+    registry: &PresetRegistry<K>,
+) -> Result<Preset<K>> {
+    if !is_fully_flattened(profile) {
+        bail!(
+            "Profile {:?} already inherits from {:?}; resolve it first",
+            profile.name().unwrap_or("<unnamed>"),
+            profile.inherits().unwrap_or_default()
+        );
+    }
+    let parent = registry
+        .get_by_name(parent_name)
+        .ok_or_else(|| anyhow::anyhow!("Parent profile not found: {:?}", parent_name))?;
+    let resolved_parent = resolve_inheritance(parent, registry)?;
+
+    let mut delta = Map::new();
+    for (key, value) in profile.raw() {
+        match key.as_str() {
+            // Re-resolving must not pull in fragments a second time
+            "include" => continue,
+            "inherits" => {
+                delta.insert(key.clone(), Value::String(parent_name.to_string()));
+            }
+            _ if skips_inheritance::<K>(key) || resolved_parent.raw().get(key) != Some(value) => {
+                delta.insert(key.clone(), value.clone());
+            }
+            _ => {}
+        }
+    }
+    delta
+        .entry("inherits")
+        .or_insert_with(|| Value::String(parent_name.to_string()));
+    let delta = Preset::from_map(delta);
+
+    let resolved = resolve_inheritance(&delta, registry)?;
+    let changed: Vec<&str> = profile
+        .raw()
+        .keys()
+        .chain(resolved.raw().keys())
+        .map(String::as_str)
+        .filter(|key| !matches!(*key, "inherits" | "include"))
+        .filter(|key| profile.raw().get(*key) != resolved.raw().get(*key))
+        .collect::<std::collections::BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    if !changed.is_empty() {
+        bail!(
+            "Profile {:?} can't be expressed as a delta of {:?}; these fields would change: {}",
+            profile.name().unwrap_or("<unnamed>"),
+            parent_name,
+            changed.join(", ")
+        );
+    }
+
+    debug!(
+        "Minimized {:?} against {:?}: {} of {} fields kept",
+        profile.name().unwrap_or("<unnamed>"),
+        parent_name,
+        delta.field_count(),
+        profile.field_count()
+    );
+    Ok(delta)
+}
+
+/// Append the profiles contributing to `profile` to `layers` in merge order
+/// (parent chain, then each include, then `profile` itself).
+///
+/// `stack` holds the names on the current reference path and is used to
+/// detect cycles and enforce the depth limit. A profile reached twice along
+/// different paths (e.g. two fragments including the same template) is not a
+/// cycle; it is simply applied again at its later position.
+fn collect_layers<'a, K: PresetKind>(
+    profile: &'a Preset<K>,
+    registry: &'a PresetRegistry<K>,
+    stack: &mut Vec<&'a str>,
+    layers: &mut Vec<&'a Preset<K>>,
+) -> Result<()> {
+    stack.push(profile.name().unwrap_or(""));
+
+    if let Some(parent_name) = profile.inherits().filter(|p| !p.is_empty()) {
+        let parent = lookup_reference(parent_name, "Parent", profile, registry, stack)?;
+        collect_layers(parent, registry, stack, layers)?;
+    }
+
+    for include_name in include_names(profile) {
+        debug!(
+            "Profile {:?} includes {:?}",
+            profile.name().unwrap_or("<unnamed>"),
+            include_name
+        );
+        let fragment = lookup_reference(include_name, "Included", profile, registry, stack)?;
+        collect_layers(fragment, registry, stack, layers)?;
+    }
+
+    stack.pop();
+    layers.push(profile);
+    Ok(())
+}
+
+/// Look up a profile referenced by `referrer`, applying the cycle and depth
+/// guards. `kind` is "Parent" or "Included" for error messages.
+fn lookup_reference<'a, K: PresetKind>(
+    name: &str,
+    kind: &str,
+    referrer: &Preset<K>,
+    registry: &'a PresetRegistry<K>,
+    stack: &[&str],
+) -> Result<&'a Preset<K>> {
+    // Guard against circular inheritance
+    if stack.contains(&name) {
+        bail!(
+            "Circular inheritance detected: {:?} already visited in chain",
+            name
+        );
+    }
+
+    // Guard against excessive depth
+    if stack.len() >= MAX_INHERITANCE_DEPTH {
+        bail!(
+            "Inheritance chain exceeds maximum depth of {} for profile {:?}",
+            MAX_INHERITANCE_DEPTH,
+            stack
+                .first()
+                .copied()
+                .filter(|n| !n.is_empty())
+                .unwrap_or("<unnamed>")
+        );
+    }
+
+    registry.get_by_name(name).ok_or_else(|| {
+        anyhow::anyhow!(
+            "{} profile not found: {:?} (referenced by {:?})",
+            kind,
+            name,
+            referrer.name().unwrap_or("<unnamed>")
+        )
+    })
+}
+
+/// Names listed in a profile's `include` field.
+///
+/// Bambu Studio writes `include` as an array of profile names; a bare string
+/// is accepted as a single name. Empty entries are ignored.
+fn include_names<K: PresetKind>(profile: &Preset<K>) -> Vec<&str> {
+    match profile.raw().get("include") {
+        Some(Value::String(s)) if !s.is_empty() => vec![s.as_str()],
+        Some(Value::Array(items)) => items
+            .iter()
+            .filter_map(|v| v.as_str())
+            .filter(|s| !s.is_empty())
+            .collect(),
+        _ => Vec::new(),
+    }
 }
 
 /// Check if a value represents "nil" (inherit from parent).
@@ -189,7 +317,7 @@ pub fn is_nil_value(value: &Value) -> bool {
 ///
 /// Returns true if the `inherits` field is empty or missing.
 /// User profiles exported by Bambu Studio are typically fully flattened.
-pub fn is_fully_flattened(profile: &FilamentProfile) -> bool {
+pub fn is_fully_flattened<K: PresetKind>(profile: &Preset<K>) -> bool {
     match profile.inherits() {
         None => true,
         Some(s) => s.is_empty(),
@@ -199,6 +327,8 @@ pub fn is_fully_flattened(profile: &FilamentProfile) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::profile::registry::ProfileRegistry;
+    use crate::profile::types::FilamentProfile;
     use serde_json::{json, Map};
 
     fn make_profile(name: &str, inherits: Option<&str>, extras: &[(&str, serde_json::Value)]) -> FilamentProfile {
@@ -289,4 +419,166 @@ mod tests {
         assert!(resolved.raw().contains_key("leaf_only_nil"),
             "nil field present only on the leaf must be preserved");
     }
+
+    // -- include --
+
+    /// A fragment referenced from two places (directly and through another
+    /// fragment) is a diamond, not a cycle.
+    #[test]
+    fn shared_include_is_not_a_cycle() {
+        let common = make_profile("common", None, &[("speed", json!(["10"]))]);
+        let frag_a = make_profile(
+            "frag_a",
+            None,
+            &[("include", json!(["common"])), ("a", json!(["1"]))],
+        );
+        let leaf = make_profile("leaf", None, &[("include", json!(["common", "frag_a"]))]);
+
+        let registry = registry_of(vec![common, frag_a]);
+        let resolved = resolve_inheritance(&leaf, &registry).unwrap();
+
+        assert_eq!(resolved.raw()["speed"], json!(["10"]));
+        assert_eq!(resolved.raw()["a"], json!(["1"]));
+    }
+
+    /// A bare string `include` is treated as a single fragment name.
+    #[test]
+    fn include_as_string() {
+        let frag = make_profile("frag", None, &[("temp", json!(["230"]))]);
+        let leaf = make_profile("leaf", None, &[("include", json!("frag"))]);
+
+        let registry = registry_of(vec![frag]);
+        let resolved = resolve_inheritance(&leaf, &registry).unwrap();
+
+        assert_eq!(resolved.raw()["temp"], json!(["230"]));
+    }
+
+    #[test]
+    fn missing_include_is_an_error() {
+        let leaf = make_profile("leaf", None, &[("include", json!(["nowhere"]))]);
+
+        let err = match resolve_inheritance(&leaf, &ProfileRegistry::new()) {
+            Ok(_) => panic!("missing include should be rejected"),
+            Err(e) => e,
+        };
+        assert!(
+            err.to_string().contains("Included profile not found"),
+            "{}",
+            err
+        );
+    }
+
+    // -- minimize --
+
+    #[test]
+    fn minimize_round_trips() {
+        let base = make_profile(
+            "base",
+            None,
+            &[
+                ("temp", json!(["200"])),
+                ("speed", json!(["10"])),
+                ("retract", json!(["nil"])),
+                ("filament_id", json!("GFB00")),
+            ],
+        );
+        let registry = registry_of(vec![base]);
+        let flattened = make_profile(
+            "mine",
+            Some(""),
+            &[
+                ("filament_id", json!("P1234567")),
+                ("temp", json!(["215"])),
+                ("speed", json!(["10"])),
+                ("retract", json!(["nil"])),
+            ],
+        );
+
+        let delta = minimize(&flattened, "base", &registry).unwrap();
+        assert_eq!(delta.inherits(), Some("base"));
+        let keys: Vec<&str> = delta.raw().keys().map(String::as_str).collect();
+        assert_eq!(keys, ["name", "inherits", "filament_id", "temp"]);
+
+        let mut resolved = resolve_inheritance(&delta, &registry).unwrap();
+        resolved.set_string("inherits", String::new());
+        assert_eq!(resolved.raw(), flattened.raw());
+    }
+
+    #[test]
+    fn minimize_rejects_unrepresentable_fields() {
+        let base = make_profile(
+            "base",
+            None,
+            &[("temp", json!(["200"])), ("extra", json!(["1"]))],
+        );
+        let registry = registry_of(vec![base]);
+        let flattened = make_profile("mine", None, &[("temp", json!(["nil"]))]);
+
+        let err = match minimize(&flattened, "base", &registry) {
+            Ok(_) => panic!("nil over a parent value should be rejected"),
+            Err(e) => e,
+        };
+        assert!(
+            err.to_string().ends_with("would change: extra, temp"),
+            "{}",
+            err
+        );
+
+        let inherited = make_profile("other", Some("base"), &[]);
+        assert!(minimize(&inherited, "base", &registry).is_err());
+    }
+
+    /// Nesting through `include` counts toward the same depth limit as `inherits`.
+    #[test]
+    fn include_depth_limit() {
+        let mut profiles = Vec::new();
+        for i in 0..MAX_INHERITANCE_DEPTH {
+            let next = format!("frag{}", i + 1);
+            profiles.push(make_profile(
+                &format!("frag{}", i),
+                None,
+                &[("include", json!([next]))],
+            ));
+        }
+        profiles.push(make_profile(
+            &format!("frag{}", MAX_INHERITANCE_DEPTH),
+            None,
+            &[],
+        ));
+        let leaf = make_profile("leaf", None, &[("include", json!(["frag0"]))]);
+
+        let registry = registry_of(profiles);
+        let err = match resolve_inheritance(&leaf, &registry) {
+            Ok(_) => panic!("over-deep include chain should be rejected"),
+            Err(e) => e,
+        };
+        assert!(err.to_string().contains("maximum depth"), "{}", err);
+    }
+
+    #[test]
+    fn inherited_values_ignore_own_fields_and_metadata() {
+        let base = make_profile(
+            "base",
+            None,
+            &[
+                ("nozzle_temperature", json!(["210"])),
+                ("fan_max_speed", json!(["80"])),
+            ],
+        );
+        let leaf = make_profile(
+            "leaf",
+            Some("base"),
+            &[
+                ("nozzle_temperature", json!(["230"])),
+                ("chamber_temperatures", json!(["40"])),
+            ],
+        );
+        let registry = registry_of(vec![base]);
+
+        let inherited = inherited_values(&leaf, &registry).unwrap();
+        assert_eq!(inherited["nozzle_temperature"], json!(["210"]));
+        assert_eq!(inherited["fan_max_speed"], json!(["80"]));
+        assert!(!inherited.contains_key("chamber_temperatures"));
+        assert!(!inherited.contains_key("name"));
+    }
 }
@@ -0,0 +1,531 @@
+//! Profile linter: checks a filament profile against the field dictionary
+//! (see `profile::fields`).
+//!
+//! Bambu Studio rejects or silently drops profiles whose fields have the
+//! wrong shape, so everything BambuMate writes goes through [`lint_profile`]
+//! first. The dictionary only covers fields BambuMate writes or reads;
+//! anything else is passed through unchecked.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use super::fields::{dictionary, Arity, FieldInfo, FieldKind};
+use super::types::FilamentProfile;
+
+use Arity::{List, PerExtruder, Scalar};
+use FieldKind::{Bool, Float, Int, Percent, Text};
+
+/// How bad a diagnostic is. Profiles with errors are not written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// What a diagnostic is about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LintCode {
+    /// A required field is absent from a flattened profile.
+    MissingField,
+    /// Scalar where an array is expected, or the other way round.
+    WrongShape,
+    /// Per-extruder array with the wrong number of elements.
+    WrongArity,
+    /// `nil` in a field that needs a concrete value.
+    UnexpectedNil,
+    /// Value doesn't parse as the field's type.
+    InvalidValue,
+    /// Value outside the field's allowed range.
+    OutOfRange,
+    /// Value not among the field's allowed options.
+    NotAllowed,
+    /// Fields that disagree with each other.
+    Inconsistent,
+}
+
+/// One problem found in a profile.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: LintCode,
+    pub key: String,
+    /// Element index for per-extruder and list fields.
+    pub index: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.index {
+            Some(i) => write!(f, "{}[{}]: {}", self.key, i, self.message),
+            None => write!(f, "{}: {}", self.key, self.message),
+        }
+    }
+}
+
+/// Everything [`lint_profile`] found.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LintReport {
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl LintReport {
+    pub fn has_errors(&self) -> bool {
+        self.errors().next().is_some()
+    }
+
+    pub fn errors(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics
+            .iter()
+            .filter(|d| d.severity == Severity::Error)
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics
+            .iter()
+            .filter(|d| d.severity == Severity::Warning)
+    }
+
+    /// Only the diagnostics for `keys`. Edits are checked this way, so a
+    /// problem elsewhere in the profile (such as per-extruder arrays sized
+    /// before they followed the printer) doesn't block them.
+    pub fn for_keys(self, keys: &[&str]) -> LintReport {
+        LintReport {
+            diagnostics: self
+                .diagnostics
+                .into_iter()
+                .filter(|d| keys.contains(&d.key.as_str()))
+                .collect(),
+        }
+    }
+
+    /// The errors as one line, for command error messages.
+    pub fn error_summary(&self) -> String {
+        self.errors()
+            .map(|d| d.to_string())
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+
+    fn push(
+        &mut self,
+        severity: Severity,
+        code: LintCode,
+        key: &str,
+        index: Option<usize>,
+        message: String,
+    ) {
+        self.diagnostics.push(Diagnostic {
+            severity,
+            code,
+            key: key.to_string(),
+            index,
+            message,
+        });
+    }
+}
+
+/// Context the dictionary alone can't supply.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LintOptions {
+    /// Extruder count of the target printer. When unknown, per-extruder
+    /// arrays only have to agree with each other.
+    pub extruder_count: Option<usize>,
+}
+
+/// Check `profile` against the field dictionary.
+///
+/// Required fields are only checked on flattened profiles: a profile that
+/// inherits gets the rest from its parent.
+pub fn lint_profile(profile: &FilamentProfile, options: &LintOptions) -> LintReport {
+    let mut report = LintReport::default();
+    let raw = profile.raw();
+    let flattened = profile.inherits().is_none_or(str::is_empty);
+
+    let mut lengths: BTreeMap<usize, Vec<&str>> = BTreeMap::new();
+    for spec in dictionary().fields() {
+        let Some(value) = raw.get(&spec.key) else {
+            if spec.required && flattened {
+                report.push(
+                    Severity::Error,
+                    LintCode::MissingField,
+                    &spec.key,
+                    None,
+                    "required field is missing".to_string(),
+                );
+            }
+            continue;
+        };
+        match (spec.arity, value) {
+            (Scalar, Value::String(s)) => check_value(&mut report, spec, None, s),
+            (Scalar, _) => report.push(
+                Severity::Error,
+                LintCode::WrongShape,
+                &spec.key,
+                None,
+                "expected a string".to_string(),
+            ),
+            (PerExtruder | List, Value::Array(items)) => {
+                if spec.arity == PerExtruder {
+                    if items.is_empty() {
+                        report.push(
+                            Severity::Error,
+                            LintCode::WrongArity,
+                            &spec.key,
+                            None,
+                            "needs at least one value".to_string(),
+                        );
+                    } else {
+                        lengths.entry(items.len()).or_default().push(&spec.key);
+                    }
+                }
+                for (i, item) in items.iter().enumerate() {
+                    match item {
+                        Value::String(s) => check_value(&mut report, spec, Some(i), s),
+                        _ => report.push(
+                            Severity::Error,
+                            LintCode::WrongShape,
+                            &spec.key,
+                            Some(i),
+                            "expected a string".to_string(),
+                        ),
+                    }
+                }
+            }
+            (PerExtruder | List, _) => report.push(
+                Severity::Error,
+                LintCode::WrongShape,
+                &spec.key,
+                None,
+                "expected an array".to_string(),
+            ),
+        }
+    }
+
+    check_arity(&mut report, &lengths, options.extruder_count);
+    check_temperature_range(&mut report, profile);
+    report
+}
+
+fn check_value(report: &mut LintReport, spec: &FieldInfo, index: Option<usize>, value: &str) {
+    if value == "nil" {
+        if !spec.nullable {
+            report.push(
+                Severity::Error,
+                LintCode::UnexpectedNil,
+                &spec.key,
+                index,
+                "'nil' where a value is required".to_string(),
+            );
+        }
+        return;
+    }
+    let number = match spec.kind {
+        Text => return,
+        FieldKind::Enum => {
+            if !spec.options.iter().any(|option| option == value) {
+                report.push(
+                    Severity::Error,
+                    LintCode::NotAllowed,
+                    &spec.key,
+                    index,
+                    format!("'{}' is not one of: {}", value, spec.options.join(", ")),
+                );
+            }
+            return;
+        }
+        Bool => {
+            if value != "0" && value != "1" {
+                report.push(
+                    Severity::Error,
+                    LintCode::InvalidValue,
+                    &spec.key,
+                    index,
+                    format!("'{}' is not 0 or 1", value),
+                );
+            }
+            return;
+        }
+        Int => value.trim().parse::<i64>().ok().map(|n| n as f64),
+        Float => value.trim().parse::<f64>().ok().filter(|n| n.is_finite()),
+        Percent => value
+            .trim()
+            .trim_end_matches('%')
+            .parse::<f64>()
+            .ok()
+            .filter(|n| n.is_finite()),
+    };
+    let Some(number) = number else {
+        let expected = match spec.kind {
+            Int => "a whole number",
+            Percent => "a percentage",
+            _ => "a number",
+        };
+        report.push(
+            Severity::Error,
+            LintCode::InvalidValue,
+            &spec.key,
+            index,
+            format!("'{}' is not {}", value, expected),
+        );
+        return;
+    };
+    if let Some([min, max]) = spec.range {
+        if number < min || number > max {
+            report.push(
+                Severity::Error,
+                LintCode::OutOfRange,
+                &spec.key,
+                index,
+                format!("{} is outside {}-{}", value, min, max),
+            );
+        }
+    }
+}
+
+/// Per-extruder arrays hold one element per extruder, or a single element
+/// Bambu Studio uses for every extruder.
+fn check_arity(
+    report: &mut LintReport,
+    lengths: &BTreeMap<usize, Vec<&str>>,
+    extruder_count: Option<usize>,
+) {
+    match extruder_count {
+        Some(count) => {
+            for (len, keys) in lengths {
+                if *len != 1 && *len != count {
+                    for key in keys {
+                        report.push(
+                            Severity::Error,
+                            LintCode::WrongArity,
+                            key,
+                            None,
+                            format!(
+                                "has {} values; the printer has {} extruder{}",
+                                len,
+                                count,
+                                if count == 1 { "" } else { "s" }
+                            ),
+                        );
+                    }
+                }
+            }
+        }
+        None => {
+            let multi: Vec<(&usize, &Vec<&str>)> =
+                lengths.iter().filter(|(len, _)| **len > 1).collect();
+            if multi.len() > 1 {
+                // Blame the less common lengths
+                let (majority, _) = multi
+                    .iter()
+                    .max_by_key(|(len, keys)| (keys.len(), **len))
+                    .copied()
+                    .unwrap_or((&1, &Vec::new()));
+                for (len, keys) in &multi {
+                    if *len == majority {
+                        continue;
+                    }
+                    for key in keys.iter() {
+                        report.push(
+                            Severity::Error,
+                            LintCode::WrongArity,
+                            key,
+                            None,
+                            format!(
+                                "has {} values where other per-extruder fields have {}",
+                                len, majority
+                            ),
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Printing temperatures outside the profile's own slider range load fine
+/// but confuse Bambu Studio's temperature checks.
+fn check_temperature_range(report: &mut LintReport, profile: &FilamentProfile) {
+    let Some((low, high)) = profile.nozzle_temperature_range() else {
+        return;
+    };
+    if low > high {
+        report.push(
+            Severity::Error,
+            LintCode::Inconsistent,
+            "nozzle_temperature_range_low",
+            None,
+            format!(
+                "{}C is above nozzle_temperature_range_high ({}C)",
+                low, high
+            ),
+        );
+        return;
+    }
+    for key in ["nozzle_temperature", "nozzle_temperature_initial_layer"] {
+        let Some(values) = profile.get_string_array(key) else {
+            continue;
+        };
+        for (i, value) in values.iter().enumerate() {
+            if let Ok(temp) = value.trim().parse::<u16>() {
+                if temp < low || temp > high {
+                    report.push(
+                        Severity::Warning,
+                        LintCode::Inconsistent,
+                        key,
+                        Some(i),
+                        format!(
+                            "{}C is outside the profile's range ({}-{}C)",
+                            temp, low, high
+                        ),
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn profile(data: Value) -> FilamentProfile {
+        FilamentProfile::from_json(&data.to_string()).unwrap()
+    }
+
+    fn complete() -> Value {
+        json!({
+            "name": "Polymaker PLA Matte @BBL X1C",
+            "inherits": "",
+            "from": "User",
+            "filament_id": "P1a2b3c4",
+            "instantiation": "true",
+            "filament_type": ["PLA"],
+            "nozzle_temperature": ["215", "215"],
+            "nozzle_temperature_range_low": ["190"],
+            "nozzle_temperature_range_high": ["230"],
+            "filament_retraction_length": ["nil", "nil"],
+            "fan_max_speed": ["80%", "80%"],
+        })
+    }
+
+    fn codes(report: &LintReport) -> Vec<(String, LintCode)> {
+        report
+            .diagnostics
+            .iter()
+            .map(|d| (d.key.clone(), d.code))
+            .collect()
+    }
+
+    #[test]
+    fn test_clean_profile() {
+        let report = lint_profile(&profile(complete()), &LintOptions::default());
+        assert!(report.diagnostics.is_empty(), "{:?}", report.diagnostics);
+    }
+
+    #[test]
+    fn test_missing_fields_only_when_flattened() {
+        let report = lint_profile(
+            &profile(json!({"name": "Mine", "inherits": ""})),
+            &LintOptions::default(),
+        );
+        let missing: Vec<String> = report
+            .errors()
+            .filter(|d| d.code == LintCode::MissingField)
+            .map(|d| d.key.clone())
+            .collect();
+        assert_eq!(
+            missing,
+            ["filament_id", "filament_type", "nozzle_temperature"]
+        );
+
+        let report = lint_profile(
+            &profile(json!({"name": "Mine", "inherits": "Bambu PLA Basic @BBL X1C"})),
+            &LintOptions::default(),
+        );
+        assert!(!report.has_errors());
+    }
+
+    #[test]
+    fn test_values_and_shapes() {
+        let mut data = complete();
+        data["nozzle_temperature"] = json!(["nil", "215"]);
+        data["filament_flow_ratio"] = json!(["0.98x"]);
+        data["fan_min_speed"] = json!(["120%"]);
+        data["instantiation"] = json!("yes");
+        data["filament_id"] = json!(["P1a2b3c4"]);
+        data["hot_plate_temp"] = json!("60");
+        let report = lint_profile(&profile(data), &LintOptions::default());
+        assert_eq!(
+            codes(&report),
+            [
+                ("filament_id".to_string(), LintCode::WrongShape),
+                ("instantiation".to_string(), LintCode::NotAllowed),
+                ("nozzle_temperature".to_string(), LintCode::UnexpectedNil),
+                ("hot_plate_temp".to_string(), LintCode::WrongShape),
+                ("filament_flow_ratio".to_string(), LintCode::InvalidValue),
+                ("fan_min_speed".to_string(), LintCode::OutOfRange),
+            ]
+        );
+        assert!(report.error_summary().contains("nozzle_temperature[0]"));
+    }
+
+    #[test]
+    fn test_per_extruder_arity() {
+        let mut data = complete();
+        data["filament_flow_ratio"] = json!(["0.98", "0.98", "0.98"]);
+        let report = lint_profile(&profile(data.clone()), &LintOptions::default());
+        assert_eq!(
+            codes(&report),
+            [("filament_flow_ratio".to_string(), LintCode::WrongArity)]
+        );
+
+        // Against a single-extruder printer the dual arrays are wrong too;
+        // single values are always fine
+        let report = lint_profile(
+            &profile(data),
+            &LintOptions {
+                extruder_count: Some(1),
+            },
+        );
+        let keys: Vec<String> = report.errors().map(|d| d.key.clone()).collect();
+        assert_eq!(
+            keys,
+            [
+                "nozzle_temperature",
+                "fan_max_speed",
+                "filament_retraction_length",
+                "filament_flow_ratio"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_for_keys_keeps_only_those_keys() {
+        let report = lint_profile(
+            &profile(complete()),
+            &LintOptions {
+                extruder_count: Some(1),
+            },
+        );
+        assert!(report.has_errors());
+        let report = report.for_keys(&["filament_vendor", "fan_max_speed"]);
+        let keys: Vec<String> = report.diagnostics.iter().map(|d| d.key.clone()).collect();
+        assert_eq!(keys, ["fan_max_speed"]);
+    }
+
+    #[test]
+    fn test_temperature_outside_own_range_warns() {
+        let mut data = complete();
+        data["nozzle_temperature"] = json!(["235"]);
+        let report = lint_profile(&profile(data), &LintOptions::default());
+        assert!(!report.has_errors());
+        assert_eq!(report.warnings().count(), 1);
+    }
+}
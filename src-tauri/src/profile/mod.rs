@@ -1,13 +1,29 @@
+pub mod bundle;
+pub mod conflict;
+pub mod family;
+pub mod field_edit;
+pub mod fields;
 pub mod generator;
 pub mod inheritance;
+pub mod lint;
+pub mod nozzle;
 pub mod paths;
+pub mod query;
 pub mod reader;
+pub mod rebase;
 pub mod registry;
+pub mod removal;
+pub mod slicer;
+pub mod snapshot;
+pub mod sync;
 pub mod types;
 pub mod writer;
 
 pub use generator::{find_existing_filament_id, generate_profile, is_bambu_studio_running};
 pub use paths::BambuPaths;
-pub use registry::ProfileRegistry;
-pub use types::{FilamentProfile, ProfileMetadata};
+pub use registry::{MachineRegistry, PresetRegistry, ProcessRegistry, ProfileRegistry};
+pub use types::{
+    Filament, FilamentProfile, Machine, MachinePreset, Preset, PresetKind, Process, ProcessPreset,
+    ProfileMetadata,
+};
 pub use writer::write_profile_atomic;
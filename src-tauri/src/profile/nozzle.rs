@@ -0,0 +1,356 @@
+//! Nozzle-aware scaling of filament specs.
+//!
+//! Scraped specs describe a standard 0.4mm nozzle. A 0.2mm nozzle can't melt
+//! anywhere near that volumetric speed and an 0.8mm one wants more
+//! retraction, so before specs are applied for another nozzle their
+//! volumetric speed, retraction length and flow ratio are scaled by the
+//! table in `src-tauri/config/nozzle_scaling.toml`.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use anyhow::Result;
+use serde::Deserialize;
+use tracing::debug;
+
+use super::inheritance::resolve_inheritance;
+use super::registry::MachineRegistry;
+use crate::scraper::types::{FilamentSpecs, MaterialType};
+
+/// Scaling table embedded in the binary at compile time.
+const DEFAULT_TABLE: &str = include_str!("../../config/nozzle_scaling.toml");
+
+/// Kind of nozzle, as far as scaling is concerned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NozzleType {
+    /// Stainless steel or brass.
+    Standard,
+    /// Hardened steel or tungsten carbide.
+    Hardened,
+    /// A high-flow hotend.
+    HighFlow,
+}
+
+impl NozzleType {
+    fn table_key(self) -> Option<&'static str> {
+        match self {
+            NozzleType::Standard => None,
+            NozzleType::Hardened => Some("hardened"),
+            NozzleType::HighFlow => Some("high_flow"),
+        }
+    }
+}
+
+/// The nozzle a profile is generated for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Nozzle {
+    /// Diameter in mm.
+    pub diameter: f32,
+    pub nozzle_type: NozzleType,
+}
+
+impl Nozzle {
+    /// The nozzle `FilamentSpecs` values are written for.
+    pub const REFERENCE: Nozzle = Nozzle {
+        diameter: 0.4,
+        nozzle_type: NozzleType::Standard,
+    };
+}
+
+/// `[diameter, factor]` points, sorted by diameter.
+type Curve = Vec<[f32; 2]>;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct MaterialScaling {
+    volumetric_speed: Option<Curve>,
+    retraction_length: Option<Curve>,
+    flow_ratio: Option<Curve>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct TypeScaling {
+    #[serde(default = "one")]
+    volumetric_speed: f32,
+    #[serde(default = "one")]
+    retraction_length: f32,
+    #[serde(default = "one")]
+    flow_ratio: f32,
+}
+
+fn one() -> f32 {
+    1.0
+}
+
+/// Multipliers for the scaled specs, relative to the reference nozzle.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScaleFactors {
+    pub volumetric_speed: f32,
+    pub retraction_length: f32,
+    pub flow_ratio: f32,
+}
+
+/// Per-material nozzle scaling curves (see `config/nozzle_scaling.toml`).
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScalingTable {
+    default: MaterialScaling,
+    #[serde(default)]
+    materials: HashMap<String, MaterialScaling>,
+    #[serde(default)]
+    nozzle_types: HashMap<String, TypeScaling>,
+}
+
+impl ScalingTable {
+    /// Parse a scaling table from TOML.
+    pub fn from_toml(content: &str) -> Result<Self> {
+        let mut table: ScalingTable = toml::from_str(content)?;
+        for scaling in std::iter::once(&mut table.default).chain(table.materials.values_mut()) {
+            for curve in [
+                &mut scaling.volumetric_speed,
+                &mut scaling.retraction_length,
+                &mut scaling.flow_ratio,
+            ]
+            .into_iter()
+            .flatten()
+            {
+                curve.sort_by(|a, b| a[0].total_cmp(&b[0]));
+            }
+        }
+        Ok(table)
+    }
+
+    /// Factors for `material` on `nozzle`, relative to the reference nozzle.
+    pub fn factors(&self, material: &str, nozzle: Nozzle) -> ScaleFactors {
+        let material_scaling = self.material_scaling(material);
+        let curve = |pick: fn(&MaterialScaling) -> &Option<Curve>| {
+            let curve = material_scaling
+                .and_then(|s| pick(s).as_ref())
+                .or(pick(&self.default).as_ref());
+            curve.map_or(1.0, |c| interpolate(c, nozzle.diameter))
+        };
+        let type_scaling = nozzle
+            .nozzle_type
+            .table_key()
+            .and_then(|key| self.nozzle_types.get(key));
+
+        ScaleFactors {
+            volumetric_speed: curve(|s| &s.volumetric_speed)
+                * type_scaling.map_or(1.0, |t| t.volumetric_speed),
+            retraction_length: curve(|s| &s.retraction_length)
+                * type_scaling.map_or(1.0, |t| t.retraction_length),
+            flow_ratio: curve(|s| &s.flow_ratio) * type_scaling.map_or(1.0, |t| t.flow_ratio),
+        }
+    }
+
+    /// `specs` written for nozzle `from`, rescaled for nozzle `to`.
+    /// Extruder overrides are scaled the same way as the filament-wide
+    /// values.
+    pub fn scale_specs(&self, specs: &FilamentSpecs, from: Nozzle, to: Nozzle) -> FilamentSpecs {
+        let mut scaled = specs.clone();
+        if from == to {
+            return scaled;
+        }
+        let f = self.factors(&specs.material, from);
+        let t = self.factors(&specs.material, to);
+        let volumetric = |v: Option<f32>| v.map(|v| v * t.volumetric_speed / f.volumetric_speed);
+        let retraction = |v: Option<f32>| v.map(|v| v * t.retraction_length / f.retraction_length);
+        let flow = |v: Option<f32>| v.map(|v| v * t.flow_ratio / f.flow_ratio);
+
+        scaled.max_volumetric_speed = volumetric(specs.max_volumetric_speed);
+        scaled.retraction_distance_mm = retraction(specs.retraction_distance_mm);
+        scaled.filament_flow_ratio = flow(specs.filament_flow_ratio);
+        for o in &mut scaled.extruder_overrides {
+            o.max_volumetric_speed = volumetric(o.max_volumetric_speed);
+            o.retraction_distance_mm = retraction(o.retraction_distance_mm);
+            o.filament_flow_ratio = flow(o.filament_flow_ratio);
+        }
+        debug!(
+            "Scaled {} specs from {:?} to {:?}: volumetric x{:.2}, retraction x{:.2}, flow x{:.3}",
+            specs.material,
+            from,
+            to,
+            t.volumetric_speed / f.volumetric_speed,
+            t.retraction_length / f.retraction_length,
+            t.flow_ratio / f.flow_ratio,
+        );
+        scaled
+    }
+
+    /// The material's own curves: by exact name first, then by family.
+    fn material_scaling(&self, material: &str) -> Option<&MaterialScaling> {
+        let upper = material.trim().to_uppercase();
+        self.materials.get(&upper).or_else(|| {
+            let family = match MaterialType::from_str(material) {
+                MaterialType::PLA => "PLA",
+                MaterialType::PETG => "PETG",
+                MaterialType::ABS => "ABS",
+                MaterialType::ASA => "ASA",
+                MaterialType::TPU => "TPU",
+                MaterialType::Nylon => "NYLON",
+                MaterialType::PC => "PC",
+                MaterialType::PVA => "PVA",
+                MaterialType::HIPS => "HIPS",
+                MaterialType::Other(_) => return None,
+            };
+            self.materials.get(family)
+        })
+    }
+}
+
+/// The embedded scaling table.
+///
+/// # Panics
+/// Panics if the embedded TOML is invalid (this would be a compile-time bug).
+pub fn default_table() -> &'static ScalingTable {
+    static TABLE: OnceLock<ScalingTable> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        ScalingTable::from_toml(DEFAULT_TABLE)
+            .expect("embedded nozzle_scaling.toml must be a valid scaling table")
+    })
+}
+
+/// Linear interpolation along `curve`, clamped to its end points.
+fn interpolate(curve: &[[f32; 2]], diameter: f32) -> f32 {
+    let (Some(first), Some(last)) = (curve.first(), curve.last()) else {
+        return 1.0;
+    };
+    if diameter <= first[0] {
+        return first[1];
+    }
+    if diameter >= last[0] {
+        return last[1];
+    }
+    curve
+        .windows(2)
+        .find(|pair| diameter <= pair[1][0])
+        .map_or(last[1], |pair| {
+            let [(d0, f0), (d1, f1)] = [(pair[0][0], pair[0][1]), (pair[1][0], pair[1][1])];
+            f0 + (f1 - f0) * (diameter - d0) / (d1 - d0)
+        })
+}
+
+/// The nozzle of printer preset `printer`: from its machine preset when
+/// installed, else the diameter in its name ("... 0.6 nozzle"). None when
+/// neither tells.
+pub fn printer_nozzle(machines: &MachineRegistry, printer: &str) -> Option<Nozzle> {
+    let resolved = machines
+        .get_by_name(printer)
+        .and_then(|preset| resolve_inheritance(preset, machines).ok());
+
+    let diameter = resolved
+        .as_ref()
+        .and_then(|m| m.nozzle_diameter()?.first()?.parse::<f32>().ok())
+        .or_else(|| diameter_from_name(printer))?;
+
+    let first = |key: &str| -> Option<String> {
+        let m = resolved.as_ref()?;
+        m.get_first_array_value(key)
+            .or_else(|| m.get_scalar(key))
+            .map(str::to_lowercase)
+    };
+    let nozzle_type = if first("nozzle_volume_type").is_some_and(|v| v.contains("high flow")) {
+        NozzleType::HighFlow
+    } else if first("nozzle_type").is_some_and(|v| v.contains("hardened") || v.contains("tungsten"))
+    {
+        NozzleType::Hardened
+    } else {
+        NozzleType::Standard
+    };
+
+    Some(Nozzle {
+        diameter,
+        nozzle_type,
+    })
+}
+
+/// Diameter from a printer preset name like "Bambu Lab X1 Carbon 0.6 nozzle".
+fn diameter_from_name(printer: &str) -> Option<f32> {
+    let before = printer.strip_suffix(" nozzle")?;
+    before.rsplit(' ').next()?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nozzle(diameter: f32) -> Nozzle {
+        Nozzle {
+            diameter,
+            nozzle_type: NozzleType::Standard,
+        }
+    }
+
+    fn specs(material: &str) -> FilamentSpecs {
+        FilamentSpecs {
+            material: material.to_string(),
+            max_volumetric_speed: Some(20.0),
+            retraction_distance_mm: Some(0.8),
+            filament_flow_ratio: Some(0.98),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_embedded_table_parses_with_reference_at_one() {
+        let factors = default_table().factors("PLA", Nozzle::REFERENCE);
+        assert_eq!(
+            factors,
+            ScaleFactors {
+                volumetric_speed: 1.0,
+                retraction_length: 1.0,
+                flow_ratio: 1.0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_scale_specs_by_diameter_and_type() {
+        let table = default_table();
+        let small = table.scale_specs(&specs("PLA"), Nozzle::REFERENCE, nozzle(0.2));
+        assert_eq!(small.max_volumetric_speed, Some(5.0));
+        let large = table.scale_specs(&specs("PLA"), Nozzle::REFERENCE, nozzle(0.8));
+        assert!(large.max_volumetric_speed.unwrap() > 20.0);
+        assert!(large.retraction_distance_mm.unwrap() > 0.8);
+
+        // Scaling back to the reference nozzle recovers the original specs
+        let back = table.scale_specs(&large, nozzle(0.8), Nozzle::REFERENCE);
+        assert!((back.max_volumetric_speed.unwrap() - 20.0).abs() < 1e-4);
+
+        let high_flow = Nozzle {
+            diameter: 0.4,
+            nozzle_type: NozzleType::HighFlow,
+        };
+        let fast = table.scale_specs(&specs("PLA"), Nozzle::REFERENCE, high_flow);
+        assert!(fast.max_volumetric_speed.unwrap() > 20.0);
+        assert_eq!(fast.retraction_distance_mm, Some(0.8));
+    }
+
+    #[test]
+    fn test_material_curves_override_default() {
+        let table = default_table();
+        // TPU keeps its retraction on big nozzles; PLA-CF falls back to PLA
+        // and then to the default curves
+        assert_eq!(table.factors("TPU 95A", nozzle(0.8)).retraction_length, 1.0);
+        assert_eq!(
+            table.factors("PLA-CF", nozzle(0.8)),
+            table.factors("Mystery", nozzle(0.8))
+        );
+    }
+
+    #[test]
+    fn test_interpolate_between_points() {
+        let curve = [[0.2, 0.5], [0.4, 1.0]];
+        assert!((interpolate(&curve, 0.3) - 0.75).abs() < 1e-6);
+        assert_eq!(interpolate(&curve, 0.1), 0.5);
+        assert_eq!(interpolate(&curve, 1.0), 1.0);
+    }
+
+    #[test]
+    fn test_printer_nozzle_from_name() {
+        let machines = MachineRegistry::new();
+        assert_eq!(
+            printer_nozzle(&machines, "Bambu Lab X1 Carbon 0.6 nozzle"),
+            Some(nozzle(0.6))
+        );
+        assert_eq!(printer_nozzle(&machines, "Custom Printer"), None);
+    }
+}
@@ -2,6 +2,15 @@ use anyhow::{bail, Result};
 use std::path::{Path, PathBuf};
 use tracing::{debug, warn};
 
+use super::types::{Filament, PresetKind};
+
+/// Flathub application ID for Bambu Studio.
+///
+/// The Flatpak build keeps its config under
+/// `~/.var/app/com.bambulab.BambuStudio/config/BambuStudio/` and exports a
+/// launcher script named after the app ID.
+pub const FLATPAK_APP_ID: &str = "com.bambulab.BambuStudio";
+
 /// Resolved paths to Bambu Studio configuration and profile directories.
 pub struct BambuPaths {
     /// Root configuration directory (e.g., ~/Library/Application Support/BambuStudio/)
@@ -19,9 +28,20 @@ impl BambuPaths {
     ///
     /// On macOS, looks for `~/Library/Application Support/BambuStudio/`.
     /// On Windows, looks for `%APPDATA%\BambuStudio\`.
+    /// On Linux, looks for `$XDG_CONFIG_HOME/BambuStudio/` (native and
+    /// AppImage installs) and the Flatpak sandbox config directory.
     /// Reads `preset_folder` from `BambuStudio.conf` if available.
     pub fn detect() -> Result<Self> {
         let config_root = Self::find_config_root()?;
+        Ok(Self::from_config_root(config_root))
+    }
+
+    /// Build paths from a known config root directory.
+    ///
+    /// Used by `detect()` once the platform lookup has found the root, and
+    /// directly by callers that already know where the config lives (a
+    /// user-selected folder, or a fixture tree in tests).
+    pub fn from_config_root(config_root: PathBuf) -> Self {
         let system_filaments = config_root.join("system").join("BBL").join("filament");
         let user_root = config_root.join("user");
 
@@ -32,12 +52,12 @@ impl BambuPaths {
             debug!("No preset_folder found in BambuStudio.conf");
         }
 
-        Ok(Self {
+        Self {
             config_root,
             system_filaments,
             user_root,
             preset_folder,
-        })
+        }
     }
 
     /// Find the Bambu Studio config root directory.
@@ -117,10 +137,54 @@ impl BambuPaths {
         bail!("Bambu Studio config directory not found. Is Bambu Studio installed?")
     }
 
-    /// Linux stub -- not yet supported.
+    /// Find Bambu Studio config root on Linux.
+    ///
+    /// See `find_linux_config_root` for the search order.
     #[cfg(target_os = "linux")]
     fn find_config_root() -> Result<PathBuf> {
-        bail!("Linux support is not yet implemented")
+        let home = dirs::home_dir()
+            .ok_or_else(|| anyhow::anyhow!("Could not determine the home directory"))?;
+        let xdg_config_home = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .filter(|p| p.is_absolute());
+
+        if let Some(bs_dir) = Self::find_linux_config_root(&home, xdg_config_home.as_deref()) {
+            return Ok(bs_dir);
+        }
+
+        bail!("Bambu Studio config directory not found. Is Bambu Studio installed?")
+    }
+
+    /// Search a Linux home directory for the Bambu Studio config root.
+    ///
+    /// Searches in order:
+    /// 1. `$XDG_CONFIG_HOME/BambuStudio/` (when set)
+    /// 2. `~/.config/BambuStudio/` (native packages and AppImage builds)
+    /// 3. `~/.var/app/com.bambulab.BambuStudio/config/BambuStudio/` (Flatpak)
+    ///
+    /// Takes the home directory explicitly so the lookup can be exercised
+    /// against fixture trees on any platform.
+    pub fn find_linux_config_root(home: &Path, xdg_config_home: Option<&Path>) -> Option<PathBuf> {
+        let mut candidates = Vec::with_capacity(3);
+        if let Some(xdg) = xdg_config_home {
+            candidates.push(xdg.join("BambuStudio"));
+        }
+        candidates.push(home.join(".config").join("BambuStudio"));
+        candidates.push(
+            home.join(".var")
+                .join("app")
+                .join(FLATPAK_APP_ID)
+                .join("config")
+                .join("BambuStudio"),
+        );
+
+        for bs_dir in candidates {
+            if bs_dir.is_dir() {
+                debug!("Found Bambu Studio config at {:?}", bs_dir);
+                return Some(bs_dir);
+            }
+        }
+        None
     }
 
     /// Fallback for other platforms.
@@ -155,30 +219,43 @@ impl BambuPaths {
     /// to scanning for non-"default" directories that have a `filament/base/`
     /// subdirectory.
     pub fn user_filament_dir(&self) -> Option<PathBuf> {
+        self.user_preset_dir::<Filament>()
+    }
+
+    /// Get the active user preset directory for kind `K`
+    /// (`user/{preset_folder}/{filament,process,machine}/base/`).
+    ///
+    /// Same lookup as `user_filament_dir`: the `preset_folder` from
+    /// BambuStudio.conf first, then any non-"default" user directory.
+    pub fn user_preset_dir<K: PresetKind>(&self) -> Option<PathBuf> {
         // Try preset_folder first
         if let Some(ref folder) = self.preset_folder {
-            let path = self.user_root.join(folder).join("filament").join("base");
+            let path = self.user_root.join(folder).join(K::FOLDER).join("base");
             if path.exists() {
-                debug!("Found user filament dir via preset_folder: {:?}", path);
+                debug!("Found user {} dir via preset_folder: {:?}", K::FOLDER, path);
                 return Some(path);
             }
         }
 
-        // Fallback: scan for non-default directories with filament/base/
+        // Fallback: scan for non-default directories with <kind>/base/
         if let Ok(entries) = std::fs::read_dir(&self.user_root) {
             for entry in entries.flatten() {
                 let name = entry.file_name().to_string_lossy().to_string();
                 if name != "default" && entry.path().is_dir() {
-                    let path = entry.path().join("filament").join("base");
+                    let path = entry.path().join(K::FOLDER).join("base");
                     if path.exists() {
-                        debug!("Found user filament dir via directory scan: {:?}", path);
+                        debug!(
+                            "Found user {} dir via directory scan: {:?}",
+                            K::FOLDER,
+                            path
+                        );
                         return Some(path);
                     }
                 }
             }
         }
 
-        warn!("No user filament directory found");
+        warn!("No user {} directory found", K::FOLDER);
         None
     }
 
@@ -187,6 +264,11 @@ impl BambuPaths {
         self.system_filaments.clone()
     }
 
+    /// Get the system preset directory for kind `K` (`system/BBL/{kind}/`).
+    pub fn system_preset_dir<K: PresetKind>(&self) -> PathBuf {
+        self.config_root.join("system").join("BBL").join(K::FOLDER)
+    }
+
     /// Detect the installed Bambu Studio schema/format version.
     ///
     /// Reads `system/BBL.json` (present in the user's BS config root on both
@@ -205,6 +287,95 @@ impl BambuPaths {
     }
 }
 
+/// Locate a Bambu Studio executable on Linux.
+///
+/// `root` is the filesystem root (`/` outside of tests) and `home` the
+/// user's home directory. Checks, in order:
+/// 1. Distro packages and tarball installs (`/usr/bin`, `/usr/local/bin`, `/opt`)
+/// 2. Flatpak exports, system-wide then per-user
+/// 3. `bambu-studio` in `~/.local/bin`
+/// 4. AppImages named `*Bambu*.AppImage` in the usual download/install
+///    folders; when several versions are present the one with the highest
+///    version number in its filename wins.
+pub fn find_linux_executable(root: &Path, home: &Path) -> Option<PathBuf> {
+    let fixed = [
+        root.join("usr/bin/bambu-studio"),
+        root.join("usr/bin/BambuStudio"),
+        root.join("usr/local/bin/bambu-studio"),
+        root.join("usr/local/bin/BambuStudio"),
+        root.join("opt/BambuStudio/BambuStudio"),
+        root.join("opt/bambu-studio/bin/bambu-studio"),
+        root.join("var/lib/flatpak/exports/bin")
+            .join(FLATPAK_APP_ID),
+        home.join(".local/share/flatpak/exports/bin")
+            .join(FLATPAK_APP_ID),
+        home.join(".local/bin/bambu-studio"),
+    ];
+    if let Some(path) = fixed.into_iter().find(|p| p.is_file()) {
+        debug!("Found Bambu Studio executable at {:?}", path);
+        return Some(path);
+    }
+
+    let appimage_dirs = [
+        home.join("Applications"),
+        home.join("Apps"),
+        home.join(".local/bin"),
+        home.join("Downloads"),
+        home.to_path_buf(),
+        root.join("opt"),
+    ];
+    for dir in &appimage_dirs {
+        if let Some(path) = newest_bambu_appimage(dir) {
+            debug!("Found Bambu Studio AppImage at {:?}", path);
+            return Some(path);
+        }
+    }
+
+    None
+}
+
+/// Return the newest Bambu Studio AppImage directly inside `dir`, by the
+/// version number in its filename.
+fn newest_bambu_appimage(dir: &Path) -> Option<PathBuf> {
+    std::fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .filter(|path| {
+            let name = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or("")
+                .to_lowercase();
+            name.contains("bambu") && name.ends_with(".appimage")
+        })
+        .max_by(|a, b| {
+            let version =
+                |p: &Path| appimage_version(&p.file_name().unwrap_or_default().to_string_lossy());
+            version(a).cmp(&version(b)).then_with(|| a.cmp(b))
+        })
+}
+
+/// Release version in an AppImage filename, as numeric components: the
+/// last run of digits and dots, so `..._ubuntu_22.04_v01.10.02.76.AppImage`
+/// gives `[1, 10, 2, 76]`. Compared numerically, so 1.10 sorts above 1.9.
+fn appimage_version(file_name: &str) -> Vec<u64> {
+    let stem = file_name
+        .strip_suffix(".AppImage")
+        .or_else(|| file_name.strip_suffix(".appimage"))
+        .unwrap_or(file_name);
+    stem.rsplit(|c: char| !c.is_ascii_digit() && c != '.')
+        .map(|run| run.trim_matches('.'))
+        .find(|run| !run.is_empty())
+        .map(|run| {
+            run.split('.')
+                .filter_map(|part| part.parse().ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
 /// Strip leading zeros from each dotted component of a version string.
 ///
 /// `"02.07.00.07"` → `"2.7.0.7"`. Empty components collapse to `"0"` so
@@ -226,7 +397,7 @@ fn normalize_version(raw: &str) -> String {
 
 #[cfg(test)]
 mod tests {
-    use super::normalize_version;
+    use super::{appimage_version, normalize_version};
 
     #[test]
     fn strips_leading_zeros_from_each_component() {
@@ -247,4 +418,13 @@ mod tests {
     fn preserves_non_numeric_components() {
         assert_eq!(normalize_version("2.7.beta.01"), "2.7.beta.1");
     }
+
+    #[test]
+    fn appimage_version_is_the_last_number_in_the_name() {
+        assert_eq!(
+            appimage_version("Bambu_Studio_linux_ubuntu_22.04_v01.10.02.76.AppImage"),
+            vec![1, 10, 2, 76]
+        );
+        assert_eq!(appimage_version("BambuStudio.AppImage"), Vec::<u64>::new());
+    }
 }
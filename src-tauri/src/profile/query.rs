@@ -0,0 +1,532 @@
+//! A small query language over profile fields.
+//!
+//! ```text
+//! filament_type = PETG and nozzle_temperature[0] > 250
+//!     and compatible_printers contains "X1 Carbon"
+//! ```
+//!
+//! A query is comparisons joined with `and`, `or` and `not`, grouped with
+//! parentheses. A comparison is a field, an operator and a value:
+//!
+//! - `=` and `!=` compare numerically when both sides are numbers (so
+//!   `0.40 = 0.4`), otherwise as case-insensitive strings.
+//! - `<`, `<=`, `>` and `>=` compare numerically; non-numeric values never
+//!   match.
+//! - `contains` is a case-insensitive substring match.
+//! - `field exists` matches when the field is set to something other than
+//!   `nil`.
+//!
+//! Most Bambu Studio fields are arrays of strings, one element per extruder
+//! or per plate. `field[i]` compares element `i`; a bare field matches when
+//! any element does (and `!=` when none is equal). Values can be bare words
+//! (`PETG`, `0.4`) or double-quoted strings (`"Bambu Lab X1 Carbon"`).
+
+use anyhow::{anyhow, bail, Result};
+use serde_json::{Map, Value};
+
+use super::inheritance::is_nil_value;
+
+/// A parsed query.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProfileQuery {
+    expr: Expr,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Compare(Field, Op, String),
+    Exists(Field),
+}
+
+/// A field reference, optionally indexing into an array.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Field {
+    key: String,
+    index: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+}
+
+impl ProfileQuery {
+    /// Parse `input`. Errors name the column the problem was found at.
+    pub fn parse(input: &str) -> Result<Self> {
+        let tokens = tokenize(input)?;
+        if tokens.is_empty() {
+            bail!("Query is empty");
+        }
+        let mut parser = Parser {
+            tokens: &tokens,
+            pos: 0,
+            len: input.chars().count(),
+        };
+        let expr = parser.or()?;
+        if let Some((token, col)) = parser.peek_with_col() {
+            bail!("Unexpected {} at column {}", token.describe(), col);
+        }
+        Ok(Self { expr })
+    }
+
+    /// Whether `profile` (ideally resolved, so inherited values count)
+    /// matches.
+    pub fn matches(&self, profile: &Map<String, Value>) -> bool {
+        self.expr.eval(profile)
+    }
+
+    /// The fields the query mentions, in order of first mention, as written
+    /// (e.g. `nozzle_temperature[0]`), with the profile's value for each.
+    pub fn field_values(&self, profile: &Map<String, Value>) -> Vec<(String, String)> {
+        let mut fields = Vec::new();
+        self.expr.collect_fields(&mut fields);
+        let mut seen = std::collections::HashSet::new();
+        fields
+            .into_iter()
+            .filter(|f| seen.insert((*f).clone()))
+            .map(|f| {
+                let label = match f.index {
+                    Some(i) => format!("{}[{}]", f.key, i),
+                    None => f.key.clone(),
+                };
+                let value = f.values(profile);
+                let display = if value.is_empty() {
+                    "--".to_string()
+                } else {
+                    value.join(", ")
+                };
+                (label, display)
+            })
+            .collect()
+    }
+}
+
+impl std::str::FromStr for ProfileQuery {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse(s)
+    }
+}
+
+impl Expr {
+    fn eval(&self, profile: &Map<String, Value>) -> bool {
+        match self {
+            Expr::And(a, b) => a.eval(profile) && b.eval(profile),
+            Expr::Or(a, b) => a.eval(profile) || b.eval(profile),
+            Expr::Not(e) => !e.eval(profile),
+            Expr::Exists(field) => !field.values(profile).is_empty(),
+            Expr::Compare(field, op, literal) => {
+                let values = field.values(profile);
+                match op {
+                    Op::Ne => !values.is_empty() && !values.iter().any(|v| equals(v, literal)),
+                    _ => values.iter().any(|v| compare(v, *op, literal)),
+                }
+            }
+        }
+    }
+
+    fn collect_fields<'a>(&'a self, out: &mut Vec<&'a Field>) {
+        match self {
+            Expr::And(a, b) | Expr::Or(a, b) => {
+                a.collect_fields(out);
+                b.collect_fields(out);
+            }
+            Expr::Not(e) => e.collect_fields(out),
+            Expr::Compare(field, _, _) | Expr::Exists(field) => out.push(field),
+        }
+    }
+}
+
+impl Field {
+    /// The values this field refers to, as strings, skipping `nil`.
+    fn values(&self, profile: &Map<String, Value>) -> Vec<String> {
+        let elements: Vec<&Value> = match profile.get(&self.key) {
+            None => return Vec::new(),
+            Some(Value::Array(items)) => match self.index {
+                Some(i) => items.get(i).into_iter().collect(),
+                None => items.iter().collect(),
+            },
+            // A scalar is its own only element
+            Some(value) => match self.index {
+                Some(0) | None => vec![value],
+                Some(_) => Vec::new(),
+            },
+        };
+        elements
+            .into_iter()
+            .filter(|v| !v.is_null() && !is_nil_value(v))
+            .map(|v| match v {
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            })
+            .collect()
+    }
+}
+
+fn equals(value: &str, literal: &str) -> bool {
+    match (value.trim().parse::<f64>(), literal.parse::<f64>()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => value.eq_ignore_ascii_case(literal),
+    }
+}
+
+fn compare(value: &str, op: Op, literal: &str) -> bool {
+    if op == Op::Eq {
+        return equals(value, literal);
+    }
+    if op == Op::Contains {
+        return value.to_lowercase().contains(&literal.to_lowercase());
+    }
+    let (Ok(a), Ok(b)) = (value.trim().parse::<f64>(), literal.parse::<f64>()) else {
+        return false;
+    };
+    match op {
+        Op::Lt => a < b,
+        Op::Le => a <= b,
+        Op::Gt => a > b,
+        Op::Ge => a >= b,
+        Op::Eq | Op::Ne | Op::Contains => unreachable!("handled above"),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// A bare word: field name, keyword or unquoted value.
+    Word(String),
+    /// A double-quoted string.
+    Str(String),
+    Op(Op),
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Word(w) => format!("'{}'", w),
+            Token::Str(s) => format!("\"{}\"", s),
+            Token::Op(op) => format!("operator {:?}", op),
+            Token::LParen => "'('".to_string(),
+            Token::RParen => "')'".to_string(),
+            Token::LBracket => "'['".to_string(),
+            Token::RBracket => "']'".to_string(),
+        }
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self, Token::Word(w) if w.eq_ignore_ascii_case(keyword))
+    }
+}
+
+/// Tokens with their 1-based column.
+fn tokenize(input: &str) -> Result<Vec<(Token, usize)>> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let col = i + 1;
+        match c {
+            c if c.is_whitespace() => i += 1,
+            '(' | ')' | '[' | ']' => {
+                tokens.push((
+                    match c {
+                        '(' => Token::LParen,
+                        ')' => Token::RParen,
+                        '[' => Token::LBracket,
+                        _ => Token::RBracket,
+                    },
+                    col,
+                ));
+                i += 1;
+            }
+            '=' => {
+                // Accept `==` as well as `=`
+                i += if chars.get(i + 1) == Some(&'=') { 2 } else { 1 };
+                tokens.push((Token::Op(Op::Eq), col));
+            }
+            '!' | '<' | '>' => {
+                let followed_by_eq = chars.get(i + 1) == Some(&'=');
+                let op = match (c, followed_by_eq) {
+                    ('!', true) => Op::Ne,
+                    ('<', true) => Op::Le,
+                    ('<', false) => Op::Lt,
+                    ('>', true) => Op::Ge,
+                    ('>', false) => Op::Gt,
+                    _ => bail!("Expected '!=' at column {}", col),
+                };
+                i += if followed_by_eq { 2 } else { 1 };
+                tokens.push((Token::Op(op), col));
+            }
+            '"' => {
+                let mut s = String::new();
+                i += 1;
+                loop {
+                    match chars.get(i) {
+                        None => bail!("Unterminated string starting at column {}", col),
+                        Some('"') => break,
+                        Some('\\') if chars.get(i + 1).is_some() => {
+                            s.push(chars[i + 1]);
+                            i += 2;
+                        }
+                        Some(&ch) => {
+                            s.push(ch);
+                            i += 1;
+                        }
+                    }
+                }
+                i += 1;
+                tokens.push((Token::Str(s), col));
+            }
+            c if is_word_char(c) => {
+                let start = i;
+                while i < chars.len() && is_word_char(chars[i]) {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+                let token = if word.eq_ignore_ascii_case("contains") {
+                    Token::Op(Op::Contains)
+                } else {
+                    Token::Word(word)
+                };
+                tokens.push((token, col));
+            }
+            other => bail!("Unexpected '{}' at column {}", other, col),
+        }
+    }
+    Ok(tokens)
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '.' | '-' | '+' | '%')
+}
+
+struct Parser<'a> {
+    tokens: &'a [(Token, usize)],
+    pos: usize,
+    /// Input length, for errors at the end of the query.
+    len: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(t, _)| t)
+    }
+
+    fn peek_with_col(&self) -> Option<(&Token, usize)> {
+        self.tokens.get(self.pos).map(|(t, c)| (t, *c))
+    }
+
+    fn next(&mut self) -> Option<(Token, usize)> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    /// The next token, or an error saying `expected` was wanted.
+    fn expect_any(&mut self, expected: &str) -> Result<(Token, usize)> {
+        let end = self.len + 1;
+        self.next()
+            .ok_or_else(|| anyhow!("Expected {} at column {}", expected, end))
+    }
+
+    fn or(&mut self) -> Result<Expr> {
+        let mut left = self.and()?;
+        while self.peek().is_some_and(|t| t.is_keyword("or")) {
+            self.pos += 1;
+            left = Expr::Or(Box::new(left), Box::new(self.and()?));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Expr> {
+        let mut left = self.not()?;
+        while self.peek().is_some_and(|t| t.is_keyword("and")) {
+            self.pos += 1;
+            left = Expr::And(Box::new(left), Box::new(self.not()?));
+        }
+        Ok(left)
+    }
+
+    fn not(&mut self) -> Result<Expr> {
+        if self.peek().is_some_and(|t| t.is_keyword("not")) {
+            self.pos += 1;
+            return Ok(Expr::Not(Box::new(self.not()?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr> {
+        match self.expect_any("a field name or '('")? {
+            (Token::LParen, _) => {
+                let expr = self.or()?;
+                match self.expect_any("')'")? {
+                    (Token::RParen, _) => Ok(expr),
+                    (other, col) => {
+                        bail!("Expected ')' at column {}, found {}", col, other.describe())
+                    }
+                }
+            }
+            (Token::Word(key), _) => {
+                let field = self.field(key)?;
+                self.comparison(field)
+            }
+            (other, col) => bail!(
+                "Expected a field name at column {}, found {}",
+                col,
+                other.describe()
+            ),
+        }
+    }
+
+    fn field(&mut self, key: String) -> Result<Field> {
+        if self.peek() != Some(&Token::LBracket) {
+            return Ok(Field { key, index: None });
+        }
+        self.pos += 1;
+        let index = match self.expect_any("an index")? {
+            (Token::Word(w), col) => w
+                .parse::<usize>()
+                .map_err(|_| anyhow!("Expected an index at column {}, found '{}'", col, w))?,
+            (other, col) => bail!(
+                "Expected an index at column {}, found {}",
+                col,
+                other.describe()
+            ),
+        };
+        match self.expect_any("']'")? {
+            (Token::RBracket, _) => Ok(Field {
+                key,
+                index: Some(index),
+            }),
+            (other, col) => bail!("Expected ']' at column {}, found {}", col, other.describe()),
+        }
+    }
+
+    fn comparison(&mut self, field: Field) -> Result<Expr> {
+        match self.expect_any("an operator")? {
+            (Token::Word(w), _) if w.eq_ignore_ascii_case("exists") => Ok(Expr::Exists(field)),
+            (Token::Op(op), _) => match self.expect_any("a value")? {
+                (Token::Word(value) | Token::Str(value), _) => Ok(Expr::Compare(field, op, value)),
+                (other, col) => bail!(
+                    "Expected a value at column {}, found {}",
+                    col,
+                    other.describe()
+                ),
+            },
+            (other, col) => bail!(
+                "Expected an operator after '{}' at column {}, found {}",
+                field.key,
+                col,
+                other.describe()
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn profile(value: Value) -> Map<String, Value> {
+        value.as_object().unwrap().clone()
+    }
+
+    fn petg() -> Map<String, Value> {
+        profile(json!({
+            "name": "Generic PETG HF @BBL X1C",
+            "filament_type": ["PETG"],
+            "nozzle_temperature": ["255", "260"],
+            "filament_flow_ratio": ["0.95"],
+            "compatible_printers": ["Bambu Lab X1 Carbon 0.4 nozzle", "Bambu Lab P1S 0.4 nozzle"],
+            "filament_start_gcode": ["nil"],
+        }))
+    }
+
+    fn matches(query: &str, profile: &Map<String, Value>) -> bool {
+        ProfileQuery::parse(query).unwrap().matches(profile)
+    }
+
+    #[test]
+    fn test_numeric_and_string_comparisons() {
+        let p = petg();
+        assert!(matches(
+            r#"filament_type = PETG and nozzle_temperature[0] > 250 and compatible_printers contains "X1 Carbon""#,
+            &p
+        ));
+        assert!(matches("filament_type = petg", &p));
+        assert!(matches("filament_flow_ratio = 0.950", &p));
+        assert!(!matches("nozzle_temperature[0] >= 260", &p));
+        // A bare field matches if any element does
+        assert!(matches("nozzle_temperature >= 260", &p));
+        assert!(!matches("nozzle_temperature[2] > 0", &p));
+        // Ordering is numeric only
+        assert!(!matches("filament_type > A", &p));
+    }
+
+    #[test]
+    fn test_boolean_operators_and_grouping() {
+        let p = petg();
+        assert!(matches("filament_type = PLA or filament_type = PETG", &p));
+        assert!(!matches(
+            "(filament_type = PLA or filament_type = PETG) and not nozzle_temperature[1] = 260",
+            &p
+        ));
+        // `and` binds tighter than `or`
+        assert!(matches(
+            "filament_type = PETG or filament_type = PLA and nozzle_temperature > 999",
+            &p
+        ));
+    }
+
+    #[test]
+    fn test_missing_and_nil_fields() {
+        let p = petg();
+        assert!(matches("name exists", &p));
+        assert!(!matches("filament_start_gcode exists", &p));
+        assert!(!matches("hot_plate_temp != 60", &p));
+        assert!(matches("not hot_plate_temp = 60", &p));
+        assert!(matches("filament_type != PLA", &p));
+    }
+
+    #[test]
+    fn test_parse_errors_name_the_column() {
+        let err = ProfileQuery::parse("filament_type PETG")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("column 15"), "{}", err);
+        let err = ProfileQuery::parse("nozzle_temperature[x] > 1")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("column 20"), "{}", err);
+        assert!(ProfileQuery::parse("(a = 1").is_err());
+        assert!(ProfileQuery::parse(r#"a = "open"#).is_err());
+        assert!(ProfileQuery::parse("").is_err());
+    }
+
+    #[test]
+    fn test_field_values_for_display() {
+        let query =
+            ProfileQuery::parse("nozzle_temperature[1] > 250 and hot_plate_temp exists").unwrap();
+        assert_eq!(
+            query.field_values(&petg()),
+            vec![
+                ("nozzle_temperature[1]".to_string(), "260".to_string()),
+                ("hot_plate_temp".to_string(), "--".to_string()),
+            ]
+        );
+    }
+}
@@ -2,12 +2,17 @@ use anyhow::Result;
 use std::path::Path;
 use tracing::debug;
 
-use super::types::{FilamentProfile, ProfileMetadata};
+use super::types::{FilamentProfile, Preset, PresetKind, ProfileMetadata};
 
 /// Read a filament profile from a JSON file on disk.
 pub fn read_profile(path: &Path) -> Result<FilamentProfile> {
+    read_preset(path)
+}
+
+/// Read a preset of any kind from a JSON file on disk.
+pub fn read_preset<K: PresetKind>(path: &Path) -> Result<Preset<K>> {
     let content = std::fs::read_to_string(path)?;
-    let profile = FilamentProfile::from_json(&content)?;
+    let profile = Preset::<K>::from_json(&content)?;
 
     debug!(
         "Read profile {:?} with {} fields from {:?}",
@@ -0,0 +1,377 @@
+//! Re-basing generated profiles onto updated system presets.
+//!
+//! Generated profiles are flattened, so they keep the base values they were
+//! generated from even after Bambu Studio ships new system presets. Each
+//! generation is recorded in a [`GenerationStore`] together with its specs
+//! and a fingerprint of the resolved base; when the base's fingerprint
+//! changes, [`rebase_profile`] regenerates the profile from the same specs
+//! on top of the new base for the user to accept or reject.
+
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use chrono::{Duration, Utc};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use super::generator::generate_profile;
+use super::inheritance::{is_fully_flattened, resolve_inheritance};
+use super::registry::{MachineRegistry, ProfileRegistry};
+use super::types::FilamentProfile;
+use crate::scraper::types::FilamentSpecs;
+
+/// Records whose profile was never installed are dropped after this many days.
+const UNINSTALLED_RECORD_TTL_DAYS: i64 = 30;
+
+/// How a profile was generated, keyed by the `setting_id` in its `.info` file.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GenerationRecord {
+    pub setting_id: String,
+    pub profile_name: String,
+    pub specs: FilamentSpecs,
+    pub target_printer: Option<String>,
+    /// Name of the base profile the specs were applied on top of.
+    pub base_name: String,
+    /// [`base_fingerprint`] of the resolved base at generation time.
+    pub base_fingerprint: String,
+    /// Fingerprint of a base the user declined to rebase onto, so the same
+    /// update isn't offered again.
+    pub rejected_fingerprint: Option<String>,
+    /// RFC 3339 timestamp.
+    pub generated_at: String,
+}
+
+impl GenerationRecord {
+    /// A record for a profile generated just now.
+    pub fn new(
+        setting_id: String,
+        profile_name: String,
+        specs: FilamentSpecs,
+        target_printer: Option<String>,
+        base_name: String,
+        base_fingerprint: String,
+    ) -> Self {
+        Self {
+            setting_id,
+            profile_name,
+            specs,
+            target_printer,
+            base_name,
+            base_fingerprint,
+            rejected_fingerprint: None,
+            generated_at: Utc::now().to_rfc3339(),
+        }
+    }
+}
+
+/// Fingerprint of a resolved base profile's values.
+///
+/// 64-bit FNV-1a over the fields in key order, so it's independent of field
+/// order in the JSON and stable across releases (unlike `DefaultHasher`).
+pub fn base_fingerprint(resolved_base: &FilamentProfile) -> String {
+    let sorted: BTreeMap<&String, &serde_json::Value> = resolved_base.raw().iter().collect();
+    let canonical = serde_json::to_string(&sorted).unwrap_or_default();
+    let hash = canonical
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+        });
+    format!("{:016x}", hash)
+}
+
+/// [`base_fingerprint`] of `base_name` as currently installed.
+pub fn current_base_fingerprint(registry: &ProfileRegistry, base_name: &str) -> Result<String> {
+    let base = registry
+        .get_by_name(base_name)
+        .ok_or_else(|| anyhow!("Base profile '{}' is no longer installed", base_name))?;
+    Ok(base_fingerprint(&resolve_inheritance(base, registry)?))
+}
+
+/// A profile regenerated on top of an updated base.
+pub struct Rebased {
+    pub profile: FilamentProfile,
+    pub base_fingerprint: String,
+}
+
+/// Regenerate `installed` from its generation `record` if the base has
+/// changed since.
+///
+/// Returns None when the base's fingerprint still matches (or matches one
+/// the user rejected), and for profiles that inherit rather than being
+/// flattened, which pick up base changes by themselves. The regenerated
+/// profile keeps the installed profile's name and `filament_id`; anything
+/// edited by hand after generation is replaced, which the caller's diff
+/// shows.
+pub fn rebase_profile(
+    record: &GenerationRecord,
+    installed: &FilamentProfile,
+    registry: &ProfileRegistry,
+    machines: &MachineRegistry,
+) -> Result<Option<Rebased>> {
+    if !is_fully_flattened(installed) {
+        return Ok(None);
+    }
+    let fingerprint = current_base_fingerprint(registry, &record.base_name)?;
+    if fingerprint == record.base_fingerprint
+        || record.rejected_fingerprint.as_deref() == Some(fingerprint.as_str())
+    {
+        return Ok(None);
+    }
+
+    let (mut profile, _, _) = generate_profile(
+        &record.specs,
+        registry,
+        machines,
+        record.target_printer.as_deref(),
+        Some(&record.base_name),
+        installed.filament_id().map(str::to_string),
+    )?;
+    // Keep the identity the user sees, even if they renamed the profile
+    for key in ["name", "filament_settings_id"] {
+        if let Some(value) = installed.raw().get(key) {
+            profile.raw_mut().insert(key.to_string(), value.clone());
+        }
+    }
+
+    Ok(Some(Rebased {
+        profile,
+        base_fingerprint: fingerprint,
+    }))
+}
+
+/// SQLite store of [`GenerationRecord`]s.
+/// All operations are synchronous (rusqlite is blocking).
+/// Callers in async contexts should use `tokio::task::spawn_blocking`.
+pub struct GenerationStore {
+    conn: Connection,
+}
+
+impl GenerationStore {
+    /// Open or create the store at `db_path`.
+    /// Typically called with: app.path().app_data_dir()?.join("generated_profiles.db")
+    pub fn new(db_path: &Path) -> Result<Self, String> {
+        if let Some(parent) = db_path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create data dir: {}", e))?;
+        }
+        let conn = Connection::open(db_path)
+            .map_err(|e| format!("Failed to open generation store at {:?}: {}", db_path, e))?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS generated_profiles (
+                setting_id TEXT PRIMARY KEY,
+                profile_name TEXT NOT NULL,
+                specs_json TEXT NOT NULL,
+                target_printer TEXT,
+                base_name TEXT NOT NULL,
+                base_fingerprint TEXT NOT NULL,
+                rejected_fingerprint TEXT,
+                generated_at TEXT NOT NULL
+            )",
+            [],
+        )
+        .map_err(|e| format!("Failed to create generation table: {}", e))?;
+
+        Ok(Self { conn })
+    }
+
+    /// Store `record`, replacing any record with the same setting_id.
+    pub fn record(&self, record: &GenerationRecord) -> Result<(), String> {
+        let specs_json = serde_json::to_string(&record.specs)
+            .map_err(|e| format!("Failed to serialize specs: {}", e))?;
+        self.conn
+            .execute(
+                "INSERT OR REPLACE INTO generated_profiles
+                 (setting_id, profile_name, specs_json, target_printer, base_name,
+                  base_fingerprint, rejected_fingerprint, generated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![
+                    record.setting_id,
+                    record.profile_name,
+                    specs_json,
+                    record.target_printer,
+                    record.base_name,
+                    record.base_fingerprint,
+                    record.rejected_fingerprint,
+                    record.generated_at,
+                ],
+            )
+            .map_err(|e| format!("Failed to store generation record: {}", e))?;
+        Ok(())
+    }
+
+    /// All records, oldest first.
+    pub fn list(&self) -> Result<Vec<GenerationRecord>, String> {
+        self.query("ORDER BY generated_at", [])
+    }
+
+    /// Look up the record for `setting_id`.
+    pub fn get(&self, setting_id: &str) -> Result<Option<GenerationRecord>, String> {
+        Ok(self
+            .query("WHERE setting_id = ?1", params![setting_id])?
+            .pop())
+    }
+
+    fn query(
+        &self,
+        clause: &str,
+        query_params: impl rusqlite::Params,
+    ) -> Result<Vec<GenerationRecord>, String> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT setting_id, profile_name, specs_json, target_printer, base_name,
+                        base_fingerprint, rejected_fingerprint, generated_at
+                 FROM generated_profiles {}",
+                clause
+            ))
+            .map_err(|e| format!("Failed to prepare query: {}", e))?;
+        let rows = stmt
+            .query_map(query_params, |row| {
+                Ok((
+                    GenerationRecord {
+                        setting_id: row.get(0)?,
+                        profile_name: row.get(1)?,
+                        specs: FilamentSpecs::default(),
+                        target_printer: row.get(3)?,
+                        base_name: row.get(4)?,
+                        base_fingerprint: row.get(5)?,
+                        rejected_fingerprint: row.get(6)?,
+                        generated_at: row.get(7)?,
+                    },
+                    row.get::<_, String>(2)?,
+                ))
+            })
+            .map_err(|e| format!("Failed to query generation records: {}", e))?;
+
+        let mut records = Vec::new();
+        for row in rows {
+            let (mut record, specs_json) = row.map_err(|e| format!("Failed to read row: {}", e))?;
+            record.specs = serde_json::from_str(&specs_json)
+                .map_err(|e| format!("Failed to deserialize specs: {}", e))?;
+            records.push(record);
+        }
+        Ok(records)
+    }
+
+    /// The profile was rebased onto the base with `fingerprint`.
+    pub fn mark_rebased(&self, setting_id: &str, fingerprint: &str) -> Result<(), String> {
+        self.conn
+            .execute(
+                "UPDATE generated_profiles
+                 SET base_fingerprint = ?2, rejected_fingerprint = NULL
+                 WHERE setting_id = ?1",
+                params![setting_id, fingerprint],
+            )
+            .map_err(|e| format!("Failed to update generation record: {}", e))?;
+        Ok(())
+    }
+
+    /// The user declined to rebase onto the base with `fingerprint`.
+    pub fn mark_rejected(&self, setting_id: &str, fingerprint: &str) -> Result<(), String> {
+        self.conn
+            .execute(
+                "UPDATE generated_profiles SET rejected_fingerprint = ?2 WHERE setting_id = ?1",
+                params![setting_id, fingerprint],
+            )
+            .map_err(|e| format!("Failed to update generation record: {}", e))?;
+        Ok(())
+    }
+
+    /// Delete records for profiles that aren't installed (previews that
+    /// were never installed, or deleted profiles) once they're older than
+    /// a month. Returns the number of deleted records.
+    pub fn prune(&self, installed_setting_ids: &HashSet<String>) -> Result<usize, String> {
+        let cutoff = (Utc::now() - Duration::days(UNINSTALLED_RECORD_TTL_DAYS)).to_rfc3339();
+        let stale: Vec<String> = self
+            .list()?
+            .into_iter()
+            .filter(|r| r.generated_at < cutoff && !installed_setting_ids.contains(&r.setting_id))
+            .map(|r| r.setting_id)
+            .collect();
+        for setting_id in &stale {
+            self.conn
+                .execute(
+                    "DELETE FROM generated_profiles WHERE setting_id = ?1",
+                    params![setting_id],
+                )
+                .map_err(|e| format!("Failed to delete generation record: {}", e))?;
+        }
+        if !stale.is_empty() {
+            info!("Pruned {} stale generation records", stale.len());
+        }
+        Ok(stale.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn record(setting_id: &str, generated_at: &str) -> GenerationRecord {
+        GenerationRecord {
+            setting_id: setting_id.to_string(),
+            profile_name: "Polymaker PLA Matte @Bambu Lab X1 Carbon 0.4 nozzle".to_string(),
+            specs: FilamentSpecs {
+                brand: "Polymaker".to_string(),
+                material: "PLA".to_string(),
+                nozzle_temperature: Some(215),
+                ..FilamentSpecs::default()
+            },
+            target_printer: Some("Bambu Lab X1 Carbon 0.4 nozzle".to_string()),
+            base_name: "fdm_filament_pla".to_string(),
+            base_fingerprint: "0123456789abcdef".to_string(),
+            rejected_fingerprint: None,
+            generated_at: generated_at.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_fingerprint_ignores_field_order() {
+        let a = FilamentProfile::from_json(r#"{"a": ["1"], "b": ["2"]}"#).unwrap();
+        let b = FilamentProfile::from_json(r#"{"b": ["2"], "a": ["1"]}"#).unwrap();
+        let c = FilamentProfile::from_json(r#"{"a": ["1"], "b": ["3"]}"#).unwrap();
+        assert_eq!(base_fingerprint(&a), base_fingerprint(&b));
+        assert_ne!(base_fingerprint(&a), base_fingerprint(&c));
+        assert_eq!(base_fingerprint(&a).len(), 16);
+    }
+
+    #[test]
+    fn test_store_round_trip_and_prune() {
+        let dir = TempDir::new().unwrap();
+        let store = GenerationStore::new(&dir.path().join("generated.db")).unwrap();
+        let now = Utc::now().to_rfc3339();
+        store
+            .record(&record("PFUSold", "2020-01-01T00:00:00+00:00"))
+            .unwrap();
+        store
+            .record(&record("PFUSinstalled", "2020-01-01T00:00:00+00:00"))
+            .unwrap();
+        store.record(&record("PFUSnew", &now)).unwrap();
+        assert_eq!(store.get("PFUSnew").unwrap(), Some(record("PFUSnew", &now)));
+
+        store.mark_rejected("PFUSnew", "fedcba9876543210").unwrap();
+        let rejected = store.get("PFUSnew").unwrap().unwrap();
+        assert_eq!(
+            rejected.rejected_fingerprint.as_deref(),
+            Some("fedcba9876543210")
+        );
+        store.mark_rebased("PFUSnew", "fedcba9876543210").unwrap();
+        let rebased = store.get("PFUSnew").unwrap().unwrap();
+        assert_eq!(rebased.base_fingerprint, "fedcba9876543210");
+        assert_eq!(rebased.rejected_fingerprint, None);
+
+        let installed = HashSet::from(["PFUSinstalled".to_string()]);
+        assert_eq!(store.prune(&installed).unwrap(), 1);
+        let left: Vec<String> = store
+            .list()
+            .unwrap()
+            .into_iter()
+            .map(|r| r.setting_id)
+            .collect();
+        assert_eq!(left, ["PFUSinstalled", "PFUSnew"]);
+    }
+}
@@ -1,30 +1,48 @@
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use anyhow::Result;
 use tracing::{debug, info, warn};
 use walkdir::WalkDir;
 
 use super::reader;
-use super::types::FilamentProfile;
+use super::types::{Filament, FilamentProfile, Machine, Preset, PresetKind, Process};
 
-/// Registry of discovered filament profiles, keyed by profile name.
+/// Registry of discovered presets of one kind, keyed by preset name.
 ///
 /// Provides discovery (via walkdir) and name-based lookup for both
-/// system and user filament profiles.
-pub struct ProfileRegistry {
-    profiles: HashMap<String, FilamentProfile>,
+/// system and user presets.
+pub struct PresetRegistry<K: PresetKind> {
+    profiles: HashMap<String, Preset<K>>,
+    sources: HashMap<String, PresetSource>,
 }
 
-impl ProfileRegistry {
+/// Where a discovered preset was read from.
+#[derive(Debug, Clone)]
+pub struct PresetSource {
+    pub path: PathBuf,
+    pub is_user: bool,
+}
+
+/// Registry of filament profiles.
+pub type ProfileRegistry = PresetRegistry<Filament>;
+
+/// Registry of process (print settings) presets.
+pub type ProcessRegistry = PresetRegistry<Process>;
+
+/// Registry of machine (printer settings) presets.
+pub type MachineRegistry = PresetRegistry<Machine>;
+
+impl<K: PresetKind> PresetRegistry<K> {
     /// Create an empty registry.
     pub fn new() -> Self {
         Self {
             profiles: HashMap::new(),
+            sources: HashMap::new(),
         }
     }
 
-    /// Discover and index all system filament profiles in the given directory.
+    /// Discover and index all system presets in the given directory.
     ///
     /// Recursively walks the directory tree, reads each `.json` file,
     /// and indexes profiles by their `name` field. Files that fail to
@@ -44,11 +62,18 @@ impl ProfileRegistry {
                 continue;
             }
 
-            match reader::read_profile(path) {
+            match reader::read_preset::<K>(path) {
                 Ok(profile) => {
                     if let Some(name) = profile.name() {
                         let name = name.to_string();
                         debug!("Indexed system profile: {}", name);
+                        registry.sources.insert(
+                            name.clone(),
+                            PresetSource {
+                                path: path.to_path_buf(),
+                                is_user: false,
+                            },
+                        );
                         registry.profiles.insert(name, profile);
                         count += 1;
                     } else {
@@ -65,14 +90,17 @@ impl ProfileRegistry {
         }
 
         info!(
-            "Discovered {} system profiles ({} files skipped) from {:?}",
-            count, skipped, system_dir
+            "Discovered {} system {} profiles ({} files skipped) from {:?}",
+            count,
+            K::FOLDER,
+            skipped,
+            system_dir
         );
 
         Ok(registry)
     }
 
-    /// Discover and add user filament profiles from the given directory.
+    /// Discover and add user presets from the given directory.
     ///
     /// Similar to system profile discovery but adds to the existing registry.
     /// User profiles are typically in the `base/` subdirectory.
@@ -89,11 +117,18 @@ impl ProfileRegistry {
                 continue;
             }
 
-            match reader::read_profile(path) {
+            match reader::read_preset::<K>(path) {
                 Ok(profile) => {
                     if let Some(name) = profile.name() {
                         let name = name.to_string();
                         debug!("Indexed user profile: {}", name);
+                        self.sources.insert(
+                            name.clone(),
+                            PresetSource {
+                                path: path.to_path_buf(),
+                                is_user: true,
+                            },
+                        );
                         self.profiles.insert(name, profile);
                         count += 1;
                     } else {
@@ -109,28 +144,43 @@ impl ProfileRegistry {
         }
 
         info!(
-            "Discovered {} user profiles ({} files skipped) from {:?}",
-            count, skipped, user_dir
+            "Discovered {} user {} profiles ({} files skipped) from {:?}",
+            count,
+            K::FOLDER,
+            skipped,
+            user_dir
         );
 
         Ok(())
     }
 
     /// Look up a profile by its name.
-    pub fn get_by_name(&self, name: &str) -> Option<&FilamentProfile> {
+    pub fn get_by_name(&self, name: &str) -> Option<&Preset<K>> {
         self.profiles.get(name)
     }
 
     /// Insert a profile into the registry using its name as key.
     ///
     /// The profile must have a `name` field; if not, this is a no-op.
-    pub fn insert(&mut self, profile: FilamentProfile) {
+    pub fn insert(&mut self, profile: Preset<K>) {
         if let Some(name) = profile.name() {
             let name = name.to_string();
+            self.sources.remove(&name);
             self.profiles.insert(name, profile);
         }
     }
 
+    /// File a discovered profile was read from. None for profiles added
+    /// with [`Self::insert`].
+    pub fn source(&self, name: &str) -> Option<&PresetSource> {
+        self.sources.get(name)
+    }
+
+    /// Iterate over all profiles, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = &Preset<K>> {
+        self.profiles.values()
+    }
+
     /// Return all profile names in the registry.
     pub fn names(&self) -> Vec<&str> {
         self.profiles.keys().map(|s| s.as_str()).collect()
@@ -146,3 +196,40 @@ impl ProfileRegistry {
         self.profiles.is_empty()
     }
 }
+
+impl PresetRegistry<Filament> {
+    /// The `filament_id` a profile is identified by, following `inherits`
+    /// when the profile doesn't set one itself.
+    ///
+    /// System printer variants (`Bambu PLA Basic @BBL X1C`) carry no
+    /// `filament_id` of their own; it lives on the `@base` preset they
+    /// inherit from.
+    pub fn effective_filament_id<'a>(&'a self, profile: &'a FilamentProfile) -> Option<&'a str> {
+        let mut current = profile;
+        // Bounded walk; inheritance chains are a handful of levels deep
+        for _ in 0..16 {
+            if let Some(id) = current.filament_id().filter(|id| !id.is_empty()) {
+                return Some(id);
+            }
+            current = self.get_by_name(current.inherits()?)?;
+        }
+        None
+    }
+
+    /// Selectable profiles identified by `filament_id`, user profiles
+    /// first, then by name. Templates and `@base` presets are left out.
+    pub fn find_by_filament_id(&self, filament_id: &str) -> Vec<&FilamentProfile> {
+        let mut matches: Vec<&FilamentProfile> = self
+            .profiles
+            .values()
+            .filter(|p| p.is_instantiable())
+            .filter(|p| self.effective_filament_id(p) == Some(filament_id))
+            .collect();
+        matches.sort_by_key(|p| {
+            let name = p.name().unwrap_or_default();
+            let is_user = self.source(name).is_some_and(|s| s.is_user);
+            (!is_user, name.to_string())
+        });
+        matches
+    }
+}
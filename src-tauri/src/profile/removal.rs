@@ -0,0 +1,486 @@
+//! Deleting and renaming user filament profiles without leaving anything
+//! dangling.
+//!
+//! Bambu Studio lists a user preset in BambuStudio.conf's `filaments` array
+//! by its file stem, and other user presets can name it in `inherits`.
+//! Deleting or renaming a profile keeps both consistent: the conf
+//! registration follows the profile, and the user profiles that inherit
+//! from it are re-pointed, re-parented, flattened or deleted with it (see
+//! [`DependentPolicy`]).
+//!
+//! Planning is separate from applying so the whole operation can be
+//! previewed first. [`apply_removal`] writes everything in one
+//! [`InstallTransaction`].
+
+use std::collections::{BTreeSet, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use super::inheritance::{minimize, resolve_inheritance};
+use super::reader::read_profile_metadata;
+use super::registry::ProfileRegistry;
+use super::types::FilamentProfile;
+use super::writer::InstallTransaction;
+
+/// What happens to the user profiles that inherit from a deleted profile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DependentPolicy {
+    /// Inherit from the deleted profile's own parent instead, taking over
+    /// the fields it set so they resolve to the same settings.
+    #[default]
+    Reparent,
+    /// Resolve fully and stop inheriting.
+    Flatten,
+    /// Delete them too, along with everything that inherits from them.
+    Cascade,
+}
+
+/// What is (or, in a preview, would be) done to one dependent profile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DependentAction {
+    Reparent,
+    Flatten,
+    Delete,
+}
+
+/// A user profile that inherits from the profile being deleted or renamed.
+#[derive(Debug, Clone, Serialize)]
+pub struct DependentChange {
+    pub name: String,
+    pub path: String,
+    pub action: DependentAction,
+    /// Profile it inherits from afterwards, when re-parented.
+    pub new_parent: Option<String>,
+    /// Fields written into it so it keeps resolving to the same settings.
+    pub added_fields: Vec<String>,
+    /// Why the action differs from the chosen policy.
+    pub note: Option<String>,
+}
+
+/// Everything a delete or rename touches.
+#[derive(Serialize)]
+pub struct RemovalPlan {
+    pub name: String,
+    pub path: String,
+    /// Set for a rename.
+    pub new_name: Option<String>,
+    pub new_path: Option<String>,
+    pub dependents: Vec<DependentChange>,
+    /// Names removed from and added to BambuStudio.conf's `filaments`.
+    pub unregister: Vec<String>,
+    pub register: Vec<String>,
+    #[serde(skip)]
+    renamed: Option<FilamentProfile>,
+    #[serde(skip)]
+    rewrites: Vec<(PathBuf, FilamentProfile)>,
+    #[serde(skip)]
+    deletions: Vec<PathBuf>,
+}
+
+impl RemovalPlan {
+    fn new(name: &str, path: &Path) -> Self {
+        Self {
+            name: name.to_string(),
+            path: path.to_string_lossy().to_string(),
+            new_name: None,
+            new_path: None,
+            dependents: Vec::new(),
+            unregister: Vec::new(),
+            register: Vec::new(),
+            renamed: None,
+            rewrites: Vec::new(),
+            deletions: Vec::new(),
+        }
+    }
+}
+
+/// User profiles that inherit directly from `name`, sorted by name.
+pub fn find_dependents<'a>(
+    registry: &'a ProfileRegistry,
+    name: &str,
+) -> Vec<(&'a FilamentProfile, &'a Path)> {
+    let mut dependents: Vec<(&FilamentProfile, &Path)> = registry
+        .iter()
+        .filter(|p| p.inherits() == Some(name))
+        .filter_map(|p| {
+            let source = registry.source(p.name()?)?;
+            source.is_user.then_some((p, source.path.as_path()))
+        })
+        .collect();
+    dependents.sort_by_key(|(p, _)| p.name().unwrap_or_default().to_string());
+    dependents
+}
+
+/// Work out what deleting the user profile `name` would do, without
+/// writing anything.
+pub fn plan_delete(
+    registry: &ProfileRegistry,
+    name: &str,
+    policy: DependentPolicy,
+) -> Result<RemovalPlan> {
+    let (profile, path) = user_profile(registry, name)?;
+    let mut plan = RemovalPlan::new(name, path);
+    plan.unregister.push(file_stem(path));
+    plan.deletions.push(path.to_path_buf());
+
+    if policy == DependentPolicy::Cascade {
+        // Breadth first; `seen` also guards against inheritance cycles
+        let mut seen = HashSet::from([name.to_string()]);
+        let mut queue = VecDeque::from([name.to_string()]);
+        while let Some(parent) = queue.pop_front() {
+            for (dependent, dep_path) in find_dependents(registry, &parent) {
+                let dep_name = dependent.name().unwrap_or_default().to_string();
+                if !seen.insert(dep_name.clone()) {
+                    continue;
+                }
+                plan.unregister.push(file_stem(dep_path));
+                plan.deletions.push(dep_path.to_path_buf());
+                plan.dependents
+                    .push(change(&dep_name, dep_path, DependentAction::Delete, None));
+                queue.push_back(dep_name);
+            }
+        }
+        return Ok(plan);
+    }
+
+    let grandparent = profile.inherits().filter(|p| !p.is_empty());
+    for (dependent, dep_path) in find_dependents(registry, name) {
+        let dep_name = dependent.name().unwrap_or_default();
+        let flat = flatten(dependent, registry)?;
+
+        let (rewritten, mut dep_change) = match (policy, grandparent) {
+            (DependentPolicy::Reparent, Some(grandparent)) => {
+                match minimize(&flat, grandparent, registry) {
+                    Ok(delta) => (
+                        delta,
+                        change(
+                            dep_name,
+                            dep_path,
+                            DependentAction::Reparent,
+                            Some(grandparent),
+                        ),
+                    ),
+                    Err(e) => {
+                        let mut c = change(dep_name, dep_path, DependentAction::Flatten, None);
+                        c.note = Some(format!("Can't be re-parented, so it is flattened: {}", e));
+                        (flat, c)
+                    }
+                }
+            }
+            (DependentPolicy::Reparent, None) => {
+                let mut c = change(dep_name, dep_path, DependentAction::Flatten, None);
+                c.note = Some(format!(
+                    "{:?} doesn't inherit from anything, so it is flattened",
+                    name
+                ));
+                (flat, c)
+            }
+            _ => (
+                flat,
+                change(dep_name, dep_path, DependentAction::Flatten, None),
+            ),
+        };
+        dep_change.added_fields = added_fields(dependent, &rewritten);
+        plan.dependents.push(dep_change);
+        plan.rewrites.push((dep_path.to_path_buf(), rewritten));
+    }
+    Ok(plan)
+}
+
+/// Work out what renaming the user profile `name` to `new_name` would do,
+/// without writing anything.
+///
+/// The profile moves to `<new_name>.json` beside the old file, and every
+/// user profile that inherits from it is pointed at the new name.
+pub fn plan_rename(registry: &ProfileRegistry, name: &str, new_name: &str) -> Result<RemovalPlan> {
+    let new_name = new_name.trim();
+    if new_name.is_empty() {
+        bail!("The new name is empty");
+    }
+    if new_name.contains(['/', '\\']) || new_name.contains("..") {
+        bail!("Invalid profile name: {:?}", new_name);
+    }
+    if new_name == name {
+        bail!("The profile is already called {:?}", name);
+    }
+    if registry.get_by_name(new_name).is_some() {
+        bail!("A profile called {:?} already exists", new_name);
+    }
+
+    let (profile, path) = user_profile(registry, name)?;
+    let new_path = path.with_file_name(format!("{}.json", new_name));
+    if new_path != path && new_path.exists() {
+        bail!("{:?} already exists", new_path);
+    }
+
+    let mut renamed = FilamentProfile::from_map(profile.raw().clone());
+    renamed.set_string("name", new_name.to_string());
+    if renamed.raw().contains_key("filament_settings_id") {
+        renamed.set_string_array("filament_settings_id", vec![new_name.to_string()]);
+    }
+
+    let mut plan = RemovalPlan::new(name, path);
+    plan.new_name = Some(new_name.to_string());
+    plan.new_path = Some(new_path.to_string_lossy().to_string());
+    plan.renamed = Some(renamed);
+    if new_path != path {
+        plan.unregister.push(file_stem(path));
+        plan.register.push(file_stem(&new_path));
+    }
+
+    for (dependent, dep_path) in find_dependents(registry, name) {
+        let dep_name = dependent.name().unwrap_or_default();
+        let mut rewritten = FilamentProfile::from_map(dependent.raw().clone());
+        rewritten.set_string("inherits", new_name.to_string());
+        plan.dependents.push(change(
+            dep_name,
+            dep_path,
+            DependentAction::Reparent,
+            Some(new_name),
+        ));
+        plan.rewrites.push((dep_path.to_path_buf(), rewritten));
+    }
+    Ok(plan)
+}
+
+/// Carry out `plan`: write or delete every file it lists and update
+/// BambuStudio.conf under `config_root`, all or nothing.
+///
+/// Deleted profiles keep a cloud deletion marker as `delete_profile_files`
+/// does; a renamed profile keeps its `.info` file, marked modified, so the
+/// cloud copy is renamed rather than duplicated.
+pub fn apply_removal(plan: &RemovalPlan, config_root: &Path, journal_dir: &Path) -> Result<()> {
+    let mut transaction = InstallTransaction::begin(journal_dir)?;
+    let old_path = Path::new(&plan.path);
+
+    if let (Some(renamed), Some(new_path)) = (&plan.renamed, &plan.new_path) {
+        let new_path = Path::new(new_path);
+        transaction.stage_profile(renamed, new_path)?;
+        if let Some(mut metadata) = read_profile_metadata(old_path)? {
+            metadata.mark_modified();
+            transaction.stage_metadata(&metadata, new_path)?;
+            if new_path != old_path {
+                transaction.stage_removal(&old_path.with_extension("info"))?;
+            }
+        }
+        if new_path != old_path {
+            transaction.stage_removal(old_path)?;
+        }
+    }
+    for path in &plan.deletions {
+        transaction.stage_profile_deletion(path)?;
+    }
+    for (path, profile) in &plan.rewrites {
+        transaction.stage_edited_profile(profile, path)?;
+    }
+
+    let register: Vec<&str> = plan.register.iter().map(String::as_str).collect();
+    let unregister: Vec<&str> = plan.unregister.iter().map(String::as_str).collect();
+    transaction.stage_conf_update(config_root, &register, &unregister)?;
+    transaction.commit()?;
+
+    match &plan.new_name {
+        Some(new_name) => info!("Renamed profile {:?} to {:?}", plan.name, new_name),
+        None => info!("Deleted profile {:?}", plan.name),
+    }
+    if !plan.dependents.is_empty() {
+        info!("Updated {} dependent profiles", plan.dependents.len());
+    }
+    Ok(())
+}
+
+/// The user profile called `name` and the file it was read from.
+fn user_profile<'a>(
+    registry: &'a ProfileRegistry,
+    name: &str,
+) -> Result<(&'a FilamentProfile, &'a Path)> {
+    let profile = registry
+        .get_by_name(name)
+        .ok_or_else(|| anyhow::anyhow!("Profile not found: {:?}", name))?;
+    match registry.source(name) {
+        Some(source) if source.is_user => Ok((profile, source.path.as_path())),
+        _ => bail!("{:?} is not a user profile", name),
+    }
+}
+
+/// `profile` fully resolved, with nothing left to inherit.
+fn flatten(profile: &FilamentProfile, registry: &ProfileRegistry) -> Result<FilamentProfile> {
+    let mut flat = resolve_inheritance(profile, registry)?;
+    flat.set_string("inherits", String::new());
+    flat.raw_mut().remove("include");
+    Ok(flat)
+}
+
+/// Fields `rewritten` sets that `original` didn't, in key order.
+fn added_fields(original: &FilamentProfile, rewritten: &FilamentProfile) -> Vec<String> {
+    rewritten
+        .raw()
+        .keys()
+        .filter(|key| !original.raw().contains_key(*key))
+        .cloned()
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+fn change(
+    name: &str,
+    path: &Path,
+    action: DependentAction,
+    new_parent: Option<&str>,
+) -> DependentChange {
+    DependentChange {
+        name: name.to_string(),
+        path: path.to_string_lossy().to_string(),
+        action,
+        new_parent: new_parent.map(str::to_string),
+        added_fields: Vec::new(),
+        note: None,
+    }
+}
+
+/// The name BambuStudio.conf lists a profile under: its file stem.
+fn file_stem(path: &Path) -> String {
+    path.file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profile::writer::register_filament_in_conf;
+    use serde_json::{json, Value};
+    use tempfile::TempDir;
+
+    /// A user dir with `Base` <- `Mid` <- `Leaf` and `Mid` <- `Other`, all
+    /// registered in the conf; `Base` is a system profile.
+    fn library(dir: &Path) -> ProfileRegistry {
+        let system_dir = dir.join("system");
+        let user_dir = dir.join("user");
+        std::fs::create_dir_all(&system_dir).unwrap();
+        std::fs::create_dir_all(&user_dir).unwrap();
+        std::fs::write(dir.join("BambuStudio.conf"), "{\"filaments\": []}").unwrap();
+
+        let write = |dir: &Path, name: &str, value: Value| {
+            std::fs::write(dir.join(format!("{}.json", name)), value.to_string()).unwrap();
+        };
+        write(
+            &system_dir,
+            "Base",
+            json!({"name": "Base", "inherits": "", "nozzle_temperature": ["200"], "fan": ["50"]}),
+        );
+        write(
+            &user_dir,
+            "Mid",
+            json!({"name": "Mid", "inherits": "Base", "nozzle_temperature": ["210"]}),
+        );
+        write(
+            &user_dir,
+            "Leaf",
+            json!({"name": "Leaf", "inherits": "Mid", "fan": ["80"]}),
+        );
+        write(
+            &user_dir,
+            "Other",
+            json!({"name": "Other", "inherits": "Mid"}),
+        );
+        for name in ["Mid", "Leaf", "Other"] {
+            register_filament_in_conf(dir, name).unwrap();
+        }
+
+        let mut registry = ProfileRegistry::discover_system_profiles(&system_dir).unwrap();
+        registry.discover_user_profiles(&user_dir).unwrap();
+        registry
+    }
+
+    fn registered(dir: &Path) -> Vec<String> {
+        let content = std::fs::read_to_string(dir.join("BambuStudio.conf")).unwrap();
+        let conf: Value = serde_json::from_str(&content).unwrap();
+        conf["filaments"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|v| v.as_str().unwrap().to_string())
+            .collect()
+    }
+
+    fn read(dir: &Path, name: &str) -> Value {
+        let path = dir.join("user").join(format!("{}.json", name));
+        serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_delete_reparents_dependents_onto_grandparent() {
+        let dir = TempDir::new().unwrap();
+        let registry = library(dir.path());
+
+        let plan = plan_delete(&registry, "Mid", DependentPolicy::Reparent).unwrap();
+        let actions: Vec<(&str, DependentAction)> = plan
+            .dependents
+            .iter()
+            .map(|d| (d.name.as_str(), d.action))
+            .collect();
+        assert_eq!(
+            actions,
+            vec![
+                ("Leaf", DependentAction::Reparent),
+                ("Other", DependentAction::Reparent)
+            ]
+        );
+        assert_eq!(plan.dependents[0].added_fields, vec!["nozzle_temperature"]);
+        assert_eq!(plan.unregister, vec!["Mid"]);
+
+        apply_removal(&plan, dir.path(), &dir.path().join("journal")).unwrap();
+        assert!(!dir.path().join("user/Mid.json").exists());
+        let leaf = read(dir.path(), "Leaf");
+        assert_eq!(leaf["inherits"], "Base");
+        assert_eq!(leaf["nozzle_temperature"], json!(["210"]));
+        assert_eq!(leaf["fan"], json!(["80"]));
+        assert_eq!(registered(dir.path()), vec!["Leaf", "Other"]);
+    }
+
+    #[test]
+    fn test_delete_flattens_or_cascades() {
+        let dir = TempDir::new().unwrap();
+        let registry = library(dir.path());
+
+        let plan = plan_delete(&registry, "Mid", DependentPolicy::Flatten).unwrap();
+        assert!(plan
+            .dependents
+            .iter()
+            .all(|d| d.action == DependentAction::Flatten));
+
+        let plan = plan_delete(&registry, "Mid", DependentPolicy::Cascade).unwrap();
+        assert_eq!(plan.dependents.len(), 2);
+        apply_removal(&plan, dir.path(), &dir.path().join("journal")).unwrap();
+        assert!(std::fs::read_dir(dir.path().join("user"))
+            .unwrap()
+            .next()
+            .is_none());
+        assert!(registered(dir.path()).is_empty());
+    }
+
+    #[test]
+    fn test_rename_moves_profile_and_repoints_dependents() {
+        let dir = TempDir::new().unwrap();
+        let registry = library(dir.path());
+
+        assert!(plan_rename(&registry, "Mid", "Leaf").is_err());
+        assert!(plan_rename(&registry, "Base", "Mine").is_err());
+
+        let plan = plan_rename(&registry, "Mid", "Middle").unwrap();
+        assert_eq!(plan.dependents.len(), 2);
+        apply_removal(&plan, dir.path(), &dir.path().join("journal")).unwrap();
+
+        assert!(!dir.path().join("user/Mid.json").exists());
+        assert_eq!(read(dir.path(), "Middle")["name"], "Middle");
+        assert_eq!(read(dir.path(), "Leaf")["inherits"], "Middle");
+        assert_eq!(registered(dir.path()), vec!["Leaf", "Other", "Middle"]);
+    }
+}
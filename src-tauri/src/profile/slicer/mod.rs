@@ -0,0 +1,347 @@
+//! Slicer targets: emit one `FilamentSpecs` as a profile for each slicer we run.
+//!
+//! `FilamentSpecs` is slicer-agnostic; every target only differs in file
+//! format, key names and how the profile hooks into the slicer's own
+//! presets. Each emitter implements [`SlicerEmitter`] and describes its keys
+//! with a `&[(SpecField, &str)]` mapping table, which drives both emitting
+//! and parsing so the two directions can't drift apart.
+//!
+//! - [`orca::OrcaSlicerEmitter`]: OrcaSlicer user-preset JSON.
+//! - [`prusa::PrusaSlicerEmitter`]: PrusaSlicer `.ini`.
+//!
+//! Bambu Studio profiles come from `generator::generate_profile`, which
+//! needs the installed system profiles (see `commands::slicer`).
+
+pub mod orca;
+pub mod prusa;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::scraper::types::FilamentSpecs;
+
+pub use orca::OrcaSlicerEmitter;
+pub use prusa::PrusaSlicerEmitter;
+
+/// The slicers BambuMate can produce filament profiles for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SlicerTarget {
+    BambuStudio,
+    OrcaSlicer,
+    PrusaSlicer,
+}
+
+impl SlicerTarget {
+    /// Parse a target name as accepted by the CLI and commands.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().replace(['-', '_', ' '], "").as_str() {
+            "bambu" | "bambustudio" => Some(Self::BambuStudio),
+            "orca" | "orcaslicer" => Some(Self::OrcaSlicer),
+            "prusa" | "prusaslicer" => Some(Self::PrusaSlicer),
+            _ => None,
+        }
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Self::BambuStudio => "Bambu Studio",
+            Self::OrcaSlicer => "OrcaSlicer",
+            Self::PrusaSlicer => "PrusaSlicer",
+        }
+    }
+}
+
+/// A profile rendered for one slicer, ready to be written to disk.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmittedProfile {
+    pub target: SlicerTarget,
+    /// Preset name as the slicer will display it.
+    pub name: String,
+    pub filename: String,
+    /// Where the file goes, relative to the slicer's config root
+    /// (e.g. `user/default/filament` for OrcaSlicer).
+    pub install_subdir: String,
+    pub content: String,
+    /// Companion `.info` content (Bambu Studio only).
+    pub metadata_info: Option<String>,
+}
+
+/// A slicer output format for filament profiles.
+pub trait SlicerEmitter {
+    fn target(&self) -> SlicerTarget;
+
+    /// Render `specs` as a profile for this slicer.
+    fn emit(&self, specs: &FilamentSpecs) -> Result<EmittedProfile>;
+
+    /// Read the mapped fields of a profile written by [`Self::emit`] (or by
+    /// the slicer itself) back into `FilamentSpecs`.
+    fn parse(&self, content: &str) -> Result<FilamentSpecs>;
+}
+
+/// A `FilamentSpecs` field that a slicer mapping table can refer to.
+///
+/// Only fields with a direct counterpart in at least one slicer are listed;
+/// derived or legacy fields (`fan_speed_percent`, `max_speed_mm_s`, the
+/// range bounds Bambu Studio pads) are left to the individual emitters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpecField {
+    Material,
+    Brand,
+    NozzleTempMin,
+    NozzleTempMax,
+    NozzleTemperature,
+    NozzleTemperatureInitialLayer,
+    HotPlateTemp,
+    HotPlateTempInitialLayer,
+    CoolPlateTemp,
+    CoolPlateTempInitialLayer,
+    EngPlateTemp,
+    EngPlateTempInitialLayer,
+    TexturedPlateTemp,
+    TexturedPlateTempInitialLayer,
+    MaxVolumetricSpeed,
+    FlowRatio,
+    PressureAdvance,
+    FanMinSpeed,
+    FanMaxSpeed,
+    OverhangFanSpeed,
+    CloseFanFirstLayers,
+    AdditionalCoolingFanSpeed,
+    SlowDownLayerTime,
+    SlowDownMinSpeed,
+    RetractionLength,
+    RetractionSpeed,
+    DeretractionSpeed,
+    Density,
+    Diameter,
+    TemperatureVitrification,
+    Cost,
+}
+
+impl SpecField {
+    /// Current value of this field, formatted the way slicers store it.
+    ///
+    /// Floats use Rust's shortest round-trip formatting, so `0.04` stays
+    /// `"0.04"` rather than picking up a fixed precision.
+    pub fn get(&self, specs: &FilamentSpecs) -> Option<String> {
+        fn text(s: &str) -> Option<String> {
+            (!s.is_empty()).then(|| s.to_string())
+        }
+        fn num<T: ToString>(v: Option<T>) -> Option<String> {
+            v.map(|v| v.to_string())
+        }
+        match self {
+            Self::Material => text(&specs.material),
+            Self::Brand => text(&specs.brand),
+            Self::NozzleTempMin => num(specs.nozzle_temp_min),
+            Self::NozzleTempMax => num(specs.nozzle_temp_max),
+            Self::NozzleTemperature => num(specs.nozzle_temperature),
+            Self::NozzleTemperatureInitialLayer => num(specs.nozzle_temperature_initial_layer),
+            Self::HotPlateTemp => num(specs.hot_plate_temp),
+            Self::HotPlateTempInitialLayer => num(specs.hot_plate_temp_initial_layer),
+            Self::CoolPlateTemp => num(specs.cool_plate_temp),
+            Self::CoolPlateTempInitialLayer => num(specs.cool_plate_temp_initial_layer),
+            Self::EngPlateTemp => num(specs.eng_plate_temp),
+            Self::EngPlateTempInitialLayer => num(specs.eng_plate_temp_initial_layer),
+            Self::TexturedPlateTemp => num(specs.textured_plate_temp),
+            Self::TexturedPlateTempInitialLayer => num(specs.textured_plate_temp_initial_layer),
+            Self::MaxVolumetricSpeed => num(specs.max_volumetric_speed),
+            Self::FlowRatio => num(specs.filament_flow_ratio),
+            Self::PressureAdvance => num(specs.pressure_advance),
+            Self::FanMinSpeed => num(specs.fan_min_speed),
+            Self::FanMaxSpeed => num(specs.fan_max_speed),
+            Self::OverhangFanSpeed => num(specs.overhang_fan_speed),
+            Self::CloseFanFirstLayers => num(specs.close_fan_the_first_x_layers),
+            Self::AdditionalCoolingFanSpeed => num(specs.additional_cooling_fan_speed),
+            Self::SlowDownLayerTime => num(specs.slow_down_layer_time),
+            Self::SlowDownMinSpeed => num(specs.slow_down_min_speed),
+            Self::RetractionLength => num(specs.retraction_distance_mm),
+            Self::RetractionSpeed => num(specs.retraction_speed_mm_s),
+            Self::DeretractionSpeed => num(specs.deretraction_speed_mm_s),
+            Self::Density => num(specs.density_g_cm3),
+            Self::Diameter => num(specs.diameter_mm),
+            Self::TemperatureVitrification => num(specs.temperature_vitrification),
+            Self::Cost => num(specs.filament_cost),
+        }
+    }
+
+    /// Parse `value` into this field. Values that don't parse as the
+    /// field's type (e.g. `"nil"`) leave the field unchanged.
+    pub fn set(&self, specs: &mut FilamentSpecs, value: &str) {
+        fn parse<T: std::str::FromStr>(slot: &mut Option<T>, value: &str) {
+            // Slicers write integer settings as "35" but some hand-edited
+            // profiles use "35.0"; accept both for integer fields.
+            if let Ok(v) = value.parse() {
+                *slot = Some(v);
+            } else if let Some(v) = value
+                .parse::<f64>()
+                .ok()
+                .filter(|f| f.fract() == 0.0)
+                .and_then(|f| format!("{}", f as i64).parse().ok())
+            {
+                *slot = Some(v);
+            }
+        }
+        let value = value.trim();
+        match self {
+            Self::Material => specs.material = value.to_string(),
+            Self::Brand => specs.brand = value.to_string(),
+            Self::NozzleTempMin => parse(&mut specs.nozzle_temp_min, value),
+            Self::NozzleTempMax => parse(&mut specs.nozzle_temp_max, value),
+            Self::NozzleTemperature => parse(&mut specs.nozzle_temperature, value),
+            Self::NozzleTemperatureInitialLayer => {
+                parse(&mut specs.nozzle_temperature_initial_layer, value)
+            }
+            Self::HotPlateTemp => parse(&mut specs.hot_plate_temp, value),
+            Self::HotPlateTempInitialLayer => parse(&mut specs.hot_plate_temp_initial_layer, value),
+            Self::CoolPlateTemp => parse(&mut specs.cool_plate_temp, value),
+            Self::CoolPlateTempInitialLayer => {
+                parse(&mut specs.cool_plate_temp_initial_layer, value)
+            }
+            Self::EngPlateTemp => parse(&mut specs.eng_plate_temp, value),
+            Self::EngPlateTempInitialLayer => parse(&mut specs.eng_plate_temp_initial_layer, value),
+            Self::TexturedPlateTemp => parse(&mut specs.textured_plate_temp, value),
+            Self::TexturedPlateTempInitialLayer => {
+                parse(&mut specs.textured_plate_temp_initial_layer, value)
+            }
+            Self::MaxVolumetricSpeed => parse(&mut specs.max_volumetric_speed, value),
+            Self::FlowRatio => parse(&mut specs.filament_flow_ratio, value),
+            Self::PressureAdvance => parse(&mut specs.pressure_advance, value),
+            Self::FanMinSpeed => parse(&mut specs.fan_min_speed, value),
+            Self::FanMaxSpeed => parse(&mut specs.fan_max_speed, value),
+            Self::OverhangFanSpeed => parse(&mut specs.overhang_fan_speed, value),
+            Self::CloseFanFirstLayers => parse(&mut specs.close_fan_the_first_x_layers, value),
+            Self::AdditionalCoolingFanSpeed => {
+                parse(&mut specs.additional_cooling_fan_speed, value)
+            }
+            Self::SlowDownLayerTime => parse(&mut specs.slow_down_layer_time, value),
+            Self::SlowDownMinSpeed => parse(&mut specs.slow_down_min_speed, value),
+            Self::RetractionLength => parse(&mut specs.retraction_distance_mm, value),
+            Self::RetractionSpeed => parse(&mut specs.retraction_speed_mm_s, value),
+            Self::DeretractionSpeed => parse(&mut specs.deretraction_speed_mm_s, value),
+            Self::Density => parse(&mut specs.density_g_cm3, value),
+            Self::Diameter => parse(&mut specs.diameter_mm, value),
+            Self::TemperatureVitrification => parse(&mut specs.temperature_vitrification, value),
+            Self::Cost => parse(&mut specs.filament_cost, value),
+        }
+    }
+}
+
+/// Display name shared by the Orca and Prusa targets: "Brand Material Serial".
+pub(crate) fn preset_name(specs: &FilamentSpecs) -> String {
+    [
+        specs.brand.trim(),
+        specs.material.trim(),
+        specs.serial.trim(),
+    ]
+    .iter()
+    .filter(|s| !s.is_empty())
+    .copied()
+    .collect::<Vec<_>>()
+    .join(" ")
+}
+
+/// Specs parsed back from a profile start out as "came from a profile",
+/// matching `generator::extract_specs_from_profile`.
+pub(crate) fn parsed_specs() -> FilamentSpecs {
+    FilamentSpecs {
+        source_url: "profile".to_string(),
+        extraction_confidence: 1.0,
+        ..FilamentSpecs::default()
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Specs with every `SpecField` populated, for round-trip tests.
+    pub(crate) fn full_specs() -> FilamentSpecs {
+        FilamentSpecs {
+            serial: "Matte".to_string(),
+            brand: "Polymaker".to_string(),
+            material: "PLA".to_string(),
+            nozzle_temp_min: Some(190),
+            nozzle_temp_max: Some(230),
+            nozzle_temperature: Some(215),
+            nozzle_temperature_initial_layer: Some(220),
+            hot_plate_temp: Some(60),
+            hot_plate_temp_initial_layer: Some(65),
+            cool_plate_temp: Some(35),
+            cool_plate_temp_initial_layer: Some(40),
+            eng_plate_temp: Some(55),
+            eng_plate_temp_initial_layer: Some(58),
+            textured_plate_temp: Some(62),
+            textured_plate_temp_initial_layer: Some(63),
+            max_volumetric_speed: Some(18.5),
+            filament_flow_ratio: Some(0.97),
+            pressure_advance: Some(0.035),
+            fan_min_speed: Some(60),
+            fan_max_speed: Some(100),
+            overhang_fan_speed: Some(90),
+            close_fan_the_first_x_layers: Some(2),
+            additional_cooling_fan_speed: Some(70),
+            slow_down_layer_time: Some(6),
+            slow_down_min_speed: Some(20),
+            retraction_distance_mm: Some(0.8),
+            retraction_speed_mm_s: Some(30),
+            deretraction_speed_mm_s: Some(25),
+            density_g_cm3: Some(1.24),
+            diameter_mm: Some(1.75),
+            temperature_vitrification: Some(58),
+            filament_cost: Some(24.99),
+            ..parsed_specs()
+        }
+    }
+
+    /// Assert every field in `table` survived the round trip unchanged.
+    pub(crate) fn assert_mapped_fields_equal(
+        table: &[(SpecField, &str)],
+        original: &FilamentSpecs,
+        parsed: &FilamentSpecs,
+    ) {
+        for (field, key) in table {
+            assert_eq!(
+                field.get(original),
+                field.get(parsed),
+                "{:?} ({}) changed after round trip",
+                field,
+                key
+            );
+        }
+    }
+
+    #[test]
+    fn test_slicer_target_parse() {
+        assert_eq!(SlicerTarget::parse("orca"), Some(SlicerTarget::OrcaSlicer));
+        assert_eq!(
+            SlicerTarget::parse("PrusaSlicer"),
+            Some(SlicerTarget::PrusaSlicer)
+        );
+        assert_eq!(
+            SlicerTarget::parse("bambu-studio"),
+            Some(SlicerTarget::BambuStudio)
+        );
+        assert_eq!(SlicerTarget::parse("cura"), None);
+    }
+
+    #[test]
+    fn test_spec_field_set_tolerates_formats() {
+        let mut specs = FilamentSpecs::default();
+        SpecField::NozzleTemperature.set(&mut specs, "215.0");
+        SpecField::FlowRatio.set(&mut specs, " 0.98 ");
+        SpecField::FanMaxSpeed.set(&mut specs, "nil");
+        assert_eq!(specs.nozzle_temperature, Some(215));
+        assert_eq!(specs.filament_flow_ratio, Some(0.98));
+        assert_eq!(specs.fan_max_speed, None);
+    }
+
+    #[test]
+    fn test_preset_name_skips_empty_serial() {
+        let mut specs = full_specs();
+        assert_eq!(preset_name(&specs), "Polymaker PLA Matte");
+        specs.serial.clear();
+        assert_eq!(preset_name(&specs), "Polymaker PLA");
+    }
+}
@@ -0,0 +1,247 @@
+//! OrcaSlicer filament presets.
+//!
+//! OrcaSlicer is a Bambu Studio fork and keeps its filament schema, so the
+//! key names mostly match. What differs is the layout: user presets live
+//! in `user/default/filament/` and store only their overrides on top of a
+//! vendor-neutral system preset from `OrcaFilamentLibrary`
+//! (`"Generic PLA @System"` and friends) via `inherits`. Every per-extruder
+//! value is a single-element array.
+
+use anyhow::{anyhow, Result};
+use serde_json::{Map, Value};
+
+use super::{parsed_specs, preset_name, EmittedProfile, SlicerEmitter, SlicerTarget, SpecField};
+use crate::profile::generator::generate_filament_id;
+use crate::scraper::types::{FilamentSpecs, MaterialType};
+
+/// OrcaSlicer key for each spec field. Every value is written as a
+/// one-element array, as OrcaSlicer and Bambu Studio store them.
+pub const ORCA_FIELD_MAP: &[(SpecField, &str)] = &[
+    (SpecField::Material, "filament_type"),
+    (SpecField::Brand, "filament_vendor"),
+    (SpecField::NozzleTempMin, "nozzle_temperature_range_low"),
+    (SpecField::NozzleTempMax, "nozzle_temperature_range_high"),
+    (SpecField::NozzleTemperature, "nozzle_temperature"),
+    (
+        SpecField::NozzleTemperatureInitialLayer,
+        "nozzle_temperature_initial_layer",
+    ),
+    (SpecField::HotPlateTemp, "hot_plate_temp"),
+    (
+        SpecField::HotPlateTempInitialLayer,
+        "hot_plate_temp_initial_layer",
+    ),
+    (SpecField::CoolPlateTemp, "cool_plate_temp"),
+    (
+        SpecField::CoolPlateTempInitialLayer,
+        "cool_plate_temp_initial_layer",
+    ),
+    (SpecField::EngPlateTemp, "eng_plate_temp"),
+    (
+        SpecField::EngPlateTempInitialLayer,
+        "eng_plate_temp_initial_layer",
+    ),
+    (SpecField::TexturedPlateTemp, "textured_plate_temp"),
+    (
+        SpecField::TexturedPlateTempInitialLayer,
+        "textured_plate_temp_initial_layer",
+    ),
+    (
+        SpecField::MaxVolumetricSpeed,
+        "filament_max_volumetric_speed",
+    ),
+    (SpecField::FlowRatio, "filament_flow_ratio"),
+    (SpecField::PressureAdvance, "pressure_advance"),
+    (SpecField::FanMinSpeed, "fan_min_speed"),
+    (SpecField::FanMaxSpeed, "fan_max_speed"),
+    (SpecField::OverhangFanSpeed, "overhang_fan_speed"),
+    (
+        SpecField::CloseFanFirstLayers,
+        "close_fan_the_first_x_layers",
+    ),
+    (
+        SpecField::AdditionalCoolingFanSpeed,
+        "additional_cooling_fan_speed",
+    ),
+    (SpecField::SlowDownLayerTime, "slow_down_layer_time"),
+    (SpecField::SlowDownMinSpeed, "slow_down_min_speed"),
+    (SpecField::RetractionLength, "filament_retraction_length"),
+    (SpecField::RetractionSpeed, "filament_retraction_speed"),
+    (SpecField::DeretractionSpeed, "filament_deretraction_speed"),
+    (SpecField::Density, "filament_density"),
+    (SpecField::Diameter, "filament_diameter"),
+    (
+        SpecField::TemperatureVitrification,
+        "temperature_vitrification",
+    ),
+    (SpecField::Cost, "filament_cost"),
+];
+
+/// Preset format version OrcaSlicer stamps on user presets.
+const ORCA_PRESET_VERSION: &str = "2.2.0.0";
+
+/// Map a material to the OrcaFilamentLibrary system preset it inherits from.
+pub fn orca_base_preset(material: &MaterialType) -> &'static str {
+    match material {
+        MaterialType::PLA => "Generic PLA @System",
+        MaterialType::PETG => "Generic PETG @System",
+        MaterialType::ABS => "Generic ABS @System",
+        MaterialType::ASA => "Generic ASA @System",
+        MaterialType::TPU => "Generic TPU @System",
+        MaterialType::Nylon => "Generic PA @System",
+        MaterialType::PC => "Generic PC @System",
+        MaterialType::PVA => "Generic PVA @System",
+        MaterialType::HIPS => "Generic HIPS @System",
+        MaterialType::Other(_) => "Generic PLA @System",
+    }
+}
+
+/// Emits OrcaSlicer user filament presets.
+#[derive(Debug, Default, Clone)]
+pub struct OrcaSlicerEmitter {
+    /// Printer presets to restrict the filament to. Empty means the
+    /// inherited `compatible_printers` applies (all printers for the
+    /// `@System` generics).
+    pub compatible_printers: Vec<String>,
+}
+
+impl SlicerEmitter for OrcaSlicerEmitter {
+    fn target(&self) -> SlicerTarget {
+        SlicerTarget::OrcaSlicer
+    }
+
+    fn emit(&self, specs: &FilamentSpecs) -> Result<EmittedProfile> {
+        let name = preset_name(specs);
+        if name.is_empty() {
+            return Err(anyhow!(
+                "Specs need at least a brand or material to name the preset"
+            ));
+        }
+        let material = MaterialType::from_str(&specs.material);
+
+        let mut data = Map::new();
+        data.insert("type".into(), Value::String("filament".into()));
+        data.insert("name".into(), Value::String(name.clone()));
+        data.insert(
+            "inherits".into(),
+            Value::String(orca_base_preset(&material).into()),
+        );
+        data.insert("from".into(), Value::String("User".into()));
+        data.insert("instantiation".into(), Value::String("true".into()));
+        data.insert("is_custom_defined".into(), Value::String("0".into()));
+        data.insert("version".into(), Value::String(ORCA_PRESET_VERSION.into()));
+        data.insert("filament_id".into(), Value::String(generate_filament_id()));
+        data.insert(
+            "filament_settings_id".into(),
+            Value::Array(vec![Value::String(name.clone())]),
+        );
+
+        for (field, key) in ORCA_FIELD_MAP {
+            if let Some(value) = field.get(specs) {
+                data.insert((*key).into(), Value::Array(vec![Value::String(value)]));
+            }
+        }
+        // Orca ignores pressure_advance unless it is switched on per filament
+        if specs.pressure_advance.is_some() {
+            data.insert(
+                "enable_pressure_advance".into(),
+                Value::Array(vec![Value::String("1".into())]),
+            );
+        }
+        if !self.compatible_printers.is_empty() {
+            data.insert(
+                "compatible_printers".into(),
+                Value::Array(
+                    self.compatible_printers
+                        .iter()
+                        .map(|p| Value::String(p.clone()))
+                        .collect(),
+                ),
+            );
+        }
+
+        Ok(EmittedProfile {
+            target: SlicerTarget::OrcaSlicer,
+            filename: format!("{}.json", name),
+            name,
+            install_subdir: "user/default/filament".to_string(),
+            content: serde_json::to_string_pretty(&Value::Object(data))?,
+            metadata_info: None,
+        })
+    }
+
+    fn parse(&self, content: &str) -> Result<FilamentSpecs> {
+        let data: Map<String, Value> = serde_json::from_str(content)?;
+        let mut specs = parsed_specs();
+        for (field, key) in ORCA_FIELD_MAP {
+            let value = match data.get(*key) {
+                Some(Value::String(s)) => Some(s.as_str()),
+                Some(Value::Array(arr)) => arr.first().and_then(|v| v.as_str()),
+                _ => None,
+            };
+            if let Some(value) = value {
+                field.set(&mut specs, value);
+            }
+        }
+        if let Some(name) = data.get("name").and_then(|v| v.as_str()) {
+            specs.serial = crate::scraper::html_extractor::infer_serial(name);
+        }
+        Ok(specs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{assert_mapped_fields_equal, full_specs};
+    use super::*;
+
+    #[test]
+    fn test_orca_round_trip() {
+        let emitter = OrcaSlicerEmitter::default();
+        let specs = full_specs();
+        let emitted = emitter.emit(&specs).unwrap();
+        let parsed = emitter.parse(&emitted.content).unwrap();
+        assert_mapped_fields_equal(ORCA_FIELD_MAP, &specs, &parsed);
+    }
+
+    #[test]
+    fn test_orca_layout_and_inheritance() {
+        let emitter = OrcaSlicerEmitter {
+            compatible_printers: vec!["Voron 2.4 350 0.4 nozzle".to_string()],
+        };
+        let emitted = emitter.emit(&full_specs()).unwrap();
+        assert_eq!(emitted.name, "Polymaker PLA Matte");
+        assert_eq!(emitted.filename, "Polymaker PLA Matte.json");
+        assert_eq!(emitted.install_subdir, "user/default/filament");
+
+        let data: Value = serde_json::from_str(&emitted.content).unwrap();
+        assert_eq!(data["inherits"], "Generic PLA @System");
+        assert_eq!(data["from"], "User");
+        // Values are single-element arrays, range bounds included
+        assert_eq!(data["nozzle_temperature"], serde_json::json!(["215"]));
+        assert_eq!(
+            data["nozzle_temperature_range_high"],
+            serde_json::json!(["230"])
+        );
+        assert_eq!(data["enable_pressure_advance"], serde_json::json!(["1"]));
+        assert_eq!(
+            data["compatible_printers"],
+            serde_json::json!(["Voron 2.4 350 0.4 nozzle"])
+        );
+    }
+
+    #[test]
+    fn test_orca_omits_unknown_values() {
+        let specs = FilamentSpecs {
+            brand: "Generic".to_string(),
+            material: "PETG".to_string(),
+            nozzle_temperature: Some(240),
+            ..FilamentSpecs::default()
+        };
+        let emitted = OrcaSlicerEmitter::default().emit(&specs).unwrap();
+        let data: Value = serde_json::from_str(&emitted.content).unwrap();
+        assert_eq!(data["inherits"], "Generic PETG @System");
+        assert!(data.get("fan_max_speed").is_none());
+        assert!(data.get("enable_pressure_advance").is_none());
+    }
+}
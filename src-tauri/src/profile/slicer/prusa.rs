@@ -0,0 +1,263 @@
+//! PrusaSlicer filament presets (`.ini`).
+//!
+//! PrusaSlicer keeps one flat `key = value` file per user preset under
+//! `<config>/filament/`. It has a single bed temperature instead of
+//! per-plate ones, so the hot (smooth PEI) plate values are used, and no
+//! pressure advance setting: linear advance goes into
+//! `start_filament_gcode` as `M900 K<value>`, which is how Prusa's own
+//! presets handle it.
+
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use std::collections::BTreeMap;
+
+use super::{parsed_specs, preset_name, EmittedProfile, SlicerEmitter, SlicerTarget, SpecField};
+use crate::scraper::types::FilamentSpecs;
+
+/// PrusaSlicer key for each spec field. Pressure advance is handled
+/// separately through `start_filament_gcode`.
+pub const PRUSA_FIELD_MAP: &[(SpecField, &str)] = &[
+    (SpecField::Material, "filament_type"),
+    (SpecField::Brand, "filament_vendor"),
+    (SpecField::NozzleTemperature, "temperature"),
+    (
+        SpecField::NozzleTemperatureInitialLayer,
+        "first_layer_temperature",
+    ),
+    (SpecField::HotPlateTemp, "bed_temperature"),
+    (
+        SpecField::HotPlateTempInitialLayer,
+        "first_layer_bed_temperature",
+    ),
+    (
+        SpecField::MaxVolumetricSpeed,
+        "filament_max_volumetric_speed",
+    ),
+    (SpecField::FlowRatio, "extrusion_multiplier"),
+    (SpecField::FanMinSpeed, "min_fan_speed"),
+    (SpecField::FanMaxSpeed, "max_fan_speed"),
+    (SpecField::OverhangFanSpeed, "bridge_fan_speed"),
+    (SpecField::CloseFanFirstLayers, "disable_fan_first_layers"),
+    (SpecField::SlowDownLayerTime, "slowdown_below_layer_time"),
+    (SpecField::SlowDownMinSpeed, "min_print_speed"),
+    (SpecField::RetractionLength, "filament_retract_length"),
+    (SpecField::RetractionSpeed, "filament_retract_speed"),
+    (SpecField::DeretractionSpeed, "filament_deretract_speed"),
+    (SpecField::Density, "filament_density"),
+    (SpecField::Diameter, "filament_diameter"),
+    (SpecField::Cost, "filament_cost"),
+];
+
+const START_GCODE_KEY: &str = "start_filament_gcode";
+
+/// Emits PrusaSlicer filament `.ini` presets.
+#[derive(Debug, Default, Clone)]
+pub struct PrusaSlicerEmitter {
+    /// Written to `compatible_printers_condition`, e.g.
+    /// `printer_notes=~/.*PRINTER_VENDOR_PRUSA3D.*/`. Empty means any printer.
+    pub compatible_printers_condition: String,
+}
+
+impl SlicerEmitter for PrusaSlicerEmitter {
+    fn target(&self) -> SlicerTarget {
+        SlicerTarget::PrusaSlicer
+    }
+
+    fn emit(&self, specs: &FilamentSpecs) -> Result<EmittedProfile> {
+        let name = preset_name(specs);
+        if name.is_empty() {
+            return Err(anyhow!(
+                "Specs need at least a brand or material to name the preset"
+            ));
+        }
+
+        // PrusaSlicer writes keys sorted; a BTreeMap gives the same order.
+        let mut values: BTreeMap<&str, String> = BTreeMap::new();
+        for (field, key) in PRUSA_FIELD_MAP {
+            if let Some(value) = field.get(specs) {
+                values.insert(key, value);
+            }
+        }
+        values.insert("filament_settings_id", quote(&name));
+        values.insert("inherits", String::new());
+        values.insert(
+            "compatible_printers_condition",
+            quote(&self.compatible_printers_condition),
+        );
+        // Cooling logic is off unless enabled; turn it on when we have fan data
+        if specs.fan_max_speed.is_some() || specs.fan_min_speed.is_some() {
+            values.insert("cooling", "1".to_string());
+            let always_on = specs.fan_min_speed.unwrap_or(0) > 0;
+            values.insert("fan_always_on", (always_on as u8).to_string());
+        }
+        if let Some(pa) = specs.pressure_advance {
+            values.insert(START_GCODE_KEY, quote(&format!("M900 K{}", pa)));
+        }
+
+        let mut content = format!(
+            "# generated by BambuMate {} on {}\n",
+            env!("CARGO_PKG_VERSION"),
+            Utc::now().format("%Y-%m-%d at %H:%M:%S UTC")
+        );
+        for (key, value) in &values {
+            if value.is_empty() {
+                content.push_str(&format!("{} =\n", key));
+            } else {
+                content.push_str(&format!("{} = {}\n", key, value));
+            }
+        }
+
+        Ok(EmittedProfile {
+            target: SlicerTarget::PrusaSlicer,
+            filename: format!("{}.ini", name),
+            name,
+            install_subdir: "filament".to_string(),
+            content,
+            metadata_info: None,
+        })
+    }
+
+    fn parse(&self, content: &str) -> Result<FilamentSpecs> {
+        let values = parse_ini(content);
+        let mut specs = parsed_specs();
+        for (field, key) in PRUSA_FIELD_MAP {
+            if let Some(value) = values.get(*key) {
+                field.set(&mut specs, value);
+            }
+        }
+        if let Some(gcode) = values.get(START_GCODE_KEY) {
+            specs.pressure_advance = parse_m900_k(gcode);
+        }
+        if let Some(name) = values.get("filament_settings_id") {
+            specs.serial = crate::scraper::html_extractor::infer_serial(name);
+        }
+        Ok(specs)
+    }
+}
+
+/// Parse PrusaSlicer `key = value` lines, unquoting string values.
+/// Comments and section headers are ignored.
+pub fn parse_ini(content: &str) -> BTreeMap<String, String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#') && !line.starts_with('['))
+        .filter_map(|line| {
+            let (key, value) = line.split_once('=')?;
+            Some((key.trim().to_string(), unquote(value.trim())))
+        })
+        .collect()
+}
+
+/// Quote a string value the way PrusaSlicer does for free-text options.
+fn quote(value: &str) -> String {
+    if value.is_empty() {
+        return String::new();
+    }
+    format!(
+        "\"{}\"",
+        value
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n")
+    )
+}
+
+fn unquote(value: &str) -> String {
+    match value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+        Some(inner) => {
+            let mut out = String::with_capacity(inner.len());
+            let mut chars = inner.chars();
+            while let Some(c) = chars.next() {
+                if c == '\\' {
+                    match chars.next() {
+                        Some('n') => out.push('\n'),
+                        Some(other) => out.push(other),
+                        None => out.push('\\'),
+                    }
+                } else {
+                    out.push(c);
+                }
+            }
+            out
+        }
+        None => value.to_string(),
+    }
+}
+
+/// Extract the K factor from the first `M900 K...` command in a gcode block.
+fn parse_m900_k(gcode: &str) -> Option<f32> {
+    gcode.lines().find_map(|line| {
+        let line = line.split(';').next()?.trim();
+        let mut words = line.split_whitespace();
+        if !words.next()?.eq_ignore_ascii_case("M900") {
+            return None;
+        }
+        words.find_map(|w| {
+            w.strip_prefix('K')
+                .or_else(|| w.strip_prefix('k'))?
+                .parse()
+                .ok()
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{assert_mapped_fields_equal, full_specs};
+    use super::*;
+
+    #[test]
+    fn test_prusa_round_trip() {
+        let emitter = PrusaSlicerEmitter::default();
+        let specs = full_specs();
+        let emitted = emitter.emit(&specs).unwrap();
+        let parsed = emitter.parse(&emitted.content).unwrap();
+        assert_mapped_fields_equal(PRUSA_FIELD_MAP, &specs, &parsed);
+        assert_eq!(parsed.pressure_advance, specs.pressure_advance);
+    }
+
+    #[test]
+    fn test_prusa_ini_layout() {
+        let emitted = PrusaSlicerEmitter::default().emit(&full_specs()).unwrap();
+        assert_eq!(emitted.filename, "Polymaker PLA Matte.ini");
+        assert_eq!(emitted.install_subdir, "filament");
+
+        let lines: Vec<&str> = emitted.content.lines().collect();
+        assert!(lines[0].starts_with("# generated by BambuMate"));
+        assert!(lines.contains(&"temperature = 215"));
+        assert!(lines.contains(&"bed_temperature = 60"));
+        assert!(lines.contains(&"extrusion_multiplier = 0.97"));
+        assert!(lines.contains(&"start_filament_gcode = \"M900 K0.035\""));
+        assert!(lines.contains(&"inherits ="));
+        // Keys are sorted like PrusaSlicer writes them
+        let keys: Vec<&str> = lines[1..]
+            .iter()
+            .map(|l| l.split(" =").next().unwrap())
+            .collect();
+        let mut sorted = keys.clone();
+        sorted.sort();
+        assert_eq!(keys, sorted);
+    }
+
+    #[test]
+    fn test_parse_prusa_written_ini() {
+        let ini = "# generated by PrusaSlicer 2.8.1+linux-x64-GTK3 on 2024-10-01 at 12:00:00 UTC\n\
+                   bed_temperature = 85\n\
+                   extrusion_multiplier = 1\n\
+                   filament_settings_id = \"Prusament PETG\"\n\
+                   start_filament_gcode = \"; Filament gcode\\nM900 K0.08 ; LA\\n\"\n\
+                   temperature = 250\n";
+        let specs = PrusaSlicerEmitter::default().parse(ini).unwrap();
+        assert_eq!(specs.hot_plate_temp, Some(85));
+        assert_eq!(specs.filament_flow_ratio, Some(1.0));
+        assert_eq!(specs.nozzle_temperature, Some(250));
+        assert_eq!(specs.pressure_advance, Some(0.08));
+    }
+
+    #[test]
+    fn test_quote_round_trip() {
+        let raw = "line \"one\"\nback\\slash";
+        assert_eq!(unquote(&quote(raw)), raw);
+    }
+}
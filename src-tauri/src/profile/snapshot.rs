@@ -0,0 +1,513 @@
+//! Whole-library snapshots of the user filament directory.
+//!
+//! A snapshot is a deflated zip holding every `.json`/`.info` file in the
+//! user filament directory (under `filament/`, keeping subdirectories),
+//! `BambuStudio.conf` (at the root) and a `snapshot.json` manifest. They're
+//! taken before operations that rewrite many profiles at once, so the whole
+//! library can be put back as it was, or just the profiles that went wrong.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io::{Read, Seek, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use tempfile::NamedTempFile;
+use tracing::{info, warn};
+use walkdir::WalkDir;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+/// Name of the manifest inside each snapshot.
+const MANIFEST_FILE: &str = "snapshot.json";
+
+/// Folder inside the archive holding the user filament directory.
+const FILAMENT_PREFIX: &str = "filament/";
+
+/// Name of Bambu Studio's app config, stored at the archive root.
+pub const CONF_FILE: &str = "BambuStudio.conf";
+
+/// Refuse to read archive entries larger than this (a real preset is a few
+/// KB; BambuStudio.conf a few hundred).
+const MAX_ENTRY_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Snapshots kept by [`prune_snapshots`] by default.
+pub const DEFAULT_KEEP: usize = 30;
+
+/// Manifest stored in each snapshot.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotInfo {
+    /// Archive file name, which identifies the snapshot.
+    #[serde(default)]
+    pub id: String,
+    /// RFC 3339 timestamp.
+    pub created_at: String,
+    /// What the snapshot was taken before, e.g. "batch install".
+    pub reason: String,
+    /// Number of profile files (`.json` and `.info`) captured.
+    pub file_count: usize,
+    /// Whether `BambuStudio.conf` was captured.
+    pub has_conf: bool,
+}
+
+/// How a file differs between a snapshot and the current library.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileChangeKind {
+    /// Exists now but not in the snapshot.
+    Added,
+    /// In the snapshot but deleted since.
+    Removed,
+    /// In both, with different content.
+    Modified,
+}
+
+/// One file that differs between a snapshot and the current library.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileChange {
+    /// Path relative to the user filament directory, or `BambuStudio.conf`.
+    pub path: String,
+    pub kind: FileChangeKind,
+}
+
+/// Capture `user_dir` and `config_root/BambuStudio.conf` into a new
+/// snapshot in `snapshot_dir`.
+pub fn create_snapshot(
+    user_dir: &Path,
+    config_root: &Path,
+    snapshot_dir: &Path,
+    reason: &str,
+) -> Result<SnapshotInfo> {
+    std::fs::create_dir_all(snapshot_dir)
+        .with_context(|| format!("Failed to create {:?}", snapshot_dir))?;
+    let current = current_files(user_dir, config_root)?;
+
+    let now = Utc::now();
+    let id = unique_id(snapshot_dir, &now.format("%Y%m%d_%H%M%S").to_string());
+    let info = SnapshotInfo {
+        id: id.clone(),
+        created_at: now.to_rfc3339(),
+        reason: reason.to_string(),
+        file_count: current.keys().filter(|k| k.as_str() != CONF_FILE).count(),
+        has_conf: current.contains_key(CONF_FILE),
+    };
+
+    let mut temp = NamedTempFile::new_in(snapshot_dir)?;
+    {
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        let mut zip = ZipWriter::new(temp.as_file_mut());
+        zip.start_file(MANIFEST_FILE, options)?;
+        zip.write_all(serde_json::to_string_pretty(&info)?.as_bytes())?;
+        for (path, source) in &current {
+            zip.start_file(archive_name(path), options)?;
+            zip.write_all(&std::fs::read(source)?)?;
+        }
+        zip.finish()?;
+    }
+    temp.as_file_mut().flush()?;
+    temp.persist(snapshot_dir.join(&id))?;
+
+    info!(
+        "Snapshot {} ({}): {} profile files",
+        id, reason, info.file_count
+    );
+    Ok(info)
+}
+
+/// Snapshots in `snapshot_dir`, newest first. Unreadable archives are
+/// skipped with a warning.
+pub fn list_snapshots(snapshot_dir: &Path) -> Result<Vec<SnapshotInfo>> {
+    if !snapshot_dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut snapshots = Vec::new();
+    for entry in std::fs::read_dir(snapshot_dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("zip") {
+            continue;
+        }
+        match read_info(&path) {
+            Ok(info) => snapshots.push(info),
+            Err(e) => warn!("Skipping unreadable snapshot {:?}: {:#}", path, e),
+        }
+    }
+    snapshots.sort_by(|a, b| (&b.created_at, &b.id).cmp(&(&a.created_at, &a.id)));
+    Ok(snapshots)
+}
+
+/// Delete all but the `keep` newest snapshots. Returns how many were removed.
+pub fn prune_snapshots(snapshot_dir: &Path, keep: usize) -> Result<usize> {
+    let snapshots = list_snapshots(snapshot_dir)?;
+    let mut removed = 0;
+    for old in snapshots.iter().skip(keep) {
+        std::fs::remove_file(snapshot_dir.join(&old.id))?;
+        removed += 1;
+    }
+    Ok(removed)
+}
+
+/// Path of snapshot `id` in `snapshot_dir`. Rejects ids that aren't a plain
+/// archive file name.
+pub fn snapshot_path(snapshot_dir: &Path, id: &str) -> Result<PathBuf> {
+    if id.is_empty() || id.contains(['/', '\\']) || id.contains("..") || !id.ends_with(".zip") {
+        bail!("Invalid snapshot id '{}'", id);
+    }
+    let path = snapshot_dir.join(id);
+    if !path.is_file() {
+        bail!("Snapshot '{}' not found", id);
+    }
+    Ok(path)
+}
+
+/// Files that differ between the snapshot at `archive` and the current
+/// library, sorted by path.
+pub fn diff_snapshot(
+    archive: &Path,
+    user_dir: &Path,
+    config_root: &Path,
+) -> Result<Vec<FileChange>> {
+    let mut zip = open(archive)?;
+    let saved = saved_files(&mut zip)?;
+    let current = current_files(user_dir, config_root)?;
+
+    let paths: BTreeSet<&String> = saved.iter().chain(current.keys()).collect();
+    let mut changes = Vec::new();
+    for path in paths {
+        let kind = match (saved.contains(path), current.get(path)) {
+            (true, None) => FileChangeKind::Removed,
+            (false, Some(_)) => FileChangeKind::Added,
+            (true, Some(source)) => {
+                let before = read_entry(&mut zip, &archive_name(path))?;
+                if before == std::fs::read(source)? {
+                    continue;
+                }
+                FileChangeKind::Modified
+            }
+            (false, None) => continue,
+        };
+        changes.push(FileChange {
+            path: path.clone(),
+            kind,
+        });
+    }
+    Ok(changes)
+}
+
+/// Restore from the snapshot at `archive`.
+///
+/// With `only`, restores just those paths (as reported by
+/// [`diff_snapshot`]); a profile's `.info` comes back with its `.json`.
+/// Paths not in the snapshot are deleted, since they didn't exist when it
+/// was taken. Without `only`, the whole library is put back: every file in
+/// the snapshot is restored and profile files added since are deleted.
+///
+/// Files that already match the snapshot are left alone. Returns the paths
+/// that were written or deleted.
+pub fn restore_snapshot(
+    archive: &Path,
+    user_dir: &Path,
+    config_root: &Path,
+    only: Option<&[String]>,
+) -> Result<Vec<String>> {
+    let mut zip = open(archive)?;
+    let saved = saved_files(&mut zip)?;
+    let current = current_files(user_dir, config_root)?;
+
+    let targets: BTreeSet<String> = match only {
+        Some(paths) => paths
+            .iter()
+            .flat_map(|p| {
+                let companion = Path::new(p)
+                    .extension()
+                    .and_then(|e| e.to_str())
+                    .filter(|e| *e == "json")
+                    .map(|_| {
+                        Path::new(p)
+                            .with_extension("info")
+                            .to_string_lossy()
+                            .to_string()
+                    });
+                std::iter::once(p.clone()).chain(companion)
+            })
+            .filter(|p| saved.contains(p) || current.contains_key(p))
+            .collect(),
+        None => saved.iter().chain(current.keys()).cloned().collect(),
+    };
+
+    let mut restored = Vec::new();
+    for path in targets {
+        let target = target_path(&path, user_dir, config_root)?;
+        if saved.contains(&path) {
+            let content = read_entry(&mut zip, &archive_name(&path))?;
+            let unchanged = current
+                .get(&path)
+                .is_some_and(|source| std::fs::read(source).is_ok_and(|now| now == content));
+            if unchanged {
+                continue;
+            }
+            write_atomic(&target, &content)?;
+        } else if path == CONF_FILE {
+            // Never delete Bambu Studio's config, even if the snapshot
+            // predates it
+            continue;
+        } else {
+            std::fs::remove_file(&target)
+                .with_context(|| format!("Failed to delete {:?}", target))?;
+        }
+        restored.push(path);
+    }
+
+    info!("Restored {} files from {:?}", restored.len(), archive);
+    Ok(restored)
+}
+
+/// The snapshot's manifest, with `id` set from its file name.
+fn read_info(archive: &Path) -> Result<SnapshotInfo> {
+    let mut zip = open(archive)?;
+    let mut info: SnapshotInfo = serde_json::from_slice(&read_entry(&mut zip, MANIFEST_FILE)?)
+        .with_context(|| format!("Invalid {}", MANIFEST_FILE))?;
+    info.id = archive
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    Ok(info)
+}
+
+fn open(archive: &Path) -> Result<ZipArchive<File>> {
+    let file = File::open(archive).with_context(|| format!("Failed to open {:?}", archive))?;
+    ZipArchive::new(file).with_context(|| format!("{:?} is not a snapshot archive", archive))
+}
+
+fn read_entry<R: Read + Seek>(zip: &mut ZipArchive<R>, name: &str) -> Result<Vec<u8>> {
+    let entry = zip
+        .by_name(name)
+        .with_context(|| format!("Snapshot has no entry '{}'", name))?;
+    if entry.size() > MAX_ENTRY_SIZE {
+        bail!(
+            "Snapshot entry '{}' is too large ({} bytes)",
+            name,
+            entry.size()
+        );
+    }
+    let mut content = Vec::with_capacity(entry.size() as usize);
+    entry.take(MAX_ENTRY_SIZE).read_to_end(&mut content)?;
+    Ok(content)
+}
+
+/// Library paths stored in a snapshot (relative to the user filament dir,
+/// plus `BambuStudio.conf`).
+fn saved_files<R: Read + Seek>(zip: &mut ZipArchive<R>) -> Result<BTreeSet<String>> {
+    let mut files = BTreeSet::new();
+    for name in zip.file_names() {
+        if name == CONF_FILE {
+            files.insert(name.to_string());
+        } else if let Some(rel) = name.strip_prefix(FILAMENT_PREFIX) {
+            if is_safe_relative(rel) {
+                files.insert(rel.to_string());
+            } else {
+                warn!("Ignoring unsafe snapshot entry '{}'", name);
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// Library paths on disk now, mapped to their absolute paths.
+fn current_files(user_dir: &Path, config_root: &Path) -> Result<BTreeMap<String, PathBuf>> {
+    let mut files = BTreeMap::new();
+    if user_dir.exists() {
+        for entry in WalkDir::new(user_dir).into_iter().filter_map(|e| e.ok()) {
+            let path = entry.path();
+            let ext = path.extension().and_then(|e| e.to_str());
+            if !path.is_file() || !matches!(ext, Some("json") | Some("info")) {
+                continue;
+            }
+            // Single-profile backups live in .backups; they aren't part of
+            // the library
+            let rel = path.strip_prefix(user_dir)?;
+            if rel.components().any(|c| c.as_os_str() == ".backups") {
+                continue;
+            }
+            let rel = rel
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            files.insert(rel, path.to_path_buf());
+        }
+    }
+    let conf = config_root.join(CONF_FILE);
+    if conf.is_file() {
+        files.insert(CONF_FILE.to_string(), conf);
+    }
+    Ok(files)
+}
+
+fn archive_name(path: &str) -> String {
+    if path == CONF_FILE {
+        path.to_string()
+    } else {
+        format!("{}{}", FILAMENT_PREFIX, path)
+    }
+}
+
+fn target_path(path: &str, user_dir: &Path, config_root: &Path) -> Result<PathBuf> {
+    if path == CONF_FILE {
+        return Ok(config_root.join(CONF_FILE));
+    }
+    if !is_safe_relative(path) {
+        bail!("Refusing to restore unsafe path '{}'", path);
+    }
+    Ok(user_dir.join(path))
+}
+
+/// A relative path with no `..`, root or drive components.
+fn is_safe_relative(path: &str) -> bool {
+    !path.is_empty()
+        && Path::new(path)
+            .components()
+            .all(|c| matches!(c, std::path::Component::Normal(_)))
+}
+
+fn write_atomic(target: &Path, content: &[u8]) -> Result<()> {
+    let parent = target
+        .parent()
+        .ok_or_else(|| anyhow::anyhow!("No parent directory for {:?}", target))?;
+    std::fs::create_dir_all(parent)?;
+    let mut temp = NamedTempFile::new_in(parent)?;
+    temp.write_all(content)?;
+    temp.as_file_mut().sync_all()?;
+    temp.persist(target)?;
+    Ok(())
+}
+
+/// `snapshot_<stamp>.zip`, with a counter if one was already taken this
+/// second.
+fn unique_id(snapshot_dir: &Path, stamp: &str) -> String {
+    let mut id = format!("snapshot_{}.zip", stamp);
+    let mut n = 1;
+    while snapshot_dir.join(&id).exists() {
+        n += 1;
+        id = format!("snapshot_{}_{}.zip", stamp, n);
+    }
+    id
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    struct Library {
+        _root: TempDir,
+        user_dir: PathBuf,
+        config_root: PathBuf,
+        snapshots: PathBuf,
+    }
+
+    fn library() -> Library {
+        let root = TempDir::new().unwrap();
+        let config_root = root.path().join("config");
+        let user_dir = config_root.join("user/123/filament");
+        std::fs::create_dir_all(&user_dir).unwrap();
+        std::fs::write(user_dir.join("A.json"), r#"{"name": "A"}"#).unwrap();
+        std::fs::write(user_dir.join("A.info"), "setting_id = A1\n").unwrap();
+        std::fs::write(user_dir.join("B.json"), r#"{"name": "B"}"#).unwrap();
+        std::fs::write(config_root.join(CONF_FILE), r#"{"filaments": ["A", "B"]}"#).unwrap();
+        Library {
+            snapshots: root.path().join("snapshots"),
+            _root: root,
+            user_dir,
+            config_root,
+        }
+    }
+
+    fn change(path: &str, kind: FileChangeKind) -> FileChange {
+        FileChange {
+            path: path.to_string(),
+            kind,
+        }
+    }
+
+    #[test]
+    fn test_snapshot_diff_and_full_restore() {
+        let lib = library();
+        let info =
+            create_snapshot(&lib.user_dir, &lib.config_root, &lib.snapshots, "test").unwrap();
+        assert_eq!(info.file_count, 3);
+        assert!(info.has_conf);
+        let archive = snapshot_path(&lib.snapshots, &info.id).unwrap();
+
+        std::fs::write(lib.user_dir.join("A.json"), r#"{"name": "A2"}"#).unwrap();
+        std::fs::remove_file(lib.user_dir.join("B.json")).unwrap();
+        std::fs::write(lib.user_dir.join("C.json"), r#"{"name": "C"}"#).unwrap();
+
+        assert_eq!(
+            diff_snapshot(&archive, &lib.user_dir, &lib.config_root).unwrap(),
+            vec![
+                change("A.json", FileChangeKind::Modified),
+                change("B.json", FileChangeKind::Removed),
+                change("C.json", FileChangeKind::Added),
+            ]
+        );
+
+        restore_snapshot(&archive, &lib.user_dir, &lib.config_root, None).unwrap();
+        assert!(diff_snapshot(&archive, &lib.user_dir, &lib.config_root)
+            .unwrap()
+            .is_empty());
+        assert!(!lib.user_dir.join("C.json").exists());
+    }
+
+    #[test]
+    fn test_restore_selected_profiles() {
+        let lib = library();
+        let info =
+            create_snapshot(&lib.user_dir, &lib.config_root, &lib.snapshots, "test").unwrap();
+        let archive = snapshot_path(&lib.snapshots, &info.id).unwrap();
+
+        std::fs::write(lib.user_dir.join("A.json"), "{}").unwrap();
+        std::fs::write(lib.user_dir.join("A.info"), "").unwrap();
+        std::fs::write(lib.user_dir.join("B.json"), "{}").unwrap();
+
+        let restored = restore_snapshot(
+            &archive,
+            &lib.user_dir,
+            &lib.config_root,
+            Some(&["A.json".to_string()]),
+        )
+        .unwrap();
+        assert_eq!(restored, vec!["A.info", "A.json"]);
+        assert_eq!(
+            diff_snapshot(&archive, &lib.user_dir, &lib.config_root).unwrap(),
+            vec![change("B.json", FileChangeKind::Modified)]
+        );
+    }
+
+    #[test]
+    fn test_list_and_prune_newest_first() {
+        let lib = library();
+        let ids: Vec<String> = (0..3)
+            .map(|_| {
+                create_snapshot(&lib.user_dir, &lib.config_root, &lib.snapshots, "test")
+                    .unwrap()
+                    .id
+            })
+            .collect();
+        let listed = list_snapshots(&lib.snapshots).unwrap();
+        assert_eq!(listed.len(), 3);
+        assert_eq!(listed[0].id, *ids.iter().max().unwrap());
+
+        assert_eq!(prune_snapshots(&lib.snapshots, 1).unwrap(), 2);
+        assert_eq!(list_snapshots(&lib.snapshots).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_snapshot_path_rejects_traversal() {
+        let lib = library();
+        std::fs::create_dir_all(&lib.snapshots).unwrap();
+        assert!(snapshot_path(&lib.snapshots, "../secrets.zip").is_err());
+        assert!(snapshot_path(&lib.snapshots, "snapshot.txt").is_err());
+    }
+}
@@ -0,0 +1,259 @@
+//! Bambu cloud sync bookkeeping for user presets.
+//!
+//! Bambu Studio syncs a logged-in user's presets with Bambu cloud and
+//! tracks each one in the `.info` file next to it: `sync_info` says what's
+//! waiting to be uploaded (`create`, `update` or `delete`, empty once the
+//! cloud copy is current) and `updated_time` decides which side wins. A
+//! profile rewritten without touching these looks unchanged to Bambu
+//! Studio, so the next sync can put the cloud copy back over it.
+
+use chrono::Utc;
+use serde::Serialize;
+
+use super::types::ProfileMetadata;
+
+/// The sync state recorded in a preset's `sync_info`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncState {
+    /// Matches the cloud copy, or the preset isn't synced.
+    Synced,
+    /// Created locally, not uploaded yet.
+    Create,
+    /// Changed locally, upload pending.
+    Update,
+    /// Deleted locally, deletion from the cloud pending.
+    Delete,
+    /// Held back from syncing by Bambu Studio.
+    Hold,
+    /// A value this version doesn't know; left as it is.
+    Other(String),
+}
+
+impl SyncState {
+    pub fn parse(sync_info: &str) -> Self {
+        match sync_info.trim() {
+            "" => SyncState::Synced,
+            "create" => SyncState::Create,
+            "update" => SyncState::Update,
+            "delete" => SyncState::Delete,
+            "hold" => SyncState::Hold,
+            other => SyncState::Other(other.to_string()),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            SyncState::Synced => "",
+            SyncState::Create => "create",
+            SyncState::Update => "update",
+            SyncState::Delete => "delete",
+            SyncState::Hold => "hold",
+            SyncState::Other(s) => s,
+        }
+    }
+}
+
+/// Whether `id` is a Bambu cloud account, as opposed to the `default`
+/// preset folder Bambu Studio uses when nobody is logged in.
+pub fn is_cloud_account(id: &str) -> bool {
+    !id.is_empty() && id.chars().all(|c| c.is_ascii_digit())
+}
+
+fn now() -> u64 {
+    Utc::now().timestamp() as u64
+}
+
+impl ProfileMetadata {
+    pub fn sync_state(&self) -> SyncState {
+        SyncState::parse(&self.sync_info)
+    }
+
+    /// Whether a copy of this preset may exist in the cloud: it belongs to
+    /// an account and its creation has been uploaded.
+    pub fn is_in_cloud(&self) -> bool {
+        is_cloud_account(&self.user_id) && self.sync_state() != SyncState::Create
+    }
+
+    /// Record that the preset was just created locally, so Bambu Studio
+    /// uploads it if it belongs to an account.
+    pub fn mark_created(&mut self) {
+        self.updated_time = now();
+        self.sync_info = if is_cloud_account(&self.user_id) {
+            SyncState::Create.as_str().to_string()
+        } else {
+            String::new()
+        };
+    }
+
+    /// Record that the preset was just changed locally, so Bambu Studio
+    /// uploads the change instead of restoring the cloud copy.
+    ///
+    /// A preset that was never uploaded stays pending creation, and one
+    /// held back by Bambu Studio stays held.
+    pub fn mark_modified(&mut self) {
+        self.updated_time = now();
+        if !is_cloud_account(&self.user_id) {
+            return;
+        }
+        match self.sync_state() {
+            SyncState::Create | SyncState::Hold | SyncState::Other(_) => {}
+            SyncState::Synced | SyncState::Update | SyncState::Delete => {
+                self.sync_info = SyncState::Update.as_str().to_string();
+            }
+        }
+    }
+
+    /// Record a write of this metadata over `existing`, the `.info` already
+    /// at the target path if there is one.
+    ///
+    /// The existing preset's `setting_id`, `user_id` and sync state are
+    /// kept and the write is recorded as a change of it, so Bambu Studio
+    /// updates its cloud copy instead of downloading it back as a duplicate
+    /// beside a preset with a new `setting_id`. `base_id` names the parent
+    /// of what's being written, so it's only taken from `existing` when
+    /// this metadata has none and the parent is unknown. Without `existing`
+    /// the preset is recorded as created.
+    pub fn mark_written_over(&mut self, existing: Option<ProfileMetadata>) {
+        let Some(existing) = existing else {
+            self.mark_created();
+            return;
+        };
+        if !existing.setting_id.is_empty() {
+            self.setting_id = existing.setting_id;
+        }
+        if !existing.user_id.is_empty() {
+            self.user_id = existing.user_id;
+        }
+        if self.base_id.is_empty() {
+            self.base_id = existing.base_id;
+        }
+        self.sync_info = existing.sync_info;
+        self.mark_modified();
+    }
+
+    /// Record that the preset was deleted locally, so Bambu Studio deletes
+    /// the cloud copy instead of downloading it again.
+    pub fn mark_deleted(&mut self) {
+        self.updated_time = now();
+        self.sync_info = SyncState::Delete.as_str().to_string();
+    }
+
+    /// A warning if the preset belongs to a different account from the
+    /// active `preset_folder`, where the other account's sync can undo or
+    /// duplicate changes made here.
+    pub fn account_warning(&self, preset_folder: Option<&str>) -> Option<String> {
+        let active = preset_folder?;
+        if !is_cloud_account(&self.user_id) || self.user_id == active {
+            return None;
+        }
+        Some(format!(
+            "This profile belongs to Bambu account {}, but account {} is logged in. \
+             Changes may be undone or duplicated when either account syncs.",
+            self.user_id,
+            if is_cloud_account(active) {
+                active
+            } else {
+                "(none)"
+            }
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn meta(user_id: &str, sync_info: &str) -> ProfileMetadata {
+        ProfileMetadata {
+            sync_info: sync_info.to_string(),
+            user_id: user_id.to_string(),
+            setting_id: "PFUS123".to_string(),
+            base_id: "GFSA04".to_string(),
+            updated_time: 1,
+        }
+    }
+
+    #[test]
+    fn test_sync_state_round_trips() {
+        for info in ["", "create", "update", "delete", "hold", "mystery"] {
+            assert_eq!(SyncState::parse(info).as_str(), info);
+        }
+        assert_eq!(
+            SyncState::parse("mystery"),
+            SyncState::Other("mystery".into())
+        );
+    }
+
+    #[test]
+    fn test_mark_modified_follows_sync_state() {
+        let mut synced = meta("1881310893", "");
+        synced.mark_modified();
+        assert_eq!(synced.sync_state(), SyncState::Update);
+        assert!(synced.updated_time > 1);
+
+        let mut pending_create = meta("1881310893", "create");
+        pending_create.mark_modified();
+        assert_eq!(pending_create.sync_state(), SyncState::Create);
+
+        let mut held = meta("1881310893", "hold");
+        held.mark_modified();
+        assert_eq!(held.sync_state(), SyncState::Hold);
+
+        // Not logged in: nothing to sync
+        let mut local = meta("default", "");
+        local.mark_modified();
+        assert_eq!(local.sync_state(), SyncState::Synced);
+        assert!(local.updated_time > 1);
+    }
+
+    #[test]
+    fn test_mark_created_only_queues_uploads_for_accounts() {
+        let mut account = meta("1881310893", "");
+        account.mark_created();
+        assert_eq!(account.sync_state(), SyncState::Create);
+        assert!(!account.is_in_cloud());
+
+        let mut local = meta("", "");
+        local.mark_created();
+        assert_eq!(local.sync_state(), SyncState::Synced);
+    }
+
+    #[test]
+    fn test_mark_written_over_keeps_the_existing_identity() {
+        let mut generated = ProfileMetadata {
+            user_id: "1881310893".to_string(),
+            setting_id: "PFUSnew".to_string(),
+            base_id: "GFSA00".to_string(),
+            ..ProfileMetadata::default()
+        };
+        generated.mark_written_over(Some(meta("1881310893", "")));
+        assert_eq!(generated.setting_id, "PFUS123");
+        assert_eq!(generated.base_id, "GFSA00");
+        assert_eq!(generated.sync_state(), SyncState::Update);
+
+        let mut flattened = ProfileMetadata::default();
+        flattened.mark_written_over(Some(meta("1881310893", "create")));
+        assert_eq!(flattened.setting_id, "PFUS123");
+        assert_eq!(flattened.user_id, "1881310893");
+        assert_eq!(flattened.base_id, "GFSA04");
+        assert_eq!(flattened.sync_state(), SyncState::Create);
+
+        let mut new = meta("1881310893", "");
+        new.mark_written_over(None);
+        assert_eq!(new.setting_id, "PFUS123");
+        assert_eq!(new.sync_state(), SyncState::Create);
+    }
+
+    #[test]
+    fn test_account_warning() {
+        let profile = meta("1881310893", "");
+        assert!(profile.account_warning(Some("1881310893")).is_none());
+        assert!(profile.account_warning(None).is_none());
+        assert!(profile
+            .account_warning(Some("2222222222"))
+            .unwrap()
+            .contains("1881310893"));
+        assert!(meta("", "").account_warning(Some("2222222222")).is_none());
+    }
+}
@@ -1,27 +1,106 @@
+use std::marker::PhantomData;
+
 use anyhow::Result;
 use serde::Serialize;
 use serde_json::ser::{PrettyFormatter, Serializer};
 use serde_json::{Map, Value};
 
-/// A Bambu Studio filament profile.
+/// A kind of Bambu Studio preset.
+///
+/// Filament, process (print) and machine (printer) presets share the same
+/// JSON layout, `inherits`/`include` resolution and `.info` companion files;
+/// they differ only in which folder they live in and which key carries the
+/// display identifier. The marker types below let registries and helpers be
+/// written once while keeping the kinds from being mixed up.
+pub trait PresetKind {
+    /// Folder name under `system/<vendor>/` and `user/<preset_folder>/`.
+    const FOLDER: &'static str;
+    /// Key holding the preset's display identifier
+    /// (`filament_settings_id`, `print_settings_id`, `printer_settings_id`).
+    const SETTINGS_ID_KEY: &'static str;
+    /// Fields worth showing in list and detail views.
+    const SUMMARY_KEYS: &'static [&'static str];
+}
+
+/// Marker for filament presets (`filament/`).
+pub struct Filament;
+
+/// Marker for process (print) presets (`process/`).
+pub struct Process;
+
+/// Marker for machine (printer) presets (`machine/`).
+pub struct Machine;
+
+impl PresetKind for Filament {
+    const FOLDER: &'static str = "filament";
+    const SETTINGS_ID_KEY: &'static str = "filament_settings_id";
+    const SUMMARY_KEYS: &'static [&'static str] = &[
+        "filament_type",
+        "filament_vendor",
+        "nozzle_temperature",
+        "hot_plate_temp",
+        "filament_max_volumetric_speed",
+    ];
+}
+
+impl PresetKind for Process {
+    const FOLDER: &'static str = "process";
+    const SETTINGS_ID_KEY: &'static str = "print_settings_id";
+    const SUMMARY_KEYS: &'static [&'static str] = &[
+        "layer_height",
+        "initial_layer_print_height",
+        "wall_loops",
+        "sparse_infill_density",
+        "outer_wall_speed",
+        "inner_wall_speed",
+        "sparse_infill_speed",
+    ];
+}
+
+impl PresetKind for Machine {
+    const FOLDER: &'static str = "machine";
+    const SETTINGS_ID_KEY: &'static str = "printer_settings_id";
+    const SUMMARY_KEYS: &'static [&'static str] = &[
+        "printer_model",
+        "printer_variant",
+        "nozzle_diameter",
+        "nozzle_type",
+        "printable_height",
+    ];
+}
+
+/// A Bambu Studio preset of kind `K`.
 ///
 /// Wraps the raw JSON `Map<String, Value>` to preserve ALL fields (139+)
 /// without needing a typed struct for every field. Typed accessors are
 /// provided for the fields BambuMate actively manipulates.
-pub struct FilamentProfile {
+pub struct Preset<K: PresetKind> {
     data: Map<String, Value>,
+    kind: PhantomData<K>,
 }
 
-impl FilamentProfile {
-    /// Parse a filament profile from a JSON string.
+/// A Bambu Studio filament profile.
+pub type FilamentProfile = Preset<Filament>;
+
+/// A Bambu Studio process (print settings) preset.
+pub type ProcessPreset = Preset<Process>;
+
+/// A Bambu Studio machine (printer settings) preset.
+pub type MachinePreset = Preset<Machine>;
+
+impl<K: PresetKind> Preset<K> {
+    /// Parse a preset from a JSON string.
     pub fn from_json(json: &str) -> Result<Self> {
         let data: Map<String, Value> = serde_json::from_str(json)?;
-        Ok(Self { data })
+        Ok(Self::from_map(data))
     }
 
-    /// Construct a FilamentProfile from an existing Map.
+    /// Construct a preset from an existing Map.
     pub fn from_map(data: Map<String, Value>) -> Self {
-        Self { data }
+        Self {
+            data,
+            kind: PhantomData,
+        }
     }
 
     /// Serialize to JSON string with 4-space indentation (matching Bambu Studio format).
@@ -51,21 +130,6 @@ impl FilamentProfile {
         self.data.get("inherits")?.as_str()
     }
 
-    /// Filament material identifier (bare string field).
-    pub fn filament_id(&self) -> Option<&str> {
-        self.data.get("filament_id")?.as_str()
-    }
-
-    /// Filament type (e.g., "PLA", "ABS") -- first element of array field.
-    pub fn filament_type(&self) -> Option<&str> {
-        self.get_first_array_value("filament_type")
-    }
-
-    /// Nozzle temperature(s) -- array field with one element per extruder.
-    pub fn nozzle_temperature(&self) -> Option<Vec<&str>> {
-        self.get_string_array("nozzle_temperature")
-    }
-
     /// Compatible printers -- array of printer+nozzle strings.
     pub fn compatible_printers(&self) -> Option<Vec<&str>> {
         self.get_string_array("compatible_printers")
@@ -76,9 +140,12 @@ impl FilamentProfile {
         self.data.get("setting_id")?.as_str()
     }
 
-    /// Filament settings ID -- array field used as display identifier.
-    pub fn filament_settings_id(&self) -> Option<Vec<&str>> {
-        self.get_string_array("filament_settings_id")
+    /// Whether Bambu Studio offers this preset for selection.
+    ///
+    /// Templates such as `fdm_filament_common` set `instantiation` to
+    /// `"false"`; a missing field counts as instantiable.
+    pub fn is_instantiable(&self) -> bool {
+        self.data.get("instantiation").and_then(|v| v.as_str()) != Some("false")
     }
 
     // --- Helpers ---
@@ -88,6 +155,16 @@ impl FilamentProfile {
         self.data.get(key)?.as_array()?.first()?.as_str()
     }
 
+    /// Get a field as a single string: a bare string field, or the first
+    /// element of an array field. Process presets mix both forms.
+    pub fn get_scalar(&self, key: &str) -> Option<&str> {
+        match self.data.get(key)? {
+            Value::String(s) => Some(s),
+            Value::Array(arr) => arr.first()?.as_str(),
+            _ => None,
+        }
+    }
+
     /// Get all elements of a string array field.
     pub fn get_string_array(&self, key: &str) -> Option<Vec<&str>> {
         self.data
@@ -129,6 +206,70 @@ impl FilamentProfile {
     }
 }
 
+impl Preset<Filament> {
+    /// Filament material identifier (bare string field).
+    pub fn filament_id(&self) -> Option<&str> {
+        self.data.get("filament_id")?.as_str()
+    }
+
+    /// Filament type (e.g., "PLA", "ABS") -- first element of array field.
+    pub fn filament_type(&self) -> Option<&str> {
+        self.get_first_array_value("filament_type")
+    }
+
+    /// Nozzle temperature(s) -- array field with one element per extruder.
+    pub fn nozzle_temperature(&self) -> Option<Vec<&str>> {
+        self.get_string_array("nozzle_temperature")
+    }
+
+    /// Filament settings ID -- array field used as display identifier.
+    pub fn filament_settings_id(&self) -> Option<Vec<&str>> {
+        self.get_string_array("filament_settings_id")
+    }
+
+    /// Recommended nozzle temperature range `(low, high)` for the first
+    /// extruder, from `nozzle_temperature_range_low`/`_high`.
+    pub fn nozzle_temperature_range(&self) -> Option<(u16, u16)> {
+        let low = self.get_first_array_value("nozzle_temperature_range_low")?;
+        let high = self.get_first_array_value("nozzle_temperature_range_high")?;
+        Some((low.trim().parse().ok()?, high.trim().parse().ok()?))
+    }
+
+    /// Display colour (`default_filament_colour`, e.g. "#F2754E"), if set.
+    pub fn default_colour(&self) -> Option<&str> {
+        self.get_first_array_value("default_filament_colour")
+            .filter(|c| !c.is_empty())
+    }
+}
+
+impl Preset<Process> {
+    /// Layer height in mm (e.g. "0.2").
+    pub fn layer_height(&self) -> Option<&str> {
+        self.get_scalar("layer_height")
+    }
+}
+
+impl Preset<Machine> {
+    /// Printer model this preset belongs to (e.g. "Bambu Lab X1 Carbon").
+    pub fn printer_model(&self) -> Option<&str> {
+        self.get_scalar("printer_model")
+    }
+
+    /// Nozzle diameter(s) -- array field with one element per extruder.
+    pub fn nozzle_diameter(&self) -> Option<Vec<&str>> {
+        self.get_string_array("nozzle_diameter")
+    }
+
+    /// Number of extruders, taken from the length of `nozzle_diameter`.
+    /// Returns None when the field is absent (e.g. on an unresolved preset).
+    pub fn extruder_count(&self) -> Option<usize> {
+        self.data
+            .get("nozzle_diameter")?
+            .as_array()
+            .map(|a| a.len())
+    }
+}
+
 /// Metadata from a `.info` companion file (user profiles only).
 ///
 /// Format is INI-like with `key = value` lines.
@@ -1,18 +1,19 @@
 use anyhow::Result;
 use chrono::Utc;
+use serde::{Deserialize, Serialize};
 use std::io::Write;
 use std::path::{Path, PathBuf};
 use tempfile::NamedTempFile;
 use tracing::{debug, info, warn};
 
-use super::types::{FilamentProfile, ProfileMetadata};
+use super::types::{Preset, PresetKind, ProfileMetadata};
 
-/// Write a filament profile to disk atomically.
+/// Write a profile (filament, process or machine preset) to disk atomically.
 ///
 /// Uses a temporary file in the same directory as `target_path`, writes
 /// the JSON content, then atomically renames the temp file to the target.
 /// This guarantees that an interrupted write never leaves a partial file.
-pub fn write_profile_atomic(profile: &FilamentProfile, target_path: &Path) -> Result<()> {
+pub fn write_profile_atomic<K: PresetKind>(profile: &Preset<K>, target_path: &Path) -> Result<()> {
     let json = profile.to_json_4space()?;
 
     let parent = target_path
@@ -61,8 +62,8 @@ pub fn write_profile_metadata_atomic(metadata: &ProfileMetadata, target_path: &P
 /// The metadata file path is derived from `json_path` by changing the
 /// extension to `.info`. If the metadata write fails, the JSON file
 /// is kept (a valid profile with stale metadata is better than no profile).
-pub fn write_profile_with_metadata(
-    profile: &FilamentProfile,
+pub fn write_profile_with_metadata<K: PresetKind>(
+    profile: &Preset<K>,
     json_path: &Path,
     metadata: &ProfileMetadata,
 ) -> Result<()> {
@@ -83,6 +84,72 @@ pub fn write_profile_with_metadata(
     Ok(())
 }
 
+/// Write an edited user profile in place and record the edit in its `.info`
+/// file (see `profile::sync`), so Bambu Studio uploads the change instead
+/// of restoring the cloud copy over it.
+///
+/// A profile without an `.info` file is just written. As with
+/// `write_profile_with_metadata`, a failed metadata write is logged and
+/// the written JSON kept.
+pub fn write_edited_profile<K: PresetKind>(profile: &Preset<K>, json_path: &Path) -> Result<()> {
+    write_profile_atomic(profile, json_path)?;
+    if let Err(e) = mark_profile_modified(json_path) {
+        warn!(
+            "Failed to update sync metadata for {:?}: {}. Profile JSON was written successfully.",
+            json_path, e
+        );
+    }
+    Ok(())
+}
+
+/// Mark the profile at `json_path` as changed locally in its `.info` file,
+/// if it has one.
+pub fn mark_profile_modified(json_path: &Path) -> Result<()> {
+    if let Some(mut metadata) = super::reader::read_profile_metadata(json_path)? {
+        metadata.mark_modified();
+        write_profile_metadata_atomic(&metadata, &json_path.with_extension("info"))?;
+    }
+    Ok(())
+}
+
+/// Delete a user profile and its `.info` file.
+///
+/// A profile with a copy in Bambu cloud keeps its `.info` file, marked for
+/// deletion, so Bambu Studio deletes the cloud copy on its next sync
+/// instead of downloading it again.
+pub fn delete_profile_files(json_path: &Path) -> Result<()> {
+    let tombstone = deletion_tombstone(json_path);
+    std::fs::remove_file(json_path)?;
+
+    let info_path = json_path.with_extension("info");
+    match tombstone {
+        Some(metadata) => {
+            write_profile_metadata_atomic(&metadata, &info_path)?;
+            info!("Marked {:?} for deletion from Bambu cloud", json_path);
+        }
+        None => {
+            if info_path.exists() {
+                if let Err(e) = std::fs::remove_file(&info_path) {
+                    info!("Could not delete companion .info file: {}", e);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The `.info` content to leave behind when deleting the profile at
+/// `json_path`, if it has a copy in Bambu cloud.
+fn deletion_tombstone(json_path: &Path) -> Option<ProfileMetadata> {
+    let metadata = super::reader::read_profile_metadata(json_path).unwrap_or_else(|e| {
+        warn!("Ignoring unreadable metadata for {:?}: {}", json_path, e);
+        None
+    });
+    let mut metadata = metadata.filter(|m| m.is_in_cloud())?;
+    metadata.mark_deleted();
+    Some(metadata)
+}
+
 /// Create a timestamped backup of a profile before modification.
 /// Returns the backup path on success.
 ///
@@ -113,10 +180,11 @@ pub fn backup_profile(profile_path: &Path) -> Result<PathBuf> {
 
 /// Restore a profile from a backup file.
 ///
-/// Reads the backup profile and atomically writes it to the target profile path.
+/// Reads the backup profile and atomically writes it to the target profile
+/// path, recording the change for cloud sync as any other edit.
 pub fn restore_from_backup(backup_path: &Path, profile_path: &Path) -> Result<()> {
     let backup_profile = super::reader::read_profile(backup_path)?;
-    write_profile_atomic(&backup_profile, profile_path)?;
+    write_edited_profile(&backup_profile, profile_path)?;
     info!("Restored profile from {:?}", backup_path);
     Ok(())
 }
@@ -139,13 +207,40 @@ pub fn restore_from_backup(backup_path: &Path, profile_path: &Path) -> Result<()
 /// on next full rescan).
 pub fn register_filament_in_conf(config_root: &Path, profile_name: &str) -> Result<()> {
     let conf_path = config_root.join("BambuStudio.conf");
+    let Some(output) = conf_with_filament_changes(config_root, &[profile_name], &[])? else {
+        return Ok(());
+    };
+
+    // Safety net: keep a one-time `.conf.bak` alongside the conf so users can
+    // recover if a future write ever corrupts it. Only created if missing.
+    backup_conf_once(&conf_path);
+
+    // Write atomically using temp file
+    let mut temp = NamedTempFile::new_in(config_root)?;
+    temp.write_all(output.as_bytes())?;
+    temp.flush()?;
+    temp.persist(&conf_path)?;
+
+    info!("Updated BambuStudio.conf at {:?}", conf_path);
+    Ok(())
+}
+
+/// The content BambuStudio.conf should have with `add` added to its
+/// "filaments" array and `remove` taken out of it, or `None` if there's
+/// nothing to write: the conf doesn't exist, or already lists exactly that.
+fn conf_with_filament_changes(
+    config_root: &Path,
+    add: &[&str],
+    remove: &[&str],
+) -> Result<Option<String>> {
+    let conf_path = config_root.join("BambuStudio.conf");
 
     if !conf_path.exists() {
         warn!(
             "BambuStudio.conf not found at {:?}, skipping filament registration",
             conf_path
         );
-        return Ok(());
+        return Ok(None);
     }
 
     // Read the conf file
@@ -170,55 +265,407 @@ pub fn register_filament_in_conf(config_root: &Path, profile_name: &str) -> Resu
         .as_array_mut()
         .ok_or_else(|| anyhow::anyhow!("BambuStudio.conf 'filaments' section is not an array"))?;
 
-    // Check if the profile name is already registered
-    let name_value = serde_json::Value::String(profile_name.to_string());
-    if filaments_arr.contains(&name_value) {
-        debug!(
-            "Profile '{}' already registered in BambuStudio.conf filaments section",
+    let before = filaments_arr.len();
+    filaments_arr.retain(|v| !v.as_str().is_some_and(|name| remove.contains(&name)));
+    let mut changed = filaments_arr.len() != before;
+    if changed {
+        info!(
+            "Unregistered {:?} from BambuStudio.conf filaments section",
+            remove
+        );
+    }
+
+    for profile_name in add {
+        // Check if the profile name is already registered
+        let name_value = serde_json::Value::String(profile_name.to_string());
+        if filaments_arr.contains(&name_value) {
+            debug!(
+                "Profile '{}' already registered in BambuStudio.conf filaments section",
+                profile_name
+            );
+            continue;
+        }
+
+        // Add the profile name to the filaments array
+        filaments_arr.push(name_value);
+        changed = true;
+        info!(
+            "Registered profile '{}' in BambuStudio.conf filaments section",
             profile_name
         );
-        return Ok(());
+    }
+    if !changed {
+        return Ok(None);
     }
 
-    // Add the profile name to the filaments array
-    filaments_arr.push(name_value);
-    info!(
-        "Registered profile '{}' in BambuStudio.conf filaments section",
-        profile_name
-    );
+    // serde_json::to_string_pretty uses 2-space indentation
+    let json = serde_json::to_string_pretty(&conf)?;
+    let output = format!("{}\n", json);
 
-    // Safety net: keep a one-time `.conf.bak` alongside the conf so users can
-    // recover if a future write ever corrupts it. Only created if missing.
+    // On Windows, BambuStudio expects an MD5 checksum appended after the JSON
+    #[cfg(target_os = "windows")]
+    let output = output + &compute_md5_checksum_line(&json);
+
+    Ok(Some(output))
+}
+
+fn backup_conf_once(conf_path: &Path) {
     let backup_path = conf_path.with_extension("conf.bak");
     if !backup_path.exists() {
-        if let Err(e) = std::fs::copy(&conf_path, &backup_path) {
-            warn!(
-                "Failed to create BambuStudio.conf.bak safety backup: {}",
-                e
-            );
+        if let Err(e) = std::fs::copy(conf_path, &backup_path) {
+            warn!("Failed to create BambuStudio.conf.bak safety backup: {}", e);
         }
     }
+}
 
-    // Write back (serde_json::to_string_pretty uses 2-space indentation)
-    let output = serde_json::to_string_pretty(&conf)?;
+/// Hidden folder, in the user filament directory, holding the journals of
+/// installs in progress.
+pub const INSTALL_JOURNAL_DIR: &str = ".bambumate-journal";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum JournalState {
+    /// Files are being staged; nothing has been replaced yet.
+    Staged,
+    /// Staged files are being moved into place.
+    Committing,
+    /// Every file is in place; only the backups are left to remove.
+    Committed,
+}
 
-    // Write atomically using temp file
-    let mut temp = NamedTempFile::new_in(config_root)?;
-    temp.write_all(output.as_bytes())?;
-    temp.write_all(b"\n")?;
+#[derive(Debug, Serialize, Deserialize)]
+struct JournalEntry {
+    target: PathBuf,
+    staged: PathBuf,
+    backup: PathBuf,
+    /// Whether `target` existed when the commit started.
+    had_original: bool,
+    /// Remove `target` instead of replacing it; there is no staged file.
+    #[serde(default)]
+    remove: bool,
+}
 
-    // On Windows, BambuStudio expects an MD5 checksum appended after the JSON
-    #[cfg(target_os = "windows")]
-    {
-        let md5_line = compute_md5_checksum_line(&output);
-        temp.write_all(md5_line.as_bytes())?;
+/// The on-disk record of an [`InstallTransaction`], enough to finish or
+/// undo it after a crash.
+#[derive(Debug, Serialize, Deserialize)]
+struct Journal {
+    id: String,
+    state: JournalState,
+    entries: Vec<JournalEntry>,
+}
+
+impl Journal {
+    fn write(&self, path: &Path) -> Result<()> {
+        let parent = path
+            .parent()
+            .ok_or_else(|| anyhow::anyhow!("Journal path has no parent directory: {:?}", path))?;
+        let mut temp = NamedTempFile::new_in(parent)?;
+        temp.write_all(serde_json::to_string_pretty(self)?.as_bytes())?;
+        temp.as_file().sync_all()?;
+        temp.persist(path)?;
+        Ok(())
     }
 
-    temp.flush()?;
-    temp.persist(&conf_path)?;
+    /// Put every target back the way it was before the transaction.
+    ///
+    /// Safe to repeat, and to run on a commit that stopped part way: an
+    /// entry whose target was replaced has its backup moved back, and a
+    /// target that didn't exist before is removed once its staged file has
+    /// been moved into place.
+    fn roll_back(&self) -> Result<()> {
+        for entry in self.entries.iter().rev() {
+            if self.state != JournalState::Staged {
+                if entry.backup.exists() {
+                    std::fs::rename(&entry.backup, &entry.target)?;
+                } else if !entry.remove
+                    && !entry.had_original
+                    && !entry.staged.exists()
+                    && entry.target.exists()
+                {
+                    std::fs::remove_file(&entry.target)?;
+                }
+            }
+            if entry.staged.exists() {
+                std::fs::remove_file(&entry.staged)?;
+            }
+        }
+        Ok(())
+    }
 
-    info!("Updated BambuStudio.conf at {:?}", conf_path);
-    Ok(())
+    /// Remove the backups of a committed transaction.
+    fn clean_up(&self) {
+        for entry in &self.entries {
+            if entry.backup.exists() {
+                if let Err(e) = std::fs::remove_file(&entry.backup) {
+                    warn!("Failed to remove install backup {:?}: {}", entry.backup, e);
+                }
+            }
+        }
+    }
+}
+
+/// A set of files installed (or removed) together: all of them or none.
+///
+/// Each file is first written to a hidden staged copy beside its target.
+/// [`InstallTransaction::commit`] then moves the originals aside and the
+/// staged copies into place, and puts the originals back if any step
+/// fails. Every step is recorded in a journal in `journal_dir` first, so
+/// [`recover_install_journals`] can finish or undo a transaction cut short
+/// by a crash or power loss. Dropping an uncommitted transaction discards
+/// it.
+pub struct InstallTransaction {
+    journal: Journal,
+    journal_path: PathBuf,
+    finished: bool,
+}
+
+impl InstallTransaction {
+    pub fn begin(journal_dir: &Path) -> Result<Self> {
+        std::fs::create_dir_all(journal_dir)?;
+        let id = format!(
+            "{}-{:08x}",
+            Utc::now().format("%Y%m%d%H%M%S"),
+            rand::random::<u32>()
+        );
+        let journal_path = journal_dir.join(format!("{}.journal", id));
+        Ok(Self {
+            journal: Journal {
+                id,
+                state: JournalState::Staged,
+                entries: Vec::new(),
+            },
+            journal_path,
+            finished: false,
+        })
+    }
+
+    /// Stage `contents` to replace (or create) `target` on commit.
+    pub fn stage(&mut self, target: &Path, contents: &[u8]) -> Result<()> {
+        let staged = self.add_entry(target, false)?;
+
+        // Staged files sit beside their target; add_entry checked it has a parent
+        let parent = staged.parent().unwrap_or(Path::new("."));
+        std::fs::create_dir_all(parent)?;
+        let mut temp = NamedTempFile::new_in(parent)?;
+        temp.write_all(contents)?;
+        temp.as_file().sync_all()?;
+        temp.persist(&staged)?;
+
+        debug!("Staged {:?} for install", target);
+        Ok(())
+    }
+
+    /// Stage the removal of `target` on commit. A missing target is left
+    /// alone.
+    pub fn stage_removal(&mut self, target: &Path) -> Result<()> {
+        self.add_entry(target, true)?;
+        debug!("Staged {:?} for removal", target);
+        Ok(())
+    }
+
+    /// Journal an entry for `target` and return the path of its staged file.
+    fn add_entry(&mut self, target: &Path, remove: bool) -> Result<PathBuf> {
+        if self.journal.entries.iter().any(|e| e.target == target) {
+            anyhow::bail!("{:?} is already staged in this install", target);
+        }
+        let parent = target
+            .parent()
+            .ok_or_else(|| anyhow::anyhow!("Target path has no parent directory: {:?}", target))?;
+        let name = target
+            .file_name()
+            .and_then(|n| n.to_str())
+            .ok_or_else(|| anyhow::anyhow!("Invalid target path: {:?}", target))?;
+        let entry = JournalEntry {
+            target: target.to_path_buf(),
+            staged: parent.join(format!(".{}.{}.staged", name, self.journal.id)),
+            backup: parent.join(format!(".{}.{}.orig", name, self.journal.id)),
+            had_original: false,
+            remove,
+        };
+        let staged = entry.staged.clone();
+
+        // Journal the staged file before creating it, so it can't be orphaned
+        self.journal.entries.push(entry);
+        self.journal.write(&self.journal_path)?;
+        Ok(staged)
+    }
+
+    /// Stage a profile's JSON.
+    pub fn stage_profile<K: PresetKind>(
+        &mut self,
+        profile: &Preset<K>,
+        json_path: &Path,
+    ) -> Result<()> {
+        self.stage(json_path, profile.to_json_4space()?.as_bytes())
+    }
+
+    /// Stage a profile's `.info` file, next to `json_path`.
+    pub fn stage_metadata(&mut self, metadata: &ProfileMetadata, json_path: &Path) -> Result<()> {
+        self.stage(
+            &json_path.with_extension("info"),
+            metadata.to_info_string().as_bytes(),
+        )
+    }
+
+    /// Stage an edit to a user profile, with its `.info` file marked
+    /// modified as [`write_edited_profile`] does.
+    pub fn stage_edited_profile<K: PresetKind>(
+        &mut self,
+        profile: &Preset<K>,
+        json_path: &Path,
+    ) -> Result<()> {
+        self.stage_profile(profile, json_path)?;
+        if let Some(mut metadata) = super::reader::read_profile_metadata(json_path)? {
+            metadata.mark_modified();
+            self.stage_metadata(&metadata, json_path)?;
+        }
+        Ok(())
+    }
+
+    /// Stage the deletion of a user profile and its `.info` file, keeping
+    /// a cloud deletion marker as [`delete_profile_files`] does.
+    pub fn stage_profile_deletion(&mut self, json_path: &Path) -> Result<()> {
+        let tombstone = deletion_tombstone(json_path);
+        self.stage_removal(json_path)?;
+        let info_path = json_path.with_extension("info");
+        match tombstone {
+            Some(metadata) => self.stage_metadata(&metadata, json_path),
+            None if info_path.exists() => self.stage_removal(&info_path),
+            None => Ok(()),
+        }
+    }
+
+    /// Stage BambuStudio.conf with `profile_names` added to its "filaments"
+    /// array, as [`register_filament_in_conf`] does. Nothing is staged if
+    /// the conf doesn't exist or already lists every name.
+    pub fn stage_conf_registration(
+        &mut self,
+        config_root: &Path,
+        profile_names: &[&str],
+    ) -> Result<()> {
+        self.stage_conf_update(config_root, profile_names, &[])
+    }
+
+    /// Stage BambuStudio.conf with `add` registered in its "filaments"
+    /// array and `remove` unregistered from it.
+    pub fn stage_conf_update(
+        &mut self,
+        config_root: &Path,
+        add: &[&str],
+        remove: &[&str],
+    ) -> Result<()> {
+        let Some(output) = conf_with_filament_changes(config_root, add, remove)? else {
+            return Ok(());
+        };
+        let conf_path = config_root.join("BambuStudio.conf");
+        backup_conf_once(&conf_path);
+        self.stage(&conf_path, output.as_bytes())
+    }
+
+    /// Move every staged file into place, or none of them.
+    pub fn commit(mut self) -> Result<()> {
+        for entry in &mut self.journal.entries {
+            entry.had_original = entry.target.exists();
+        }
+        self.journal.state = JournalState::Committing;
+        self.journal.write(&self.journal_path)?;
+
+        for entry in &self.journal.entries {
+            if entry.had_original {
+                std::fs::rename(&entry.target, &entry.backup)?;
+            }
+            if !entry.remove {
+                std::fs::rename(&entry.staged, &entry.target)?;
+            }
+        }
+
+        self.journal.state = JournalState::Committed;
+        self.journal.write(&self.journal_path)?;
+
+        self.finished = true;
+        self.journal.clean_up();
+        if let Err(e) = std::fs::remove_file(&self.journal_path) {
+            warn!(
+                "Failed to remove install journal {:?}: {}",
+                self.journal_path, e
+            );
+        }
+        info!(
+            "Installed {} files (transaction {})",
+            self.journal.entries.len(),
+            self.journal.id
+        );
+        Ok(())
+    }
+}
+
+impl Drop for InstallTransaction {
+    /// Roll back a transaction that was dropped or failed before committing.
+    fn drop(&mut self) {
+        if self.finished {
+            return;
+        }
+        match self.journal.roll_back() {
+            Ok(()) => {
+                if self.journal_path.exists() {
+                    let _ = std::fs::remove_file(&self.journal_path);
+                }
+                if self.journal.state != JournalState::Staged {
+                    warn!("Rolled back install transaction {}", self.journal.id);
+                }
+            }
+            // The journal stays for recover_install_journals to retry
+            Err(e) => warn!(
+                "Failed to roll back install transaction {}: {}",
+                self.journal.id, e
+            ),
+        }
+    }
+}
+
+/// Finish or undo the install transactions left in `journal_dir` by a
+/// previous run that didn't complete them. Transactions that had moved
+/// every file into place are finished; the rest are rolled back.
+///
+/// Returns a description of each recovered transaction.
+pub fn recover_install_journals(journal_dir: &Path) -> Result<Vec<String>> {
+    if !journal_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut recovered = Vec::new();
+    for entry in std::fs::read_dir(journal_dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("journal") {
+            continue;
+        }
+        let journal: Journal = match std::fs::read_to_string(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|s| serde_json::from_str(&s).map_err(anyhow::Error::from))
+        {
+            Ok(journal) => journal,
+            Err(e) => {
+                warn!("Skipping unreadable install journal {:?}: {}", path, e);
+                continue;
+            }
+        };
+
+        let outcome = if journal.state == JournalState::Committed {
+            journal.clean_up();
+            "finished"
+        } else {
+            journal.roll_back()?;
+            "rolled back"
+        };
+        std::fs::remove_file(&path)?;
+        info!("Recovered install transaction {}: {}", journal.id, outcome);
+        recovered.push(format!(
+            "Install {} ({} files) {}",
+            journal.id,
+            journal.entries.len(),
+            outcome
+        ));
+    }
+    Ok(recovered)
 }
 
 /// Strip the MD5 checksum comment line that BambuStudio appends on Windows.
@@ -413,4 +860,122 @@ mod tests {
         let parsed: serde_json::Value = serde_json::from_str(json_slice).unwrap();
         assert_eq!(parsed["filaments"].as_array().unwrap().len(), 1);
     }
+
+    fn read_filaments(config_root: &Path) -> Vec<String> {
+        let content = std::fs::read_to_string(config_root.join("BambuStudio.conf")).unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(strip_md5_checksum(&content)).unwrap();
+        parsed["filaments"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|v| v.as_str().unwrap().to_string())
+            .collect()
+    }
+
+    /// Files left in `dir` other than the journal folder.
+    fn dir_names(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .filter(|n| n != INSTALL_JOURNAL_DIR)
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_install_transaction_commits_every_file() {
+        let dir = TempDir::new().unwrap();
+        let journal_dir = dir.path().join(INSTALL_JOURNAL_DIR);
+        std::fs::write(dir.path().join("BambuStudio.conf"), "{\"filaments\": []}").unwrap();
+        let json_path = create_test_profile_file(dir.path(), "PLA.json");
+
+        let mut tx = InstallTransaction::begin(&journal_dir).unwrap();
+        tx.stage(&json_path, b"new").unwrap();
+        tx.stage(&dir.path().join("PLA.info"), b"sync_info = create")
+            .unwrap();
+        tx.stage_conf_registration(dir.path(), &["PLA"]).unwrap();
+        // Nothing is visible before the commit
+        assert!(std::fs::read_to_string(&json_path)
+            .unwrap()
+            .contains("test123"));
+        assert!(read_filaments(dir.path()).is_empty());
+        tx.commit().unwrap();
+
+        assert_eq!(std::fs::read_to_string(&json_path).unwrap(), "new");
+        assert_eq!(read_filaments(dir.path()), vec!["PLA"]);
+        assert_eq!(
+            dir_names(dir.path()),
+            vec![
+                "BambuStudio.conf",
+                "BambuStudio.conf.bak",
+                "PLA.info",
+                "PLA.json"
+            ]
+        );
+        assert_eq!(std::fs::read_dir(&journal_dir).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_install_transaction_rolls_back_when_dropped() {
+        let dir = TempDir::new().unwrap();
+        let journal_dir = dir.path().join(INSTALL_JOURNAL_DIR);
+        let json_path = create_test_profile_file(dir.path(), "PLA.json");
+
+        let mut tx = InstallTransaction::begin(&journal_dir).unwrap();
+        tx.stage(&json_path, b"new").unwrap();
+        tx.stage(&dir.path().join("PLA.info"), b"info").unwrap();
+        // A conf that can't be parsed fails the install part way
+        std::fs::write(dir.path().join("BambuStudio.conf"), "not json").unwrap();
+        assert!(tx.stage_conf_registration(dir.path(), &["PLA"]).is_err());
+        drop(tx);
+
+        assert!(std::fs::read_to_string(&json_path)
+            .unwrap()
+            .contains("test123"));
+        assert_eq!(dir_names(dir.path()), vec!["BambuStudio.conf", "PLA.json"]);
+        assert_eq!(std::fs::read_dir(&journal_dir).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_recover_install_journals_undoes_interrupted_commit() {
+        let dir = TempDir::new().unwrap();
+        let journal_dir = dir.path().join(INSTALL_JOURNAL_DIR);
+        let json_path = create_test_profile_file(dir.path(), "PLA.json");
+        let info_path = dir.path().join("PLA.info");
+
+        let mut tx = InstallTransaction::begin(&journal_dir).unwrap();
+        tx.stage(&json_path, b"new").unwrap();
+        tx.stage(&info_path, b"info").unwrap();
+
+        // Simulate a crash after the first file was moved into place
+        let mut journal = Journal {
+            id: tx.journal.id.clone(),
+            state: JournalState::Committing,
+            entries: Vec::new(),
+        };
+        for entry in &tx.journal.entries {
+            journal.entries.push(JournalEntry {
+                target: entry.target.clone(),
+                staged: entry.staged.clone(),
+                backup: entry.backup.clone(),
+                had_original: entry.target.exists(),
+                remove: entry.remove,
+            });
+        }
+        journal.write(&tx.journal_path).unwrap();
+        std::fs::rename(&journal.entries[0].target, &journal.entries[0].backup).unwrap();
+        std::fs::rename(&journal.entries[0].staged, &journal.entries[0].target).unwrap();
+        std::mem::forget(tx);
+        assert_eq!(std::fs::read_to_string(&json_path).unwrap(), "new");
+
+        let recovered = recover_install_journals(&journal_dir).unwrap();
+        assert_eq!(recovered.len(), 1);
+        assert!(recovered[0].contains("rolled back"));
+        assert!(std::fs::read_to_string(&json_path)
+            .unwrap()
+            .contains("test123"));
+        assert_eq!(dir_names(dir.path()), vec!["PLA.json"]);
+        assert!(recover_install_journals(&journal_dir).unwrap().is_empty());
+    }
 }
@@ -0,0 +1,134 @@
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+use tracing::{info, warn};
+
+use crate::profile::conflict::ProfileVersion;
+
+/// Tauri event emitted when a user filament profile changes on disk.
+pub const PROFILE_CHANGED_EVENT: &str = "profile-changed";
+
+/// Payload of [`PROFILE_CHANGED_EVENT`].
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfileChange {
+    pub path: String,
+    /// "created", "modified" or "removed".
+    pub kind: &'static str,
+    /// Version token of the file now, or `None` if it's gone. Lets the
+    /// frontend ignore events for writes it made itself.
+    pub version: Option<String>,
+}
+
+/// Recover a `MutexGuard` even if the mutex has been poisoned by a prior
+/// panic, as `stl_watcher` does.
+fn lock_recover<T>(m: &Mutex<T>) -> MutexGuard<'_, T> {
+    m.lock().unwrap_or_else(|p| p.into_inner())
+}
+
+/// Shared state for the user filament directory watcher.
+#[derive(Default)]
+pub struct ProfileWatcherState {
+    pub watcher: Mutex<Option<RecommendedWatcher>>,
+    pub watch_dir: Mutex<Option<PathBuf>>,
+}
+
+impl ProfileWatcherState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Watch `dir` for profiles created, changed or deleted outside
+    /// BambuMate (or by it). Each change drops the base-profile index and
+    /// emits [`PROFILE_CHANGED_EVENT`].
+    pub fn start_watching(&self, app: AppHandle, dir: &Path) -> Result<(), String> {
+        if !dir.is_dir() {
+            return Err(format!("Directory does not exist: {:?}", dir));
+        }
+
+        self.stop_watching();
+
+        // Some backends (FSEvents) report canonical paths
+        let roots = [
+            dir.to_path_buf(),
+            dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf()),
+        ];
+        let mut watcher =
+            notify::recommended_watcher(move |res: Result<Event, notify::Error>| match res {
+                Ok(event) => {
+                    let kind = match event.kind {
+                        EventKind::Create(_) => "created",
+                        EventKind::Modify(_) => "modified",
+                        EventKind::Remove(_) => "removed",
+                        _ => return,
+                    };
+                    let paths = event
+                        .paths
+                        .iter()
+                        .filter(|p| roots.iter().any(|root| is_profile_file(root, p)));
+                    for path in paths {
+                        let _ = crate::commands::profile::refresh_base_profile_index();
+                        let version = ProfileVersion::of_file(path).ok().map(|v| v.token());
+                        let change = ProfileChange {
+                            path: path.to_string_lossy().to_string(),
+                            kind: if version.is_none() { "removed" } else { kind },
+                            version,
+                        };
+                        if let Err(e) = app.emit(PROFILE_CHANGED_EVENT, change) {
+                            warn!("Failed to emit profile change: {}", e);
+                        }
+                    }
+                }
+                Err(e) => {
+                    warn!("Profile watcher error: {}", e);
+                }
+            })
+            .map_err(|e| format!("Failed to create profile watcher: {}", e))?;
+
+        watcher
+            .watch(dir, RecursiveMode::Recursive)
+            .map_err(|e| format!("Failed to watch directory: {}", e))?;
+
+        *lock_recover(&self.watcher) = Some(watcher);
+        *lock_recover(&self.watch_dir) = Some(dir.to_path_buf());
+
+        info!("Watching user profiles in: {:?}", dir);
+        Ok(())
+    }
+
+    /// Stop the current watcher.
+    pub fn stop_watching(&self) {
+        *lock_recover(&self.watcher) = None;
+    }
+}
+
+/// A profile `.json` under `root`, outside hidden folders such as
+/// `.backups`. Atomic writes go through temp files with other names, so
+/// only the final rename counts.
+fn is_profile_file(root: &Path, path: &Path) -> bool {
+    let Ok(relative) = path.strip_prefix(root) else {
+        return false;
+    };
+    path.extension().and_then(|e| e.to_str()) == Some("json")
+        && !relative
+            .components()
+            .any(|c| c.as_os_str().to_str().is_some_and(|s| s.starts_with('.')))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_profile_json_outside_hidden_dirs_counts() {
+        let root = Path::new("/home/me/.config/BambuStudio/user/1/filament");
+        assert!(is_profile_file(root, &root.join("PLA.json")));
+        assert!(is_profile_file(root, &root.join("base/PLA.json")));
+        assert!(!is_profile_file(root, &root.join("PLA.info")));
+        assert!(!is_profile_file(root, &root.join(".backups/PLA.json")));
+        assert!(!is_profile_file(root, &root.join(".tmpAbc123")));
+        assert!(!is_profile_file(root, Path::new("/elsewhere/PLA.json")));
+    }
+}
@@ -166,6 +166,7 @@ mod tests {
             temperature_vitrification: Some(55),
             filament_cost: Some(24.99),
             max_speed_mm_s: Some(200),
+            extruder_overrides: Vec::new(),
             source_url: "https://example.com/test".to_string(),
             extraction_confidence: 0.85,
         }
@@ -380,6 +380,7 @@ fn map_response_to_specs(
         max_speed_mm_s: json["max_speed_mm_s"].as_u64().map(|v| v as u16),
 
         // Metadata
+        extruder_overrides: Vec::new(),
         source_url: json["source_url"].as_str().unwrap_or("").to_string(),
         extraction_confidence: json["confidence"].as_f64().unwrap_or(0.0) as f32,
     })
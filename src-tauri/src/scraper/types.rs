@@ -95,11 +95,31 @@ pub struct FilamentSpecs {
     // === Speed (legacy) ===
     pub max_speed_mm_s: Option<u16>,
 
+    // === Per-extruder overrides ===
+    /// Values for individual extruders, in the printer's extruder order.
+    /// Extruders without an entry (or fields left `None`) use the
+    /// filament-wide values above.
+    #[serde(default)]
+    pub extruder_overrides: Vec<ExtruderOverrides>,
+
     // === Metadata ===
     pub source_url: String,
     pub extraction_confidence: f32,
 }
 
+/// Settings for one extruder that differ from the filament-wide specs, e.g.
+/// a hotter right nozzle on an H2D fitted with a high-flow hotend.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct ExtruderOverrides {
+    pub nozzle_temperature: Option<u16>,
+    pub nozzle_temperature_initial_layer: Option<u16>,
+    pub max_volumetric_speed: Option<f32>,
+    pub filament_flow_ratio: Option<f32>,
+    pub pressure_advance: Option<f32>,
+    pub retraction_distance_mm: Option<f32>,
+    pub retraction_speed_mm_s: Option<u16>,
+}
+
 /// Recognized material types for filament classification.
 /// Used for physical constraint validation lookups.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -209,6 +229,7 @@ mod tests {
             diameter_mm: Some(1.75),
             temperature_vitrification: Some(55),
             filament_cost: Some(24.99),
+            extruder_overrides: Vec::new(),
             source_url: "https://polymaker.com/products/polylite-pla-pro".to_string(),
             extraction_confidence: 0.85,
         };
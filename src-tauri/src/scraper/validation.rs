@@ -236,6 +236,7 @@ mod tests {
             temperature_vitrification: Some(55),
             filament_cost: Some(24.99),
             max_speed_mm_s: Some(200),
+            extruder_overrides: Vec::new(),
             source_url: "https://example.com".to_string(),
             extraction_confidence: 0.9,
         }
@@ -368,6 +369,7 @@ mod tests {
             temperature_vitrification: None,
             filament_cost: None,
             max_speed_mm_s: None,
+            extruder_overrides: Vec::new(),
             source_url: "https://example.com".to_string(),
             extraction_confidence: 0.0,
         };
@@ -415,6 +417,7 @@ mod tests {
             temperature_vitrification: None,
             filament_cost: None,
             max_speed_mm_s: Some(100),
+            extruder_overrides: Vec::new(),
             source_url: "https://example.com".to_string(),
             extraction_confidence: 0.5,
         };
@@ -0,0 +1,120 @@
+use std::path::PathBuf;
+
+use bambumate_tauri::calibration::{build_kit, CalibrationRange, CalibrationTest};
+use bambumate_tauri::profile::{MachineRegistry, ProfileRegistry};
+use bambumate_tauri::scraper::types::FilamentSpecs;
+
+fn fixture_registry() -> ProfileRegistry {
+    let system_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/include_system/BBL/filament");
+    ProfileRegistry::discover_system_profiles(&system_dir).unwrap()
+}
+
+fn pla_specs() -> FilamentSpecs {
+    FilamentSpecs {
+        serial: "Matte".to_string(),
+        brand: "Polymaker".to_string(),
+        material: "PLA".to_string(),
+        nozzle_temp_min: Some(200),
+        nozzle_temp_max: Some(220),
+        nozzle_temperature: Some(210),
+        filament_flow_ratio: Some(0.98),
+        ..FilamentSpecs::default()
+    }
+}
+
+#[test]
+fn test_temperature_kit_variants() {
+    let samples = build_kit(
+        CalibrationTest::TemperatureTower,
+        &pla_specs(),
+        None,
+        &fixture_registry(),
+        &MachineRegistry::new(),
+        Some("Bambu Lab X1 Carbon 0.4 nozzle"),
+        None,
+    )
+    .unwrap();
+
+    let labels: Vec<&str> = samples.iter().map(|s| s.label.as_str()).collect();
+    assert_eq!(labels, ["200C", "205C", "210C", "215C", "220C"]);
+
+    let filament_id = samples[0].profile.filament_id().unwrap();
+    for sample in &samples {
+        let temp = sample.label.trim_end_matches('C');
+        let profile = &sample.profile;
+        assert_eq!(profile.nozzle_temperature().unwrap()[0], temp);
+        assert_eq!(
+            profile.get_first_array_value("nozzle_temperature_initial_layer"),
+            Some(temp)
+        );
+        // Only the parameter under test differs from the filament's specs
+        assert_eq!(
+            profile.get_first_array_value("filament_flow_ratio"),
+            Some("0.98")
+        );
+        assert_eq!(profile.filament_id(), Some(filament_id));
+        assert_eq!(
+            profile.name().unwrap(),
+            format!(
+                "Polymaker PLA Matte Cal Temp {} @Bambu Lab X1 Carbon 0.4 nozzle",
+                sample.label
+            )
+        );
+        assert_eq!(
+            sample.stl_filename,
+            sample.profile_filename.replace(".json", ".stl")
+        );
+        let triangles = u32::from_le_bytes(sample.stl[80..84].try_into().unwrap());
+        assert_eq!(sample.stl.len(), 84 + triangles as usize * 50);
+    }
+}
+
+#[test]
+fn test_pressure_advance_kit_enables_pa() {
+    let samples = build_kit(
+        CalibrationTest::PressureAdvance,
+        &pla_specs(),
+        Some(CalibrationRange {
+            start: 0.02,
+            end: 0.04,
+            step: 0.01,
+        }),
+        &fixture_registry(),
+        &MachineRegistry::new(),
+        None,
+        None,
+    )
+    .unwrap();
+    assert_eq!(samples.len(), 3);
+    for sample in &samples {
+        assert_eq!(
+            sample.profile.get_first_array_value("pressure_advance"),
+            Some(format!("{:.3}", sample.value).as_str())
+        );
+        assert!(sample
+            .profile
+            .get_string_array("enable_pressure_advance")
+            .unwrap()
+            .iter()
+            .all(|v| *v == "1"));
+    }
+}
+
+#[test]
+fn test_temperature_kit_rejects_unsafe_values() {
+    let result = build_kit(
+        CalibrationTest::TemperatureTower,
+        &pla_specs(),
+        Some(CalibrationRange {
+            start: 220.0,
+            end: 250.0,
+            step: 10.0,
+        }),
+        &fixture_registry(),
+        &MachineRegistry::new(),
+        None,
+        None,
+    );
+    assert!(result.is_err());
+}
@@ -1,7 +1,31 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tempfile::TempDir;
 
-use bambumate_tauri::profile::paths::BambuPaths;
+use bambumate_tauri::profile::paths::{find_linux_executable, BambuPaths, FLATPAK_APP_ID};
+
+/// Create a minimal Bambu Studio config tree under `root`: the system
+/// filament folder, a logged-in user's filament dir, and BambuStudio.conf.
+fn create_config_tree(root: &Path, preset_folder: &str) {
+    std::fs::create_dir_all(root.join("system").join("BBL").join("filament")).unwrap();
+    std::fs::create_dir_all(
+        root.join("user")
+            .join(preset_folder)
+            .join("filament")
+            .join("base"),
+    )
+    .unwrap();
+    std::fs::write(
+        root.join("BambuStudio.conf"),
+        format!(r#"{{"preset_folder": "{}"}}"#, preset_folder),
+    )
+    .unwrap();
+}
+
+/// Create an empty file (and its parent directories) to stand in for an executable.
+fn touch(path: &Path) {
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, "").unwrap();
+}
 
 /// Test that BambuPaths correctly resolves user_filament_dir from preset_folder.
 #[test]
@@ -181,3 +205,206 @@ fn test_path_normalization() {
     assert!(path.components().count() == 4);
     assert_eq!(path.file_name().and_then(|n| n.to_str()), Some("base"));
 }
+
+/// Test that from_config_root derives every path and reads preset_folder.
+#[test]
+fn test_from_config_root_reads_preset_folder() {
+    let tmp = TempDir::new().unwrap();
+    let root = tmp.path().join("BambuStudio");
+    create_config_tree(&root, "1881310893");
+
+    let paths = BambuPaths::from_config_root(root.clone());
+
+    assert_eq!(paths.config_root, root);
+    assert_eq!(
+        paths.system_filament_dir(),
+        root.join("system").join("BBL").join("filament")
+    );
+    assert_eq!(paths.preset_folder.as_deref(), Some("1881310893"));
+    assert_eq!(
+        paths.user_filament_dir(),
+        Some(
+            root.join("user")
+                .join("1881310893")
+                .join("filament")
+                .join("base")
+        )
+    );
+}
+
+/// Test that a native/AppImage install under ~/.config is found.
+#[test]
+fn test_linux_config_root_native() {
+    let tmp = TempDir::new().unwrap();
+    let home = tmp.path();
+    let native = home.join(".config").join("BambuStudio");
+    create_config_tree(&native, "12345678");
+
+    let result = BambuPaths::find_linux_config_root(home, None);
+    assert_eq!(result, Some(native));
+}
+
+/// Test that the Flatpak sandbox config is found when there is no native config.
+#[test]
+fn test_linux_config_root_flatpak() {
+    let tmp = TempDir::new().unwrap();
+    let home = tmp.path();
+    let flatpak = home
+        .join(".var")
+        .join("app")
+        .join(FLATPAK_APP_ID)
+        .join("config")
+        .join("BambuStudio");
+    create_config_tree(&flatpak, "12345678");
+
+    let result = BambuPaths::find_linux_config_root(home, None);
+    assert_eq!(result, Some(flatpak.clone()));
+
+    // The whole profile pipeline hangs off the detected root.
+    let paths = BambuPaths::from_config_root(result.unwrap());
+    assert_eq!(
+        paths.user_filament_dir(),
+        Some(
+            flatpak
+                .join("user")
+                .join("12345678")
+                .join("filament")
+                .join("base")
+        )
+    );
+}
+
+/// Test that the native config wins when both native and Flatpak exist.
+#[test]
+fn test_linux_config_root_prefers_native_over_flatpak() {
+    let tmp = TempDir::new().unwrap();
+    let home = tmp.path();
+    let native = home.join(".config").join("BambuStudio");
+    create_config_tree(&native, "1");
+    create_config_tree(
+        &home
+            .join(".var")
+            .join("app")
+            .join(FLATPAK_APP_ID)
+            .join("config")
+            .join("BambuStudio"),
+        "2",
+    );
+
+    assert_eq!(BambuPaths::find_linux_config_root(home, None), Some(native));
+}
+
+/// Test that $XDG_CONFIG_HOME takes precedence over ~/.config.
+#[test]
+fn test_linux_config_root_honours_xdg_config_home() {
+    let tmp = TempDir::new().unwrap();
+    let home = tmp.path().join("home");
+    create_config_tree(&home.join(".config").join("BambuStudio"), "1");
+    let xdg = tmp.path().join("xdg");
+    create_config_tree(&xdg.join("BambuStudio"), "2");
+
+    assert_eq!(
+        BambuPaths::find_linux_config_root(&home, Some(&xdg)),
+        Some(xdg.join("BambuStudio"))
+    );
+}
+
+/// Test that no config root is reported for an empty home directory.
+#[test]
+fn test_linux_config_root_missing() {
+    let tmp = TempDir::new().unwrap();
+    assert_eq!(BambuPaths::find_linux_config_root(tmp.path(), None), None);
+}
+
+/// Test that a distro package binary is found under the filesystem root.
+#[test]
+fn test_linux_executable_system_package() {
+    let tmp = TempDir::new().unwrap();
+    let root = tmp.path().join("root");
+    let home = tmp.path().join("home");
+    let exe = root.join("usr").join("bin").join("bambu-studio");
+    touch(&exe);
+
+    assert_eq!(find_linux_executable(&root, &home), Some(exe));
+}
+
+/// Test that a per-user Flatpak export is found.
+#[test]
+fn test_linux_executable_user_flatpak() {
+    let tmp = TempDir::new().unwrap();
+    let root = tmp.path().join("root");
+    let home = tmp.path().join("home");
+    let exe = home
+        .join(".local")
+        .join("share")
+        .join("flatpak")
+        .join("exports")
+        .join("bin")
+        .join(FLATPAK_APP_ID);
+    touch(&exe);
+
+    assert_eq!(find_linux_executable(&root, &home), Some(exe));
+}
+
+/// Test that the newest AppImage is picked and unrelated AppImages are ignored.
+#[test]
+fn test_linux_executable_newest_appimage() {
+    let tmp = TempDir::new().unwrap();
+    let root = tmp.path().join("root");
+    let home = tmp.path().join("home");
+    let apps = home.join("Applications");
+    touch(&apps.join("Bambu_Studio_linux_ubuntu_22.04_v01.10.02.76.AppImage"));
+    let newest = apps.join("Bambu_Studio_linux_ubuntu_22.04_v02.00.03.54.AppImage");
+    touch(&newest);
+    touch(&apps.join("OrcaSlicer_Linux_V2.2.0.AppImage"));
+
+    assert_eq!(find_linux_executable(&root, &home), Some(newest));
+}
+
+/// Test that AppImage versions compare numerically, so 1.10 beats 1.9.
+#[test]
+fn test_linux_executable_appimage_version_order() {
+    let tmp = TempDir::new().unwrap();
+    let root = tmp.path().join("root");
+    let home = tmp.path().join("home");
+    let apps = home.join("Applications");
+    touch(&apps.join("Bambu_Studio_linux_v1.9.7.AppImage"));
+    let newest = apps.join("Bambu_Studio_linux_v1.10.0.AppImage");
+    touch(&newest);
+
+    assert_eq!(find_linux_executable(&root, &home), Some(newest));
+}
+
+/// Test that packaged installs take precedence over a downloaded AppImage.
+#[test]
+fn test_linux_executable_prefers_package_over_appimage() {
+    let tmp = TempDir::new().unwrap();
+    let root = tmp.path().join("root");
+    let home = tmp.path().join("home");
+    touch(
+        &home
+            .join("Downloads")
+            .join("BambuStudio_ubuntu-v02.00.00.95.AppImage"),
+    );
+    let exe = root
+        .join("var")
+        .join("lib")
+        .join("flatpak")
+        .join("exports")
+        .join("bin")
+        .join(FLATPAK_APP_ID);
+    touch(&exe);
+
+    assert_eq!(find_linux_executable(&root, &home), Some(exe));
+}
+
+/// Test that nothing is reported when no install exists.
+#[test]
+fn test_linux_executable_missing() {
+    let tmp = TempDir::new().unwrap();
+    let root = tmp.path().join("root");
+    let home = tmp.path().join("home");
+    std::fs::create_dir_all(&home).unwrap();
+
+    assert_eq!(find_linux_executable(&root, &home), None);
+}
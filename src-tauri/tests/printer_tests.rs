@@ -0,0 +1,424 @@
+//! Printer connection tests against a minimal in-process MQTT broker that
+//! plays back recorded report payloads.
+
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use bambumate_tauri::printer::report::parse_report;
+use bambumate_tauri::printer::AmsFilamentSetting;
+use bambumate_tauri::printer::{
+    resolve_trays, ConnectionState, PrinterConfig, PrinterConnection, PrinterEvent, PrinterStatus,
+    TrayMatchState,
+};
+use bambumate_tauri::profile::generate_profile;
+use bambumate_tauri::profile::inheritance::resolve_inheritance;
+use bambumate_tauri::profile::registry::{MachineRegistry, ProfileRegistry};
+
+const SERIAL: &str = "00M09A350100123";
+const ACCESS_CODE: &str = "12345678";
+
+fn fixture(name: &str) -> Vec<u8> {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/printer")
+        .join(name);
+    std::fs::read(path).unwrap()
+}
+
+fn read_packet(stream: &mut TcpStream) -> Option<(u8, Vec<u8>)> {
+    let mut header = [0u8; 1];
+    stream.read_exact(&mut header).ok()?;
+    let mut len = 0usize;
+    let mut shift = 0;
+    loop {
+        let mut byte = [0u8; 1];
+        stream.read_exact(&mut byte).ok()?;
+        len |= ((byte[0] & 0x7f) as usize) << shift;
+        if byte[0] & 0x80 == 0 {
+            break;
+        }
+        shift += 7;
+    }
+    let mut body = vec![0u8; len];
+    stream.read_exact(&mut body).ok()?;
+    Some((header[0], body))
+}
+
+fn write_packet(stream: &mut TcpStream, header: u8, body: &[u8]) {
+    let mut packet = vec![header];
+    let mut len = body.len();
+    loop {
+        let mut byte = (len % 128) as u8;
+        len /= 128;
+        if len > 0 {
+            byte |= 0x80;
+        }
+        packet.push(byte);
+        if len == 0 {
+            break;
+        }
+    }
+    packet.extend_from_slice(body);
+    stream.write_all(&packet).unwrap();
+}
+
+fn read_string(body: &[u8], pos: &mut usize) -> String {
+    let len = u16::from_be_bytes([body[*pos], body[*pos + 1]]) as usize;
+    let s = String::from_utf8(body[*pos + 2..*pos + 2 + len].to_vec()).unwrap();
+    *pos += 2 + len;
+    s
+}
+
+fn publish_body(topic: &str, payload: &[u8]) -> Vec<u8> {
+    let mut body = (topic.len() as u16).to_be_bytes().to_vec();
+    body.extend_from_slice(topic.as_bytes());
+    body.extend_from_slice(payload);
+    body
+}
+
+/// Accept one client and behave like a printer's LAN broker: check the
+/// `bblp` login, ack subscriptions, and answer a `pushall` request with
+/// `reports` on the report topic. Request payloads received are forwarded
+/// on `requests`, and a DISCONNECT as `null`.
+fn spawn_broker(reports: Vec<Vec<u8>>, requests: mpsc::Sender<serde_json::Value>) -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        while let Some((header, body)) = read_packet(&mut stream) {
+            match header >> 4 {
+                // CONNECT
+                1 => {
+                    let mut pos = 0;
+                    assert_eq!(read_string(&body, &mut pos), "MQTT");
+                    pos += 1; // protocol level
+                    let flags = body[pos];
+                    pos += 3; // flags + keep alive
+                    let _client_id = read_string(&body, &mut pos);
+                    let username = (flags & 0x80 != 0).then(|| read_string(&body, &mut pos));
+                    let password = (flags & 0x40 != 0).then(|| read_string(&body, &mut pos));
+                    let accepted = username.as_deref() == Some("bblp")
+                        && password.as_deref() == Some(ACCESS_CODE);
+                    // Return code 5: not authorized
+                    write_packet(&mut stream, 0x20, &[0x00, if accepted { 0 } else { 5 }]);
+                    if !accepted {
+                        return;
+                    }
+                }
+                // SUBSCRIBE
+                8 => {
+                    let mut pos = 2;
+                    assert_eq!(
+                        read_string(&body, &mut pos),
+                        format!("device/{}/report", SERIAL)
+                    );
+                    write_packet(&mut stream, 0x90, &[body[0], body[1], 0x00]);
+                }
+                // PUBLISH (QoS 0)
+                3 => {
+                    let mut pos = 0;
+                    let topic = read_string(&body, &mut pos);
+                    assert_eq!(topic, format!("device/{}/request", SERIAL));
+                    let request: serde_json::Value = serde_json::from_slice(&body[pos..]).unwrap();
+                    let is_pushall = request["pushing"]["command"] == "pushall";
+                    let _ = requests.send(request);
+                    if is_pushall {
+                        let report_topic = format!("device/{}/report", SERIAL);
+                        for report in &reports {
+                            write_packet(&mut stream, 0x30, &publish_body(&report_topic, report));
+                        }
+                    }
+                }
+                // PINGREQ
+                12 => write_packet(&mut stream, 0xd0, &[]),
+                // DISCONNECT
+                14 => {
+                    let _ = requests.send(serde_json::Value::Null);
+                    return;
+                }
+                other => panic!("unexpected packet type {}", other),
+            }
+        }
+    });
+    port
+}
+
+fn connect(port: u16, access_code: &str) -> (PrinterConnection, mpsc::Receiver<PrinterEvent>) {
+    let config = PrinterConfig {
+        serial: SERIAL.to_string(),
+        host: "127.0.0.1".to_string(),
+        access_code: access_code.to_string(),
+        name: Some("Workshop X1C".to_string()),
+        port,
+        tls: false,
+    };
+    let (tx, rx) = mpsc::channel();
+    let connection = PrinterConnection::connect(
+        config,
+        Arc::new(move |event| {
+            let _ = tx.send(event);
+        }),
+    )
+    .unwrap();
+    (connection, rx)
+}
+
+/// Collect events until `done` matches one, or fail after a timeout.
+fn wait_for(
+    rx: &mpsc::Receiver<PrinterEvent>,
+    done: impl Fn(&PrinterEvent) -> bool,
+) -> Vec<PrinterEvent> {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    let mut events = Vec::new();
+    while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+        match rx.recv_timeout(remaining) {
+            Ok(event) => {
+                let finished = done(&event);
+                events.push(event);
+                if finished {
+                    return events;
+                }
+            }
+            Err(_) => break,
+        }
+    }
+    panic!("timed out waiting for printer event; got {:?}", events);
+}
+
+#[test]
+fn test_connection_streams_merged_status() {
+    let (req_tx, req_rx) = mpsc::channel();
+    let port = spawn_broker(
+        vec![fixture("x1c_pushall.json"), fixture("x1c_delta.json")],
+        req_tx,
+    );
+    let (connection, rx) = connect(port, ACCESS_CODE);
+
+    let events = wait_for(
+        &rx,
+        |e| matches!(e, PrinterEvent::Status { status, .. } if status.layer == Some(43)),
+    );
+    assert!(matches!(
+        &events[0],
+        PrinterEvent::Connection { state: ConnectionState::Connected, serial, .. } if serial == SERIAL
+    ));
+    let statuses: Vec<&PrinterStatus> = events
+        .iter()
+        .filter_map(|e| match e {
+            PrinterEvent::Status { status, .. } => Some(status.as_ref()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(statuses.len(), 2);
+
+    let request = req_rx.recv_timeout(Duration::from_secs(1)).unwrap();
+    assert_eq!(request["pushing"]["command"], "pushall");
+
+    // The delta updates temperatures and progress but keeps the rest
+    let status = connection.status();
+    assert_eq!(&status, statuses[1]);
+    assert_eq!(connection.state(), ConnectionState::Connected);
+    assert_eq!(status.nozzle_temp, Some(220.0625));
+    assert_eq!(status.print_percent, Some(38));
+    assert_eq!(status.remaining_minutes, Some(82));
+    assert_eq!(status.total_layers, Some(240));
+    assert_eq!(status.gcode_state.as_deref(), Some("RUNNING"));
+    assert_eq!(status.job_name.as_deref(), Some("benchy"));
+    assert_eq!(status.part_fan_percent, Some(100));
+    assert_eq!(status.wifi_signal.as_deref(), Some("-45dBm"));
+
+    assert_eq!(status.ams.len(), 1);
+    let trays = &status.ams[0].trays;
+    assert_eq!(trays.len(), 4);
+    assert_eq!(trays[0].filament_id.as_deref(), Some("GFA00"));
+    assert_eq!(trays[0].remain_percent, Some(82));
+    assert_eq!(trays[0].tag_uid.as_deref(), Some("A1B2C3D4E5F60708"));
+    assert_eq!(trays[1].filament_type.as_deref(), Some("PETG"));
+    assert_eq!(trays[1].remain_percent, None);
+    assert_eq!(trays[1].tag_uid, None);
+    assert!(trays[2].is_empty());
+
+    let active = status.active_tray().unwrap();
+    assert_eq!(active.sub_brand.as_deref(), Some("PLA Basic"));
+    let external = status.external_spool.as_ref().unwrap();
+    assert_eq!(external.filament_type.as_deref(), Some("ABS"));
+    assert_eq!(external.filament_id.as_deref(), Some("GFB99"));
+
+    connection.disconnect();
+}
+
+#[test]
+fn test_disconnect_is_sent_to_the_broker() {
+    let (req_tx, req_rx) = mpsc::channel();
+    let port = spawn_broker(vec![fixture("x1c_pushall.json")], req_tx);
+    let (connection, rx) = connect(port, ACCESS_CODE);
+    wait_for(&rx, |e| matches!(e, PrinterEvent::Status { .. }));
+    let pushall = req_rx.recv_timeout(Duration::from_secs(1)).unwrap();
+    assert_eq!(pushall["pushing"]["command"], "pushall");
+
+    connection.disconnect();
+    let request = req_rx.recv_timeout(Duration::from_secs(2)).unwrap();
+    assert!(request.is_null());
+}
+
+#[test]
+fn test_wrong_access_code_is_reported() {
+    let (req_tx, _req_rx) = mpsc::channel();
+    let port = spawn_broker(Vec::new(), req_tx);
+    let (connection, rx) = connect(port, "00000000");
+
+    let events = wait_for(&rx, |e| {
+        matches!(
+            e,
+            PrinterEvent::Connection {
+                state: ConnectionState::Reconnecting,
+                ..
+            }
+        )
+    });
+    match events.last().unwrap() {
+        PrinterEvent::Connection { message, .. } => {
+            assert!(message
+                .as_deref()
+                .unwrap_or_default()
+                .contains("NotAuthorized"));
+        }
+        other => panic!("unexpected event {:?}", other),
+    }
+    assert_eq!(connection.state(), ConnectionState::Reconnecting);
+    connection.disconnect();
+}
+
+/// Merged status from the recorded X1C `pushall` report.
+fn recorded_status() -> PrinterStatus {
+    let report = parse_report(&fixture("x1c_pushall.json")).unwrap();
+    let mut status = PrinterStatus::default();
+    status.apply(report.print.as_ref().unwrap());
+    status
+}
+
+fn registry_with_user_profile(user_dir: &std::path::Path) -> ProfileRegistry {
+    let system_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/include_system/BBL/filament");
+    let mut registry = ProfileRegistry::discover_system_profiles(&system_dir).unwrap();
+    std::fs::write(
+        user_dir.join("Overture PETG @BBL X1C.json"),
+        r#"{"name": "Overture PETG @BBL X1C", "inherits": "", "filament_id": "P4d64437", "filament_type": ["PETG"]}"#,
+    )
+    .unwrap();
+    registry.discover_user_profiles(user_dir).unwrap();
+    registry
+}
+
+#[test]
+fn test_resolve_recorded_ams_trays() {
+    let user_dir = tempfile::tempdir().unwrap();
+    let registry = registry_with_user_profile(user_dir.path());
+    let trays = resolve_trays(&recorded_status(), &registry);
+
+    let slots: Vec<&str> = trays.iter().map(|t| t.slot.as_str()).collect();
+    assert_eq!(slots, ["A1", "A2", "A3", "A4", "Ext"]);
+
+    // Bambu spool: resolves through the @base preset's filament_id to the
+    // printer variant; the @base preset itself isn't selectable
+    assert_eq!(trays[0].state, TrayMatchState::Matched);
+    let names: Vec<&str> = trays[0].profiles.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(names, ["Bambu PLA Basic @BBL X1C"]);
+    assert!(!trays[0].profiles[0].is_user_profile);
+
+    // Third-party spool assigned a user preset
+    assert_eq!(trays[1].state, TrayMatchState::Matched);
+    assert_eq!(trays[1].profiles[0].name, "Overture PETG @BBL X1C");
+    assert!(trays[1].profiles[0].is_user_profile);
+    assert!(trays[1].profiles[0]
+        .path
+        .as_deref()
+        .unwrap()
+        .ends_with("Overture PETG @BBL X1C.json"));
+    assert!(trays[1].suggested_specs.is_none());
+
+    assert_eq!(trays[2].state, TrayMatchState::Empty);
+
+    // Generic presets aren't installed in the fixture tree
+    assert_eq!(trays[3].state, TrayMatchState::Unmatched);
+    let specs = trays[3].suggested_specs.as_ref().unwrap();
+    assert_eq!(specs.material, "PLA");
+    assert_eq!(specs.nozzle_temp_min, Some(190));
+    assert_eq!(specs.nozzle_temp_max, Some(230));
+    assert_eq!(trays[4].state, TrayMatchState::Unmatched);
+    assert_eq!(trays[4].ams_id, None);
+    assert_eq!(trays[4].suggested_specs.as_ref().unwrap().material, "ABS");
+}
+
+#[test]
+fn test_generated_profile_resolves_unmatched_tray() {
+    let user_dir = tempfile::tempdir().unwrap();
+    let mut registry = registry_with_user_profile(user_dir.path());
+    let unmatched = resolve_trays(&recorded_status(), &registry)
+        .into_iter()
+        .find(|t| t.slot == "A4")
+        .unwrap();
+    assert_eq!(unmatched.state, TrayMatchState::Unmatched);
+
+    let (profile, _, _) = generate_profile(
+        unmatched.suggested_specs.as_ref().unwrap(),
+        &registry,
+        &MachineRegistry::new(),
+        Some("Bambu Lab X1 Carbon 0.4 nozzle"),
+        None,
+        unmatched.tray.filament_id.clone(),
+    )
+    .unwrap();
+    assert_eq!(profile.filament_id(), Some("GFL99"));
+    let name = profile.name().unwrap().to_string();
+    registry.insert(profile);
+
+    let resolved = resolve_trays(&recorded_status(), &registry)
+        .into_iter()
+        .find(|t| t.slot == "A4")
+        .unwrap();
+    assert_eq!(resolved.state, TrayMatchState::Matched);
+    assert_eq!(resolved.profiles[0].name, name);
+}
+
+#[test]
+fn test_ams_filament_setting_is_published() {
+    let (req_tx, req_rx) = mpsc::channel();
+    let port = spawn_broker(vec![fixture("x1c_pushall.json")], req_tx);
+    let (connection, rx) = connect(port, ACCESS_CODE);
+    wait_for(&rx, |e| matches!(e, PrinterEvent::Status { .. }));
+    let pushall = req_rx.recv_timeout(Duration::from_secs(1)).unwrap();
+    assert_eq!(pushall["pushing"]["command"], "pushall");
+
+    let user_dir = tempfile::tempdir().unwrap();
+    let registry = registry_with_user_profile(user_dir.path());
+    let profile = registry.get_by_name("Bambu PLA Basic @BBL X1C").unwrap();
+    let resolved = resolve_inheritance(profile, &registry).unwrap();
+    let filament_id = registry.effective_filament_id(profile).unwrap();
+    let setting =
+        AmsFilamentSetting::from_profile(&resolved, filament_id, Some(0), 3, "#FFFFFF").unwrap();
+    // The fixture's common template allows 240C, above the PLA limit
+    assert_eq!(setting.nozzle_temp_max, 235);
+    assert_eq!(setting.warnings.len(), 1);
+
+    tauri::async_runtime::block_on(connection.handle().send_request(&setting.to_request()))
+        .unwrap();
+
+    let request = req_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+    let print = &request["print"];
+    assert_eq!(print["command"], "ams_filament_setting");
+    assert_eq!(print["ams_id"], 0);
+    assert_eq!(print["tray_id"], 3);
+    assert_eq!(print["tray_info_idx"], "GFA00");
+    assert_eq!(print["setting_id"], "GFSA00");
+    assert_eq!(print["tray_type"], "PLA");
+    assert_eq!(print["tray_color"], "FFFFFFFF");
+    assert_eq!(print["nozzle_temp_min"], 190);
+    assert_eq!(print["nozzle_temp_max"], 235);
+
+    connection.disconnect();
+}
@@ -236,3 +236,553 @@ fn test_atomic_write_with_metadata() {
     assert_eq!(metadata.setting_id, reparsed_meta.setting_id);
     assert_eq!(metadata.updated_time, reparsed_meta.updated_time);
 }
+
+/// Load the registry for the `include_system` fixture tree, which mirrors a
+/// Bambu Studio system filament folder that uses `include` fragments.
+fn include_fixture_registry() -> ProfileRegistry {
+    ProfileRegistry::discover_system_profiles(&fixture_path("include_system"))
+        .expect("Failed to load include fixtures")
+}
+
+fn first_value(profile: &FilamentProfile, key: &str) -> Option<String> {
+    profile
+        .raw()
+        .get(key)
+        .and_then(|v| v.as_array())
+        .and_then(|a| a.first())
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string())
+}
+
+#[test]
+fn test_include_fragments_resolved() {
+    let registry = include_fixture_registry();
+    let leaf = registry
+        .get_by_name("Bambu PLA Basic @BBL X1C")
+        .expect("leaf fixture missing");
+
+    let resolved = inheritance::resolve_inheritance(leaf, &registry).expect("Failed to resolve");
+
+    // Fields only defined in fragments are pulled in
+    assert_eq!(
+        first_value(&resolved, "filament_retraction_speed").as_deref(),
+        Some("30")
+    );
+    // Fields from the inherits chain are still present
+    assert_eq!(
+        first_value(&resolved, "fan_max_speed").as_deref(),
+        Some("100")
+    );
+    assert_eq!(
+        first_value(&resolved, "cool_plate_temp").as_deref(),
+        Some("35")
+    );
+    // Leaf identity is kept
+    assert_eq!(resolved.name(), Some("Bambu PLA Basic @BBL X1C"));
+    assert_eq!(
+        resolved.raw().get("setting_id").and_then(|v| v.as_str()),
+        Some("GFSA00")
+    );
+}
+
+#[test]
+fn test_include_precedence() {
+    let registry = include_fixture_registry();
+    let leaf = registry
+        .get_by_name("Bambu PLA Basic @BBL X1C")
+        .expect("leaf fixture missing");
+
+    let resolved = inheritance::resolve_inheritance(leaf, &registry).expect("Failed to resolve");
+
+    // An included fragment overrides the inherits parent (pla: 220 -> high_flow: 230)
+    assert_eq!(
+        first_value(&resolved, "nozzle_temperature").as_deref(),
+        Some("230")
+    );
+    // Later includes override earlier ones (direct_drive: 0.8 -> high_flow: 0.6)
+    assert_eq!(
+        first_value(&resolved, "filament_retraction_length").as_deref(),
+        Some("0.6")
+    );
+    // The including profile's own fields override its fragments
+    assert_eq!(
+        first_value(&resolved, "filament_flow_ratio").as_deref(),
+        Some("0.98")
+    );
+    // nil-only fields survive the merge
+    assert_eq!(
+        first_value(&resolved, "filament_deretraction_speed").as_deref(),
+        Some("nil")
+    );
+    // Fragment metadata is not inherited
+    assert!(!resolved.raw().contains_key("description"));
+    assert!(!resolved.raw().contains_key("include"));
+}
+
+#[test]
+fn test_include_cycle_detected() {
+    let registry = include_fixture_registry();
+    let leaf = registry
+        .get_by_name("Bambu PLA Loop @BBL X1C")
+        .expect("loop fixture missing");
+
+    let err = match inheritance::resolve_inheritance(leaf, &registry) {
+        Ok(_) => panic!("include cycle should be rejected"),
+        Err(e) => e,
+    };
+    assert!(
+        err.to_string().contains("Circular inheritance"),
+        "unexpected error: {}",
+        err
+    );
+}
+
+/// Config root for the `preset_config` fixture tree, which holds system
+/// process and machine presets in the Bambu Studio layout.
+fn preset_fixture_paths() -> paths::BambuPaths {
+    paths::BambuPaths::from_config_root(fixture_path("preset_config"))
+}
+
+#[test]
+fn test_process_presets_resolved() {
+    let paths = preset_fixture_paths();
+    let registry = ProcessRegistry::discover_system_profiles(&paths.system_preset_dir::<Process>())
+        .expect("Failed to load process fixtures");
+    assert_eq!(registry.len(), 3);
+
+    let leaf = registry
+        .get_by_name("0.20mm Standard @BBL X1C")
+        .expect("process fixture missing");
+    assert!(leaf.is_instantiable());
+    assert_eq!(leaf.layer_height(), None);
+
+    let resolved = inheritance::resolve_inheritance(leaf, &registry).expect("Failed to resolve");
+    assert_eq!(resolved.layer_height(), Some("0.2"));
+    assert_eq!(resolved.get_scalar("top_shell_layers"), Some("5"));
+    // Each level overrides the one above it
+    assert_eq!(resolved.get_scalar("outer_wall_speed"), Some("200"));
+    assert_eq!(resolved.get_scalar("inner_wall_speed"), Some("300"));
+    assert_eq!(resolved.setting_id(), Some("GP004"));
+    assert_eq!(
+        resolved.get_scalar("print_settings_id"),
+        Some("0.20mm Standard @BBL X1C")
+    );
+}
+
+#[test]
+fn test_machine_presets_resolved() {
+    let paths = preset_fixture_paths();
+    let registry = MachineRegistry::discover_system_profiles(&paths.system_preset_dir::<Machine>())
+        .expect("Failed to load machine fixtures");
+
+    let x1c = registry
+        .get_by_name("Bambu Lab X1 Carbon 0.4 nozzle")
+        .expect("X1C fixture missing");
+    let x1c = inheritance::resolve_inheritance(x1c, &registry).expect("Failed to resolve");
+    assert_eq!(x1c.printer_model(), Some("Bambu Lab X1 Carbon"));
+    assert_eq!(x1c.nozzle_diameter(), Some(vec!["0.4"]));
+    assert_eq!(x1c.extruder_count(), Some(1));
+
+    let h2d = registry
+        .get_by_name("Bambu Lab H2D 0.4 nozzle")
+        .expect("H2D fixture missing");
+    let h2d = inheritance::resolve_inheritance(h2d, &registry).expect("Failed to resolve");
+    assert_eq!(h2d.extruder_count(), Some(2));
+    assert_eq!(h2d.get_scalar("printable_height"), Some("325"));
+    // Inherited per-extruder arrays are left as-is
+    assert_eq!(h2d.get_string_array("retraction_length"), Some(vec!["0.8"]));
+}
+
+#[test]
+fn test_process_preset_write_to_user_dir() {
+    let tmp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let user_dir = tmp_dir.path().join("user/12345/process/base");
+    std::fs::create_dir_all(&user_dir).unwrap();
+    std::fs::write(
+        tmp_dir.path().join("BambuStudio.conf"),
+        r#"{"app": {"preset_folder": "12345"}}"#,
+    )
+    .unwrap();
+
+    let paths = paths::BambuPaths::from_config_root(tmp_dir.path().to_path_buf());
+    assert_eq!(paths.user_preset_dir::<Process>(), Some(user_dir.clone()));
+    assert_eq!(paths.user_preset_dir::<Machine>(), None);
+
+    let preset = ProcessPreset::from_json(
+        r#"{"name": "My Draft", "inherits": "0.20mm Standard @BBL X1C", "from": "User", "print_settings_id": "My Draft", "layer_height": "0.28"}"#,
+    )
+    .unwrap();
+    let target = user_dir.join("My Draft.json");
+    let metadata = ProfileMetadata {
+        base_id: "GP004".to_string(),
+        ..ProfileMetadata::default()
+    };
+    write_profile_with_metadata(&preset, &target, &metadata).expect("Failed to write");
+
+    let mut registry = ProcessRegistry::new();
+    registry
+        .discover_user_profiles(&user_dir)
+        .expect("Failed to discover user presets");
+    let read_back = registry
+        .get_by_name("My Draft")
+        .expect("written preset missing");
+    assert_eq!(read_back.layer_height(), Some("0.28"));
+    assert!(target.with_extension("info").exists());
+}
+
+#[test]
+fn test_sample_and_generated_profiles_lint_clean() {
+    use bambumate_tauri::profile::lint::{lint_profile, LintOptions};
+    use bambumate_tauri::scraper::types::FilamentSpecs;
+
+    let sample = read_profile(&fixture_path("sample_profile.json")).unwrap();
+    let report = lint_profile(&sample, &LintOptions::default());
+    assert!(report.diagnostics.is_empty(), "{:?}", report.diagnostics);
+
+    let specs = FilamentSpecs {
+        serial: "Matte".to_string(),
+        brand: "Polymaker".to_string(),
+        material: "PLA".to_string(),
+        nozzle_temp_min: Some(190),
+        nozzle_temp_max: Some(230),
+        nozzle_temperature: Some(215),
+        ..FilamentSpecs::default()
+    };
+    let (profile, _, _) = generate_profile(
+        &specs,
+        &include_fixture_registry(),
+        &MachineRegistry::new(),
+        Some("Bambu Lab X1 Carbon 0.4 nozzle"),
+        None,
+        None,
+    )
+    .unwrap();
+    let report = lint_profile(&profile, &LintOptions::default());
+    assert!(!report.has_errors(), "{}", report.error_summary());
+}
+
+#[test]
+fn test_generated_profile_drops_merged_includes() {
+    use bambumate_tauri::scraper::types::FilamentSpecs;
+
+    let specs = FilamentSpecs {
+        brand: "Polymaker".to_string(),
+        material: "PLA".to_string(),
+        ..FilamentSpecs::default()
+    };
+    // The base has `include` fragments of its own
+    let (profile, _, _) = generate_profile(
+        &specs,
+        &include_fixture_registry(),
+        &MachineRegistry::new(),
+        Some("Bambu Lab X1 Carbon 0.4 nozzle"),
+        Some("Bambu PLA Basic @base"),
+        None,
+    )
+    .unwrap();
+    assert_eq!(profile.inherits(), Some(""));
+    assert!(!profile.raw().contains_key("include"));
+    // ...but what they contributed is kept
+    assert_eq!(
+        first_value(&profile, "filament_retraction_length").as_deref(),
+        Some("0.6")
+    );
+}
+
+#[test]
+fn test_generated_arrays_follow_printer_extruder_count() {
+    use bambumate_tauri::profile::lint::{lint_profile, LintOptions};
+    use bambumate_tauri::scraper::types::{ExtruderOverrides, FilamentSpecs};
+
+    let paths = preset_fixture_paths();
+    let machines = MachineRegistry::discover_system_profiles(&paths.system_preset_dir::<Machine>())
+        .expect("Failed to load machine fixtures");
+    let specs = FilamentSpecs {
+        serial: "Matte".to_string(),
+        brand: "Polymaker".to_string(),
+        material: "PLA".to_string(),
+        nozzle_temperature: Some(215),
+        filament_flow_ratio: Some(0.98),
+        extruder_overrides: vec![
+            ExtruderOverrides::default(),
+            ExtruderOverrides {
+                nozzle_temperature: Some(220),
+                ..ExtruderOverrides::default()
+            },
+        ],
+        ..FilamentSpecs::default()
+    };
+
+    let generate = |printer: &str| {
+        generate_profile(
+            &specs,
+            &include_fixture_registry(),
+            &machines,
+            Some(printer),
+            None,
+            None,
+        )
+        .unwrap()
+        .0
+    };
+
+    // Single extruder: the second extruder's override has nowhere to go
+    let x1c = generate("Bambu Lab X1 Carbon 0.4 nozzle");
+    assert_eq!(x1c.nozzle_temperature(), Some(vec!["215"]));
+    assert_eq!(x1c.get_string_array("filament_wipe"), Some(vec!["1"]));
+    let options = LintOptions {
+        extruder_count: Some(1),
+    };
+    assert!(lint_profile(&x1c, &options).diagnostics.is_empty());
+
+    let h2d = generate("Bambu Lab H2D 0.4 nozzle");
+    assert_eq!(h2d.nozzle_temperature(), Some(vec!["215", "220"]));
+    assert_eq!(
+        h2d.get_string_array("nozzle_temperature_initial_layer"),
+        Some(vec!["220", "225"])
+    );
+    assert_eq!(
+        h2d.get_string_array("filament_flow_ratio"),
+        Some(vec!["0.98", "0.98"])
+    );
+    // Base-profile values are padded to one per extruder too
+    assert_eq!(
+        h2d.get_string_array("nozzle_temperature_range_low"),
+        Some(vec!["190", "190"])
+    );
+    // Identity fields stay single-element
+    assert_eq!(h2d.get_string_array("filament_type"), Some(vec!["PLA"]));
+    let options = LintOptions {
+        extruder_count: Some(2),
+    };
+    assert!(lint_profile(&h2d, &options).diagnostics.is_empty());
+
+    // Reading it back reports the hotter second extruder as an override
+    let extracted = generator::extract_specs_from_profile(&h2d);
+    assert_eq!(extracted.nozzle_temperature, Some(215));
+    assert_eq!(extracted.extruder_overrides.len(), 2);
+    assert_eq!(
+        extracted.extruder_overrides[1].nozzle_temperature,
+        Some(220)
+    );
+    assert_eq!(
+        extracted.extruder_overrides[1].nozzle_temperature_initial_layer,
+        Some(225)
+    );
+}
+
+#[test]
+fn test_unknown_printer_keeps_base_array_size() {
+    use bambumate_tauri::scraper::types::FilamentSpecs;
+
+    // A dual-extruder base, and no machine preset to say otherwise
+    let tmp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+    std::fs::write(
+        tmp_dir.path().join("fdm_filament_pla.json"),
+        r#"{
+            "type": "filament",
+            "name": "fdm_filament_pla",
+            "from": "system",
+            "instantiation": "false",
+            "nozzle_temperature": ["220", "220"],
+            "filament_flow_ratio": ["0.98", "0.98"]
+        }"#,
+    )
+    .unwrap();
+    let registry = ProfileRegistry::discover_system_profiles(tmp_dir.path()).unwrap();
+    let specs = FilamentSpecs {
+        brand: "Polymaker".to_string(),
+        material: "PLA".to_string(),
+        nozzle_temperature: Some(215),
+        ..FilamentSpecs::default()
+    };
+
+    let (profile, _, _) = generate_profile(
+        &specs,
+        &registry,
+        &MachineRegistry::new(),
+        Some("Some Future Printer 0.4 nozzle"),
+        None,
+        None,
+    )
+    .unwrap();
+    assert_eq!(profile.nozzle_temperature(), Some(vec!["215", "215"]));
+    assert_eq!(
+        profile.get_string_array("filament_flow_ratio"),
+        Some(vec!["0.98", "0.98"])
+    );
+}
+
+#[test]
+fn test_generated_specs_scale_with_nozzle_diameter() {
+    use bambumate_tauri::scraper::types::FilamentSpecs;
+
+    let specs = FilamentSpecs {
+        serial: "Matte".to_string(),
+        brand: "Polymaker".to_string(),
+        material: "PLA".to_string(),
+        max_volumetric_speed: Some(20.0),
+        retraction_distance_mm: Some(0.8),
+        ..FilamentSpecs::default()
+    };
+    // No machine presets: the nozzle is read from the printer name
+    let generate = |printer: &str| {
+        generate_profile(
+            &specs,
+            &include_fixture_registry(),
+            &MachineRegistry::new(),
+            Some(printer),
+            None,
+            Some("PFUS1234".to_string()),
+        )
+        .unwrap()
+        .0
+    };
+
+    let standard = generate("Bambu Lab X1 Carbon 0.4 nozzle");
+    let fine = generate("Bambu Lab X1 Carbon 0.2 nozzle");
+    let coarse = generate("Bambu Lab X1 Carbon 0.8 nozzle");
+
+    let volumetric = |p: &FilamentProfile| -> f32 {
+        first_value(p, "filament_max_volumetric_speed")
+            .unwrap()
+            .parse()
+            .unwrap()
+    };
+    assert_eq!(volumetric(&standard), 20.0);
+    assert_eq!(volumetric(&fine), 5.0);
+    assert!(volumetric(&coarse) > 20.0);
+    let retraction = first_value(&coarse, "filament_retraction_length").unwrap();
+    assert!(retraction.parse::<f32>().unwrap() > 0.8);
+    assert_eq!(fine.filament_id(), coarse.filament_id());
+}
+
+#[test]
+fn test_generated_profile_minimizes_to_delta() {
+    use bambumate_tauri::scraper::types::FilamentSpecs;
+
+    let registry = include_fixture_registry();
+    let specs = FilamentSpecs {
+        serial: "Matte".to_string(),
+        brand: "Polymaker".to_string(),
+        material: "PLA".to_string(),
+        nozzle_temperature: Some(215),
+        ..FilamentSpecs::default()
+    };
+    let (flattened, _, _) = generate_profile(
+        &specs,
+        &registry,
+        &MachineRegistry::new(),
+        Some("Bambu Lab X1 Carbon 0.4 nozzle"),
+        None,
+        None,
+    )
+    .unwrap();
+
+    let delta = inheritance::minimize(&flattened, "fdm_filament_pla", &registry).unwrap();
+    assert_eq!(delta.inherits(), Some("fdm_filament_pla"));
+    assert!(delta.field_count() < flattened.field_count());
+    assert_eq!(
+        first_value(&delta, "nozzle_temperature").as_deref(),
+        Some("215")
+    );
+    assert_eq!(delta.filament_id(), flattened.filament_id());
+    // Unchanged base values are left to inheritance
+    assert!(!delta.raw().contains_key("filament_density"));
+
+    let mut resolved = inheritance::resolve_inheritance(&delta, &registry).unwrap();
+    resolved.set_string("inherits", String::new());
+    assert_eq!(resolved.raw(), flattened.raw());
+}
+
+#[test]
+fn test_delta_parent_is_an_instantiable_system_preset() {
+    use bambumate_tauri::scraper::types::MaterialType;
+
+    let registry = include_fixture_registry();
+    // Not the abstract fdm_filament_pla, and not the Loop preset, which has
+    // no setting_id for a user preset to record as its base_id
+    assert_eq!(
+        generator::delta_parent_name(
+            &registry,
+            &MaterialType::PLA,
+            "Bambu Lab X1 Carbon 0.4 nozzle"
+        )
+        .as_deref(),
+        Some("Bambu PLA Basic @BBL X1C")
+    );
+    assert_eq!(
+        generator::delta_parent_name(&registry, &MaterialType::PLA, "Bambu Lab A1 0.4 nozzle"),
+        None
+    );
+    assert_eq!(
+        generator::delta_parent_name(
+            &registry,
+            &MaterialType::PETG,
+            "Bambu Lab X1 Carbon 0.4 nozzle"
+        ),
+        None
+    );
+}
+
+#[test]
+fn test_rebase_after_base_update() {
+    use bambumate_tauri::profile::rebase::{
+        current_base_fingerprint, rebase_profile, GenerationRecord,
+    };
+    use bambumate_tauri::scraper::types::FilamentSpecs;
+
+    let registry = include_fixture_registry();
+    let machines = MachineRegistry::new();
+    let specs = FilamentSpecs {
+        serial: "Matte".to_string(),
+        brand: "Polymaker".to_string(),
+        material: "PLA".to_string(),
+        nozzle_temperature: Some(215),
+        ..FilamentSpecs::default()
+    };
+    let printer = "Bambu Lab X1 Carbon 0.4 nozzle";
+    let (mut installed, metadata, _) =
+        generate_profile(&specs, &registry, &machines, Some(printer), None, None).unwrap();
+    installed.set_string("name", "My PLA".to_string());
+    let mut record = GenerationRecord::new(
+        metadata.setting_id,
+        "My PLA".to_string(),
+        specs,
+        Some(printer.to_string()),
+        "fdm_filament_pla".to_string(),
+        current_base_fingerprint(&registry, "fdm_filament_pla").unwrap(),
+    );
+    assert!(rebase_profile(&record, &installed, &registry, &machines)
+        .unwrap()
+        .is_none());
+
+    // Bambu Studio ships a new density for PLA
+    let mut updated = include_fixture_registry();
+    let mut pla = updated
+        .get_by_name("fdm_filament_pla")
+        .unwrap()
+        .raw()
+        .clone();
+    pla.insert("filament_density".to_string(), serde_json::json!(["1.30"]));
+    updated.insert(FilamentProfile::from_map(pla));
+
+    let rebased = rebase_profile(&record, &installed, &updated, &machines)
+        .unwrap()
+        .expect("base changed");
+    assert_eq!(
+        first_value(&rebased.profile, "filament_density").as_deref(),
+        Some("1.30")
+    );
+    assert_eq!(
+        first_value(&rebased.profile, "nozzle_temperature").as_deref(),
+        Some("215")
+    );
+    assert_eq!(rebased.profile.name(), Some("My PLA"));
+    assert_eq!(rebased.profile.filament_id(), installed.filament_id());
+
+    record.rejected_fingerprint = Some(rebased.base_fingerprint);
+    assert!(rebase_profile(&record, &installed, &updated, &machines)
+        .unwrap()
+        .is_none());
+}
@@ -7,12 +7,16 @@ use crate::commands::{self, FeatureFlags, UpdateInfo};
 use crate::components::sidebar::Sidebar;
 use crate::pages::about::AboutPage;
 use crate::pages::batch_generate::BatchGeneratePage;
+use crate::pages::calibration::CalibrationPage;
+use crate::pages::families::FamiliesPage;
 use crate::pages::filament_search::FilamentSearchPage;
 use crate::pages::health::HealthPage;
 use crate::pages::home::HomePage;
 use crate::pages::print_analysis::PrintAnalysisPage;
+use crate::pages::printers::PrintersPage;
 use crate::pages::profile_diff::ProfileDiffPage;
 use crate::pages::profile_management::ProfileManagementPage;
+use crate::pages::rebase::RebasePage;
 use crate::pages::settings::SettingsPage;
 use crate::pages::setup_wizard::SetupWizard;
 use crate::theme::{apply_theme, normalize_theme, ThemeContext};
@@ -114,7 +118,11 @@ pub fn App() -> impl IntoView {
                             <Route path=path!("/analysis") view=PrintAnalysisPage />
                             <Route path=path!("/profiles") view=ProfileManagementPage />
                             <Route path=path!("/batch") view=BatchGeneratePage />
+                            <Route path=path!("/families") view=FamiliesPage />
+                            <Route path=path!("/calibration") view=CalibrationPage />
+                            <Route path=path!("/printers") view=PrintersPage />
                             <Route path=path!("/compare") view=ProfileDiffPage />
+                            <Route path=path!("/updates") view=RebasePage />
                             <Route path=path!("/settings") view=SettingsPage />
                             <Route path=path!("/health") view=HealthPage />
                             <Route path=path!("/about") view=AboutPage />
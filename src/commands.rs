@@ -5,6 +5,9 @@ use wasm_bindgen::prelude::*;
 extern "C" {
     #[wasm_bindgen(js_namespace = ["window", "__TAURI__", "core"], catch)]
     async fn invoke(cmd: &str, args: JsValue) -> Result<JsValue, JsValue>;
+
+    #[wasm_bindgen(js_namespace = ["window", "__TAURI__", "event"], catch)]
+    async fn listen(event: &str, handler: &js_sys::Function) -> Result<JsValue, JsValue>;
 }
 
 // -- Feature Flags --
@@ -354,11 +357,27 @@ pub struct FilamentSpecs {
     // Legacy speed
     pub max_speed_mm_s: Option<u16>,
 
+    // Per-extruder overrides (index = extruder)
+    #[serde(default)]
+    pub extruder_overrides: Vec<ExtruderOverrides>,
+
     // Metadata
     pub source_url: String,
     pub extraction_confidence: f32,
 }
 
+/// Values for one extruder that differ from the filament-wide specs.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ExtruderOverrides {
+    pub nozzle_temperature: Option<u16>,
+    pub nozzle_temperature_initial_layer: Option<u16>,
+    pub max_volumetric_speed: Option<f32>,
+    pub filament_flow_ratio: Option<f32>,
+    pub pressure_advance: Option<f32>,
+    pub retraction_distance_mm: Option<f32>,
+    pub retraction_speed_mm_s: Option<u16>,
+}
+
 /// Material-type defaults for fields that can't be derived from other spec values.
 /// These match the well-known Bambu Studio community defaults.
 struct MaterialDefaults {
@@ -608,6 +627,8 @@ pub struct GenerateResult {
     pub diffs: Vec<ProfileDiff>,
     pub warnings: Vec<String>,
     pub bambu_studio_running: bool,
+    #[serde(default)]
+    pub variants: Vec<GenerateResult>,
 }
 
 /// Result from profile installation (files written to disk).
@@ -616,6 +637,9 @@ pub struct InstallResult {
     pub installed_path: String,
     pub profile_name: String,
     pub bambu_studio_was_running: bool,
+    /// Queued until Bambu Studio exits instead of written.
+    #[serde(default)]
+    pub queued: bool,
 }
 
 // -- Catalog types for autocomplete search --
@@ -667,6 +691,22 @@ struct GenerateProfileArgs {
     target_printer: Option<String>,
     base_profile_path: Option<String>,
     existing_filament_id: Option<String>,
+    delta: Option<bool>,
+    additional_printers: Option<Vec<String>>,
+}
+
+/// One profile for `install_generated_profiles`, as the backend's
+/// `ProfileInstall`.
+#[derive(Serialize)]
+struct ProfileInstall {
+    profile_json: String,
+    metadata_info: String,
+    filename: String,
+}
+
+#[derive(Serialize)]
+struct InstallProfilesArgs {
+    installs: Vec<ProfileInstall>,
 }
 
 #[derive(Serialize)]
@@ -723,17 +763,25 @@ pub async fn search_filament(name: &str) -> Result<FilamentSpecs, String> {
 
 /// Generate a filament profile from scraped specifications (preview only).
 /// Does NOT write any files. Returns the generated profile for UI preview.
+/// With `delta` the profile inherits from a system preset and keeps only
+/// the fields that differ from it. A variant for each of
+/// `additional_printers` comes back in `variants`, sharing the profile's
+/// `filament_id`.
 pub async fn generate_profile(
     specs: &FilamentSpecs,
     target_printer: Option<String>,
+    additional_printers: Vec<String>,
     base_profile_path: Option<String>,
     existing_filament_id: Option<String>,
+    delta: bool,
 ) -> Result<GenerateResult, String> {
     let args = serde_wasm_bindgen::to_value(&GenerateProfileArgs {
         specs: specs.clone(),
         target_printer,
         base_profile_path,
         existing_filament_id,
+        delta: Some(delta),
+        additional_printers: Some(additional_printers).filter(|p| !p.is_empty()),
     })
     .map_err(|e| e.to_string())?;
 
@@ -767,6 +815,29 @@ pub async fn install_profile(
     serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
 }
 
+/// Install several generated profiles together: all of them or none. The
+/// library is snapshotted first; while Bambu Studio is running they are
+/// queued until it closes.
+pub async fn install_profiles(generated: &[GenerateResult]) -> Result<Vec<InstallResult>, String> {
+    let args = serde_wasm_bindgen::to_value(&InstallProfilesArgs {
+        installs: generated
+            .iter()
+            .map(|g| ProfileInstall {
+                profile_json: g.profile_json.clone(),
+                metadata_info: g.metadata_info.clone(),
+                filename: g.filename.clone(),
+            })
+            .collect(),
+    })
+    .map_err(|e| e.to_string())?;
+
+    let result = invoke("install_generated_profiles", args)
+        .await
+        .map_err(|e| e.as_string().unwrap_or_else(|| "Unknown error".to_string()))?;
+
+    serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
+}
+
 pub fn default_target_printer_options() -> TargetPrinterOptions {
     TargetPrinterOptions {
         printer_models: vec![
@@ -951,6 +1022,15 @@ pub struct ProfileDetail {
     pub compatible_printers: Option<Vec<String>>,
     pub metadata: Option<ProfileMetadataInfo>,
     pub raw_json: String,
+    /// Version token to pass back when editing.
+    #[serde(default)]
+    pub version: String,
+    /// Set when the profile belongs to another Bambu account.
+    #[serde(default)]
+    pub sync_warning: Option<String>,
+    /// Set when the edit was queued until Bambu Studio exits.
+    #[serde(default)]
+    pub queued: bool,
 }
 
 /// Metadata from a .info companion file.
@@ -971,18 +1051,13 @@ struct ReadProfileArgs {
     path: String,
 }
 
-#[derive(Serialize)]
-#[serde(rename_all = "camelCase")]
-struct DeleteProfileArgs {
-    path: String,
-}
-
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 struct UpdateProfileFieldArgs {
     path: String,
     key: String,
     value: String,
+    expected_version: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -992,6 +1067,14 @@ struct DuplicateProfileArgs {
     new_name: String,
 }
 
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ConvertProfileToDeltaArgs {
+    path: String,
+    parent: Option<String>,
+    expected_version: Option<String>,
+}
+
 // -- Profile CRUD invoke wrappers --
 
 /// Read a single profile with full detail.
@@ -1008,29 +1091,19 @@ pub async fn read_profile(path: &str) -> Result<ProfileDetail, String> {
     serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
 }
 
-/// Delete a profile and its companion .info file.
-pub async fn delete_profile(path: &str) -> Result<(), String> {
-    let args = serde_wasm_bindgen::to_value(&DeleteProfileArgs {
-        path: path.to_string(),
-    })
-    .map_err(|e| e.to_string())?;
-
-    invoke("delete_profile", args)
-        .await
-        .map(|_| ())
-        .map_err(|e| e.as_string().unwrap_or_else(|| "Unknown error".to_string()))
-}
 
 /// Update a single field in a profile.
 pub async fn update_profile_field(
     path: &str,
     key: &str,
     value: &str,
+    expected_version: Option<&str>,
 ) -> Result<ProfileDetail, String> {
     let args = serde_wasm_bindgen::to_value(&UpdateProfileFieldArgs {
         path: path.to_string(),
         key: key.to_string(),
         value: value.to_string(),
+        expected_version: expected_version.map(|v| v.to_string()),
     })
     .map_err(|e| e.to_string())?;
 
@@ -1056,6 +1129,27 @@ pub async fn duplicate_profile(path: &str, new_name: &str) -> Result<ProfileDeta
     serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
 }
 
+/// Rewrite a flattened profile to inherit from `parent` (default: its
+/// printer's system preset), keeping only the fields that differ.
+pub async fn convert_profile_to_delta(
+    path: &str,
+    parent: Option<String>,
+    expected_version: Option<&str>,
+) -> Result<ProfileDetail, String> {
+    let args = serde_wasm_bindgen::to_value(&ConvertProfileToDeltaArgs {
+        path: path.to_string(),
+        parent,
+        expected_version: expected_version.map(|v| v.to_string()),
+    })
+    .map_err(|e| e.to_string())?;
+
+    let result = invoke("convert_profile_to_delta", args)
+        .await
+        .map_err(|e| e.as_string().unwrap_or_else(|| "Unknown error".to_string()))?;
+
+    serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
+}
+
 // -- Profile Specs Extraction/Save --
 
 #[derive(Serialize)]
@@ -1069,6 +1163,7 @@ struct ExtractSpecsArgs {
 struct SaveProfileSpecsArgs {
     path: String,
     specs: FilamentSpecs,
+    expected_version: Option<String>,
 }
 
 /// Extract FilamentSpecs from an existing profile for editing.
@@ -1089,10 +1184,12 @@ pub async fn extract_specs_from_profile(path: &str) -> Result<FilamentSpecs, Str
 pub async fn save_profile_specs(
     path: &str,
     specs: &FilamentSpecs,
+    expected_version: Option<&str>,
 ) -> Result<ProfileDetail, String> {
     let args = serde_wasm_bindgen::to_value(&SaveProfileSpecsArgs {
         path: path.to_string(),
         specs: specs.clone(),
+        expected_version: expected_version.map(|v| v.to_string()),
     })
     .map_err(|e| e.to_string())?;
 
@@ -1103,6 +1200,84 @@ pub async fn save_profile_specs(
     serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
 }
 
+/// One field that differs between the loaded, intended and on-disk
+/// versions of a profile.
+#[derive(Debug, Clone, Deserialize)]
+pub struct FieldConflict {
+    pub key: String,
+    pub base: Option<serde_json::Value>,
+    pub ours: Option<serde_json::Value>,
+    pub theirs: Option<serde_json::Value>,
+    pub conflicting: bool,
+}
+
+/// An edit refused because the profile changed on disk since it was loaded.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProfileConflict {
+    pub message: String,
+    pub current_version: String,
+    pub has_base: bool,
+    pub fields: Vec<FieldConflict>,
+}
+
+impl ProfileConflict {
+    /// The conflict carried by an `update_profile_field`,
+    /// `update_profile_fields` or `save_profile_specs` error, if that's what
+    /// the error is.
+    pub fn from_error(error: &str) -> Option<Self> {
+        serde_json::from_str(error).ok()
+    }
+}
+
+/// A user profile created, changed or removed on disk.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProfileChange {
+    pub path: String,
+    pub kind: String,
+    pub version: Option<String>,
+}
+
+/// A backend event subscription, to one or more events. Dropping it
+/// unsubscribes.
+pub struct EventListener {
+    _handler: Closure<dyn FnMut(JsValue)>,
+    unlisten: Vec<js_sys::Function>,
+}
+
+impl Drop for EventListener {
+    fn drop(&mut self) {
+        for unlisten in &self.unlisten {
+            let _ = unlisten.call0(&JsValue::NULL);
+        }
+    }
+}
+
+/// Call `on_change` for each user profile changed on disk, by Bambu Studio
+/// or anything else, for as long as the returned listener is kept.
+pub async fn listen_profile_changes(
+    on_change: impl Fn(ProfileChange) + 'static,
+) -> Result<EventListener, String> {
+    let handler = Closure::<dyn FnMut(JsValue)>::new(move |event: JsValue| {
+        let payload =
+            js_sys::Reflect::get(&event, &JsValue::from_str("payload")).unwrap_or(JsValue::NULL);
+        if let Ok(change) = serde_wasm_bindgen::from_value::<ProfileChange>(payload) {
+            on_change(change);
+        }
+    });
+
+    let unlisten = listen("profile-changed", handler.as_ref().unchecked_ref())
+        .await
+        .map_err(|e| e.as_string().unwrap_or_else(|| "Unknown error".to_string()))?;
+
+    Ok(EventListener {
+        _handler: handler,
+        unlisten: unlisten
+            .dyn_into::<js_sys::Function>()
+            .into_iter()
+            .collect(),
+    })
+}
+
 // -- Print Analysis --
 
 /// Inner request matching the backend AnalyzeRequest struct.
@@ -1111,6 +1286,7 @@ struct AnalyzeRequest {
     image_base64: String,
     profile_path: Option<String>,
     material_type: Option<String>,
+    calibration: Option<CalibrationContext>,
 }
 
 /// Wrapper to provide the `request` key expected by the Tauri command.
@@ -1119,17 +1295,20 @@ struct AnalyzePrintArgs {
     request: AnalyzeRequest,
 }
 
-/// Analyze a print photo for defects.
+/// Analyze a print photo for defects. `calibration` is set when the photo
+/// shows the samples of a calibration kit.
 pub async fn analyze_print(
     image_base64: String,
     profile_path: Option<String>,
     material_type: Option<String>,
+    calibration: Option<CalibrationContext>,
 ) -> Result<crate::pages::print_analysis::AnalyzeResponse, String> {
     let args = AnalyzePrintArgs {
         request: AnalyzeRequest {
             image_base64,
             profile_path,
             material_type,
+            calibration,
         },
     };
 
@@ -1145,6 +1324,69 @@ pub async fn analyze_print(
     })
 }
 
+// -- Calibration Kits --
+
+/// One sample of a calibration kit: its STL and profile variant.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CalibrationSample {
+    pub value: f32,
+    pub label: String,
+    pub stl_path: String,
+    pub profile_name: String,
+    pub installed_path: Option<String>,
+}
+
+/// A generated calibration kit. `test` is the backend's test id, e.g.
+/// "temperature_tower".
+#[derive(Debug, Clone, Deserialize)]
+pub struct CalibrationKit {
+    pub test: String,
+    pub test_name: String,
+    pub parameter: String,
+    pub samples: Vec<CalibrationSample>,
+    pub queued: bool,
+}
+
+/// What a photo of calibration samples shows: the test and each sample's
+/// value, left to right.
+#[derive(Debug, Clone, Serialize)]
+pub struct CalibrationContext {
+    pub test: String,
+    pub values: Vec<f32>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GenerateCalibrationKitArgs {
+    test: String,
+    specs: FilamentSpecs,
+    target_printer: Option<String>,
+    install: bool,
+}
+
+/// Generate a calibration kit: STLs in the STL watch folder and a profile
+/// variant per sample, installed (or queued) when `install` is set.
+pub async fn generate_calibration_kit(
+    test: &str,
+    specs: &FilamentSpecs,
+    target_printer: Option<String>,
+    install: bool,
+) -> Result<CalibrationKit, String> {
+    let args = serde_wasm_bindgen::to_value(&GenerateCalibrationKitArgs {
+        test: test.to_string(),
+        specs: specs.clone(),
+        target_printer,
+        install,
+    })
+    .map_err(|e| e.to_string())?;
+
+    let result = invoke("generate_calibration_kit", args)
+        .await
+        .map_err(|e| e.as_string().unwrap_or_else(|| "Unknown error".to_string()))?;
+
+    serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
+}
+
 // -- Apply Recommendations Types --
 
 /// Result of applying recommendations to a profile.
@@ -1156,6 +1398,9 @@ pub struct ApplyResult {
     pub changes_applied: Vec<AppliedChange>,
     /// Path to the modified profile
     pub profile_path: String,
+    /// Queued until Bambu Studio exits; no backup yet
+    #[serde(default)]
+    pub queued: bool,
 }
 
 // -- History Types --
@@ -1359,6 +1604,9 @@ pub struct BatchProgress {
     pub succeeded: usize,
     pub failed: usize,
     pub results: Vec<BatchEntry>,
+    /// Set when the installs were queued until Bambu Studio exits.
+    #[serde(default)]
+    pub queued: bool,
 }
 
 #[derive(Serialize)]
@@ -1641,6 +1889,834 @@ pub async fn search_base_profiles(
     serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
 }
 
+/// A profile matched by `query_profiles`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ProfileQueryMatch {
+    pub name: String,
+    pub path: String,
+    pub filament_type: Option<String>,
+    /// Each field the query mentions, with this profile's value for it.
+    pub fields: Vec<(String, String)>,
+}
+
+/// Result of a profile query: the first matches and the total count.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ProfileQueryResult {
+    pub matches: Vec<ProfileQueryMatch>,
+    pub total: usize,
+}
+
+#[derive(Serialize)]
+struct QueryProfilesArgs {
+    query: String,
+}
+
+/// Find system and user profiles matching a query expression such as
+/// `filament_type = PETG and nozzle_temperature[0] > 250`.
+pub async fn query_profiles(query: &str) -> Result<ProfileQueryResult, String> {
+    let args = serde_wasm_bindgen::to_value(&QueryProfilesArgs {
+        query: query.to_string(),
+    })
+    .map_err(|e| e.to_string())?;
+
+    let result = invoke("query_profiles", args)
+        .await
+        .map_err(|e| e.as_string().unwrap_or_else(|| "Unknown error".to_string()))?;
+
+    serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
+}
+
+// -- Library Snapshots --
+
+/// A snapshot of the user filament library and `BambuStudio.conf`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SnapshotInfo {
+    pub id: String,
+    pub created_at: String,
+    pub reason: String,
+    pub file_count: usize,
+    pub has_conf: bool,
+}
+
+/// One file that differs between a snapshot and the current library.
+/// `kind` is "added", "removed" or "modified".
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SnapshotFileChange {
+    pub path: String,
+    pub kind: String,
+}
+
+/// A snapshot with the files changed since it was taken.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SnapshotSummary {
+    #[serde(flatten)]
+    pub info: SnapshotInfo,
+    pub changes: Vec<SnapshotFileChange>,
+}
+
+#[derive(Serialize)]
+struct CreateSnapshotArgs {
+    label: Option<String>,
+}
+
+/// Snapshot the user filament library now.
+pub async fn create_library_snapshot(label: Option<String>) -> Result<SnapshotInfo, String> {
+    let args =
+        serde_wasm_bindgen::to_value(&CreateSnapshotArgs { label }).map_err(|e| e.to_string())?;
+
+    let result = invoke("create_library_snapshot", args)
+        .await
+        .map_err(|e| e.as_string().unwrap_or_else(|| "Unknown error".to_string()))?;
+
+    serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
+}
+
+/// List library snapshots, newest first.
+pub async fn list_library_snapshots() -> Result<Vec<SnapshotSummary>, String> {
+    let args = serde_wasm_bindgen::to_value(&serde_json::json!({})).map_err(|e| e.to_string())?;
+
+    let result = invoke("list_library_snapshots", args)
+        .await
+        .map_err(|e| e.as_string().unwrap_or_else(|| "Unknown error".to_string()))?;
+
+    serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
+}
+
+#[derive(Serialize)]
+struct RestoreSnapshotArgs {
+    id: String,
+    profiles: Option<Vec<String>>,
+    force: bool,
+}
+
+/// Restore a snapshot, or only the given files from it. Returns the
+/// restored paths.
+pub async fn restore_library_snapshot(
+    id: &str,
+    profiles: Option<Vec<String>>,
+    force: bool,
+) -> Result<Vec<String>, String> {
+    let args = serde_wasm_bindgen::to_value(&RestoreSnapshotArgs {
+        id: id.to_string(),
+        profiles,
+        force,
+    })
+    .map_err(|e| e.to_string())?;
+
+    let result = invoke("restore_library_snapshot", args)
+        .await
+        .map_err(|e| e.as_string().unwrap_or_else(|| "Unknown error".to_string()))?;
+
+    serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
+}
+
+// -- Profile Delete / Rename --
+
+/// A user profile that inherits from a profile being deleted or renamed.
+/// `action` is "reparent", "flatten" or "delete".
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct DependentChange {
+    pub name: String,
+    pub path: String,
+    pub action: String,
+    pub new_parent: Option<String>,
+    pub added_fields: Vec<String>,
+    pub note: Option<String>,
+}
+
+/// Everything a delete or rename touches, as previewed or as done.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RemovalPlan {
+    pub name: String,
+    pub path: String,
+    pub new_name: Option<String>,
+    pub new_path: Option<String>,
+    pub dependents: Vec<DependentChange>,
+    pub unregister: Vec<String>,
+    pub register: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct DeleteProfileArgs {
+    path: String,
+    policy: Option<String>,
+}
+
+#[derive(Serialize)]
+struct RunDeleteProfileArgs {
+    path: String,
+    policy: Option<String>,
+    force: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RenameProfileArgs {
+    path: String,
+    new_name: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RunRenameProfileArgs {
+    path: String,
+    new_name: String,
+    force: bool,
+}
+
+/// Preview deleting a profile. `policy` ("reparent", "flatten" or
+/// "cascade") says what happens to the profiles that inherit from it.
+pub async fn preview_profile_delete(path: &str, policy: &str) -> Result<RemovalPlan, String> {
+    let args = serde_wasm_bindgen::to_value(&DeleteProfileArgs {
+        path: path.to_string(),
+        policy: Some(policy.to_string()),
+    })
+    .map_err(|e| e.to_string())?;
+
+    let result = invoke("preview_profile_delete", args)
+        .await
+        .map_err(|e| e.as_string().unwrap_or_else(|| "Unknown error".to_string()))?;
+
+    serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
+}
+
+/// Delete a profile and its companion .info file, unregister it from
+/// BambuStudio.conf and apply `policy` to the profiles that inherit from it.
+/// Needs `force` while Bambu Studio is running.
+pub async fn delete_profile(path: &str, policy: &str, force: bool) -> Result<RemovalPlan, String> {
+    let args = serde_wasm_bindgen::to_value(&RunDeleteProfileArgs {
+        path: path.to_string(),
+        policy: Some(policy.to_string()),
+        force,
+    })
+    .map_err(|e| e.to_string())?;
+
+    let result = invoke("delete_profile", args)
+        .await
+        .map_err(|e| e.as_string().unwrap_or_else(|| "Unknown error".to_string()))?;
+
+    serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
+}
+
+/// Preview renaming a profile.
+pub async fn preview_profile_rename(path: &str, new_name: &str) -> Result<RemovalPlan, String> {
+    let args = serde_wasm_bindgen::to_value(&RenameProfileArgs {
+        path: path.to_string(),
+        new_name: new_name.to_string(),
+    })
+    .map_err(|e| e.to_string())?;
+
+    let result = invoke("preview_profile_rename", args)
+        .await
+        .map_err(|e| e.as_string().unwrap_or_else(|| "Unknown error".to_string()))?;
+
+    serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
+}
+
+/// Rename a profile and point the profiles that inherit from it at the new
+/// name. Needs `force` while Bambu Studio is running.
+pub async fn rename_profile(
+    path: &str,
+    new_name: &str,
+    force: bool,
+) -> Result<RemovalPlan, String> {
+    let args = serde_wasm_bindgen::to_value(&RunRenameProfileArgs {
+        path: path.to_string(),
+        new_name: new_name.to_string(),
+        force,
+    })
+    .map_err(|e| e.to_string())?;
+
+    let result = invoke("rename_profile", args)
+        .await
+        .map_err(|e| e.as_string().unwrap_or_else(|| "Unknown error".to_string()))?;
+
+    serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
+}
+
+// -- Pending Writes --
+
+/// A profile write queued until Bambu Studio exits. `error` is set when
+/// the last attempt to write it failed.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PendingWrite {
+    pub id: String,
+    pub queued_at: String,
+    pub summary: String,
+    pub target: String,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// Summaries of the queued writes written and failed by a flush.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct FlushReport {
+    pub written: Vec<String>,
+    pub failed: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct DiscardPendingWriteArgs {
+    id: String,
+}
+
+/// Profile writes waiting for Bambu Studio to exit, oldest first.
+pub async fn list_pending_writes() -> Result<Vec<PendingWrite>, String> {
+    let args = serde_wasm_bindgen::to_value(&serde_json::json!({})).map_err(|e| e.to_string())?;
+
+    let result = invoke("list_pending_writes", args)
+        .await
+        .map_err(|e| e.as_string().unwrap_or_else(|| "Unknown error".to_string()))?;
+
+    serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
+}
+
+/// Drop a queued write without applying it.
+pub async fn discard_pending_write(id: &str) -> Result<(), String> {
+    let args = serde_wasm_bindgen::to_value(&DiscardPendingWriteArgs { id: id.to_string() })
+        .map_err(|e| e.to_string())?;
+
+    invoke("discard_pending_write", args)
+        .await
+        .map(|_| ())
+        .map_err(|e| e.as_string().unwrap_or_else(|| "Unknown error".to_string()))
+}
+
+/// Write the queue now, retrying failed writes. Fails while Bambu Studio
+/// is still running.
+pub async fn flush_pending_writes() -> Result<FlushReport, String> {
+    let args = serde_wasm_bindgen::to_value(&serde_json::json!({})).map_err(|e| e.to_string())?;
+
+    let result = invoke("flush_pending_writes", args)
+        .await
+        .map_err(|e| e.as_string().unwrap_or_else(|| "Unknown error".to_string()))?;
+
+    serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
+}
+
+/// Call `on_change` with the whole queue each time it changes, for as long
+/// as the returned listener is kept.
+pub async fn listen_pending_writes(
+    on_change: impl Fn(Vec<PendingWrite>) + 'static,
+) -> Result<EventListener, String> {
+    let handler = Closure::<dyn FnMut(JsValue)>::new(move |event: JsValue| {
+        let payload =
+            js_sys::Reflect::get(&event, &JsValue::from_str("payload")).unwrap_or(JsValue::NULL);
+        if let Ok(items) = serde_wasm_bindgen::from_value::<Vec<PendingWrite>>(payload) {
+            on_change(items);
+        }
+    });
+
+    let unlisten = listen("pending-writes-changed", handler.as_ref().unchecked_ref())
+        .await
+        .map_err(|e| e.as_string().unwrap_or_else(|| "Unknown error".to_string()))?;
+
+    Ok(EventListener {
+        _handler: handler,
+        unlisten: unlisten
+            .dyn_into::<js_sys::Function>()
+            .into_iter()
+            .collect(),
+    })
+}
+
+// -- Field Dictionary --
+
+/// What the backend knows about one profile field. `kind` is "text",
+/// "int", "float", "percent", "bool" or "enum"; `arity` is "scalar",
+/// "per_extruder" or "list".
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct FieldInfo {
+    pub key: String,
+    pub label: String,
+    pub category: String,
+    pub kind: String,
+    pub arity: String,
+    #[serde(default)]
+    pub unit: Option<String>,
+    #[serde(default)]
+    pub options: Vec<String>,
+    #[serde(default)]
+    pub range: Option<[f64; 2]>,
+    #[serde(default)]
+    pub step: Option<f64>,
+    #[serde(default)]
+    pub nullable: bool,
+    #[serde(default)]
+    pub required: bool,
+    #[serde(default)]
+    pub identity: bool,
+    /// Belongs to the preset file, so it's never inherited.
+    #[serde(default)]
+    pub metadata: bool,
+}
+
+/// Every field in the backend's field dictionary.
+pub async fn get_field_dictionary() -> Result<Vec<FieldInfo>, String> {
+    let args = serde_wasm_bindgen::to_value(&serde_json::json!({})).map_err(|e| e.to_string())?;
+
+    let result = invoke("get_field_dictionary", args)
+        .await
+        .map_err(|e| e.as_string().unwrap_or_else(|| "Unknown error".to_string()))?;
+
+    serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
+}
+
+// -- Raw Field Editor --
+
+/// One field of a profile. `value` is `None` when the profile inherits it;
+/// `inherited` is what its parent chain gives it.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RawField {
+    pub key: String,
+    pub label: String,
+    pub category: String,
+    #[serde(default)]
+    pub value: Option<serde_json::Value>,
+    #[serde(default)]
+    pub inherited: Option<serde_json::Value>,
+}
+
+/// Every field a user profile sets or inherits, sorted by category.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProfileFields {
+    pub inherits: Option<String>,
+    #[serde(default)]
+    pub extruder_count: Option<usize>,
+    pub fields: Vec<RawField>,
+}
+
+/// One change to a profile field: set it to a string or array of
+/// strings, or remove it so it's inherited.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum FieldEdit {
+    Set {
+        key: String,
+        value: serde_json::Value,
+    },
+    Inherit {
+        key: String,
+    },
+}
+
+/// A lint problem in an edited field. `severity` is "error" or "warning";
+/// `index` is the element for per-extruder and list fields.
+#[derive(Debug, Clone, Deserialize)]
+pub struct FieldDiagnostic {
+    pub severity: String,
+    pub key: String,
+    #[serde(default)]
+    pub index: Option<usize>,
+    pub message: String,
+}
+
+/// Result of applying field edits. `backup_path` is `None` when the edits
+/// were queued until Bambu Studio exits.
+#[derive(Debug, Clone, Deserialize)]
+pub struct FieldEditResult {
+    pub detail: ProfileDetail,
+    #[serde(default)]
+    pub backup_path: Option<String>,
+}
+
+#[derive(Serialize)]
+struct ProfileFieldEditsArgs {
+    path: String,
+    edits: Vec<FieldEdit>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct UpdateProfileFieldsArgs {
+    path: String,
+    edits: Vec<FieldEdit>,
+    expected_version: Option<String>,
+}
+
+/// Read every field of a user profile, with the values it inherits.
+pub async fn get_profile_fields(path: &str) -> Result<ProfileFields, String> {
+    let args = serde_wasm_bindgen::to_value(&ReadProfileArgs {
+        path: path.to_string(),
+    })
+    .map_err(|e| e.to_string())?;
+
+    let result = invoke("get_profile_fields", args)
+        .await
+        .map_err(|e| e.as_string().unwrap_or_else(|| "Unknown error".to_string()))?;
+
+    serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
+}
+
+/// Lint field edits without writing them.
+pub async fn check_profile_field_edits(
+    path: &str,
+    edits: &[FieldEdit],
+) -> Result<Vec<FieldDiagnostic>, String> {
+    let args = serde_wasm_bindgen::to_value(&ProfileFieldEditsArgs {
+        path: path.to_string(),
+        edits: edits.to_vec(),
+    })
+    .map_err(|e| e.to_string())?;
+
+    let result = invoke("check_profile_field_edits", args)
+        .await
+        .map_err(|e| e.as_string().unwrap_or_else(|| "Unknown error".to_string()))?;
+
+    serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
+}
+
+/// Apply field edits to a profile in one write, after backing it up.
+pub async fn update_profile_fields(
+    path: &str,
+    edits: &[FieldEdit],
+    expected_version: Option<&str>,
+) -> Result<FieldEditResult, String> {
+    let args = serde_wasm_bindgen::to_value(&UpdateProfileFieldsArgs {
+        path: path.to_string(),
+        edits: edits.to_vec(),
+        expected_version: expected_version.map(|v| v.to_string()),
+    })
+    .map_err(|e| e.to_string())?;
+
+    let result = invoke("update_profile_fields", args)
+        .await
+        .map_err(|e| e.as_string().unwrap_or_else(|| "Unknown error".to_string()))?;
+
+    serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
+}
+
+// -- Filament Families --
+
+/// One printer/nozzle variant of a family.
+#[derive(Debug, Clone, Deserialize)]
+pub struct FamilyVariant {
+    pub path: String,
+    pub name: String,
+    pub printer: Option<String>,
+    /// Version token to pass back when editing.
+    pub version: String,
+}
+
+/// A field whose value isn't the same across a family.
+#[derive(Debug, Clone, Deserialize)]
+pub struct FieldDivergence {
+    pub key: String,
+    /// One value per variant, in `FilamentFamily::variants` order.
+    pub values: Vec<String>,
+}
+
+/// Profiles sharing a `filament_id`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct FilamentFamily {
+    pub filament_id: String,
+    pub display_name: String,
+    pub variants: Vec<FamilyVariant>,
+    pub divergences: Vec<FieldDivergence>,
+}
+
+/// Result of a family edit.
+#[derive(Debug, Clone, Deserialize)]
+pub struct FamilyEdit {
+    pub family: FilamentFamily,
+    /// Queued until Bambu Studio exits instead of written.
+    pub queued: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct UpdateFamilyFieldArgs {
+    filament_id: String,
+    key: String,
+    value: String,
+    expected_versions: std::collections::HashMap<String, String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GenerateFamilyVariantsArgs {
+    filament_id: String,
+    printers: Vec<String>,
+}
+
+/// List the user's filament families, with the fields their variants
+/// disagree on.
+pub async fn list_filament_families() -> Result<Vec<FilamentFamily>, String> {
+    let args = serde_wasm_bindgen::to_value(&serde_json::json!({})).map_err(|e| e.to_string())?;
+
+    let result = invoke("list_filament_families", args)
+        .await
+        .map_err(|e| e.as_string().unwrap_or_else(|| "Unknown error".to_string()))?;
+
+    serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
+}
+
+/// Set `key` to `value` (JSON) in every variant of a family. Refused if any
+/// variant changed since `family` was listed.
+pub async fn update_family_field(
+    family: &FilamentFamily,
+    key: &str,
+    value: &str,
+) -> Result<FamilyEdit, String> {
+    let args = serde_wasm_bindgen::to_value(&UpdateFamilyFieldArgs {
+        filament_id: family.filament_id.clone(),
+        key: key.to_string(),
+        value: value.to_string(),
+        expected_versions: family
+            .variants
+            .iter()
+            .map(|v| (v.path.clone(), v.version.clone()))
+            .collect(),
+    })
+    .map_err(|e| e.to_string())?;
+
+    let result = invoke("update_family_field", args)
+        .await
+        .map_err(|e| e.as_string().unwrap_or_else(|| "Unknown error".to_string()))?;
+
+    serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
+}
+
+/// Generate and install a variant of a family for each of `printers` it
+/// doesn't have yet. Queued while Bambu Studio is running.
+pub async fn generate_family_variants(
+    filament_id: &str,
+    printers: Vec<String>,
+) -> Result<Vec<InstallResult>, String> {
+    let args = serde_wasm_bindgen::to_value(&GenerateFamilyVariantsArgs {
+        filament_id: filament_id.to_string(),
+        printers,
+    })
+    .map_err(|e| e.to_string())?;
+
+    let result = invoke("generate_family_variants", args)
+        .await
+        .map_err(|e| e.as_string().unwrap_or_else(|| "Unknown error".to_string()))?;
+
+    serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
+}
+
+// -- Base Updates (Rebase) --
+
+/// An installed profile whose base changed since it was generated.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RebaseCandidate {
+    pub path: String,
+    pub profile_name: String,
+    pub base_name: String,
+    /// Installed profile (a) against the regenerated one (b).
+    pub diff: CompareResult,
+}
+
+/// Result of scanning the user filament directory for rebases.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RebaseReport {
+    pub candidates: Vec<RebaseCandidate>,
+    pub errors: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ResolveProfileRebaseArgs {
+    path: String,
+    accept: bool,
+}
+
+/// Find generated profiles whose base profile changed since, each with the
+/// changes regenerating it would make. Nothing is written.
+pub async fn check_profile_rebases() -> Result<RebaseReport, String> {
+    let args = serde_wasm_bindgen::to_value(&serde_json::json!({})).map_err(|e| e.to_string())?;
+
+    let result = invoke("check_profile_rebases", args)
+        .await
+        .map_err(|e| e.as_string().unwrap_or_else(|| "Unknown error".to_string()))?;
+
+    serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
+}
+
+/// Accept (regenerate and write) or reject the rebase of a profile.
+/// Returns true if the write was queued until Bambu Studio exits.
+pub async fn resolve_profile_rebase(path: &str, accept: bool) -> Result<bool, String> {
+    let args = serde_wasm_bindgen::to_value(&ResolveProfileRebaseArgs {
+        path: path.to_string(),
+        accept,
+    })
+    .map_err(|e| e.to_string())?;
+
+    let result = invoke("resolve_profile_rebase", args)
+        .await
+        .map_err(|e| e.as_string().unwrap_or_else(|| "Unknown error".to_string()))?;
+
+    serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
+}
+
+// -- Printers --
+
+/// How to reach a printer on the LAN.
+#[derive(Debug, Clone, Serialize)]
+pub struct PrinterConfig {
+    pub serial: String,
+    pub host: String,
+    pub access_code: String,
+    pub name: Option<String>,
+}
+
+/// A loaded (or empty) AMS tray. `color` is RRGGBBAA hex.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct AmsTray {
+    pub id: String,
+    pub filament_type: Option<String>,
+    pub sub_brand: Option<String>,
+    pub color: Option<String>,
+    pub filament_id: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct AmsUnit {
+    pub id: String,
+    pub trays: Vec<AmsTray>,
+}
+
+/// The parts of a printer's merged status the frontend shows.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct PrinterStatus {
+    #[serde(default)]
+    pub ams: Vec<AmsUnit>,
+    #[serde(default)]
+    pub external_spool: Option<AmsTray>,
+}
+
+/// A connected printer. `state` is "connecting", "connected",
+/// "reconnecting" or "disconnected".
+#[derive(Debug, Clone, Deserialize)]
+pub struct PrinterSummary {
+    pub serial: String,
+    pub name: Option<String>,
+    pub host: String,
+    pub state: String,
+    pub status: PrinterStatus,
+}
+
+/// An installed profile a tray resolves to.
+#[derive(Debug, Clone, Deserialize)]
+pub struct MatchedProfile {
+    pub name: String,
+    pub is_user_profile: bool,
+}
+
+/// One AMS tray and the profiles it resolves to. `state` is "empty",
+/// "matched" or "unmatched"; unmatched trays carry `suggested_specs`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TrayResolution {
+    pub slot: String,
+    pub tray: AmsTray,
+    pub state: String,
+    pub profiles: Vec<MatchedProfile>,
+    pub suggested_specs: Option<FilamentSpecs>,
+}
+
+/// Pushed by the backend for each status report and connection change.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PrinterEvent {
+    Status {
+        serial: String,
+        status: PrinterStatus,
+    },
+    Connection {
+        serial: String,
+        state: String,
+        message: Option<String>,
+    },
+}
+
+#[derive(Serialize)]
+struct ConnectPrinterArgs {
+    config: PrinterConfig,
+}
+
+#[derive(Serialize)]
+struct PrinterSerialArgs {
+    serial: String,
+}
+
+/// Connect to a printer. Returns once the connection is started; the
+/// outcome arrives as a printer event.
+pub async fn connect_printer(config: PrinterConfig) -> Result<(), String> {
+    let args =
+        serde_wasm_bindgen::to_value(&ConnectPrinterArgs { config }).map_err(|e| e.to_string())?;
+
+    invoke("connect_printer", args)
+        .await
+        .map_err(|e| e.as_string().unwrap_or_else(|| "Unknown error".to_string()))?;
+    Ok(())
+}
+
+pub async fn disconnect_printer(serial: &str) -> Result<(), String> {
+    let args = serde_wasm_bindgen::to_value(&PrinterSerialArgs {
+        serial: serial.to_string(),
+    })
+    .map_err(|e| e.to_string())?;
+
+    invoke("disconnect_printer", args)
+        .await
+        .map_err(|e| e.as_string().unwrap_or_else(|| "Unknown error".to_string()))?;
+    Ok(())
+}
+
+pub async fn list_printers() -> Result<Vec<PrinterSummary>, String> {
+    let args = serde_wasm_bindgen::to_value(&serde_json::json!({})).map_err(|e| e.to_string())?;
+
+    let result = invoke("list_printers", args)
+        .await
+        .map_err(|e| e.as_string().unwrap_or_else(|| "Unknown error".to_string()))?;
+
+    serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
+}
+
+/// Match a connected printer's AMS trays to installed filament profiles.
+pub async fn resolve_ams_trays(serial: &str) -> Result<Vec<TrayResolution>, String> {
+    let args = serde_wasm_bindgen::to_value(&PrinterSerialArgs {
+        serial: serial.to_string(),
+    })
+    .map_err(|e| e.to_string())?;
+
+    let result = invoke("resolve_ams_trays", args)
+        .await
+        .map_err(|e| e.as_string().unwrap_or_else(|| "Unknown error".to_string()))?;
+
+    serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
+}
+
+/// Call `on_event` for each printer status report and connection change,
+/// for as long as the returned listener is kept.
+pub async fn listen_printer_events(
+    on_event: impl Fn(PrinterEvent) + 'static,
+) -> Result<EventListener, String> {
+    let handler = Closure::<dyn FnMut(JsValue)>::new(move |event: JsValue| {
+        let payload =
+            js_sys::Reflect::get(&event, &JsValue::from_str("payload")).unwrap_or(JsValue::NULL);
+        if let Ok(event) = serde_wasm_bindgen::from_value::<PrinterEvent>(payload) {
+            on_event(event);
+        }
+    });
+
+    // On failure, dropping the listener undoes the subscriptions made so far
+    let mut listener = EventListener {
+        _handler: handler,
+        unlisten: Vec::new(),
+    };
+    for event in ["printer-status", "printer-connection"] {
+        let unlisten = listen(event, listener._handler.as_ref().unchecked_ref())
+            .await
+            .map_err(|e| e.as_string().unwrap_or_else(|| "Unknown error".to_string()))?;
+        listener
+            .unlisten
+            .extend(unlisten.dyn_into::<js_sys::Function>().ok());
+    }
+    Ok(listener)
+}
+
 // -- Version / Auto-Update --
 
 /// Current app version returned by the backend.
@@ -0,0 +1,681 @@
+//! Editor for every field of a user profile, not just the ones in
+//! `FilamentSpecs`: typed inputs from the field dictionary, per-extruder
+//! values, `nil` and inherit toggles. Changes are collected and linted as
+//! they're made, then applied in one write with a backup.
+
+use std::collections::{BTreeMap, HashMap};
+
+use leptos::prelude::*;
+use serde_json::Value;
+use wasm_bindgen_futures::spawn_local;
+
+use crate::commands::{
+    self, FieldDiagnostic, FieldEdit, FieldEditResult, FieldInfo, ProfileFields, RawField,
+};
+
+/// Element value meaning "use the printer's value".
+const NIL: &str = "nil";
+
+/// Longest value shown in full for an inherited field.
+const MAX_PREVIEW_CHARS: usize = 80;
+
+/// The elements of a field value, and whether it's an array.
+fn elements(value: &Value) -> (Vec<String>, bool) {
+    let text = |v: &Value| {
+        v.as_str()
+            .map(str::to_string)
+            .unwrap_or_else(|| v.to_string())
+    };
+    match value {
+        Value::Array(items) => (items.iter().map(text).collect(), true),
+        other => (vec![text(other)], false),
+    }
+}
+
+fn to_value(elements: Vec<String>, is_array: bool) -> Value {
+    if is_array {
+        Value::Array(elements.into_iter().map(Value::String).collect())
+    } else {
+        Value::String(elements.into_iter().next().unwrap_or_default())
+    }
+}
+
+fn display_value(value: &Value) -> String {
+    let text = elements(value).0.join(", ");
+    if text.chars().count() > MAX_PREVIEW_CHARS {
+        format!(
+            "{}…",
+            text.chars().take(MAX_PREVIEW_CHARS).collect::<String>()
+        )
+    } else {
+        text
+    }
+}
+
+/// Empty value of the right shape for a field the profile doesn't have.
+fn blank_value(info: Option<&FieldInfo>, extruder_count: usize) -> Value {
+    match info.map(|f| f.arity.as_str()) {
+        Some("scalar") => Value::String(String::new()),
+        Some("per_extruder") => to_value(vec![String::new(); extruder_count], true),
+        _ => to_value(vec![String::new()], true),
+    }
+}
+
+/// Input for one element of a field value, typed by the field's kind.
+fn element_input(
+    info: Option<&FieldInfo>,
+    multiline: bool,
+    item: String,
+    disabled: bool,
+    set_item: impl Fn(String) + Clone + 'static,
+) -> AnyView {
+    let kind = info.map(|f| f.kind.as_str()).unwrap_or("text");
+    match kind {
+        "bool" => view! {
+            <input
+                type="checkbox"
+                prop:checked=item == "1"
+                disabled=disabled
+                on:change=move |ev| {
+                    set_item(if event_target_checked(&ev) { "1" } else { "0" }.to_string())
+                }
+            />
+        }
+        .into_any(),
+        "enum" => {
+            let mut options = info.map(|f| f.options.clone()).unwrap_or_default();
+            if !options.contains(&item) {
+                options.insert(0, item.clone());
+            }
+            view! {
+                <select
+                    class="field-editor-input"
+                    disabled=disabled
+                    on:change=move |ev| set_item(event_target_value(&ev))
+                >
+                    {options.into_iter().map(|o| {
+                        let selected = o == item;
+                        let label = o.clone();
+                        view! { <option value=o selected=selected>{label}</option> }
+                    }).collect::<Vec<_>>()}
+                </select>
+            }
+            .into_any()
+        }
+        "int" | "float" => {
+            let range = info.and_then(|f| f.range);
+            let step = match info.and_then(|f| f.step) {
+                Some(step) => step.to_string(),
+                None if kind == "int" => "1".to_string(),
+                None => "any".to_string(),
+            };
+            view! {
+                <input
+                    type="number"
+                    class="field-editor-input"
+                    min=range.map(|[min, _]| min.to_string())
+                    max=range.map(|[_, max]| max.to_string())
+                    step=step
+                    prop:value=item
+                    disabled=disabled
+                    on:change=move |ev| set_item(event_target_value(&ev))
+                />
+            }
+            .into_any()
+        }
+        _ if multiline => view! {
+            <textarea
+                class="field-editor-input field-editor-textarea"
+                rows="4"
+                prop:value=item
+                disabled=disabled
+                on:change=move |ev| set_item(event_target_value(&ev))
+            ></textarea>
+        }
+        .into_any(),
+        _ => view! {
+            <input
+                type="text"
+                class="field-editor-input"
+                prop:value=item
+                disabled=disabled
+                on:change=move |ev| set_item(event_target_value(&ev))
+            />
+        }
+        .into_any(),
+    }
+}
+
+/// Inputs for every element of `value`, with the per-extruder layout,
+/// `nil` and list controls the field allows.
+fn value_editor(
+    info: Option<&FieldInfo>,
+    key: &str,
+    value: &Value,
+    extruder_count: usize,
+    on_change: Callback<Value>,
+) -> AnyView {
+    let (items, is_array) = elements(value);
+    let per_extruder = info.is_some_and(|f| f.arity == "per_extruder");
+    let is_list = is_array && !per_extruder;
+    let nullable = info.is_some_and(|f| f.nullable);
+    let multiline = key.contains("gcode");
+    let count = items.len();
+
+    let inputs = items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| {
+            let set_item = {
+                let items = items.clone();
+                move |new: String| {
+                    let mut items = items.clone();
+                    items[i] = new;
+                    on_change.run(to_value(items, is_array));
+                }
+            };
+            let is_nil = item == NIL;
+            let label = (per_extruder && count > 1).then(|| format!("Extruder {}", i + 1));
+            let nil_toggle = nullable.then(|| {
+                let set_item = set_item.clone();
+                view! {
+                    <label class="field-editor-nil" title="Use the printer's value">
+                        <input
+                            type="checkbox"
+                            prop:checked=is_nil
+                            on:change=move |ev| {
+                                let nil = event_target_checked(&ev);
+                                set_item(if nil { NIL.to_string() } else { String::new() })
+                            }
+                        />
+                        "nil"
+                    </label>
+                }
+            });
+            let remove = (is_list && count > 1).then(|| {
+                let items = items.clone();
+                view! {
+                    <button
+                        class="btn btn-small btn-secondary"
+                        title="Remove this element"
+                        on:click=move |_| {
+                            let mut items = items.clone();
+                            items.remove(i);
+                            on_change.run(to_value(items, true));
+                        }
+                    >
+                        "Remove"
+                    </button>
+                }
+            });
+            view! {
+                <div class="field-editor-element">
+                    {label.map(|l| view! { <span class="field-editor-element-label">{l}</span> })}
+                    {element_input(info, multiline, item.clone(), is_nil, set_item)}
+                    {nil_toggle}
+                    {remove}
+                </div>
+            }
+        })
+        .collect::<Vec<_>>();
+
+    // One element stands for every extruder
+    let layout = (per_extruder && extruder_count > 1).then(|| {
+        let first = items.first().cloned().unwrap_or_default();
+        if count == 1 {
+            view! {
+                <button
+                    class="btn btn-small btn-secondary"
+                    on:click=move |_| {
+                        on_change.run(to_value(vec![first.clone(); extruder_count], true))
+                    }
+                >
+                    "Per extruder"
+                </button>
+            }
+            .into_any()
+        } else {
+            view! {
+                <button
+                    class="btn btn-small btn-secondary"
+                    on:click=move |_| on_change.run(to_value(vec![first.clone()], true))
+                >
+                    "Same for all"
+                </button>
+            }
+            .into_any()
+        }
+    });
+    let add = is_list.then(|| {
+        let items = items.clone();
+        view! {
+            <button
+                class="btn btn-small btn-secondary"
+                on:click=move |_| {
+                    let mut items = items.clone();
+                    items.push(String::new());
+                    on_change.run(to_value(items, true));
+                }
+            >
+                "Add element"
+            </button>
+        }
+    });
+
+    view! {
+        <div class="field-editor-elements">
+            {inputs}
+            {(layout.is_some() || add.is_some()).then(|| view! {
+                <div class="field-editor-element-actions">{layout}{add}</div>
+            })}
+        </div>
+    }
+    .into_any()
+}
+
+fn describe_diagnostic(diagnostic: &FieldDiagnostic, per_extruder: bool) -> String {
+    match diagnostic.index {
+        Some(i) if per_extruder => format!("Extruder {}: {}", i + 1, diagnostic.message),
+        Some(i) => format!("Element {}: {}", i + 1, diagnostic.message),
+        None => diagnostic.message.clone(),
+    }
+}
+
+/// Raw field editor for the user profile at `path`.
+#[component]
+pub fn FieldEditor(
+    path: String,
+    /// Field dictionary entries by key.
+    #[prop(into)]
+    field_info: Signal<HashMap<String, FieldInfo>>,
+    /// Version the profile was loaded at, so the write is refused if it has
+    /// changed on disk since.
+    #[prop(into)]
+    version: Signal<Option<String>>,
+    /// Pending edits by field, in key order. Owned by the caller so they
+    /// survive the editor being rebuilt, e.g. after a refused write.
+    edits: RwSignal<BTreeMap<String, FieldEdit>>,
+    /// Called once the edits are written or queued.
+    #[prop(into)]
+    on_saved: Callback<FieldEditResult>,
+    /// Called with the error when the write is refused, which may be a
+    /// `ProfileConflict`.
+    #[prop(into)]
+    on_error: Callback<String>,
+    #[prop(into)] on_cancel: Callback<()>,
+) -> impl IntoView {
+    let (profile, set_profile) = signal::<Option<ProfileFields>>(None);
+    let (load_error, set_load_error) = signal::<Option<String>>(None);
+    let (diagnostics, set_diagnostics) = signal(Vec::<FieldDiagnostic>::new());
+    let (check_error, set_check_error) = signal::<Option<String>>(None);
+    let (filter, set_filter) = signal(String::new());
+    let (new_key, set_new_key) = signal(String::new());
+    let (saving, set_saving) = signal(false);
+    let path = StoredValue::new(path);
+
+    let load_path = path.get_value();
+    spawn_local(async move {
+        match commands::get_profile_fields(&load_path).await {
+            Ok(p) => set_profile.set(Some(p)),
+            Err(e) => set_load_error.set(Some(e)),
+        }
+    });
+
+    // Lint the pending edits whenever they change
+    Effect::new(move |_| {
+        let pending: Vec<FieldEdit> = edits.with(|e| e.values().cloned().collect());
+        if pending.is_empty() {
+            set_diagnostics.set(vec![]);
+            set_check_error.set(None);
+            return;
+        }
+        let path = path.get_value();
+        spawn_local(async move {
+            let result = commands::check_profile_field_edits(&path, &pending).await;
+            // Stale if the edits changed (or the editor closed) meanwhile
+            if edits.try_with_untracked(|e| e.values().eq(pending.iter())) != Some(true) {
+                return;
+            }
+            match result {
+                Ok(d) => {
+                    set_diagnostics.set(d);
+                    set_check_error.set(None);
+                }
+                Err(e) => {
+                    set_diagnostics.set(vec![]);
+                    set_check_error.set(Some(e));
+                }
+            }
+        });
+    });
+
+    let extruder_count = move || {
+        profile
+            .with(|p| p.as_ref().and_then(|p| p.extruder_count))
+            .unwrap_or(1)
+    };
+    let has_parent = move || {
+        profile.with(|p| {
+            p.as_ref()
+                .and_then(|p| p.inherits.as_deref())
+                .is_some_and(|i| !i.is_empty())
+        })
+    };
+
+    // Record `value` (`None` to inherit) for `field`, dropping the edit if
+    // that's what the file already has
+    let set_value = move |field: &RawField, value: Option<Value>| {
+        edits.update(|e| {
+            if value == field.value {
+                e.remove(&field.key);
+                return;
+            }
+            let key = field.key.clone();
+            let edit = match value {
+                Some(value) => FieldEdit::Set { key, value },
+                None => FieldEdit::Inherit { key },
+            };
+            e.insert(field.key.clone(), edit);
+        });
+    };
+
+    // The profile's fields plus any added here, filtered and sorted
+    let rows = move || {
+        let Some(mut rows) = profile.with(|p| p.as_ref().map(|p| p.fields.clone())) else {
+            return vec![];
+        };
+        edits.with(|e| {
+            for key in e.keys() {
+                if !rows.iter().any(|f| &f.key == key) {
+                    let info = field_info.with(|i| i.get(key).cloned());
+                    rows.push(RawField {
+                        key: key.clone(),
+                        label: info
+                            .as_ref()
+                            .map_or_else(|| key.clone(), |f| f.label.clone()),
+                        category: info.map_or_else(|| "Other".to_string(), |f| f.category),
+                        value: None,
+                        inherited: None,
+                    });
+                }
+            }
+        });
+        let q = filter.get().to_lowercase();
+        if !q.is_empty() {
+            rows.retain(|f| {
+                f.key.to_lowercase().contains(&q) || f.label.to_lowercase().contains(&q)
+            });
+        }
+        rows.sort_by(|a, b| a.category.cmp(&b.category).then(a.label.cmp(&b.label)));
+        rows
+    };
+
+    let render_row = move |field: RawField| {
+        let info = field_info.with(|i| i.get(&field.key).cloned());
+        let value = edits.with(|e| match e.get(&field.key) {
+            Some(FieldEdit::Set { value, .. }) => Some(value.clone()),
+            Some(FieldEdit::Inherit { .. }) => None,
+            None => field.value.clone(),
+        });
+        let modified = edits.with(|e| e.contains_key(&field.key));
+        let per_extruder = info.as_ref().is_some_and(|f| f.arity == "per_extruder");
+        let label = match &info {
+            Some(FieldInfo {
+                label,
+                unit: Some(unit),
+                ..
+            }) => format!("{} ({})", label, unit),
+            Some(f) => f.label.clone(),
+            None => field.label.clone(),
+        };
+        let row_diagnostics: Vec<FieldDiagnostic> =
+            diagnostics.with(|d| d.iter().filter(|d| d.key == field.key).cloned().collect());
+
+        let editor = match &value {
+            Some(value) => {
+                let changed = field.clone();
+                let on_change = Callback::new(move |v: Value| set_value(&changed, Some(v)));
+                let parent = field.inherited.as_ref().map(|v| {
+                    view! {
+                        <div class="field-editor-hint" title=elements(v).0.join(", ")>
+                            "Parent: " {display_value(v)}
+                        </div>
+                    }
+                });
+                view! {
+                    {value_editor(info.as_ref(), &field.key, value, extruder_count(), on_change)}
+                    {parent}
+                }
+                .into_any()
+            }
+            None => {
+                let inherited = field
+                    .inherited
+                    .as_ref()
+                    .map_or_else(|| "(not set)".to_string(), display_value);
+                let full = field
+                    .inherited
+                    .as_ref()
+                    .map(|v| elements(v).0.join(", "))
+                    .unwrap_or_default();
+                let overridden = field.clone();
+                let info = info.clone();
+                view! {
+                    <span class="field-editor-inherited" title=full>{inherited}</span>
+                    <button
+                        class="btn btn-small btn-secondary"
+                        title="Set a value in this profile"
+                        on:click=move |_| {
+                            let value = overridden.inherited.clone().unwrap_or_else(|| {
+                                blank_value(info.as_ref(), extruder_count())
+                            });
+                            set_value(&overridden, Some(value));
+                        }
+                    >
+                        "Override"
+                    </button>
+                }
+                .into_any()
+            }
+        };
+
+        // Metadata fields are never inherited; removing one just loses it
+        let inheritable = !info.as_ref().is_some_and(|f| f.metadata);
+        let inherit = (value.is_some() && has_parent() && inheritable).then(|| {
+            let field = field.clone();
+            view! {
+                <button
+                    class="btn btn-small btn-secondary"
+                    title="Remove the value so it comes from the parent profile"
+                    on:click=move |_| set_value(&field, None)
+                >
+                    "Inherit"
+                </button>
+            }
+        });
+        let revert = modified.then(|| {
+            let key = field.key.clone();
+            view! {
+                <button
+                    class="btn btn-small btn-secondary"
+                    on:click=move |_| edits.update(|e| {
+                        e.remove(&key);
+                    })
+                >
+                    "Revert"
+                </button>
+            }
+        });
+
+        view! {
+            <tr class:field-editor-modified=modified>
+                <td class="field-key" title=field.key.clone()>{label}</td>
+                <td class="field-editor-value">
+                    {editor}
+                    {row_diagnostics.iter().map(|d| view! {
+                        <div
+                            class="field-editor-diagnostic"
+                            class:warning=d.severity == "warning"
+                        >
+                            {describe_diagnostic(d, per_extruder)}
+                        </div>
+                    }).collect::<Vec<_>>()}
+                </td>
+                <td class="field-editor-row-actions">{inherit}{revert}</td>
+            </tr>
+        }
+    };
+
+    // Add a field the profile doesn't set, or jump to one it does
+    let add_field = move || {
+        let key = new_key.get_untracked().trim().to_string();
+        if key.is_empty() {
+            return;
+        }
+        set_new_key.set(String::new());
+        let listed = profile.with_untracked(|p| {
+            p.as_ref()
+                .is_some_and(|p| p.fields.iter().any(|f| f.key == key))
+        });
+        if !listed && !edits.with_untracked(|e| e.contains_key(&key)) {
+            let value = field_info.with_untracked(|i| blank_value(i.get(&key), extruder_count()));
+            edits.update(|e| {
+                e.insert(
+                    key.clone(),
+                    FieldEdit::Set {
+                        key: key.clone(),
+                        value,
+                    },
+                );
+            });
+        }
+        set_filter.set(key);
+    };
+
+    let has_errors = move || {
+        check_error.with(Option::is_some)
+            || diagnostics.with(|d| d.iter().any(|d| d.severity == "error"))
+    };
+
+    let apply = move |_| {
+        let pending: Vec<FieldEdit> = edits.with_untracked(|e| e.values().cloned().collect());
+        let path = path.get_value();
+        let version = version.get_untracked();
+        set_saving.set(true);
+        spawn_local(async move {
+            let result = commands::update_profile_fields(&path, &pending, version.as_deref()).await;
+            set_saving.set(false);
+            match result {
+                Ok(r) => on_saved.run(r),
+                Err(e) => on_error.run(e),
+            }
+        });
+    };
+
+    view! {
+        <div class="field-editor">
+            <style>{include_str!("field_editor.css")}</style>
+
+            {move || load_error.get().map(|e| view! { <div class="profile-error">{e}</div> })}
+
+            {move || profile.get().map(|p| {
+                let parent = match p.inherits.as_deref().filter(|i| !i.is_empty()) {
+                    Some(parent) => format!("Inherits from \"{}\"", parent),
+                    None => "Standalone profile (no parent)".to_string(),
+                };
+                let extruders = p
+                    .extruder_count
+                    .map(|n| format!(" | {} extruders", n))
+                    .unwrap_or_default();
+                view! {
+                    <div class="field-editor-summary">
+                        {parent}{extruders}{format!(" | {} fields", p.fields.len())}
+                    </div>
+                }
+            })}
+
+            <div class="field-editor-toolbar">
+                <input
+                    type="text"
+                    class="field-editor-filter"
+                    placeholder="Filter fields..."
+                    prop:value=move || filter.get()
+                    on:input=move |ev| set_filter.set(event_target_value(&ev))
+                />
+                <input
+                    type="text"
+                    class="field-editor-filter"
+                    placeholder="Add field by key..."
+                    list="field-editor-keys"
+                    prop:value=move || new_key.get()
+                    on:input=move |ev| set_new_key.set(event_target_value(&ev))
+                    on:keydown=move |ev| {
+                        if ev.key() == "Enter" { add_field(); }
+                    }
+                />
+                <button class="btn btn-small btn-secondary" on:click=move |_| add_field()>
+                    "Add"
+                </button>
+                <datalist id="field-editor-keys">
+                    {move || field_info.with(|info| {
+                        let mut keys: Vec<String> = info.keys().cloned().collect();
+                        keys.sort();
+                        keys.into_iter().map(|k| view! { <option value=k></option> }).collect::<Vec<_>>()
+                    })}
+                </datalist>
+            </div>
+
+            <Show when=move || profile.with(Option::is_none) && load_error.with(Option::is_none)>
+                <div class="profile-loading">
+                    <span>"Loading fields..."</span>
+                </div>
+            </Show>
+
+            <table class="profile-fields field-editor-table">
+                {move || {
+                    let mut groups: Vec<(String, Vec<RawField>)> = Vec::new();
+                    for row in rows() {
+                        match groups.last_mut() {
+                            Some((category, fields)) if *category == row.category => fields.push(row),
+                            _ => groups.push((row.category.clone(), vec![row])),
+                        }
+                    }
+                    groups.into_iter().map(|(category, fields)| view! {
+                        <tbody>
+                            <tr class="field-editor-category">
+                                <th colspan="3">{category}</th>
+                            </tr>
+                            {fields.into_iter().map(render_row).collect::<Vec<_>>()}
+                        </tbody>
+                    }).collect::<Vec<_>>()
+                }}
+            </table>
+
+            {move || check_error.get().map(|e| view! { <div class="profile-error">{e}</div> })}
+
+            <div class="modal-actions">
+                <button class="btn btn-secondary" on:click=move |_| on_cancel.run(())>
+                    "Close"
+                </button>
+                <button
+                    class="btn btn-secondary"
+                    disabled=move || edits.with(BTreeMap::is_empty)
+                    on:click=move |_| edits.set(BTreeMap::new())
+                >
+                    "Discard changes"
+                </button>
+                <button
+                    class="btn btn-primary"
+                    disabled=move || saving.get() || edits.with(BTreeMap::is_empty) || has_errors()
+                    on:click=apply
+                >
+                    {move || match edits.with(BTreeMap::len) {
+                        _ if saving.get() => "Applying...".to_string(),
+                        1 => "Apply 1 change".to_string(),
+                        n => format!("Apply {} changes", n),
+                    }}
+                </button>
+            </div>
+        </div>
+    }
+}
@@ -0,0 +1,206 @@
+//! Panel listing snapshots of the user filament library.
+//!
+//! Snapshots are taken automatically before batch installs, rebases and
+//! family-wide edits, or on demand here. Each one lists the files that have
+//! changed since, and can be restored whole or for selected files.
+
+use std::collections::HashSet;
+
+use leptos::prelude::*;
+use wasm_bindgen_futures::spawn_local;
+
+use crate::commands::{self, SnapshotSummary};
+
+/// Library snapshots panel.
+#[component]
+pub fn LibrarySnapshots(
+    /// Called after a restore, so the page can reload its profile list.
+    #[prop(into)]
+    on_restored: Callback<()>,
+) -> impl IntoView {
+    let (open, set_open) = signal(false);
+    let (snapshots, set_snapshots) = signal::<Vec<SnapshotSummary>>(vec![]);
+    let (loading, set_loading) = signal(false);
+    let (error, set_error) = signal::<Option<String>>(None);
+    let (message, set_message) = signal::<Option<String>>(None);
+    let (expanded, set_expanded) = signal::<Option<String>>(None);
+    let (selected, set_selected) = signal(HashSet::<String>::new());
+
+    let load = move || {
+        set_loading.set(true);
+        spawn_local(async move {
+            match commands::list_library_snapshots().await {
+                Ok(list) => {
+                    set_snapshots.set(list);
+                    set_error.set(None);
+                }
+                Err(e) => set_error.set(Some(e)),
+            }
+            set_loading.set(false);
+        });
+    };
+
+    let toggle_open = move |_| {
+        let now_open = !open.get();
+        set_open.set(now_open);
+        if now_open {
+            load();
+        }
+    };
+
+    let take_snapshot = move |_| {
+        spawn_local(async move {
+            match commands::create_library_snapshot(None).await {
+                Ok(info) => {
+                    set_message.set(Some(format!("Snapshot of {} files taken", info.file_count)));
+                    load();
+                }
+                Err(e) => set_error.set(Some(e)),
+            }
+        });
+    };
+
+    let restore = move |id: String, files: Option<Vec<String>>| {
+        set_message.set(None);
+        spawn_local(async move {
+            match commands::restore_library_snapshot(&id, files, false).await {
+                Ok(restored) => {
+                    set_message.set(Some(format!("Restored {} files", restored.len())));
+                    set_error.set(None);
+                    set_selected.set(HashSet::new());
+                    on_restored.run(());
+                    load();
+                }
+                Err(e) => set_error.set(Some(e)),
+            }
+        });
+    };
+
+    view! {
+        <div class="library-snapshots">
+            <style>{include_str!("library_snapshots.css")}</style>
+            <div class="library-snapshots-bar">
+                <button class="btn btn-small btn-secondary" on:click=toggle_open>
+                    {move || if open.get() { "Hide snapshots" } else { "Snapshots" }}
+                </button>
+                <Show when=move || open.get()>
+                    <button class="btn btn-small btn-secondary" on:click=take_snapshot>
+                        "Take snapshot"
+                    </button>
+                </Show>
+            </div>
+
+            <Show when=move || open.get()>
+                {move || error.get().map(|e| view! {
+                    <div class="library-snapshots-error">{e}</div>
+                })}
+                {move || message.get().map(|m| view! {
+                    <div class="library-snapshots-message">{m}</div>
+                })}
+
+                {move || {
+                    if loading.get() {
+                        return view! { <p class="library-snapshots-empty">"Loading snapshots..."</p> }.into_any();
+                    }
+                    let list = snapshots.get();
+                    if list.is_empty() {
+                        return view! { <p class="library-snapshots-empty">"No snapshots yet."</p> }.into_any();
+                    }
+                    view! {
+                        <div class="library-snapshots-list">
+                            {list.into_iter().map(|s| {
+                                let id = s.info.id.clone();
+                                let toggle_id = id.clone();
+                                let is_expanded = {
+                                    let id = id.clone();
+                                    move || expanded.get().as_deref() == Some(id.as_str())
+                                };
+                                let restore_all_id = id.clone();
+                                let restore_selected_id = id.clone();
+                                let summary = if s.changes.is_empty() {
+                                    "No changes since".to_string()
+                                } else {
+                                    format!("{} files changed since", s.changes.len())
+                                };
+                                let has_changes = !s.changes.is_empty();
+                                let changes = s.changes.clone();
+                                view! {
+                                    <div class="library-snapshot">
+                                        <div
+                                            class="library-snapshot-header"
+                                            on:click=move |_| {
+                                                set_selected.set(HashSet::new());
+                                                if expanded.get().as_deref() == Some(toggle_id.as_str()) {
+                                                    set_expanded.set(None);
+                                                } else {
+                                                    set_expanded.set(Some(toggle_id.clone()));
+                                                }
+                                            }
+                                        >
+                                            <span class="library-snapshot-reason">{s.info.reason.clone()}</span>
+                                            <span class="library-snapshot-meta">
+                                                {format!("{} · {} files · {}", s.info.created_at, s.info.file_count, summary)}
+                                            </span>
+                                        </div>
+                                        {move || is_expanded().then(|| {
+                                            let restore_all_id = restore_all_id.clone();
+                                            let restore_selected_id = restore_selected_id.clone();
+                                            view! {
+                                                <div class="library-snapshot-changes">
+                                                    {changes.iter().map(|c| {
+                                                        let path = c.path.clone();
+                                                        let checked_path = path.clone();
+                                                        view! {
+                                                            <label class="library-snapshot-change">
+                                                                <input
+                                                                    type="checkbox"
+                                                                    prop:checked=move || selected.get().contains(&checked_path)
+                                                                    on:change=move |ev| {
+                                                                        let on = event_target_checked(&ev);
+                                                                        let path = path.clone();
+                                                                        set_selected.update(|set| {
+                                                                            if on {
+                                                                                set.insert(path);
+                                                                            } else {
+                                                                                set.remove(&path);
+                                                                            }
+                                                                        });
+                                                                    }
+                                                                />
+                                                                <span class=format!("library-snapshot-kind kind-{}", c.kind)>{c.kind.clone()}</span>
+                                                                {c.path.clone()}
+                                                            </label>
+                                                        }
+                                                    }).collect::<Vec<_>>()}
+                                                    <div class="library-snapshot-actions">
+                                                        <button
+                                                            class="btn btn-small btn-secondary"
+                                                            disabled=move || !has_changes
+                                                            on:click=move |_| restore(restore_all_id.clone(), None)
+                                                        >
+                                                            "Restore all"
+                                                        </button>
+                                                        <button
+                                                            class="btn btn-small btn-secondary"
+                                                            disabled=move || selected.get().is_empty()
+                                                            on:click=move |_| {
+                                                                let files = selected.get().into_iter().collect();
+                                                                restore(restore_selected_id.clone(), Some(files));
+                                                            }
+                                                        >
+                                                            "Restore selected"
+                                                        </button>
+                                                    </div>
+                                                </div>
+                                            }
+                                        })}
+                                    </div>
+                                }
+                            }).collect::<Vec<_>>()}
+                        </div>
+                    }.into_any()
+                }}
+            </Show>
+        </div>
+    }
+}
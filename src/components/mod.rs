@@ -2,9 +2,15 @@ pub mod api_key_form;
 pub mod branding;
 pub mod change_preview;
 pub mod defect_report;
+pub mod field_editor;
 pub mod filament_card;
 pub mod history_panel;
+pub mod library_snapshots;
+pub mod pending_writes;
+pub mod profile_conflict;
 pub mod profile_preview;
+pub mod profile_query;
+pub mod profile_removal;
 pub mod searchable_select;
 pub mod settings_merge;
 pub mod sidebar;
@@ -0,0 +1,134 @@
+//! Banner listing profile writes queued while Bambu Studio is running.
+//!
+//! Bambu Studio overwrites user presets when it exits, so installs, field
+//! edits and applied recommendations made while it runs wait in a queue
+//! and are written once it has closed. Hidden while the queue is empty.
+
+use leptos::prelude::*;
+use wasm_bindgen_futures::spawn_local;
+
+use crate::commands::{self, PendingWrite};
+
+/// Pending profile writes panel.
+#[component]
+pub fn PendingWrites(
+    /// Called after queued writes were written, so the page can reload.
+    #[prop(into)]
+    on_written: Callback<()>,
+) -> impl IntoView {
+    let (items, set_items) = signal::<Vec<PendingWrite>>(vec![]);
+    let (error, set_error) = signal::<Option<String>>(None);
+    let (flushing, set_flushing) = signal(false);
+
+    spawn_local(async move {
+        match commands::list_pending_writes().await {
+            Ok(list) => set_items.set(list),
+            Err(e) => set_error.set(Some(e)),
+        }
+    });
+
+    // The queue shrinks on its own when Bambu Studio exits. The listener
+    // lives as long as the component.
+    let listener: StoredValue<Option<commands::EventListener>, LocalStorage> =
+        StoredValue::new_local(None);
+    spawn_local(async move {
+        let result = commands::listen_pending_writes(move |list| {
+            if list.len() < items.get_untracked().len() {
+                on_written.run(());
+            }
+            set_items.set(list);
+        })
+        .await;
+        match result {
+            Ok(l) => {
+                // Dropped (unsubscribing) if the component is already gone
+                let _ = listener.try_update_value(|slot| *slot = Some(l));
+            }
+            Err(e) => leptos::logging::warn!("Failed to watch pending writes: {}", e),
+        }
+    });
+    on_cleanup(move || {
+        listener.update_value(|slot| {
+            slot.take();
+        });
+    });
+
+    let discard = move |id: String| {
+        spawn_local(async move {
+            match commands::discard_pending_write(&id).await {
+                Ok(()) => {
+                    set_items.update(|list| list.retain(|item| item.id != id));
+                    set_error.set(None);
+                }
+                Err(e) => set_error.set(Some(e)),
+            }
+        });
+    };
+
+    let write_now = move |_| {
+        set_flushing.set(true);
+        spawn_local(async move {
+            match commands::flush_pending_writes().await {
+                Ok(report) if report.failed.is_empty() => set_error.set(None),
+                Ok(report) => set_error.set(Some(format!(
+                    "{} queued changes could not be written",
+                    report.failed.len()
+                ))),
+                Err(e) => set_error.set(Some(e)),
+            }
+            set_flushing.set(false);
+        });
+    };
+
+    view! {
+        <Show when=move || !items.get().is_empty()>
+            <div class="pending-writes">
+                <style>{include_str!("pending_writes.css")}</style>
+                <div class="pending-writes-bar">
+                    <span class="pending-writes-title">
+                        {move || format!(
+                            "{} changes waiting for Bambu Studio to close",
+                            items.get().len()
+                        )}
+                    </span>
+                    <button
+                        class="btn btn-small btn-secondary"
+                        disabled=move || flushing.get()
+                        on:click=write_now
+                    >
+                        "Write now"
+                    </button>
+                </div>
+                <p class="pending-writes-hint">
+                    "Bambu Studio overwrites presets when it exits, so these are written \
+                     automatically once it has closed."
+                </p>
+                {move || error.get().map(|e| view! {
+                    <div class="pending-writes-error">{e}</div>
+                })}
+                <ul class="pending-writes-list">
+                    {move || items.get().into_iter().map(|item| {
+                        let id = item.id.clone();
+                        view! {
+                            <li class="pending-write">
+                                <div class="pending-write-text">
+                                    <span class="pending-write-summary">{item.summary.clone()}</span>
+                                    <span class="pending-write-meta">{item.queued_at.clone()}</span>
+                                    {item.error.clone().map(|e| view! {
+                                        <span class="pending-write-error">{format!("Failed: {}", e)}</span>
+                                    })}
+                                </div>
+                                <button
+                                    class="btn btn-small btn-secondary"
+                                    on:click=move |_| discard(id.clone())
+                                >
+                                    "Discard"
+                                </button>
+                            </li>
+                        }
+                    }).collect::<Vec<_>>()}
+                </ul>
+            </div>
+        </Show>
+    }
+}
@@ -0,0 +1,79 @@
+//! Three-way diff shown when an edit is refused because the profile
+//! changed on disk (e.g. in Bambu Studio) after it was loaded.
+
+use leptos::prelude::*;
+
+use crate::commands::ProfileConflict;
+
+fn display_value(value: &Option<serde_json::Value>) -> String {
+    match value {
+        None => "(unset)".to_string(),
+        Some(serde_json::Value::String(s)) => s.clone(),
+        Some(v) => v.to_string(),
+    }
+}
+
+/// Conflict panel listing each field the edit and the external change
+/// touched.
+#[component]
+pub fn ProfileConflictPanel(
+    conflict: ProfileConflict,
+    /// Discard the edit and reload the profile from disk.
+    #[prop(into)]
+    on_reload: Callback<()>,
+    /// Keep the edit: the next save overwrites the change on disk.
+    #[prop(into)]
+    on_overwrite: Callback<()>,
+) -> impl IntoView {
+    let conflicting = conflict.fields.iter().filter(|f| f.conflicting).count();
+    let summary = if conflicting > 0 {
+        format!(
+            "{} fields changed on disk, {} of them also edited here",
+            conflict.fields.len(),
+            conflicting
+        )
+    } else {
+        format!("{} fields differ", conflict.fields.len())
+    };
+    let base_header = if conflict.has_base {
+        "Loaded"
+    } else {
+        "Loaded (unknown)"
+    };
+
+    view! {
+        <div class="profile-conflict">
+            <style>{include_str!("profile_conflict.css")}</style>
+            <p class="profile-conflict-message">{conflict.message.clone()}</p>
+            <p class="profile-conflict-summary">{summary}</p>
+            <table class="profile-conflict-table">
+                <thead>
+                    <tr>
+                        <th>"Field"</th>
+                        <th>{base_header}</th>
+                        <th>"Your edit"</th>
+                        <th>"On disk"</th>
+                    </tr>
+                </thead>
+                <tbody>
+                    {conflict.fields.iter().map(|f| view! {
+                        <tr class:conflicting=f.conflicting>
+                            <td class="profile-conflict-key">{f.key.clone()}</td>
+                            <td>{display_value(&f.base)}</td>
+                            <td>{display_value(&f.ours)}</td>
+                            <td>{display_value(&f.theirs)}</td>
+                        </tr>
+                    }).collect::<Vec<_>>()}
+                </tbody>
+            </table>
+            <div class="profile-conflict-actions">
+                <button class="btn btn-small btn-secondary" on:click=move |_| on_reload.run(())>
+                    "Reload from disk"
+                </button>
+                <button class="btn btn-small btn-secondary" on:click=move |_| on_overwrite.run(())>
+                    "Keep my edit"
+                </button>
+            </div>
+        </div>
+    }
+}
@@ -0,0 +1,125 @@
+//! Query box for searching the whole profile library by field values.
+//!
+//! Runs `query_profiles` expressions (e.g.
+//! `filament_type = PETG and nozzle_temperature[0] > 250`) against system
+//! and user profiles, and lists the matches with the values of the fields
+//! the query mentions.
+
+use leptos::prelude::*;
+use wasm_bindgen_futures::spawn_local;
+
+use crate::commands::{self, ProfileQueryResult};
+
+/// Shown as the input placeholder and in the syntax hint.
+const EXAMPLE_QUERY: &str =
+    r#"filament_type = PETG and nozzle_temperature[0] > 250 and compatible_printers contains "X1""#;
+
+/// Profile query panel.
+#[component]
+pub fn ProfileQuery(
+    /// Called with the path of a match the user clicks.
+    #[prop(into)]
+    on_select: Callback<String>,
+) -> impl IntoView {
+    let (query, set_query) = signal(String::new());
+    let (result, set_result) = signal::<Option<ProfileQueryResult>>(None);
+    let (error, set_error) = signal::<Option<String>>(None);
+    let (running, set_running) = signal(false);
+
+    let run_query = move || {
+        let q = query.get();
+        if q.trim().is_empty() {
+            set_result.set(None);
+            set_error.set(None);
+            return;
+        }
+        set_running.set(true);
+        spawn_local(async move {
+            match commands::query_profiles(&q).await {
+                Ok(r) => {
+                    set_result.set(Some(r));
+                    set_error.set(None);
+                }
+                Err(e) => {
+                    set_result.set(None);
+                    set_error.set(Some(e));
+                }
+            }
+            set_running.set(false);
+        });
+    };
+
+    view! {
+        <div class="profile-query">
+            <style>{include_str!("profile_query.css")}</style>
+            <div class="profile-query-bar">
+                <input
+                    type="text"
+                    class="profile-query-input"
+                    placeholder=EXAMPLE_QUERY
+                    prop:value=move || query.get()
+                    on:input=move |ev| set_query.set(event_target_value(&ev))
+                    on:keydown=move |ev| {
+                        if ev.key() == "Enter" {
+                            run_query();
+                        }
+                    }
+                />
+                <button
+                    class="btn btn-small btn-secondary"
+                    disabled=move || running.get()
+                    on:click=move |_| run_query()
+                >
+                    {move || if running.get() { "Running..." } else { "Query" }}
+                </button>
+            </div>
+            <p class="profile-query-hint">
+                "Compare fields with = != < <= > >= contains, or test them with exists. "
+                "Combine with and, or, not and parentheses. field[i] picks one extruder's value."
+            </p>
+
+            {move || error.get().map(|e| view! {
+                <div class="profile-query-error">{e}</div>
+            })}
+
+            {move || result.get().map(|r| {
+                let summary = if r.total > r.matches.len() {
+                    format!("Showing {} of {} matching profiles", r.matches.len(), r.total)
+                } else {
+                    format!("{} matching profiles", r.total)
+                };
+                view! {
+                    <div class="profile-query-results">
+                        <div class="profile-query-summary">{summary}</div>
+                        {r.matches.into_iter().map(|m| {
+                            let path = m.path.clone();
+                            view! {
+                                <div
+                                    class="profile-query-row"
+                                    title=m.path.clone()
+                                    on:click=move |_| on_select.run(path.clone())
+                                >
+                                    <div class="profile-query-name">
+                                        {m.name.clone()}
+                                        {m.filament_type.clone().map(|t| view! {
+                                            <span class="profile-item-badge">{t}</span>
+                                        })}
+                                    </div>
+                                    <div class="profile-query-fields">
+                                        {m.fields.iter().map(|(key, value)| view! {
+                                            <span class="profile-query-field">
+                                                <span class="profile-query-key">{key.clone()}</span>
+                                                " "
+                                                {value.clone()}
+                                            </span>
+                                        }).collect::<Vec<_>>()}
+                                    </div>
+                                </div>
+                            }
+                        }).collect::<Vec<_>>()}
+                    </div>
+                }
+            })}
+        </div>
+    }
+}
@@ -0,0 +1,173 @@
+//! Confirmation dialog for deleting or renaming a user profile, previewing
+//! what happens to the profiles that inherit from it and to
+//! BambuStudio.conf before anything is written.
+
+use leptos::prelude::*;
+use wasm_bindgen_futures::spawn_local;
+
+use crate::commands::{self, DependentChange, RemovalPlan};
+
+fn describe(change: &DependentChange) -> String {
+    let added = match change.added_fields.len() {
+        0 => String::new(),
+        1 => " (1 field copied in)".to_string(),
+        n => format!(" ({} fields copied in)", n),
+    };
+    match change.action.as_str() {
+        "reparent" => format!(
+            "inherits from \"{}\"{}",
+            change.new_parent.as_deref().unwrap_or_default(),
+            added
+        ),
+        "flatten" => format!("flattened{}", added),
+        "delete" => "deleted".to_string(),
+        other => other.to_string(),
+    }
+}
+
+fn conf_summary(plan: &RemovalPlan) -> Option<String> {
+    let mut parts = Vec::new();
+    if !plan.unregister.is_empty() {
+        parts.push(format!("unregisters {}", plan.unregister.join(", ")));
+    }
+    if !plan.register.is_empty() {
+        parts.push(format!("registers {}", plan.register.join(", ")));
+    }
+    (!parts.is_empty()).then(|| format!("BambuStudio.conf: {}", parts.join("; ")))
+}
+
+/// Delete or rename dialog. Renames when `new_name` is set.
+#[component]
+pub fn ProfileRemovalDialog(
+    path: String,
+    name: String,
+    new_name: Option<String>,
+    /// Called with what was done once the operation succeeds.
+    #[prop(into)]
+    on_done: Callback<RemovalPlan>,
+    #[prop(into)] on_cancel: Callback<()>,
+) -> impl IntoView {
+    let (policy, set_policy) = signal("reparent".to_string());
+    let (plan, set_plan) = signal::<Option<RemovalPlan>>(None);
+    let (error, set_error) = signal::<Option<String>>(None);
+    let (running, set_running) = signal(false);
+    let is_rename = new_name.is_some();
+    let summary = match &new_name {
+        Some(new_name) => format!("\"{}\" will be renamed to \"{}\".", name, new_name),
+        None => format!(
+            "This will permanently delete \"{}\" and its metadata. \
+             The library is snapshotted first if other profiles change.",
+            name
+        ),
+    };
+
+    // Re-preview whenever the dependent policy changes
+    let preview_path = path.clone();
+    let preview_name = new_name.clone();
+    Effect::new(move |_| {
+        let policy = policy.get();
+        let path = preview_path.clone();
+        let new_name = preview_name.clone();
+        spawn_local(async move {
+            let result = match &new_name {
+                Some(new_name) => commands::preview_profile_rename(&path, new_name).await,
+                None => commands::preview_profile_delete(&path, &policy).await,
+            };
+            match result {
+                Ok(p) => {
+                    set_plan.set(Some(p));
+                    set_error.set(None);
+                }
+                Err(e) => {
+                    set_plan.set(None);
+                    set_error.set(Some(e));
+                }
+            }
+        });
+    });
+
+    let confirm = move |_| {
+        let path = path.clone();
+        let new_name = new_name.clone();
+        let policy = policy.get_untracked();
+        set_running.set(true);
+        spawn_local(async move {
+            let result = match &new_name {
+                Some(new_name) => commands::rename_profile(&path, new_name, false).await,
+                None => commands::delete_profile(&path, &policy, false).await,
+            };
+            set_running.set(false);
+            match result {
+                Ok(done) => on_done.run(done),
+                Err(e) => set_error.set(Some(e)),
+            }
+        });
+    };
+
+    let title = if is_rename {
+        "Rename Profile?"
+    } else {
+        "Delete Profile?"
+    };
+
+    view! {
+        <div class="profile-removal">
+            <style>{include_str!("profile_removal.css")}</style>
+            <h3>{title}</h3>
+            <p>{summary}</p>
+
+            {move || plan.get().map(|p| {
+                let dependents = p.dependents.clone();
+                let conf = conf_summary(&p);
+                view! {
+                    {(!dependents.is_empty()).then(|| view! {
+                        <div class="profile-removal-dependents">
+                            <div class="profile-removal-heading">
+                                {format!("{} profiles inherit from it", dependents.len())}
+                            </div>
+                            {(!is_rename).then(|| view! {
+                                <select
+                                    class="profile-removal-policy"
+                                    prop:value=move || policy.get()
+                                    on:change=move |ev| set_policy.set(event_target_value(&ev))
+                                >
+                                    <option value="reparent">"Re-parent them onto its parent"</option>
+                                    <option value="flatten">"Flatten them"</option>
+                                    <option value="cascade">"Delete them too"</option>
+                                </select>
+                            })}
+                            <ul>
+                                {dependents.iter().map(|d| view! {
+                                    <li>
+                                        <span class="profile-removal-name">{d.name.clone()}</span>
+                                        ": "
+                                        {describe(d)}
+                                        {d.note.clone().map(|n| view! {
+                                            <div class="profile-removal-note">{n}</div>
+                                        })}
+                                    </li>
+                                }).collect::<Vec<_>>()}
+                            </ul>
+                        </div>
+                    })}
+                    {conf.map(|c| view! { <p class="profile-removal-conf">{c}</p> })}
+                }
+            })}
+
+            {move || error.get().map(|e| view! { <div class="profile-error">{e}</div> })}
+
+            <div class="modal-actions">
+                <button class="btn btn-secondary" on:click=move |_| on_cancel.run(())>
+                    "Cancel"
+                </button>
+                <button
+                    class="btn btn-primary"
+                    disabled=move || running.get() || plan.get().is_none()
+                    on:click=confirm
+                >
+                    {if is_rename { "Rename" } else { "Delete" }}
+                </button>
+            </div>
+        </div>
+    }
+}
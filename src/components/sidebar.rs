@@ -48,9 +48,21 @@ pub fn Sidebar() -> impl IntoView {
                 <li class="nav-item">
                     <a href="/batch" class="nav-link">"Batch Generate"</a>
                 </li>
+                <li class="nav-item">
+                    <a href="/families" class="nav-link">"Families"</a>
+                </li>
+                <li class="nav-item">
+                    <a href="/calibration" class="nav-link">"Calibration"</a>
+                </li>
+                <li class="nav-item">
+                    <a href="/printers" class="nav-link">"Printers"</a>
+                </li>
                 <li class="nav-item">
                     <a href="/compare" class="nav-link">"Compare Profiles"</a>
                 </li>
+                <li class="nav-item">
+                    <a href="/updates" class="nav-link">"Base Updates"</a>
+                </li>
                 <li class="nav-item">
                     <a href="/settings" class="nav-link">"Settings"</a>
                 </li>
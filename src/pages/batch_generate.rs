@@ -155,6 +155,9 @@ pub fn BatchGeneratePage() -> impl IntoView {
                             <span class="batch-stat">{format!("{} total", r.total)}</span>
                             <span class="batch-stat batch-success">{format!("{} succeeded", r.succeeded)}</span>
                             <span class="batch-stat batch-fail">{format!("{} failed", r.failed)}</span>
+                            {r.queued.then(|| view! {
+                                <span class="batch-stat">"Installs queued until Bambu Studio closes"</span>
+                            })}
                         </div>
 
                         <table class="batch-table">
@@ -0,0 +1,270 @@
+//! Calibration kits: test models with a profile variant per sample.
+//!
+//! A kit is generated from an installed profile's specs. Its STLs land in
+//! the STL watch folder; each sample opens in Bambu Studio with its
+//! variant, and a photo of the printed samples is analyzed with the
+//! test's context.
+
+use leptos::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::spawn_local;
+
+use crate::commands::{self, CalibrationContext, CalibrationKit, ProfileInfo};
+use crate::components::defect_report::DefectReportDisplay;
+use crate::components::specs_editor::PRINTER_OPTIONS;
+use crate::pages::print_analysis::{read_file_as_base64, AnalyzeResponse};
+
+/// Tests the backend can generate: (id, label).
+const TESTS: &[(&str, &str)] = &[
+    ("temperature_tower", "Temperature tower"),
+    ("retraction", "Retraction test"),
+    ("flow", "Flow rate squares"),
+    ("pressure_advance", "Pressure advance pattern"),
+];
+
+#[component]
+pub fn CalibrationPage() -> impl IntoView {
+    let profiles = RwSignal::new(Vec::<ProfileInfo>::new());
+    let source_path = RwSignal::new(String::new());
+    let test = RwSignal::new(TESTS[0].0.to_string());
+    let target_printer = RwSignal::new(String::from("Bambu Lab X1 Carbon 0.4 nozzle"));
+    let install = RwSignal::new(true);
+
+    let generating = RwSignal::new(false);
+    let kit = RwSignal::new(Option::<CalibrationKit>::None);
+    let error = RwSignal::new(Option::<String>::None);
+    let launch_message = RwSignal::new(Option::<String>::None);
+
+    let analyzing = RwSignal::new(false);
+    let analysis = RwSignal::new(Option::<AnalyzeResponse>::None);
+
+    spawn_local(async move {
+        match commands::list_profiles().await {
+            Ok(list) => profiles.set(list),
+            Err(e) => error.set(Some(e)),
+        }
+    });
+
+    let do_generate = move |_| {
+        let path = source_path.get();
+        if path.is_empty() {
+            error.set(Some("Choose the profile to calibrate".to_string()));
+            return;
+        }
+        let test = test.get();
+        let printer = target_printer.get();
+        let install = install.get();
+        generating.set(true);
+        error.set(None);
+        kit.set(None);
+        analysis.set(None);
+        spawn_local(async move {
+            let result = async {
+                let specs = commands::extract_specs_from_profile(&path).await?;
+                commands::generate_calibration_kit(&test, &specs, Some(printer), install).await
+            }
+            .await;
+            match result {
+                Ok(k) => kit.set(Some(k)),
+                Err(e) => error.set(Some(e)),
+            }
+            generating.set(false);
+        });
+    };
+
+    let open_sample = move |stl_path: String, profile_path: Option<String>| {
+        spawn_local(async move {
+            match commands::launch_bambu_studio(Some(stl_path), profile_path).await {
+                Ok(r) if r.was_already_running => launch_message.set(Some(
+                    "Bambu Studio was already running; the model was sent to it.".to_string(),
+                )),
+                Ok(_) => launch_message.set(None),
+                Err(e) => launch_message.set(Some(e)),
+            }
+        });
+    };
+
+    // Analyze a photo of the printed samples, laid out left to right in
+    // kit order.
+    let on_photo = move |ev: leptos::ev::Event| {
+        let Some(file) = ev
+            .target()
+            .and_then(|t| t.dyn_into::<web_sys::HtmlInputElement>().ok())
+            .and_then(|input| input.files())
+            .and_then(|files| files.get(0))
+        else {
+            return;
+        };
+        let Some(k) = kit.get_untracked() else {
+            return;
+        };
+        let path = source_path.get_untracked();
+        let context = CalibrationContext {
+            test: k.test.clone(),
+            values: k.samples.iter().map(|s| s.value).collect(),
+        };
+        analyzing.set(true);
+        error.set(None);
+        spawn_local(async move {
+            let result = async {
+                let base64 = read_file_as_base64(file).await?;
+                commands::analyze_print(base64, Some(path), None, Some(context)).await
+            }
+            .await;
+            match result {
+                Ok(response) => analysis.set(Some(response)),
+                Err(e) => error.set(Some(e)),
+            }
+            analyzing.set(false);
+        });
+    };
+
+    view! {
+        <div class="page calibration-page">
+            <style>{include_str!("calibration.css")}</style>
+
+            <h2>"Calibration"</h2>
+            <p class="page-description">
+                "Print a set of test samples, each with its own profile variant, then photograph them to find the best value."
+            </p>
+
+            <div class="calibration-config">
+                <div class="form-group">
+                    <label for="calibration-profile">"Profile"</label>
+                    <select
+                        id="calibration-profile"
+                        class="input"
+                        on:change=move |ev| source_path.set(event_target_value(&ev))
+                        prop:value=move || source_path.get()
+                    >
+                        <option value="">"-- Select a profile --"</option>
+                        {move || profiles.get().into_iter().map(|p| {
+                            view! { <option value={p.path}>{p.name}</option> }
+                        }).collect::<Vec<_>>()}
+                    </select>
+                </div>
+
+                <div class="form-group">
+                    <label for="calibration-test">"Test"</label>
+                    <select
+                        id="calibration-test"
+                        class="input"
+                        on:change=move |ev| test.set(event_target_value(&ev))
+                        prop:value=move || test.get()
+                    >
+                        {TESTS.iter().map(|&(id, label)| {
+                            view! { <option value={id}>{label}</option> }
+                        }).collect::<Vec<_>>()}
+                    </select>
+                </div>
+
+                <div class="form-group">
+                    <label for="calibration-printer">"Target Printer"</label>
+                    <select
+                        id="calibration-printer"
+                        class="input"
+                        on:change=move |ev| target_printer.set(event_target_value(&ev))
+                        prop:value=move || target_printer.get()
+                    >
+                        {PRINTER_OPTIONS.iter().map(|&p| {
+                            view! { <option value={p}>{p}</option> }
+                        }).collect::<Vec<_>>()}
+                    </select>
+                </div>
+
+                <div class="form-group checkbox-group">
+                    <label>
+                        <input
+                            type="checkbox"
+                            prop:checked=move || install.get()
+                            on:change=move |ev| install.set(event_target_checked(&ev))
+                        />
+                        " Install the variants to Bambu Studio"
+                    </label>
+                </div>
+
+                <button
+                    class="btn btn-primary"
+                    on:click=do_generate
+                    disabled=move || generating.get() || source_path.get().is_empty()
+                >
+                    {move || if generating.get() { "Generating..." } else { "Generate Kit" }}
+                </button>
+            </div>
+
+            {move || error.get().map(|e| view! { <div class="calibration-error">{e}</div> })}
+
+            {move || kit.get().map(|k| {
+                view! {
+                    <div class="calibration-kit">
+                        <h3>{format!("{} ({})", k.test_name, k.parameter)}</h3>
+                        {k.queued.then(|| view! {
+                            <p class="calibration-note">
+                                "Bambu Studio is running: the variants will be installed when it closes."
+                            </p>
+                        })}
+                        {move || launch_message.get().map(|m| view! { <p class="calibration-note">{m}</p> })}
+                        <table class="calibration-table">
+                            <thead>
+                                <tr>
+                                    <th>"Sample"</th>
+                                    <th>"Profile"</th>
+                                    <th></th>
+                                </tr>
+                            </thead>
+                            <tbody>
+                                {k.samples.into_iter().map(|sample| {
+                                    let stl_path = sample.stl_path.clone();
+                                    let installed = sample.installed_path.clone();
+                                    view! {
+                                        <tr>
+                                            <td>{sample.label}</td>
+                                            <td>{sample.profile_name}</td>
+                                            <td>
+                                                <button class="btn btn-small btn-secondary"
+                                                    on:click=move |_| open_sample(stl_path.clone(), installed.clone())>
+                                                    "Open in Bambu Studio"
+                                                </button>
+                                            </td>
+                                        </tr>
+                                    }
+                                }).collect::<Vec<_>>()}
+                            </tbody>
+                        </table>
+
+                        <div class="calibration-photo">
+                            <label for="calibration-photo-input" class="btn btn-secondary">
+                                {move || if analyzing.get() { "Analyzing..." } else { "Analyze a photo of the samples" }}
+                            </label>
+                            <input
+                                type="file"
+                                id="calibration-photo-input"
+                                accept="image/*"
+                                style="display: none"
+                                disabled=move || analyzing.get()
+                                on:change=on_photo
+                            />
+                            <p class="calibration-note">"Lay the samples out left to right in the order above."</p>
+                        </div>
+                    </div>
+                }
+            })}
+
+            {move || analysis.get().map(|response| view! {
+                <DefectReportDisplay
+                    defect_report=response.defect_report
+                    recommendations=response.recommendations
+                    conflicts=response.conflicts
+                    material_type=response.material_type
+                />
+            })}
+        </div>
+    }
+}
+
+fn event_target_checked(ev: &leptos::ev::Event) -> bool {
+    ev.target()
+        .and_then(|t| t.dyn_into::<web_sys::HtmlInputElement>().ok())
+        .map(|e| e.checked())
+        .unwrap_or(false)
+}
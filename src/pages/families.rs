@@ -0,0 +1,252 @@
+//! Filament families: the printer/nozzle variants of one filament.
+//!
+//! Variants sharing a `filament_id` are listed together with the fields
+//! they disagree on. A field can be set across every variant at once, and
+//! variants can be generated for printers the family doesn't cover yet.
+
+use leptos::prelude::*;
+use wasm_bindgen_futures::spawn_local;
+
+use crate::commands::{self, FilamentFamily, ProfileConflict};
+use crate::components::specs_editor::PRINTER_OPTIONS;
+
+#[component]
+pub fn FamiliesPage() -> impl IntoView {
+    let families = RwSignal::new(Vec::<FilamentFamily>::new());
+    let loading = RwSignal::new(false);
+    // filament_id of the family being edited or extended
+    let busy = RwSignal::new(Option::<String>::None);
+    let error = RwSignal::new(Option::<String>::None);
+    let message = RwSignal::new(Option::<String>::None);
+
+    let reload = move || {
+        loading.set(true);
+        spawn_local(async move {
+            match commands::list_filament_families().await {
+                Ok(list) => families.set(list),
+                Err(e) => error.set(Some(e)),
+            }
+            loading.set(false);
+        });
+    };
+    reload();
+
+    let do_edit = move |family: FilamentFamily, key: String, value: String| {
+        // Same rule as the raw field editor: only obvious JSON is taken as
+        // JSON, anything else is a string.
+        let json_val = if value.starts_with('[') || value.starts_with('{') || value.starts_with('"')
+        {
+            value
+        } else {
+            format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+        };
+        busy.set(Some(family.filament_id.clone()));
+        error.set(None);
+        message.set(None);
+        spawn_local(async move {
+            match commands::update_family_field(&family, &key, &json_val).await {
+                Ok(edit) => {
+                    message.set(Some(if edit.queued {
+                        format!(
+                            "Queued '{}' for {} until Bambu Studio closes",
+                            key, family.display_name
+                        )
+                    } else {
+                        format!(
+                            "Set '{}' in {} variants of {}",
+                            key,
+                            edit.family.variants.len(),
+                            family.display_name
+                        )
+                    }));
+                    families.update(|list| {
+                        if let Some(f) = list
+                            .iter_mut()
+                            .find(|f| f.filament_id == edit.family.filament_id)
+                        {
+                            *f = edit.family;
+                        }
+                    });
+                }
+                Err(e) => match ProfileConflict::from_error(&e) {
+                    // Someone else changed a variant; show the current state
+                    Some(conflict) => {
+                        error.set(Some(conflict.message));
+                        reload();
+                    }
+                    None => error.set(Some(format!("{}: {}", family.display_name, e))),
+                },
+            }
+            busy.set(None);
+        });
+    };
+
+    let do_generate = move |family: FilamentFamily, printer: String| {
+        busy.set(Some(family.filament_id.clone()));
+        error.set(None);
+        message.set(None);
+        spawn_local(async move {
+            match commands::generate_family_variants(&family.filament_id, vec![printer.clone()])
+                .await
+            {
+                Ok(installed) if installed.is_empty() => message.set(Some(format!(
+                    "{} already has a variant for {}",
+                    family.display_name, printer
+                ))),
+                Ok(installed) if installed.iter().any(|i| i.queued) => message.set(Some(format!(
+                    "The {} variant of {} will be installed when Bambu Studio closes",
+                    printer, family.display_name
+                ))),
+                Ok(_) => {
+                    message.set(Some(format!(
+                        "Added a {} variant to {}",
+                        printer, family.display_name
+                    )));
+                    reload();
+                }
+                Err(e) => error.set(Some(format!("{}: {}", family.display_name, e))),
+            }
+            busy.set(None);
+        });
+    };
+
+    view! {
+        <div class="page families-page">
+            <style>{include_str!("families.css")}</style>
+
+            <h2>"Filament Families"</h2>
+            <p class="page-description">
+                "Profiles for the same filament on different printers and nozzles share a filament ID. Keep them in step here."
+            </p>
+
+            {move || error.get().map(|e| view! { <div class="family-error">{e}</div> })}
+            {move || message.get().map(|m| view! { <div class="family-message">{m}</div> })}
+
+            <Show when=move || !loading.get() && families.with(|f| f.is_empty())>
+                <p class="family-empty">"No user profiles with a filament ID yet."</p>
+            </Show>
+
+            <For
+                each=move || families.get()
+                key=|f| (f.filament_id.clone(), f.variants.iter().map(|v| v.version.clone()).collect::<Vec<_>>())
+                let:family
+            >
+                {family_card(family, busy, do_edit, do_generate)}
+            </For>
+        </div>
+    }
+}
+
+fn family_card(
+    family: FilamentFamily,
+    busy: RwSignal<Option<String>>,
+    on_edit: impl Fn(FilamentFamily, String, String) + Copy + 'static,
+    on_generate: impl Fn(FilamentFamily, String) + Copy + 'static,
+) -> impl IntoView {
+    let edit_key = RwSignal::new(String::new());
+    let edit_value = RwSignal::new(String::new());
+    let printer = RwSignal::new(String::from(PRINTER_OPTIONS[0]));
+
+    let busy_id = family.filament_id.clone();
+    let is_busy = move || busy.get().is_some();
+    let is_this_busy = move || busy.get().as_deref() == Some(busy_id.as_str());
+    let edit_family = family.clone();
+    let generate_family = family.clone();
+    let title = format!("{} ({})", family.display_name, family.filament_id);
+
+    let variant_headers = family
+        .variants
+        .iter()
+        .map(|v| {
+            let label = v.printer.clone().unwrap_or_else(|| v.name.clone());
+            view! { <th title=v.name.clone()>{label}</th> }
+        })
+        .collect::<Vec<_>>();
+    let divergences = family.divergences.clone();
+
+    view! {
+        <div class="family-card">
+            <div class="family-header">
+                <strong>{title}</strong>
+                <span class="family-count">{format!("{} variants", family.variants.len())}</span>
+            </div>
+
+            <ul class="family-variants">
+                {family.variants.iter().map(|v| view! {
+                    <li>{v.name.clone()}</li>
+                }).collect::<Vec<_>>()}
+            </ul>
+
+            {if divergences.is_empty() {
+                view! { <p class="family-note">"All variants agree on every shared setting."</p> }.into_any()
+            } else {
+                view! {
+                    <table class="family-table">
+                        <thead>
+                            <tr>
+                                <th>"Setting"</th>
+                                {variant_headers}
+                            </tr>
+                        </thead>
+                        <tbody>
+                            {divergences.into_iter().map(|d| {
+                                let key = d.key.clone();
+                                view! {
+                                    <tr>
+                                        <td>
+                                            <a class="family-key" href="#"
+                                                title="Set this field across the family"
+                                                on:click=move |ev| {
+                                                    ev.prevent_default();
+                                                    edit_key.set(key.clone());
+                                                }>
+                                                {d.key}
+                                            </a>
+                                        </td>
+                                        {d.values.into_iter().map(|v| view! {
+                                            <td class="family-value">{v}</td>
+                                        }).collect::<Vec<_>>()}
+                                    </tr>
+                                }
+                            }).collect::<Vec<_>>()}
+                        </tbody>
+                    </table>
+                }.into_any()
+            }}
+
+            <div class="family-actions">
+                <div class="family-edit">
+                    <input class="input" placeholder="Field (e.g. nozzle_temperature)"
+                        prop:value=move || edit_key.get()
+                        on:input=move |ev| edit_key.set(event_target_value(&ev)) />
+                    <input class="input" placeholder="Value"
+                        prop:value=move || edit_value.get()
+                        on:input=move |ev| edit_value.set(event_target_value(&ev)) />
+                    <button class="btn btn-small btn-primary"
+                        disabled=move || is_busy() || edit_key.get().trim().is_empty()
+                        on:click=move |_| on_edit(
+                            edit_family.clone(),
+                            edit_key.get().trim().to_string(),
+                            edit_value.get(),
+                        )>
+                        "Set in all variants"
+                    </button>
+                </div>
+                <div class="family-generate">
+                    <select class="input"
+                        on:change=move |ev| printer.set(event_target_value(&ev))
+                        prop:value=move || printer.get()>
+                        {PRINTER_OPTIONS.iter().map(|&p| {
+                            view! { <option value={p}>{p}</option> }
+                        }).collect::<Vec<_>>()}
+                    </select>
+                    <button class="btn btn-small btn-secondary"
+                        disabled=is_busy
+                        on:click=move |_| on_generate(generate_family.clone(), printer.get())>
+                        {move || if is_this_busy() { "Working..." } else { "Add variant" }}
+                    </button>
+                </div>
+            </div>
+        </div>
+    }
+}
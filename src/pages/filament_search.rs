@@ -77,6 +77,8 @@ pub fn FilamentSearchPage() -> impl IntoView {
     let (base_profile_specs, set_base_profile_specs) = signal::<Option<FilamentSpecs>>(None);
     let (show_merge_screen, set_show_merge_screen) = signal(false);
     let (base_profile_search, set_base_profile_search) = signal(String::new());
+    // Inherit from the system preset instead of writing every field
+    let (delta, set_delta) = signal(false);
 
     // Guards the "reset base picker + re-search" Effect below so it only fires
     // when specs come from a new fetch, not when the user completes the merge
@@ -355,7 +357,7 @@ pub fn FilamentSearchPage() -> impl IntoView {
     };
 
     // Generate handler — takes edited specs and a list of printer labels from SpecsEditor.
-    // Generates one profile per selected nozzle type sequentially.
+    // Generates one profile per selected nozzle type.
     let do_generate_with_specs = move |(edited_specs, printers): (FilamentSpecs, Vec<String>)| {
         set_generate_results.set(vec![]);
         set_install_results.set(vec![]);
@@ -363,31 +365,38 @@ pub fn FilamentSearchPage() -> impl IntoView {
 
         set_is_generating.set(true);
         let base_profile_path = selected_base_profile_path.get();
+        let delta = delta.get();
         spawn_local(async move {
-            let mut results: Vec<(String, Result<GenerateResult, String>)> = Vec::new();
-            let mut installs: Vec<GenerateResult> = Vec::new();
-            // Track the filament_id resolved by the first successful generation in
-            // this batch. All subsequent nozzle variants reuse the same ID so that
-            // profiles for the same physical filament are grouped correctly in
-            // Bambu Studio regardless of nozzle size.
-            let mut shared_filament_id: Option<String> = None;
-            for printer in printers {
-                let result = commands::generate_profile(
-                    &edited_specs,
-                    Some(printer.clone()),
-                    base_profile_path.clone(),
-                    shared_filament_id.clone(),
-                )
-                .await;
-                if let Ok(ref gen) = result {
-                    // Capture the filament_id from the first successful result
-                    if shared_filament_id.is_none() {
-                        shared_filament_id = Some(gen.filament_id.clone());
+            // One call generates every selected nozzle variant, all sharing
+            // one filament_id so Bambu Studio groups them as one filament.
+            let Some((first, rest)) = printers.split_first() else {
+                set_is_generating.set(false);
+                return;
+            };
+            let result = commands::generate_profile(
+                &edited_specs,
+                Some(first.clone()),
+                rest.to_vec(),
+                base_profile_path,
+                None,
+                delta,
+            )
+            .await;
+            let (results, installs): (Vec<(String, Result<GenerateResult, String>)>, _) =
+                match result {
+                    Ok(mut gen) => {
+                        let variants = std::mem::take(&mut gen.variants);
+                        let installs: Vec<GenerateResult> =
+                            std::iter::once(gen).chain(variants).collect();
+                        let results = printers
+                            .iter()
+                            .cloned()
+                            .zip(installs.iter().cloned().map(Ok))
+                            .collect();
+                        (results, installs)
                     }
-                    installs.push(gen.clone());
-                }
-                results.push((printer, result));
-            }
+                    Err(e) => (vec![(printers.join(", "), Err(e))], Vec::new()),
+                };
             set_pending_installs.set(installs);
             set_generate_results.set(results);
             set_is_generating.set(false);
@@ -402,18 +411,18 @@ pub fn FilamentSearchPage() -> impl IntoView {
         }
         set_is_installing.set(true);
         spawn_local(async move {
-            let mut results: Vec<(String, Result<InstallResult, String>)> = Vec::new();
-            for gen in installs {
-                let profile_name = gen.profile_name.clone();
-                let result = commands::install_profile(
-                    &gen.profile_json,
-                    &gen.metadata_info,
-                    &gen.filename,
-                    true,
-                )
-                .await;
-                results.push((profile_name, result));
-            }
+            // Installed together, after one library snapshot
+            let results: Vec<(String, Result<InstallResult, String>)> =
+                match commands::install_profiles(&installs).await {
+                    Ok(installed) => installed
+                        .into_iter()
+                        .map(|r| (r.profile_name.clone(), Ok(r)))
+                        .collect(),
+                    Err(e) => installs
+                        .iter()
+                        .map(|gen| (gen.profile_name.clone(), Err(e.clone())))
+                        .collect(),
+                };
             set_install_results.set(results);
             set_is_installing.set(false);
         });
@@ -835,6 +844,14 @@ pub fn FilamentSearchPage() -> impl IntoView {
                                 "No close installed matches were found for this filament. You can continue with the default base."
                             </p>
                         </Show>
+                        <label class="delta-option">
+                            <input
+                                type="checkbox"
+                                prop:checked=move || delta.get()
+                                on:change=move |ev| set_delta.set(event_target_checked(&ev))
+                            />
+                            " Inherit from the Bambu Studio system preset and save only the differences, so the profile picks up preset updates"
+                        </label>
                     </div>
                 })
             }}
@@ -1004,7 +1021,7 @@ pub fn FilamentSearchPage() -> impl IntoView {
                     .filter_map(|(name, r)| r.err().map(|e| (name, e)))
                     .collect();
                 let ok_count = ok_installs.len();
-                let any_bs_running = ok_installs.iter().any(|r| r.bambu_studio_was_running);
+                let queued_count = ok_installs.iter().filter(|r| r.queued).count();
 
                 Some(view! {
                     <div class="success-message">
@@ -1025,9 +1042,13 @@ pub fn FilamentSearchPage() -> impl IntoView {
                                             <code>{r.installed_path.clone()}</code>
                                         </p>
                                     }).collect::<Vec<_>>()}
-                                    {any_bs_running.then(|| view! {
+                                    {(queued_count > 0).then(|| view! {
                                         <p class="warning-text">
-                                            "Restart Bambu Studio to see the new profile(s)."
+                                            {format!(
+                                                "Bambu Studio is running: {} queued until it closes, \
+                                                 then written automatically.",
+                                                queued_count
+                                            )}
                                         </p>
                                     })}
                                 </div>
@@ -1063,3 +1084,10 @@ pub fn FilamentSearchPage() -> impl IntoView {
         </div>
     }
 }
+
+fn event_target_checked(ev: &leptos::ev::Event) -> bool {
+    ev.target()
+        .and_then(|t| t.dyn_into::<web_sys::HtmlInputElement>().ok())
+        .map(|e| e.checked())
+        .unwrap_or(false)
+}
@@ -1,10 +1,14 @@
 pub mod about;
 pub mod batch_generate;
+pub mod calibration;
+pub mod families;
 pub mod filament_search;
 pub mod health;
 pub mod home;
 pub mod print_analysis;
+pub mod printers;
 pub mod profile_diff;
 pub mod profile_management;
+pub mod rebase;
 pub mod settings;
 pub mod setup_wizard;
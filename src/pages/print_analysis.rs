@@ -126,7 +126,7 @@ pub fn PrintAnalysisPage() -> impl IntoView {
             let material = material_override.get();
             let path = profile_path.get();
             spawn_local(async move {
-                match commands::analyze_print(base64, path, material).await {
+                match commands::analyze_print(base64, path, material, None).await {
                     Ok(response) => {
                         // Store session ID for apply flow
                         set_current_session_id.set(response.session_id);
@@ -327,11 +327,18 @@ pub fn PrintAnalysisPage() -> impl IntoView {
                                                     if let Some(sid) = session {
                                                         match commands::apply_recommendations(p.clone(), sid, selected).await {
                                                             Ok(result) => {
-                                                                set_apply_message.set(Some(format!(
-                                                                    "Applied {} changes. Backup: {}",
-                                                                    result.changes_applied.len(),
-                                                                    result.backup_path
-                                                                )));
+                                                                set_apply_message.set(Some(if result.queued {
+                                                                    format!(
+                                                                        "Queued {} changes until Bambu Studio closes.",
+                                                                        result.changes_applied.len()
+                                                                    )
+                                                                } else {
+                                                                    format!(
+                                                                        "Applied {} changes. Backup: {}",
+                                                                        result.changes_applied.len(),
+                                                                        result.backup_path
+                                                                    )
+                                                                }));
                                                                 // Refresh history panel
                                                                 set_history_key.update(|k| *k += 1);
                                                             }
@@ -527,7 +534,7 @@ fn PhotoDropZone(
 }
 
 /// Read a File as base64 string.
-async fn read_file_as_base64(file: web_sys::File) -> Result<String, String> {
+pub(crate) async fn read_file_as_base64(file: web_sys::File) -> Result<String, String> {
     use js_sys::{ArrayBuffer, Uint8Array};
     use wasm_bindgen_futures::JsFuture;
 
@@ -0,0 +1,380 @@
+//! Connected printers and what's loaded in their AMS trays.
+//!
+//! Trays are matched to installed profiles by `filament_id` and re-matched
+//! whenever a status report changes what's loaded. Trays no profile
+//! matches can get one generated from what the printer reports.
+
+use std::collections::HashMap;
+
+use leptos::prelude::*;
+use wasm_bindgen_futures::spawn_local;
+
+use crate::commands::{self, PrinterConfig, PrinterEvent, PrinterSummary, TrayResolution};
+use crate::components::specs_editor::PRINTER_OPTIONS;
+
+#[component]
+pub fn PrintersPage() -> impl IntoView {
+    let printers = RwSignal::new(Vec::<PrinterSummary>::new());
+    // Tray resolutions keyed by printer serial
+    let trays = RwSignal::new(HashMap::<String, Vec<TrayResolution>>::new());
+    // Outcome of generating a profile, keyed by "serial/slot"
+    let tray_messages = RwSignal::new(HashMap::<String, String>::new());
+    let generating = RwSignal::new(Option::<String>::None);
+    let error = RwSignal::new(Option::<String>::None);
+
+    let host = RwSignal::new(String::new());
+    let serial = RwSignal::new(String::new());
+    let access_code = RwSignal::new(String::new());
+    let name = RwSignal::new(String::new());
+    let connecting = RwSignal::new(false);
+    let target_printer = RwSignal::new(String::from("Bambu Lab X1 Carbon 0.4 nozzle"));
+
+    let resolve = move |serial: String| {
+        spawn_local(async move {
+            match commands::resolve_ams_trays(&serial).await {
+                Ok(list) => trays.update(|t| {
+                    t.insert(serial, list);
+                }),
+                Err(e) => error.set(Some(e)),
+            }
+        });
+    };
+
+    let reload = move || {
+        spawn_local(async move {
+            match commands::list_printers().await {
+                Ok(list) => {
+                    for printer in &list {
+                        resolve(printer.serial.clone());
+                    }
+                    printers.set(list);
+                }
+                Err(e) => error.set(Some(e)),
+            }
+        });
+    };
+    reload();
+
+    // Status reports arrive every second or so while printing; trays are
+    // only re-matched when what's loaded changes. The listener lives as
+    // long as the page.
+    let listener: StoredValue<Option<commands::EventListener>, LocalStorage> =
+        StoredValue::new_local(None);
+    spawn_local(async move {
+        let result = commands::listen_printer_events(move |event| match event {
+            PrinterEvent::Status { serial, status } => {
+                let changed = printers.with_untracked(|list| {
+                    list.iter()
+                        .find(|p| p.serial == serial)
+                        .map(|p| p.status != status)
+                });
+                match changed {
+                    Some(true) => {
+                        printers.update(|list| {
+                            if let Some(p) = list.iter_mut().find(|p| p.serial == serial) {
+                                p.status = status;
+                            }
+                        });
+                        resolve(serial);
+                    }
+                    Some(false) => {}
+                    None => reload(),
+                }
+            }
+            PrinterEvent::Connection {
+                serial,
+                state,
+                message,
+            } => {
+                if let Some(message) = message {
+                    error.set(Some(format!("Printer {}: {}", serial, message)));
+                }
+                let known = printers.with_untracked(|list| list.iter().any(|p| p.serial == serial));
+                if known {
+                    printers.update(|list| {
+                        if let Some(p) = list.iter_mut().find(|p| p.serial == serial) {
+                            p.state = state;
+                        }
+                    });
+                } else {
+                    reload();
+                }
+            }
+        })
+        .await;
+        match result {
+            Ok(l) => {
+                // Dropped (unsubscribing) if the page is already gone
+                let _ = listener.try_update_value(|slot| *slot = Some(l));
+            }
+            Err(e) => leptos::logging::warn!("Failed to watch printers: {}", e),
+        }
+    });
+    on_cleanup(move || {
+        listener.update_value(|slot| {
+            slot.take();
+        });
+    });
+
+    let do_connect = move |_| {
+        let config = PrinterConfig {
+            serial: serial.get().trim().to_string(),
+            host: host.get().trim().to_string(),
+            access_code: access_code.get().trim().to_string(),
+            name: Some(name.get().trim().to_string()).filter(|n| !n.is_empty()),
+        };
+        if config.serial.is_empty() || config.host.is_empty() || config.access_code.is_empty() {
+            error.set(Some(
+                "Enter the printer's IP address, serial number and access code".to_string(),
+            ));
+            return;
+        }
+        connecting.set(true);
+        error.set(None);
+        spawn_local(async move {
+            match commands::connect_printer(config).await {
+                Ok(()) => {
+                    access_code.set(String::new());
+                    reload();
+                }
+                Err(e) => error.set(Some(e)),
+            }
+            connecting.set(false);
+        });
+    };
+
+    let do_disconnect = move |serial: String| {
+        spawn_local(async move {
+            match commands::disconnect_printer(&serial).await {
+                Ok(()) => {
+                    printers.update(|list| list.retain(|p| p.serial != serial));
+                    trays.update(|t| {
+                        t.remove(&serial);
+                    });
+                }
+                Err(e) => error.set(Some(e)),
+            }
+        });
+    };
+
+    // Generate a profile from what the tray reports and install it under
+    // the tray's filament_id, so the tray resolves to it from now on.
+    let do_generate = move |serial: String, resolution: TrayResolution| {
+        let Some(specs) = resolution.suggested_specs.clone() else {
+            return;
+        };
+        let key = format!("{}/{}", serial, resolution.slot);
+        let printer = target_printer.get();
+        generating.set(Some(key.clone()));
+        spawn_local(async move {
+            let outcome = async {
+                let generated = commands::generate_profile(
+                    &specs,
+                    Some(printer),
+                    Vec::new(),
+                    None,
+                    resolution.tray.filament_id.clone(),
+                    false,
+                )
+                .await?;
+                commands::install_profile(
+                    &generated.profile_json,
+                    &generated.metadata_info,
+                    &generated.filename,
+                    false,
+                )
+                .await
+            }
+            .await;
+            let message = match outcome {
+                Ok(installed) if installed.queued => format!(
+                    "{} will be installed when Bambu Studio closes",
+                    installed.profile_name
+                ),
+                Ok(installed) => format!("Installed {}", installed.profile_name),
+                Err(e) => format!("Failed: {}", e),
+            };
+            tray_messages.update(|m| {
+                m.insert(key, message);
+            });
+            generating.set(None);
+            resolve(serial);
+        });
+    };
+
+    view! {
+        <div class="page printers-page">
+            <style>{include_str!("printers.css")}</style>
+
+            <h2>"Printers"</h2>
+            <p class="page-description">
+                "Connect to printers in LAN mode to see which profiles their AMS trays use."
+            </p>
+
+            <div class="printer-connect">
+                <div class="printer-connect-fields">
+                    <input class="input" placeholder="IP address"
+                        prop:value=move || host.get()
+                        on:input=move |ev| host.set(event_target_value(&ev)) />
+                    <input class="input" placeholder="Serial number"
+                        prop:value=move || serial.get()
+                        on:input=move |ev| serial.set(event_target_value(&ev)) />
+                    <input class="input" type="password" placeholder="Access code"
+                        prop:value=move || access_code.get()
+                        on:input=move |ev| access_code.set(event_target_value(&ev)) />
+                    <input class="input" placeholder="Name (optional)"
+                        prop:value=move || name.get()
+                        on:input=move |ev| name.set(event_target_value(&ev)) />
+                </div>
+                <button class="btn btn-primary" on:click=do_connect disabled=move || connecting.get()>
+                    {move || if connecting.get() { "Connecting..." } else { "Connect" }}
+                </button>
+            </div>
+
+            {move || error.get().map(|e| view! { <div class="printer-error">{e}</div> })}
+
+            <div class="form-group">
+                <label for="tray-target-printer">"Generate profiles for"</label>
+                <select
+                    id="tray-target-printer"
+                    class="input"
+                    on:change=move |ev| target_printer.set(event_target_value(&ev))
+                    prop:value=move || target_printer.get()
+                >
+                    {PRINTER_OPTIONS.iter().map(|&p| {
+                        view! { <option value={p}>{p}</option> }
+                    }).collect::<Vec<_>>()}
+                </select>
+            </div>
+
+            <Show when=move || printers.with(|p| p.is_empty())>
+                <p class="printer-empty">"No printers connected."</p>
+            </Show>
+
+            <For
+                each=move || printers.get()
+                key=|p| (p.serial.clone(), p.state.clone())
+                let:printer
+            >
+                {
+                    let serial = printer.serial.clone();
+                    let title = printer.name.clone().unwrap_or_else(|| printer.serial.clone());
+                    let state_class = format!("printer-state printer-state-{}", printer.state);
+                    let disconnect_serial = serial.clone();
+                    view! {
+                        <div class="printer-card">
+                            <div class="printer-header">
+                                <strong>{title}</strong>
+                                <span class="printer-host">{printer.host.clone()}</span>
+                                <span class=state_class>{printer.state.clone()}</span>
+                                <button class="btn btn-small btn-secondary"
+                                    on:click=move |_| do_disconnect(disconnect_serial.clone())>
+                                    "Disconnect"
+                                </button>
+                            </div>
+                            {move || {
+                                let serial = serial.clone();
+                                let list = trays.with(|t| t.get(&serial).cloned()).unwrap_or_default();
+                                if list.is_empty() {
+                                    return view! { <p class="printer-empty">"No AMS trays reported yet."</p> }.into_any();
+                                }
+                                view! {
+                                    <table class="tray-table">
+                                        <thead>
+                                            <tr>
+                                                <th>"Slot"</th>
+                                                <th>"Filament"</th>
+                                                <th>"Profile"</th>
+                                                <th></th>
+                                            </tr>
+                                        </thead>
+                                        <tbody>
+                                            {list.into_iter().map(|resolution| {
+                                                tray_row(serial.clone(), resolution, tray_messages, generating, do_generate)
+                                            }).collect::<Vec<_>>()}
+                                        </tbody>
+                                    </table>
+                                }.into_any()
+                            }}
+                        </div>
+                    }
+                }
+            </For>
+        </div>
+    }
+}
+
+fn tray_row(
+    serial: String,
+    resolution: TrayResolution,
+    tray_messages: RwSignal<HashMap<String, String>>,
+    generating: RwSignal<Option<String>>,
+    on_generate: impl Fn(String, TrayResolution) + Copy + 'static,
+) -> impl IntoView {
+    let key = format!("{}/{}", serial, resolution.slot);
+    let tray = &resolution.tray;
+    let swatch = tray
+        .color
+        .as_deref()
+        .map(|c| format!("background: #{}", c.get(..6).unwrap_or(c)))
+        .unwrap_or_default();
+    let filament = match (&tray.filament_type, &tray.sub_brand) {
+        (Some(kind), Some(sub)) if sub != kind => format!("{} ({})", sub, kind),
+        (Some(kind), _) => kind.clone(),
+        (None, _) => "Empty".to_string(),
+    };
+    let profiles = match resolution.state.as_str() {
+        "matched" => resolution
+            .profiles
+            .iter()
+            .map(|p| {
+                if p.is_user_profile {
+                    format!("{} (user)", p.name)
+                } else {
+                    p.name.clone()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", "),
+        "unmatched" => format!(
+            "No profile for {}",
+            tray.filament_id.as_deref().unwrap_or("this filament")
+        ),
+        _ => String::new(),
+    };
+    let row_class = format!("tray-{}", resolution.state);
+    let can_generate = resolution.suggested_specs.is_some();
+    let message_key = key.clone();
+    let busy_key = key.clone();
+    view! {
+        <tr class=row_class>
+            <td>
+                <span class="tray-swatch" style=swatch></span>
+                {resolution.slot.clone()}
+            </td>
+            <td>{filament}</td>
+            <td>
+                {profiles}
+                {move || tray_messages.with(|m| m.get(&message_key).cloned()).map(|msg| view! {
+                    <div class="tray-message">{msg}</div>
+                })}
+            </td>
+            <td>
+                {can_generate.then(|| {
+                    let busy_key = busy_key.clone();
+                    view! {
+                        <button class="btn btn-small btn-primary"
+                            disabled=move || generating.get().is_some()
+                            on:click=move |_| on_generate(serial.clone(), resolution.clone())>
+                            {move || if generating.get().as_deref() == Some(busy_key.as_str()) {
+                                "Generating..."
+                            } else {
+                                "Generate profile"
+                            }}
+                        </button>
+                    }
+                })}
+            </td>
+        </tr>
+    }
+}
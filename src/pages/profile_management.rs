@@ -1,7 +1,18 @@
+use std::collections::{BTreeMap, HashMap};
+
 use leptos::prelude::*;
 use wasm_bindgen_futures::spawn_local;
 
-use crate::commands::{self, FilamentSpecs, ProfileDetail, ProfileInfo};
+use crate::commands::{
+    self, FieldEdit, FieldEditResult, FieldInfo, FilamentSpecs, ProfileConflict, ProfileDetail,
+    ProfileInfo, RemovalPlan,
+};
+use crate::components::field_editor::FieldEditor;
+use crate::components::library_snapshots::LibrarySnapshots;
+use crate::components::pending_writes::PendingWrites;
+use crate::components::profile_conflict::ProfileConflictPanel;
+use crate::components::profile_query::ProfileQuery;
+use crate::components::profile_removal::ProfileRemovalDialog;
 use crate::components::specs_editor::SpecsEditor;
 
 /// Key profile fields to display in the detail panel.
@@ -75,16 +86,37 @@ pub fn ProfileManagementPage() -> impl IntoView {
     let (show_delete_confirm, set_show_delete_confirm) = signal(false);
     let (show_duplicate_input, set_show_duplicate_input) = signal(false);
     let (duplicate_name, set_duplicate_name) = signal(String::new());
+    let (show_rename_input, set_show_rename_input) = signal(false);
+    let (rename_name, set_rename_name) = signal(String::new());
+    // New name awaiting confirmation in the rename dialog
+    let (rename_to, set_rename_to) = signal::<Option<String>>(None);
 
     // Inline edit state
     let (editing_field, set_editing_field) = signal::<Option<String>>(None);
     let (edit_value, set_edit_value) = signal(String::new());
 
+    // Labels and units from the backend's field dictionary
+    let (field_info, set_field_info) = signal(HashMap::<String, FieldInfo>::new());
+    spawn_local(async move {
+        if let Ok(fields) = commands::get_field_dictionary().await {
+            set_field_info.set(fields.into_iter().map(|f| (f.key.clone(), f)).collect());
+        }
+    });
+
     // Specs editor state
     let (show_specs_editor, set_show_specs_editor) = signal(false);
     let (editor_specs, set_editor_specs) = signal::<Option<FilamentSpecs>>(None);
     let (specs_loading, set_specs_loading) = signal(false);
 
+    // Raw field editor state
+    let (show_field_editor, set_show_field_editor) = signal(false);
+    let field_edits = RwSignal::new(BTreeMap::<String, FieldEdit>::new());
+
+    // External edits: a save refused because the file changed on disk, or a
+    // notice that it changed while being edited here
+    let (conflict, set_conflict) = signal::<Option<ProfileConflict>>(None);
+    let (external_change, set_external_change) = signal::<Option<String>>(None);
+
     // Load profiles on mount
     let load_profiles = move || {
         set_is_loading.set(true);
@@ -115,8 +147,14 @@ pub fn ProfileManagementPage() -> impl IntoView {
         set_editing_field.set(None);
         set_show_delete_confirm.set(false);
         set_show_duplicate_input.set(false);
+        set_show_rename_input.set(false);
+        set_rename_to.set(None);
         set_show_specs_editor.set(false);
         set_editor_specs.set(None);
+        set_show_field_editor.set(false);
+        field_edits.set(BTreeMap::new());
+        set_conflict.set(None);
+        set_external_change.set(None);
 
         spawn_local(async move {
             match commands::read_profile(&path).await {
@@ -130,29 +168,120 @@ pub fn ProfileManagementPage() -> impl IntoView {
         });
     };
 
-    // Delete handler
-    let do_delete = move || {
-        let path = match selected_path.get() {
-            Some(p) => p,
-            None => return,
-        };
-        set_show_delete_confirm.set(false);
+    // Keep the list and the open profile in step with edits made on disk,
+    // e.g. in Bambu Studio. The listener lives as long as the page.
+    let change_listener: StoredValue<Option<commands::EventListener>, LocalStorage> =
+        StoredValue::new_local(None);
+    spawn_local(async move {
+        let listener = commands::listen_profile_changes(move |change| {
+            spawn_local(async move {
+                if let Ok(list) = commands::list_profiles().await {
+                    set_profiles.set(list);
+                }
+            });
+            if selected_path.get_untracked().as_deref() != Some(change.path.as_str()) {
+                return;
+            }
+            let loaded_version = selected_detail.get_untracked().map(|d| d.version);
+            if change.version.is_some() && change.version == loaded_version {
+                // Our own write
+                return;
+            }
+            if change.kind == "removed" {
+                set_external_change.set(Some(
+                    "This profile was deleted outside BambuMate.".to_string(),
+                ));
+            } else if show_specs_editor.get_untracked()
+                || show_field_editor.get_untracked()
+                || editing_field.get_untracked().is_some()
+            {
+                set_external_change.set(Some(
+                    "This profile was changed outside BambuMate. Saving will show what changed."
+                        .to_string(),
+                ));
+            } else {
+                let path = change.path.clone();
+                spawn_local(async move {
+                    if let Ok(detail) = commands::read_profile(&path).await {
+                        set_selected_detail.set(Some(detail));
+                        set_external_change.set(Some(
+                            "Reloaded after a change outside BambuMate.".to_string(),
+                        ));
+                    }
+                });
+            }
+        })
+        .await;
+        match listener {
+            Ok(listener) => {
+                // Dropped (unsubscribing) if the page is already gone
+                let _ = change_listener.try_update_value(|slot| *slot = Some(listener));
+            }
+            Err(e) => leptos::logging::warn!("Failed to watch profile changes: {}", e),
+        }
+    });
+    on_cleanup(move || {
+        change_listener.update_value(|slot| {
+            slot.take();
+        });
+    });
+
+    // Version the open profile was loaded at, so a save is refused if it
+    // has changed on disk since
+    let loaded_version = move || {
+        selected_detail
+            .get()
+            .map(|d| d.version)
+            .filter(|v| !v.is_empty())
+    };
 
+    // Show a refused save as a conflict, anything else as an error
+    let report_save_error = move |e: String, prefix: &str| match ProfileConflict::from_error(&e) {
+        Some(c) => set_conflict.set(Some(c)),
+        None => set_action_error.set(Some(format!("{}{}", prefix, e))),
+    };
+
+    // Delete / rename finished
+    let on_removed = move |plan: RemovalPlan| {
+        set_show_delete_confirm.set(false);
+        set_rename_to.set(None);
+        let updated = match plan.dependents.len() {
+            0 => String::new(),
+            n => format!(" ({} dependent profiles updated)", n),
+        };
+        match plan.new_path {
+            Some(new_path) => {
+                select_profile(new_path);
+                set_action_success.set(Some(format!(
+                    "Renamed to '{}'{}",
+                    plan.new_name.unwrap_or_default(),
+                    updated
+                )));
+            }
+            None => {
+                set_action_success.set(Some(format!("Profile deleted{}", updated)));
+                set_selected_path.set(None);
+                set_selected_detail.set(None);
+            }
+        }
         spawn_local(async move {
-            match commands::delete_profile(&path).await {
-                Ok(()) => {
-                    set_action_success.set(Some("Profile deleted".to_string()));
-                    set_selected_path.set(None);
-                    set_selected_detail.set(None);
-                    if let Ok(list) = commands::list_profiles().await {
-                        set_profiles.set(list);
-                    }
-                }
-                Err(e) => set_action_error.set(Some(e)),
+            if let Ok(list) = commands::list_profiles().await {
+                set_profiles.set(list);
             }
         });
     };
 
+    // Rename: confirm the new name in the preview dialog
+    let start_rename = move || {
+        let name = rename_name.get();
+        if name.trim().is_empty() {
+            set_action_error.set(Some("Please enter a new name".to_string()));
+            return;
+        }
+        set_show_rename_input.set(false);
+        set_rename_to.set(Some(name.trim().to_string()));
+    };
+
     // Duplicate handler
     let do_duplicate = move || {
         let path = match selected_path.get() {
@@ -182,6 +311,37 @@ pub fn ProfileManagementPage() -> impl IntoView {
         });
     };
 
+    // Rewrite a flattened profile as a delta over its system preset
+    let do_convert_to_delta = move || {
+        let path = match selected_path.get() {
+            Some(p) => p,
+            None => return,
+        };
+        set_action_error.set(None);
+        set_action_success.set(None);
+
+        let version = loaded_version();
+        spawn_local(async move {
+            match commands::convert_profile_to_delta(&path, None, version.as_deref()).await {
+                Ok(detail) => {
+                    let message = if detail.queued {
+                        "Queued the conversion until Bambu Studio closes".to_string()
+                    } else {
+                        format!(
+                            "Now inherits from '{}'",
+                            detail.inherits.as_deref().unwrap_or_default()
+                        )
+                    };
+                    set_selected_detail.set(Some(detail));
+                    set_conflict.set(None);
+                    set_external_change.set(None);
+                    set_action_success.set(Some(message));
+                }
+                Err(e) => report_save_error(e, "Failed to convert: "),
+            }
+        });
+    };
+
     // Save edited field
     let save_field = move || {
         let field = match editing_field.get() {
@@ -209,13 +369,22 @@ pub fn ProfileManagementPage() -> impl IntoView {
             format!("\"{}\"", val.replace('\\', "\\\\").replace('"', "\\\""))
         };
 
+        let version = loaded_version();
         spawn_local(async move {
-            match commands::update_profile_field(&path, &field, &json_val).await {
+            match commands::update_profile_field(&path, &field, &json_val, version.as_deref()).await
+            {
                 Ok(detail) => {
+                    let message = if detail.queued {
+                        format!("Queued '{}' until Bambu Studio closes", field)
+                    } else {
+                        format!("Updated '{}'", field)
+                    };
                     set_selected_detail.set(Some(detail));
-                    set_action_success.set(Some(format!("Updated '{}'", field)));
+                    set_conflict.set(None);
+                    set_external_change.set(None);
+                    set_action_success.set(Some(message));
                 }
-                Err(e) => set_action_error.set(Some(e)),
+                Err(e) => report_save_error(e, ""),
             }
         });
     };
@@ -234,6 +403,7 @@ pub fn ProfileManagementPage() -> impl IntoView {
             match commands::extract_specs_from_profile(&path).await {
                 Ok(specs) => {
                     set_editor_specs.set(Some(specs));
+                    set_show_field_editor.set(false);
                     set_show_specs_editor.set(true);
                 }
                 Err(e) => set_action_error.set(Some(format!("Failed to load specs: {}", e))),
@@ -250,19 +420,27 @@ pub fn ProfileManagementPage() -> impl IntoView {
         };
         set_action_error.set(None);
 
+        let version = loaded_version();
         spawn_local(async move {
-            match commands::save_profile_specs(&path, &specs).await {
+            match commands::save_profile_specs(&path, &specs, version.as_deref()).await {
                 Ok(detail) => {
+                    let message = if detail.queued {
+                        "Queued the specs until Bambu Studio closes"
+                    } else {
+                        "Profile specs saved"
+                    };
                     set_selected_detail.set(Some(detail));
+                    set_conflict.set(None);
+                    set_external_change.set(None);
                     set_show_specs_editor.set(false);
                     set_editor_specs.set(None);
-                    set_action_success.set(Some("Profile specs saved".to_string()));
+                    set_action_success.set(Some(message.to_string()));
                     // Refresh list in case name changed
                     if let Ok(list) = commands::list_profiles().await {
                         set_profiles.set(list);
                     }
                 }
-                Err(e) => set_action_error.set(Some(format!("Failed to save: {}", e))),
+                Err(e) => report_save_error(e, "Failed to save: "),
             }
         });
     };
@@ -272,6 +450,21 @@ pub fn ProfileManagementPage() -> impl IntoView {
         set_editor_specs.set(None);
     };
 
+    // Raw field edits applied (or queued) in one write
+    let on_fields_saved = move |result: FieldEditResult| {
+        let message = match (&result.backup_path, result.detail.queued) {
+            (_, true) => "Queued field changes until Bambu Studio closes".to_string(),
+            (Some(backup), false) => format!("Field changes saved (backup at {})", backup),
+            (None, false) => "Field changes saved".to_string(),
+        };
+        set_selected_detail.set(Some(result.detail));
+        set_conflict.set(None);
+        set_external_change.set(None);
+        set_show_field_editor.set(false);
+        field_edits.set(BTreeMap::new());
+        set_action_success.set(Some(message));
+    };
+
     // Filtered profiles
     let filtered_profiles = move || {
         let q = filter_query.get().to_lowercase();
@@ -308,6 +501,37 @@ pub fn ProfileManagementPage() -> impl IntoView {
             {move || action_success.get().map(|s| view! {
                 <div class="profile-success">{s}</div>
             })}
+            {move || external_change.get().map(|m| view! {
+                <div class="profile-error">{m}</div>
+            })}
+            {move || conflict.get().map(|c| {
+                let current_version = c.current_version.clone();
+                view! {
+                    <ProfileConflictPanel
+                        conflict=c
+                        on_reload=move |_| {
+                            if let Some(path) = selected_path.get() {
+                                select_profile(path);
+                            }
+                        }
+                        on_overwrite=move |_| {
+                            // Treat the version on disk as the one loaded, so
+                            // the next save goes through
+                            let version = current_version.clone();
+                            set_selected_detail.update(|d| {
+                                if let Some(d) = d {
+                                    d.version = version;
+                                }
+                            });
+                            set_conflict.set(None);
+                            set_external_change.set(None);
+                            set_action_success.set(Some(
+                                "Save again to overwrite the change on disk".to_string(),
+                            ));
+                        }
+                    />
+                }
+            })}
 
             // Toolbar
             <div class="profile-toolbar">
@@ -331,6 +555,15 @@ pub fn ProfileManagementPage() -> impl IntoView {
                 </span>
             </div>
 
+            // Library-wide query over resolved system and user profiles
+            <ProfileQuery on_select=move |path: String| select_profile(path) />
+
+            // Changes held back until Bambu Studio closes
+            <PendingWrites on_written=move |_| load_profiles() />
+
+            // Snapshots of the whole library, taken before bulk changes
+            <LibrarySnapshots on_restored=move |_| load_profiles() />
+
             // Loading
             <Show when=move || is_loading.get()>
                 <div class="profile-loading">
@@ -453,6 +686,27 @@ pub fn ProfileManagementPage() -> impl IntoView {
                                                             >
                                                                 {move || if specs_loading.get() { "Loading..." } else { "Edit Specs" }}
                                                             </button>
+                                                            <button
+                                                                class="btn-icon"
+                                                                title="Edit any field"
+                                                                on:click=move |_| {
+                                                                    set_show_specs_editor.set(false);
+                                                                    set_show_field_editor.set(true);
+                                                                    set_action_error.set(None);
+                                                                    set_action_success.set(None);
+                                                                }
+                                                            >
+                                                                "Edit All Fields"
+                                                            </button>
+                                                            {detail.inherits.as_deref().unwrap_or_default().is_empty().then(|| view! {
+                                                                <button
+                                                                    class="btn-icon"
+                                                                    title="Inherit from the Bambu Studio system preset, keeping only the fields that differ"
+                                                                    on:click=move |_| do_convert_to_delta()
+                                                                >
+                                                                    "Inherit"
+                                                                </button>
+                                                            })}
                                                             <button
                                                                 class="btn-icon"
                                                                 title="Duplicate"
@@ -468,6 +722,21 @@ pub fn ProfileManagementPage() -> impl IntoView {
                                                             >
                                                                 "Copy"
                                                             </button>
+                                                            <button
+                                                                class="btn-icon"
+                                                                title="Rename"
+                                                                on:click=move |_| {
+                                                                    let name = selected_detail.get()
+                                                                        .and_then(|d| d.name.clone())
+                                                                        .unwrap_or_default();
+                                                                    set_rename_name.set(name);
+                                                                    set_show_rename_input.set(true);
+                                                                    set_action_error.set(None);
+                                                                    set_action_success.set(None);
+                                                                }
+                                                            >
+                                                                "Rename"
+                                                            </button>
                                                             <button
                                                                 class="btn-icon btn-danger"
                                                                 title="Delete"
@@ -482,6 +751,11 @@ pub fn ProfileManagementPage() -> impl IntoView {
                                                         </div>
                                                     </div>
 
+                                                    // Cloud account mismatch
+                                                    {detail.sync_warning.clone().map(|w| view! {
+                                                        <div class="profile-error">{w}</div>
+                                                    })}
+
                                                     // Duplicate input
                                                     <Show when=move || show_duplicate_input.get()>
                                                         <div class="duplicate-input-row">
@@ -504,8 +778,48 @@ pub fn ProfileManagementPage() -> impl IntoView {
                                                         </div>
                                                     </Show>
 
-                                                    // Specs editor or fields table
+                                                    // Rename input
+                                                    <Show when=move || show_rename_input.get()>
+                                                        <div class="duplicate-input-row">
+                                                            <input
+                                                                type="text"
+                                                                placeholder="New name..."
+                                                                prop:value=move || rename_name.get()
+                                                                on:input=move |ev| set_rename_name.set(event_target_value(&ev))
+                                                                on:keydown=move |ev| {
+                                                                    if ev.key() == "Enter" { start_rename(); }
+                                                                    if ev.key() == "Escape" { set_show_rename_input.set(false); }
+                                                                }
+                                                            />
+                                                            <button class="btn btn-primary" on:click=move |_| start_rename()>
+                                                                "Rename"
+                                                            </button>
+                                                            <button class="btn btn-secondary" on:click=move |_| set_show_rename_input.set(false)>
+                                                                "Cancel"
+                                                            </button>
+                                                        </div>
+                                                    </Show>
+
+                                                    // Specs editor, raw field editor or fields table
                                                     {move || {
+                                                        if show_field_editor.get() {
+                                                            if let Some(path) = selected_path.get() {
+                                                                return view! {
+                                                                    <FieldEditor
+                                                                        path=path
+                                                                        field_info=field_info
+                                                                        version=Signal::derive(loaded_version)
+                                                                        edits=field_edits
+                                                                        on_saved=on_fields_saved
+                                                                        on_error=move |e: String| report_save_error(e, "Failed to save: ")
+                                                                        on_cancel=move |_| {
+                                                                            set_show_field_editor.set(false);
+                                                                            field_edits.set(BTreeMap::new());
+                                                                        }
+                                                                    />
+                                                                }.into_any();
+                                                            }
+                                                        }
                                                         if show_specs_editor.get() {
                                                             if let Some(specs) = editor_specs.get() {
                                                                 return view! {
@@ -532,12 +846,17 @@ pub fn ProfileManagementPage() -> impl IntoView {
                                                                 <tbody>
                                                                     {rows.into_iter().map(|(key, value)| {
                                                                         let key_edit = key.clone();
-                                                                        let key_display = key.clone();
+                                                                        let key_display = field_info.with(|info| match info.get(&key) {
+                                                                            Some(FieldInfo { label, unit: Some(unit), .. }) => format!("{} ({})", label, unit),
+                                                                            Some(f) => f.label.clone(),
+                                                                            None => key.clone(),
+                                                                        });
+                                                                        let key_title = key.clone();
                                                                         let value_display = value.clone();
                                                                         let value_for_edit = value.clone();
                                                                         view! {
                                                                             <tr>
-                                                                                <td class="field-key">{key_display}</td>
+                                                                                <td class="field-key" title=key_title>{key_display}</td>
                                                                                 <td class="field-value">
                                                                                     {move || {
                                                                                         let is_editing = editing_field.get().as_deref() == Some(&key_edit);
@@ -591,27 +910,35 @@ pub fn ProfileManagementPage() -> impl IntoView {
                 }}
             </Show>
 
-            // Delete confirmation modal
-            <Show when=move || show_delete_confirm.get()>
-                <div class="modal-overlay" on:click=move |_| set_show_delete_confirm.set(false)>
-                    <div class="modal-content" on:click=move |ev| ev.stop_propagation()>
-                        <h3>"Delete Profile?"</h3>
-                        <p>
-                            "This will permanently delete \""
-                            {move || selected_detail.get().and_then(|d| d.name).unwrap_or_default()}
-                            "\" and its metadata. This cannot be undone."
-                        </p>
-                        <div class="modal-actions">
-                            <button class="btn btn-secondary" on:click=move |_| set_show_delete_confirm.set(false)>
-                                "Cancel"
-                            </button>
-                            <button class="btn btn-primary" on:click=move |_| do_delete()>
-                                "Delete"
-                            </button>
+            // Delete / rename confirmation, previewing what else changes
+            {move || {
+                let path = selected_path.get()?;
+                let new_name = rename_to.get();
+                if new_name.is_none() && !show_delete_confirm.get() {
+                    return None;
+                }
+                let name = selected_detail
+                    .get_untracked()
+                    .and_then(|d| d.name)
+                    .unwrap_or_default();
+                let close = move || {
+                    set_show_delete_confirm.set(false);
+                    set_rename_to.set(None);
+                };
+                Some(view! {
+                    <div class="modal-overlay" on:click=move |_| close()>
+                        <div class="modal-content" on:click=move |ev| ev.stop_propagation()>
+                            <ProfileRemovalDialog
+                                path=path
+                                name=name
+                                new_name=new_name
+                                on_done=on_removed
+                                on_cancel=move |_| close()
+                            />
                         </div>
                     </div>
-                </div>
-            </Show>
+                })
+            }}
         </div>
     }
 }
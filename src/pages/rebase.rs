@@ -0,0 +1,167 @@
+//! Base updates: generated profiles whose Bambu Studio base changed.
+//!
+//! Each candidate shows what regenerating it on the new base would change.
+//! Accepting writes the regenerated profile; rejecting keeps the installed
+//! one and stops offering that update.
+
+use leptos::prelude::*;
+use wasm_bindgen_futures::spawn_local;
+
+use crate::commands::{self, RebaseCandidate, RebaseReport};
+
+#[component]
+pub fn RebasePage() -> impl IntoView {
+    let report = RwSignal::new(Option::<RebaseReport>::None);
+    let checking = RwSignal::new(false);
+    let resolving = RwSignal::new(Option::<String>::None);
+    let error = RwSignal::new(Option::<String>::None);
+    let message = RwSignal::new(Option::<String>::None);
+
+    let do_check = move || {
+        checking.set(true);
+        error.set(None);
+        spawn_local(async move {
+            match commands::check_profile_rebases().await {
+                Ok(r) => report.set(Some(r)),
+                Err(e) => error.set(Some(e)),
+            }
+            checking.set(false);
+        });
+    };
+    do_check();
+
+    let do_resolve = move |candidate: RebaseCandidate, accept: bool| {
+        resolving.set(Some(candidate.path.clone()));
+        error.set(None);
+        message.set(None);
+        spawn_local(async move {
+            match commands::resolve_profile_rebase(&candidate.path, accept).await {
+                Ok(queued) => {
+                    message.set(Some(if queued {
+                        format!(
+                            "Queued the update of {} until Bambu Studio closes",
+                            candidate.profile_name
+                        )
+                    } else if accept {
+                        format!("Updated {} to the new base", candidate.profile_name)
+                    } else {
+                        format!("Kept {} as it is", candidate.profile_name)
+                    }));
+                    report.update(|r| {
+                        if let Some(r) = r {
+                            r.candidates.retain(|c| c.path != candidate.path);
+                        }
+                    });
+                }
+                Err(e) => error.set(Some(format!("{}: {}", candidate.profile_name, e))),
+            }
+            resolving.set(None);
+        });
+    };
+
+    view! {
+        <div class="page rebase-page">
+            <style>{include_str!("rebase.css")}</style>
+
+            <h2>"Base Updates"</h2>
+            <p class="page-description">
+                "Bambu Studio updates its system presets from time to time. Profiles generated here can be regenerated on the updated base, keeping your specs."
+            </p>
+
+            <button class="btn btn-primary" on:click=move |_| do_check() disabled=move || checking.get()>
+                {move || if checking.get() { "Checking..." } else { "Check Again" }}
+            </button>
+
+            {move || error.get().map(|e| view! { <div class="rebase-error">{e}</div> })}
+            {move || message.get().map(|m| view! { <div class="rebase-message">{m}</div> })}
+
+            {move || report.get().map(|RebaseReport { candidates, errors }| {
+                view! {
+                    {candidates.is_empty().then(|| view! {
+                        <p class="rebase-empty">"All generated profiles are up to date with their base."</p>
+                    })}
+                    {candidates.into_iter().map(|candidate| {
+                        candidate_card(candidate, resolving, do_resolve)
+                    }).collect::<Vec<_>>()}
+                    {(!errors.is_empty()).then(|| view! {
+                        <div class="rebase-errors">
+                            <h4>"Couldn't check"</h4>
+                            <ul>
+                                {errors.into_iter().map(|e| view! { <li>{e}</li> }).collect::<Vec<_>>()}
+                            </ul>
+                        </div>
+                    })}
+                }
+            })}
+        </div>
+    }
+}
+
+fn candidate_card(
+    candidate: RebaseCandidate,
+    resolving: RwSignal<Option<String>>,
+    on_resolve: impl Fn(RebaseCandidate, bool) + Copy + 'static,
+) -> impl IntoView {
+    let accept = candidate.clone();
+    let reject = candidate.clone();
+    let busy_path = candidate.path.clone();
+    let summary = format!(
+        "'{}' changed: {} of {} settings differ",
+        candidate.base_name, candidate.diff.changed_fields, candidate.diff.total_fields
+    );
+    let rows = candidate
+        .diff
+        .categories
+        .into_iter()
+        .flat_map(|category| {
+            let name = category.category;
+            category.diffs.into_iter().map(move |d| {
+                view! {
+                    <tr>
+                        <td class="rebase-category">{name.clone()}</td>
+                        <td>{d.label}</td>
+                        <td class="rebase-value">{d.base_value}</td>
+                        <td class="rebase-value">{d.new_value}</td>
+                    </tr>
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    view! {
+        <div class="rebase-card">
+            <div class="rebase-header">
+                <div>
+                    <strong>{candidate.profile_name}</strong>
+                    <div class="rebase-summary">{summary}</div>
+                </div>
+                <div class="rebase-actions">
+                    <button class="btn btn-small btn-primary"
+                        disabled=move || resolving.get().is_some()
+                        on:click=move |_| on_resolve(accept.clone(), true)>
+                        {move || if resolving.get().as_deref() == Some(busy_path.as_str()) {
+                            "Updating..."
+                        } else {
+                            "Update"
+                        }}
+                    </button>
+                    <button class="btn btn-small btn-secondary"
+                        disabled=move || resolving.get().is_some()
+                        on:click=move |_| on_resolve(reject.clone(), false)>
+                        "Keep Current"
+                    </button>
+                </div>
+            </div>
+            <table class="rebase-table">
+                <thead>
+                    <tr>
+                        <th>"Category"</th>
+                        <th>"Setting"</th>
+                        <th>"Installed"</th>
+                        <th>"Regenerated"</th>
+                    </tr>
+                </thead>
+                <tbody>{rows}</tbody>
+            </table>
+        </div>
+    }
+}